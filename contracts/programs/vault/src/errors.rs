@@ -62,7 +62,7 @@ pub enum VaultError {
     #[msg("Bet ID already processed (duplicate)")]
     DuplicateBetId,
 
-    #[msg("Bet ID is invalid or too long")]
+    #[msg("Bet ID must be 32 lowercase hex characters")]
     InvalidBetId,
 
     #[msg("Token mint mismatch with allowance")]
@@ -82,4 +82,61 @@ pub enum VaultError {
 
     #[msg("Invalid allowance nonce")]
     InvalidAllowanceNonce,
+
+    #[msg("Invalid casino authority")]
+    InvalidAuthority,
+
+    #[msg("Processor is already authorized")]
+    ProcessorAlreadyAuthorized,
+
+    #[msg("No free processor slot")]
+    ProcessorSetFull,
+
+    #[msg("Processor is not authorized")]
+    ProcessorNotFound,
+
+    #[msg("Cannot remove the last authorized processor")]
+    LastProcessor,
+
+    #[msg("Spend exceeds the allowance's single-spend limit")]
+    SpendExceedsSingleLimit,
+
+    #[msg("Spend exceeds the allowance's hourly limit")]
+    SpendVelocityExceeded,
+
+    #[msg("Only a lost bet with no payout can be refunded")]
+    BetNotRefundable,
+
+    #[msg("Bet has already been refunded")]
+    BetAlreadyRefunded,
+
+    #[msg("Token config needs 0 < min_bet <= max_bet")]
+    InvalidTokenConfig,
+
+    #[msg("Bets in this token are disabled")]
+    TokenBetsDisabled,
+
+    #[msg("No token config for this mint")]
+    TokenNotConfigured,
+
+    #[msg("Casino was migrated by a newer program version")]
+    ProgramVersionDowngrade,
+
+    #[msg("Unauthorized: caller is not the casino treasury")]
+    UnauthorizedTreasury,
+
+    #[msg("Funding amount must be greater than zero")]
+    InvalidFundingAmount,
+
+    #[msg("Payout address must be a wallet other than the vault")]
+    InvalidPayoutAddress,
+
+    #[msg("Vault has a payout address; pass it (or its token account) as payout_destination")]
+    PayoutDestinationRequired,
+
+    #[msg("payout_destination does not match the vault's payout address")]
+    PayoutDestinationMismatch,
+
+    #[msg("Bet was already settled by settle_bet")]
+    BetAlreadySettled,
 }
@@ -99,6 +99,10 @@ pub fn handler(
     allowance.bump = ctx.bumps.allowance;
     allowance.last_spent_at = 0;
     allowance.spend_count = 0;
+    allowance.max_single_spend = 0;
+    allowance.max_spend_per_hour = 0;
+    allowance.window_start = 0;
+    allowance.window_spent = 0;
 
     // Increment rate limiter
     rate_limiter.approvals_count += 1;
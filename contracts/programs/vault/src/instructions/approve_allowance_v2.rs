@@ -67,6 +67,20 @@ pub fn handler(
     duration_seconds: i64,
     token_mint: Pubkey,
     nonce: u64,
+) -> Result<()> {
+    limited_handler(ctx, amount, duration_seconds, token_mint, nonce, 0, 0)
+}
+
+/// `approve_allowance_v3`: v2 plus spend velocity limits (0 = unlimited). The
+/// v3 arguments extend v2's, so it shares the `ApproveAllowanceV2` accounts.
+pub fn limited_handler(
+    ctx: Context<ApproveAllowanceV2>,
+    amount: u64,
+    duration_seconds: i64,
+    token_mint: Pubkey,
+    nonce: u64,
+    max_single_spend: u64,
+    max_spend_per_hour: u64,
 ) -> Result<()> {
     let allowance = &mut ctx.accounts.allowance;
     let nonce_registry = &mut ctx.accounts.allowance_nonce_registry;
@@ -123,6 +137,10 @@ pub fn handler(
     allowance.bump = ctx.bumps.allowance;
     allowance.last_spent_at = 0;
     allowance.spend_count = 0;
+    allowance.max_single_spend = max_single_spend;
+    allowance.max_spend_per_hour = max_spend_per_hour;
+    allowance.window_start = 0;
+    allowance.window_spent = 0;
 
     // Increment nonce + rate limiter
     nonce_registry.next_nonce = nonce_registry
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::state::*;
+use crate::errors::*;
+use crate::validation::CheckedMath;
+
+/// Top up the casino vault from the treasury (treasury or authority only)
+#[derive(Accounts)]
+pub struct FundCasinoVault<'info> {
+    #[account(
+        seeds = [b"casino"],
+        bump = casino.bump
+    )]
+    pub casino: Account<'info, Casino>,
+
+    /// Casino vault - program-owned account holding casino funds
+    #[account(
+        mut,
+        seeds = [b"casino-vault", casino.key().as_ref()],
+        bump = casino_vault.bump
+    )]
+    pub casino_vault: Account<'info, CasinoVault>,
+
+    /// Funding account; `casino.treasury` or, e.g. when that is a multisig vault, the authority
+    #[account(
+        mut,
+        constraint = treasury.key() == casino.treasury
+            || treasury.key() == casino.authority @ VaultError::UnauthorizedTreasury
+    )]
+    pub treasury: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<FundCasinoVault>, amount: u64) -> Result<()> {
+    require!(amount > 0, VaultError::InvalidFundingAmount);
+
+    let casino_vault = &mut ctx.accounts.casino_vault;
+    let clock = Clock::get()?;
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.treasury.to_account_info(),
+                to: casino_vault.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    // Keep the tracked balance in step so no reconcile is needed
+    casino_vault.sol_balance = casino_vault.sol_balance.safe_add(amount)?;
+    casino_vault.last_activity = clock.unix_timestamp;
+
+    msg!(
+        "Funded casino vault with {} lamports from {}; balance {}",
+        amount,
+        ctx.accounts.treasury.key(),
+        casino_vault.sol_balance
+    );
+
+    Ok(())
+}
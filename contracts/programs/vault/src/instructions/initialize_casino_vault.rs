@@ -50,6 +50,8 @@ pub fn handler(ctx: Context<InitializeCasinoVault>, authority: Pubkey) -> Result
     casino.total_bets = 0;
     casino.total_volume = 0;
     casino.created_at = clock.unix_timestamp;
+    casino.extra_processors = [Pubkey::default(); MAX_EXTRA_PROCESSORS];
+    casino.program_version = CURRENT_PROGRAM_VERSION;
 
     casino_vault.casino = casino.key();
     casino_vault.bump = ctx.bumps.casino_vault;
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+/// Initialize a user's vault on their behalf (processor only). The processor
+/// pays the rent, so a first settlement can land without a user transaction.
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct InitializeVaultFor<'info> {
+    #[account(
+        init,
+        payer = processor,
+        space = Vault::LEN,
+        seeds = [b"vault", casino.key().as_ref(), owner.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [b"casino"],
+        bump = casino.bump
+    )]
+    pub casino: Account<'info, Casino>,
+
+    #[account(
+        mut,
+        constraint = casino.is_processor(&processor.key()) @ VaultError::UnauthorizedProcessor
+    )]
+    pub processor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<InitializeVaultFor>, owner: Pubkey) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let clock = Clock::get()?;
+
+    vault.owner = owner;
+    vault.casino = ctx.accounts.casino.key();
+    vault.bump = ctx.bumps.vault;
+    vault.sol_balance = 0;
+    vault.created_at = clock.unix_timestamp;
+    vault.last_activity = clock.unix_timestamp;
+
+    msg!("Vault initialized for user: {} (paid by processor {})", owner, ctx.accounts.processor.key());
+
+    Ok(())
+}
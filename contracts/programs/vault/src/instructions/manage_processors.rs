@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+/// Authorize an additional processor key, e.g. ahead of a rotation or for
+/// another processor instance (admin only)
+#[derive(Accounts)]
+pub struct AddProcessor<'info> {
+    #[account(
+        mut,
+        seeds = [b"casino"],
+        bump = casino.bump,
+        constraint = casino.authority == authority.key() @ VaultError::UnauthorizedAuthority
+    )]
+    pub casino: Account<'info, Casino>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn add_handler(ctx: Context<AddProcessor>, processor: Pubkey) -> Result<()> {
+    require!(processor != Pubkey::default(), VaultError::InvalidAuthority);
+
+    let casino = &mut ctx.accounts.casino;
+    require!(!casino.is_processor(&processor), VaultError::ProcessorAlreadyAuthorized);
+
+    let slot = casino
+        .extra_processors
+        .iter_mut()
+        .find(|key| **key == Pubkey::default())
+        .ok_or(VaultError::ProcessorSetFull)?;
+    *slot = processor;
+
+    msg!("Casino processor {} authorized", processor);
+
+    Ok(())
+}
+
+/// Revoke a processor key (admin only). Removing the primary processor
+/// promotes the first extra key in its place.
+#[derive(Accounts)]
+pub struct RemoveProcessor<'info> {
+    #[account(
+        mut,
+        seeds = [b"casino"],
+        bump = casino.bump,
+        constraint = casino.authority == authority.key() @ VaultError::UnauthorizedAuthority
+    )]
+    pub casino: Account<'info, Casino>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn remove_handler(ctx: Context<RemoveProcessor>, processor: Pubkey) -> Result<()> {
+    let casino = &mut ctx.accounts.casino;
+    require!(
+        processor != Pubkey::default() && casino.is_processor(&processor),
+        VaultError::ProcessorNotFound
+    );
+
+    if casino.processor == processor {
+        let promoted = casino
+            .extra_processors
+            .iter_mut()
+            .find(|key| **key != Pubkey::default())
+            .ok_or(VaultError::LastProcessor)?;
+        casino.processor = std::mem::take(promoted);
+        msg!("Casino processor {} removed, {} promoted to primary", processor, casino.processor);
+    } else {
+        for key in casino.extra_processors.iter_mut().filter(|key| **key == processor) {
+            *key = Pubkey::default();
+        }
+        msg!("Casino processor {} removed", processor);
+    }
+
+    Ok(())
+}
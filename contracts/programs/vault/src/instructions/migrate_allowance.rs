@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_lang::error::ErrorCode;
+use crate::state::*;
+
+/// Grow an allowance created before spend velocity limits existed. The new
+/// bytes are zeroed, i.e. no single-spend or hourly limit and an empty window.
+/// Anyone may pay for it: the allowance's terms are left as they were.
+#[derive(Accounts)]
+pub struct MigrateAllowance<'info> {
+    /// CHECK: Deserializing as `Allowance` fails until the account is resized;
+    /// owner and discriminator are checked in the handler
+    #[account(mut)]
+    pub allowance: UncheckedAccount<'info>,
+
+    /// Pays the extra rent, e.g. the processor before settling against the allowance
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateAllowance>) -> Result<()> {
+    let allowance = ctx.accounts.allowance.to_account_info();
+    require_keys_eq!(*allowance.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+
+    {
+        let data = allowance.try_borrow_data()?;
+        require!(
+            data.len() >= Allowance::LEGACY_LEN && data[..8] == Allowance::DISCRIMINATOR[..],
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+    }
+
+    if allowance.data_len() >= Allowance::LEN {
+        msg!("Allowance already migrated");
+        return Ok(());
+    }
+
+    let required = Rent::get()?.minimum_balance(Allowance::LEN);
+    let top_up = required.saturating_sub(allowance.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: allowance.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    allowance.realloc(Allowance::LEN, true)?;
+
+    msg!("Allowance {} migrated to {} bytes", allowance.key(), Allowance::LEN);
+
+    Ok(())
+}
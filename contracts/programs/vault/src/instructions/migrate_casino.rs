@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_lang::error::ErrorCode;
+use crate::state::*;
+use crate::errors::*;
+
+/// Grow a casino account created before `Casino::extra_processors` existed
+/// (admin only). The new bytes are zeroed, i.e. every extra slot starts empty.
+#[derive(Accounts)]
+pub struct MigrateCasino<'info> {
+    /// CHECK: Deserializing as `Casino` fails until the account is resized;
+    /// owner, discriminator and authority are checked in the handler
+    #[account(mut, seeds = [b"casino"], bump)]
+    pub casino: UncheckedAccount<'info>,
+
+    /// Casino authority; pays the extra rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateCasino>) -> Result<()> {
+    if !grow_casino(&ctx)? {
+        msg!("Casino already migrated");
+    }
+    Ok(())
+}
+
+/// Resize the casino like `migrate_casino`, then stamp `CURRENT_PROGRAM_VERSION`.
+/// Run after every upgrade; repeating it is a no-op.
+pub fn migrate_handler(ctx: Context<MigrateCasino>) -> Result<()> {
+    grow_casino(&ctx)?;
+
+    let casino = ctx.accounts.casino.to_account_info();
+    let mut data = casino.try_borrow_mut_data()?;
+    let field = &mut data[Casino::PROGRAM_VERSION_OFFSET..Casino::LEN];
+    let previous = u32::from_le_bytes(<[u8; 4]>::try_from(&field[..]).map_err(|_| ErrorCode::AccountDidNotDeserialize)?);
+    // An older build must not relabel an account a newer one has laid out
+    require!(previous <= CURRENT_PROGRAM_VERSION, VaultError::ProgramVersionDowngrade);
+    field.copy_from_slice(&CURRENT_PROGRAM_VERSION.to_le_bytes());
+
+    msg!("Casino program version {} -> {}", previous, CURRENT_PROGRAM_VERSION);
+
+    Ok(())
+}
+
+/// Check the account is the authority's casino and resize it to `Casino::LEN`;
+/// returns false when it already had that size
+fn grow_casino(ctx: &Context<MigrateCasino>) -> Result<bool> {
+    let casino = ctx.accounts.casino.to_account_info();
+    require_keys_eq!(*casino.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+
+    {
+        let data = casino.try_borrow_data()?;
+        require!(
+            data.len() >= Casino::LEGACY_LEN && data[..8] == Casino::DISCRIMINATOR[..],
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        // `authority` is the first field after the discriminator
+        let authority = Pubkey::try_from(&data[8..40]).map_err(|_| VaultError::InvalidAuthority)?;
+        require_keys_eq!(authority, ctx.accounts.authority.key(), VaultError::UnauthorizedAuthority);
+    }
+
+    if casino.data_len() >= Casino::LEN {
+        return Ok(false);
+    }
+
+    let required = Rent::get()?.minimum_balance(Casino::LEN);
+    let top_up = required.saturating_sub(casino.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: casino.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    casino.realloc(Casino::LEN, true)?;
+
+    msg!("Casino migrated to {} bytes", Casino::LEN);
+
+    Ok(true)
+}
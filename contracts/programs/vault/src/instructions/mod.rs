@@ -1,4 +1,5 @@
 pub mod initialize_vault;
+pub mod initialize_vault_for;
 pub mod initialize_casino_vault;
 pub mod initialize_vault_only;
 pub mod reconcile_casino_vault;
@@ -9,12 +10,23 @@ pub mod approve_allowance_v2;
 pub mod revoke_allowance;
 pub mod spend_from_allowance;
 pub mod payout;
+pub mod settle_bet;
+pub mod refund_bet;
 pub mod withdraw_sol;
 pub mod withdraw_spl;
 pub mod pause_casino;
 pub mod withdraw_casino_funds;
+pub mod fund_casino_vault;
+pub mod set_processor;
+pub mod set_authority;
+pub mod manage_processors;
+pub mod migrate_casino;
+pub mod migrate_allowance;
+pub mod set_token_config;
+pub mod set_payout_address;
 
 pub use initialize_vault::*;
+pub use initialize_vault_for::*;
 pub use initialize_casino_vault::*;
 pub use initialize_vault_only::*;
 pub use reconcile_casino_vault::*;
@@ -25,7 +37,17 @@ pub use approve_allowance_v2::*;
 pub use revoke_allowance::*;
 pub use spend_from_allowance::*;
 pub use payout::*;
+pub use settle_bet::*;
+pub use refund_bet::*;
 pub use withdraw_sol::*;
 pub use withdraw_spl::*;
 pub use pause_casino::*;
 pub use withdraw_casino_funds::*;
+pub use fund_casino_vault::*;
+pub use set_processor::*;
+pub use set_authority::*;
+pub use manage_processors::*;
+pub use migrate_casino::*;
+pub use migrate_allowance::*;
+pub use set_token_config::*;
+pub use set_payout_address::*;
@@ -46,18 +46,40 @@ pub struct Payout<'info> {
     #[account(mut)]
     pub casino_token_account: Option<Account<'info, TokenAccount>>,
 
-    /// Reference to processed bet (optional - may not exist yet in same tx)
-    /// CHECK: We trust the processor signer, so this is just for tracking
-    pub processed_bet: UncheckedAccount<'info>,
+    /// Payout record; creating it fails if this bet was already paid out
+    #[account(
+        init,
+        payer = processor,
+        space = PayoutRecord::LEN,
+        seeds = [b"payout", bet_id.as_bytes()],
+        bump
+    )]
+    pub payout_record: Account<'info, PayoutRecord>,
 
-    /// Processor (authorized to execute payouts)
+    /// Processor (authorized to execute payouts; pays for the payout record)
     #[account(
-        constraint = processor.key() == casino.processor @ VaultError::UnauthorizedProcessor
+        mut,
+        constraint = casino.is_processor(&processor.key()) @ VaultError::UnauthorizedProcessor
     )]
     pub processor: Signer<'info>,
 
     pub system_program: Program<'info, System>,
     pub token_program: Option<Program<'info, Token>>,
+
+    /// Where winnings go when the vault has a payout address: that wallet for
+    /// SOL, a token account it owns for SPL. Pass the program id when it has none.
+    /// CHECK: Checked against `Vault::payout_address` in `payout_redirect`
+    #[account(mut)]
+    pub payout_destination: Option<UncheckedAccount<'info>>,
+
+    /// The bet's processed-bet record, if it was staked; a bet `settle_bet`
+    /// already settled can't be paid again here
+    /// CHECK: Address checked by seeds; contents read in `require_unsettled`
+    #[account(
+        seeds = [b"processed-bet", bet_id.as_bytes()],
+        bump
+    )]
+    pub processed_bet: UncheckedAccount<'info>,
 }
 
 pub fn handler(
@@ -70,14 +92,91 @@ pub fn handler(
     let clock = Clock::get()?;
 
     validate_bet_id(&bet_id)?;
+    require_unsettled(&ctx.accounts.processed_bet)?;
+
+    let redirect = payout_redirect(vault, ctx.accounts.payout_destination.as_ref())?;
+    pay_winnings(
+        vault,
+        casino,
+        &mut ctx.accounts.casino_vault,
+        &ctx.accounts.vault_authority,
+        payout_mint(ctx.accounts.casino_token_account.as_ref()),
+        ctx.accounts.user_token_account.as_ref(),
+        ctx.accounts.casino_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+        redirect,
+        amount,
+        clock.unix_timestamp,
+    )?;
+
+    // Update vault activity
+    vault.last_activity = clock.unix_timestamp;
 
-    // Determine if SOL or SPL payout
-    let is_sol = ctx.accounts.user_token_account.is_none();
+    let payout_record = &mut ctx.accounts.payout_record;
+    payout_record.bet_id = bet_id.clone();
+    payout_record.vault = vault.key();
+    payout_record.amount = amount;
+    payout_record.paid_at = clock.unix_timestamp;
+    payout_record.bump = ctx.bumps.payout_record;
 
-    if is_sol {
+    msg!("Payout {} for bet {}", amount, bet_id);
+
+    Ok(())
+}
+
+/// Fail for a bet whose processed-bet record already carries an outcome: `settle_bet`
+/// paid its winnings (or settled it as lost) and `refund_bet` may have returned its
+/// stake. Bets staked by `spend_from_allowance`, or never staked, can be paid.
+fn require_unsettled(processed_bet: &UncheckedAccount) -> Result<()> {
+    if *processed_bet.owner != crate::ID || processed_bet.data_is_empty() {
+        return Ok(());
+    }
+    let record = {
+        let data = processed_bet.try_borrow_data()?;
+        ProcessedBet::try_deserialize(&mut &data[..])?
+    };
+    require!(record.outcome == BET_OUTCOME_UNRECORDED, VaultError::BetAlreadySettled);
+    Ok(())
+}
+
+/// Mint of a payout without an allowance to go by: the casino token account's,
+/// or native SOL when none is passed
+pub(crate) fn payout_mint(casino_token_account: Option<&Account<TokenAccount>>) -> Pubkey {
+    casino_token_account.map_or(System::id(), |account| account.mint)
+}
+
+/// The vault's payout address and the account passed for it, when the owner set one
+pub(crate) fn payout_redirect<'a, 'info>(
+    vault: &Account<'info, Vault>,
+    payout_destination: Option<&'a UncheckedAccount<'info>>,
+) -> Result<Option<(Pubkey, &'a UncheckedAccount<'info>)>> {
+    let Some(address) = Vault::payout_address(&vault.to_account_info())? else {
+        return Ok(None);
+    };
+    let destination = payout_destination.ok_or(VaultError::PayoutDestinationRequired)?;
+    Ok(Some((address, destination)))
+}
+
+/// Pay `amount` of winnings from the casino to the user in `token_mint`; `System::id()`
+/// pays native SOL. With a `redirect` the winnings go to the vault's payout address
+/// instead of the vault, otherwise SPL goes to a token account of the vault or its owner.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn pay_winnings<'info>(
+    vault: &mut Account<'info, Vault>,
+    casino: &Account<'info, Casino>,
+    casino_vault: &mut Account<'info, CasinoVault>,
+    vault_authority: &UncheckedAccount<'info>,
+    token_mint: Pubkey,
+    user_token_account: Option<&Account<'info, TokenAccount>>,
+    casino_token_account: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    redirect: Option<(Pubkey, &UncheckedAccount<'info>)>,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    if token_mint == System::id() {
         // SOL payout: casino_vault -> user vault
         // Direct lamports manipulation - works because both accounts are program-owned
-        let casino_vault = &mut ctx.accounts.casino_vault;
         let rent = Rent::get()?;
         
         // Balance check with reconciliation
@@ -95,18 +194,28 @@ pub fn handler(
         );
         
         **casino_vault.to_account_info().try_borrow_mut_lamports()? -= amount;
-        **vault.to_account_info().try_borrow_mut_lamports()? += amount;
+        match redirect {
+            Some((address, destination)) => {
+                require_keys_eq!(destination.key(), address, VaultError::PayoutDestinationMismatch);
+                **destination.to_account_info().try_borrow_mut_lamports()? += amount;
+            }
+            None => {
+                **vault.to_account_info().try_borrow_mut_lamports()? += amount;
+                vault.sol_balance = vault.sol_balance.safe_add(amount)?;
+            }
+        }
 
         // Update tracked balances
         casino_vault.sol_balance = casino_vault.sol_balance.safe_sub(amount)?;
-        casino_vault.last_activity = clock.unix_timestamp;
-        vault.sol_balance = vault.sol_balance.safe_add(amount)?;
+        casino_vault.last_activity = now;
     } else {
         // SPL payout: casino_token_account -> user_token_account
-        let user_token = ctx.accounts.user_token_account.as_ref()
+        let user_token = user_token_account
             .ok_or(VaultError::InvalidTokenAccountOwner)?;
-        let casino_token = ctx.accounts.casino_token_account.as_ref()
+        let casino_token = casino_token_account
             .ok_or(VaultError::InvalidTokenAccountOwner)?;
+        let token_program = token_program.ok_or(VaultError::MissingTokenProgram)?;
+        require_keys_eq!(casino_token.mint, token_mint, VaultError::TokenMintMismatch);
 
         let casino_key = casino.key();
         let seeds = &[
@@ -116,13 +225,34 @@ pub fn handler(
         ];
         let signer_seeds = &[&seeds[..]];
 
+        let to = match redirect {
+            Some((address, destination)) => {
+                require_keys_eq!(*destination.owner, token::ID, VaultError::InvalidTokenAccountOwner);
+                let account = {
+                    let data = destination.try_borrow_data()?;
+                    TokenAccount::try_deserialize(&mut &data[..])?
+                };
+                require_keys_eq!(account.owner, address, VaultError::PayoutDestinationMismatch);
+                require_keys_eq!(account.mint, casino_token.mint, VaultError::InvalidTokenMint);
+                destination.to_account_info()
+            }
+            None => {
+                require!(
+                    user_token.owner == vault.owner || user_token.owner == vault.key(),
+                    VaultError::InvalidTokenAccountOwner
+                );
+                require_keys_eq!(user_token.mint, casino_token.mint, VaultError::InvalidTokenMint);
+                user_token.to_account_info()
+            }
+        };
+
         token::transfer(
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.as_ref().unwrap().to_account_info(),
+                token_program.to_account_info(),
                 Transfer {
                     from: casino_token.to_account_info(),
-                    to: user_token.to_account_info(),
-                    authority: ctx.accounts.vault_authority.to_account_info(),
+                    to,
+                    authority: vault_authority.to_account_info(),
                 },
                 signer_seeds,
             ),
@@ -130,10 +260,5 @@ pub fn handler(
         )?;
     }
 
-    // Update vault activity
-    vault.last_activity = clock.unix_timestamp;
-
-    msg!("Payout {} for bet {}", amount, bet_id);
-
     Ok(())
 }
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::state::*;
+use crate::errors::*;
+use crate::instructions::payout::{pay_winnings, payout_mint};
+use crate::validation::validate_bet_id;
+
+/// Return the stake of a bet voided after settlement. Only a lost `settle_bet`
+/// record qualifies: nothing was paid out, so the stake is the whole refund.
+#[derive(Accounts)]
+#[instruction(bet_id: String)]
+pub struct RefundBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", casino.key().as_ref(), vault.owner.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [b"casino"],
+        bump = casino.bump,
+        constraint = !casino.paused @ VaultError::CasinoPaused
+    )]
+    pub casino: Account<'info, Casino>,
+
+    /// Processed bet record; its outcome becomes the refund marker
+    #[account(
+        mut,
+        seeds = [b"processed-bet", bet_id.as_bytes()],
+        bump = processed_bet.bump,
+        constraint = processed_bet.user == vault.owner @ VaultError::InvalidVaultPDA
+    )]
+    pub processed_bet: Account<'info, ProcessedBet>,
+
+    /// Casino vault (for SOL) - source of the refund
+    #[account(
+        mut,
+        seeds = [b"casino-vault", casino.key().as_ref()],
+        bump = casino_vault.bump
+    )]
+    pub casino_vault: Account<'info, CasinoVault>,
+
+    /// Vault authority PDA (for signing SPL refunds)
+    #[account(
+        seeds = [b"vault-authority", casino.key().as_ref()],
+        bump = casino.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used only for signing SPL transfers
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// Optional: User's token account (for SPL); the refund goes back to the
+    /// vault owner in the casino token account's mint
+    #[account(
+        mut,
+        token::authority = vault.owner,
+        token::mint = casino_token_account.as_ref().ok_or(VaultError::MissingTokenAccount)?.mint
+    )]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Optional: Casino's token account (for SPL)
+    #[account(mut)]
+    pub casino_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Processor (authorized to refund bets)
+    #[account(
+        mut,
+        constraint = casino.is_processor(&processor.key()) @ VaultError::UnauthorizedProcessor
+    )]
+    pub processor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+pub fn handler(ctx: Context<RefundBet>, bet_id: String) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let casino = &ctx.accounts.casino;
+    let processed_bet = &mut ctx.accounts.processed_bet;
+    let clock = Clock::get()?;
+
+    validate_bet_id(&bet_id)?;
+
+    require!(
+        processed_bet.outcome != BET_OUTCOME_REFUNDED,
+        VaultError::BetAlreadyRefunded
+    );
+    // Bets settled by `spend_from_allowance` carry no outcome, so a separate
+    // `payout` may already have returned funds; those are refunded off-chain
+    require!(
+        processed_bet.outcome == BET_OUTCOME_LOST && processed_bet.payout == 0,
+        VaultError::BetNotRefundable
+    );
+
+    let amount = processed_bet.amount;
+    pay_winnings(
+        vault,
+        casino,
+        &mut ctx.accounts.casino_vault,
+        &ctx.accounts.vault_authority,
+        payout_mint(ctx.accounts.casino_token_account.as_ref()),
+        ctx.accounts.user_token_account.as_ref(),
+        ctx.accounts.casino_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+        // The stake came out of the vault, so it goes back there whatever the payout address
+        None,
+        amount,
+        clock.unix_timestamp,
+    )?;
+
+    vault.last_activity = clock.unix_timestamp;
+
+    // Refund marker; `payout` records what went back to the user
+    processed_bet.outcome = BET_OUTCOME_REFUNDED;
+    processed_bet.payout = amount;
+
+    msg!("Bet {} refunded: {} returned", bet_id, amount);
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+#[derive(Accounts)]
+pub struct SetAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"casino"],
+        bump = casino.bump,
+        constraint = casino.authority == authority.key() @ VaultError::UnauthorizedAuthority
+    )]
+    pub casino: Account<'info, Casino>,
+
+    pub authority: Signer<'info>,
+}
+
+/// The new authority may be a multisig vault PDA (e.g. Squads); it signs later
+/// admin instructions through CPI, so no private key is needed.
+pub fn handler(ctx: Context<SetAuthority>, new_authority: Pubkey) -> Result<()> {
+    require!(new_authority != Pubkey::default(), VaultError::InvalidAuthority);
+
+    let casino = &mut ctx.accounts.casino;
+    let previous = casino.authority;
+    casino.authority = new_authority;
+
+    msg!("Casino authority transferred from {} to {}", previous, new_authority);
+
+    Ok(())
+}
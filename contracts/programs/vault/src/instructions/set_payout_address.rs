@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+/// Set or clear where the vault's winnings are paid (owner only). The first
+/// call grows the vault to `Vault::LEN_WITH_PAYOUT_ADDRESS`; the owner pays the
+/// extra rent.
+#[derive(Accounts)]
+pub struct SetPayoutAddress<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", casino.key().as_ref(), owner.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == owner.key(),
+        realloc = Vault::LEN_WITH_PAYOUT_ADDRESS,
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [b"casino"],
+        bump = casino.bump
+    )]
+    pub casino: Account<'info, Casino>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<SetPayoutAddress>, payout_address: Option<Pubkey>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let clock = Clock::get()?;
+
+    if let Some(address) = payout_address {
+        // Paying the vault itself is what `None` means
+        require!(
+            address != Pubkey::default() && address != vault.key(),
+            VaultError::InvalidPayoutAddress
+        );
+    }
+
+    {
+        let info = vault.to_account_info();
+        let mut data = info.try_borrow_mut_data()?;
+        data[Vault::LEN..Vault::LEN_WITH_PAYOUT_ADDRESS]
+            .copy_from_slice(payout_address.unwrap_or_default().as_ref());
+    }
+    vault.last_activity = clock.unix_timestamp;
+
+    match payout_address {
+        Some(address) => msg!("Vault {} pays winnings to {}", vault.key(), address),
+        None => msg!("Vault {} pays winnings to itself", vault.key()),
+    }
+
+    Ok(())
+}
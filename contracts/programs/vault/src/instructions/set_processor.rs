@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+#[derive(Accounts)]
+pub struct SetProcessor<'info> {
+    #[account(
+        mut,
+        seeds = [b"casino"],
+        bump = casino.bump,
+        constraint = casino.authority == authority.key() @ VaultError::UnauthorizedAuthority
+    )]
+    pub casino: Account<'info, Casino>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetProcessor>, new_processor: Pubkey) -> Result<()> {
+    let casino = &mut ctx.accounts.casino;
+    let previous = casino.processor;
+    casino.processor = new_processor;
+    // Keep the set free of duplicates if the new primary was an extra key
+    for key in casino.extra_processors.iter_mut().filter(|key| **key == new_processor) {
+        *key = Pubkey::default();
+    }
+
+    msg!("Casino processor rotated from {} to {}", previous, new_processor);
+
+    Ok(())
+}
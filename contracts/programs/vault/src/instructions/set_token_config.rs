@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+/// Create or update the bet limits for one mint (admin only)
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetTokenConfig<'info> {
+    #[account(
+        seeds = [b"casino"],
+        bump = casino.bump,
+        constraint = casino.authority == authority.key() @ VaultError::UnauthorizedAuthority
+    )]
+    pub casino: Account<'info, Casino>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = TokenConfig::LEN,
+        seeds = [b"token-config", casino.key().as_ref(), mint.as_ref()],
+        bump
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<SetTokenConfig>,
+    mint: Pubkey,
+    min_bet: u64,
+    max_bet: u64,
+    bet_enabled: bool,
+) -> Result<()> {
+    require!(
+        min_bet > 0 && min_bet <= max_bet,
+        VaultError::InvalidTokenConfig
+    );
+
+    let token_config = &mut ctx.accounts.token_config;
+    token_config.casino = ctx.accounts.casino.key();
+    token_config.mint = mint;
+    token_config.min_bet = min_bet;
+    token_config.max_bet = max_bet;
+    token_config.bet_enabled = bet_enabled;
+    token_config.updated_at = Clock::get()?.unix_timestamp;
+    token_config.bump = ctx.bumps.token_config;
+
+    msg!(
+        "Token config for {}: bets {} to {}, enabled {}",
+        mint,
+        min_bet,
+        max_bet,
+        bet_enabled
+    );
+
+    Ok(())
+}
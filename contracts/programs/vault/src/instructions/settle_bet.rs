@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::state::*;
+use crate::errors::*;
+use crate::instructions::payout::{pay_winnings, payout_redirect};
+use crate::instructions::spend_from_allowance::collect_stake;
+use crate::validation::{load_token_config, validate_bet_id, validate_token_bet, CheckedMath};
+
+/// Settle a bet in one step: spend the stake from the allowance and, for a win,
+/// pay out winnings. Replaces the `spend_from_allowance` + `payout` pair, which
+/// could land the spend without the payout.
+#[derive(Accounts)]
+#[instruction(amount: u64, payout: u64, bet_id: String)]
+pub struct SettleBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", casino.key().as_ref(), vault.owner.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"casino"],
+        bump = casino.bump,
+        constraint = !casino.paused @ VaultError::CasinoPaused
+    )]
+    pub casino: Account<'info, Casino>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"allowance",
+            allowance.user.as_ref(),
+            casino.key().as_ref(),
+            &allowance.nonce.to_le_bytes()
+        ],
+        bump = allowance.bump,
+        constraint = allowance.user == vault.owner @ VaultError::InvalidAllowancePDA
+    )]
+    pub allowance: Account<'info, Allowance>,
+
+    /// Processed bet record with outcome and payout (prevents double settlement)
+    #[account(
+        init,
+        payer = processor,
+        space = ProcessedBet::LEN,
+        seeds = [b"processed-bet", bet_id.as_bytes()],
+        bump
+    )]
+    pub processed_bet: Account<'info, ProcessedBet>,
+
+    /// Casino vault (for SOL) - receives the stake and funds the payout
+    #[account(
+        mut,
+        seeds = [b"casino-vault", casino.key().as_ref()],
+        bump = casino_vault.bump
+    )]
+    pub casino_vault: Account<'info, CasinoVault>,
+
+    /// Vault authority PDA (for signing SPL payouts)
+    #[account(
+        seeds = [b"vault-authority", casino.key().as_ref()],
+        bump = casino.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used only for signing SPL transfers
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// Optional: User's token account (for SPL)
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Optional: Casino's token account (for SPL)
+    #[account(mut)]
+    pub casino_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Processor (authorized to settle bets)
+    #[account(
+        mut,
+        constraint = casino.is_processor(&processor.key()) @ VaultError::UnauthorizedProcessor
+    )]
+    pub processor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// Bet limits for the allowance's mint. Required even before the authority
+    /// configures them, so a configured mint's limits can't be skipped by leaving it out.
+    /// CHECK: Address checked by seeds; contents read by `load_token_config`
+    #[account(
+        seeds = [b"token-config", casino.key().as_ref(), allowance.token_mint.as_ref()],
+        bump
+    )]
+    pub token_config: UncheckedAccount<'info>,
+
+    /// Where winnings go when the vault has a payout address: that wallet for
+    /// SOL, a token account it owns for SPL. Last so older clients can omit it.
+    /// CHECK: Checked against `Vault::payout_address` in `payout_redirect`
+    #[account(mut)]
+    pub payout_destination: Option<UncheckedAccount<'info>>,
+}
+
+pub fn handler(
+    ctx: Context<SettleBet>,
+    amount: u64,
+    payout: u64,
+    bet_id: String,
+) -> Result<()> {
+    let allowance = &mut ctx.accounts.allowance;
+    let vault = &mut ctx.accounts.vault;
+    let casino = &mut ctx.accounts.casino;
+    let processed_bet = &mut ctx.accounts.processed_bet;
+    let clock = Clock::get()?;
+
+    let token_config = load_token_config(&ctx.accounts.token_config)?;
+    validate_token_bet(amount, &allowance.token_mint, token_config.as_ref())?;
+    validate_bet_id(&bet_id)?;
+
+    require!(allowance.is_valid(&clock), VaultError::AllowanceExpired);
+
+    let new_spent = allowance.spent.safe_add(amount)?;
+    require!(
+        new_spent <= allowance.amount,
+        VaultError::InsufficientAllowance
+    );
+    allowance.record_spend_velocity(amount, clock.unix_timestamp)?;
+
+    collect_stake(
+        vault,
+        casino.key(),
+        &mut ctx.accounts.casino_vault,
+        allowance.token_mint,
+        ctx.accounts.user_token_account.as_ref(),
+        ctx.accounts.casino_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+        amount,
+        clock.unix_timestamp,
+    )?;
+
+    // The stake is already in the casino vault, so a win can be paid from it
+    if payout > 0 {
+        let redirect = payout_redirect(vault, ctx.accounts.payout_destination.as_ref())?;
+        pay_winnings(
+            vault,
+            casino,
+            &mut ctx.accounts.casino_vault,
+            &ctx.accounts.vault_authority,
+            allowance.token_mint,
+            ctx.accounts.user_token_account.as_ref(),
+            ctx.accounts.casino_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            redirect,
+            payout,
+            clock.unix_timestamp,
+        )?;
+    }
+
+    // Update allowance
+    allowance.spent = new_spent;
+    allowance.last_spent_at = clock.unix_timestamp;
+    allowance.spend_count = allowance.spend_count.saturating_add(1);
+
+    // Update vault activity
+    vault.last_activity = clock.unix_timestamp;
+
+    // Update casino stats
+    casino.total_bets = casino.total_bets.safe_add(1)?;
+    casino.total_volume = casino.total_volume.safe_add(amount)?;
+
+    // Record processed bet
+    processed_bet.bet_id = bet_id.clone();
+    processed_bet.user = vault.owner;
+    processed_bet.amount = amount;
+    processed_bet.processed_at = clock.unix_timestamp;
+    processed_bet.signature = String::new(); // Will be filled by backend
+    processed_bet.bump = ctx.bumps.processed_bet;
+    processed_bet.outcome = if payout > 0 { BET_OUTCOME_WON } else { BET_OUTCOME_LOST };
+    processed_bet.payout = payout;
+
+    msg!("Bet {} settled: {} staked, {} paid out", bet_id, amount, payout);
+
+    Ok(())
+}
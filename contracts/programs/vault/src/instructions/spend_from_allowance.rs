@@ -2,7 +2,7 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 use crate::state::*;
 use crate::errors::*;
-use crate::validation::{validate_bet_amount, validate_bet_id, CheckedMath};
+use crate::validation::{load_token_config, validate_bet_id, validate_token_bet, CheckedMath};
 
 // Wrapped SOL mint address
 const WRAPPED_SOL_MINT: Pubkey = solana_program::pubkey!("So11111111111111111111111111111111111111112");
@@ -75,12 +75,21 @@ pub struct SpendFromAllowance<'info> {
     /// Processor (authorized to execute spends)
     #[account(
         mut,
-        constraint = processor.key() == casino.processor @ VaultError::UnauthorizedProcessor
+        constraint = casino.is_processor(&processor.key()) @ VaultError::UnauthorizedProcessor
     )]
     pub processor: Signer<'info>,
 
     pub system_program: Program<'info, System>,
     pub token_program: Option<Program<'info, Token>>,
+
+    /// Bet limits for the allowance's mint. Required even before the authority
+    /// configures them, so a configured mint's limits can't be skipped by leaving it out.
+    /// CHECK: Address checked by seeds; contents read by `load_token_config`
+    #[account(
+        seeds = [b"token-config", casino.key().as_ref(), allowance.token_mint.as_ref()],
+        bump
+    )]
+    pub token_config: UncheckedAccount<'info>,
 }
 
 pub fn handler(
@@ -94,8 +103,9 @@ pub fn handler(
     let processed_bet = &mut ctx.accounts.processed_bet;
     let clock = Clock::get()?;
 
-    // Validate bet amount
-    validate_bet_amount(amount)?;
+    // Validate bet amount against the mint's limits
+    let token_config = load_token_config(&ctx.accounts.token_config)?;
+    validate_token_bet(amount, &allowance.token_mint, token_config.as_ref())?;
 
     // Validate bet ID length BEFORE PDA derivation (critical: prevents seed overflow)
     // This must happen before the ProcessedBet account is initialized in the Context
@@ -116,9 +126,61 @@ pub fn handler(
         new_spent <= allowance.amount,
         VaultError::InsufficientAllowance
     );
+    allowance.record_spend_velocity(amount, clock.unix_timestamp)?;
+
+    collect_stake(
+        vault,
+        casino.key(),
+        &mut ctx.accounts.casino_vault,
+        allowance.token_mint,
+        ctx.accounts.user_token_account.as_ref(),
+        ctx.accounts.casino_token_account.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+        amount,
+        clock.unix_timestamp,
+    )?;
+
+    // Update allowance
+    allowance.spent = new_spent;
+    allowance.last_spent_at = clock.unix_timestamp;
+    allowance.spend_count = allowance.spend_count.saturating_add(1);
+
+    // Update vault activity
+    vault.last_activity = clock.unix_timestamp;
+
+    // Update casino stats
+    casino.total_bets = casino.total_bets.safe_add(1)?;
+    casino.total_volume = casino.total_volume.safe_add(amount)?;
+
+    // Record processed bet
+    processed_bet.bet_id = bet_id.clone();
+    processed_bet.user = vault.owner;
+    processed_bet.amount = amount;
+    processed_bet.processed_at = clock.unix_timestamp;
+    processed_bet.signature = String::new(); // Will be filled by backend
+    processed_bet.bump = ctx.bumps.processed_bet;
+    processed_bet.outcome = BET_OUTCOME_UNRECORDED;
+    processed_bet.payout = 0;
+
+    msg!("Bet {} processed: {} spent from allowance", bet_id, amount);
+
+    Ok(())
+}
 
-    // Handle different token types with clear separation
-    if allowance.token_mint == System::id() {
+/// Move a bet's stake from the user to the casino, by allowance token type
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn collect_stake<'info>(
+    vault: &mut Account<'info, Vault>,
+    casino: Pubkey,
+    casino_vault: &mut Account<'info, CasinoVault>,
+    token_mint: Pubkey,
+    user_token_account: Option<&Account<'info, TokenAccount>>,
+    casino_token_account: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    if token_mint == System::id() {
         // NATIVE SOL: vault -> casino_vault
         require!(vault.sol_balance >= amount, VaultError::InsufficientBalance);
 
@@ -126,24 +188,16 @@ pub fn handler(
         // The System Program's transfer instruction cannot be used on accounts with data,
         // so we manipulate the lamports directly using Anchor's account methods
         **vault.to_account_info().try_borrow_mut_lamports()? -= amount;
-        **ctx.accounts.casino_vault.to_account_info().try_borrow_mut_lamports()? += amount;
+        **casino_vault.to_account_info().try_borrow_mut_lamports()? += amount;
 
         vault.sol_balance = vault.sol_balance.safe_sub(amount)?;
-        ctx.accounts.casino_vault.sol_balance = ctx.accounts.casino_vault.sol_balance.safe_add(amount)?;
-        ctx.accounts.casino_vault.last_activity = clock.unix_timestamp;
+        casino_vault.sol_balance = casino_vault.sol_balance.safe_add(amount)?;
+        casino_vault.last_activity = now;
         msg!("Native SOL transfer: {} lamports from vault to casino", amount);
-    } else if allowance.token_mint == WRAPPED_SOL_MINT {
+    } else if token_mint == WRAPPED_SOL_MINT {
         // WRAPPED SOL: user_token_account -> casino_token_account (SPL transfer)
-        let user_token = ctx
-            .accounts
-            .user_token_account
-            .as_ref()
-            .ok_or(VaultError::MissingTokenAccount)?;
-        let casino_token = ctx
-            .accounts
-            .casino_token_account
-            .as_ref()
-            .ok_or(VaultError::MissingTokenAccount)?;
+        let user_token = user_token_account.ok_or(VaultError::MissingTokenAccount)?;
+        let casino_token = casino_token_account.ok_or(VaultError::MissingTokenAccount)?;
 
         require!(user_token.mint == WRAPPED_SOL_MINT, VaultError::TokenMintMismatch);
         require!(casino_token.mint == WRAPPED_SOL_MINT, VaultError::TokenMintMismatch);
@@ -160,10 +214,9 @@ pub fn handler(
             return Err(VaultError::MissingTokenDelegation.into());
         }
 
-        let casino_key = casino.key();
         let seeds = &[
             b"vault",
-            casino_key.as_ref(),
+            casino.as_ref(),
             vault.owner.as_ref(),
             &[vault.bump],
         ];
@@ -171,9 +224,7 @@ pub fn handler(
 
         token::transfer(
             CpiContext::new_with_signer(
-                ctx.accounts
-                    .token_program
-                    .as_ref()
+                token_program
                     .ok_or(VaultError::MissingTokenProgram)?
                     .to_account_info(),
                 Transfer {
@@ -192,19 +243,11 @@ pub fn handler(
         );
     } else {
         // OTHER SPL TOKENS: user_token_account -> casino_token_account
-        let user_token = ctx
-            .accounts
-            .user_token_account
-            .as_ref()
-            .ok_or(VaultError::MissingTokenAccount)?;
-        let casino_token = ctx
-            .accounts
-            .casino_token_account
-            .as_ref()
-            .ok_or(VaultError::MissingTokenAccount)?;
-
-        require!(user_token.mint == allowance.token_mint, VaultError::TokenMintMismatch);
-        require!(casino_token.mint == allowance.token_mint, VaultError::TokenMintMismatch);
+        let user_token = user_token_account.ok_or(VaultError::MissingTokenAccount)?;
+        let casino_token = casino_token_account.ok_or(VaultError::MissingTokenAccount)?;
+
+        require!(user_token.mint == token_mint, VaultError::TokenMintMismatch);
+        require!(casino_token.mint == token_mint, VaultError::TokenMintMismatch);
 
         let has_delegation = user_token.delegate.is_some()
             && user_token.delegate.unwrap() == vault.key()
@@ -213,10 +256,9 @@ pub fn handler(
 
         require!(has_delegation || vault_owned, VaultError::InvalidTokenAccountOwner);
 
-        let casino_key = casino.key();
         let seeds = &[
             b"vault",
-            casino_key.as_ref(),
+            casino.as_ref(),
             vault.owner.as_ref(),
             &[vault.bump],
         ];
@@ -224,9 +266,7 @@ pub fn handler(
 
         token::transfer(
             CpiContext::new_with_signer(
-                ctx.accounts
-                    .token_program
-                    .as_ref()
+                token_program
                     .ok_or(VaultError::MissingTokenProgram)?
                     .to_account_info(),
                 Transfer {
@@ -242,31 +282,9 @@ pub fn handler(
         msg!(
             "SPL token transfer: {} units of {} from user to casino",
             amount,
-            allowance.token_mint
+            token_mint
         );
     }
 
-    // Update allowance
-    allowance.spent = new_spent;
-    allowance.last_spent_at = clock.unix_timestamp;
-    allowance.spend_count = allowance.spend_count.saturating_add(1);
-
-    // Update vault activity
-    vault.last_activity = clock.unix_timestamp;
-
-    // Update casino stats
-    casino.total_bets = casino.total_bets.safe_add(1)?;
-    casino.total_volume = casino.total_volume.safe_add(amount)?;
-
-    // Record processed bet
-    processed_bet.bet_id = bet_id.clone();
-    processed_bet.user = vault.owner;
-    processed_bet.amount = amount;
-    processed_bet.processed_at = clock.unix_timestamp;
-    processed_bet.signature = String::new(); // Will be filled by backend
-    processed_bet.bump = ctx.bumps.processed_bet;
-
-    msg!("Bet {} processed: {} spent from allowance", bet_id, amount);
-
     Ok(())
 }
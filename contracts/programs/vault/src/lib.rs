@@ -19,15 +19,26 @@ use crate::instructions::deposit_sol::DepositSol;
 use crate::instructions::deposit_spl::DepositSpl;
 use crate::instructions::initialize_casino_vault::InitializeCasinoVault;
 use crate::instructions::initialize_vault::InitializeVault;
+use crate::instructions::initialize_vault_for::InitializeVaultFor;
 use crate::instructions::initialize_vault_only::InitializeVaultOnly;
 use crate::instructions::reconcile_casino_vault::ReconcileCasinoVault;
 use crate::instructions::pause_casino::{PauseCasino, UnpauseCasino};
 use crate::instructions::payout::Payout;
 use crate::instructions::revoke_allowance::RevokeAllowance;
+use crate::instructions::settle_bet::SettleBet;
+use crate::instructions::refund_bet::RefundBet;
 use crate::instructions::spend_from_allowance::SpendFromAllowance;
 use crate::instructions::withdraw_sol::WithdrawSol;
 use crate::instructions::withdraw_spl::WithdrawSpl;
 use crate::instructions::withdraw_casino_funds::WithdrawCasinoFunds;
+use crate::instructions::fund_casino_vault::FundCasinoVault;
+use crate::instructions::set_processor::SetProcessor;
+use crate::instructions::set_authority::SetAuthority;
+use crate::instructions::manage_processors::{AddProcessor, RemoveProcessor};
+use crate::instructions::migrate_casino::MigrateCasino;
+use crate::instructions::migrate_allowance::MigrateAllowance;
+use crate::instructions::set_token_config::SetTokenConfig;
+use crate::instructions::set_payout_address::SetPayoutAddress;
 
 #[program]
 pub mod vault {
@@ -38,6 +49,11 @@ pub mod vault {
         instructions::initialize_vault::handler(ctx)
     }
 
+    /// Initialize a user vault on the user's behalf (processor only, processor pays)
+    pub fn initialize_vault_for(ctx: Context<InitializeVaultFor>, owner: Pubkey) -> Result<()> {
+        instructions::initialize_vault_for::handler(ctx, owner)
+    }
+
     /// Initialize the casino vault (admin only, one-time setup)
     pub fn initialize_casino_vault(
         ctx: Context<InitializeCasinoVault>,
@@ -87,6 +103,27 @@ pub mod vault {
         instructions::approve_allowance_v2::handler(ctx, amount, duration_seconds, token_mint, nonce)
     }
 
+    /// Approve spending allowance with per-spend and hourly velocity limits (0 = unlimited)
+    pub fn approve_allowance_v3(
+        ctx: Context<ApproveAllowanceV2>,
+        amount: u64,
+        duration_seconds: i64,
+        token_mint: Pubkey,
+        nonce: u64,
+        max_single_spend: u64,
+        max_spend_per_hour: u64,
+    ) -> Result<()> {
+        instructions::approve_allowance_v2::limited_handler(
+            ctx,
+            amount,
+            duration_seconds,
+            token_mint,
+            nonce,
+            max_single_spend,
+            max_spend_per_hour,
+        )
+    }
+
     /// Revoke an active allowance
     pub fn revoke_allowance(ctx: Context<RevokeAllowance>) -> Result<()> {
         instructions::revoke_allowance::handler(ctx)
@@ -110,6 +147,26 @@ pub mod vault {
         instructions::payout::handler(ctx, amount, bet_id)
     }
 
+    /// Spend a bet's stake and pay any winnings atomically (called by processor)
+    pub fn settle_bet(
+        ctx: Context<SettleBet>,
+        amount: u64,
+        payout: u64,
+        bet_id: String,
+    ) -> Result<()> {
+        instructions::settle_bet::handler(ctx, amount, payout, bet_id)
+    }
+
+    /// Return the stake of a lost, voided bet and mark it refunded (called by processor)
+    pub fn refund_bet(ctx: Context<RefundBet>, bet_id: String) -> Result<()> {
+        instructions::refund_bet::handler(ctx, bet_id)
+    }
+
+    /// Pay the vault's winnings to another wallet, e.g. cold storage; `None` pays the vault again (owner only)
+    pub fn set_payout_address(ctx: Context<SetPayoutAddress>, payout_address: Option<Pubkey>) -> Result<()> {
+        instructions::set_payout_address::handler(ctx, payout_address)
+    }
+
     /// Withdraw SOL from vault to user wallet (user only, always available)
     pub fn withdraw_sol(ctx: Context<WithdrawSol>, amount: u64) -> Result<()> {
         instructions::withdraw_sol::handler(ctx, amount)
@@ -134,4 +191,56 @@ pub mod vault {
     pub fn withdraw_casino_funds(ctx: Context<WithdrawCasinoFunds>, amount: u64) -> Result<()> {
         instructions::withdraw_casino_funds::handler(ctx, amount)
     }
+
+    /// Top up the casino vault from the treasury (treasury or authority only)
+    pub fn fund_casino_vault(ctx: Context<FundCasinoVault>, amount: u64) -> Result<()> {
+        instructions::fund_casino_vault::handler(ctx, amount)
+    }
+
+    /// Rotate the processor key allowed to settle bets (admin only)
+    pub fn set_processor(ctx: Context<SetProcessor>, new_processor: Pubkey) -> Result<()> {
+        instructions::set_processor::handler(ctx, new_processor)
+    }
+
+    /// Transfer casino admin rights, e.g. to a Squads multisig vault (admin only)
+    pub fn set_authority(ctx: Context<SetAuthority>, new_authority: Pubkey) -> Result<()> {
+        instructions::set_authority::handler(ctx, new_authority)
+    }
+
+    /// Authorize an additional processor key (admin only)
+    pub fn add_processor(ctx: Context<AddProcessor>, processor: Pubkey) -> Result<()> {
+        instructions::manage_processors::add_handler(ctx, processor)
+    }
+
+    /// Revoke a processor key; the last one cannot be removed (admin only)
+    pub fn remove_processor(ctx: Context<RemoveProcessor>, processor: Pubkey) -> Result<()> {
+        instructions::manage_processors::remove_handler(ctx, processor)
+    }
+
+    /// Resize a casino account created before multiple processors were supported (admin only)
+    pub fn migrate_casino(ctx: Context<MigrateCasino>) -> Result<()> {
+        instructions::migrate_casino::handler(ctx)
+    }
+
+    /// Bring the casino account to this program's layout and stamp its version (admin only)
+    pub fn migrate(ctx: Context<MigrateCasino>) -> Result<()> {
+        instructions::migrate_casino::migrate_handler(ctx)
+    }
+
+    /// Resize an allowance created before spend velocity limits; needed before it
+    /// can be spent from or revoked (anyone may pay)
+    pub fn migrate_allowance(ctx: Context<MigrateAllowance>) -> Result<()> {
+        instructions::migrate_allowance::handler(ctx)
+    }
+
+    /// Set a mint's bet limits and whether it accepts bets (admin only)
+    pub fn set_token_config(
+        ctx: Context<SetTokenConfig>,
+        mint: Pubkey,
+        min_bet: u64,
+        max_bet: u64,
+        bet_enabled: bool,
+    ) -> Result<()> {
+        instructions::set_token_config::handler(ctx, mint, min_bet, max_bet, bet_enabled)
+    }
 }
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::errors::VaultError;
 
 /// User vault account - stores SOL and tracks allowances
 #[account]
@@ -25,6 +26,21 @@ impl Vault {
         8 + // sol_balance
         8 + // created_at
         8; // last_activity
+
+    /// Size once `set_payout_address` has appended the payout address. Kept out
+    /// of the struct so vaults created before it still deserialize.
+    pub const LEN_WITH_PAYOUT_ADDRESS: usize = Self::LEN + 32;
+
+    /// Where the owner asked winnings to be paid instead of this vault;
+    /// `None` for vaults never extended or cleared back to `Pubkey::default()`
+    pub fn payout_address(info: &AccountInfo) -> Result<Option<Pubkey>> {
+        let data = info.try_borrow_data()?;
+        let Some(bytes) = data.get(Self::LEN..Self::LEN_WITH_PAYOUT_ADDRESS) else {
+            return Ok(None);
+        };
+        let address = Pubkey::try_from(bytes).map_err(|_| VaultError::InvalidPayoutAddress)?;
+        Ok((address != Pubkey::default()).then_some(address))
+    }
 }
 
 /// Casino vault account - program-owned account holding casino funds
@@ -72,6 +88,10 @@ pub struct Casino {
     pub total_volume: u64,
     /// Timestamp when casino was created
     pub created_at: i64,
+    /// Additional processors authorized to execute bets (`Pubkey::default()` = empty slot)
+    pub extra_processors: [Pubkey; MAX_EXTRA_PROCESSORS],
+    /// Layout version stamped by `migrate` (0 = never migrated)
+    pub program_version: u32,
 }
 
 impl Casino {
@@ -84,7 +104,21 @@ impl Casino {
         1 + // paused
         8 + // total_bets
         8 + // total_volume
-        8; // created_at
+        8 + // created_at
+        32 * MAX_EXTRA_PROCESSORS + // extra_processors
+        4; // program_version
+
+    /// Size before `extra_processors` was added; such accounts need `migrate_casino`
+    pub const LEGACY_LEN: usize = Self::LEN - 32 * MAX_EXTRA_PROCESSORS - 4;
+
+    /// Byte offset of `program_version`, the last field
+    pub const PROGRAM_VERSION_OFFSET: usize = Self::LEN - 4;
+
+    /// Whether `key` may settle bets: the primary processor or any extra one
+    pub fn is_processor(&self, key: &Pubkey) -> bool {
+        *key != Pubkey::default()
+            && (self.processor == *key || self.extra_processors.contains(key))
+    }
 }
 
 /// Allowance for spending without per-transaction signatures
@@ -114,6 +148,14 @@ pub struct Allowance {
     pub last_spent_at: i64,
     /// Number of times spent
     pub spend_count: u32,
+    /// Cap on a single spend (0 = no limit beyond the allowance amount)
+    pub max_single_spend: u64,
+    /// Cap on spends within one `SPEND_WINDOW_DURATION` window (0 = no limit)
+    pub max_spend_per_hour: u64,
+    /// Start of the current spend window
+    pub window_start: i64,
+    /// Amount spent in the current spend window
+    pub window_spent: u64,
 }
 
 impl Allowance {
@@ -129,7 +171,16 @@ impl Allowance {
         1 + // revoked
         1 + // bump
         8 + // last_spent_at
-        4; // spend_count
+        4 + // spend_count
+        8 + // max_single_spend
+        8 + // max_spend_per_hour
+        8 + // window_start
+        8; // window_spent
+
+    /// Size before the velocity limit fields were added; such accounts need `migrate_allowance`
+    pub const LEGACY_LEN: usize = Self::LEN - 8 * 4;
+
+    pub const SPEND_WINDOW_DURATION: i64 = 3600; // 1 hour
 
     pub fn remaining(&self) -> u64 {
         self.amount.saturating_sub(self.spent)
@@ -138,6 +189,31 @@ impl Allowance {
     pub fn is_valid(&self, clock: &Clock) -> bool {
         !self.revoked && clock.unix_timestamp <= self.expires_at
     }
+
+    /// Enforce the velocity limits set at approval and count `amount` against
+    /// the current spend window
+    pub fn record_spend_velocity(&mut self, amount: u64, now: i64) -> Result<()> {
+        require!(
+            self.max_single_spend == 0 || amount <= self.max_single_spend,
+            VaultError::SpendExceedsSingleLimit
+        );
+
+        if now - self.window_start >= Self::SPEND_WINDOW_DURATION {
+            self.window_start = now;
+            self.window_spent = 0;
+        }
+        let window_spent = self
+            .window_spent
+            .checked_add(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        require!(
+            self.max_spend_per_hour == 0 || window_spent <= self.max_spend_per_hour,
+            VaultError::SpendVelocityExceeded
+        );
+        self.window_spent = window_spent;
+
+        Ok(())
+    }
 }
 
 /// Per-user-per-casino nonce registry for deterministic allowance PDA creation
@@ -200,6 +276,10 @@ pub struct ProcessedBet {
     pub signature: String,
     /// Bump seed
     pub bump: u8,
+    /// `BET_OUTCOME_*`; unrecorded for bets settled by `spend_from_allowance`
+    pub outcome: u8,
+    /// Winnings paid by `settle_bet` (0 for a loss), or the stake returned by `refund_bet`
+    pub payout: u64,
 }
 
 impl ProcessedBet {
@@ -212,13 +292,77 @@ impl ProcessedBet {
         8 + // amount
         8 + // processed_at
         4 + Self::MAX_SIGNATURE_LEN + // signature
+        1 + // bump
+        1 + // outcome
+        8; // payout
+}
+
+/// Record of a `payout`; its PDA can only be created once per bet id
+#[account]
+pub struct PayoutRecord {
+    /// Bet ID
+    pub bet_id: String,
+    /// Vault the winnings were paid for
+    pub vault: Pubkey,
+    /// Amount paid
+    pub amount: u64,
+    /// Timestamp of the payout
+    pub paid_at: i64,
+    /// Bump seed
+    pub bump: u8,
+}
+
+impl PayoutRecord {
+    pub const LEN: usize = 8 + // discriminator
+        4 + MAX_BET_ID_LENGTH + // bet_id (String with length prefix)
+        32 + // vault
+        8 + // amount
+        8 + // paid_at
+        1; // bump
+}
+
+/// Per-mint bet limits set by the casino authority, checked on every spend
+#[account]
+pub struct TokenConfig {
+    /// Casino this config belongs to
+    pub casino: Pubkey,
+    /// Token mint; `System::id()` for native SOL
+    pub mint: Pubkey,
+    /// Smallest stake, in the mint's base units
+    pub min_bet: u64,
+    /// Largest stake, in the mint's base units
+    pub max_bet: u64,
+    /// Whether new bets in this token are accepted
+    pub bet_enabled: bool,
+    /// Last change timestamp
+    pub updated_at: i64,
+    /// Bump seed
+    pub bump: u8,
+}
+
+impl TokenConfig {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // casino
+        32 + // mint
+        8 + // min_bet
+        8 + // max_bet
+        1 + // bet_enabled
+        8 + // updated_at
         1; // bump
 }
 
+/// `ProcessedBet::outcome` values
+pub const BET_OUTCOME_UNRECORDED: u8 = 0;
+pub const BET_OUTCOME_LOST: u8 = 1;
+pub const BET_OUTCOME_WON: u8 = 2;
+/// Voided after a loss; `payout` holds the returned stake
+pub const BET_OUTCOME_REFUNDED: u8 = 3;
+
 // Constants with rationale
 
 /// Minimum bet amount in lamports (0.01 SOL)
 /// Rationale: Prevents spam bets and ensures meaningful transactions
+/// Only applies to native SOL spends made without a `TokenConfig`
 pub const MIN_BET_LAMPORTS: u64 = 10_000_000;
 
 /// Maximum bet amount in lamports (1000 SOL)
@@ -242,6 +386,15 @@ pub const RENT_EXEMPT_RESERVE_CASINO_VAULT: u64 = 1_343_280;
 /// IMPORTANT: Must be updated if Vault::LEN changes
 pub const RENT_EXEMPT_RESERVE_USER_VAULT: u64 = 1_566_960;
 
-/// Maximum bet ID length (UUID without hyphens = 32 chars)
-/// Rationale: Solana PDA seeds have 32-byte limit per seed
+/// Extra processor slots per casino, on top of `Casino::processor`
+/// Rationale: Enough for overlapping key rotation plus a few horizontally scaled processors
+pub const MAX_EXTRA_PROCESSORS: usize = 4;
+
+/// Version `migrate` stamps into `Casino::program_version`
+/// Rationale: Bump with every upgrade that changes an account layout or instruction
+/// discriminator, so processors built for an older program refuse to settle against it
+pub const CURRENT_PROGRAM_VERSION: u32 = 3;
+
+/// Bet ID length (UUID without hyphens = 32 chars); ids must be exactly this long
+/// Rationale: Solana PDA seeds have 32-byte limit per seed, and truncated ids could collide
 pub const MAX_BET_ID_LENGTH: usize = 32;
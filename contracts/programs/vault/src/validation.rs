@@ -38,6 +38,35 @@ pub fn validate_bet_amount(amount: u64) -> Result<()> {
     Ok(())
 }
 
+/// The `TokenConfig` at a mint's (seed-checked) PDA, `None` until the authority creates it
+pub fn load_token_config(account: &UncheckedAccount) -> Result<Option<crate::state::TokenConfig>> {
+    if *account.owner != crate::ID || account.data_is_empty() {
+        return Ok(None);
+    }
+    let data = account.try_borrow_data()?;
+    Ok(Some(crate::state::TokenConfig::try_deserialize(&mut &data[..])?))
+}
+
+/// Validate a stake against the mint's `TokenConfig`. Without one, only native
+/// SOL is accepted, within the `MIN_BET_LAMPORTS`..`MAX_BET_LAMPORTS` defaults.
+pub fn validate_token_bet(
+    amount: u64,
+    token_mint: &Pubkey,
+    token_config: Option<&crate::state::TokenConfig>,
+) -> Result<()> {
+    let Some(config) = token_config else {
+        require!(*token_mint == System::id(), VaultError::TokenNotConfigured);
+        return validate_bet_amount(amount);
+    };
+    require!(config.mint == *token_mint, VaultError::TokenMintMismatch);
+    require!(config.bet_enabled, VaultError::TokenBetsDisabled);
+    require!(
+        amount >= config.min_bet && amount <= config.max_bet,
+        VaultError::InvalidBetAmount
+    );
+    Ok(())
+}
+
 /// Validate allowance parameters
 pub fn validate_allowance_params(amount: u64, duration_seconds: i64) -> Result<()> {
     require!(
@@ -53,10 +82,12 @@ pub fn validate_allowance_params(amount: u64, duration_seconds: i64) -> Result<(
     Ok(())
 }
 
-/// Validate bet ID format
+/// Validate bet ID format: exactly 32 lowercase hex chars (a UUID without hyphens).
+/// Shorter ids are rejected so truncated ids cannot collide on PDA seeds.
 pub fn validate_bet_id(bet_id: &str) -> Result<()> {
     require!(
-        !bet_id.is_empty() && bet_id.len() <= crate::state::MAX_BET_ID_LENGTH,
+        bet_id.len() == crate::state::MAX_BET_ID_LENGTH
+            && bet_id.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)),
         VaultError::InvalidBetId
     );
     Ok(())
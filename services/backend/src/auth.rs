@@ -0,0 +1,127 @@
+//! Wallet-scoped read tokens
+//!
+//! `GET /api/bets*` serves the bets of one wallet, so reading them should take
+//! proof of holding it. A client signs `wallet_token_message` with the wallet's
+//! key and exchanges it at `POST /api/auth/token` for an opaque bearer token;
+//! the token maps to the wallet in Redis until it expires. Each signature is
+//! accepted once, and only while `issued_at` is recent, so a leaked request
+//! can't be replayed for a fresh token.
+
+use chrono::{DateTime, Duration, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use shared::api::wallet_token_message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::domain::WalletTokenRequest;
+use crate::errors::{AppError, Result};
+
+/// Redis key prefix for a token's wallet
+const TOKEN_PREFIX: &str = "auth:tokens:";
+
+/// Redis key prefix for signatures already exchanged for a token
+const SIGNATURE_PREFIX: &str = "auth:signatures:";
+
+fn token_key(token: &str) -> String {
+    format!("{}{}", TOKEN_PREFIX, token)
+}
+
+fn signature_key(signature: &str) -> String {
+    format!("{}{}", SIGNATURE_PREFIX, signature)
+}
+
+/// Check that `req` is a recent signature by its wallet over the token message
+pub fn verify_request(req: &WalletTokenRequest, now: DateTime<Utc>, max_age_seconds: i64) -> Result<Pubkey> {
+    let wallet = Pubkey::from_str(&req.wallet)
+        .map_err(|_| AppError::invalid_input(format!("Invalid wallet address: {}", req.wallet)))?;
+    let signature =
+        Signature::from_str(&req.signature).map_err(|_| AppError::invalid_input("Invalid signature encoding"))?;
+
+    let age = now.timestamp() - req.issued_at;
+    // A little allowance for clients whose clock runs ahead
+    if age > max_age_seconds || age < -max_age_seconds {
+        return Err(AppError::unauthorized("Token request has expired; sign a new one"));
+    }
+
+    let message = wallet_token_message(&req.wallet, req.issued_at);
+    if !signature.verify(wallet.as_ref(), message.as_bytes()) {
+        return Err(AppError::unauthorized("Signature does not match wallet"));
+    }
+    Ok(wallet)
+}
+
+/// Random bearer token
+pub(crate) fn new_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Store a new token for `wallet`, once per signature; returns it with its expiry
+pub async fn issue(
+    redis: &mut ConnectionManager,
+    req: &WalletTokenRequest,
+    ttl_seconds: u64,
+    signature_max_age_seconds: i64,
+) -> Result<(String, DateTime<Utc>)> {
+    // Remembered as long as the signature could still pass `verify_request`
+    let first: bool = redis::cmd("SET")
+        .arg(signature_key(&req.signature))
+        .arg(&req.wallet)
+        .arg("NX")
+        .arg("EX")
+        .arg((signature_max_age_seconds * 2).max(1))
+        .query_async::<Option<String>>(redis)
+        .await?
+        .is_some();
+    if !first {
+        return Err(AppError::unauthorized("Signature has already been used"));
+    }
+
+    let token = new_token();
+    let _: () = redis.set_ex(token_key(&token), &req.wallet, ttl_seconds).await?;
+    Ok((token, Utc::now() + Duration::seconds(ttl_seconds as i64)))
+}
+
+/// Wallet a token grants read access to, `None` if unknown or expired
+pub async fn wallet_for(redis: &mut ConnectionManager, token: &str) -> Result<Option<String>> {
+    Ok(redis.get(token_key(token)).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    fn signed(keypair: &Keypair, issued_at: i64) -> WalletTokenRequest {
+        let wallet = keypair.pubkey().to_string();
+        let signature = keypair.sign_message(wallet_token_message(&wallet, issued_at).as_bytes());
+        WalletTokenRequest { wallet, issued_at, signature: signature.to_string() }
+    }
+
+    #[test]
+    fn test_verify_request() {
+        let keypair = Keypair::new();
+        let now = Utc::now();
+
+        let req = signed(&keypair, now.timestamp() - 10);
+        assert_eq!(verify_request(&req, now, 300).unwrap(), keypair.pubkey());
+
+        // Stale or far-future requests
+        assert!(verify_request(&signed(&keypair, now.timestamp() - 301), now, 300).is_err());
+        assert!(verify_request(&signed(&keypair, now.timestamp() + 301), now, 300).is_err());
+
+        // Signed by another key, or for another timestamp
+        let mut other = signed(&Keypair::new(), now.timestamp());
+        other.wallet = req.wallet.clone();
+        assert!(verify_request(&other, now, 300).is_err());
+        let mut moved = req.clone();
+        moved.issued_at += 1;
+        assert!(verify_request(&moved, now, 300).is_err());
+
+        let mut garbled = req;
+        garbled.signature = "not-a-signature".to_string();
+        assert!(verify_request(&garbled, now, 300).is_err());
+    }
+}
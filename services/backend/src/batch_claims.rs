@@ -0,0 +1,209 @@
+//! Batch ownership for external updates
+//!
+//! A claim records the claiming processor and version 1 for its batch. Each
+//! update must name that processor and the batch's current version, which is
+//! bumped once every bet in the update has been written. An update that failed
+//! partway leaves the version alone, so the processor retries it as is. An
+//! update at an older version is a resend of one already applied: it succeeds
+//! when its bets already hold its results and gets 409 otherwise. When two
+//! workers report results for the same claim, only the first to finish bumps the
+//! version; the other gets 409.
+
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+use redis::Script;
+use shared::errors::ServiceError;
+use uuid::Uuid;
+
+use crate::errors::{AppError, Result};
+
+/// Redis key prefix for a batch's claim record
+const CLAIM_PREFIX: &str = "batch:claim:";
+
+/// Updates arriving after this are rejected as for an unknown batch
+const CLAIM_TTL_SECONDS: i64 = 7 * 86_400;
+
+/// Version handed out with a fresh claim
+pub const INITIAL_VERSION: u64 = 1;
+
+/// Check the claim's processor and version, then bump the version if ARGV[3] is "1".
+/// Returns {"ok", version after the call, claimed_at_ms}, {"processor", owner},
+/// {"version", current} or {"unknown"}.
+const ADVANCE_SCRIPT: &str = r#"
+local claim = redis.call('HMGET', KEYS[1], 'processor_id', 'version', 'claimed_at_ms')
+if not claim[1] then
+    return {'unknown'}
+end
+if claim[1] ~= ARGV[1] then
+    return {'processor', claim[1]}
+end
+if claim[2] ~= ARGV[2] then
+    return {'version', claim[2]}
+end
+if ARGV[3] ~= '1' then
+    return {'ok', claim[2], claim[3]}
+end
+local version = redis.call('HINCRBY', KEYS[1], 'version', 1)
+return {'ok', tostring(version), claim[3]}
+"#;
+
+fn claim_key(batch_id: Uuid) -> String {
+    format!("{}{}", CLAIM_PREFIX, batch_id)
+}
+
+/// The batch's owner as of an accepted check or update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchClaim {
+    pub processor_id: String,
+    /// Version the next update must carry
+    pub version: u64,
+    pub claimed_at_ms: Option<i64>,
+}
+
+/// How an update's version compares with its batch's claim
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimCheck {
+    /// At the current version: the update is applied
+    Current(BatchClaim),
+    /// At an earlier version: a resend of an update that was already applied
+    Stale(BatchClaim),
+}
+
+/// Record `processor_id` as the owner of a freshly claimed batch
+pub async fn record(redis: &mut ConnectionManager, batch_id: Uuid, processor_id: &str) -> Result<u64> {
+    let key = claim_key(batch_id);
+    let _: () = redis::pipe()
+        .atomic()
+        .hset_multiple(
+            &key,
+            &[
+                ("processor_id", processor_id.to_string()),
+                ("version", INITIAL_VERSION.to_string()),
+                ("claimed_at_ms", Utc::now().timestamp_millis().to_string()),
+            ],
+        )
+        .ignore()
+        .expire(&key, CLAIM_TTL_SECONDS)
+        .ignore()
+        .query_async(redis)
+        .await?;
+    Ok(INITIAL_VERSION)
+}
+
+/// Accept an update from `processor_id` at `version` or an earlier one without
+/// bumping it, or fail with 409 (404 for unknown batches)
+pub async fn check(redis: &mut ConnectionManager, batch_id: Uuid, processor_id: &str, version: u64) -> Result<ClaimCheck> {
+    let reply = run_script(redis, batch_id, processor_id, version, false).await?;
+    parse_check_reply(batch_id, processor_id, version, &reply)
+}
+
+/// Bump the version once the update at `version` has been written; fails with
+/// 409 if another update for the claim finished first
+pub async fn advance(
+    redis: &mut ConnectionManager,
+    batch_id: Uuid,
+    processor_id: &str,
+    version: u64,
+) -> Result<BatchClaim> {
+    let reply = run_script(redis, batch_id, processor_id, version, true).await?;
+    parse_reply(batch_id, processor_id, version, &reply)
+}
+
+async fn run_script(
+    redis: &mut ConnectionManager,
+    batch_id: Uuid,
+    processor_id: &str,
+    version: u64,
+    bump: bool,
+) -> Result<Vec<String>> {
+    Ok(Script::new(ADVANCE_SCRIPT)
+        .key(claim_key(batch_id))
+        .arg(processor_id)
+        .arg(version)
+        .arg(if bump { "1" } else { "0" })
+        .invoke_async(redis)
+        .await?)
+}
+
+/// A version behind the claim's is a resend from its owner; the script only
+/// compares versions once the processor matched
+pub(crate) fn parse_check_reply(batch_id: Uuid, processor_id: &str, version: u64, reply: &[String]) -> Result<ClaimCheck> {
+    if reply.first().map(String::as_str) == Some("version") {
+        let current = reply.get(1).and_then(|current| current.parse::<u64>().ok());
+        if let Some(current) = current.filter(|current| *current > version) {
+            return Ok(ClaimCheck::Stale(BatchClaim {
+                processor_id: processor_id.to_string(),
+                version: current,
+                claimed_at_ms: None,
+            }));
+        }
+    }
+    parse_reply(batch_id, processor_id, version, reply).map(ClaimCheck::Current)
+}
+
+pub(crate) fn parse_reply(batch_id: Uuid, processor_id: &str, version: u64, reply: &[String]) -> Result<BatchClaim> {
+    let field = |i: usize| reply.get(i).map(String::as_str).unwrap_or_default();
+    match field(0) {
+        "ok" => Ok(BatchClaim {
+            processor_id: processor_id.to_string(),
+            version: field(1).parse().map_err(anyhow::Error::from)?,
+            claimed_at_ms: field(2).parse().ok(),
+        }),
+        "processor" => Err(AppError::batch_conflict(format!(
+            "Batch {} was claimed by {}, not {}",
+            batch_id,
+            field(1),
+            processor_id
+        ))),
+        "version" => Err(AppError::batch_conflict(format!(
+            "Batch {} is at version {}; update sent version {}",
+            batch_id,
+            field(1),
+            version
+        ))),
+        _ => Err(AppError::Service(ServiceError::batch_not_found(batch_id))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::errors::ErrorCategory;
+
+    fn reply(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|f| f.to_string()).collect()
+    }
+
+    fn category(result: Result<BatchClaim>) -> ErrorCategory {
+        result.unwrap_err().to_service_error().category
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        let batch_id = Uuid::new_v4();
+        let claim = parse_reply(batch_id, "processor-1", 1, &reply(&["ok", "2", "1700000000000"])).unwrap();
+        assert_eq!(claim.version, 2);
+        assert_eq!(claim.claimed_at_ms, Some(1_700_000_000_000));
+
+        let stale = parse_reply(batch_id, "processor-1", 1, &reply(&["version", "2"]));
+        assert_eq!(category(stale), ErrorCategory::Conflict);
+        let foreign = parse_reply(batch_id, "processor-2", 1, &reply(&["processor", "processor-1"]));
+        assert_eq!(category(foreign), ErrorCategory::Conflict);
+        assert_eq!(category(parse_reply(batch_id, "processor-1", 1, &reply(&["unknown"]))), ErrorCategory::NotFound);
+    }
+
+    #[test]
+    fn test_parse_check_reply() {
+        let batch_id = Uuid::new_v4();
+        let check = |version: u64, fields: &[&str]| parse_check_reply(batch_id, "processor-1", version, &reply(fields));
+        let current = check(2, &["ok", "2", "1700000000000"]).unwrap();
+        assert!(matches!(current, ClaimCheck::Current(BatchClaim { version: 2, .. })));
+        let stale = check(1, &["version", "3"]).unwrap();
+        assert!(matches!(stale, ClaimCheck::Stale(BatchClaim { version: 3, .. })));
+
+        // A version the batch hasn't reached, or another processor, is still a conflict
+        assert_eq!(category(check(4, &["version", "3"]).map(|_| unreachable!())), ErrorCategory::Conflict);
+        let foreign = parse_check_reply(batch_id, "processor-2", 1, &reply(&["processor", "processor-1"]));
+        assert!(foreign.is_err());
+    }
+}
@@ -0,0 +1,39 @@
+//! Bet archival worker
+//!
+//! Terminal bets are only read for history, but they would otherwise stay in Redis
+//! forever. The archiver periodically copies terminal bets older than the retention
+//! window into the bet archive and replaces their hashes with tombstones. Reads keep
+//! working because `RedisBetRepository` falls back to the archive for tombstones.
+
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::repository::BetRepository;
+
+/// Run the archiver forever; archives up to `batch_size` bets per pass
+pub async fn run_archiver(repo: Arc<dyn BetRepository>, retention_days: u64, batch_size: i64, interval: Duration) {
+    tracing::info!(
+        retention_days,
+        batch_size,
+        interval_seconds = interval.as_secs(),
+        "Bet archiver started"
+    );
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let cutoff_ms = Utc::now().timestamp_millis() - (retention_days as i64).saturating_mul(86_400_000);
+        match repo.archive_terminal(cutoff_ms, batch_size).await {
+            Ok(0) => {}
+            Ok(archived) => {
+                metrics::counter!("bets_archived_total").increment(archived as u64);
+                tracing::info!(count = archived, "Archived terminal bets");
+            }
+            Err(e) => {
+                metrics::counter!("bet_archival_failures_total").increment(1);
+                tracing::error!(error = %e, "Bet archival pass failed");
+            }
+        }
+    }
+}
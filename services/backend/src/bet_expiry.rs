@@ -0,0 +1,185 @@
+//! Bet expiration sweeper
+//!
+//! A bet that sits unclaimed past `BET_TTL_SECONDS` (e.g. processors were down) must
+//! not be settled later at stale odds. The sweeper periodically moves such bets from
+//! the claimable queue to `Expired`, oldest first, until none are left. Users see the
+//! new status through `GET /api/bets/:bet_id` and get a `bet_expired` notification.
+//! The ledger is where a bet's stake is held against the casino's liability: its
+//! release books the stake back to the wallet, and the total is also reported via
+//! metrics.
+
+use shared::clock::Clock;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::SloConfig;
+use crate::domain::{Bet, BetStatus};
+use crate::errors::Result;
+use crate::ledger;
+use crate::notifications::Notification;
+use crate::repository::{BetRepository, OpsRepository};
+use crate::telemetry;
+
+/// Unclaimed bets inspected per repository call
+const SWEEP_SCAN_LIMIT: i64 = 500;
+
+/// Expire the bets left unclaimed for `ttl_seconds` by `clock`
+async fn expire_due(repo: &dyn BetRepository, clock: &dyn Clock, ttl_seconds: u64) -> Result<Vec<Bet>> {
+    let cutoff_ms = clock.now().timestamp_millis() - (ttl_seconds as i64).saturating_mul(1000);
+    let mut expired = Vec::new();
+    loop {
+        let page = repo.expire_unclaimed(cutoff_ms, SWEEP_SCAN_LIMIT).await?;
+        // Each full page left the index, so the next call sees bets further back
+        let more = page.len() as i64 == SWEEP_SCAN_LIMIT;
+        expired.extend(page);
+        if !more {
+            return Ok(expired);
+        }
+    }
+}
+
+/// Expire the due bets, releasing their stakes and telling their owners
+async fn sweep(
+    repo: &dyn BetRepository,
+    ops: &dyn OpsRepository,
+    clock: &dyn Clock,
+    ttl_seconds: u64,
+    slo_objective: &SloConfig,
+    notify: bool,
+) -> Result<Vec<Bet>> {
+    let expired = expire_due(repo, clock, ttl_seconds).await?;
+    for bet in &expired {
+        telemetry::record_bet_finished(bet.created_at, &BetStatus::Expired);
+        ops.record_slo(slo_objective, bet.created_at, &BetStatus::Expired).await;
+        tracing::info!(
+            bet_id = %bet.bet_id,
+            user_wallet = %bet.user_wallet,
+            stake_amount = bet.stake_amount,
+            "Bet expired before settlement"
+        );
+        ops.post_ledger_or_warn(&[ledger::release(bet)]).await;
+    }
+    if notify && !expired.is_empty() {
+        let notifications: Vec<_> = expired.iter().map(Notification::bet_expired).collect();
+        ops.publish_notifications(&notifications).await;
+    }
+    Ok(expired)
+}
+
+/// Run the sweeper forever; returns immediately if `ttl_seconds` is 0
+pub async fn run_expiry_sweeper(
+    repo: Arc<dyn BetRepository>,
+    ops: Arc<dyn OpsRepository>,
+    clock: Arc<dyn Clock>,
+    ttl_seconds: u64,
+    interval: Duration,
+    slo_objective: SloConfig,
+    notify: bool,
+) {
+    if ttl_seconds == 0 {
+        tracing::info!("Bet TTL disabled; expiry sweeper not started");
+        return;
+    }
+
+    tracing::info!(
+        ttl_seconds,
+        interval_seconds = interval.as_secs(),
+        "Bet expiry sweeper started"
+    );
+
+    loop {
+        clock.sleep(interval).await;
+
+        match sweep(repo.as_ref(), ops.as_ref(), clock.as_ref(), ttl_seconds, &slo_objective, notify).await {
+            Ok(expired) if expired.is_empty() => {}
+            Ok(expired) => {
+                let released_stake: i64 = expired.iter().map(|bet| bet.stake_amount).sum();
+                metrics::counter!("bets_expired_total").increment(expired.len() as u64);
+                metrics::counter!("bets_expired_stake_lamports_total").increment(released_stake.max(0) as u64);
+                tracing::info!(count = expired.len(), released_stake, "Expired stale bets");
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Bet expiry sweep failed");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::NotificationEvent;
+    use crate::testing::{self, InMemoryBetRepository, InMemoryOpsRepository};
+    use shared::clock::ManualClock;
+
+    #[tokio::test]
+    async fn test_expires_once_ttl_passes_on_the_clock() {
+        let clock = ManualClock::at(chrono::Utc::now());
+        let repo = InMemoryBetRepository::new();
+        let bet = Bet { created_at: clock.now(), ..testing::pending_bet("W1") };
+        repo.insert(bet.clone());
+
+        clock.advance(Duration::from_secs(299));
+        assert!(expire_due(&repo, &clock, 300).await.unwrap().is_empty());
+
+        clock.advance(Duration::from_secs(1));
+        let expired = expire_due(&repo, &clock, 300).await.unwrap();
+        assert_eq!(expired.iter().map(|bet| bet.bet_id).collect::<Vec<_>>(), vec![bet.bet_id]);
+        assert_eq!(repo.find_by_id(bet.bet_id).await.unwrap().unwrap().status, BetStatus::Expired);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_pages_past_claimed_and_manual_bets() {
+        let clock = ManualClock::at(chrono::Utc::now());
+        let repo = InMemoryBetRepository::new();
+        let bet_at = |wallet: &str| Bet { created_at: clock.now(), ..testing::pending_bet(wallet) };
+
+        // More claimed-and-retried bets than one scan holds, all older than the pending ones
+        for _ in 0..SWEEP_SCAN_LIMIT {
+            let retried = bet_at("W1");
+            repo.insert(retried.clone());
+            repo.update_status(retried.bet_id, BetStatus::Batched, None).await.unwrap();
+            repo.update_status(retried.bet_id, BetStatus::FailedRetryable, None).await.unwrap();
+        }
+        clock.advance(Duration::from_secs(1));
+        let pending: Vec<Bet> = (0..SWEEP_SCAN_LIMIT + 1).map(|_| bet_at("W2")).collect();
+        for bet in &pending {
+            repo.insert(bet.clone());
+        }
+        // Bets an operator settled by hand, expired first or not, settle as they chose
+        let manual = bet_at("W3");
+        repo.insert(manual.clone());
+        repo.update_status(manual.bet_id, BetStatus::Expired, None).await.unwrap();
+        repo.enqueue_manual_settlement(manual.bet_id, true, 200).await.unwrap();
+        let manual_pending = bet_at("W3");
+        repo.insert(manual_pending.clone());
+        repo.enqueue_manual_settlement(manual_pending.bet_id, false, 0).await.unwrap();
+
+        clock.advance(Duration::from_secs(300));
+        let expired = expire_due(&repo, &clock, 300).await.unwrap();
+        assert_eq!(expired.len(), pending.len());
+        assert!(expired.iter().all(|bet| bet.user_wallet == "W2"));
+        assert_eq!(repo.find_by_id(manual.bet_id).await.unwrap().unwrap().status, BetStatus::Pending);
+        assert_eq!(repo.find_by_id(manual_pending.bet_id).await.unwrap().unwrap().status, BetStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_releases_stakes_and_notifies_through_the_ops_repository() {
+        let clock = ManualClock::at(chrono::Utc::now());
+        let repo = InMemoryBetRepository::new();
+        let ops = InMemoryOpsRepository::new();
+        let bet = Bet { created_at: clock.now(), ..testing::pending_bet("W1") };
+        repo.insert(bet.clone());
+        ops.post_ledger(&[ledger::reserve(&bet)]).await.unwrap();
+        let slo = testing::config().slo;
+
+        clock.advance(Duration::from_secs(300));
+        let expired = sweep(&repo, &ops, &clock, 300, &slo, true).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(ops.balance(&ledger::wallet_reserved("W1", &bet.stake_token)), 0);
+        let published = ops.published();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].event, NotificationEvent::BetExpired);
+        assert_eq!(published[0].bet_id, Some(bet.bet_id));
+    }
+}
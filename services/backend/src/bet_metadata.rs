@@ -0,0 +1,86 @@
+//! Client metadata on bets
+//!
+//! Frontends attach a small JSON object to a bet (client version, UI session
+//! id, campaign tag). The backend never interprets it: `create_bet` checks it
+//! against `BET_METADATA_MAX_BYTES` and `BET_METADATA_MAX_KEYS`, the bet hash
+//! stores it as JSON and reads return it as sent. String values of the keys in
+//! `BET_METADATA_INDEXED_KEYS` are also indexed, newest bet first, for
+//! `GET /api/admin/bets/search`.
+
+use crate::config::MetadataConfig;
+use crate::domain::BetMetadata;
+use crate::errors::{AppError, Result};
+
+/// Longest metadata key accepted
+const MAX_KEY_LEN: usize = 64;
+
+/// Longer string values are stored but not indexed
+const MAX_INDEXED_VALUE_LEN: usize = 128;
+
+/// Keys are limited to characters that can't collide with the index key layout
+fn valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Refuse metadata over the configured caps or with malformed keys
+pub fn validate(metadata: &BetMetadata, config: &MetadataConfig) -> Result<()> {
+    if metadata.len() > config.max_keys {
+        return Err(AppError::invalid_input(format!("metadata may have at most {} keys", config.max_keys)));
+    }
+    if let Some(key) = metadata.keys().find(|key| !valid_key(key)) {
+        return Err(AppError::invalid_input(format!(
+            "metadata key '{}' must be 1-{} characters of A-Z, a-z, 0-9, '_', '-' or '.'",
+            key.chars().take(MAX_KEY_LEN).collect::<String>(),
+            MAX_KEY_LEN
+        )));
+    }
+    let size = serde_json::to_vec(metadata).map_err(anyhow::Error::from)?.len();
+    if size > config.max_bytes {
+        return Err(AppError::invalid_input(format!(
+            "metadata is {} bytes; at most {} are accepted",
+            size, config.max_bytes
+        )));
+    }
+    Ok(())
+}
+
+/// Key/value pairs of `metadata` to index: string values of `indexed_keys`
+pub fn indexed_entries<'a>(metadata: &'a BetMetadata, indexed_keys: &[String]) -> Vec<(&'a str, &'a str)> {
+    metadata
+        .iter()
+        .filter(|(key, _)| indexed_keys.contains(key))
+        .filter_map(|(key, value)| value.as_str().map(|value| (key.as_str(), value)))
+        .filter(|(_, value)| !value.is_empty() && value.len() <= MAX_INDEXED_VALUE_LEN)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn metadata(value: serde_json::Value) -> BetMetadata {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_validate_and_index() {
+        let config = MetadataConfig {
+            max_bytes: 64,
+            max_keys: 3,
+            indexed_keys: vec!["campaign".to_string(), "client_version".to_string()],
+        };
+
+        let ok = metadata(json!({"campaign": "spring", "client_version": 7, "ui_session": "abc"}));
+        assert!(validate(&ok, &config).is_ok());
+        // Only string values of allowlisted keys are indexed
+        assert_eq!(indexed_entries(&ok, &config.indexed_keys), vec![("campaign", "spring")]);
+
+        assert!(validate(&metadata(json!({"a": 1, "b": 2, "c": 3, "d": 4})), &config).is_err());
+        assert!(validate(&metadata(json!({"bad:key": 1})), &config).is_err());
+        assert!(validate(&metadata(json!({"": 1})), &config).is_err());
+        assert!(validate(&metadata(json!({"blob": "x".repeat(64)})), &config).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+//! In-process cache for bets that can no longer change
+//!
+//! Only terminal bets are cached. `FailedManualReview` and `Expired` bets can
+//! still be settled by an admin, so they are kept for `SETTLEABLE_BET_TTL`
+//! only: another instance's cache may miss the change. Admin mutations call
+//! `invalidate` as well.
+
+use moka::future::Cache;
+use moka::Expiry;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::config::CacheConfig;
+use crate::domain::{Bet, BetStatus};
+use crate::handlers::admin::can_manually_settle;
+
+/// Longest a bet an admin can still settle is served from the cache
+const SETTLEABLE_BET_TTL: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct BetCache {
+    inner: Cache<Uuid, Arc<Bet>>,
+}
+
+impl BetCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        Self {
+            inner: Cache::builder()
+                .max_capacity(config.bet_cache_capacity)
+                .time_to_live(Duration::from_secs(config.bet_cache_ttl_seconds))
+                .expire_after(BetExpiry)
+                .build(),
+        }
+    }
+
+    pub async fn get(&self, bet_id: Uuid) -> Option<Arc<Bet>> {
+        let hit = self.inner.get(&bet_id).await;
+        let result = if hit.is_some() { "hit" } else { "miss" };
+        metrics::counter!("bet_cache_lookups_total", "result" => result).increment(1);
+        hit
+    }
+
+    /// Cache the bet if it is terminal; other bets are ignored
+    pub async fn insert(&self, bet: Arc<Bet>) {
+        if is_cacheable(&bet.status) {
+            self.inner.insert(bet.bet_id, bet).await;
+        }
+    }
+
+    pub async fn invalidate(&self, bet_id: Uuid) {
+        self.inner.invalidate(&bet_id).await;
+    }
+}
+
+fn is_cacheable(status: &BetStatus) -> bool {
+    status.is_terminal()
+}
+
+/// Shortens the TTL of bets an admin can still settle
+struct BetExpiry;
+
+impl Expiry<Uuid, Arc<Bet>> for BetExpiry {
+    fn expire_after_create(&self, _bet_id: &Uuid, bet: &Arc<Bet>, _created_at: Instant) -> Option<Duration> {
+        can_manually_settle(&bet.status).then_some(SETTLEABLE_BET_TTL)
+    }
+}
+
+/// Weak ETag over the serialized bet
+pub fn etag_for(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("W/\"{:016x}\"", hasher.finish())
+}
+
+/// Whether an `If-None-Match` header value matches `etag` (weak comparison)
+pub fn if_none_match(header: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_none_match() {
+        let etag = etag_for(b"{\"status\":\"completed\"}");
+        assert!(if_none_match(&etag, &etag));
+        assert!(if_none_match(&format!("\"other\", {}", etag), &etag));
+        assert!(if_none_match(etag.trim_start_matches("W/"), &etag));
+        assert!(if_none_match("*", &etag));
+        assert!(!if_none_match("W/\"0000000000000000\"", &etag));
+    }
+
+    #[tokio::test]
+    async fn test_only_terminal_bets_cacheable() {
+        let cache = BetCache::new(&crate::testing::config().cache);
+        let terminal = [BetStatus::Completed, BetStatus::Expired, BetStatus::FailedManualReview];
+        for status in terminal.iter().cloned().chain([BetStatus::Pending, BetStatus::SubmittedToSolana]) {
+            let bet = Arc::new(Bet { status: status.clone(), ..crate::testing::pending_bet("wallet") });
+            cache.insert(bet.clone()).await;
+            assert_eq!(cache.get(bet.bet_id).await.is_some(), terminal.contains(&status), "{:?}", status);
+        }
+    }
+
+    #[test]
+    fn test_settleable_bets_expire_early() {
+        let bet_with = |status| Arc::new(Bet { status, ..crate::testing::pending_bet("wallet") });
+        let expires = |bet: &Arc<Bet>| BetExpiry.expire_after_create(&bet.bet_id, bet, Instant::now());
+        assert_eq!(expires(&bet_with(BetStatus::Completed)), None);
+        assert_eq!(expires(&bet_with(BetStatus::Expired)), Some(SETTLEABLE_BET_TTL));
+        assert_eq!(expires(&bet_with(BetStatus::FailedManualReview)), Some(SETTLEABLE_BET_TTL));
+    }
+}
@@ -0,0 +1,88 @@
+//! Push of newly claimable bets to processors
+//!
+//! Processors may hold `GET /api/external/bets/subscribe` open and claim as soon
+//! as a `claimable` event arrives instead of on their next poll. Handlers
+//! PUBLISH each new bet to a Redis channel and every instance relays the channel
+//! to its own subscribers, so a processor hears about bets created on any
+//! instance. Signals are hints only: a missed one delays the bet until the
+//! processor's next poll, so publishing never fails a request.
+
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::domain::ClaimableEvent;
+
+/// Redis pub/sub channel carrying `ClaimableEvent` JSON
+const CHANNEL: &str = "bets:claimable:signals";
+
+/// Events buffered per subscriber before it starts missing some
+const HUB_CAPACITY: usize = 4096;
+
+/// Fan-out of claimable events to this instance's subscribers
+#[derive(Clone)]
+pub struct ClaimSignalHub {
+    sender: broadcast::Sender<ClaimableEvent>,
+}
+
+impl Default for ClaimSignalHub {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(HUB_CAPACITY).0 }
+    }
+}
+
+impl ClaimSignalHub {
+    pub fn subscribe(&self) -> broadcast::Receiver<ClaimableEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Processors currently subscribed on this instance
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+/// Tell every instance's subscribers a bet became claimable; failures are logged, never returned
+pub async fn signal_or_warn(redis: &mut ConnectionManager, event: ClaimableEvent) {
+    let result = match serde_json::to_string(&event) {
+        Ok(payload) => redis.publish::<_, _, i64>(CHANNEL, payload).await.map(|_| ()).map_err(anyhow::Error::from),
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = result {
+        tracing::warn!(bet_id = %event.bet_id, error = %e, "Failed to signal claimable bet");
+        metrics::counter!("claim_signal_publish_failures_total").increment(1);
+    }
+}
+
+/// Relay the channel into `hub`; resubscribes after `retry` when the connection drops
+pub async fn run_relay(client: redis::Client, hub: ClaimSignalHub, retry: Duration) {
+    loop {
+        if let Err(e) = relay(&client, &hub).await {
+            tracing::warn!(error = %e, "Claim signal subscription lost; resubscribing");
+            metrics::counter!("claim_signal_relay_reconnects_total").increment(1);
+        }
+        tokio::time::sleep(retry).await;
+    }
+}
+
+async fn relay(client: &redis::Client, hub: &ClaimSignalHub) -> anyhow::Result<()> {
+    use futures_util::StreamExt;
+
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.subscribe(CHANNEL).await?;
+    tracing::info!(channel = CHANNEL, "Relaying claim signals");
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let payload: String = message.get_payload()?;
+        match serde_json::from_str::<ClaimableEvent>(&payload) {
+            // Err only means no processor is subscribed right now
+            Ok(event) => {
+                let _ = hub.sender.send(event);
+            }
+            Err(e) => tracing::warn!(error = %e, "Ignoring malformed claim signal"),
+        }
+    }
+    anyhow::bail!("subscription closed")
+}
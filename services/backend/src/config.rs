@@ -1,21 +1,86 @@
-use serde::Deserialize;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use shared::profile::{self, Profile, ProfileDefaults};
+use shared::secrets::Resolver;
 use std::env;
 
-#[derive(Debug, Clone, Deserialize)]
+use crate::domain::GameConfig;
+use crate::games;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Profile the defaults came from (`--profile` / `CONFIG_PROFILE`)
+    pub profile: Option<Profile>,
     pub api_port: u16,
     pub metrics_port: u16,
     pub redis: RedisConfig,
+    pub queue: QueueConfig,
+    pub admin: AdminConfig,
+    pub auth: AuthConfig,
     pub solana: SolanaConfig,
     pub betting: BettingConfig,
+    pub cache: CacheConfig,
+    pub receipts: ReceiptConfig,
+    pub archive: ArchiveConfig,
+    pub pii: PiiConfig,
+    pub reports: ReportsConfig,
+    pub ledger: LedgerConfig,
+    pub settlements: SettlementsConfig,
+    pub slo: SloConfig,
+    pub jurisdiction: JurisdictionConfig,
+    pub indexer: IndexerConfig,
+    pub notifications: NotificationsConfig,
+    pub metadata: MetadataConfig,
+    /// Games bets may be placed on (`GAMES`)
+    pub games: Vec<GameConfig>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RedisConfig {
+    #[serde(serialize_with = "profile::redact_url")]
     pub url: String,
+    /// Refuse to start when a newer release has written bet records
+    pub refuse_future_schema: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueConfig {
+    /// "redis" (default) or "nats" (requires the `nats` feature)
+    pub backend: String,
+    pub nats_url: String,
+    /// Claim bets round-robin across wallets rather than strictly oldest first
+    pub fair_claims: bool,
+    /// Window of due bets a fair claim selects from, as a multiple of its limit
+    pub fair_claim_scan_multiplier: i64,
+    /// Bets of one wallet per fair claim (0 = no cap)
+    pub max_claim_per_wallet: usize,
+    /// Push new bets to processors on `/api/external/bets/subscribe`
+    pub claim_signals: bool,
+    /// Comment sent on idle subscriptions so proxies keep them open
+    pub subscribe_keep_alive_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminConfig {
+    /// Shared secret for `/api/admin/*`; admin routes reject everything when unset
+    #[serde(serialize_with = "profile::redact_optional")]
+    pub api_key: Option<String>,
+    /// Casino authority keypair, needed only to pause the casino on-chain from the kill switch
+    #[serde(serialize_with = "profile::redact_optional_keypair")]
+    pub casino_authority_keypair_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Lifetime of wallet read tokens
+    pub token_ttl_seconds: u64,
+    /// How old a signed token request may be
+    pub signature_max_age_seconds: i64,
+    /// Reject `GET /api/bets*` without a wallet token or the admin key
+    pub require_bet_read_token: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SolanaConfig {
     pub network: String,
     pub rpc_url: String,
@@ -23,17 +88,173 @@ pub struct SolanaConfig {
     pub vault_program_id: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptConfig {
+    /// Solana CLI keypair (file or secret reference) used to sign bet receipts; ephemeral key when unset
+    #[serde(serialize_with = "profile::redact_optional_keypair")]
+    pub keypair_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveConfig {
+    /// Postgres URL for archived bets; archival is disabled when unset
+    #[serde(serialize_with = "profile::redact_optional_url")]
+    pub database_url: Option<String>,
+    /// Terminal bets older than this are moved out of Redis
+    pub retention_days: u64,
+    pub interval_seconds: u64,
+    pub batch_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PiiConfig {
+    /// Key-encryption keys for client info kept with bets, comma-separated
+    /// `<id>:<base64 32-byte key>`; client info is not stored when unset
+    #[serde(serialize_with = "profile::redact_optional")]
+    pub keys: Option<String>,
+    /// Key new values are encrypted under; the first of `keys` when unset
+    pub active_key_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportsConfig {
+    /// Webhook (e.g. Slack incoming webhook) receiving each daily report
+    #[serde(serialize_with = "profile::redact_optional")]
+    pub daily_webhook_url: Option<String>,
+    pub daily_check_interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerConfig {
+    /// How often the ledger is checked against on-chain vaults; 0 disables the check
+    pub check_interval_seconds: u64,
+    /// Divergence in lamports tolerated before a check is flagged
+    pub tolerance_lamports: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementsConfig {
+    /// How often unfinalized settlement transactions are read again from RPC; 0 disables
+    pub reconcile_interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloConfig {
+    /// Share of bets that must settle within `latency_seconds`, e.g. 0.99
+    pub target: f64,
+    pub latency_seconds: u64,
+    /// Period the error budget `1 - target` is spread over
+    pub period_days: u64,
+    /// Burn rate over 1h (and 5m) that pages
+    pub fast_burn_threshold: f64,
+    /// Burn rate over 6h (and 30m) that warns
+    pub slow_burn_threshold: f64,
+    /// How often burn rates are exported and alerts evaluated; 0 disables
+    pub evaluate_interval_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JurisdictionConfig {
+    /// Client networks that may not place bets
+    pub blocked_cidrs: Vec<String>,
+    /// Networks exempt from blocking (e.g. office or test ranges)
+    pub allowed_cidrs: Vec<String>,
+    /// ISO 3166-1 alpha-2 country codes; requires `geoip_database_path`
+    pub blocked_countries: Vec<String>,
+    /// MaxMind GeoIP2/GeoLite2 Country database (requires the `geoip` feature)
+    pub geoip_database_path: Option<String>,
+    /// Take the client IP from `X-Forwarded-For`; only safe behind a trusted proxy
+    pub trust_forwarded_for: bool,
+    /// Proxy networks whose `X-Forwarded-For` hops are skipped when finding the client
+    pub trusted_proxies: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerConfig {
+    /// Internal indexer API; vault and allowance reads fall back to RPC or 503 when unset
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// Maximum completed bets held in the in-process cache
+    pub bet_cache_capacity: u64,
+    pub bet_cache_ttl_seconds: u64,
+    /// How long `/api/tokens` serves a cached read of the on-chain token configs
+    pub token_registry_ttl_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BettingConfig {
     pub min_bet_lamports: u64,
     pub max_bet_lamports: u64,
+    /// Unclaimed bets older than this are expired (0 disables expiry)
+    pub bet_ttl_seconds: u64,
+    pub bet_expiry_sweep_interval_seconds: u64,
+    /// Batched bets whose processor has not reported for this long are made claimable again (0 disables)
+    pub stale_claim_seconds: u64,
+    pub stale_claim_sweep_interval_seconds: u64,
+    /// Attach a renewal suggestion to bets leaving less than this on the allowance (0 disables)
+    pub allowance_warning_remaining_lamports: u64,
+    /// ...or whose allowance expires within this many seconds (0 disables)
+    pub allowance_warning_expiry_seconds: u64,
+    /// Most bets one session may hold, and its `max_rounds` when the client sets none
+    pub session_max_rounds: u32,
+    /// Refuse new bets while more than this many await a processor (0 disables)
+    pub intake_max_queue_depth: u64,
+    /// `Retry-After` sent with refused bets
+    pub intake_retry_after_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// Queue user notifications and run the delivery worker
+    pub enabled: bool,
+    /// Payout, in the stake token's base units, that also raises `big_win`; 0 disables
+    pub big_win_amount: i64,
+    pub webhook_timeout_ms: u64,
+    /// How often the notification stream is polled for delivery and websocket push
+    pub poll_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataConfig {
+    /// Largest client metadata object accepted on a bet, as serialized JSON
+    pub max_bytes: usize,
+    pub max_keys: usize,
+    /// Metadata keys whose string values are indexed for `/api/admin/bets/search`
+    pub indexed_keys: Vec<String>,
+}
+
+/// Split a comma-separated env var into trimmed, non-empty entries
+fn env_list(name: &str) -> Vec<String> {
+    env_list_or(name, "")
+}
+
+/// `env_list`, splitting `default` when the variable is unset
+fn env_list_or(name: &str, default: &str) -> Vec<String> {
+    env::var(name)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 impl Config {
-    pub fn load() -> anyhow::Result<Self> {
+    /// Load from the environment, with defaults from `profile` (else `CONFIG_PROFILE`)
+    pub fn load(profile: Option<Profile>) -> anyhow::Result<Self> {
         dotenvy::dotenv().ok();
 
-        Ok(Config {
+        let profile = match profile {
+            Some(profile) => Some(profile),
+            None => Profile::from_env()?,
+        };
+        let defaults = ProfileDefaults::of(profile);
+
+        let config = Config {
+            profile,
             api_port: env::var("API_PORT")
                 .unwrap_or_else(|_| "3001".to_string())
                 .parse()?,
@@ -43,16 +264,55 @@ impl Config {
             redis: RedisConfig {
                 url: env::var("REDIS_URL")
                     .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+                refuse_future_schema: env::var("BET_SCHEMA_REFUSE_FUTURE_VERSIONS")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
+            },
+            queue: QueueConfig {
+                backend: env::var("QUEUE_BACKEND")
+                    .unwrap_or_else(|_| "redis".to_string()),
+                nats_url: env::var("NATS_URL")
+                    .unwrap_or_else(|_| "nats://localhost:4222".to_string()),
+                fair_claims: env::var("QUEUE_FAIR_CLAIMS")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
+                fair_claim_scan_multiplier: env::var("QUEUE_FAIR_CLAIM_SCAN_MULTIPLIER")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()?,
+                max_claim_per_wallet: env::var("QUEUE_MAX_CLAIM_PER_WALLET")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()?,
+                claim_signals: env::var("QUEUE_CLAIM_SIGNALS")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
+                subscribe_keep_alive_seconds: env::var("QUEUE_SUBSCRIBE_KEEP_ALIVE_SECONDS")
+                    .unwrap_or_else(|_| "15".to_string())
+                    .parse()?,
+            },
+            admin: AdminConfig {
+                api_key: env::var("ADMIN_API_KEY").ok().filter(|v| !v.is_empty()),
+                casino_authority_keypair_path: env::var("CASINO_AUTHORITY_KEYPAIR_PATH")
+                    .ok()
+                    .filter(|v| !v.is_empty()),
+            },
+            auth: AuthConfig {
+                token_ttl_seconds: env::var("AUTH_TOKEN_TTL_SECONDS")
+                    .unwrap_or_else(|_| "86400".to_string())
+                    .parse()?,
+                signature_max_age_seconds: env::var("AUTH_SIGNATURE_MAX_AGE_SECONDS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()?,
+                require_bet_read_token: env::var("AUTH_REQUIRE_BET_READ_TOKEN")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
             },
             solana: SolanaConfig {
                 network: env::var("SOLANA_NETWORK")
-                    .unwrap_or_else(|_| "devnet".to_string()),
-                rpc_url: env::var("SOLANA_RPC_URL")
-                    .expect("SOLANA_RPC_URL must be set"),
+                    .unwrap_or_else(|_| defaults.network.to_string()),
+                rpc_url: profile::setting("SOLANA_RPC_URL", defaults.rpc_url)?,
                 commitment: env::var("SOLANA_COMMITMENT")
-                    .unwrap_or_else(|_| "confirmed".to_string()),
-                vault_program_id: env::var("VAULT_PROGRAM_ID")
-                    .expect("VAULT_PROGRAM_ID must be set"),
+                    .unwrap_or_else(|_| defaults.commitment.to_string()),
+                vault_program_id: profile::setting("VAULT_PROGRAM_ID", defaults.vault_program_id)?,
             },
             betting: BettingConfig {
                 min_bet_lamports: env::var("MIN_BET_LAMPORTS")
@@ -61,7 +321,172 @@ impl Config {
                 max_bet_lamports: env::var("MAX_BET_LAMPORTS")
                     .unwrap_or_else(|_| "1000000000000".to_string())
                     .parse()?,
+                bet_ttl_seconds: env::var("BET_TTL_SECONDS")
+                    .unwrap_or_else(|_| "86400".to_string())
+                    .parse()?,
+                bet_expiry_sweep_interval_seconds: env::var("BET_EXPIRY_SWEEP_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()?,
+                stale_claim_seconds: env::var("STALE_CLAIM_SECONDS")
+                    .unwrap_or_else(|_| "600".to_string())
+                    .parse()?,
+                stale_claim_sweep_interval_seconds: env::var("STALE_CLAIM_SWEEP_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()?,
+                allowance_warning_remaining_lamports: env::var("ALLOWANCE_WARNING_REMAINING_LAMPORTS")
+                    .unwrap_or_else(|_| "500000000".to_string())
+                    .parse()?,
+                allowance_warning_expiry_seconds: env::var("ALLOWANCE_WARNING_EXPIRY_SECONDS")
+                    .unwrap_or_else(|_| "600".to_string())
+                    .parse()?,
+                session_max_rounds: env::var("SESSION_MAX_ROUNDS")
+                    .unwrap_or_else(|_| "1000".to_string())
+                    .parse()?,
+                intake_max_queue_depth: env::var("BET_INTAKE_MAX_QUEUE_DEPTH")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()?,
+                intake_retry_after_seconds: env::var("BET_INTAKE_RETRY_AFTER_SECONDS")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()?,
+            },
+            cache: CacheConfig {
+                bet_cache_capacity: env::var("BET_CACHE_CAPACITY")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()?,
+                bet_cache_ttl_seconds: env::var("BET_CACHE_TTL_SECONDS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()?,
+                token_registry_ttl_seconds: env::var("TOKEN_REGISTRY_TTL_SECONDS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()?,
+            },
+            receipts: ReceiptConfig {
+                keypair_path: env::var("RECEIPT_KEYPAIR_PATH").ok().filter(|v| !v.is_empty()),
+            },
+            archive: ArchiveConfig {
+                database_url: env::var("ARCHIVE_DATABASE_URL").ok().filter(|v| !v.is_empty()),
+                retention_days: env::var("BET_ARCHIVE_RETENTION_DAYS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()?,
+                interval_seconds: env::var("BET_ARCHIVE_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()?,
+                batch_size: env::var("BET_ARCHIVE_BATCH_SIZE")
+                    .unwrap_or_else(|_| "500".to_string())
+                    .parse()?,
+            },
+            pii: PiiConfig {
+                keys: env::var("BET_PII_KEYS").ok().filter(|v| !v.is_empty()),
+                active_key_id: env::var("BET_PII_ACTIVE_KEY_ID").ok().filter(|v| !v.is_empty()),
+            },
+            reports: ReportsConfig {
+                daily_webhook_url: env::var("DAILY_REPORT_WEBHOOK_URL").ok().filter(|v| !v.is_empty()),
+                daily_check_interval_seconds: env::var("DAILY_REPORT_CHECK_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "600".to_string())
+                    .parse()?,
+            },
+            ledger: LedgerConfig {
+                check_interval_seconds: env::var("LEDGER_CHECK_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()?,
+                tolerance_lamports: env::var("LEDGER_TOLERANCE_LAMPORTS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()?,
             },
-        })
+            settlements: SettlementsConfig {
+                reconcile_interval_seconds: env::var("SETTLEMENT_RECONCILE_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "15".to_string())
+                    .parse()?,
+            },
+            slo: SloConfig {
+                target: env::var("SLO_TARGET")
+                    .unwrap_or_else(|_| "0.99".to_string())
+                    .parse()?,
+                latency_seconds: env::var("SLO_LATENCY_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()?,
+                period_days: env::var("SLO_PERIOD_DAYS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()?,
+                fast_burn_threshold: env::var("SLO_FAST_BURN_THRESHOLD")
+                    .unwrap_or_else(|_| "14.4".to_string())
+                    .parse()?,
+                slow_burn_threshold: env::var("SLO_SLOW_BURN_THRESHOLD")
+                    .unwrap_or_else(|_| "6".to_string())
+                    .parse()?,
+                evaluate_interval_seconds: env::var("SLO_EVALUATE_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()?,
+            },
+            jurisdiction: JurisdictionConfig {
+                blocked_cidrs: env_list("JURISDICTION_BLOCKED_CIDRS"),
+                allowed_cidrs: env_list("JURISDICTION_ALLOWED_CIDRS"),
+                blocked_countries: env_list("JURISDICTION_BLOCKED_COUNTRIES"),
+                geoip_database_path: env::var("GEOIP_DATABASE_PATH").ok().filter(|v| !v.is_empty()),
+                trust_forwarded_for: env::var("TRUST_FORWARDED_FOR")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()?,
+                trusted_proxies: env_list("TRUSTED_PROXY_CIDRS"),
+            },
+            indexer: IndexerConfig {
+                url: env::var("INDEXER_URL").ok().filter(|v| !v.is_empty()),
+            },
+            notifications: NotificationsConfig {
+                enabled: env::var("NOTIFICATIONS_ENABLED")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
+                big_win_amount: env::var("NOTIFICATIONS_BIG_WIN_AMOUNT")
+                    .unwrap_or_else(|_| "10000000000".to_string())
+                    .parse()?,
+                webhook_timeout_ms: env::var("NOTIFICATIONS_WEBHOOK_TIMEOUT_MS")
+                    .unwrap_or_else(|_| "5000".to_string())
+                    .parse()?,
+                poll_interval_ms: env::var("NOTIFICATIONS_POLL_INTERVAL_MS")
+                    .unwrap_or_else(|_| "500".to_string())
+                    .parse()?,
+            },
+            metadata: MetadataConfig {
+                max_bytes: env::var("BET_METADATA_MAX_BYTES")
+                    .unwrap_or_else(|_| "1024".to_string())
+                    .parse()?,
+                max_keys: env::var("BET_METADATA_MAX_KEYS")
+                    .unwrap_or_else(|_| "16".to_string())
+                    .parse()?,
+                indexed_keys: env_list_or("BET_METADATA_INDEXED_KEYS", "client_version,campaign"),
+            },
+            games: games::parse(&env::var("GAMES").unwrap_or_default())?,
+        };
+        games::check_stake_ranges(&config.games, &config.betting)?;
+        if !(config.slo.target > 0.0 && config.slo.target < 1.0) {
+            anyhow::bail!("SLO_TARGET must be between 0 and 1 exclusive, got {}", config.slo.target);
+        }
+        Ok(config)
+    }
+
+    /// The loaded configuration as JSON with secrets redacted, logged at startup
+    pub fn effective(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|e| format!("<unprintable: {}>", e))
+    }
+
+    /// Replace secret references (`file://`, `env://`, `aws-sm://`, `gcp-sm://`;
+    /// see `shared::secrets`) with the secrets they name
+    ///
+    /// Keypair settings may then hold the keypair itself rather than a path.
+    pub async fn resolve_secrets(&mut self) -> anyhow::Result<()> {
+        let mut resolver = Resolver::new();
+        resolver.resolve_in_place(&mut self.redis.url).await.context("REDIS_URL")?;
+        resolver.resolve_optional(&mut self.admin.api_key).await.context("ADMIN_API_KEY")?;
+        resolver
+            .resolve_optional(&mut self.admin.casino_authority_keypair_path)
+            .await
+            .context("CASINO_AUTHORITY_KEYPAIR_PATH")?;
+        resolver.resolve_optional(&mut self.receipts.keypair_path).await.context("RECEIPT_KEYPAIR_PATH")?;
+        resolver.resolve_optional(&mut self.archive.database_url).await.context("ARCHIVE_DATABASE_URL")?;
+        resolver.resolve_optional(&mut self.pii.keys).await.context("BET_PII_KEYS")?;
+        resolver
+            .resolve_optional(&mut self.reports.daily_webhook_url)
+            .await
+            .context("DAILY_REPORT_WEBHOOK_URL")?;
+        Ok(())
     }
 }
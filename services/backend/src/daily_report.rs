@@ -0,0 +1,291 @@
+//! Daily settlement summary
+//!
+//! Batch updates bump per-day counters in Redis as settlements land. Shortly after
+//! midnight UTC the report job freezes the previous day's counters into a stored
+//! report, adds the outstanding manual review count, and optionally posts it to a
+//! webhook (Slack-compatible: the payload carries a `text` summary).
+
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::domain::Bet;
+use crate::errors::Result;
+use crate::repository::BetRepository;
+
+/// Redis key prefix for a day's running counters
+const COUNTERS_PREFIX: &str = "reports:daily:counters:";
+
+/// Redis key prefix for a finalized daily report
+const REPORT_PREFIX: &str = "reports:daily:";
+
+/// Counters outlive their report long enough to regenerate a missed day
+const COUNTERS_TTL_SECONDS: i64 = 40 * 86_400;
+
+fn counters_key(date: NaiveDate) -> String {
+    format!("{}{}", COUNTERS_PREFIX, date)
+}
+
+fn report_key(date: NaiveDate) -> String {
+    format!("{}{}", REPORT_PREFIX, date)
+}
+
+/// Value moved per stake token, in the token's base units
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenFlow {
+    /// Stakes of settled bets, taken into the casino
+    pub stake_in: i64,
+    /// Payouts of settled bets, paid out to users
+    pub payout_out: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyReport {
+    pub date: NaiveDate,
+    pub settlements_processed: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    pub tokens: BTreeMap<String, TokenFlow>,
+    /// Transaction fees reported by processors with their batch updates
+    pub fees_lamports: i64,
+    /// Bets waiting for an operator when the report was generated
+    pub outstanding_manual_reviews: u64,
+    pub generated_at: DateTime<Utc>,
+    /// False while the day is still open and counters can change
+    #[serde(rename = "final")]
+    pub is_final: bool,
+}
+
+impl DailyReport {
+    pub(crate) fn from_counters(
+        date: NaiveDate,
+        counters: &HashMap<String, i64>,
+        outstanding_manual_reviews: u64,
+        is_final: bool,
+    ) -> Self {
+        let counter = |name: &str| counters.get(name).copied().unwrap_or(0);
+
+        let mut tokens: BTreeMap<String, TokenFlow> = BTreeMap::new();
+        for (field, value) in counters {
+            if let Some(token) = field.strip_prefix("in:") {
+                tokens.entry(token.to_string()).or_default().stake_in = *value;
+            } else if let Some(token) = field.strip_prefix("out:") {
+                tokens.entry(token.to_string()).or_default().payout_out = *value;
+            }
+        }
+
+        let succeeded = counter("succeeded");
+        let failed = counter("failed");
+        Self {
+            date,
+            settlements_processed: succeeded + failed,
+            succeeded,
+            failed,
+            tokens,
+            fees_lamports: counter("fees_lamports"),
+            outstanding_manual_reviews,
+            generated_at: Utc::now(),
+            is_final,
+        }
+    }
+
+    /// One-paragraph summary for chat webhooks
+    pub fn summary(&self) -> String {
+        let flows: Vec<String> = self
+            .tokens
+            .iter()
+            .map(|(token, flow)| format!("{}: in {} / out {}", token, flow.stake_in, flow.payout_out))
+            .collect();
+        format!(
+            "Settlement summary {}: {} processed ({} succeeded, {} failed); {}; fees {} lamports; {} awaiting manual review",
+            self.date,
+            self.settlements_processed,
+            self.succeeded,
+            self.failed,
+            if flows.is_empty() { "no value moved".to_string() } else { flows.join(", ") },
+            self.fees_lamports,
+            self.outstanding_manual_reviews
+        )
+    }
+}
+
+/// Settlement outcomes from one batch update, recorded against the current day
+#[derive(Debug, Default)]
+pub struct DailyTally {
+    succeeded: i64,
+    failed: i64,
+    tokens: BTreeMap<String, TokenFlow>,
+    fees_lamports: i64,
+}
+
+impl DailyTally {
+    pub fn add_completed(&mut self, bet: &Bet) {
+        self.succeeded += 1;
+        let flow = self.tokens.entry(bet.stake_token.clone()).or_default();
+        flow.stake_in = flow.stake_in.saturating_add(bet.stake_amount);
+        flow.payout_out = flow.payout_out.saturating_add(bet.payout_amount.unwrap_or(0));
+    }
+
+    pub fn add_failed(&mut self) {
+        self.failed += 1;
+    }
+
+    pub fn add_fees(&mut self, lamports: i64) {
+        self.fees_lamports = self.fees_lamports.saturating_add(lamports.max(0));
+    }
+
+    /// Counter fields and what the tally adds to each
+    pub(crate) fn increments(&self) -> Vec<(String, i64)> {
+        let mut increments = vec![
+            ("succeeded".to_string(), self.succeeded),
+            ("failed".to_string(), self.failed),
+            ("fees_lamports".to_string(), self.fees_lamports),
+        ];
+        for (token, flow) in &self.tokens {
+            increments.push((format!("in:{}", token), flow.stake_in));
+            increments.push((format!("out:{}", token), flow.payout_out));
+        }
+        increments
+    }
+
+    pub async fn record(&self, redis: &mut ConnectionManager, date: NaiveDate) -> Result<()> {
+        let key = counters_key(date);
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for (field, by) in self.increments() {
+            pipe.hincr(&key, field, by).ignore();
+        }
+        pipe.expire(&key, COUNTERS_TTL_SECONDS).ignore();
+        let _: () = pipe.query_async(redis).await?;
+        Ok(())
+    }
+}
+
+async fn build_report(
+    redis: &mut ConnectionManager,
+    repo: &dyn BetRepository,
+    date: NaiveDate,
+    is_final: bool,
+) -> Result<DailyReport> {
+    let counters: HashMap<String, i64> = redis.hgetall(counters_key(date)).await?;
+    let outstanding = repo.count_manual_review().await?;
+    Ok(DailyReport::from_counters(date, &counters, outstanding, is_final))
+}
+
+/// Stored report for `date`, or a provisional one built from the live counters
+pub async fn load_report(
+    redis: &mut ConnectionManager,
+    repo: &dyn BetRepository,
+    date: NaiveDate,
+) -> Result<DailyReport> {
+    let stored: Option<String> = redis.get(report_key(date)).await?;
+    if let Some(report) = stored.and_then(|raw| serde_json::from_str(&raw).ok()) {
+        return Ok(report);
+    }
+    build_report(redis, repo, date, false).await
+}
+
+/// Finalize `date`; returns `None` if another instance already stored it
+async fn finalize_report(
+    redis: &mut ConnectionManager,
+    repo: &dyn BetRepository,
+    date: NaiveDate,
+) -> Result<Option<DailyReport>> {
+    let report = build_report(redis, repo, date, true).await?;
+    let payload = serde_json::to_string(&report).map_err(anyhow::Error::from)?;
+    let stored: bool = redis.set_nx(report_key(date), payload).await?;
+    Ok(stored.then_some(report))
+}
+
+async fn deliver(client: &reqwest::Client, url: &str, report: &DailyReport) -> anyhow::Result<()> {
+    client
+        .post(url)
+        .json(&serde_json::json!({ "text": report.summary(), "report": report }))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Finalize the previous UTC day's report once it is over; checks every `interval`
+pub async fn run_daily_reports(
+    mut redis: ConnectionManager,
+    repo: Arc<dyn BetRepository>,
+    webhook_url: Option<String>,
+    interval: Duration,
+) {
+    tracing::info!(
+        interval_seconds = interval.as_secs(),
+        webhook = webhook_url.is_some(),
+        "Daily report job started"
+    );
+    let client = reqwest::Client::new();
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let date = Utc::now().date_naive() - ChronoDuration::days(1);
+        match redis.exists::<_, bool>(report_key(date)).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => {
+                tracing::error!(error = %e, "Daily report lookup failed");
+                continue;
+            }
+        }
+
+        let report = match finalize_report(&mut redis, repo.as_ref(), date).await {
+            Ok(Some(report)) => report,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::error!(%date, error = %e, "Daily report generation failed");
+                continue;
+            }
+        };
+        metrics::counter!("daily_reports_generated_total").increment(1);
+        tracing::info!(%date, summary = %report.summary(), "Daily report generated");
+
+        if let Some(url) = &webhook_url {
+            match deliver(&client, url, &report).await {
+                Ok(()) => metrics::counter!("daily_report_deliveries_total", "result" => "ok").increment(1),
+                Err(e) => {
+                    metrics::counter!("daily_report_deliveries_total", "result" => "error").increment(1);
+                    tracing::warn!(%date, error = %e, "Daily report webhook delivery failed");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_from_counters() {
+        let counters: HashMap<String, i64> = [
+            ("succeeded", 8),
+            ("failed", 2),
+            ("fees_lamports", 50_000),
+            ("in:SOL", 1_000),
+            ("out:SOL", 900),
+            ("in:USDC", 40),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+
+        let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let report = DailyReport::from_counters(date, &counters, 3, true);
+
+        assert_eq!(report.settlements_processed, 10);
+        assert_eq!(report.tokens["SOL"], TokenFlow { stake_in: 1_000, payout_out: 900 });
+        assert_eq!(report.tokens["USDC"], TokenFlow { stake_in: 40, payout_out: 0 });
+        assert_eq!(report.outstanding_manual_reviews, 3);
+        assert!(report.summary().contains("10 processed"));
+    }
+}
@@ -0,0 +1,88 @@
+//! Deposit credit notifications
+//!
+//! The indexer appends every deposit it detects to `DEPOSITS_STREAM_KEY`. One
+//! notifier per backend instance reads the stream through a shared consumer
+//! group and queues a `deposit_credited` notification for the vault owner, so
+//! the UI can show the new balance before the user's next bet.
+
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use shared::indexer::{IndexedDeposit, DEPOSITS_STREAM_KEY};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::notifications::{self, Notification};
+
+/// Consumer group shared by the notifiers of every instance
+const CONSUMER_GROUP: &str = "deposit-notifiers";
+
+/// Entries read per poll
+const READ_COUNT: usize = 100;
+
+fn parse_entries(reply: StreamReadReply) -> Vec<(String, Option<IndexedDeposit>)> {
+    reply
+        .keys
+        .into_iter()
+        .flat_map(|key| key.ids)
+        .map(|entry| {
+            let deposit = entry
+                .get::<String>("payload")
+                .and_then(|payload| serde_json::from_str(&payload).ok());
+            (entry.id, deposit)
+        })
+        .collect()
+}
+
+/// Queue a notification for each deposit the indexer detects; polls every `interval`
+pub async fn run_deposit_notifier(mut redis: ConnectionManager, interval: Duration) {
+    loop {
+        // From now on: deposits detected before the first start were never promised a notification
+        let created: redis::RedisResult<()> = redis.xgroup_create_mkstream(DEPOSITS_STREAM_KEY, CONSUMER_GROUP, "$").await;
+        match created {
+            Ok(()) => break,
+            Err(e) if e.code() == Some("BUSYGROUP") => break,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to create deposit consumer group");
+                tokio::time::sleep(interval).await;
+            }
+        }
+    }
+
+    let consumer = format!("backend-{}", Uuid::new_v4());
+    tracing::info!(%consumer, poll_interval_ms = interval.as_millis() as u64, "Deposit notifier started");
+
+    loop {
+        tokio::time::sleep(interval).await;
+        let options = StreamReadOptions::default().group(CONSUMER_GROUP, &consumer).count(READ_COUNT);
+        let reply: StreamReadReply = match redis.xread_options(&[DEPOSITS_STREAM_KEY], &[">"], &options).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                tracing::warn!(error = %e, "Deposit stream read failed");
+                continue;
+            }
+        };
+        for (id, deposit) in parse_entries(reply) {
+            match deposit {
+                Some(deposit) => {
+                    tracing::info!(
+                        owner = %deposit.owner,
+                        amount = deposit.amount,
+                        signature = %deposit.signature,
+                        "Deposit credited"
+                    );
+                    metrics::counter!(
+                        "deposits_credited_total",
+                        "token" => if deposit.token_mint.is_some() { "spl" } else { "SOL" }
+                    )
+                    .increment(1);
+                    notifications::publish_or_warn(&mut redis, &[Notification::deposit_credited(&deposit)]).await;
+                }
+                None => tracing::warn!(%id, "Skipping malformed deposit"),
+            }
+            if let Err(e) = redis.xack::<_, _, _, ()>(DEPOSITS_STREAM_KEY, CONSUMER_GROUP, &[&id]).await {
+                tracing::warn!(%id, error = %e, "Failed to acknowledge deposit");
+            }
+        }
+    }
+}
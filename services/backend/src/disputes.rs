@@ -0,0 +1,210 @@
+//! Outcome disputes
+//!
+//! A user can flag one of their finished bets. The dispute is stored next to the
+//! bet in Redis and listed in an admin queue until an operator either upholds
+//! the recorded outcome or refunds the stake, through a manual settlement or,
+//! for a loss already settled on-chain, a voided-bet refund.
+//! Outbound notifications about a bet should hold off while [`is_open`] is true,
+//! so a contested result isn't announced as final.
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::BetStatus;
+use crate::errors::Result;
+
+/// Redis key prefix for a bet's dispute record
+const DISPUTE_PREFIX: &str = "disputes:bet:";
+
+/// Redis key for open disputes, scored by `created_at` ms
+const OPEN_INDEX: &str = "disputes:open";
+
+/// Redis key for resolved disputes, scored by `resolved_at` ms
+const RESOLVED_INDEX: &str = "disputes:resolved";
+
+/// Longest free-text explanation a user may attach
+pub const MAX_DISPUTE_DETAILS_LEN: usize = 1000;
+
+fn dispute_key(bet_id: Uuid) -> String {
+    format!("{}{}", DISPUTE_PREFIX, bet_id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeReason {
+    /// The drawn outcome does not match the revealed seeds
+    IncorrectOutcome,
+    /// The outcome is right but the payout is not
+    IncorrectPayout,
+    /// The bet was never settled on-chain
+    NotSettled,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    Open,
+    /// The recorded outcome stands
+    Upheld,
+    /// The stake was refunded through a manual settlement or a void
+    Refunded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeResolution {
+    Uphold,
+    Refund,
+}
+
+impl DisputeResolution {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DisputeResolution::Uphold => "uphold",
+            DisputeResolution::Refund => "refund",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dispute {
+    pub bet_id: Uuid,
+    pub user_wallet: String,
+    pub reason: DisputeReason,
+    pub details: String,
+    pub status: DisputeStatus,
+    /// Bet status when the dispute was opened
+    pub bet_status: BetStatus,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub resolved_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub resolved_by: Option<String>,
+    #[serde(default)]
+    pub resolution_note: Option<String>,
+}
+
+impl Dispute {
+    pub fn is_open(&self) -> bool {
+        self.status == DisputeStatus::Open
+    }
+
+    /// Close the dispute with `resolution`
+    pub fn resolve(&mut self, resolution: DisputeResolution, operator: &str, note: &str, now: DateTime<Utc>) {
+        self.status = match resolution {
+            DisputeResolution::Uphold => DisputeStatus::Upheld,
+            DisputeResolution::Refund => DisputeStatus::Refunded,
+        };
+        self.resolved_at = Some(now);
+        self.resolved_by = Some(operator.to_string());
+        self.resolution_note = Some(note.to_string());
+    }
+}
+
+/// Only finished bets can be disputed; in-flight ones may still settle correctly
+pub fn can_dispute(status: &BetStatus) -> bool {
+    status.is_terminal()
+}
+
+pub async fn get(redis: &mut ConnectionManager, bet_id: Uuid) -> Result<Option<Dispute>> {
+    let raw: Option<String> = redis.get(dispute_key(bet_id)).await?;
+    Ok(raw.and_then(|raw| serde_json::from_str(&raw).ok()))
+}
+
+/// Whether the bet has an unresolved dispute
+pub async fn is_open(redis: &mut ConnectionManager, bet_id: Uuid) -> Result<bool> {
+    Ok(redis.zscore::<_, _, Option<f64>>(OPEN_INDEX, bet_id.to_string()).await?.is_some())
+}
+
+/// Store a new dispute; `false` if the bet was already disputed
+pub async fn open(redis: &mut ConnectionManager, dispute: &Dispute) -> Result<bool> {
+    let payload = serde_json::to_string(dispute).map_err(anyhow::Error::from)?;
+    let created: bool = redis.set_nx(dispute_key(dispute.bet_id), payload).await?;
+    if created {
+        let _: () = redis
+            .zadd(OPEN_INDEX, dispute.bet_id.to_string(), dispute.created_at.timestamp_millis())
+            .await?;
+    }
+    Ok(created)
+}
+
+/// Persist a resolved dispute and move it to the resolved queue
+pub async fn save_resolved(redis: &mut ConnectionManager, dispute: &Dispute) -> Result<()> {
+    let payload = serde_json::to_string(dispute).map_err(anyhow::Error::from)?;
+    let resolved_at = dispute.resolved_at.unwrap_or_else(Utc::now).timestamp_millis();
+    let _: () = redis::pipe()
+        .atomic()
+        .set(dispute_key(dispute.bet_id), payload)
+        .ignore()
+        .zrem(OPEN_INDEX, dispute.bet_id.to_string())
+        .ignore()
+        .zadd(RESOLVED_INDEX, dispute.bet_id.to_string(), resolved_at)
+        .ignore()
+        .query_async(redis)
+        .await?;
+    Ok(())
+}
+
+/// Disputes in the open (oldest first) or resolved (newest first) queue
+pub async fn list(redis: &mut ConnectionManager, open: bool, limit: isize) -> Result<Vec<Dispute>> {
+    let stop = limit.max(1) - 1;
+    let ids: Vec<String> = if open {
+        redis.zrange(OPEN_INDEX, 0, stop).await?
+    } else {
+        redis.zrevrange(RESOLVED_INDEX, 0, stop).await?
+    };
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let keys: Vec<String> = ids
+        .iter()
+        .filter_map(|id| Uuid::parse_str(id).ok())
+        .map(dispute_key)
+        .collect();
+    let raw: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(redis).await?;
+    Ok(raw
+        .into_iter()
+        .flatten()
+        .filter_map(|raw| serde_json::from_str(&raw).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dispute_lifecycle() {
+        assert!(can_dispute(&BetStatus::Completed));
+        assert!(can_dispute(&BetStatus::Expired));
+        assert!(!can_dispute(&BetStatus::Batched));
+
+        let mut dispute = Dispute {
+            bet_id: Uuid::new_v4(),
+            user_wallet: "8JQCVcxGMN2kQKXDzgCEJN8AawnQskWU4ha6NqZ83uDm".to_string(),
+            reason: DisputeReason::NotSettled,
+            details: "Stake left my vault but the bet expired".to_string(),
+            status: DisputeStatus::Open,
+            bet_status: BetStatus::Expired,
+            created_at: Utc::now(),
+            resolved_at: None,
+            resolved_by: None,
+            resolution_note: None,
+        };
+        assert!(dispute.is_open());
+
+        dispute.resolve(DisputeResolution::Refund, "ops@casino", "Refunded stake", Utc::now());
+        assert_eq!(dispute.status, DisputeStatus::Refunded);
+        assert!(!dispute.is_open());
+        assert_eq!(dispute.resolved_by.as_deref(), Some("ops@casino"));
+
+        let json = serde_json::to_value(&dispute).unwrap();
+        assert_eq!(json["reason"], "not_settled");
+        assert_eq!(json["status"], "refunded");
+    }
+}
@@ -1,111 +1,186 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use shared::LamportAmount;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
-pub enum BetStatus {
-    Pending,
-    Batched,
-    SubmittedToSolana,
-    ConfirmedOnSolana,
-    Completed,
-    FailedRetryable,
-    FailedManualReview,
-}
+use crate::disputes::{DisputeReason, DisputeResolution};
+
+// Wire types of the bet and processor endpoints, shared with `atomiq-client`
+pub use shared::api::{
+    BatchStatus, Bet, BetDetail, BetMetadata, BetResult, BetSettlement, BetStatus, BetUpdateOutcome, BetUpdateResult, ClaimableEvent, ConfirmationStatus,
+    CreateBetRequest, GameConfig, GameInfo, GamesResponse, SettlementReport, PendingBetsResponse, PendingRefundsResponse, Refund,
+    RefundResult, RefundStatus, PayoutHold, PayoutHoldStatus, RetryBudgetTrip, CreateSessionRequest, Session, SessionLimits, SessionResponse, SessionResults, TokenInfo,
+    TokenLimitSource, TokensResponse, UpdateBatchRequest, UpdateBatchResponse, WalletTokenRequest, WalletTokenResponse,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Bet {
-    pub bet_id: Uuid,
+pub struct Batch {
+    pub batch_id: Uuid,
     pub created_at: DateTime<Utc>,
-    pub user_wallet: String,
-    pub vault_address: String,
-    pub allowance_pda: Option<String>,
-    pub casino_id: Option<String>,
-    pub game_type: String,
-    pub stake_amount: i64,
-    pub stake_token: String,
-    pub choice: String,
-    pub status: BetStatus,
-    pub external_batch_id: Option<Uuid>,
+    pub processor_id: String,
+    pub status: BatchStatus,
+    pub bet_count: i32,
     pub solana_tx_id: Option<String>,
+    pub confirm_slot: Option<i64>,
+    pub confirm_status: Option<String>,
     pub retry_count: i32,
-    pub processor_id: Option<String>,
     pub last_error_code: Option<String>,
     pub last_error_message: Option<String>,
+}
+
+/// Outcome an operator assigns when settling a bet by hand
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SettlementOutcome {
+    Win,
+    Loss,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManualSettleRequest {
+    pub outcome: SettlementOutcome,
+    /// Required for wins; ignored for losses
     pub payout_amount: Option<i64>,
-    pub won: Option<bool>,
+    pub operator: String,
+    pub reason: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CreateBetRequest {
-    pub user_wallet: Option<String>,
-    pub vault_address: Option<String>,
-    pub allowance_pda: Option<String>,
-    #[serde(deserialize_with = "deserialize_lamport_amount")]
-    pub stake_amount: LamportAmount,
-    pub stake_token: String,
-    pub choice: String,
-}
-
-// Custom deserializer for LamportAmount from u64
-fn deserialize_lamport_amount<'de, D>(deserializer: D) -> Result<LamportAmount, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let amount_u64 = u64::deserialize(deserializer)?;
-    LamportAmount::try_from(amount_u64)
-        .map_err(|e| serde::de::Error::custom(format!("Invalid stake amount: {}", e)))
+pub struct DisputeRequest {
+    /// Must own the bet
+    pub user_wallet: String,
+    pub reason: DisputeReason,
+    #[serde(default)]
+    pub details: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum BatchStatus {
-    Created,
-    Submitted,
-    Confirmed,
-    Failed,
+pub struct ResolveDisputeRequest {
+    pub action: DisputeResolution,
+    pub operator: String,
+    pub reason: String,
 }
 
+/// Void a bet that settled as a loss and refund its stake
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Batch {
-    pub batch_id: Uuid,
-    pub created_at: DateTime<Utc>,
-    pub processor_id: String,
-    pub status: BatchStatus,
-    pub bet_count: i32,
-    pub solana_tx_id: Option<String>,
-    pub confirm_slot: Option<i64>,
-    pub confirm_status: Option<String>,
-    pub retry_count: i32,
-    pub last_error_code: Option<String>,
-    pub last_error_message: Option<String>,
+pub struct VoidBetRequest {
+    pub operator: String,
+    pub reason: String,
 }
 
+/// Release a held payout for dispatch
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UpdateBatchRequest {
-    pub status: BatchStatus,
-    pub solana_tx_id: Option<String>,
-    pub bet_results: Vec<BetResult>,
-    pub error_message: Option<String>,
+pub struct ApprovePayoutRequest {
+    pub operator: String,
+    pub reason: String,
 }
 
+/// Clear a tripped permanent-failure retry budget
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BetResult {
-    pub bet_id: Uuid,
-    pub status: BetStatus,
-    pub solana_tx_id: Option<String>,
-    pub error_message: Option<String>,
-    pub won: Option<bool>,
-    pub payout_amount: Option<i64>,
+pub struct AcknowledgeRetryBudgetRequest {
+    pub operator: String,
+    pub reason: String,
 }
 
+/// Book casino vault funding or a withdrawal in the settlement ledger
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PendingBetsResponse {
-    pub batch_id: Uuid,
-    pub processor_id: String,
-    pub bets: Vec<Bet>,
+pub struct LedgerAdjustmentRequest {
+    /// Positive for funds added to the casino vault, negative for funds taken out
+    pub amount: i64,
+    /// Defaults to the main casino
+    pub casino_id: Option<String>,
+    /// Defaults to SOL
+    pub stake_token: Option<String>,
+    pub operator: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillSwitchRequest {
+    /// true halts intake and settlement, false resumes them
+    pub engaged: bool,
+    pub operator: String,
+    pub reason: String,
+    /// Also pause (or unpause) the casino on-chain
+    #[serde(default)]
+    pub on_chain: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrepareWithdrawalRequest {
+    pub amount: u64,
+    /// SPL mint to withdraw; SOL when absent
+    pub token_mint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrepareAllowanceRequest {
+    pub amount: u64,
+    pub duration_seconds: i64,
+    /// SPL mint the allowance covers; SOL when absent
+    pub token_mint: Option<String>,
+    /// Largest single spend the processor may take; no limit when absent
+    pub max_single_spend: Option<u64>,
+    /// Most the processor may spend per hour; no limit when absent
+    pub max_spend_per_hour: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreparePayoutAddressRequest {
+    /// Wallet to pay winnings to; the vault itself when absent
+    pub payout_address: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitTransactionRequest {
+    /// Base64 bincode-serialized transaction signed by the user
+    pub transaction: String,
+}
 
+/// Who placed a bet, as seen by the backend
+///
+/// Kept encrypted in the bet record and only served to admins.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn is_empty(&self) -> bool {
+        self.ip.is_none() && self.user_agent.is_none()
+    }
+}
+
+/// Append-only record of an admin action on a bet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub bet_id: Uuid,
+    pub action: String,
+    pub operator: String,
+    pub reason: String,
+    pub previous_status: BetStatus,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One failure recorded against a bet, from its error history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BetError {
+    pub at: DateTime<Utc>,
+    /// Shared `ErrorCode` string, when the reporter sent one
+    pub code: Option<String>,
+    pub message: String,
+    /// Processor that reported the failure; `None` for failures raised by the backend
+    pub processor_id: Option<String>,
+}
+
+/// One write to a bet, from its event log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BetEvent {
+    /// `created`, the status the write moved the bet to, or what else it changed
+    pub kind: String,
+    /// Stored fields the write set
+    pub fields: BTreeMap<String, String>,
+    pub recorded_at: DateTime<Utc>,
+}
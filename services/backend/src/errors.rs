@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -22,8 +22,15 @@ pub enum AppError {
     #[error("Internal error: {0}")]
     Internal(#[from] anyhow::Error),
 
+    #[error("Archive error: {0}")]
+    Archive(#[from] tokio_postgres::Error),
+
     #[error("Shared type validation error: {0}")]
     SharedValidation(#[from] shared::types::ValidationError),
+
+    /// Bet intake is shedding load (503 with `Retry-After`)
+    #[error("Bet intake overloaded: {queue_depth} bets queued")]
+    Overloaded { queue_depth: u64, retry_after_seconds: u64 },
 }
 
 impl AppError {
@@ -33,6 +40,7 @@ impl AppError {
             AppError::Service(e) => e.clone(),
             AppError::Redis(e) => ServiceError::redis_error(e),
             AppError::Internal(e) => ServiceError::internal(e.to_string()),
+            AppError::Archive(e) => ServiceError::database_error(e),
             AppError::SharedValidation(e) => {
                 ServiceError::new(
                     ErrorCategory::Validation,
@@ -40,6 +48,12 @@ impl AppError {
                     e.to_string(),
                 )
             }
+            AppError::Overloaded { queue_depth, .. } => ServiceError::new(
+                ErrorCategory::Network,
+                shared::errors::ErrorCode::NETWORK_INTAKE_OVERLOADED,
+                "Too many bets are awaiting settlement; retry later",
+            )
+            .with_context(format!("{} bets queued", queue_depth)),
         }
     }
 }
@@ -47,6 +61,10 @@ impl AppError {
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let service_error = self.to_service_error();
+        let retry_after = match &self {
+            AppError::Overloaded { retry_after_seconds, .. } => Some(*retry_after_seconds),
+            _ => None,
+        };
         let status = StatusCode::from_u16(service_error.category.status_code())
             .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
 
@@ -61,7 +79,7 @@ impl IntoResponse for AppError {
                     "Request failed with error"
                 );
             }
-            ErrorCategory::Validation | ErrorCategory::NotFound => {
+            ErrorCategory::Validation | ErrorCategory::NotFound | ErrorCategory::Conflict => {
                 tracing::warn!(
                     error_code = %service_error.code,
                     error_category = ?service_error.category,
@@ -84,6 +102,9 @@ impl IntoResponse for AppError {
         // Increment error metrics by category and code
         let category_str = format!("{:?}", service_error.category);
         metrics::counter!("errors_total", "category" => category_str, "code" => service_error.code.clone()).increment(1);
+        if service_error.category == ErrorCategory::Validation {
+            crate::telemetry::record_validation_rejection(&service_error.code);
+        }
 
         // Return standardized JSON error response
         let body = Json(json!({
@@ -94,7 +115,11 @@ impl IntoResponse for AppError {
             }
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let Some(seconds) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
     }
 }
 
@@ -116,9 +141,101 @@ impl AppError {
         ))
     }
 
+    pub fn invalid_amount(message: impl Into<String>) -> Self {
+        AppError::Service(ServiceError::new(
+            ErrorCategory::Validation,
+            shared::errors::ErrorCode::VALIDATION_INVALID_AMOUNT,
+            message,
+        ))
+    }
+
+    /// The bet names a game that isn't configured or is disabled
+    pub fn game_unavailable(game_type: &str) -> Self {
+        AppError::Service(ServiceError::new(
+            ErrorCategory::Validation,
+            shared::errors::ErrorCode::VALIDATION_GAME_UNAVAILABLE,
+            format!("Game {} is not available", game_type),
+        ))
+    }
+
+    pub fn session_not_found(session_id: uuid::Uuid) -> Self {
+        AppError::Service(ServiceError::new(
+            ErrorCategory::NotFound,
+            shared::errors::ErrorCode::NOT_FOUND_SESSION,
+            format!("Session {} not found", session_id),
+        ))
+    }
+
+    /// The bet would take a session past one of its limits, or the session has expired
+    pub fn session_limit(message: impl Into<String>) -> Self {
+        AppError::Service(ServiceError::new(
+            ErrorCategory::Validation,
+            shared::errors::ErrorCode::VALIDATION_SESSION_LIMIT,
+            message,
+        ))
+    }
+
     pub fn insufficient_balance(required: i64, available: i64) -> Self {
         AppError::Service(ServiceError::insufficient_balance(required, available))
     }
+
+    pub fn invalid_status(message: impl Into<String>) -> Self {
+        AppError::Service(ServiceError::new(
+            ErrorCategory::Validation,
+            shared::errors::ErrorCode::VALIDATION_INVALID_STATUS,
+            message,
+        ))
+    }
+
+    pub fn rpc_unavailable(error: impl std::fmt::Display) -> Self {
+        AppError::Service(ServiceError::rpc_unavailable(error.to_string()))
+    }
+
+    /// The account indexer is not configured or did not answer (503)
+    pub fn indexer_unavailable(error: impl std::fmt::Display) -> Self {
+        AppError::Service(
+            ServiceError::new(
+                ErrorCategory::Network,
+                shared::errors::ErrorCode::NETWORK_INDEXER_UNAVAILABLE,
+                "Account indexer unavailable",
+            )
+            .with_context(error.to_string()),
+        )
+    }
+
+    pub fn jurisdiction_blocked() -> Self {
+        AppError::Service(ServiceError::new(
+            ErrorCategory::Unauthorized,
+            shared::errors::ErrorCode::AUTH_JURISDICTION_BLOCKED,
+            "Betting is not available in your jurisdiction",
+        ))
+    }
+
+    /// Intake is stopped by the kill switch (503)
+    pub fn service_halted() -> Self {
+        AppError::Service(ServiceError::new(
+            ErrorCategory::Network,
+            shared::errors::ErrorCode::NETWORK_SERVICE_HALTED,
+            "Bet intake is temporarily halted",
+        ))
+    }
+
+    /// The batch was claimed by another processor, or updated since the caller's version (409)
+    pub fn batch_conflict(message: impl Into<String>) -> Self {
+        AppError::Service(ServiceError::new(
+            ErrorCategory::Conflict,
+            shared::errors::ErrorCode::CONFLICT_BATCH_CLAIM,
+            message,
+        ))
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        AppError::Service(ServiceError::new(
+            ErrorCategory::Unauthorized,
+            shared::errors::ErrorCode::AUTH_UNAUTHORIZED,
+            message,
+        ))
+    }
 }
 
 pub type Result<T> = std::result::Result<T, AppError>;
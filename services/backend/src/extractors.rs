@@ -1,13 +1,20 @@
 use axum::{
     async_trait,
-    extract::{rejection::JsonRejection, FromRequest, Request},
-    http::StatusCode,
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::de::DeserializeOwned;
+use shared::errors::ErrorCode;
 use serde_json::json;
 
+use crate::{
+    errors::AppError,
+    handlers::admin::{require_admin, ADMIN_KEY_HEADER},
+    state::AppState,
+};
+
 /// Custom JSON extractor that provides better error messages
 ///
 /// This wrapper catches JSON deserialization errors (including validation errors
@@ -61,7 +68,7 @@ impl IntoResponse for ValidationJsonRejection {
             
             (
                 StatusCode::BAD_REQUEST,
-                "VALIDATION_INVALID_INPUT",
+                ErrorCode::VALIDATION_INVALID_INPUT.as_str(),
                 msg,
             )
         } else if error_message.contains("missing field") {
@@ -72,13 +79,13 @@ impl IntoResponse for ValidationJsonRejection {
                 .unwrap_or("unknown");
             (
                 StatusCode::BAD_REQUEST,
-                "VALIDATION_MISSING_FIELD",
+                ErrorCode::VALIDATION_MISSING_FIELD.as_str(),
                 format!("Missing required field: {}", field),
             )
         } else {
             (
                 StatusCode::BAD_REQUEST,
-                "VALIDATION_INVALID_INPUT",
+                ErrorCode::VALIDATION_INVALID_INPUT.as_str(),
                 "Invalid request body".to_string(),
             )
         };
@@ -93,6 +100,7 @@ impl IntoResponse for ValidationJsonRejection {
 
         // Increment error metrics
         metrics::counter!("errors_total", "category" => "Validation", "code" => code).increment(1);
+        crate::telemetry::record_validation_rejection(code);
 
         // Return standardized JSON error response
         let body = Json(json!({
@@ -106,3 +114,56 @@ impl IntoResponse for ValidationJsonRejection {
         (status, body).into_response()
     }
 }
+
+/// Who is reading bets through `GET /api/bets*`
+///
+/// A bearer token from `POST /api/auth/token` scopes the request to its
+/// wallet; the admin key reads any bet. Without either the request is rejected,
+/// unless `AUTH_REQUIRE_BET_READ_TOKEN` is off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BetReader {
+    Wallet(String),
+    Admin,
+    Anonymous,
+}
+
+impl BetReader {
+    /// Whether this reader may see the bets of `wallet`
+    pub fn can_read(&self, wallet: &str) -> bool {
+        match self {
+            BetReader::Wallet(own) => own == wallet,
+            BetReader::Admin | BetReader::Anonymous => true,
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for BetReader {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if parts.headers.contains_key(ADMIN_KEY_HEADER) {
+            require_admin(&state.config, &parts.headers)?;
+            return Ok(BetReader::Admin);
+        }
+
+        let bearer = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim);
+        match bearer {
+            Some(token) => state
+                .ops
+                .token_wallet(token)
+                .await?
+                .map(BetReader::Wallet)
+                .ok_or_else(|| AppError::unauthorized("Invalid or expired token")),
+            None if state.config.auth.require_bet_read_token => {
+                Err(AppError::unauthorized("A wallet token is required to read bets"))
+            }
+            None => Ok(BetReader::Anonymous),
+        }
+    }
+}
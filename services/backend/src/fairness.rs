@@ -0,0 +1,130 @@
+//! Provably-fair commit-reveal seeds
+//!
+//! One server seed per UTC day. Its hash is published before any bet of that
+//! day is placed and stamped on every bet (and its signed receipt); the seed
+//! itself is only revealed by `GET /api/fairness/seeds` once the day has
+//! rotated. Outcomes follow `shared::fairness`, so players can verify a settled
+//! bet from its `bet_id`, `client_seed` and the revealed seed.
+
+use chrono::NaiveDate;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::Serialize;
+use simulation::{ServerSeed, Simulator};
+use std::collections::HashMap;
+
+use crate::domain::Bet;
+use crate::errors::{AppError, Result};
+use crate::repository::OpsRepository;
+
+/// Seed of one day: `fairness:seed:{YYYY-MM-DD}` hash with `server_seed`
+const SEED_KEY_PREFIX: &str = "fairness:seed:";
+/// Sorted set of seed days, scored by days since the epoch
+const SEED_DAYS_KEY: &str = "fairness:seeds";
+/// Commitment -> day, to find the seed a bet was committed to
+const SEED_BY_HASH_KEY: &str = "fairness:seed_days";
+
+/// A daily seed as published; `server_seed` stays hidden until the day is over
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DailySeed {
+    pub day: NaiveDate,
+    pub server_seed_hash: String,
+    pub server_seed: Option<String>,
+}
+
+fn seed_key(day: NaiveDate) -> String {
+    format!("{}{}", SEED_KEY_PREFIX, day)
+}
+
+fn day_score(day: NaiveDate) -> i64 {
+    (day - NaiveDate::default()).num_days()
+}
+
+fn parse_seed(hex_seed: &str) -> Result<ServerSeed> {
+    ServerSeed::from_hex(hex_seed)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Stored fairness seed is invalid: {}", e)))
+}
+
+/// Commitment for `day`, creating the day's seed on first use
+pub async fn commitment_for(redis: &mut ConnectionManager, day: NaiveDate) -> Result<String> {
+    let key = seed_key(day);
+    // HSETNX so concurrent instances agree on a single seed per day
+    let _: bool = redis.hset_nx(&key, "server_seed", ServerSeed::generate().to_hex()).await?;
+    let stored: String = redis.hget(&key, "server_seed").await?;
+    let commitment = parse_seed(&stored)?.commitment();
+
+    let _: () = redis::pipe()
+        .zadd(SEED_DAYS_KEY, day.to_string(), day_score(day))
+        .ignore()
+        .hset(SEED_BY_HASH_KEY, &commitment, day.to_string())
+        .ignore()
+        .query_async(redis)
+        .await?;
+    Ok(commitment)
+}
+
+/// Server seed behind a commitment, if this backend issued it
+pub async fn seed_for_commitment(redis: &mut ConnectionManager, commitment: &str) -> Result<Option<ServerSeed>> {
+    let day: Option<String> = redis.hget(SEED_BY_HASH_KEY, commitment).await?;
+    let Some(day) = day else {
+        return Ok(None);
+    };
+    let stored: Option<String> = redis.hget(format!("{}{}", SEED_KEY_PREFIX, day), "server_seed").await?;
+    stored.map(|s| parse_seed(&s)).transpose()
+}
+
+/// Most recent daily seeds, newest first; seeds of days before `today` are revealed
+pub async fn list_seeds(redis: &mut ConnectionManager, today: NaiveDate, limit: usize) -> Result<Vec<DailySeed>> {
+    let days: Vec<String> = redis.zrevrange(SEED_DAYS_KEY, 0, limit as isize - 1).await?;
+    let mut seeds = Vec::with_capacity(days.len());
+    for day in days {
+        let Ok(day) = day.parse::<NaiveDate>() else {
+            continue;
+        };
+        let stored: Option<String> = redis.hget(seed_key(day), "server_seed").await?;
+        let Some(stored) = stored else {
+            continue;
+        };
+        let seed = parse_seed(&stored)?;
+        seeds.push(DailySeed {
+            day,
+            server_seed_hash: seed.commitment(),
+            server_seed: (day < today).then(|| seed.to_hex()),
+        });
+    }
+    Ok(seeds)
+}
+
+/// Draw outcomes for committed bets that don't have one yet, caching seeds per call.
+/// Bets committed to an unknown seed are left for the processor to simulate.
+pub async fn draw_outcomes(ops: &dyn OpsRepository, bets: &mut [Bet]) -> Result<()> {
+    let mut simulators: HashMap<String, Option<Simulator>> = HashMap::new();
+    for bet in bets.iter_mut().filter(|b| b.won.is_none()) {
+        let Some(commitment) = bet.server_seed_hash.clone() else {
+            continue;
+        };
+        if !simulators.contains_key(&commitment) {
+            let seed = ops.fairness_seed(&commitment).await?;
+            simulators.insert(commitment.clone(), seed.map(Simulator::new));
+        }
+        if let Some(simulator) = &simulators[&commitment] {
+            let outcome = simulator.coinflip(bet.bet_id, bet.client_seed.as_deref().unwrap_or_default(), bet.stake_amount);
+            bet.won = Some(outcome.won);
+            bet.payout_amount = Some(outcome.payout);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_day_score_orders_days() {
+        let day: NaiveDate = "2025-01-02".parse().unwrap();
+        assert_eq!(day_score(day), 20090);
+        assert_eq!(day_score(day.succ_opt().unwrap()), day_score(day) + 1);
+        assert_eq!(seed_key(day), "fairness:seed:2025-01-02");
+    }
+}
@@ -0,0 +1,175 @@
+//! Games bets may be placed on
+//!
+//! `GAMES` is a JSON array of `GameConfig`: each game's valid choices and the
+//! multiple of the stake each pays, how many choices one bet may combine, and
+//! its stake range as multiples of `MIN_BET_LAMPORTS` and `MAX_BET_LAMPORTS`.
+//! Only coinflip is configured when it is unset. A disabled game rejects new
+//! bets and drops out of `/api/games`; bets already placed on it still settle.
+
+use anyhow::{bail, Context};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::config::BettingConfig;
+use crate::domain::{GameConfig, GameInfo};
+use crate::errors::{AppError, Result};
+
+/// Parse `GAMES`, falling back to coinflip alone when it is empty
+pub fn parse(json: &str) -> anyhow::Result<Vec<GameConfig>> {
+    if json.trim().is_empty() {
+        return Ok(vec![coinflip()]);
+    }
+    let games: Vec<GameConfig> = serde_json::from_str(json).context("GAMES is not a JSON array of games")?;
+    let mut seen = HashSet::new();
+    for game in &games {
+        if game.game_type.is_empty() || !seen.insert(game.game_type.as_str()) {
+            bail!("GAMES: game_type {:?} is empty or repeated", game.game_type);
+        }
+        if game.payout_table.is_empty()
+            || game.payout_table.iter().any(|(choice, payout)| {
+                choice.is_empty() || choice.contains(',') || !payout.is_finite() || *payout <= 0.0
+            })
+        {
+            bail!("GAMES: {} needs choices without commas, each paying a positive multiple", game.game_type);
+        }
+        if !(game.min_stake_multiplier > 0.0 && game.max_stake_multiplier.is_finite()) {
+            bail!("GAMES: {} needs positive, finite stake multipliers", game.game_type);
+        }
+        if game.max_choices == 0 {
+            bail!("GAMES: {} must allow at least one choice", game.game_type);
+        }
+    }
+    Ok(games)
+}
+
+/// Fail when a game's stake range is empty under the backend's bet limits
+pub fn check_stake_ranges(games: &[GameConfig], betting: &BettingConfig) -> anyhow::Result<()> {
+    for game in games {
+        let (min_stake, max_stake) = min_max(game, betting.min_bet_lamports, betting.max_bet_lamports);
+        if min_stake > max_stake {
+            bail!("GAMES: {} allows no stake ({} > {})", game.game_type, min_stake, max_stake);
+        }
+    }
+    Ok(())
+}
+
+fn coinflip() -> GameConfig {
+    GameConfig {
+        game_type: "coinflip".to_string(),
+        enabled: true,
+        min_stake_multiplier: 1.0,
+        max_stake_multiplier: 1.0,
+        payout_table: BTreeMap::from([("heads".to_string(), 2.0), ("tails".to_string(), 2.0)]),
+        max_choices: 1,
+    }
+}
+
+/// The game's stake range, given the backend's minimum and maximum bet
+fn min_max(game: &GameConfig, min_bet: u64, max_bet: u64) -> (u64, u64) {
+    (
+        (min_bet as f64 * game.min_stake_multiplier).ceil() as u64,
+        (max_bet as f64 * game.max_stake_multiplier).floor() as u64,
+    )
+}
+
+pub struct GameRegistry {
+    games: BTreeMap<String, GameConfig>,
+}
+
+impl GameRegistry {
+    pub fn new(games: &[GameConfig]) -> Self {
+        Self { games: games.iter().map(|game| (game.game_type.clone(), game.clone())).collect() }
+    }
+
+    /// Enabled games with their stake ranges
+    pub fn enabled(&self, betting: &BettingConfig) -> Vec<GameInfo> {
+        self.games
+            .values()
+            .filter(|game| game.enabled)
+            .map(|game| {
+                let (min_stake, max_stake) = min_max(game, betting.min_bet_lamports, betting.max_bet_lamports);
+                GameInfo { config: game.clone(), min_stake, max_stake }
+            })
+            .collect()
+    }
+
+    /// Check a new bet against its game's rules
+    ///
+    /// `choice` holds up to `max_choices` distinct, comma-separated entries of
+    /// the game's payout table.
+    pub fn validate(&self, game_type: &str, choice: &str, stake: u64, betting: &BettingConfig) -> Result<()> {
+        let game = self
+            .games
+            .get(game_type)
+            .filter(|game| game.enabled)
+            .ok_or_else(|| AppError::game_unavailable(game_type))?;
+
+        let choices: Vec<&str> = choice.split(',').map(str::trim).collect();
+        if let Some(unknown) = choices.iter().find(|choice| !game.payout_table.contains_key(**choice)) {
+            return Err(AppError::invalid_input(format!("{:?} is not a choice of {}", unknown, game_type)));
+        }
+        if choices.len() > game.max_choices || choices.iter().collect::<HashSet<_>>().len() < choices.len() {
+            return Err(AppError::invalid_input(format!(
+                "{} takes at most {} distinct choice(s)",
+                game_type, game.max_choices
+            )));
+        }
+
+        let (min_stake, max_stake) = min_max(game, betting.min_bet_lamports, betting.max_bet_lamports);
+        if !(min_stake..=max_stake).contains(&stake) {
+            return Err(AppError::invalid_amount(format!(
+                "{} stakes must be between {} and {}",
+                game_type, min_stake, max_stake
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_games_enforce_choices_and_stakes() {
+        let betting = BettingConfig {
+            min_bet_lamports: 100,
+            max_bet_lamports: 10_000,
+            bet_ttl_seconds: 0,
+            bet_expiry_sweep_interval_seconds: 60,
+            stale_claim_seconds: 0,
+            stale_claim_sweep_interval_seconds: 60,
+            allowance_warning_remaining_lamports: 0,
+            allowance_warning_expiry_seconds: 0,
+            session_max_rounds: 10,
+            intake_max_queue_depth: 0,
+            intake_retry_after_seconds: 5,
+        };
+        assert_eq!(parse("").unwrap(), vec![coinflip()]);
+        let games = parse(
+            r#"[{"game_type":"coinflip","payout_table":{"heads":2.0,"tails":2.0}},
+                {"game_type":"dice","min_stake_multiplier":5,"max_stake_multiplier":0.5,
+                 "payout_table":{"1":6,"2":6,"3":6,"4":6,"5":6,"6":6},"max_choices":3},
+                {"game_type":"plinko","enabled":false,"payout_table":{"drop":1.5}}]"#,
+        )
+        .unwrap();
+        let registry = GameRegistry::new(&games);
+
+        let listed = registry.enabled(&betting);
+        assert_eq!(listed.iter().map(|game| game.config.game_type.as_str()).collect::<Vec<_>>(), ["coinflip", "dice"]);
+        assert_eq!((listed[1].min_stake, listed[1].max_stake), (500, 5_000));
+
+        assert!(registry.validate("coinflip", "heads", 100, &betting).is_ok());
+        assert!(registry.validate("coinflip", "edge", 100, &betting).is_err());
+        assert!(registry.validate("coinflip", "heads,tails", 100, &betting).is_err());
+        assert!(registry.validate("dice", "1, 4,6", 500, &betting).is_ok());
+        assert!(registry.validate("dice", "1,1", 500, &betting).is_err());
+        assert!(registry.validate("dice", "1", 5_001, &betting).is_err());
+        assert!(registry.validate("plinko", "drop", 100, &betting).is_err());
+        assert!(registry.validate("roulette", "red", 100, &betting).is_err());
+
+        assert!(parse(r#"[{"game_type":"dice","payout_table":{}}]"#).is_err());
+        assert!(check_stake_ranges(&games, &betting).is_ok());
+        let narrow = parse(r#"[{"game_type":"dice","min_stake_multiplier":200,"payout_table":{"1":6}}]"#).unwrap();
+        assert!(check_stake_ranges(&narrow, &betting).is_err());
+    }
+}
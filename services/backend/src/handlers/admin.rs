@@ -0,0 +1,794 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use shared::secrets::read_keypair;
+use solana_sdk::{
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    domain::{
+        AcknowledgeRetryBudgetRequest, ApprovePayoutRequest, AuditEntry, Bet, BetDetail, BetError, BetEvent, BetSettlement, BetStatus, ClaimableEvent, ClientInfo, KillSwitchRequest, ManualSettleRequest, PayoutHold,
+        PayoutHoldStatus, RetryBudgetTrip, SettlementOutcome,
+    },
+    errors::{AppError, Result},
+    extractors::ValidatedJson,
+    handlers::withdrawals::vault_program_id,
+    killswitch::KillSwitchState,
+    pipeline_latency::PipelineLatencyReport,
+    processor_stats::ProcessorStats,
+    repository::replay_events,
+    slo::SloReport,
+    state::{AppState, Repositories},
+    vault_transactions::build_set_paused_instruction,
+};
+
+/// Header carrying the admin API key
+pub(crate) const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+#[derive(Debug, Serialize)]
+pub struct ManualSettleResponse {
+    pub bet_id: Uuid,
+    pub status: BetStatus,
+    pub audit: AuditEntry,
+}
+
+/// Reject the request unless it carries the configured admin key
+pub(crate) fn require_admin(config: &Config, headers: &HeaderMap) -> Result<()> {
+    let expected = config
+        .admin
+        .api_key
+        .as_deref()
+        .ok_or_else(|| AppError::unauthorized("Admin API is not configured"))?;
+
+    let provided = headers
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    if provided != expected {
+        return Err(AppError::unauthorized("Invalid admin key"));
+    }
+    Ok(())
+}
+
+/// Statuses a bet may be manually settled from; anything else is in flight or final
+pub(crate) fn can_manually_settle(status: &BetStatus) -> bool {
+    matches!(
+        status,
+        BetStatus::Pending
+            | BetStatus::FailedRetryable
+            | BetStatus::FailedManualReview
+            | BetStatus::Expired
+    )
+}
+
+/// Settle a bet by hand: record the outcome, audit it, and put it on the priority lane
+pub async fn settle_bet(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(bet_id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<ManualSettleRequest>,
+) -> Result<Json<ManualSettleResponse>> {
+    require_admin(&state.config, &headers)?;
+
+    let span = tracing::info_span!("admin_settle_bet", %bet_id, operator = %req.operator);
+    let _enter = span.enter();
+
+    if req.operator.trim().is_empty() || req.reason.trim().is_empty() {
+        return Err(AppError::invalid_input("operator and reason are required"));
+    }
+
+    let repo = &state.bets;
+    let bet = repo
+        .find_by_id(bet_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Bet {} not found", bet_id)))?;
+
+    if !can_manually_settle(&bet.status) {
+        return Err(AppError::invalid_status(format!(
+            "Bet {} cannot be manually settled from status {:?}",
+            bet_id, bet.status
+        )));
+    }
+
+    let (won, payout_amount) = match req.outcome {
+        SettlementOutcome::Win => match req.payout_amount {
+            Some(amount) if amount > 0 => (true, amount),
+            _ => return Err(AppError::invalid_input("payout_amount must be positive for a win")),
+        },
+        SettlementOutcome::Loss => (false, 0),
+    };
+
+    let audit = AuditEntry {
+        bet_id,
+        action: "manual_settle".to_string(),
+        operator: req.operator.clone(),
+        reason: req.reason.clone(),
+        previous_status: bet.status.clone(),
+        details: serde_json::json!({
+            "outcome": req.outcome,
+            "payout_amount": payout_amount,
+        }),
+        created_at: chrono::Utc::now(),
+    };
+
+    // Audit first so a failed enqueue still leaves a trace of the attempt
+    state.audit.append_audit(&audit).await?;
+    repo.enqueue_manual_settlement(bet_id, won, payout_amount).await?;
+    state.bet_cache.invalidate(bet_id).await;
+    if state.config.queue.claim_signals {
+        state.ops.signal_claimable(ClaimableEvent { bet_id, priority: true }).await;
+    }
+
+    tracing::warn!(
+        previous_status = ?bet.status,
+        won,
+        payout_amount,
+        reason = %req.reason,
+        "Bet manually settled by operator"
+    );
+    metrics::counter!("admin_manual_settlements_total").increment(1);
+
+    Ok(Json(ManualSettleResponse {
+        bet_id,
+        status: BetStatus::Pending,
+        audit,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminBetDetail {
+    #[serde(flatten)]
+    pub detail: BetDetail,
+    /// Most recent failures, oldest first
+    pub errors: Vec<BetError>,
+}
+
+/// A bet with its settlement and error history, whoever's wallet it is
+pub async fn get_bet(
+    State(state): State<Repositories>,
+    headers: HeaderMap,
+    Path(bet_id): Path<Uuid>,
+) -> Result<Json<AdminBetDetail>> {
+    require_admin(&state.config, &headers)?;
+
+    let repo = &state.bets;
+    let bet = repo
+        .find_by_id(bet_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Bet {} not found", bet_id)))?;
+    let report = state.batches.settlement(bet_id).await?;
+    Ok(Json(AdminBetDetail {
+        detail: BetDetail { settlement: BetSettlement::of(&bet, report.as_ref()), bet },
+        errors: repo.error_history(bet_id).await?,
+    }))
+}
+
+/// Admin audit trail for a bet
+pub async fn get_audit_trail(
+    State(state): State<Repositories>,
+    headers: HeaderMap,
+    Path(bet_id): Path<Uuid>,
+) -> Result<Json<Vec<AuditEntry>>> {
+    require_admin(&state.config, &headers)?;
+
+    Ok(Json(state.audit.audit_trail(bet_id).await?))
+}
+
+/// Client info stored with a bet, decrypted
+pub async fn get_client_info(
+    State(state): State<Repositories>,
+    headers: HeaderMap,
+    Path(bet_id): Path<Uuid>,
+) -> Result<Json<ClientInfo>> {
+    require_admin(&state.config, &headers)?;
+
+    let repo = &state.bets;
+    let client = repo
+        .client_info(bet_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("No client info stored for bet {}", bet_id)))?;
+    tracing::info!(%bet_id, "Client info read by admin");
+    Ok(Json(client))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BetAtVersionQuery {
+    /// Number of events to replay, from 1
+    pub version: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BetAtVersionResponse {
+    pub version: usize,
+    /// Events recorded for the bet so far
+    pub latest_version: usize,
+    /// The write that produced this version
+    pub event: BetEvent,
+    pub bet: Bet,
+}
+
+/// The bet as it stood after its `version`th event, e.g. when it was submitted
+pub async fn get_bet_at_version(
+    State(state): State<Repositories>,
+    headers: HeaderMap,
+    Path(bet_id): Path<Uuid>,
+    Query(query): Query<BetAtVersionQuery>,
+) -> Result<Json<BetAtVersionResponse>> {
+    require_admin(&state.config, &headers)?;
+
+    let repo = &state.bets;
+    let events = repo.events(bet_id).await?;
+    if events.is_empty() {
+        return Err(AppError::not_found(format!("No events recorded for bet {}", bet_id)));
+    }
+    if query.version == 0 || query.version > events.len() {
+        return Err(AppError::invalid_input(format!(
+            "version must be between 1 and {}",
+            events.len()
+        )));
+    }
+
+    let bet = replay_events(bet_id, &events[..query.version])?;
+    Ok(Json(BetAtVersionResponse {
+        version: query.version,
+        latest_version: events.len(),
+        event: events[query.version - 1].clone(),
+        bet,
+    }))
+}
+
+/// Every bet recorded with a Solana transaction signature, e.g. one copied from an explorer
+pub async fn get_bets_by_tx(
+    State(state): State<Repositories>,
+    headers: HeaderMap,
+    Path(signature): Path<String>,
+) -> Result<Json<Vec<Bet>>> {
+    require_admin(&state.config, &headers)?;
+
+    if signature.parse::<Signature>().is_err() {
+        return Err(AppError::invalid_input("Invalid transaction signature"));
+    }
+
+    let repo = &state.bets;
+    let mut bets = repo.find_by_solana_tx(&signature).await?;
+    bets.sort_by_key(|bet| bet.created_at);
+    Ok(Json(bets))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchBetsQuery {
+    /// One of `BET_METADATA_INDEXED_KEYS`
+    pub key: String,
+    pub value: String,
+    pub limit: Option<i64>,
+}
+
+/// Bets placed with metadata `key` set to `value`, newest first
+pub async fn search_bets(
+    State(state): State<Repositories>,
+    headers: HeaderMap,
+    Query(query): Query<SearchBetsQuery>,
+) -> Result<Json<Vec<Bet>>> {
+    require_admin(&state.config, &headers)?;
+
+    let indexed_keys = &state.config.metadata.indexed_keys;
+    if !indexed_keys.contains(&query.key) {
+        return Err(AppError::invalid_input(format!(
+            "metadata key '{}' is not indexed; indexed keys: {}",
+            query.key,
+            indexed_keys.join(", ")
+        )));
+    }
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let repo = &state.bets;
+    Ok(Json(repo.find_by_metadata(&query.key, &query.value, limit).await?))
+}
+
+#[derive(Debug, Serialize)]
+pub struct KillSwitchResponse {
+    pub engaged: bool,
+    pub state: Option<KillSwitchState>,
+    /// Error from the on-chain pause/unpause, when one was requested and failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_chain_error: Option<String>,
+}
+
+/// Current kill switch state
+pub async fn get_killswitch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<KillSwitchResponse>> {
+    require_admin(&state.config, &headers)?;
+
+    let current = state.ops.kill_switch().await?;
+    Ok(Json(KillSwitchResponse {
+        engaged: current.is_some(),
+        state: current,
+        on_chain_error: None,
+    }))
+}
+
+/// Dwell time of recent bets in each settlement pipeline stage
+pub async fn get_pipeline_latency(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<PipelineLatencyReport>> {
+    require_admin(&state.config, &headers)?;
+
+    Ok(Json(state.ops.pipeline_latency().await?))
+}
+
+/// Settlement SLO: success rate and burn rate per window, error budget left and p95 latency
+pub async fn get_slo(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<SloReport>> {
+    require_admin(&state.config, &headers)?;
+
+    Ok(Json(state.ops.slo_summary(&state.config.slo).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListProcessorsQuery {
+    pub limit: Option<isize>,
+}
+
+/// Claim and settlement statistics of recently seen processors, most recent first
+pub async fn list_processors(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListProcessorsQuery>,
+) -> Result<Json<Vec<ProcessorStats>>> {
+    require_admin(&state.config, &headers)?;
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    Ok(Json(state.ops.processor_stats(limit).await?))
+}
+
+/// Submit pause_casino / unpause_casino signed by the configured casino authority
+async fn set_paused_on_chain(state: &AppState, paused: bool) -> anyhow::Result<String> {
+    let keypair = state
+        .config
+        .admin
+        .casino_authority_keypair_path
+        .as_deref()
+        .context("CASINO_AUTHORITY_KEYPAIR_PATH is not configured")?;
+    let authority = read_keypair(keypair).context("Failed to read casino authority keypair")?;
+    let program_id = vault_program_id(state).map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    let ix = build_set_paused_instruction(&program_id, &authority.pubkey(), paused);
+    let blockhash = state.solana.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&authority.pubkey()), &[&authority], blockhash);
+    let signature = state.solana.send_and_confirm_transaction(&tx).await?;
+    Ok(signature.to_string())
+}
+
+/// Halt or resume bet intake and settlement in one call
+///
+/// The Redis flag is flipped first so intake stops even if the optional on-chain
+/// pause fails; the response reports the on-chain error instead of failing.
+pub async fn set_killswitch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<KillSwitchRequest>,
+) -> Result<Json<KillSwitchResponse>> {
+    require_admin(&state.config, &headers)?;
+
+    if req.operator.trim().is_empty() || req.reason.trim().is_empty() {
+        return Err(AppError::invalid_input("operator and reason are required"));
+    }
+
+    let mut ks_state = KillSwitchState {
+        engaged_by: req.operator.clone(),
+        reason: req.reason.clone(),
+        engaged_at: chrono::Utc::now(),
+        on_chain_signature: None,
+    };
+
+    if req.engaged {
+        state.ops.engage_kill_switch(&ks_state).await?;
+    } else {
+        state.ops.release_kill_switch().await?;
+    }
+    tracing::warn!(
+        engaged = req.engaged,
+        operator = %req.operator,
+        reason = %req.reason,
+        on_chain = req.on_chain,
+        "Kill switch changed by operator"
+    );
+    metrics::counter!(
+        "killswitch_changes_total",
+        "action" => if req.engaged { "engage" } else { "release" }
+    )
+    .increment(1);
+
+    let mut on_chain_error = None;
+    if req.on_chain {
+        match set_paused_on_chain(&state, req.engaged).await {
+            Ok(signature) => {
+                tracing::warn!(%signature, paused = req.engaged, "Casino pause state changed on-chain");
+                if req.engaged {
+                    ks_state.on_chain_signature = Some(signature);
+                    state.ops.engage_kill_switch(&ks_state).await?;
+                }
+            }
+            Err(e) => {
+                tracing::error!(error = %e, paused = req.engaged, "On-chain casino pause failed");
+                on_chain_error = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    Ok(Json(KillSwitchResponse {
+        engaged: req.engaged,
+        state: req.engaged.then_some(ks_state),
+        on_chain_error,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListPayoutHoldsQuery {
+    pub limit: Option<isize>,
+}
+
+/// Payouts held for review, oldest first
+pub async fn list_payout_holds(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListPayoutHoldsQuery>,
+) -> Result<Json<Vec<PayoutHold>>> {
+    require_admin(&state.config, &headers)?;
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    Ok(Json(state.ops.pending_payout_holds(limit).await?))
+}
+
+/// Release a held payout; the coordinator dispatches it on its next cycle
+pub async fn approve_payout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(transaction_id): Path<u64>,
+    ValidatedJson(req): ValidatedJson<ApprovePayoutRequest>,
+) -> Result<Json<PayoutHold>> {
+    require_admin(&state.config, &headers)?;
+
+    if req.operator.trim().is_empty() || req.reason.trim().is_empty() {
+        return Err(AppError::invalid_input("operator and reason are required"));
+    }
+
+    let mut hold = state
+        .ops
+        .payout_hold(transaction_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("No held payout for settlement {}", transaction_id)))?;
+    if hold.status == PayoutHoldStatus::Approved {
+        return Err(AppError::invalid_status(format!("Payout for settlement {} is already approved", transaction_id)));
+    }
+
+    hold.approve(&req.operator, chrono::Utc::now());
+    state.ops.save_payout_hold(&hold).await?;
+    tracing::warn!(
+        transaction_id,
+        payout = hold.payout,
+        operator = %req.operator,
+        reason = %req.reason,
+        "Held payout approved by operator"
+    );
+    metrics::counter!("payout_hold_approvals_total").increment(1);
+    Ok(Json(hold))
+}
+
+#[derive(Debug, Serialize)]
+pub struct RetryBudgetResponse {
+    /// Processors are holding permanent failures as retryable until acknowledged
+    pub tripped: bool,
+    pub trip: Option<RetryBudgetTrip>,
+}
+
+/// Current permanent-failure retry budget state
+pub async fn get_retry_budget(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<RetryBudgetResponse>> {
+    require_admin(&state.config, &headers)?;
+
+    let trip = state.ops.retry_budget_trip().await?;
+    Ok(Json(RetryBudgetResponse { tripped: trip.is_some(), trip }))
+}
+
+/// Acknowledge a tripped retry budget; processors classify permanent failures again
+pub async fn acknowledge_retry_budget(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<AcknowledgeRetryBudgetRequest>,
+) -> Result<Json<RetryBudgetTrip>> {
+    require_admin(&state.config, &headers)?;
+
+    if req.operator.trim().is_empty() || req.reason.trim().is_empty() {
+        return Err(AppError::invalid_input("operator and reason are required"));
+    }
+
+    let trip = state
+        .ops
+        .acknowledge_retry_budget()
+        .await?
+        .ok_or_else(|| AppError::invalid_status("Retry budget is not tripped"))?;
+    tracing::warn!(
+        tripped_at = %trip.tripped_at,
+        instance_id = %trip.instance_id,
+        permanent_failures = trip.permanent_failures,
+        outcomes = trip.outcomes,
+        operator = %req.operator,
+        reason = %req.reason,
+        "Retry budget trip acknowledged by operator"
+    );
+    metrics::counter!("retry_budget_acknowledgements_total").increment(1);
+    Ok(Json(trip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::SettlementReport;
+    use crate::pipeline_latency::Stage;
+    use crate::repository::{AuditRepository, BatchRepository, BetRepository, OpsRepository};
+    use crate::testing::{self, InMemoryRepositories, ADMIN_KEY};
+    use axum::http::{HeaderName, HeaderValue};
+    use axum::routing::get;
+    use axum::Router;
+    use axum_test::{TestRequest, TestServer};
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn server(repos: &InMemoryRepositories) -> TestServer {
+        let mut config = testing::config();
+        config.metadata.indexed_keys = vec!["campaign".to_string()];
+        let app = Router::new()
+            .route("/api/admin/bets/:bet_id", get(get_bet))
+            .route("/api/admin/bets/:bet_id/audit", get(get_audit_trail))
+            .route("/api/admin/bets/:bet_id/client", get(get_client_info))
+            .route("/api/admin/bets/:bet_id/at", get(get_bet_at_version))
+            .route("/api/admin/bets/by-tx/:signature", get(get_bets_by_tx))
+            .route("/api/admin/bets/search", get(search_bets))
+            .with_state(repos.state(config));
+        TestServer::new(app).unwrap()
+    }
+
+    fn app_server(repos: &InMemoryRepositories, config: Config) -> TestServer {
+        TestServer::new(crate::build_router(repos.app_state(config))).unwrap()
+    }
+
+    fn as_admin(request: TestRequest) -> TestRequest {
+        request.add_header(HeaderName::from_static(ADMIN_KEY_HEADER), HeaderValue::from_static(ADMIN_KEY))
+    }
+
+    #[test]
+    fn test_can_manually_settle() {
+        assert!(can_manually_settle(&BetStatus::Pending));
+        assert!(can_manually_settle(&BetStatus::FailedManualReview));
+        assert!(can_manually_settle(&BetStatus::Expired));
+        assert!(!can_manually_settle(&BetStatus::Batched));
+        assert!(!can_manually_settle(&BetStatus::SubmittedToSolana));
+        assert!(!can_manually_settle(&BetStatus::Completed));
+    }
+
+    #[tokio::test]
+    async fn test_bet_routes_require_admin_key() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let bet = testing::pending_bet("W1");
+        repos.bets.insert(bet.clone());
+        let server = server(&repos);
+
+        server.get(&format!("/api/admin/bets/{}", bet.bet_id)).await.assert_status_unauthorized();
+        server
+            .get(&format!("/api/admin/bets/{}", bet.bet_id))
+            .add_header(HeaderName::from_static(ADMIN_KEY_HEADER), HeaderValue::from_static("wrong"))
+            .await
+            .assert_status_unauthorized();
+        as_admin(server.get(&format!("/api/admin/bets/{}", bet.bet_id))).await.assert_status_ok();
+        as_admin(server.get(&format!("/api/admin/bets/{}", Uuid::new_v4()))).await.assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_get_bet_with_settlement_errors_and_history() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let bet = testing::pending_bet("W1");
+        let bet_id = bet.bet_id;
+        let client = ClientInfo { ip: Some("203.0.113.7".to_string()), user_agent: None };
+        repos.bets.insert_with_client(bet, client.clone());
+        repos.bets.update_status(bet_id, BetStatus::Batched, None).await.unwrap();
+        repos.bets.update_status(bet_id, BetStatus::FailedRetryable, None).await.unwrap();
+        let error = BetError { at: chrono::Utc::now(), code: None, message: "blockhash expired".to_string(), processor_id: None };
+        repos.bets.record_error(bet_id, &error).await.unwrap();
+        let report = SettlementReport { spend_tx: Some("spend".to_string()), ..Default::default() };
+        repos.batches.record_settlement(bet_id, report).await.unwrap();
+        let audit = AuditEntry {
+            bet_id,
+            action: "manual_settle".to_string(),
+            operator: "ops".to_string(),
+            reason: "stuck".to_string(),
+            previous_status: BetStatus::FailedRetryable,
+            details: serde_json::json!({}),
+            created_at: chrono::Utc::now(),
+        };
+        repos.audit.append_audit(&audit).await.unwrap();
+        let server = server(&repos);
+
+        let detail: serde_json::Value = as_admin(server.get(&format!("/api/admin/bets/{}", bet_id))).await.json();
+        assert_eq!(detail["status"], "failed_retryable");
+        assert_eq!(detail["settlement"]["spend_tx"], "spend");
+        assert_eq!(detail["errors"][0]["message"], "blockhash expired");
+
+        let trail: Vec<AuditEntry> = as_admin(server.get(&format!("/api/admin/bets/{}/audit", bet_id))).await.json();
+        assert_eq!(trail.iter().map(|entry| entry.action.as_str()).collect::<Vec<_>>(), vec![audit.action.as_str()]);
+
+        let stored: ClientInfo = as_admin(server.get(&format!("/api/admin/bets/{}/client", bet_id))).await.json();
+        assert_eq!(stored, client);
+
+        // Version 2 is the claim, before the failure
+        let at: serde_json::Value = as_admin(server.get(&format!("/api/admin/bets/{}/at", bet_id)))
+            .add_query_param("version", 2)
+            .await
+            .json();
+        assert_eq!((at["latest_version"].as_u64(), at["bet"]["status"].as_str()), (Some(3), Some("batched")));
+        as_admin(server.get(&format!("/api/admin/bets/{}/at", bet_id)))
+            .add_query_param("version", 4)
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_find_bets_by_tx_and_metadata() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let signature = Signature::new_unique().to_string();
+        let mut tagged = testing::pending_bet("W1");
+        tagged.solana_tx_id = Some(signature.clone());
+        tagged.metadata = Some([("campaign".to_string(), serde_json::json!("spring"))].into());
+        repos.bets.insert(tagged.clone());
+        repos.bets.insert(testing::pending_bet("W2"));
+        let server = server(&repos);
+
+        let by_tx: Vec<Bet> = as_admin(server.get(&format!("/api/admin/bets/by-tx/{}", signature))).await.json();
+        assert_eq!(by_tx.iter().map(|bet| bet.bet_id).collect::<Vec<_>>(), vec![tagged.bet_id]);
+        as_admin(server.get("/api/admin/bets/by-tx/not-a-signature")).await.assert_status_bad_request();
+
+        let found: Vec<Bet> = as_admin(server.get("/api/admin/bets/search"))
+            .add_query_param("key", "campaign")
+            .add_query_param("value", "spring")
+            .await
+            .json();
+        assert_eq!(found.len(), 1);
+        as_admin(server.get("/api/admin/bets/search"))
+            .add_query_param("key", "referrer")
+            .add_query_param("value", "spring")
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_manual_settlement_requeues_the_bet() {
+        let mut config = testing::config();
+        config.queue.claim_signals = true;
+        let repos = InMemoryRepositories::new(&config);
+        let server = app_server(&repos, config);
+        let mut expired = testing::pending_bet("W1");
+        expired.status = BetStatus::Expired;
+        let mut batched = testing::pending_bet("W1");
+        batched.status = BetStatus::Batched;
+        repos.bets.insert(expired.clone());
+        repos.bets.insert(batched.clone());
+        let settle = |bet_id: Uuid, payout_amount: Option<i64>| {
+            as_admin(server.post(&format!("/api/admin/bets/{}/settle", bet_id))).json(&json!({
+                "outcome": "win",
+                "payout_amount": payout_amount,
+                "operator": "ops",
+                "reason": "stuck",
+            }))
+        };
+
+        settle(expired.bet_id, None).await.assert_status_bad_request();
+        settle(batched.bet_id, Some(200_000_000)).await.assert_status_bad_request();
+        let settled: serde_json::Value = settle(expired.bet_id, Some(200_000_000)).await.json();
+        assert_eq!(settled["status"], "pending");
+        let stored = repos.bets.find_by_id(expired.bet_id).await.unwrap().unwrap();
+        assert_eq!((stored.status, stored.payout_amount), (BetStatus::Pending, Some(200_000_000)));
+        assert_eq!(repos.ops.signals(), vec![ClaimableEvent { bet_id: expired.bet_id, priority: true }]);
+    }
+
+    #[tokio::test]
+    async fn test_kill_switch_routes() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = app_server(&repos, testing::config());
+        let set = |engaged: bool, on_chain: bool| {
+            as_admin(server.post("/api/admin/killswitch")).json(&json!({
+                "engaged": engaged,
+                "operator": "ops",
+                "reason": "incident",
+                "on_chain": on_chain,
+            }))
+        };
+
+        server.get("/api/admin/killswitch").await.assert_status_unauthorized();
+        let current: serde_json::Value = as_admin(server.get("/api/admin/killswitch")).await.json();
+        assert_eq!(current["engaged"], false);
+
+        // The Redis flag is set even when the on-chain pause can't be sent
+        let engaged: serde_json::Value = set(true, true).await.json();
+        assert!(engaged["on_chain_error"].as_str().unwrap().contains("CASINO_AUTHORITY_KEYPAIR_PATH"));
+        assert!(repos.ops.is_halted().await.unwrap());
+        let current: serde_json::Value = as_admin(server.get("/api/admin/killswitch")).await.json();
+        assert_eq!((current["engaged"].as_bool(), current["state"]["engaged_by"].as_str()), (Some(true), Some("ops")));
+
+        set(false, false).await.assert_status_ok();
+        assert!(!repos.ops.is_halted().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_payout_holds_and_retry_budget() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = app_server(&repos, testing::config());
+        repos.ops.hold_payout(PayoutHold {
+            transaction_id: 7,
+            player_address: "W1".to_string(),
+            payout: 50_000_000_000,
+            status: PayoutHoldStatus::PendingReview,
+            held_at: Utc::now(),
+            release_at: None,
+            approved_by: None,
+            approved_at: None,
+        });
+        let approval = json!({ "operator": "ops", "reason": "verified" });
+
+        let held: Vec<PayoutHold> = as_admin(server.get("/api/admin/payouts/held")).await.json();
+        assert_eq!(held.iter().map(|hold| hold.transaction_id).collect::<Vec<_>>(), vec![7]);
+        let approved: PayoutHold = as_admin(server.post("/api/admin/payouts/7/approve")).json(&approval).await.json();
+        assert_eq!((approved.status, approved.approved_by.as_deref()), (PayoutHoldStatus::Approved, Some("ops")));
+        as_admin(server.post("/api/admin/payouts/7/approve")).json(&approval).await.assert_status_bad_request();
+        as_admin(server.post("/api/admin/payouts/8/approve")).json(&approval).await.assert_status_not_found();
+
+        let budget: serde_json::Value = as_admin(server.get("/api/admin/retry-budget")).await.json();
+        assert_eq!(budget["tripped"], false);
+        let acknowledge = || as_admin(server.post("/api/admin/retry-budget/acknowledge")).json(&approval);
+        acknowledge().await.assert_status_bad_request();
+        repos.ops.trip_retry_budget(RetryBudgetTrip {
+            tripped_at: Utc::now(),
+            instance_id: "processor-1".to_string(),
+            permanent_failures: 40,
+            outcomes: 100,
+            window_seconds: 300,
+            threshold_percent: 25.0,
+        });
+        let budget: serde_json::Value = as_admin(server.get("/api/admin/retry-budget")).await.json();
+        assert_eq!(budget["tripped"], true);
+        let trip: RetryBudgetTrip = acknowledge().await.json();
+        assert_eq!(trip.permanent_failures, 40);
+        assert!(repos.ops.retry_budget_trip().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_operational_summaries() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = app_server(&repos, testing::config());
+        repos.ops.record_pipeline(Stage::Batched, &[100, 300]);
+        repos.ops.record_processor_claim("processor-1", 4).await.unwrap();
+
+        let latency: serde_json::Value = as_admin(server.get("/api/admin/pipeline/latency")).await.json();
+        assert_eq!(latency["stages"][1]["stage"], Stage::Batched.as_str());
+        assert_eq!((latency["stages"][1]["samples"].as_u64(), latency["stages"][1]["max_ms"].as_i64()), (Some(2), Some(300)));
+
+        let processors: serde_json::Value = as_admin(server.get("/api/admin/processors")).await.json();
+        assert_eq!((processors[0]["processor_id"].as_str(), processors[0]["bets_claimed"].as_i64()), (Some("processor-1"), Some(4)));
+
+        let slo: serde_json::Value = as_admin(server.get("/api/admin/slo")).await.json();
+        assert_eq!(slo["period"]["total"], 0);
+        server.get("/api/admin/slo").await.assert_status_unauthorized();
+    }
+}
@@ -0,0 +1,445 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use shared::indexer::{IndexedAllowance, UserAllowances};
+use shared::vault::AllowanceAccount;
+use shared::{MAX_ALLOWANCE_AMOUNT_LAMPORTS, MAX_ALLOWANCE_DURATION_SECS};
+use solana_sdk::{pubkey::Pubkey, system_program};
+use std::str::FromStr;
+
+use crate::{
+    config::BettingConfig,
+    domain::PrepareAllowanceRequest,
+    errors::{AppError, Result},
+    extractors::ValidatedJson,
+    handlers::withdrawals::{parse_wallet, vault_program_id},
+    state::AppState,
+    vault_transactions::{
+        allowance_nonce_registry_pda, allowance_pda, build_approve_allowance_v2_instruction,
+        build_approve_allowance_v3_instruction, casino_pda, encode_unsigned_transaction, parse_next_nonce,
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct PreparedAllowanceResponse {
+    /// Base64 bincode-serialized unsigned transaction
+    pub transaction: String,
+    pub recent_blockhash: String,
+    pub last_valid_block_height: u64,
+    /// Allowance PDA the transaction creates; pass it as `allowance_pda` when creating bets
+    pub allowance_pda: String,
+    pub nonce: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_single_spend: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_spend_per_hour: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListAllowancesQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AllowanceSummary {
+    #[serde(flatten)]
+    pub allowance: IndexedAllowance,
+    pub remaining: u64,
+    /// Not revoked, not expired and not used up: bets can use it without a new approval
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserAllowancesResponse {
+    /// `None` until the user's first allowance approval creates the registry
+    pub next_nonce: Option<u64>,
+    /// Newest (highest nonce) first
+    pub allowances: Vec<AllowanceSummary>,
+}
+
+/// On-chain allowances of a wallet, newest first
+///
+/// Served from the indexer when it is configured and reachable, otherwise read
+/// over RPC by walking the nonce registry.
+pub async fn list_allowances(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    Query(query): Query<ListAllowancesQuery>,
+) -> Result<Json<UserAllowancesResponse>> {
+    let user = parse_wallet(&wallet)?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let indexed = match &state.indexer {
+        Some(indexer) => match indexer.user_allowances(&wallet, limit).await {
+            Ok(indexed) => Some(indexed),
+            Err(e) => {
+                tracing::warn!(error = %e, "Indexer unavailable, reading allowances over RPC");
+                None
+            }
+        },
+        None => None,
+    };
+    let UserAllowances { next_nonce, allowances } = match indexed {
+        Some(indexed) => indexed,
+        None => fetch_allowances(&state, &user, limit).await?,
+    };
+
+    let now = state.clock.now().timestamp();
+    Ok(Json(UserAllowancesResponse {
+        next_nonce,
+        allowances: allowances
+            .into_iter()
+            .map(|allowance| AllowanceSummary {
+                remaining: allowance.remaining(),
+                active: allowance.is_active(now),
+                allowance,
+            })
+            .collect(),
+    }))
+}
+
+/// Read the registry's `next_nonce` over RPC; `None` before the first approval creates it
+async fn fetch_next_nonce(state: &AppState, registry: &Pubkey) -> Result<Option<u64>> {
+    let account = state
+        .solana
+        .get_account_with_commitment(registry, state.solana.commitment())
+        .await
+        .map_err(AppError::rpc_unavailable)?
+        .value;
+    Ok(account.map(|account| parse_next_nonce(&account.data)).transpose()?)
+}
+
+/// The `limit` newest allowances of `user`, fetched in one `getMultipleAccounts` call
+async fn fetch_allowances(state: &AppState, user: &Pubkey, limit: usize) -> Result<UserAllowances> {
+    let program_id = vault_program_id(state)?;
+    let casino = casino_pda(&program_id);
+    let Some(next_nonce) = fetch_next_nonce(state, &allowance_nonce_registry_pda(user, &casino, &program_id)).await?
+    else {
+        return Ok(UserAllowances::default());
+    };
+
+    let addresses: Vec<Pubkey> = (0..next_nonce)
+        .rev()
+        .take(limit)
+        .map(|nonce| allowance_pda(user, &casino, nonce, &program_id))
+        .collect();
+    let response = state
+        .solana
+        .get_multiple_accounts_with_commitment(&addresses, state.solana.commitment())
+        .await
+        .map_err(AppError::rpc_unavailable)?;
+    let slot = response.context.slot;
+
+    let mut allowances = Vec::with_capacity(addresses.len());
+    // Missing accounts are allowances that were closed
+    for (address, account) in addresses.iter().zip(response.value) {
+        let Some(account) = account else { continue };
+        match AllowanceAccount::decode(&account.data) {
+            Ok(allowance) => allowances.push(IndexedAllowance::from_account(address.to_string(), &allowance, slot)),
+            Err(e) => tracing::warn!(%address, error = %e, "Skipping undecodable allowance account"),
+        }
+    }
+
+    Ok(UserAllowances { next_nonce: Some(next_nonce), allowances })
+}
+
+/// Build an unsigned approval transaction using the next on-chain nonce; with spend
+/// limits it uses approve_allowance_v3, otherwise approve_allowance_v2
+pub async fn prepare_allowance(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    ValidatedJson(req): ValidatedJson<PrepareAllowanceRequest>,
+) -> Result<Json<PreparedAllowanceResponse>> {
+    let span = tracing::info_span!("prepare_allowance", %wallet, amount = req.amount);
+    let _enter = span.enter();
+
+    let user = parse_wallet(&wallet)?;
+    Ok(Json(prepare_approval(&state, &user, &wallet, &req).await?))
+}
+
+/// Validate `req` and build its unsigned approval transaction
+async fn prepare_approval(
+    state: &AppState,
+    user: &Pubkey,
+    wallet: &str,
+    req: &PrepareAllowanceRequest,
+) -> Result<PreparedAllowanceResponse> {
+    if req.amount == 0 || req.amount > MAX_ALLOWANCE_AMOUNT_LAMPORTS {
+        return Err(AppError::invalid_input(format!(
+            "amount must be between 1 and {}",
+            MAX_ALLOWANCE_AMOUNT_LAMPORTS
+        )));
+    }
+    if req.duration_seconds <= 0 || req.duration_seconds > MAX_ALLOWANCE_DURATION_SECS {
+        return Err(AppError::invalid_input(format!(
+            "duration_seconds must be between 1 and {}",
+            MAX_ALLOWANCE_DURATION_SECS
+        )));
+    }
+    for (name, limit) in [("max_single_spend", req.max_single_spend), ("max_spend_per_hour", req.max_spend_per_hour)] {
+        if limit.is_some_and(|limit| limit == 0 || limit > req.amount) {
+            return Err(AppError::invalid_input(format!("{} must be between 1 and amount", name)));
+        }
+    }
+    // SOL allowances use the system program as their mint
+    let token_mint = match req.token_mint.as_deref() {
+        Some(mint) => Pubkey::from_str(mint).map_err(|_| AppError::invalid_input("Invalid token mint"))?,
+        None => system_program::ID,
+    };
+
+    let program_id = vault_program_id(state)?;
+    let registry = allowance_nonce_registry_pda(user, &casino_pda(&program_id), &program_id);
+
+    // A nonce from a lagging indexer only makes the approval fail on-chain; the client re-prepares
+    let indexed_nonce = match &state.indexer {
+        Some(indexer) => match indexer.user_allowances(wallet, 1).await {
+            Ok(indexed) => Some(indexed.next_nonce.unwrap_or(0)),
+            Err(e) => {
+                tracing::warn!(error = %e, "Indexer unavailable, reading nonce registry over RPC");
+                None
+            }
+        },
+        None => None,
+    };
+
+    // The registry is created by the first approval, so a missing account means nonce 0
+    let nonce = match indexed_nonce {
+        Some(nonce) => nonce,
+        None => fetch_next_nonce(state, &registry).await?.unwrap_or(0),
+    };
+
+    let (instruction, allowance) = if req.max_single_spend.is_some() || req.max_spend_per_hour.is_some() {
+        build_approve_allowance_v3_instruction(
+            &program_id,
+            user,
+            req.amount,
+            req.duration_seconds,
+            &token_mint,
+            nonce,
+            req.max_single_spend.unwrap_or(0),
+            req.max_spend_per_hour.unwrap_or(0),
+        )
+    } else {
+        build_approve_allowance_v2_instruction(
+            &program_id,
+            user,
+            req.amount,
+            req.duration_seconds,
+            &token_mint,
+            nonce,
+        )
+    };
+
+    let (blockhash, last_valid_block_height) = state
+        .solana
+        .get_latest_blockhash_with_commitment(state.solana.commitment())
+        .await
+        .map_err(AppError::rpc_unavailable)?;
+
+    let transaction = encode_unsigned_transaction(&[instruction], user, blockhash)?;
+
+    tracing::info!(allowance_pda = %allowance, nonce, "Prepared allowance approval transaction");
+    metrics::counter!("allowances_prepared_total").increment(1);
+
+    Ok(PreparedAllowanceResponse {
+        transaction,
+        recent_blockhash: blockhash.to_string(),
+        last_valid_block_height,
+        allowance_pda: allowance.to_string(),
+        nonce,
+        max_single_spend: req.max_single_spend,
+        max_spend_per_hour: req.max_spend_per_hour,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct AllowanceWarning {
+    pub allowance_pda: String,
+    /// Left on the allowance once this bet's stake is spent
+    pub remaining_after_bet: u64,
+    pub expires_at: i64,
+    /// Any of `low_remaining`, `expiring_soon` and `inactive`
+    pub reasons: Vec<&'static str>,
+    /// Approval for a new allowance with the same amount, mint and limits
+    pub renewal: PreparedAllowanceResponse,
+}
+
+/// Why `allowance` should be renewed once `stake` is spent from it; empty when it is healthy
+fn warning_reasons(allowance: &IndexedAllowance, stake: u64, now: i64, config: &BettingConfig) -> Vec<&'static str> {
+    let mut reasons = Vec::new();
+    let remaining_after_bet = allowance.remaining().saturating_sub(stake);
+    if config.allowance_warning_remaining_lamports > 0
+        && remaining_after_bet < config.allowance_warning_remaining_lamports
+    {
+        reasons.push("low_remaining");
+    }
+    if config.allowance_warning_expiry_seconds > 0
+        && allowance.expires_at.saturating_sub(now) <= config.allowance_warning_expiry_seconds as i64
+    {
+        reasons.push("expiring_soon");
+    }
+    if allowance.revoked || now > allowance.expires_at {
+        reasons.push("inactive");
+    }
+    reasons
+}
+
+/// Renewal suggestion for the allowance a bet was placed against
+///
+/// `None` when the allowance is healthy or can't be read: the bet is already
+/// accepted, so failures here are only logged.
+pub async fn allowance_warning(
+    state: &AppState,
+    wallet: &str,
+    allowance_pda: &str,
+    stake: u64,
+) -> Option<AllowanceWarning> {
+    let config = &state.config.betting;
+    if config.allowance_warning_remaining_lamports == 0 && config.allowance_warning_expiry_seconds == 0 {
+        return None;
+    }
+    match build_allowance_warning(state, wallet, allowance_pda, stake).await {
+        Ok(warning) => warning,
+        Err(e) => {
+            tracing::warn!(%allowance_pda, error = %e, "Failed to check allowance for renewal");
+            None
+        }
+    }
+}
+
+async fn build_allowance_warning(
+    state: &AppState,
+    wallet: &str,
+    allowance_pda: &str,
+    stake: u64,
+) -> Result<Option<AllowanceWarning>> {
+    let user = parse_wallet(wallet)?;
+    let address = Pubkey::from_str(allowance_pda).map_err(|_| AppError::invalid_input("Invalid allowance PDA"))?;
+    let Some(allowance) = fetch_allowance(state, &address).await? else {
+        return Ok(None);
+    };
+    if allowance.user != wallet {
+        return Ok(None);
+    }
+
+    let reasons = warning_reasons(&allowance, stake, state.clock.now().timestamp(), &state.config.betting);
+    if reasons.is_empty() {
+        return Ok(None);
+    }
+
+    let renewal = PrepareAllowanceRequest {
+        amount: allowance.amount,
+        duration_seconds: MAX_ALLOWANCE_DURATION_SECS,
+        token_mint: Some(allowance.token_mint.clone()),
+        max_single_spend: (allowance.max_single_spend > 0).then_some(allowance.max_single_spend),
+        max_spend_per_hour: (allowance.max_spend_per_hour > 0).then_some(allowance.max_spend_per_hour),
+    };
+    let renewal = prepare_approval(state, &user, wallet, &renewal).await?;
+    metrics::counter!("allowance_warnings_total").increment(1);
+
+    Ok(Some(AllowanceWarning {
+        allowance_pda: allowance.address.clone(),
+        remaining_after_bet: allowance.remaining().saturating_sub(stake),
+        expires_at: allowance.expires_at,
+        reasons,
+        renewal,
+    }))
+}
+
+/// One allowance, from the indexer when it has it, otherwise over RPC
+pub(crate) async fn fetch_allowance(state: &AppState, address: &Pubkey) -> Result<Option<IndexedAllowance>> {
+    if let Some(indexer) = &state.indexer {
+        match indexer.allowance(&address.to_string()).await {
+            Ok(Some(allowance)) => return Ok(Some(allowance)),
+            // A just-approved allowance may not be indexed yet
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, "Indexer unavailable, reading allowance over RPC"),
+        }
+    }
+
+    let response = state
+        .solana
+        .get_account_with_commitment(address, state.solana.commitment())
+        .await
+        .map_err(AppError::rpc_unavailable)?;
+    let slot = response.context.slot;
+    match response.value {
+        Some(account) => {
+            let allowance = AllowanceAccount::decode(&account.data)?;
+            Ok(Some(IndexedAllowance::from_account(address.to_string(), &allowance, slot)))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning_reasons() {
+        let config = BettingConfig {
+            min_bet_lamports: 0,
+            max_bet_lamports: u64::MAX,
+            bet_ttl_seconds: 0,
+            bet_expiry_sweep_interval_seconds: 60,
+            stale_claim_seconds: 0,
+            stale_claim_sweep_interval_seconds: 60,
+            allowance_warning_remaining_lamports: 500,
+            allowance_warning_expiry_seconds: 600,
+            session_max_rounds: 1000,
+            intake_max_queue_depth: 0,
+            intake_retry_after_seconds: 5,
+        };
+        let allowance = IndexedAllowance {
+            address: "A".to_string(),
+            user: "U".to_string(),
+            token_mint: system_program::ID.to_string(),
+            amount: 2_000,
+            spent: 1_000,
+            expires_at: 10_000,
+            nonce: 0,
+            revoked: false,
+            spend_count: 1,
+            max_single_spend: 0,
+            max_spend_per_hour: 0,
+            slot: 1,
+        };
+
+        assert!(warning_reasons(&allowance, 100, 1_000, &config).is_empty());
+        assert_eq!(warning_reasons(&allowance, 600, 1_000, &config), vec!["low_remaining"]);
+        assert_eq!(warning_reasons(&allowance, 100, 9_400, &config), vec!["expiring_soon"]);
+        assert_eq!(
+            warning_reasons(&allowance, 100, 10_001, &config),
+            vec!["expiring_soon", "inactive"]
+        );
+
+        let disabled = BettingConfig {
+            allowance_warning_remaining_lamports: 0,
+            allowance_warning_expiry_seconds: 0,
+            ..config
+        };
+        assert!(warning_reasons(&allowance, 600, 9_400, &disabled).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_and_prepare_over_rpc() {
+        let repos = crate::testing::InMemoryRepositories::new(&crate::testing::config());
+        let server =
+            axum_test::TestServer::new(crate::build_router(repos.app_state(crate::testing::config()))).unwrap();
+        let wallet = Pubkey::new_unique();
+
+        // Before the first approval there is no nonce registry
+        let listed: serde_json::Value = server.get(&format!("/api/users/{}/allowances", wallet)).await.json();
+        assert_eq!(listed, serde_json::json!({ "next_nonce": null, "allowances": [] }));
+
+        let path = format!("/api/users/{}/allowances/prepare", wallet);
+        let request = |amount: u64| serde_json::json!({ "amount": amount, "duration_seconds": 3600 });
+        let prepared: serde_json::Value = server.post(&path).json(&request(1_000)).await.json();
+        assert_eq!(prepared["nonce"], 0);
+        assert!(prepared["allowance_pda"].is_string());
+        server.post(&path).json(&request(0)).await.assert_status_bad_request();
+    }
+}
@@ -0,0 +1,65 @@
+use axum::{extract::State, Json};
+use chrono::Utc;
+use shared::api::WALLET_READ_SCOPE;
+
+use crate::{
+    auth,
+    domain::{WalletTokenRequest, WalletTokenResponse},
+    errors::Result,
+    extractors::ValidatedJson,
+    state::AppState,
+};
+
+/// Exchange a wallet signature for a token that reads the wallet's bets
+pub async fn issue_token(
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<WalletTokenRequest>,
+) -> Result<Json<WalletTokenResponse>> {
+    let span = tracing::info_span!("issue_token", wallet = %req.wallet);
+    let _enter = span.enter();
+
+    let config = &state.config.auth;
+    auth::verify_request(&req, Utc::now(), config.signature_max_age_seconds)?;
+    let (token, expires_at) = state
+        .ops
+        .issue_token(&req, config.token_ttl_seconds, config.signature_max_age_seconds)
+        .await?;
+
+    metrics::counter!("auth_tokens_issued_total").increment(1);
+    tracing::info!(%expires_at, "Issued wallet read token");
+    Ok(Json(WalletTokenResponse {
+        token,
+        wallet: req.wallet,
+        scope: WALLET_READ_SCOPE.to_string(),
+        expires_at,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::OpsRepository;
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use shared::api::wallet_token_message;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[tokio::test]
+    async fn test_issues_a_token_once_per_signature() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let keypair = Keypair::new();
+        let wallet = keypair.pubkey().to_string();
+        let issued_at = Utc::now().timestamp();
+        let signature = keypair.sign_message(wallet_token_message(&wallet, issued_at).as_bytes());
+        let req = WalletTokenRequest { wallet: wallet.clone(), issued_at, signature: signature.to_string() };
+
+        let issued: WalletTokenResponse = server.post("/api/auth/token").json(&req).await.json();
+        assert_eq!((issued.wallet.as_str(), issued.scope.as_str()), (wallet.as_str(), WALLET_READ_SCOPE));
+        assert_eq!(repos.ops.token_wallet(&issued.token).await.unwrap(), Some(wallet.clone()));
+        server.post("/api/auth/token").json(&req).await.assert_status_unauthorized();
+
+        let forged = WalletTokenRequest { wallet: Keypair::new().pubkey().to_string(), ..req };
+        server.post("/api/auth/token").json(&forged).await.assert_status_unauthorized();
+    }
+}
@@ -1,19 +1,31 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use redis::AsyncCommands;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use shared::fairness::MAX_CLIENT_SEED_LEN;
 use solana_sdk::pubkey::Pubkey;
+use std::net::SocketAddr;
 use std::str::FromStr;
+use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
-    domain::{Bet, CreateBetRequest},
+    bet_metadata,
+    cache::{etag_for, if_none_match},
+    domain::{Bet, BetDetail, BetSettlement, ClaimableEvent, ClientInfo, CreateBetRequest},
     errors::{AppError, Result},
-    extractors::ValidatedJson,
-    repository::{BetRepository, RedisBetRepository},
+    extractors::{BetReader, ValidatedJson},
+    handlers::allowances::{allowance_warning, AllowanceWarning},
+    ledger,
+    notifications::Notification,
+    receipts::BetReceipt,
+    sessions,
     state::AppState,
+    telemetry,
 };
 
 #[derive(Debug, Deserialize)]
@@ -23,13 +35,22 @@ pub struct ListBetsQuery {
     pub user_wallet: Option<String>,
 }
 
+/// Longest user agent kept with a bet
+const MAX_USER_AGENT_LEN: usize = 512;
+
 #[derive(Debug, Serialize)]
 pub struct CreateBetResponse {
     pub bet: Bet,
+    pub receipt: BetReceipt,
+    /// Present when the bet's allowance is running low or about to expire
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowance_warning: Option<AllowanceWarning>,
 }
 
 pub async fn create_bet(
     State(state): State<AppState>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
     // TODO: Extract user_wallet from Privy authentication
     ValidatedJson(mut req): ValidatedJson<CreateBetRequest>,
 ) -> Result<Json<CreateBetResponse>> {
@@ -38,10 +59,25 @@ pub async fn create_bet(
         "create_bet",
         bet.stake_amount = %req.stake_amount,
         bet.choice = %req.choice,
-        bet.game_type = "coinflip"
+        bet.game_type = %req.game_type
     );
     let _enter = span.enter();
 
+    // Bets placed in a session take its wallet, vault and allowance
+    let stake = req.stake_amount.as_u64();
+    let session = match req.session_id {
+        Some(session_id) => {
+            let session = state
+                .ops
+                .session(session_id)
+                .await?
+                .ok_or_else(|| AppError::session_not_found(session_id))?;
+            sessions::bind_bet(&session, &mut req, stake, Utc::now())?;
+            Some(session)
+        }
+        None => None,
+    };
+
     // Use provided user_wallet or a valid test wallet for development
     // In production, extract from authenticated session
     let user_wallet = req.user_wallet.take().unwrap_or_else(|| {
@@ -79,65 +115,166 @@ pub async fn create_bet(
         "Creating bet"
     );
 
-    // Validation is now handled by LamportAmount type during deserialization
-    // No need for manual range checks
+    // LamportAmount bounds the stake during deserialization; the game narrows it below
+
+    if let Some(client_seed) = &req.client_seed {
+        if client_seed.len() > MAX_CLIENT_SEED_LEN || client_seed.chars().any(|c| c.is_control()) {
+            return Err(AppError::invalid_input(format!(
+                "client_seed must be at most {} printable characters",
+                MAX_CLIENT_SEED_LEN
+            )));
+        }
+    }
+
+    if let Some(metadata) = &req.metadata {
+        bet_metadata::validate(metadata, &state.config.metadata)?;
+    }
+
+    state.games.validate(&req.game_type, &req.choice, stake, &state.config.betting)?;
+
+    if state.ops.is_halted().await? {
+        return Err(AppError::service_halted());
+    }
+    state.load_shedder.check(state.queue.claimable_depth()).await?;
+
+    if let Some(session) = &session {
+        state.ops.reserve_session(session, stake).await?;
+    }
+
+    let client = ClientInfo {
+        ip: state
+            .jurisdiction
+            .client_ip(&headers, peer.map(|ConnectInfo(addr)| addr.ip()))
+            .map(|ip| ip.to_string()),
+        user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.chars().take(MAX_USER_AGENT_LEN).collect()),
+    };
 
-    let repo = RedisBetRepository::new(state.redis.clone());
-    let bet = repo.create(&user_wallet, &vault_address, req).await?;
+    let allowance_pda = req.allowance_pda.clone();
+    let repo = &state.bets;
+    let bet = match repo.create(&user_wallet, &vault_address, req, &client).await {
+        Ok(bet) => bet,
+        Err(e) => {
+            if let Some(session) = &session {
+                if let Err(release_error) = state.ops.release_session(session.session_id, stake).await {
+                    tracing::warn!(session_id = %session.session_id, error = %release_error, "Failed to release session reservation");
+                }
+            }
+            return Err(e);
+        }
+    };
 
     tracing::info!(
         bet_id = %bet.bet_id,
         "Bet created successfully"
     );
+    state.ops.post_ledger_or_warn(&[ledger::reserve(&bet)]).await;
 
-    // Publish to Redis stream for processor to pick up immediately
-    let mut redis_conn = state.redis.clone();
-    let _: String = redis_conn
-        .xadd(
-            "bets:pending",
-            "*",
-            &[("bet_id", bet.bet_id.to_string())],
-        )
+    // Publish to the pending stream for processor to pick up immediately
+    state
+        .queue
+        .publish_pending(bet.bet_id)
         .await
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis publish failed: {}", e)))?;
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Pending bet publish failed: {}", e)))?;
 
     tracing::info!(
         bet_id = %bet.bet_id,
-        "Published bet to Redis stream"
+        queue_backend = state.queue.name(),
+        "Published bet to pending stream"
     );
-    metrics::counter!("bets_created_total").increment(1);
+    if state.config.queue.claim_signals {
+        state.ops.signal_claimable(ClaimableEvent { bet_id: bet.bet_id, priority: false }).await;
+    }
+    telemetry::record_bet_created(&bet.stake_token);
+
+    let allowance_warning = match allowance_pda {
+        Some(allowance_pda) => allowance_warning(&state, &user_wallet, &allowance_pda, bet.stake_amount.max(0) as u64).await,
+        None => None,
+    };
+    if let Some(notification) = allowance_warning
+        .as_ref()
+        .and_then(|warning| Notification::allowance_expiring(&bet, warning))
+        .filter(|_| state.config.notifications.enabled)
+    {
+        state.ops.publish_notifications(&[notification]).await;
+    }
 
-    Ok(Json(CreateBetResponse { bet }))
+    let receipt = state.receipts.sign(&bet);
+    Ok(Json(CreateBetResponse { bet, receipt, allowance_warning }))
 }
 
 pub async fn get_bet(
     State(state): State<AppState>,
+    reader: BetReader,
+    headers: HeaderMap,
     Path(bet_id): Path<Uuid>,
-) -> Result<Json<Bet>> {
+) -> Result<Response> {
     let span = tracing::info_span!("get_bet", %bet_id);
     let _enter = span.enter();
 
-    let repo = RedisBetRepository::new(state.redis.clone());
-    let bet = repo
-        .find_by_id(bet_id)
-        .await?
-        .ok_or_else(|| {
-            tracing::debug!("Bet not found");
-            AppError::not_found(format!("Bet {} not found", bet_id))
-        })?;
+    let bet = match state.bet_cache.get(bet_id).await {
+        Some(bet) => bet,
+        None => {
+            let repo = &state.bets;
+            let bet = Arc::new(repo.find_by_id(bet_id).await?.ok_or_else(|| {
+                tracing::debug!("Bet not found");
+                AppError::not_found(format!("Bet {} not found", bet_id))
+            })?);
+            state.bet_cache.insert(bet.clone()).await;
+            bet
+        }
+    };
+
+    // Someone else's bet looks the same as a missing one
+    if !reader.can_read(&bet.user_wallet) {
+        return Err(AppError::not_found(format!("Bet {} not found", bet_id)));
+    }
 
     tracing::debug!(status = ?bet.status, "Bet retrieved");
-    Ok(Json(bet))
+
+    let report = state.batches.settlement(bet_id).await?;
+    let detail = BetDetail {
+        settlement: BetSettlement::of(&bet, report.as_ref()),
+        bet: bet.as_ref().clone(),
+    };
+    let body = serde_json::to_vec(&detail)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to serialize bet: {}", e)))?;
+    let etag = etag_for(&body);
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| if_none_match(v, &etag));
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+
+    Ok((
+        [
+            (header::ETAG, etag),
+            (header::CONTENT_TYPE, "application/json".to_string()),
+        ],
+        body,
+    )
+        .into_response())
 }
 
 pub async fn list_user_bets(
     State(state): State<AppState>,
+    reader: BetReader,
     Query(query): Query<ListBetsQuery>,
 ) -> Result<Json<Vec<Bet>>> {
-    // TODO: Extract user_wallet from authentication. For POC, allow query override.
-    let user_wallet = query
-        .user_wallet
-        .unwrap_or_else(|| "TEMP_WALLET_ADDRESS".to_string());
+    // A wallet token lists its own wallet; `user_wallet` may only repeat it
+    let user_wallet = match (reader, query.user_wallet) {
+        (BetReader::Wallet(own), Some(asked)) if asked != own => {
+            return Err(AppError::unauthorized("Token does not grant access to this wallet"));
+        }
+        (BetReader::Wallet(own), _) => own,
+        (_, Some(asked)) => asked,
+        (_, None) => return Err(AppError::invalid_input("user_wallet is required")),
+    };
 
     let limit = query.limit.unwrap_or(20).min(100);
     let offset = query.offset.unwrap_or(0);
@@ -150,9 +287,165 @@ pub async fn list_user_bets(
     );
     let _enter = span.enter();
 
-    let repo = RedisBetRepository::new(state.redis.clone());
+    let repo = &state.bets;
     let bets = repo.find_by_user(&user_wallet, limit, offset).await?;
 
     tracing::debug!(bet_count = bets.len(), "Retrieved user bets");
     Ok(Json(bets))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Session, SessionLimits, WalletTokenRequest};
+    use crate::killswitch::KillSwitchState;
+    use crate::repository::OpsRepository;
+    use crate::testing::{self, InMemoryRepositories};
+    use axum::http::HeaderValue;
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    fn server(repos: &InMemoryRepositories, config: crate::config::Config) -> TestServer {
+        TestServer::new(crate::build_router(repos.app_state(config))).unwrap()
+    }
+
+    fn bet_request(wallet: &str, vault: &str) -> serde_json::Value {
+        json!({
+            "user_wallet": wallet,
+            "vault_address": vault,
+            "stake_amount": 100_000_000u64,
+            "stake_token": "SOL",
+            "choice": "heads",
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_bet_then_read_it() {
+        let mut config = testing::config();
+        config.auth.require_bet_read_token = false;
+        let repos = InMemoryRepositories::new(&config);
+        let server = server(&repos, config);
+        let (wallet, vault) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+
+        let created: serde_json::Value = server.post("/api/bets").json(&bet_request(&wallet, &vault)).await.json();
+        let bet: Bet = serde_json::from_value(created["bet"].clone()).unwrap();
+        assert!(created["receipt"]["signature"].is_string());
+        assert_eq!((bet.user_wallet.as_str(), bet.stake_amount), (wallet.as_str(), 100_000_000));
+        assert_eq!(repos.queue.published(), vec![bet.bet_id]);
+        assert_eq!(repos.ops.balance(&ledger::wallet_reserved(&wallet, "SOL")), 100_000_000);
+
+        let response = server.get(&format!("/api/bets/{}", bet.bet_id)).await;
+        response.assert_status_ok();
+        let etag = response.header(header::ETAG);
+        let detail: BetDetail = response.json();
+        assert_eq!(detail.bet.bet_id, bet.bet_id);
+        server
+            .get(&format!("/api/bets/{}", bet.bet_id))
+            .add_header(header::IF_NONE_MATCH, etag)
+            .await
+            .assert_status(StatusCode::NOT_MODIFIED);
+        server.get(&format!("/api/bets/{}", Uuid::new_v4())).await.assert_status_not_found();
+
+        let listed: Vec<Bet> = server.get("/api/bets").add_query_param("user_wallet", &wallet).await.json();
+        assert_eq!(listed.iter().map(|bet| bet.bet_id).collect::<Vec<_>>(), vec![bet.bet_id]);
+        server.get("/api/bets").await.assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_create_bet_rejects_invalid_requests() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = server(&repos, testing::config());
+        let vault = Pubkey::new_unique().to_string();
+
+        server.post("/api/bets").json(&bet_request("not-a-wallet", &vault)).await.assert_status_bad_request();
+        let mut request = bet_request(&Pubkey::new_unique().to_string(), &vault);
+        request["client_seed"] = json!("x".repeat(MAX_CLIENT_SEED_LEN + 1));
+        server.post("/api/bets").json(&request).await.assert_status_bad_request();
+        request["client_seed"] = json!(null);
+        request["game_type"] = json!("roulette");
+        server.post("/api/bets").json(&request).await.assert_status_bad_request();
+        assert!(repos.queue.published().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_kill_switch_halts_intake() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = server(&repos, testing::config());
+        let state = KillSwitchState {
+            engaged_by: "ops".to_string(),
+            reason: "incident".to_string(),
+            engaged_at: Utc::now(),
+            on_chain_signature: None,
+        };
+        repos.ops.engage_kill_switch(&state).await.unwrap();
+
+        let request = bet_request(&Pubkey::new_unique().to_string(), &Pubkey::new_unique().to_string());
+        server.post("/api/bets").json(&request).await.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert!(repos.queue.published().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_session_bets_reserve_rounds() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = server(&repos, testing::config());
+        let session = Session {
+            session_id: Uuid::new_v4(),
+            user_wallet: Pubkey::new_unique().to_string(),
+            vault_address: Pubkey::new_unique().to_string(),
+            allowance_pda: Pubkey::new_unique().to_string(),
+            stake_token: "SOL".to_string(),
+            limits: SessionLimits { max_rounds: Some(1), ..Default::default() },
+            created_at: Utc::now(),
+            expires_at: Utc::now() + chrono::Duration::hours(1),
+        };
+        repos.ops.create_session(&session).await.unwrap();
+        let request = json!({
+            "session_id": session.session_id,
+            "stake_amount": 100_000_000u64,
+            "stake_token": "SOL",
+            "choice": "tails",
+        });
+
+        let created: serde_json::Value = server.post("/api/bets").json(&request).await.json();
+        assert_eq!(created["bet"]["user_wallet"], session.user_wallet.as_str());
+        assert_eq!(repos.ops.session_usage(session.session_id).await.unwrap().rounds, 1);
+        server.post("/api/bets").json(&request).await.assert_status_bad_request();
+
+        let mut unknown = request.clone();
+        unknown["session_id"] = json!(Uuid::new_v4());
+        server.post("/api/bets").json(&unknown).await.assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_wallet_token_scopes_reads() {
+        let mut config = testing::config();
+        config.auth.require_bet_read_token = true;
+        let repos = InMemoryRepositories::new(&config);
+        let server = server(&repos, config);
+        let bet = testing::pending_bet("W1");
+        repos.bets.insert(bet.clone());
+        let token_for = |wallet: &str| WalletTokenRequest {
+            wallet: wallet.to_string(),
+            issued_at: Utc::now().timestamp(),
+            signature: Uuid::new_v4().to_string(),
+        };
+        let (own, _) = repos.ops.issue_token(&token_for("W1"), 60, 60).await.unwrap();
+        let (other, _) = repos.ops.issue_token(&token_for("W2"), 60, 60).await.unwrap();
+        let bearer = |token: &str| HeaderValue::from_str(&format!("Bearer {}", token)).unwrap();
+
+        let path = format!("/api/bets/{}", bet.bet_id);
+        server.get(&path).await.assert_status_unauthorized();
+        server.get(&path).add_header(header::AUTHORIZATION, bearer("unknown")).await.assert_status_unauthorized();
+        server.get(&path).add_header(header::AUTHORIZATION, bearer(&own)).await.assert_status_ok();
+        server.get(&path).add_header(header::AUTHORIZATION, bearer(&other)).await.assert_status_not_found();
+
+        let listed: Vec<Bet> = server.get("/api/bets").add_header(header::AUTHORIZATION, bearer(&own)).await.json();
+        assert_eq!(listed.len(), 1);
+        server
+            .get("/api/bets")
+            .add_query_param("user_wallet", "W1")
+            .add_header(header::AUTHORIZATION, bearer(&other))
+            .await
+            .assert_status_unauthorized();
+    }
+}
@@ -0,0 +1,256 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    disputes::{can_dispute, Dispute, DisputeResolution, DisputeStatus, MAX_DISPUTE_DETAILS_LEN},
+    domain::{AuditEntry, DisputeRequest, ResolveDisputeRequest},
+    errors::{AppError, Result},
+    extractors::ValidatedJson,
+    handlers::admin::{can_manually_settle, require_admin},
+    refunds::{self, can_refund},
+    state::AppState,
+};
+
+/// Flag a finished bet for operator review
+pub async fn open_dispute(
+    State(state): State<AppState>,
+    Path(bet_id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<DisputeRequest>,
+) -> Result<Json<Dispute>> {
+    let span = tracing::info_span!("open_dispute", %bet_id, reason = ?req.reason);
+    let _enter = span.enter();
+
+    if req.details.len() > MAX_DISPUTE_DETAILS_LEN || req.details.chars().any(|c| c.is_control() && c != '\n') {
+        return Err(AppError::invalid_input(format!(
+            "details must be at most {} printable characters",
+            MAX_DISPUTE_DETAILS_LEN
+        )));
+    }
+
+    let repo = &state.bets;
+    // Don't reveal whether someone else's bet exists
+    let bet = repo
+        .find_by_id(bet_id)
+        .await?
+        .filter(|bet| bet.user_wallet == req.user_wallet)
+        .ok_or_else(|| AppError::not_found(format!("Bet {} not found", bet_id)))?;
+
+    if !can_dispute(&bet.status) {
+        return Err(AppError::invalid_status(format!(
+            "Bet {} is still being settled and cannot be disputed yet",
+            bet_id
+        )));
+    }
+
+    let dispute = Dispute {
+        bet_id,
+        user_wallet: bet.user_wallet.clone(),
+        reason: req.reason,
+        details: req.details.trim().to_string(),
+        status: DisputeStatus::Open,
+        bet_status: bet.status.clone(),
+        created_at: Utc::now(),
+        resolved_at: None,
+        resolved_by: None,
+        resolution_note: None,
+    };
+
+    if !state.ops.open_dispute(&dispute).await? {
+        return Err(AppError::invalid_status(format!("Bet {} has already been disputed", bet_id)));
+    }
+
+    state.audit.append_audit(&AuditEntry {
+        bet_id,
+        action: "dispute_opened".to_string(),
+        operator: dispute.user_wallet.clone(),
+        reason: dispute.details.clone(),
+        previous_status: bet.status.clone(),
+        details: serde_json::json!({ "dispute_reason": dispute.reason }),
+        created_at: dispute.created_at,
+    })
+    .await?;
+
+    tracing::warn!(user_wallet = %dispute.user_wallet, bet_status = ?bet.status, "Bet disputed");
+    metrics::counter!("disputes_opened_total").increment(1);
+
+    Ok(Json(dispute))
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisputeQueue {
+    #[default]
+    Open,
+    Resolved,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListDisputesQuery {
+    #[serde(default)]
+    pub status: DisputeQueue,
+    pub limit: Option<isize>,
+}
+
+/// Admin queue of open disputes (oldest first), or recently resolved ones
+pub async fn list_disputes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListDisputesQuery>,
+) -> Result<Json<Vec<Dispute>>> {
+    require_admin(&state.config, &headers)?;
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let open = matches!(query.status, DisputeQueue::Open);
+    Ok(Json(state.ops.disputes(open, limit).await?))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolveDisputeResponse {
+    pub dispute: Dispute,
+    pub audit: AuditEntry,
+}
+
+/// Close a dispute by upholding the outcome or refunding the stake
+///
+/// A bet that never settled on-chain is refunded by a manual settlement paying
+/// the stake back; one that settled as a loss is voided and refunded on-chain.
+pub async fn resolve_dispute(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(bet_id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<ResolveDisputeRequest>,
+) -> Result<Json<ResolveDisputeResponse>> {
+    require_admin(&state.config, &headers)?;
+
+    let span = tracing::info_span!("resolve_dispute", %bet_id, operator = %req.operator, action = req.action.as_str());
+    let _enter = span.enter();
+
+    if req.operator.trim().is_empty() || req.reason.trim().is_empty() {
+        return Err(AppError::invalid_input("operator and reason are required"));
+    }
+
+    let mut dispute = state
+        .ops
+        .dispute(bet_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("No dispute for bet {}", bet_id)))?;
+    if !dispute.is_open() {
+        return Err(AppError::invalid_status(format!("Dispute for bet {} is already resolved", bet_id)));
+    }
+
+    let repo = &state.bets;
+    let bet = repo
+        .find_by_id(bet_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Bet {} not found", bet_id)))?;
+
+    let refund_amount = match req.action {
+        DisputeResolution::Refund if can_manually_settle(&bet.status) || can_refund(&bet) => Some(bet.stake_amount),
+        DisputeResolution::Refund => {
+            return Err(AppError::invalid_status(format!(
+                "Bet {} cannot be refunded from status {:?} (won {:?}); refund it out of band and uphold",
+                bet_id, bet.status, bet.won
+            )));
+        }
+        DisputeResolution::Uphold => None,
+    };
+
+    let audit = AuditEntry {
+        bet_id,
+        action: format!("dispute_{}", req.action.as_str()),
+        operator: req.operator.clone(),
+        reason: req.reason.clone(),
+        previous_status: bet.status.clone(),
+        details: serde_json::json!({
+            "dispute_reason": dispute.reason,
+            "refund_amount": refund_amount,
+        }),
+        created_at: Utc::now(),
+    };
+
+    // Audit first so a failed enqueue still leaves a trace of the attempt
+    state.audit.append_audit(&audit).await?;
+    match refund_amount {
+        Some(amount) if can_manually_settle(&bet.status) => {
+            // Settling as a win paying the stake nets the user's stake back
+            repo.enqueue_manual_settlement(bet_id, true, amount).await?;
+            state.bet_cache.invalidate(bet_id).await;
+        }
+        Some(_) => {
+            let refund = refunds::for_bet(&bet, &req.operator, &req.reason, audit.created_at);
+            state.ops.request_refund(&refund).await?;
+        }
+        None => {}
+    }
+
+    dispute.resolve(req.action, &req.operator, &req.reason, audit.created_at);
+    state.ops.save_resolved_dispute(&dispute).await?;
+
+    tracing::warn!(previous_status = ?bet.status, refund_amount, reason = %req.reason, "Dispute resolved by operator");
+    metrics::counter!("disputes_resolved_total", "action" => req.action.as_str()).increment(1);
+
+    Ok(Json(ResolveDisputeResponse { dispute, audit }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::BetStatus;
+    use crate::handlers::admin::ADMIN_KEY_HEADER;
+    use crate::repository::{AuditRepository, BetRepository};
+    use crate::testing::{self, InMemoryRepositories, ADMIN_KEY};
+    use axum::http::{HeaderName, HeaderValue};
+    use axum_test::{TestRequest, TestServer};
+    use serde_json::json;
+
+    fn as_admin(request: TestRequest) -> TestRequest {
+        request.add_header(HeaderName::from_static(ADMIN_KEY_HEADER), HeaderValue::from_static(ADMIN_KEY))
+    }
+
+    #[tokio::test]
+    async fn test_open_and_refund_a_dispute() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let settling = testing::pending_bet("W1");
+        let mut expired = testing::pending_bet("W1");
+        expired.status = BetStatus::Expired;
+        repos.bets.insert(settling.clone());
+        repos.bets.insert(expired.clone());
+        let dispute = |bet_id: Uuid, wallet: &str| {
+            server
+                .post(&format!("/api/bets/{}/dispute", bet_id))
+                .json(&json!({ "user_wallet": wallet, "reason": "not_settled", "details": "never paid" }))
+        };
+
+        dispute(settling.bet_id, "W1").await.assert_status_bad_request();
+        dispute(expired.bet_id, "W2").await.assert_status_not_found();
+        let opened: Dispute = dispute(expired.bet_id, "W1").await.json();
+        assert_eq!((opened.status, opened.bet_status), (DisputeStatus::Open, BetStatus::Expired));
+        dispute(expired.bet_id, "W1").await.assert_status_bad_request();
+
+        server.get("/api/admin/disputes").await.assert_status_unauthorized();
+        let open: Vec<Dispute> = as_admin(server.get("/api/admin/disputes")).await.json();
+        assert_eq!(open.len(), 1);
+
+        // An unsettled bet is refunded by a manual settlement paying its stake back
+        let resolve = format!("/api/admin/disputes/{}/resolve", expired.bet_id);
+        let request = json!({ "action": "refund", "operator": "ops", "reason": "indexer outage" });
+        let resolved: serde_json::Value = as_admin(server.post(&resolve)).json(&request).await.json();
+        assert_eq!(resolved["dispute"]["status"], "refunded");
+        let settled = repos.bets.find_by_id(expired.bet_id).await.unwrap().unwrap();
+        assert_eq!((settled.status, settled.payout_amount), (BetStatus::Pending, Some(expired.stake_amount)));
+        as_admin(server.post(&resolve)).json(&request).await.assert_status_bad_request();
+
+        let resolved: Vec<Dispute> =
+            as_admin(server.get("/api/admin/disputes")).add_query_param("status", "resolved").await.json();
+        assert_eq!(resolved.len(), 1);
+        let trail = repos.audit.audit_trail(expired.bet_id).await.unwrap();
+        assert_eq!(trail.iter().map(|entry| entry.action.as_str()).collect::<Vec<_>>(), vec!["dispute_opened", "dispute_refund"]);
+    }
+}
@@ -0,0 +1,49 @@
+use axum::Json;
+use serde::Serialize;
+use shared::errors::{ErrorCategory, ErrorCode};
+
+#[derive(Debug, Serialize)]
+pub struct ErrorCatalogEntry {
+    pub code: &'static str,
+    pub category: ErrorCategory,
+    pub http_status: u16,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorCatalogResponse {
+    pub errors: Vec<ErrorCatalogEntry>,
+}
+
+/// Every error code a service can return, for generating client error handling
+pub async fn list_error_codes() -> Json<ErrorCatalogResponse> {
+    let errors = ErrorCode::CATALOG
+        .iter()
+        .map(|info| ErrorCatalogEntry {
+            code: info.code.as_str(),
+            category: info.category,
+            http_status: info.category.status_code(),
+            description: info.description,
+        })
+        .collect();
+    Json(ErrorCatalogResponse { errors })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_lists_every_error_code() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+
+        let catalog: Value = server.get("/api/errors").await.json();
+        let errors = catalog["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), shared::errors::ErrorCode::CATALOG.len());
+        let session_limit = errors.iter().find(|entry| entry["code"] == "VALIDATION_SESSION_LIMIT").unwrap();
+        assert_eq!(session_limit["http_status"], 400);
+    }
+}
@@ -0,0 +1,400 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::Hasher;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    domain::{Bet, BetStatus},
+    errors::{AppError, Result},
+    handlers::{admin::require_admin, withdrawals::parse_wallet},
+    receipts::ReceiptSigner,
+    reports::csv_field,
+    repository::BetRepository,
+    state::AppState,
+};
+
+/// Bets loaded per chunk; bounds memory regardless of history length
+const EXPORT_PAGE_SIZE: i64 = 200;
+
+/// Keys visited per SCAN step of a bet stream
+const STREAM_SCAN_COUNT: usize = 500;
+
+const CSV_HEADER: &str = "bet_id,created_at,status,game_type,stake_token,stake_amount,choice,won,payout_amount,solana_tx_id,external_batch_id,retry_count,last_error_code\n";
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// Newline-delimited JSON, one bet per line
+    Json,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// One exported bet: its terms, settlement outcome and on-chain references
+#[derive(Debug, Serialize)]
+struct ExportRow<'a> {
+    bet_id: Uuid,
+    created_at: String,
+    status: &'a BetStatus,
+    game_type: &'a str,
+    stake_token: &'a str,
+    stake_amount: i64,
+    choice: &'a str,
+    won: Option<bool>,
+    payout_amount: Option<i64>,
+    solana_tx_id: Option<&'a str>,
+    external_batch_id: Option<Uuid>,
+    retry_count: i32,
+    last_error_code: Option<&'a str>,
+}
+
+impl<'a> ExportRow<'a> {
+    fn from_bet(bet: &'a Bet) -> Self {
+        Self {
+            bet_id: bet.bet_id,
+            created_at: bet.created_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            status: &bet.status,
+            game_type: &bet.game_type,
+            stake_token: &bet.stake_token,
+            stake_amount: bet.stake_amount,
+            choice: &bet.choice,
+            won: bet.won,
+            payout_amount: bet.payout_amount,
+            solana_tx_id: bet.solana_tx_id.as_deref(),
+            external_batch_id: bet.external_batch_id,
+            retry_count: bet.retry_count,
+            last_error_code: bet.last_error_code.as_deref(),
+        }
+    }
+
+    fn to_csv_line(&self) -> String {
+        fn opt<T: ToString>(value: Option<T>) -> String {
+            value.map(|v| v.to_string()).unwrap_or_default()
+        }
+        let status = serde_json::to_value(self.status)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            self.bet_id,
+            self.created_at,
+            status,
+            csv_field(self.game_type),
+            csv_field(self.stake_token),
+            self.stake_amount,
+            csv_field(self.choice),
+            opt(self.won),
+            opt(self.payout_amount),
+            opt(self.solana_tx_id),
+            opt(self.external_batch_id),
+            self.retry_count,
+            csv_field(self.last_error_code.unwrap_or_default()),
+        )
+    }
+}
+
+/// Trailer proving the export is complete and unmodified
+///
+/// `signature` is an ed25519 signature by the receipt signer over the raw 32-byte
+/// SHA-256 digest of every byte that precedes the trailer.
+#[derive(Debug, Serialize)]
+struct ExportTrailer {
+    wallet: String,
+    rows: usize,
+    generated_at: String,
+    sha256: String,
+    signature: String,
+    signer: String,
+}
+
+impl ExportTrailer {
+    fn new(wallet: &str, rows: usize, digest: &[u8], signer: &ReceiptSigner) -> Self {
+        Self {
+            wallet: wallet.to_string(),
+            rows,
+            generated_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            sha256: digest.iter().map(|b| format!("{:02x}", b)).collect(),
+            signature: signer.sign_bytes(digest),
+            signer: signer.pubkey().to_string(),
+        }
+    }
+
+    fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Csv => format!(
+                "# wallet={} rows={} generated_at={} sha256={} signature={} signer={}\n",
+                self.wallet, self.rows, self.generated_at, self.sha256, self.signature, self.signer
+            ),
+            ExportFormat::Json => format!(
+                "{}\n",
+                serde_json::json!({ "export_signature": self })
+            ),
+        }
+    }
+}
+
+enum Stage {
+    Header,
+    Rows,
+    Trailer,
+    Done,
+}
+
+struct ExportStream {
+    repo: Arc<dyn BetRepository>,
+    signer: Arc<ReceiptSigner>,
+    wallet: String,
+    format: ExportFormat,
+    stage: Stage,
+    offset: i64,
+    rows: usize,
+    hasher: Hasher,
+}
+
+impl ExportStream {
+    /// Produce the next chunk; `None` once the trailer has been sent
+    async fn next_chunk(&mut self) -> Option<Result<String>> {
+        let chunk = match self.stage {
+            Stage::Done => return None,
+            Stage::Header => {
+                self.stage = Stage::Rows;
+                match self.format {
+                    ExportFormat::Csv => CSV_HEADER.to_string(),
+                    ExportFormat::Json => String::new(),
+                }
+            }
+            Stage::Rows => {
+                let page = match self.repo.find_by_user(&self.wallet, EXPORT_PAGE_SIZE, self.offset).await {
+                    Ok(page) => page,
+                    Err(e) => {
+                        self.stage = Stage::Done;
+                        metrics::counter!("wallet_exports_total", "result" => "error").increment(1);
+                        tracing::error!(wallet = %self.wallet, error = %e, "Wallet export aborted");
+                        return Some(Err(e));
+                    }
+                };
+                if page.is_empty() {
+                    self.stage = Stage::Trailer;
+                }
+                self.offset += EXPORT_PAGE_SIZE;
+                self.rows += page.len();
+                page.iter()
+                    .map(|bet| {
+                        let row = ExportRow::from_bet(bet);
+                        match self.format {
+                            ExportFormat::Csv => row.to_csv_line(),
+                            ExportFormat::Json => format!("{}\n", serde_json::to_string(&row).unwrap_or_default()),
+                        }
+                    })
+                    .collect()
+            }
+            Stage::Trailer => {
+                self.stage = Stage::Done;
+                let digest = std::mem::take(&mut self.hasher).result();
+                metrics::counter!("wallet_exports_total", "result" => "ok").increment(1);
+                tracing::info!(wallet = %self.wallet, rows = self.rows, "Wallet export completed");
+                let trailer = ExportTrailer::new(&self.wallet, self.rows, digest.as_ref(), &self.signer);
+                return Some(Ok(trailer.render(self.format)));
+            }
+        };
+
+        self.hasher.hash(chunk.as_bytes());
+        Some(Ok(chunk))
+    }
+}
+
+/// Signed export of every bet a wallet has placed, streamed page by page
+pub async fn export_wallet(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(wallet): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response> {
+    require_admin(&state.config, &headers)?;
+    parse_wallet(&wallet)?;
+
+    tracing::info!(%wallet, format = ?query.format, "Wallet export started");
+
+    let (content_type, extension) = match query.format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Json => ("application/x-ndjson", "ndjson"),
+    };
+    let disposition = format!("attachment; filename=\"wallet-{}.{}\"", wallet, extension);
+
+    let export = ExportStream {
+        repo: state.bets.clone(),
+        signer: state.receipts.clone(),
+        wallet,
+        format: query.format,
+        stage: Stage::Header,
+        offset: 0,
+        rows: 0,
+        hasher: Hasher::default(),
+    };
+    let stream = futures_util::stream::unfold(export, |mut export| async move {
+        export.next_chunk().await.map(|chunk| (chunk, export))
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BetStreamQuery {
+    /// Earliest `created_at` included; no lower bound when absent
+    pub from: Option<DateTime<Utc>>,
+    /// `created_at` upper bound, excluded; now when absent
+    pub to: Option<DateTime<Utc>>,
+}
+
+struct BetStream {
+    repo: Arc<dyn BetRepository>,
+    /// `None` once the SCAN has wrapped around
+    cursor: Option<u64>,
+    from_ms: i64,
+    to_ms: i64,
+    rows: usize,
+}
+
+impl BetStream {
+    /// NDJSON lines for the next SCAN steps that matched any bets; `None` when the scan is done
+    async fn next_chunk(&mut self) -> Option<Result<String>> {
+        while let Some(cursor) = self.cursor {
+            let (next, bets) = match self
+                .repo
+                .scan_created_between(cursor, STREAM_SCAN_COUNT, self.from_ms, self.to_ms)
+                .await
+            {
+                Ok(page) => page,
+                Err(e) => {
+                    self.cursor = None;
+                    metrics::counter!("bet_streams_total", "result" => "error").increment(1);
+                    tracing::error!(rows = self.rows, error = %e, "Bet stream aborted");
+                    return Some(Err(e));
+                }
+            };
+            self.cursor = (next != 0).then_some(next);
+            if self.cursor.is_none() {
+                metrics::counter!("bet_streams_total", "result" => "ok").increment(1);
+                tracing::info!(rows = self.rows + bets.len(), "Bet stream completed");
+            }
+            if bets.is_empty() {
+                continue;
+            }
+
+            self.rows += bets.len();
+            return Some(Ok(bets
+                .iter()
+                .map(|bet| format!("{}\n", serde_json::to_string(bet).unwrap_or_default()))
+                .collect()));
+        }
+        None
+    }
+}
+
+/// Every live bet created in `[from, to)` as newline-delimited JSON, for ETL
+///
+/// Bets are read straight from a Redis SCAN, one step per chunk, and the next
+/// step only runs once the client has taken the previous chunk, so a slow
+/// consumer slows the scan rather than growing server memory. Order is
+/// arbitrary and a bet can appear twice; archived bets are not included.
+pub async fn stream_bets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BetStreamQuery>,
+) -> Result<Response> {
+    require_admin(&state.config, &headers)?;
+
+    let from_ms = query.from.map_or(i64::MIN, |from| from.timestamp_millis());
+    let to_ms = query.to.unwrap_or_else(Utc::now).timestamp_millis();
+    if to_ms <= from_ms {
+        return Err(AppError::invalid_input("to must be after from"));
+    }
+
+    tracing::info!(from = ?query.from, to = ?query.to, "Bet stream started");
+
+    let bets = BetStream { repo: state.bets.clone(), cursor: Some(0), from_ms, to_ms, rows: 0 };
+    let stream = futures_util::stream::unfold(bets, |mut bets| async move {
+        bets.next_chunk().await.map(|chunk| (chunk, bets))
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ReceiptConfig;
+    use crate::handlers::admin::ADMIN_KEY_HEADER;
+    use crate::testing::{self, InMemoryRepositories, ADMIN_KEY};
+    use axum::http::{HeaderName, HeaderValue};
+    use axum_test::{TestRequest, TestServer};
+    use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+    fn as_admin(request: TestRequest) -> TestRequest {
+        request.add_header(HeaderName::from_static(ADMIN_KEY_HEADER), HeaderValue::from_static(ADMIN_KEY))
+    }
+
+    #[test]
+    fn test_trailer_signs_digest() {
+        let signer = ReceiptSigner::from_config(&ReceiptConfig { keypair_path: None }).unwrap();
+        let mut hasher = Hasher::default();
+        hasher.hash(CSV_HEADER.as_bytes());
+        let digest = hasher.result();
+
+        let trailer = ExportTrailer::new("wallet", 0, digest.as_ref(), &signer);
+        let signature: Signature = trailer.signature.parse().unwrap();
+        assert!(signature.verify(signer.pubkey().as_ref(), digest.as_ref()));
+        assert_eq!(trailer.sha256.len(), 64);
+        assert!(trailer.render(ExportFormat::Csv).starts_with("# wallet=wallet rows=0 "));
+    }
+
+    #[tokio::test]
+    async fn test_export_and_stream_routes() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let wallet = Pubkey::new_unique().to_string();
+        let bet = testing::pending_bet(&wallet);
+        repos.bets.insert(bet.clone());
+        repos.bets.insert(testing::pending_bet("W2"));
+
+        let export = as_admin(server.get(&format!("/api/admin/export/wallet/{}", wallet))).await.text();
+        let lines: Vec<&str> = export.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(format!("{}\n", lines[0]), CSV_HEADER);
+        assert!(lines[1].starts_with(&bet.bet_id.to_string()));
+        assert!(lines[2].starts_with(&format!("# wallet={} rows=1 ", wallet)));
+        as_admin(server.get("/api/admin/export/wallet/not-a-wallet")).await.assert_status_bad_request();
+
+        let stream = as_admin(server.get("/api/admin/bets/stream")).await.text();
+        let streamed: Vec<Bet> = stream.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(streamed.len(), 2);
+        as_admin(server.get("/api/admin/bets/stream"))
+            .add_query_param("from", Utc::now().to_rfc3339())
+            .add_query_param("to", "2020-01-01T00:00:00Z")
+            .await
+            .assert_status_bad_request();
+        server.get("/api/admin/bets/stream").await.assert_status_unauthorized();
+    }
+}
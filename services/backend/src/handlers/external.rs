@@ -1,16 +1,31 @@
 use axum::{
     extract::{Path, Query, State},
+    response::sse::{Event, KeepAlive, Sse},
     Json,
 };
-use redis::AsyncCommands;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
 use serde::Deserialize;
+use shared::api::CLAIMABLE_EVENT;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 use uuid::Uuid;
 
 use crate::{
-    domain::{PendingBetsResponse, UpdateBatchRequest},
+    batch_claims::{BatchClaim, ClaimCheck},
+    daily_report::DailyTally,
+    domain::{
+        BetError, BetResult, BetStatus, BetUpdateOutcome, BetUpdateResult, PendingBetsResponse, SettlementReport, UpdateBatchRequest,
+        UpdateBatchResponse,
+    },
     errors::{AppError, Result},
-    repository::{bet_repository::BetRepository, RedisBetRepository},
+    fairness,
+    ledger,
+    notifications::Notification,
+    processor_stats::{BatchOutcomes, UNKNOWN_PROCESSOR},
     state::AppState,
+    telemetry,
 };
 
 #[derive(Debug, Deserialize)]
@@ -26,10 +41,34 @@ pub async fn get_pending_bets(
     let limit = query.limit.unwrap_or(100).min(500);
     let processor_id = query
         .processor_id
-        .unwrap_or_else(|| "processor-unknown".to_string());
+        .unwrap_or_else(|| UNKNOWN_PROCESSOR.to_string());
 
-    let repo = RedisBetRepository::new(state.redis.clone());
-    let (batch_id, bets) = repo.claim_pending(limit, &processor_id).await?;
+    // Hand out nothing while halted so processors stop settling
+    if state.ops.is_halted().await? {
+        tracing::debug!(processor_id = %processor_id, "Kill switch engaged; no bets claimed");
+        return Ok(Json(PendingBetsResponse {
+            batch_id: Uuid::new_v4(),
+            processor_id,
+            bets: Vec::new(),
+            batch_version: 0,
+        }));
+    }
+
+    let repo = &state.bets;
+    let (batch_id, mut bets) = repo.claim_pending(limit, &processor_id).await?;
+    telemetry::record_claim(&processor_id, bets.len());
+    if let Err(e) = state.ops.record_processor_claim(&processor_id, bets.len()).await {
+        tracing::warn!(processor_id = %processor_id, "Failed to record processor claim statistics: {}", e);
+    }
+    // Only the claiming processor may report on these bets
+    let batch_version = if bets.is_empty() {
+        0
+    } else {
+        state.batches.record_claim(batch_id, &processor_id).await?
+    };
+
+    // Processors settle the outcome committed to the bet's daily seed
+    fairness::draw_outcomes(state.ops.as_ref(), &mut bets).await?;
 
     metrics::gauge!("pending_bets_count").set(bets.len() as f64);
 
@@ -37,47 +76,139 @@ pub async fn get_pending_bets(
         batch_id,
         processor_id,
         bets,
+        batch_version,
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SubscribeQuery {
+    pub processor_id: Option<String>,
+}
+
+/// Server-sent `claimable` events, one per bet as it becomes claimable, so a
+/// processor can claim right away instead of on its next poll. Events are hints:
+/// the bet may be gone by the time it is claimed, and a lagging subscriber is
+/// sent a `lagged` event and should claim anyway.
+pub async fn subscribe_claimable(
+    State(state): State<AppState>,
+    Query(query): Query<SubscribeQuery>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    if !state.config.queue.claim_signals {
+        return Err(AppError::not_found("Claim signals are disabled (QUEUE_CLAIM_SIGNALS)"));
+    }
+    let processor_id = query.processor_id.unwrap_or_else(|| UNKNOWN_PROCESSOR.to_string());
+    tracing::info!(processor_id = %processor_id, "Processor subscribed to claim signals");
+    metrics::gauge!("claim_signal_subscribers").increment(1.0);
+
+    let receiver = state.claim_signals.subscribe();
+    let events = stream::unfold((receiver, SubscriberGuard), |(mut receiver, guard)| async move {
+        let event = match receiver.recv().await {
+            Ok(event) => Event::default().event(CLAIMABLE_EVENT).json_data(event).ok()?,
+            Err(RecvError::Lagged(skipped)) => Event::default().event("lagged").data(skipped.to_string()),
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(event), (receiver, guard)))
+    });
+    let keep_alive = Duration::from_secs(state.config.queue.subscribe_keep_alive_seconds);
+    Ok(Sse::new(events).keep_alive(KeepAlive::new().interval(keep_alive)))
+}
+
+/// Counts a subscription down when its stream is dropped
+struct SubscriberGuard;
+
+impl Drop for SubscriberGuard {
+    fn drop(&mut self) {
+        metrics::gauge!("claim_signal_subscribers").decrement(1.0);
+    }
+}
+
 pub async fn update_batch(
     State(state): State<AppState>,
     Path(batch_id): Path<Uuid>,
     Json(req): Json<UpdateBatchRequest>,
-) -> Result<Json<serde_json::Value>> {
+) -> Result<Json<UpdateBatchResponse>> {
     tracing::info!("Batch {} update received: {:?}", batch_id, req.status);
 
-    // Store batch summary in Redis (best-effort)
-    {
-        let mut redis_conn = state.redis.clone();
-        let batch_key = format!("batch:{}", batch_id);
-        let _: () = redis_conn
-            .hset_multiple(
-                &batch_key,
-                &[
-                    ("status", format!("{:?}", req.status).to_lowercase()),
-                    ("solana_tx_id", req.solana_tx_id.clone().unwrap_or_default()),
-                    ("last_error_message", req.error_message.clone().unwrap_or_default()),
-                    ("updated_at_ms", chrono::Utc::now().timestamp_millis().to_string()),
-                ],
-            )
-            .await
-            .map_err(AppError::Redis)?;
-    }
+    let (Some(processor_id), Some(batch_version)) = (req.processor_id.as_deref(), req.batch_version) else {
+        return Err(AppError::invalid_input("processor_id and batch_version are required"));
+    };
+    // Rejects a report from another processor or for a version not yet reached before any bet is touched
+    let mut claim = match state.batches.check_claim(batch_id, processor_id, batch_version).await? {
+        ClaimCheck::Current(claim) => claim,
+        ClaimCheck::Stale(claim) => return replay_update(&state, batch_id, claim, &req.bet_results).await.map(Json),
+    };
+
+    // Store batch summary
+    state
+        .batches
+        .save_summary(batch_id, &req.status, req.solana_tx_id.as_deref(), req.error_message.as_deref())
+        .await?;
 
     // Update individual bet statuses
-    let repo = RedisBetRepository::new(state.redis.clone());
+    let repo = &state.bets;
     let mut updated_count = 0;
     let mut error_count = 0;
+    // Bets that could not be read or written; retrying the update may still apply them
+    let mut failed_writes = 0;
+    let mut tally = DailyTally::default();
+    let mut outcomes = BatchOutcomes::default();
+    if let Some(fee_lamports) = req.fee_lamports {
+        tally.add_fees(fee_lamports);
+    }
 
+    let mut results = Vec::with_capacity(req.bet_results.len());
     for bet_result in req.bet_results {
         let bet_id = bet_result.bet_id;
         let status = bet_result.status.clone();
+
+        // Diff against the stored bet so a resent result can't overwrite a settled one
+        let current = match repo.find_by_id(bet_id).await {
+            Ok(Some(bet)) => bet,
+            Ok(None) => {
+                error_count += 1;
+                record_result(&mut results, bet_id, BetUpdateOutcome::Conflict, None, Some("Unknown bet".to_string()));
+                continue;
+            }
+            Err(e) => {
+                error_count += 1;
+                failed_writes += 1;
+                tracing::error!("Failed to load bet {}: {}", bet_id, e);
+                record_result(&mut results, bet_id, BetUpdateOutcome::Error, None, Some(e.to_string()));
+                continue;
+            }
+        };
+        let (outcome, reason) = current.diff_result(batch_id, &bet_result);
+        if outcome != BetUpdateOutcome::Applied {
+            if outcome == BetUpdateOutcome::Conflict {
+                error_count += 1;
+                tracing::warn!(%bet_id, current = ?current.status, reported = ?status, reason = ?reason, "Bet result not applied");
+            } else {
+                tracing::debug!(%bet_id, status = ?current.status, "Bet already finished; result skipped");
+            }
+            record_result(&mut results, bet_id, outcome, Some(current.status), reason);
+            continue;
+        }
+
+        let failure = failure_entry(&bet_result, processor_id, Utc::now());
+
+        // The batch transaction spent the stake unless the processor says otherwise
+        let mut settlement = bet_result.settlement.unwrap_or_default();
+        settlement.spend_tx = settlement.spend_tx.or_else(|| bet_result.solana_tx_id.clone());
         match repo
             .update_status(bet_id, bet_result.status, bet_result.solana_tx_id)
             .await
         {
             Ok(_) => {
+                if settlement != SettlementReport::default() {
+                    if let Err(e) = state.batches.record_settlement(bet_id, settlement).await {
+                        tracing::warn!(%bet_id, error = %e, "Failed to record settlement report");
+                    }
+                }
+                if let Some(failure) = &failure {
+                    if let Err(e) = repo.record_error(bet_id, failure).await {
+                        tracing::warn!(%bet_id, error = %e, "Failed to record bet error history");
+                    }
+                }
                 // Optional result fields (POC: store for UI/status queries)
                 let _ = repo
                     .update_bet_fields(
@@ -85,14 +216,48 @@ pub async fn update_batch(
                         bet_result.won,
                         bet_result.payout_amount,
                         bet_result.error_message,
+                        bet_result.error_code,
+                        bet_result.server_seed_hash,
                     )
                     .await;
                 updated_count += 1;
                 tracing::debug!("Updated bet {} to {:?}", bet_id, status);
+                record_result(&mut results, bet_id, BetUpdateOutcome::Applied, Some(status.clone()), None);
+
+                match status {
+                    BetStatus::Completed => {
+                        outcomes.add_completed();
+                        if let Ok(Some(bet)) = repo.find_by_id(bet_id).await {
+                            tally.add_completed(&bet);
+                            state.ops.post_ledger_or_warn(&ledger::settlement(&bet)).await;
+                            if state.config.notifications.enabled {
+                                let big_win_amount = state.config.notifications.big_win_amount;
+                                state.ops.publish_notifications(&Notification::bet_completed(&bet, big_win_amount)).await;
+                            }
+                            telemetry::record_bet_finished(bet.created_at, &status);
+                            state.ops.record_slo(&state.config.slo, bet.created_at, &status).await;
+                        }
+                    }
+                    BetStatus::FailedManualReview => {
+                        tally.add_failed();
+                        outcomes.add_failed();
+                        if let Ok(Some(bet)) = repo.find_by_id(bet_id).await {
+                            telemetry::record_bet_finished(bet.created_at, &status);
+                            state.ops.record_slo(&state.config.slo, bet.created_at, &status).await;
+                        }
+                    }
+                    BetStatus::FailedRetryable => {
+                        tally.add_failed();
+                        outcomes.add_failed();
+                    }
+                    _ => {}
+                }
             }
             Err(e) => {
                 error_count += 1;
+                failed_writes += 1;
                 tracing::error!("Failed to update bet {}: {}", bet_id, e);
+                record_result(&mut results, bet_id, BetUpdateOutcome::Error, Some(current.status), Some(e.to_string()));
             }
         }
     }
@@ -104,13 +269,323 @@ pub async fn update_batch(
         error_count
     );
 
+    // Applied bets are skipped on a retry, so a partial update keeps its version and is resent as is
+    if failed_writes == 0 {
+        claim = state.batches.advance_claim(batch_id, processor_id, batch_version).await?;
+    } else {
+        tracing::warn!(%batch_id, failed_writes, batch_version, "Bet writes failed; batch version kept for a retry");
+    }
+
+    // Reporting is best-effort; the bet updates above already succeeded
+    if let Err(e) = state.ops.record_daily_tally(&tally, chrono::Utc::now().date_naive()).await {
+        tracing::warn!("Failed to record daily settlement counters: {}", e);
+    }
+    if let Err(e) = state.ops.record_batch_outcomes(&outcomes, &claim).await {
+        tracing::warn!("Failed to record processor statistics for batch {}: {}", batch_id, e);
+    }
+
     metrics::counter!("batches_processed_total").increment(1);
     metrics::counter!("bets_updated_total").increment(updated_count as u64);
 
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "batch_id": batch_id,
-        "updated_count": updated_count,
-        "error_count": error_count
-    })))
+    Ok(Json(UpdateBatchResponse {
+        success: true,
+        batch_id,
+        updated_count,
+        error_count,
+        batch_version: claim.version,
+        results,
+    }))
+}
+
+/// Answer a resend of an update the batch's version already moved past
+///
+/// Nothing is written: the resend succeeds when every known bet already holds
+/// its result, and any other result means it isn't the update that was applied.
+async fn replay_update(
+    state: &AppState,
+    batch_id: Uuid,
+    claim: BatchClaim,
+    bet_results: &[BetResult],
+) -> Result<UpdateBatchResponse> {
+    let mut error_count = 0;
+    let mut results = Vec::with_capacity(bet_results.len());
+    for bet_result in bet_results {
+        let bet_id = bet_result.bet_id;
+        match state.bets.find_by_id(bet_id).await {
+            Ok(Some(bet)) if bet.reflects_result(batch_id, bet_result) => {
+                record_result(&mut results, bet_id, BetUpdateOutcome::SkippedTerminal, Some(bet.status), None);
+            }
+            Ok(Some(bet)) => {
+                return Err(AppError::batch_conflict(format!(
+                    "Batch {} is at version {}; bet {} is {:?}, not the resent {:?}",
+                    batch_id, claim.version, bet_id, bet.status, bet_result.status
+                )));
+            }
+            Ok(None) => {
+                error_count += 1;
+                record_result(&mut results, bet_id, BetUpdateOutcome::Conflict, None, Some("Unknown bet".to_string()));
+            }
+            Err(e) => {
+                error_count += 1;
+                tracing::error!("Failed to load bet {}: {}", bet_id, e);
+                record_result(&mut results, bet_id, BetUpdateOutcome::Error, None, Some(e.to_string()));
+            }
+        }
+    }
+
+    tracing::info!(%batch_id, batch_version = claim.version, "Resent batch update already applied");
+    Ok(UpdateBatchResponse {
+        success: true,
+        batch_id,
+        updated_count: 0,
+        error_count,
+        batch_version: claim.version,
+        results,
+    })
+}
+
+/// Error history entry for a failed result; `None` when the result isn't a failure
+fn failure_entry(result: &BetResult, processor_id: &str, at: DateTime<Utc>) -> Option<BetError> {
+    if !matches!(result.status, BetStatus::FailedRetryable | BetStatus::FailedManualReview) {
+        return None;
+    }
+    Some(BetError {
+        at,
+        code: result.error_code.clone(),
+        message: result.error_message.clone().unwrap_or_else(|| "No error message reported".to_string()),
+        processor_id: Some(processor_id.to_string()),
+    })
+}
+
+fn record_result(
+    results: &mut Vec<BetUpdateResult>,
+    bet_id: Uuid,
+    outcome: BetUpdateOutcome,
+    status: Option<BetStatus>,
+    reason: Option<String>,
+) {
+    let label = match outcome {
+        BetUpdateOutcome::Applied => "applied",
+        BetUpdateOutcome::SkippedTerminal => "skipped_terminal",
+        BetUpdateOutcome::Conflict => "conflict",
+        BetUpdateOutcome::Error => "error",
+    };
+    metrics::counter!("batch_bet_results_total", "outcome" => label).increment(1);
+    results.push(BetUpdateResult { bet_id, outcome, status, reason });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::killswitch::KillSwitchState;
+    use crate::notifications::NotificationEvent;
+    use crate::repository::{BetRepository, OpsRepository};
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::{TestServer, TestServerConfig};
+    use serde_json::json;
+
+    fn server(repos: &InMemoryRepositories, config: crate::config::Config) -> TestServer {
+        TestServer::new(crate::build_router(repos.app_state(config))).unwrap()
+    }
+
+    async fn claim(server: &TestServer, processor_id: &str) -> PendingBetsResponse {
+        server.get("/api/external/bets/pending").add_query_param("processor_id", processor_id).await.json()
+    }
+
+    fn completed(bet_id: Uuid, version: u64) -> serde_json::Value {
+        json!({
+            "status": "confirmed",
+            "solana_tx_id": "batch-tx",
+            "fee_lamports": 5000,
+            "processor_id": "processor-1",
+            "batch_version": version,
+            "error_message": null,
+            "bet_results": [{
+                "bet_id": bet_id,
+                "status": "completed",
+                "solana_tx_id": "batch-tx",
+                "won": true,
+                "payout_amount": 200_000_000,
+            }],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_claim_draws_committed_outcomes_and_counts_the_claim() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = server(&repos, testing::config());
+        let mut bet = testing::pending_bet("W1");
+        bet.server_seed_hash = Some(repos.ops.fairness_commitment(Utc::now().date_naive()).await.unwrap());
+        bet.client_seed = Some("lucky".to_string());
+        repos.bets.insert(bet.clone());
+
+        let claimed = claim(&server, "processor-1").await;
+        assert_eq!(claimed.batch_version, crate::batch_claims::INITIAL_VERSION);
+        assert_eq!(claimed.bets.len(), 1);
+        assert!(claimed.bets[0].won.is_some() && claimed.bets[0].payout_amount.is_some());
+
+        let stats = repos.ops.processor_stats(10).await.unwrap();
+        assert_eq!((stats[0].processor_id.as_str(), stats[0].claim_requests, stats[0].bets_claimed), ("processor-1", 1, 1));
+
+        // Nothing is handed out while halted
+        repos.bets.insert(testing::pending_bet("W2"));
+        let state = KillSwitchState {
+            engaged_by: "ops".to_string(),
+            reason: "incident".to_string(),
+            engaged_at: Utc::now(),
+            on_chain_signature: None,
+        };
+        repos.ops.engage_kill_switch(&state).await.unwrap();
+        assert!(claim(&server, "processor-1").await.bets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_batch_settles_and_records_the_outcome() {
+        let mut config = testing::config();
+        config.notifications.enabled = true;
+        let repos = InMemoryRepositories::new(&config);
+        let server = server(&repos, config.clone());
+        let bet = testing::pending_bet("W1");
+        repos.bets.insert(bet.clone());
+        let claimed = claim(&server, "processor-1").await;
+        let path = format!("/api/external/batches/{}", claimed.batch_id);
+
+        let updated: UpdateBatchResponse = server.post(&path).json(&completed(bet.bet_id, claimed.batch_version)).await.json();
+        assert_eq!((updated.updated_count, updated.error_count), (1, 0));
+        assert_eq!(updated.batch_version, claimed.batch_version + 1);
+        assert_eq!(updated.results[0].outcome, BetUpdateOutcome::Applied);
+
+        let stored = repos.bets.find_by_id(bet.bet_id).await.unwrap().unwrap();
+        assert_eq!((stored.status, stored.won), (BetStatus::Completed, Some(true)));
+        let stats = repos.ops.processor_stats(10).await.unwrap();
+        assert_eq!((stats[0].completed, stats[0].failed), (1, 0));
+        let report = repos.ops.daily_report(repos.bets.as_ref(), Utc::now().date_naive()).await.unwrap();
+        assert_eq!((report.succeeded, report.fees_lamports), (1, 5000));
+        assert_eq!(repos.ops.slo_summary(&config.slo).await.unwrap().period.total, 1);
+        assert!(repos.ops.published().iter().any(|n| n.event == NotificationEvent::BetCompleted));
+        assert_eq!(repos.ops.balance(&ledger::wallet_reserved("W1", "SOL")), 0);
+        assert_eq!(repos.ops.balance(&ledger::wallet_vault("W1", "SOL")), 200_000_000 - bet.stake_amount);
+
+        // Another processor can't report on the batch, and a missing claim is refused
+        let mut foreign = completed(bet.bet_id, updated.batch_version);
+        foreign["processor_id"] = json!("processor-2");
+        server.post(&path).json(&foreign).await.assert_status(axum::http::StatusCode::CONFLICT);
+        let mut anonymous = completed(bet.bet_id, updated.batch_version);
+        anonymous["processor_id"] = json!(null);
+        server.post(&path).json(&anonymous).await.assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_update_failing_partway_is_retried_at_the_same_version() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = server(&repos, testing::config());
+        let (first, second) = (testing::pending_bet("W1"), testing::pending_bet("W2"));
+        repos.bets.insert(first.clone());
+        repos.bets.insert(second.clone());
+        let claimed = claim(&server, "processor-1").await;
+        let path = format!("/api/external/batches/{}", claimed.batch_id);
+        let mut update = completed(first.bet_id, claimed.batch_version);
+        let second_result = json!({ "bet_id": second.bet_id, "status": "completed", "won": false, "payout_amount": 0 });
+        update["bet_results"].as_array_mut().unwrap().push(second_result);
+
+        repos.bets.fail_status_updates(second.bet_id, true);
+        let partial: UpdateBatchResponse = server.post(&path).json(&update).await.json();
+        let outcomes: Vec<_> = partial.results.iter().map(|r| r.outcome).collect();
+        assert_eq!(outcomes, vec![BetUpdateOutcome::Applied, BetUpdateOutcome::Error]);
+        assert_eq!(partial.batch_version, claimed.batch_version);
+        assert_eq!(repos.batches.version(claimed.batch_id), Some(claimed.batch_version));
+
+        // The same update again: the applied bet is skipped and the failed one lands
+        repos.bets.fail_status_updates(second.bet_id, false);
+        let retried: UpdateBatchResponse = server.post(&path).json(&update).await.json();
+        let outcomes: Vec<_> = retried.results.iter().map(|r| r.outcome).collect();
+        assert_eq!(outcomes, vec![BetUpdateOutcome::SkippedTerminal, BetUpdateOutcome::Applied]);
+        assert_eq!(retried.batch_version, claimed.batch_version + 1);
+        let stored = repos.bets.find_by_id(second.bet_id).await.unwrap().unwrap();
+        assert_eq!(stored.status, BetStatus::Completed);
+        // The first bet's settlement was booked once
+        assert_eq!(repos.ops.balance(&ledger::wallet_vault("W1", "SOL")), 200_000_000 - first.stake_amount);
+        let stats = repos.ops.processor_stats(10).await.unwrap();
+        assert_eq!(stats[0].completed, 2);
+    }
+
+    #[tokio::test]
+    async fn test_resending_an_applied_update_succeeds_only_if_identical() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = server(&repos, testing::config());
+        let bet = testing::pending_bet("W1");
+        repos.bets.insert(bet.clone());
+        let claimed = claim(&server, "processor-1").await;
+        let path = format!("/api/external/batches/{}", claimed.batch_id);
+        let update = completed(bet.bet_id, claimed.batch_version);
+
+        let first: UpdateBatchResponse = server.post(&path).json(&update).await.json();
+        assert!(first.success);
+        assert_eq!(first.results[0].outcome, BetUpdateOutcome::Applied);
+
+        // The response was lost, so the processor sends the same update at the old version
+        let resent: UpdateBatchResponse = server.post(&path).json(&update).await.json();
+        assert!(resent.success);
+        assert_eq!((resent.updated_count, resent.error_count), (0, 0));
+        assert_eq!(resent.batch_version, first.batch_version);
+        assert_eq!(resent.results[0].outcome, BetUpdateOutcome::SkippedTerminal);
+        // Nothing was booked twice
+        assert_eq!(repos.ops.balance(&ledger::wallet_vault("W1", "SOL")), 200_000_000 - bet.stake_amount);
+        assert_eq!(repos.ops.processor_stats(10).await.unwrap()[0].completed, 1);
+
+        // A different result at the old version isn't the update that was applied
+        let mut conflicting = update.clone();
+        conflicting["bet_results"][0]["payout_amount"] = json!(300_000_000);
+        server.post(&path).json(&conflicting).await.assert_status(axum::http::StatusCode::CONFLICT);
+        let mut ahead = update;
+        ahead["batch_version"] = json!(first.batch_version + 1);
+        server.post(&path).json(&ahead).await.assert_status(axum::http::StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_needs_claim_signals() {
+        let mut config = testing::config();
+        config.queue.claim_signals = false;
+        let repos = InMemoryRepositories::new(&config);
+        server(&repos, config.clone()).get("/api/external/bets/subscribe").await.assert_status_not_found();
+
+        // The stream stays open, so read only the response head over a real connection
+        config.queue.claim_signals = true;
+        let state = repos.app_state(config);
+        let hub = state.claim_signals.clone();
+        let server = TestServer::new_with_config(crate::build_router(state), TestServerConfig::builder().http_transport().build()).unwrap();
+        let url = server.server_address().unwrap().join("/api/external/bets/subscribe").unwrap();
+        let response = reqwest::get(url).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        assert_eq!(hub.subscriber_count(), 1);
+    }
+
+    #[test]
+    fn test_failure_entry() {
+        let at = Utc::now();
+        let mut result = BetResult {
+            bet_id: Uuid::new_v4(),
+            status: BetStatus::FailedRetryable,
+            solana_tx_id: None,
+            error_message: Some("RPC unavailable".to_string()),
+            error_code: Some("NETWORK_RPC_UNAVAILABLE".to_string()),
+            won: None,
+            payout_amount: None,
+            server_seed_hash: None,
+            settlement: None,
+        };
+        assert_eq!(
+            failure_entry(&result, "processor-1", at),
+            Some(BetError {
+                at,
+                code: Some("NETWORK_RPC_UNAVAILABLE".to_string()),
+                message: "RPC unavailable".to_string(),
+                processor_id: Some("processor-1".to_string()),
+            })
+        );
+
+        result.status = BetStatus::Completed;
+        assert_eq!(failure_entry(&result, "processor-1", at), None);
+    }
 }
@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::Result,
+    fairness::DailySeed,
+    state::AppState,
+};
+
+/// Days of history returned by default, and at most
+const DEFAULT_SEED_LIMIT: usize = 30;
+const MAX_SEED_LIMIT: usize = 365;
+
+#[derive(Debug, Deserialize)]
+pub struct SeedsQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeedsResponse {
+    /// Commitment bets placed today are drawn from
+    pub current_server_seed_hash: String,
+    /// Newest first; `server_seed` is revealed once the day has rotated
+    pub seeds: Vec<DailySeed>,
+}
+
+/// Published daily seed commitments and the seeds of past days
+pub async fn list_seeds(
+    State(state): State<AppState>,
+    Query(query): Query<SeedsQuery>,
+) -> Result<Json<SeedsResponse>> {
+    let limit = query.limit.unwrap_or(DEFAULT_SEED_LIMIT).clamp(1, MAX_SEED_LIMIT);
+    let today = chrono::Utc::now().date_naive();
+
+    let current_server_seed_hash = state.ops.fairness_commitment(today).await?;
+    let seeds = state.ops.fairness_seeds(today, limit).await?;
+
+    Ok(Json(SeedsResponse {
+        current_server_seed_hash,
+        seeds,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repository::OpsRepository;
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use chrono::Duration;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_reveals_seeds_once_the_day_is_over() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let today = chrono::Utc::now().date_naive();
+        let yesterday_hash = repos.ops.fairness_commitment(today - Duration::days(1)).await.unwrap();
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+
+        let listed: Value = server.get("/api/fairness/seeds").await.json();
+        let current = listed["current_server_seed_hash"].as_str().unwrap();
+        assert_eq!(current, repos.ops.fairness_commitment(today).await.unwrap());
+        let seeds = listed["seeds"].as_array().unwrap();
+        assert_eq!(seeds.len(), 2);
+        assert_eq!((seeds[0]["server_seed_hash"].as_str(), seeds[0]["server_seed"].as_str()), (Some(current), None));
+        assert_eq!(seeds[1]["server_seed_hash"], yesterday_hash.as_str());
+        assert!(seeds[1]["server_seed"].is_string());
+
+        let limited: Value = server.get("/api/fairness/seeds").add_query_param("limit", 1).await.json();
+        assert_eq!(limited["seeds"].as_array().unwrap().len(), 1);
+    }
+}
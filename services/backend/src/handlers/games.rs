@@ -0,0 +1,25 @@
+use axum::{extract::State, Json};
+
+use crate::{domain::GamesResponse, state::AppState};
+
+/// Enabled games, their choices, payouts and stake ranges
+pub async fn list_games(State(state): State<AppState>) -> Json<GamesResponse> {
+    Json(GamesResponse { games: state.games.enabled(&state.config.betting) })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_lists_enabled_games() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+
+        let games: Value = server.get("/api/games").await.json();
+        let names: Vec<&str> = games["games"].as_array().unwrap().iter().filter_map(|game| game["game_type"].as_str()).collect();
+        assert!(names.contains(&"coinflip"));
+    }
+}
@@ -1,5 +1,4 @@
 use axum::{extract::State, Json};
-use redis::AsyncCommands;
 use serde_json::{json, Value};
 
 use crate::state::AppState;
@@ -12,12 +11,7 @@ pub async fn health_check() -> Json<Value> {
 }
 
 pub async fn detailed_health(State(state): State<AppState>) -> Json<Value> {
-    let redis_healthy = {
-        let mut conn = state.redis.clone();
-        conn.get::<_, Option<String>>("_health_check")
-            .await
-            .is_ok()
-    };
+    let redis_healthy = state.ops.ping().await.is_ok();
 
     Json(json!({
         "status": if redis_healthy { "healthy" } else { "degraded" },
@@ -27,3 +21,21 @@ pub async fn detailed_health(State(state): State<AppState>) -> Json<Value> {
         }
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_health_routes() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+
+        let health: Value = server.get("/health").await.json();
+        assert_eq!(health["status"], "healthy");
+        let detailed: Value = server.get("/health/detailed").await.json();
+        assert_eq!((detailed["status"].as_str(), detailed["components"]["redis"].as_str()), (Some("healthy"), Some("healthy")));
+    }
+}
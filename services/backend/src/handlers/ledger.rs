@@ -0,0 +1,100 @@
+use axum::{extract::State, http::HeaderMap, Json};
+use uuid::Uuid;
+
+use crate::{
+    domain::LedgerAdjustmentRequest,
+    errors::{AppError, Result},
+    extractors::ValidatedJson,
+    handlers::admin::require_admin,
+    ledger::{self, InvariantReport, Posting, DEFAULT_CASINO},
+    state::AppState,
+};
+
+/// Latest comparison of the ledger with on-chain vault balances
+pub async fn get_last_check(State(state): State<AppState>, headers: HeaderMap) -> Result<Json<InvariantReport>> {
+    require_admin(&state.config, &headers)?;
+
+    state
+        .ops
+        .last_ledger_check()
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::not_found("The ledger has not been checked yet"))
+}
+
+/// Book an operator's casino vault funding or withdrawal
+pub async fn adjust(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<LedgerAdjustmentRequest>,
+) -> Result<Json<Posting>> {
+    require_admin(&state.config, &headers)?;
+
+    if req.operator.trim().is_empty() || req.reason.trim().is_empty() {
+        return Err(AppError::invalid_input("operator and reason are required"));
+    }
+    if req.amount == 0 {
+        return Err(AppError::invalid_input("amount must not be zero"));
+    }
+
+    let casino = req.casino_id.as_deref().unwrap_or(DEFAULT_CASINO);
+    let token = req.stake_token.as_deref().unwrap_or("SOL");
+    let posting = ledger::adjustment(Uuid::new_v4(), casino, token, req.amount);
+    state.ops.post_ledger(std::slice::from_ref(&posting)).await?;
+
+    tracing::warn!(
+        reference = %posting.reference,
+        casino,
+        token,
+        amount = req.amount,
+        operator = %req.operator,
+        reason = %req.reason,
+        "Ledger adjustment booked by operator"
+    );
+
+    Ok(Json(posting))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::admin::ADMIN_KEY_HEADER;
+    use crate::testing::{self, InMemoryRepositories, ADMIN_KEY};
+    use axum::http::{HeaderName, HeaderValue};
+    use axum_test::{TestRequest, TestServer};
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn as_admin(request: TestRequest) -> TestRequest {
+        request.add_header(HeaderName::from_static(ADMIN_KEY_HEADER), HeaderValue::from_static(ADMIN_KEY))
+    }
+
+    #[tokio::test]
+    async fn test_last_check_and_adjustments() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+
+        as_admin(server.get("/api/admin/ledger/check")).await.assert_status_not_found();
+        repos.ops.set_last_ledger_check(InvariantReport {
+            checked_at: Utc::now(),
+            casino: None,
+            shortfalls: Vec::new(),
+            violations: 0,
+        });
+        let report: serde_json::Value = as_admin(server.get("/api/admin/ledger/check")).await.json();
+        assert_eq!(report["violations"], 0);
+
+        let adjust = |amount: i64| {
+            as_admin(server.post("/api/admin/ledger/adjustments"))
+                .json(&json!({ "amount": amount, "operator": "ops", "reason": "funding" }))
+        };
+        adjust(0).await.assert_status_bad_request();
+        adjust(5_000_000_000).await.assert_status_ok();
+        assert_eq!(repos.ops.balance(&ledger::casino_vault(DEFAULT_CASINO, "SOL")), 5_000_000_000);
+        server
+            .post("/api/admin/ledger/adjustments")
+            .json(&json!({ "amount": 1, "operator": "ops", "reason": "funding" }))
+            .await
+            .assert_status_unauthorized();
+    }
+}
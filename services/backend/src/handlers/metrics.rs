@@ -1,6 +1,25 @@
-use axum::response::IntoResponse;
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
 
-pub async fn metrics_handler() -> impl IntoResponse {
-    // Metrics are exposed on separate port via PrometheusBuilder
-    "Metrics available on metrics port"
+use crate::state::AppState;
+
+/// Same registry as the metrics port; there is only one recorder per process
+pub async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    match &state.metrics {
+        Some(handle) => (StatusCode::OK, handle.render()),
+        None => (StatusCode::NOT_FOUND, "Metrics recorder not installed".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_metrics_need_a_recorder() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+
+        server.get("/metrics").await.assert_status_not_found();
+    }
 }
@@ -1,4 +1,21 @@
+pub mod admin;
+pub mod allowances;
+pub mod auth;
 pub mod health;
 pub mod bets;
+pub mod disputes;
+pub mod error_catalog;
+pub mod export;
 pub mod external;
+pub mod fairness;
+pub mod games;
+pub mod ledger;
 pub mod metrics;
+pub mod notifications;
+pub mod receipts;
+pub mod refunds;
+pub mod reports;
+pub mod sessions;
+pub mod tokens;
+pub mod vaults;
+pub mod withdrawals;
@@ -0,0 +1,162 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    response::Response,
+    Json,
+};
+use std::sync::Arc;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::{
+    errors::{AppError, Result},
+    extractors::ValidatedJson,
+    handlers::withdrawals::parse_wallet,
+    notifications::{Notification, NotificationPreferences},
+    state::AppState,
+};
+
+/// A wallet's notification preferences; defaults when it never set any
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+) -> Result<Json<NotificationPreferences>> {
+    parse_wallet(&wallet)?;
+    let preferences = state.ops.notification_preferences(&wallet).await?;
+    Ok(Json(preferences))
+}
+
+/// Replace a wallet's notification preferences
+pub async fn set_preferences(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    ValidatedJson(preferences): ValidatedJson<NotificationPreferences>,
+) -> Result<Json<NotificationPreferences>> {
+    parse_wallet(&wallet)?;
+    preferences.validate()?;
+    state.ops.save_notification_preferences(&wallet, &preferences).await?;
+    tracing::info!(
+        %wallet,
+        events = ?preferences.events,
+        webhook = preferences.webhook_url.is_some(),
+        websocket = preferences.websocket,
+        "Notification preferences updated"
+    );
+    Ok(Json(preferences))
+}
+
+/// Push a wallet's notifications over a websocket; needs `websocket` in its preferences
+pub async fn subscribe(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    parse_wallet(&wallet)?;
+    let preferences = state.ops.notification_preferences(&wallet).await?;
+    if !preferences.websocket {
+        return Err(AppError::invalid_input("WebSocket push is not enabled in this wallet's notification preferences"));
+    }
+    let receiver = state.notifications.subscribe();
+    Ok(ws.on_upgrade(move |socket| push(socket, wallet, preferences, receiver)))
+}
+
+async fn push(
+    mut socket: WebSocket,
+    wallet: String,
+    preferences: NotificationPreferences,
+    mut receiver: Receiver<Arc<Notification>>,
+) {
+    metrics::gauge!("notification_websockets_open").increment(1.0);
+    loop {
+        tokio::select! {
+            received = receiver.recv() => match received {
+                Ok(notification) => {
+                    if notification.user_wallet != wallet || !preferences.wants(notification.event) {
+                        continue;
+                    }
+                    let Ok(text) = serde_json::to_string(notification.as_ref()) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                    metrics::counter!(
+                        "notifications_delivered_total",
+                        "channel" => "websocket",
+                        "event" => notification.event.as_str(),
+                        "outcome" => "ok"
+                    )
+                    .increment(1);
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(%wallet, skipped, "Notification websocket fell behind; notifications dropped");
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum; anything else from the client is ignored
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    metrics::gauge!("notification_websockets_open").decrement(1.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::NotificationEvent;
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::{TestServer, TestServerConfig};
+    use serde_json::json;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[tokio::test]
+    async fn test_preferences_round_trip() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let path = format!("/api/users/{}/notifications", Pubkey::new_unique());
+
+        let defaults: NotificationPreferences = server.get(&path).await.json();
+        assert_eq!(defaults, NotificationPreferences::default());
+
+        let wanted = json!({ "events": ["big_win"], "webhook_url": "https://example.com/hook" });
+        server.post(&path).json(&wanted).await.assert_status_ok();
+        let saved: NotificationPreferences = server.get(&path).await.json();
+        assert_eq!((saved.events, saved.websocket), (vec![NotificationEvent::BigWin], false));
+
+        server.post(&path).json(&json!({ "webhook_url": "ftp://example.com" })).await.assert_status_bad_request();
+        server.get("/api/users/not-a-wallet/notifications").await.assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_websocket_needs_opting_in() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new_with_config(
+            crate::build_router(repos.app_state(testing::config())),
+            TestServerConfig::builder().http_transport().build(),
+        )
+        .unwrap();
+        let wallet = Pubkey::new_unique().to_string();
+        let upgrade = || async {
+            let url = server.server_address().unwrap().join(&format!("/api/users/{}/notifications/ws", wallet)).unwrap();
+            reqwest::Client::new()
+                .get(url)
+                .header("connection", "upgrade")
+                .header("upgrade", "websocket")
+                .header("sec-websocket-version", "13")
+                .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+                .send()
+                .await
+                .unwrap()
+                .status()
+        };
+
+        assert_eq!(upgrade().await, reqwest::StatusCode::BAD_REQUEST);
+        let path = format!("/api/users/{}/notifications", wallet);
+        server.post(&path).json(&json!({ "websocket": true })).await.assert_status_ok();
+        assert_eq!(upgrade().await, reqwest::StatusCode::SWITCHING_PROTOCOLS);
+    }
+}
@@ -0,0 +1,88 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, Result},
+    receipts::ReceiptPayload,
+    state::AppState,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyReceiptQuery {
+    /// Base58 signature from the create-bet receipt
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyReceiptResponse {
+    pub valid: bool,
+    /// Terms as currently stored for the bet
+    pub payload: ReceiptPayload,
+    pub signer: String,
+}
+
+/// Check a receipt signature against the stored bet terms
+pub async fn verify_receipt(
+    State(state): State<AppState>,
+    Path(bet_id): Path<Uuid>,
+    Query(query): Query<VerifyReceiptQuery>,
+) -> Result<Json<VerifyReceiptResponse>> {
+    let span = tracing::info_span!("verify_receipt", %bet_id);
+    let _enter = span.enter();
+
+    let repo = &state.bets;
+    let bet = repo
+        .find_by_id(bet_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Bet {} not found", bet_id)))?;
+
+    let payload = ReceiptPayload::from_bet(&bet);
+    let valid = state.receipts.verify(&payload, &query.signature);
+
+    tracing::debug!(valid, "Receipt verified");
+    metrics::counter!("receipt_verifications_total", "valid" => valid.to_string()).increment(1);
+
+    Ok(Json(VerifyReceiptResponse {
+        valid,
+        payload,
+        signer: state.receipts.pubkey().to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use serde_json::Value;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_verifies_receipts_against_stored_terms() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let state = repos.app_state(testing::config());
+        let mut bet = testing::pending_bet("W1");
+        let receipt = state.receipts.sign(&bet);
+        repos.bets.insert(bet.clone());
+        let server = TestServer::new(crate::build_router(state)).unwrap();
+        let path = format!("/api/receipts/{}/verify", bet.bet_id);
+
+        let verified: Value = server.get(&path).add_query_param("signature", &receipt.signature).await.json();
+        assert_eq!((verified["valid"].as_bool(), verified["signer"].as_str()), (Some(true), Some(receipt.signer.as_str())));
+
+        // Terms changed after the receipt was signed
+        bet.stake_amount *= 2;
+        repos.bets.insert(bet.clone());
+        let verified: Value = server.get(&path).add_query_param("signature", &receipt.signature).await.json();
+        assert_eq!(verified["valid"], false);
+
+        server
+            .get(&format!("/api/receipts/{}/verify", Uuid::new_v4()))
+            .add_query_param("signature", &receipt.signature)
+            .await
+            .assert_status_not_found();
+    }
+}
@@ -0,0 +1,245 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::{AuditEntry, BetStatus, PendingRefundsResponse, Refund, RefundResult, RefundStatus, VoidBetRequest},
+    errors::{AppError, Result},
+    extractors::ValidatedJson,
+    handlers::admin::require_admin,
+    ledger,
+    notifications::Notification,
+    refunds::{self, can_refund},
+    state::AppState,
+};
+
+#[derive(Debug, Serialize)]
+pub struct VoidBetResponse {
+    pub refund: Refund,
+    pub audit: AuditEntry,
+}
+
+/// Void a bet that settled as a loss and queue its stake refund
+pub async fn void_bet(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(bet_id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<VoidBetRequest>,
+) -> Result<Json<VoidBetResponse>> {
+    require_admin(&state.config, &headers)?;
+
+    let span = tracing::info_span!("admin_void_bet", %bet_id, operator = %req.operator);
+    let _enter = span.enter();
+
+    if req.operator.trim().is_empty() || req.reason.trim().is_empty() {
+        return Err(AppError::invalid_input("operator and reason are required"));
+    }
+
+    let repo = &state.bets;
+    let bet = repo
+        .find_by_id(bet_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Bet {} not found", bet_id)))?;
+
+    if !can_refund(&bet) {
+        return Err(AppError::invalid_status(format!(
+            "Bet {} has no settled losing stake to refund (status {:?}, won {:?}); use manual settlement for unsettled bets",
+            bet_id, bet.status, bet.won
+        )));
+    }
+
+    let refund = refunds::for_bet(&bet, &req.operator, &req.reason, Utc::now());
+    let audit = AuditEntry {
+        bet_id,
+        action: "void".to_string(),
+        operator: req.operator.clone(),
+        reason: req.reason.clone(),
+        previous_status: bet.status.clone(),
+        details: serde_json::json!({ "refund_amount": refund.amount }),
+        created_at: refund.requested_at,
+    };
+
+    state.ops.request_refund(&refund).await?;
+    state.audit.append_audit(&audit).await?;
+
+    tracing::warn!(refund_amount = refund.amount, reason = %req.reason, "Bet voided by operator");
+    metrics::counter!("bet_voids_total").increment(1);
+
+    Ok(Json(VoidBetResponse { refund, audit }))
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefundQueue {
+    #[default]
+    Pending,
+    Done,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListRefundsQuery {
+    #[serde(default)]
+    pub status: RefundQueue,
+    pub limit: Option<isize>,
+}
+
+/// Admin view of queued refunds (oldest first), or finished ones
+pub async fn list_refunds(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListRefundsQuery>,
+) -> Result<Json<Vec<Refund>>> {
+    require_admin(&state.config, &headers)?;
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let pending = matches!(query.status, RefundQueue::Pending);
+    Ok(Json(state.ops.refunds(pending, limit).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PendingRefundsQuery {
+    pub limit: Option<i64>,
+    pub processor_id: Option<String>,
+}
+
+/// Lease due refunds to a processor
+pub async fn get_pending_refunds(
+    State(state): State<AppState>,
+    Query(query): Query<PendingRefundsQuery>,
+) -> Result<Json<PendingRefundsResponse>> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let processor_id = query
+        .processor_id
+        .unwrap_or_else(|| "processor-unknown".to_string());
+
+    // Refunds move casino funds, so they halt with settlements
+    if state.ops.is_halted().await? {
+        tracing::debug!(processor_id = %processor_id, "Kill switch engaged; no refunds claimed");
+        return Ok(Json(PendingRefundsResponse { processor_id, refunds: Vec::new() }));
+    }
+
+    let refunds = state.ops.claim_refunds(limit, Utc::now().timestamp_millis()).await?;
+    if !refunds.is_empty() {
+        tracing::info!(processor_id = %processor_id, count = refunds.len(), "Refunds claimed");
+    }
+
+    Ok(Json(PendingRefundsResponse { processor_id, refunds }))
+}
+
+/// Record the result of a processor's `refund_bet` attempt
+pub async fn report_refund(
+    State(state): State<AppState>,
+    Path(bet_id): Path<Uuid>,
+    Json(result): Json<RefundResult>,
+) -> Result<Json<Refund>> {
+    let (refund, finished) = state.ops.record_refund_result(bet_id, &result).await?;
+
+    if !finished {
+        tracing::info!(%bet_id, attempts = refund.attempts, error = ?result.error_message, "Refund attempt reported");
+        return Ok(Json(refund));
+    }
+
+    let (action, outcome) = match refund.status {
+        RefundStatus::Completed => ("refund_completed", "completed"),
+        _ => ("refund_failed", "failed"),
+    };
+    state
+        .audit
+        .append_audit(&AuditEntry {
+            bet_id,
+            action: action.to_string(),
+            operator: result.processor_id.clone(),
+            reason: refund.reason.clone(),
+            previous_status: BetStatus::Completed,
+            details: serde_json::json!({
+                "refund_amount": refund.amount,
+                "solana_tx_id": refund.solana_tx_id,
+                "error": refund.last_error,
+            }),
+            created_at: refund.completed_at.unwrap_or_else(Utc::now),
+        })
+        .await?;
+
+    if refund.status == RefundStatus::Failed {
+        tracing::error!(%bet_id, error = ?refund.last_error, "Refund failed; needs an operator");
+    } else {
+        tracing::info!(%bet_id, solana_tx_id = ?refund.solana_tx_id, amount = refund.amount, "Refund completed");
+        state.ops.post_ledger_or_warn(&[ledger::refund(&refund)]).await;
+        if state.config.notifications.enabled {
+            state.ops.publish_notifications(&[Notification::refund_completed(&refund)]).await;
+        }
+    }
+    metrics::counter!("bet_refunds_total", "outcome" => outcome).increment(1);
+
+    Ok(Json(refund))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::admin::ADMIN_KEY_HEADER;
+    use crate::killswitch::KillSwitchState;
+    use crate::repository::{AuditRepository, OpsRepository};
+    use crate::testing::{self, InMemoryRepositories, ADMIN_KEY};
+    use axum::http::{HeaderName, HeaderValue};
+    use axum_test::{TestRequest, TestServer};
+    use serde_json::json;
+
+    fn as_admin(request: TestRequest) -> TestRequest {
+        request.add_header(HeaderName::from_static(ADMIN_KEY_HEADER), HeaderValue::from_static(ADMIN_KEY))
+    }
+
+    #[tokio::test]
+    async fn test_void_claim_and_complete_a_refund() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let mut lost = testing::pending_bet("W1");
+        lost.status = BetStatus::Completed;
+        lost.won = Some(false);
+        lost.payout_amount = Some(0);
+        lost.solana_tx_id = Some("settle-tx".to_string());
+        repos.bets.insert(lost.clone());
+        let void = format!("/api/admin/bets/{}/void", lost.bet_id);
+        let request = json!({ "operator": "ops", "reason": "game fault" });
+
+        server.post(&void).json(&request).await.assert_status_unauthorized();
+        let voided: serde_json::Value = as_admin(server.post(&void)).json(&request).await.json();
+        assert_eq!(voided["refund"]["amount"], lost.stake_amount);
+        let queued: Vec<Refund> = as_admin(server.get("/api/admin/refunds")).await.json();
+        assert_eq!(queued.len(), 1);
+
+        // Refunds halt with settlements
+        let halted = KillSwitchState {
+            engaged_by: "ops".to_string(),
+            reason: "incident".to_string(),
+            engaged_at: Utc::now(),
+            on_chain_signature: None,
+        };
+        repos.ops.engage_kill_switch(&halted).await.unwrap();
+        let pending: PendingRefundsResponse = server.get("/api/external/refunds/pending").await.json();
+        assert!(pending.refunds.is_empty());
+        repos.ops.release_kill_switch().await.unwrap();
+        let pending: PendingRefundsResponse = server.get("/api/external/refunds/pending").await.json();
+        assert_eq!(pending.refunds[0].bet_id, lost.bet_id);
+
+        let result = RefundResult {
+            processor_id: "processor-1".to_string(),
+            status: RefundStatus::Completed,
+            solana_tx_id: Some("refund-tx".to_string()),
+            error_message: None,
+        };
+        let refund: Refund = server.post(&format!("/api/external/refunds/{}", lost.bet_id)).json(&result).await.json();
+        assert_eq!(refund.status, RefundStatus::Completed);
+        assert_eq!(repos.ops.balance(&ledger::wallet_vault("W1", "SOL")), lost.stake_amount);
+        let trail = repos.audit.audit_trail(lost.bet_id).await.unwrap();
+        assert_eq!(trail.iter().map(|entry| entry.action.as_str()).collect::<Vec<_>>(), vec!["void", "refund_completed"]);
+        let done: Vec<Refund> =
+            as_admin(server.get("/api/admin/refunds")).add_query_param("status", "done").await.json();
+        assert_eq!(done.len(), 1);
+    }
+}
@@ -0,0 +1,211 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use std::convert::Infallible;
+
+use crate::{
+    daily_report::DailyReport,
+    errors::{AppError, Result},
+    handlers::admin::require_admin,
+    reports::{BetReport, GroupBy, ReportRange, ReportRow, CSV_HEADER},
+    state::AppState,
+};
+
+/// Live bets loaded from Redis per round trip
+const LIVE_PAGE_SIZE: i64 = 500;
+
+/// Longest report window, to keep live scans bounded
+const MAX_REPORT_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BetReportQuery {
+    /// First UTC day included
+    pub from: NaiveDate,
+    /// Last UTC day included
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub group_by: GroupBy,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+/// Aggregated bet volumes, win rates and payout ratios over live and archived bets
+pub async fn bet_report(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BetReportQuery>,
+) -> Result<Response> {
+    require_admin(&state.config, &headers)?;
+
+    if query.to < query.from {
+        return Err(AppError::invalid_input("to must not be before from"));
+    }
+    if (query.to - query.from).num_days() >= MAX_REPORT_DAYS {
+        return Err(AppError::invalid_input(format!(
+            "Report window may span at most {} days",
+            MAX_REPORT_DAYS
+        )));
+    }
+
+    let day_after = query
+        .to
+        .succ_opt()
+        .ok_or_else(|| AppError::invalid_input("to is past the last supported date"))?;
+    let range = ReportRange {
+        from: query.from.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc(),
+        to: day_after.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc(),
+    };
+
+    let span = tracing::info_span!("bet_report", from = %query.from, to = %query.to, group_by = ?query.group_by);
+    let _enter = span.enter();
+
+    let mut report = BetReport::default();
+
+    let repo = &state.bets;
+    let (from_ms, to_ms) = (range.from.timestamp_millis(), range.to.timestamp_millis());
+    let mut offset = 0;
+    loop {
+        let page = repo.find_created_between(from_ms, to_ms, offset, LIVE_PAGE_SIZE).await?;
+        if page.is_empty() {
+            break;
+        }
+        for bet in &page {
+            report.add_bet(query.group_by, bet);
+        }
+        offset += LIVE_PAGE_SIZE;
+    }
+
+    if let Some(archive) = &state.archive {
+        for (group, aggregate) in archive.aggregate(range, query.group_by).await? {
+            report.merge(group, &aggregate);
+        }
+    }
+
+    let rows = report.rows();
+    tracing::info!(groups = rows.len(), "Bet report generated");
+    metrics::counter!(
+        "admin_reports_generated_total",
+        "report" => "bets",
+        "format" => match query.format { ReportFormat::Json => "json", ReportFormat::Csv => "csv" }
+    )
+    .increment(1);
+
+    Ok(stream_rows(rows, query.format))
+}
+
+/// Daily settlement summary; days that are not finalized yet are built from live counters
+pub async fn daily_report(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(date): Path<NaiveDate>,
+) -> Result<Json<DailyReport>> {
+    require_admin(&state.config, &headers)?;
+
+    if date > Utc::now().date_naive() {
+        return Err(AppError::invalid_input("date must not be in the future"));
+    }
+
+    Ok(Json(state.ops.daily_report(state.bets.as_ref(), date).await?))
+}
+
+/// Stream report rows one chunk per row
+fn stream_rows(rows: Vec<ReportRow>, format: ReportFormat) -> Response {
+    let count = rows.len();
+    let chunks: Vec<String> = match format {
+        ReportFormat::Csv => std::iter::once(CSV_HEADER.to_string())
+            .chain(rows.iter().map(ReportRow::to_csv_line))
+            .collect(),
+        ReportFormat::Json => std::iter::once("[".to_string())
+            .chain(rows.iter().enumerate().map(|(i, row)| {
+                let json = serde_json::to_string(row).unwrap_or_default();
+                if i + 1 < count { format!("{},", json) } else { json }
+            }))
+            .chain(std::iter::once("]".to_string()))
+            .collect(),
+    };
+
+    let content_type = match format {
+        ReportFormat::Json => "application/json",
+        ReportFormat::Csv => "text/csv; charset=utf-8",
+    };
+    let body = Body::from_stream(futures_util::stream::iter(chunks.into_iter().map(Ok::<_, Infallible>)));
+
+    ([(header::CONTENT_TYPE, content_type)], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::daily_report::DailyTally;
+    use crate::domain::BetStatus;
+    use crate::handlers::admin::ADMIN_KEY_HEADER;
+    use crate::repository::OpsRepository;
+    use crate::testing::{self, InMemoryRepositories, ADMIN_KEY};
+    use axum::http::{HeaderName, HeaderValue};
+    use axum_test::{TestRequest, TestServer};
+    use chrono::{Duration, Utc};
+
+    fn as_admin(request: TestRequest) -> TestRequest {
+        request.add_header(HeaderName::from_static(ADMIN_KEY_HEADER), HeaderValue::from_static(ADMIN_KEY))
+    }
+
+    #[tokio::test]
+    async fn test_bet_report_over_live_bets() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let mut won = testing::pending_bet("W1");
+        won.status = BetStatus::Completed;
+        won.won = Some(true);
+        won.payout_amount = Some(200_000_000);
+        repos.bets.insert(won);
+        repos.bets.insert(testing::pending_bet("W2"));
+        let today = Utc::now().date_naive().to_string();
+        let report = |from: &str, to: &str| {
+            as_admin(server.get("/api/admin/reports/bets")).add_query_param("from", from).add_query_param("to", to)
+        };
+
+        let rows: serde_json::Value = report(&today, &today).await.json();
+        assert_eq!((rows[0]["bets"].as_i64(), rows[0]["settled"].as_i64(), rows[0]["wins"].as_i64()), (Some(2), Some(1), Some(1)));
+        let csv = report(&today, &today).add_query_param("format", "csv").await.text();
+        assert!(csv.starts_with(crate::reports::CSV_HEADER));
+        assert_eq!(csv.lines().count(), 2);
+
+        let yesterday = (Utc::now() - Duration::days(1)).date_naive().to_string();
+        report(&today, &yesterday).await.assert_status_bad_request();
+        let last = chrono::NaiveDate::MAX.to_string();
+        let response = report(&last, &last).await;
+        response.assert_status_bad_request();
+        assert!(response.text().contains("last supported date"));
+    }
+
+    #[tokio::test]
+    async fn test_daily_report_from_live_counters() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let today = Utc::now().date_naive();
+        let mut tally = DailyTally::default();
+        tally.add_completed(&testing::pending_bet("W1"));
+        tally.add_failed();
+        tally.add_fees(5_000);
+        repos.ops.record_daily_tally(&tally, today).await.unwrap();
+
+        let report: serde_json::Value = as_admin(server.get(&format!("/api/admin/reports/daily/{}", today))).await.json();
+        assert_eq!((report["succeeded"].as_i64(), report["failed"].as_i64()), (Some(1), Some(1)));
+        assert_eq!((report["fees_lamports"].as_i64(), report["final"].as_bool()), (Some(5_000), Some(false)));
+        let tomorrow = today + Duration::days(1);
+        as_admin(server.get(&format!("/api/admin/reports/daily/{}", tomorrow))).await.assert_status_bad_request();
+    }
+}
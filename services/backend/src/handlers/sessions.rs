@@ -0,0 +1,178 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use solana_sdk::{pubkey::Pubkey, system_program};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{
+    domain::{CreateSessionRequest, Session, SessionLimits, SessionResponse},
+    errors::{AppError, Result},
+    extractors::ValidatedJson,
+    handlers::allowances::fetch_allowance,
+    handlers::withdrawals::{parse_wallet, vault_program_id},
+    sessions,
+    state::AppState,
+    vault_transactions::{casino_pda, user_vault_pda},
+};
+
+/// Open a session over one of the user's active allowances
+pub async fn create_session(
+    State(state): State<AppState>,
+    ValidatedJson(req): ValidatedJson<CreateSessionRequest>,
+) -> Result<Json<Session>> {
+    let span = tracing::info_span!("create_session", user_wallet = %req.user_wallet, allowance_pda = %req.allowance_pda);
+    let _enter = span.enter();
+
+    let user = parse_wallet(&req.user_wallet)?;
+    let address = Pubkey::from_str(&req.allowance_pda).map_err(|_| AppError::invalid_input("Invalid allowance PDA"))?;
+    let max_rounds = state.config.betting.session_max_rounds;
+    let limits = SessionLimits {
+        max_rounds: Some(req.limits.max_rounds.unwrap_or(max_rounds)),
+        ..req.limits
+    };
+    if limits.max_rounds.is_some_and(|rounds| rounds == 0 || rounds > max_rounds)
+        || limits.max_total_stake == Some(0)
+        || limits.max_stake_per_bet == Some(0)
+    {
+        return Err(AppError::invalid_input(format!(
+            "Session limits must be positive, with at most {} rounds",
+            max_rounds
+        )));
+    }
+    if req.duration_seconds.is_some_and(|seconds| seconds <= 0) {
+        return Err(AppError::invalid_input("duration_seconds must be positive"));
+    }
+
+    let now = Utc::now();
+    let allowance = fetch_allowance(&state, &address)
+        .await?
+        .filter(|allowance| allowance.user == req.user_wallet)
+        .ok_or_else(|| AppError::invalid_input(format!("No allowance {} for this wallet", req.allowance_pda)))?;
+    if !allowance.is_active(now.timestamp()) {
+        return Err(AppError::invalid_status(format!(
+            "Allowance {} is revoked, expired or used up",
+            req.allowance_pda
+        )));
+    }
+
+    let allowance_expires_at = DateTime::from_timestamp(allowance.expires_at, 0)
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Invalid allowance expiry {}", allowance.expires_at)))?;
+    let expires_at = match req.duration_seconds {
+        Some(seconds) => (now + Duration::seconds(seconds)).min(allowance_expires_at),
+        None => allowance_expires_at,
+    };
+
+    let program_id = vault_program_id(&state)?;
+    let mint = Pubkey::from_str(&allowance.token_mint).unwrap_or(system_program::ID);
+    let session = Session {
+        session_id: Uuid::new_v4(),
+        user_wallet: req.user_wallet.clone(),
+        vault_address: user_vault_pda(&user, &casino_pda(&program_id), &program_id).to_string(),
+        allowance_pda: req.allowance_pda.clone(),
+        stake_token: if mint == system_program::ID { "SOL".to_string() } else { allowance.token_mint.clone() },
+        limits,
+        created_at: now,
+        expires_at,
+    };
+
+    state.ops.create_session(&session).await?;
+
+    tracing::info!(session_id = %session.session_id, expires_at = %session.expires_at, "Session created");
+    metrics::counter!("sessions_created_total").increment(1);
+
+    Ok(Json(session))
+}
+
+/// A session with its remaining budget, aggregated results and bets
+pub async fn get_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<SessionResponse>> {
+    let span = tracing::info_span!("get_session", %session_id);
+    let _enter = span.enter();
+
+    let session = state
+        .ops
+        .session(session_id)
+        .await?
+        .ok_or_else(|| AppError::session_not_found(session_id))?;
+    let usage = state.ops.session_usage(session_id).await?;
+
+    // `max_rounds` bounds the session, so this reads every bet in it
+    let max_rounds = session.limits.max_rounds.unwrap_or(state.config.betting.session_max_rounds);
+    let repo = &state.bets;
+    let bets = repo.find_by_session(session_id, i64::from(max_rounds)).await?;
+
+    Ok(Json(SessionResponse {
+        rounds_remaining: max_rounds.saturating_sub(usage.rounds),
+        stake_remaining: session.limits.max_total_stake.map(|max| max.saturating_sub(usage.staked)),
+        results: sessions::results(&bets),
+        bets,
+        session,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::BetStatus;
+    use crate::repository::OpsRepository;
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_create_session_needs_an_allowance() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let request = |limits: serde_json::Value| {
+            json!({
+                "user_wallet": Pubkey::new_unique().to_string(),
+                "allowance_pda": Pubkey::new_unique().to_string(),
+                "limits": limits,
+            })
+        };
+
+        // Limits are checked before the allowance is looked up
+        server.post("/api/sessions").json(&request(json!({ "max_rounds": 0 }))).await.assert_status_bad_request();
+        server.post("/api/sessions").json(&request(json!({ "max_rounds": 100_000 }))).await.assert_status_bad_request();
+        let missing = server.post("/api/sessions").json(&request(json!({}))).await;
+        missing.assert_status_bad_request();
+        assert!(missing.text().contains("No allowance"));
+    }
+
+    #[tokio::test]
+    async fn test_get_session_reports_budget_and_results() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let session = Session {
+            session_id: Uuid::new_v4(),
+            user_wallet: Pubkey::new_unique().to_string(),
+            vault_address: Pubkey::new_unique().to_string(),
+            allowance_pda: Pubkey::new_unique().to_string(),
+            stake_token: "SOL".to_string(),
+            limits: SessionLimits { max_rounds: Some(3), max_total_stake: Some(500_000_000), max_stake_per_bet: None },
+            created_at: Utc::now(),
+            expires_at: Utc::now() + Duration::hours(1),
+        };
+        repos.ops.create_session(&session).await.unwrap();
+        let bet = json!({
+            "session_id": session.session_id,
+            "stake_amount": 100_000_000u64,
+            "stake_token": "SOL",
+            "choice": "heads",
+        });
+        server.post("/api/bets").json(&bet).await.assert_status_ok();
+
+        let response: SessionResponse = server.get(&format!("/api/sessions/{}", session.session_id)).await.json();
+        assert_eq!(response.rounds_remaining, 2);
+        assert_eq!(response.stake_remaining, Some(400_000_000));
+        assert_eq!((response.results.rounds, response.results.pending), (1, 1));
+        assert_eq!(response.bets[0].status, BetStatus::Pending);
+
+        server.get(&format!("/api/sessions/{}", Uuid::new_v4())).await.assert_status_not_found();
+    }
+}
@@ -0,0 +1,28 @@
+use axum::{extract::State, Json};
+
+use crate::{domain::TokensResponse, errors::Result, handlers::withdrawals::vault_program_id, state::AppState};
+
+/// Stake tokens the casino accepts and their on-chain bet limits
+pub async fn list_tokens(State(state): State<AppState>) -> Result<Json<TokensResponse>> {
+    let program_id = vault_program_id(&state)?;
+    let tokens = state.tokens.tokens(&state.solana, &program_id, &state.config.betting).await?;
+    Ok(Json(TokensResponse { tokens: tokens.as_ref().clone() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_lists_sol_without_registered_mints() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+
+        let listed: Value = server.get("/api/tokens").await.json();
+        let tokens = listed["tokens"].as_array().unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!((tokens[0]["stake_token"].as_str(), tokens[0]["source"].as_str()), (Some("SOL"), Some("default")));
+    }
+}
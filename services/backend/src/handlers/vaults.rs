@@ -0,0 +1,138 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use shared::errors::{ErrorCategory, ErrorCode, ServiceError};
+use shared::indexer::{IndexedDeposit, IndexedVault};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::{
+    domain::PreparePayoutAddressRequest,
+    errors::{AppError, Result},
+    extractors::ValidatedJson,
+    handlers::withdrawals::{parse_wallet, vault_program_id, PreparedTransactionResponse},
+    state::AppState,
+    vault_transactions::{build_set_payout_address_instruction, casino_pda, encode_unsigned_transaction, user_vault_pda},
+};
+
+/// On-chain vault balance of a wallet, as mirrored by the indexer
+pub async fn get_vault(State(state): State<AppState>, Path(wallet): Path<String>) -> Result<Json<IndexedVault>> {
+    parse_wallet(&wallet)?;
+    let indexer = state
+        .indexer
+        .as_ref()
+        .ok_or_else(|| AppError::indexer_unavailable("INDEXER_URL is not configured"))?;
+
+    let vault = indexer
+        .vault(&wallet)
+        .await
+        .map_err(AppError::indexer_unavailable)?
+        .ok_or_else(|| {
+            AppError::Service(ServiceError::new(
+                ErrorCategory::NotFound,
+                ErrorCode::NOT_FOUND_VAULT,
+                format!("No vault for wallet {}", wallet),
+            ))
+        })?;
+    Ok(Json(vault))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListDepositsQuery {
+    pub limit: Option<usize>,
+}
+
+/// Newest deposits into a wallet's vault detected by the indexer
+pub async fn list_deposits(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    Query(query): Query<ListDepositsQuery>,
+) -> Result<Json<Vec<IndexedDeposit>>> {
+    parse_wallet(&wallet)?;
+    let indexer = state
+        .indexer
+        .as_ref()
+        .ok_or_else(|| AppError::indexer_unavailable("INDEXER_URL is not configured"))?;
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let deposits = indexer
+        .user_deposits(&wallet, limit)
+        .await
+        .map_err(AppError::indexer_unavailable)?;
+    Ok(Json(deposits))
+}
+
+/// Build an unsigned set_payout_address transaction: wins on the vault are paid
+/// to `payout_address`, or to the vault again when it is absent. Refunds always
+/// go back to the vault.
+pub async fn prepare_payout_address(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    ValidatedJson(req): ValidatedJson<PreparePayoutAddressRequest>,
+) -> Result<Json<PreparedTransactionResponse>> {
+    let span = tracing::info_span!("prepare_payout_address", %wallet, payout_address = ?req.payout_address);
+    let _enter = span.enter();
+
+    let user = parse_wallet(&wallet)?;
+    let program_id = vault_program_id(&state)?;
+    let vault = user_vault_pda(&user, &casino_pda(&program_id), &program_id);
+    let payout_address = req
+        .payout_address
+        .as_deref()
+        .map(|address| Pubkey::from_str(address).map_err(|_| AppError::invalid_input("Invalid payout address")))
+        .transpose()?;
+    if payout_address == Some(vault) {
+        return Err(AppError::invalid_input("payout_address is the vault itself; omit it to pay the vault"));
+    }
+
+    let instruction = build_set_payout_address_instruction(&program_id, &user, payout_address.as_ref());
+    let (blockhash, last_valid_block_height) = state
+        .solana
+        .get_latest_blockhash_with_commitment(state.solana.commitment())
+        .await
+        .map_err(AppError::rpc_unavailable)?;
+    let transaction = encode_unsigned_transaction(&[instruction], &user, blockhash)?;
+
+    tracing::info!(vault = %vault, "Prepared payout address transaction");
+    metrics::counter!("payout_address_prepared_total").increment(1);
+
+    Ok(Json(PreparedTransactionResponse {
+        transaction,
+        recent_blockhash: blockhash.to_string(),
+        last_valid_block_height,
+        vault_address: vault.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_vault_reads_need_the_indexer() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let wallet = solana_sdk::pubkey::Pubkey::new_unique();
+
+        server.get(&format!("/api/users/{}/vault", wallet)).await.assert_status(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+        server.get(&format!("/api/users/{}/deposits", wallet)).await.assert_status(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+        server.get("/api/users/not-a-wallet/vault").await.assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_prepare_payout_address() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let path = format!("/api/users/{}/payout-address/prepare", solana_sdk::pubkey::Pubkey::new_unique());
+
+        let prepared: serde_json::Value = server.post(&path).json(&json!({ "payout_address": null })).await.json();
+        assert!(prepared["transaction"].is_string());
+        let vault = prepared["vault_address"].clone();
+        server.post(&path).json(&json!({ "payout_address": vault })).await.assert_status_bad_request();
+        server.post(&path).json(&json!({ "payout_address": "nope" })).await.assert_status_bad_request();
+    }
+}
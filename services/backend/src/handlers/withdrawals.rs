@@ -0,0 +1,163 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::{
+    domain::{PrepareWithdrawalRequest, SubmitTransactionRequest},
+    errors::{AppError, Result},
+    extractors::ValidatedJson,
+    state::AppState,
+    vault_transactions::{
+        build_withdraw_sol_instruction, build_withdraw_spl_instruction, casino_pda,
+        decode_signed_transaction, encode_unsigned_transaction, user_vault_pda,
+    },
+};
+
+/// Vault instructions the withdrawal relay will forward
+const WITHDRAW_INSTRUCTIONS: &[&str] = &["withdraw_sol", "withdraw_spl"];
+
+#[derive(Debug, Serialize)]
+pub struct PreparedTransactionResponse {
+    /// Base64 bincode-serialized unsigned transaction
+    pub transaction: String,
+    pub recent_blockhash: String,
+    pub last_valid_block_height: u64,
+    pub vault_address: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubmitTransactionResponse {
+    pub signature: String,
+}
+
+pub(crate) fn parse_wallet(wallet: &str) -> Result<Pubkey> {
+    Pubkey::from_str(wallet).map_err(|_| AppError::invalid_input("Invalid user wallet address"))
+}
+
+pub(crate) fn vault_program_id(state: &AppState) -> Result<Pubkey> {
+    Pubkey::from_str(&state.config.solana.vault_program_id)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Invalid VAULT_PROGRAM_ID: {}", e)))
+}
+
+/// Build an unsigned withdraw_sol / withdraw_spl transaction for the wallet to sign
+pub async fn prepare_withdrawal(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    ValidatedJson(req): ValidatedJson<PrepareWithdrawalRequest>,
+) -> Result<Json<PreparedTransactionResponse>> {
+    let span = tracing::info_span!("prepare_withdrawal", %wallet, amount = req.amount);
+    let _enter = span.enter();
+
+    let user = parse_wallet(&wallet)?;
+    if req.amount == 0 {
+        return Err(AppError::invalid_input("amount must be positive"));
+    }
+
+    let program_id = vault_program_id(&state)?;
+    let instruction = match req.token_mint.as_deref() {
+        Some(mint) => {
+            let mint = Pubkey::from_str(mint).map_err(|_| AppError::invalid_input("Invalid token mint"))?;
+            build_withdraw_spl_instruction(&program_id, &user, &mint, req.amount)
+        }
+        None => build_withdraw_sol_instruction(&program_id, &user, req.amount),
+    };
+
+    let (blockhash, last_valid_block_height) = state
+        .solana
+        .get_latest_blockhash_with_commitment(state.solana.commitment())
+        .await
+        .map_err(AppError::rpc_unavailable)?;
+
+    let transaction = encode_unsigned_transaction(&[instruction], &user, blockhash)?;
+    let vault = user_vault_pda(&user, &casino_pda(&program_id), &program_id);
+
+    tracing::info!(vault = %vault, token_mint = ?req.token_mint, "Prepared withdrawal transaction");
+    metrics::counter!("withdrawals_prepared_total").increment(1);
+
+    Ok(Json(PreparedTransactionResponse {
+        transaction,
+        recent_blockhash: blockhash.to_string(),
+        last_valid_block_height,
+        vault_address: vault.to_string(),
+    }))
+}
+
+/// Relay a user-signed withdrawal transaction to Solana
+pub async fn submit_withdrawal(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    ValidatedJson(req): ValidatedJson<SubmitTransactionRequest>,
+) -> Result<Json<SubmitTransactionResponse>> {
+    let span = tracing::info_span!("submit_withdrawal", %wallet);
+    let _enter = span.enter();
+
+    let user = parse_wallet(&wallet)?;
+    let program_id = vault_program_id(&state)?;
+    let transaction = decode_signed_transaction(&req.transaction, &user, &program_id, WITHDRAW_INSTRUCTIONS)
+        .map_err(|e| AppError::invalid_input(format!("{:#}", e)))?;
+
+    let signature = state
+        .solana
+        .send_transaction(&transaction)
+        .await
+        .map_err(AppError::rpc_unavailable)?;
+
+    tracing::info!(%signature, "Relayed withdrawal transaction");
+    metrics::counter!("withdrawals_submitted_total").increment(1);
+
+    Ok(Json(SubmitTransactionResponse {
+        signature: signature.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{self, InMemoryRepositories};
+    use axum_test::TestServer;
+    use base64::Engine;
+    use serde_json::json;
+    use solana_sdk::{
+        hash::Hash,
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use std::str::FromStr;
+
+    fn decode(encoded: &serde_json::Value) -> Transaction {
+        let bytes = base64::engine::general_purpose::STANDARD.decode(encoded.as_str().unwrap()).unwrap();
+        bincode::deserialize(&bytes).unwrap()
+    }
+
+    fn encode(transaction: &Transaction) -> String {
+        base64::engine::general_purpose::STANDARD.encode(bincode::serialize(transaction).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_relays_only_signed_withdrawals() {
+        let repos = InMemoryRepositories::new(&testing::config());
+        let server = TestServer::new(crate::build_router(repos.app_state(testing::config()))).unwrap();
+        let user = Keypair::new();
+        let base = format!("/api/users/{}/withdrawals", user.pubkey());
+
+        server.post(&format!("{}/prepare", base)).json(&json!({ "amount": 0 })).await.assert_status_bad_request();
+        let prepared: serde_json::Value =
+            server.post(&format!("{}/prepare", base)).json(&json!({ "amount": 1_000 })).await.json();
+        let mut transaction = decode(&prepared["transaction"]);
+        assert_eq!(transaction.message.account_keys[0], user.pubkey());
+
+        // Unsigned, then signed by the wallet
+        let submit = format!("{}/submit", base);
+        server.post(&submit).json(&json!({ "transaction": encode(&transaction) })).await.assert_status_bad_request();
+        let blockhash = Hash::from_str(prepared["recent_blockhash"].as_str().unwrap()).unwrap();
+        transaction.sign(&[&user], blockhash);
+        let submitted: serde_json::Value =
+            server.post(&submit).json(&json!({ "transaction": encode(&transaction) })).await.json();
+        assert!(submitted["signature"].is_string());
+
+        server.post(&submit).json(&json!({ "transaction": "not base64" })).await.assert_status_bad_request();
+    }
+}
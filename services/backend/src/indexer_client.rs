@@ -0,0 +1,62 @@
+//! Client for the internal account indexer API
+//!
+//! The indexer mirrors vault program accounts from websocket subscriptions, so
+//! reads here replace per-request RPC account fetches. It can lag the chain by
+//! a slot or two; callers that must not act on stale state keep an RPC path.
+
+use anyhow::Result;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use shared::indexer::{IndexedAllowance, IndexedDeposit, IndexedVault, UserAllowances};
+use std::time::Duration;
+
+/// Indexer reads sit on request paths with an RPC fallback; fail fast
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct IndexerClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl IndexerClient {
+    pub fn new(base_url: &str) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("static reqwest client configuration");
+        Self { http, base_url: base_url.trim_end_matches('/').to_string() }
+    }
+
+    /// `Ok(None)` when the indexer has no record at `path`
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<Option<T>> {
+        let response = self.http.get(format!("{}{}", self.base_url, path)).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    pub async fn vault(&self, owner: &str) -> Result<Option<IndexedVault>> {
+        self.get(&format!("/vaults/{}", owner)).await
+    }
+
+    /// Newest `limit` deposits into `owner`'s vault
+    pub async fn user_deposits(&self, owner: &str, limit: usize) -> Result<Vec<IndexedDeposit>> {
+        Ok(self
+            .get(&format!("/users/{}/deposits?limit={}", owner, limit))
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn allowance(&self, address: &str) -> Result<Option<IndexedAllowance>> {
+        self.get(&format!("/allowances/{}", address)).await
+    }
+
+    /// Newest `limit` allowances of `owner` and its next allowance nonce
+    pub async fn user_allowances(&self, owner: &str, limit: usize) -> Result<UserAllowances> {
+        Ok(self
+            .get(&format!("/users/{}/allowances?limit={}", owner, limit))
+            .await?
+            .unwrap_or_default())
+    }
+}
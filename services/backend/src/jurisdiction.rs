@@ -0,0 +1,230 @@
+//! Jurisdiction gating for bet intake
+//!
+//! Clients are matched by IP against blocked CIDR ranges and, when a MaxMind
+//! country database is configured, against blocked country codes. Allowed CIDRs
+//! override both. Once any block rule is configured the gate fails closed: a
+//! request whose client IP cannot be determined is rejected.
+
+use anyhow::Context;
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+use crate::config::JurisdictionConfig;
+
+/// Why a client was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denial {
+    UnknownIp,
+    BlockedNetwork,
+    BlockedCountry(String),
+}
+
+impl Denial {
+    /// Short label for metrics and audit entries
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Denial::UnknownIp => "unknown_ip",
+            Denial::BlockedNetwork => "blocked_network",
+            Denial::BlockedCountry(_) => "blocked_country",
+        }
+    }
+}
+
+pub struct JurisdictionGate {
+    blocked: Vec<IpNet>,
+    allowed: Vec<IpNet>,
+    blocked_countries: HashSet<String>,
+    trust_forwarded_for: bool,
+    trusted_proxies: Vec<IpNet>,
+    #[cfg(feature = "geoip")]
+    geoip: Option<maxminddb::Reader<Vec<u8>>>,
+}
+
+fn parse_networks(entries: &[String]) -> anyhow::Result<Vec<IpNet>> {
+    entries
+        .iter()
+        .map(|entry| {
+            // A bare address is a single-host network
+            entry
+                .parse::<IpNet>()
+                .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                .with_context(|| format!("Invalid CIDR: {}", entry))
+        })
+        .collect()
+}
+
+impl JurisdictionGate {
+    pub fn from_config(config: &JurisdictionConfig) -> anyhow::Result<Self> {
+        let blocked_countries: HashSet<String> =
+            config.blocked_countries.iter().map(|c| c.to_ascii_uppercase()).collect();
+
+        #[cfg(feature = "geoip")]
+        let geoip = match &config.geoip_database_path {
+            Some(path) => Some(
+                maxminddb::Reader::open_readfile(path)
+                    .with_context(|| format!("Failed to open GeoIP database {}", path))?,
+            ),
+            None => None,
+        };
+        #[cfg(feature = "geoip")]
+        let has_geoip = geoip.is_some();
+        #[cfg(not(feature = "geoip"))]
+        let has_geoip = {
+            if config.geoip_database_path.is_some() {
+                anyhow::bail!("GEOIP_DATABASE_PATH requires building with the `geoip` feature");
+            }
+            false
+        };
+
+        if !blocked_countries.is_empty() && !has_geoip {
+            anyhow::bail!("JURISDICTION_BLOCKED_COUNTRIES requires GEOIP_DATABASE_PATH");
+        }
+
+        Ok(Self {
+            blocked: parse_networks(&config.blocked_cidrs)?,
+            allowed: parse_networks(&config.allowed_cidrs)?,
+            blocked_countries,
+            trust_forwarded_for: config.trust_forwarded_for,
+            trusted_proxies: parse_networks(&config.trusted_proxies)?,
+            #[cfg(feature = "geoip")]
+            geoip,
+        })
+    }
+
+    /// Whether any block rule is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.blocked.is_empty() || !self.blocked_countries.is_empty()
+    }
+
+    /// Client IP: the rightmost `X-Forwarded-For` hop outside the trusted
+    /// proxies when forwarding is trusted, else the socket peer
+    ///
+    /// Hops left of that one were sent by the client and are never used. A
+    /// hop that does not parse makes the IP unknown rather than being skipped.
+    pub fn client_ip(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+        if !self.trust_forwarded_for {
+            return peer;
+        }
+        // A peer that is not one of our proxies wrote the header itself
+        if let Some(peer) = peer.filter(|peer| !self.trusted_proxies.is_empty() && !self.is_trusted_proxy(*peer)) {
+            return Some(peer);
+        }
+        let hops: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .collect();
+        let mut client = None;
+        for hop in hops.iter().rev() {
+            let ip: IpAddr = hop.trim().parse().ok()?;
+            client = Some(ip);
+            if !self.is_trusted_proxy(ip) {
+                break;
+            }
+        }
+        client
+    }
+
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+
+    pub fn check(&self, ip: Option<IpAddr>) -> Result<(), Denial> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let Some(ip) = ip else {
+            return Err(Denial::UnknownIp);
+        };
+        if self.allowed.iter().any(|net| net.contains(&ip)) {
+            return Ok(());
+        }
+        if self.blocked.iter().any(|net| net.contains(&ip)) {
+            return Err(Denial::BlockedNetwork);
+        }
+        match self.country(ip) {
+            Some(country) if self.blocked_countries.contains(&country) => Err(Denial::BlockedCountry(country)),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "geoip")]
+    fn country(&self, ip: IpAddr) -> Option<String> {
+        let record: maxminddb::geoip2::Country = self.geoip.as_ref()?.lookup(ip).ok()?;
+        record.country?.iso_code.map(str::to_string)
+    }
+
+    #[cfg(not(feature = "geoip"))]
+    fn country(&self, _ip: IpAddr) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate(blocked: &[&str], allowed: &[&str], trust_forwarded_for: bool) -> JurisdictionGate {
+        gate_behind(blocked, allowed, trust_forwarded_for, &[])
+    }
+
+    fn gate_behind(blocked: &[&str], allowed: &[&str], trust_forwarded_for: bool, proxies: &[&str]) -> JurisdictionGate {
+        let list = |v: &[&str]| v.iter().map(|s| s.to_string()).collect();
+        JurisdictionGate::from_config(&JurisdictionConfig {
+            blocked_cidrs: list(blocked),
+            allowed_cidrs: list(allowed),
+            blocked_countries: Vec::new(),
+            geoip_database_path: None,
+            trust_forwarded_for,
+            trusted_proxies: list(proxies),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cidr_rules() {
+        let gate = gate(&["203.0.113.0/24", "2001:db8::/32"], &["203.0.113.7"], false);
+        let ip = |s: &str| Some(s.parse::<IpAddr>().unwrap());
+
+        assert_eq!(gate.check(ip("203.0.113.9")), Err(Denial::BlockedNetwork));
+        assert_eq!(gate.check(ip("2001:db8::1")), Err(Denial::BlockedNetwork));
+        assert_eq!(gate.check(ip("203.0.113.7")), Ok(()));
+        assert_eq!(gate.check(ip("198.51.100.1")), Ok(()));
+        assert_eq!(gate.check(None), Err(Denial::UnknownIp));
+    }
+
+    #[test]
+    fn test_disabled_gate_allows_everything() {
+        assert_eq!(gate(&[], &[], false).check(None), Ok(()));
+    }
+
+    #[test]
+    fn test_client_ip_from_forwarded_for() {
+        let ip = |s: &str| Some(s.parse::<IpAddr>().unwrap());
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-forwarded-for", value.parse().unwrap());
+            headers
+        };
+        let peer = ip("10.0.0.1");
+        // The client claims an allowed address; the proxies append the real one
+        let spoofed = headers("198.51.100.1, 203.0.113.9, 10.0.0.2");
+
+        let proxied = gate_behind(&["203.0.113.0/24"], &[], true, &["10.0.0.0/8"]);
+        assert_eq!(proxied.client_ip(&spoofed, peer), ip("203.0.113.9"));
+        assert_eq!(proxied.check(proxied.client_ip(&spoofed, peer)), Err(Denial::BlockedNetwork));
+
+        // Without a proxy list only the hop our proxy appended counts
+        let unlisted = gate(&[], &[], true);
+        assert_eq!(unlisted.client_ip(&headers("198.51.100.1, 203.0.113.9"), peer), ip("203.0.113.9"));
+
+        // A client reaching us directly cannot forward for anyone
+        assert_eq!(proxied.client_ip(&spoofed, ip("192.0.2.4")), ip("192.0.2.4"));
+
+        assert_eq!(proxied.client_ip(&headers("198.51.100.1, garbage, 10.0.0.2"), peer), None);
+        assert_eq!(gate(&[], &[], false).client_ip(&spoofed, peer), peer);
+    }
+}
@@ -0,0 +1,52 @@
+//! Incident kill switch
+//!
+//! A single Redis key (`shared::constants::KILL_SWITCH_KEY`) halts the system: the
+//! backend rejects new bets and stops handing out pending ones, and processors poll
+//! the same key to stop dispatching settlement batches.
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use shared::constants::KILL_SWITCH_KEY;
+
+use crate::errors::Result;
+
+/// Who engaged the kill switch and why; stored as the flag's value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillSwitchState {
+    pub engaged_by: String,
+    pub reason: String,
+    pub engaged_at: DateTime<Utc>,
+    /// `pause_casino` transaction, when the casino was also paused on-chain
+    pub on_chain_signature: Option<String>,
+}
+
+/// Current kill switch state; `None` when the system is running normally
+pub async fn current(redis: &mut ConnectionManager) -> Result<Option<KillSwitchState>> {
+    let raw: Option<String> = redis.get(KILL_SWITCH_KEY).await?;
+    // An unreadable value still means "engaged"; keep the system halted
+    Ok(raw.map(|raw| {
+        serde_json::from_str(&raw).unwrap_or_else(|_| KillSwitchState {
+            engaged_by: "unknown".to_string(),
+            reason: raw,
+            engaged_at: Utc::now(),
+            on_chain_signature: None,
+        })
+    }))
+}
+
+pub async fn is_engaged(redis: &mut ConnectionManager) -> Result<bool> {
+    Ok(redis.exists(KILL_SWITCH_KEY).await?)
+}
+
+pub async fn engage(redis: &mut ConnectionManager, state: &KillSwitchState) -> Result<()> {
+    let payload = serde_json::to_string(state).map_err(anyhow::Error::from)?;
+    let _: () = redis.set(KILL_SWITCH_KEY, payload).await?;
+    Ok(())
+}
+
+pub async fn release(redis: &mut ConnectionManager) -> Result<()> {
+    let _: () = redis.del(KILL_SWITCH_KEY).await?;
+    Ok(())
+}
@@ -0,0 +1,623 @@
+//! Double-entry settlement ledger
+//!
+//! Every movement of value the backend learns about is booked as a transfer
+//! between two accounts, so the balances of a token's accounts always sum to zero:
+//! - `wallet:{wallet}:{token}:vault`: what settlement moved into or out of the
+//!   user's vault. Deposits and withdrawals are not booked, so it is relative.
+//! - `wallet:{wallet}:{token}:reserved`: stakes of bets not settled yet
+//! - `casino:{casino}:{token}:vault`: the casino vault
+//! - `external:{token}`: the outside world, for operator adjustments
+//!
+//! A bet books a reservation when created, a spend (plus a payout on a win) when
+//! it settles and a release when it expires; refunds book when they complete.
+//! Each posting is one Lua script keyed by its bet and kind: a replayed batch
+//! update can't book twice, and a crash can't leave half a transfer behind.
+//! Replaying the calls that failed completes the rest.
+//!
+//! The invariant checker compares the casino vault's on-chain SOL balance with the
+//! ledger's, as movement since the first check, and verifies each user vault still
+//! holds the stakes reserved against it. Operators book casino funding and
+//! withdrawals as adjustments so they don't read as divergence.
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Script};
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::domain::{Bet, Refund};
+use crate::errors::{AppError, Result};
+use shared::vault::{casino_pda, casino_vault_pda, user_vault_pda, CasinoVaultAccount, VaultAccount};
+
+/// Casino bets and refunds book against when they don't name one
+pub const DEFAULT_CASINO: &str = "main";
+
+/// The only token whose balances are checked on-chain
+const CHECKED_TOKEN: &str = "SOL";
+
+/// Redis hash of account balances, keyed by account name
+const BALANCES_KEY: &str = "ledger:balances";
+
+/// Redis key prefix for the kinds already posted for a bet or adjustment
+const ENTRIES_PREFIX: &str = "ledger:entries:";
+
+/// Redis stream of every posting, for audits
+const JOURNAL_KEY: &str = "ledger:journal";
+
+/// Redis set of reserved accounts with a positive balance
+const RESERVED_INDEX: &str = "ledger:reserved";
+
+/// Redis key prefix for a casino vault's check anchor
+const ANCHOR_PREFIX: &str = "ledger:anchor:";
+
+/// Redis key for the latest invariant check
+const LAST_CHECK_KEY: &str = "ledger:check:last";
+
+/// Posted kinds are remembered long enough to absorb any replay
+const ENTRIES_TTL_SECONDS: i64 = 40 * 86_400;
+
+/// Approximate number of postings kept in the journal
+const JOURNAL_MAX_LEN: usize = 1_000_000;
+
+/// Accounts per `getMultipleAccounts` call
+const RPC_BATCH_SIZE: usize = 100;
+
+/// Book one transfer unless its kind was already posted for the reference
+///
+/// Keys: [balances, entries, journal, reserved_index]
+/// Args: [kind, debit, credit, amount, fallback_kind, fallback_debit, at_ms, entries_ttl, journal_max_len, reference]
+///
+/// When `fallback_kind` was posted for the reference, `fallback_debit` is debited
+/// instead of `debit`. Returns 1 when booked, 0 when it was already posted.
+const POST_SCRIPT: &str = r#"
+if redis.call('HSETNX', KEYS[2], ARGV[1], ARGV[4]) == 0 then
+  return 0
+end
+redis.call('EXPIRE', KEYS[2], ARGV[8])
+
+local debit = ARGV[2]
+if ARGV[5] ~= '' and redis.call('HEXISTS', KEYS[2], ARGV[5]) == 1 then
+  debit = ARGV[6]
+end
+local amount = tonumber(ARGV[4])
+
+local function move(account, delta)
+  local balance = redis.call('HINCRBY', KEYS[1], account, delta)
+  if string.sub(account, -9) == ':reserved' then
+    if balance > 0 then
+      redis.call('SADD', KEYS[4], account)
+    else
+      redis.call('SREM', KEYS[4], account)
+    end
+  end
+end
+move(debit, -amount)
+move(ARGV[3], amount)
+
+redis.call('XADD', KEYS[3], 'MAXLEN', '~', ARGV[9], '*',
+  'reference', ARGV[10], 'kind', ARGV[1], 'debit', debit, 'credit', ARGV[3], 'amount', ARGV[4], 'at_ms', ARGV[7])
+return 1
+"#;
+
+pub fn wallet_vault(wallet: &str, token: &str) -> String {
+    format!("wallet:{}:{}:vault", wallet, token)
+}
+
+pub fn wallet_reserved(wallet: &str, token: &str) -> String {
+    format!("wallet:{}:{}:reserved", wallet, token)
+}
+
+pub fn casino_vault(casino: &str, token: &str) -> String {
+    format!("casino:{}:{}:vault", casino, token)
+}
+
+pub fn external(token: &str) -> String {
+    format!("external:{}", token)
+}
+
+fn entries_key(reference: &str) -> String {
+    format!("{}{}", ENTRIES_PREFIX, reference)
+}
+
+fn anchor_key(casino: &str, token: &str) -> String {
+    format!("{}{}:{}", ANCHOR_PREFIX, casino, token)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostingKind {
+    Reserve,
+    Release,
+    Spend,
+    Payout,
+    Refund,
+    Adjustment,
+}
+
+impl PostingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostingKind::Reserve => "reserve",
+            PostingKind::Release => "release",
+            PostingKind::Spend => "spend",
+            PostingKind::Payout => "payout",
+            PostingKind::Refund => "refund",
+            PostingKind::Adjustment => "adjustment",
+        }
+    }
+}
+
+/// One transfer of `amount` from `debit` to `credit`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Posting {
+    /// Bet or adjustment the posting belongs to; with `kind`, its idempotency key
+    pub reference: String,
+    pub kind: PostingKind,
+    pub debit: String,
+    pub credit: String,
+    pub amount: i64,
+    /// Debit this account instead when that kind was already posted for the reference
+    #[serde(skip)]
+    pub fallback: Option<(PostingKind, String)>,
+}
+
+fn bet_casino(bet: &Bet) -> &str {
+    bet.casino_id.as_deref().unwrap_or(DEFAULT_CASINO)
+}
+
+/// Stake locked when a bet is created
+pub fn reserve(bet: &Bet) -> Posting {
+    Posting {
+        reference: bet.bet_id.to_string(),
+        kind: PostingKind::Reserve,
+        debit: wallet_vault(&bet.user_wallet, &bet.stake_token),
+        credit: wallet_reserved(&bet.user_wallet, &bet.stake_token),
+        amount: bet.stake_amount,
+        fallback: None,
+    }
+}
+
+/// Stake unlocked when a bet expires unsettled
+pub fn release(bet: &Bet) -> Posting {
+    Posting {
+        reference: bet.bet_id.to_string(),
+        kind: PostingKind::Release,
+        debit: wallet_reserved(&bet.user_wallet, &bet.stake_token),
+        credit: wallet_vault(&bet.user_wallet, &bet.stake_token),
+        amount: bet.stake_amount,
+        fallback: None,
+    }
+}
+
+/// Postings of a bet settled on-chain: its stake goes to the casino, which pays out a win
+///
+/// The reservation is repeated for bets created before the ledger; a bet that
+/// expired and was then settled by hand spends straight from the vault.
+pub fn settlement(bet: &Bet) -> Vec<Posting> {
+    let wallet_vault = wallet_vault(&bet.user_wallet, &bet.stake_token);
+    let casino_vault = casino_vault(bet_casino(bet), &bet.stake_token);
+    let mut postings = vec![
+        reserve(bet),
+        Posting {
+            reference: bet.bet_id.to_string(),
+            kind: PostingKind::Spend,
+            debit: wallet_reserved(&bet.user_wallet, &bet.stake_token),
+            credit: casino_vault.clone(),
+            amount: bet.stake_amount,
+            fallback: Some((PostingKind::Release, wallet_vault.clone())),
+        },
+    ];
+    if let (Some(true), Some(payout)) = (bet.won, bet.payout_amount.filter(|payout| *payout > 0)) {
+        postings.push(Posting {
+            reference: bet.bet_id.to_string(),
+            kind: PostingKind::Payout,
+            debit: casino_vault,
+            credit: wallet_vault,
+            amount: payout,
+            fallback: None,
+        });
+    }
+    postings
+}
+
+/// Stake returned from the casino by a completed refund
+pub fn refund(refund: &Refund) -> Posting {
+    Posting {
+        reference: refund.bet_id.to_string(),
+        kind: PostingKind::Refund,
+        debit: casino_vault(refund.casino_id.as_deref().unwrap_or(DEFAULT_CASINO), &refund.stake_token),
+        credit: wallet_vault(&refund.user_wallet, &refund.stake_token),
+        amount: refund.amount,
+        fallback: None,
+    }
+}
+
+/// Operator funding (positive) or withdrawal (negative) of a casino vault
+pub fn adjustment(adjustment_id: Uuid, casino: &str, token: &str, amount: i64) -> Posting {
+    let (debit, credit) = if amount >= 0 {
+        (external(token), casino_vault(casino, token))
+    } else {
+        (casino_vault(casino, token), external(token))
+    };
+    Posting {
+        reference: adjustment_id.to_string(),
+        kind: PostingKind::Adjustment,
+        debit,
+        credit,
+        amount: amount.saturating_abs(),
+        fallback: None,
+    }
+}
+
+/// Book `postings`, skipping those already posted; returns how many were new
+pub async fn post(redis: &mut ConnectionManager, postings: &[Posting]) -> Result<usize> {
+    let script = Script::new(POST_SCRIPT);
+    let at_ms = Utc::now().timestamp_millis();
+    let mut booked = 0;
+    for posting in postings.iter().filter(|posting| posting.amount > 0) {
+        let (fallback_kind, fallback_debit) = match &posting.fallback {
+            Some((kind, debit)) => (kind.as_str(), debit.as_str()),
+            None => ("", ""),
+        };
+        let created: i64 = script
+            .key(BALANCES_KEY)
+            .key(entries_key(&posting.reference))
+            .key(JOURNAL_KEY)
+            .key(RESERVED_INDEX)
+            .arg(posting.kind.as_str())
+            .arg(&posting.debit)
+            .arg(&posting.credit)
+            .arg(posting.amount)
+            .arg(fallback_kind)
+            .arg(fallback_debit)
+            .arg(at_ms)
+            .arg(ENTRIES_TTL_SECONDS)
+            .arg(JOURNAL_MAX_LEN)
+            .arg(&posting.reference)
+            .invoke_async(redis)
+            .await?;
+        if created == 1 {
+            booked += 1;
+            metrics::counter!("ledger_postings_total", "kind" => posting.kind.as_str()).increment(1);
+        }
+    }
+    Ok(booked)
+}
+
+/// Book `postings` without failing the caller; the checker surfaces anything missed
+pub async fn post_or_warn(redis: &mut ConnectionManager, postings: &[Posting]) {
+    if let Err(e) = post(redis, postings).await {
+        let reference = postings.first().map(|posting| posting.reference.as_str()).unwrap_or_default();
+        tracing::warn!(reference, error = %e, "Failed to book ledger postings");
+        metrics::counter!("ledger_post_failures_total").increment(1);
+    }
+}
+
+pub async fn balance(redis: &mut ConnectionManager, account: &str) -> Result<i64> {
+    let balance: Option<i64> = redis.hget(BALANCES_KEY, account).await?;
+    Ok(balance.unwrap_or(0))
+}
+
+/// On-chain and ledger balances of a casino vault at its first check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Anchor {
+    on_chain: i64,
+    ledger: i64,
+}
+
+impl Anchor {
+    /// On-chain movement since the anchor that the ledger doesn't account for
+    fn divergence(&self, on_chain: i64, ledger: i64) -> i64 {
+        (on_chain - self.on_chain) - (ledger - self.ledger)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CasinoCheck {
+    pub casino: String,
+    pub on_chain: i64,
+    pub ledger: i64,
+    /// On-chain movement since the first check minus the ledger's
+    pub divergence: i64,
+    /// Divergence beyond the tolerance on this check and the one before
+    pub flagged: bool,
+}
+
+/// A user vault holding less than the stakes reserved against it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReservedShortfall {
+    pub wallet: String,
+    pub reserved: i64,
+    pub on_chain: i64,
+    /// Also short on the previous check
+    pub flagged: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvariantReport {
+    pub checked_at: DateTime<Utc>,
+    /// `None` while the casino vault doesn't exist on-chain
+    pub casino: Option<CasinoCheck>,
+    pub shortfalls: Vec<ReservedShortfall>,
+    /// Flagged casino divergence and shortfalls
+    pub violations: usize,
+}
+
+/// Settlements land on-chain before their batch update books them, so a breach
+/// only counts once it has outlasted a check interval
+fn confirmed(current: bool, previous: bool) -> bool {
+    current && previous
+}
+
+pub async fn load_last_check(redis: &mut ConnectionManager) -> Result<Option<InvariantReport>> {
+    let raw: Option<String> = redis.get(LAST_CHECK_KEY).await?;
+    Ok(raw.and_then(|raw| serde_json::from_str(&raw).ok()))
+}
+
+async fn check_casino(
+    redis: &mut ConnectionManager,
+    solana: &RpcClient,
+    program_id: &Pubkey,
+    tolerance: i64,
+    previous: Option<&CasinoCheck>,
+) -> Result<Option<CasinoCheck>> {
+    let vault = casino_vault_pda(&casino_pda(program_id), program_id);
+    let account = solana
+        .get_account_with_commitment(&vault, solana.commitment())
+        .await
+        .map_err(AppError::rpc_unavailable)?
+        .value;
+    let Some(account) = account else {
+        return Ok(None);
+    };
+    let on_chain = CasinoVaultAccount::decode(&account.data)?.sol_balance as i64;
+    let ledger = balance(redis, &casino_vault(DEFAULT_CASINO, CHECKED_TOKEN)).await?;
+
+    // The first check pins the starting point; later ones compare movement from it
+    let key = anchor_key(DEFAULT_CASINO, CHECKED_TOKEN);
+    let payload = serde_json::to_string(&Anchor { on_chain, ledger }).map_err(anyhow::Error::from)?;
+    let _: bool = redis.set_nx(&key, payload).await?;
+    let raw: String = redis.get(&key).await?;
+    let anchor: Anchor = serde_json::from_str(&raw).map_err(anyhow::Error::from)?;
+
+    let divergence = anchor.divergence(on_chain, ledger);
+    let previous_exceeded = previous.is_some_and(|check| check.divergence.abs() > tolerance);
+    Ok(Some(CasinoCheck {
+        casino: DEFAULT_CASINO.to_string(),
+        on_chain,
+        ledger,
+        divergence,
+        flagged: confirmed(divergence.abs() > tolerance, previous_exceeded),
+    }))
+}
+
+async fn check_reserved(
+    redis: &mut ConnectionManager,
+    solana: &RpcClient,
+    program_id: &Pubkey,
+    tolerance: i64,
+    previous: &HashSet<String>,
+) -> Result<Vec<ReservedShortfall>> {
+    let accounts: Vec<String> = redis.smembers(RESERVED_INDEX).await?;
+    let wallets: Vec<(String, Pubkey)> = accounts
+        .iter()
+        .filter_map(|account| account.strip_prefix("wallet:")?.strip_suffix(":SOL:reserved"))
+        .filter_map(|wallet| Some((wallet.to_string(), Pubkey::from_str(wallet).ok()?)))
+        .collect();
+
+    let casino = casino_pda(program_id);
+    let mut shortfalls = Vec::new();
+    for chunk in wallets.chunks(RPC_BATCH_SIZE) {
+        let reserved_accounts: Vec<String> =
+            chunk.iter().map(|(wallet, _)| wallet_reserved(wallet, CHECKED_TOKEN)).collect();
+        let reserved: Vec<Option<i64>> = redis::cmd("HMGET")
+            .arg(BALANCES_KEY)
+            .arg(&reserved_accounts)
+            .query_async(redis)
+            .await?;
+        let vaults: Vec<Pubkey> = chunk.iter().map(|(_, user)| user_vault_pda(user, &casino, program_id)).collect();
+        let response = solana
+            .get_multiple_accounts_with_commitment(&vaults, solana.commitment())
+            .await
+            .map_err(AppError::rpc_unavailable)?;
+
+        for (((wallet, _), reserved), account) in chunk.iter().zip(reserved).zip(response.value) {
+            let reserved = reserved.unwrap_or(0);
+            let on_chain = match account {
+                Some(account) => VaultAccount::decode(&account.data)?.sol_balance as i64,
+                None => 0,
+            };
+            if reserved > on_chain + tolerance {
+                shortfalls.push(ReservedShortfall {
+                    wallet: wallet.clone(),
+                    reserved,
+                    on_chain,
+                    flagged: previous.contains(wallet),
+                });
+            }
+        }
+    }
+    Ok(shortfalls)
+}
+
+/// Compare the ledger with on-chain vaults and store the result
+pub async fn check(
+    redis: &mut ConnectionManager,
+    solana: &RpcClient,
+    program_id: &Pubkey,
+    tolerance_lamports: u64,
+) -> Result<InvariantReport> {
+    let tolerance = tolerance_lamports as i64;
+    let previous = load_last_check(redis).await?;
+    let previous_short: HashSet<String> = previous
+        .iter()
+        .flat_map(|report| report.shortfalls.iter().map(|shortfall| shortfall.wallet.clone()))
+        .collect();
+
+    let casino = check_casino(
+        redis,
+        solana,
+        program_id,
+        tolerance,
+        previous.as_ref().and_then(|report| report.casino.as_ref()),
+    )
+    .await?;
+    let shortfalls = check_reserved(redis, solana, program_id, tolerance, &previous_short).await?;
+
+    let violations = casino.iter().filter(|check| check.flagged).count()
+        + shortfalls.iter().filter(|shortfall| shortfall.flagged).count();
+    let report = InvariantReport { checked_at: Utc::now(), casino, shortfalls, violations };
+    let payload = serde_json::to_string(&report).map_err(anyhow::Error::from)?;
+    let _: () = redis.set(LAST_CHECK_KEY, payload).await?;
+    Ok(report)
+}
+
+/// Run the invariant checker forever; returns immediately if `interval` is zero
+pub async fn run_ledger_checker(
+    mut redis: ConnectionManager,
+    solana: Arc<RpcClient>,
+    program_id: Pubkey,
+    tolerance_lamports: u64,
+    interval: Duration,
+) {
+    if interval.is_zero() {
+        tracing::info!("Ledger check interval is 0; invariant checker not started");
+        return;
+    }
+
+    tracing::info!(
+        interval_seconds = interval.as_secs(),
+        tolerance_lamports,
+        "Ledger invariant checker started"
+    );
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let report = match check(&mut redis, &solana, &program_id, tolerance_lamports).await {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::error!(error = %e, "Ledger invariant check failed");
+                continue;
+            }
+        };
+
+        if let Some(casino) = &report.casino {
+            metrics::gauge!("ledger_casino_divergence_lamports", "casino" => casino.casino.clone())
+                .set(casino.divergence as f64);
+            if casino.flagged {
+                metrics::counter!("ledger_invariant_violations_total", "check" => "casino_vault").increment(1);
+                tracing::error!(
+                    casino = %casino.casino,
+                    on_chain = casino.on_chain,
+                    ledger = casino.ledger,
+                    divergence = casino.divergence,
+                    "Casino vault diverges from the ledger"
+                );
+            }
+        }
+        metrics::gauge!("ledger_reserved_shortfalls").set(report.shortfalls.len() as f64);
+        for shortfall in report.shortfalls.iter().filter(|shortfall| shortfall.flagged) {
+            metrics::counter!("ledger_invariant_violations_total", "check" => "reserved_stake").increment(1);
+            tracing::error!(
+                wallet = %shortfall.wallet,
+                reserved = shortfall.reserved,
+                on_chain = shortfall.on_chain,
+                "User vault holds less than its reserved stakes"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::BetStatus;
+    use std::collections::HashMap;
+
+    fn bet(won: Option<bool>, payout_amount: Option<i64>) -> Bet {
+        Bet {
+            bet_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            user_wallet: "W".to_string(),
+            vault_address: "V".to_string(),
+            allowance_pda: None,
+            casino_id: None,
+            game_type: "coinflip".to_string(),
+            stake_amount: 100,
+            stake_token: "SOL".to_string(),
+            choice: "heads".to_string(),
+            status: BetStatus::Completed,
+            external_batch_id: None,
+            solana_tx_id: None,
+            retry_count: 0,
+            processor_id: None,
+            last_error_code: None,
+            last_error_message: None,
+            payout_amount,
+            won,
+            server_seed_hash: None,
+            client_seed: None,
+            metadata: None,
+        }
+    }
+
+    /// Apply postings the way `POST_SCRIPT` does
+    fn apply(balances: &mut HashMap<String, i64>, posted: &mut HashSet<(String, PostingKind)>, postings: &[Posting]) {
+        for posting in postings {
+            if !posted.insert((posting.reference.clone(), posting.kind)) {
+                continue;
+            }
+            let debit = match &posting.fallback {
+                Some((kind, debit)) if posted.contains(&(posting.reference.clone(), *kind)) => debit,
+                _ => &posting.debit,
+            };
+            *balances.entry(debit.clone()).or_default() -= posting.amount;
+            *balances.entry(posting.credit.clone()).or_default() += posting.amount;
+        }
+    }
+
+    #[test]
+    fn test_postings_balance_and_replay() {
+        let mut balances = HashMap::new();
+        let mut posted = HashSet::new();
+
+        let won = bet(Some(true), Some(200));
+        apply(&mut balances, &mut posted, &[reserve(&won)]);
+        assert_eq!(balances[&wallet_reserved("W", "SOL")], 100);
+
+        // Replayed settlements book once
+        apply(&mut balances, &mut posted, &settlement(&won));
+        apply(&mut balances, &mut posted, &settlement(&won));
+        assert_eq!(balances[&wallet_reserved("W", "SOL")], 0);
+        assert_eq!(balances[&wallet_vault("W", "SOL")], 100);
+        assert_eq!(balances[&casino_vault(DEFAULT_CASINO, "SOL")], -100);
+
+        // An expired bet settled by hand spends from the vault, not the reservation
+        let expired = bet(Some(false), None);
+        apply(&mut balances, &mut posted, &[reserve(&expired), release(&expired)]);
+        apply(&mut balances, &mut posted, &settlement(&expired));
+        assert_eq!(balances[&wallet_reserved("W", "SOL")], 0);
+        assert_eq!(balances[&casino_vault(DEFAULT_CASINO, "SOL")], 0);
+
+        apply(&mut balances, &mut posted, &[adjustment(Uuid::new_v4(), DEFAULT_CASINO, "SOL", -50)]);
+        assert_eq!(balances[&external("SOL")], 50);
+        assert_eq!(balances.values().sum::<i64>(), 0);
+    }
+
+    #[test]
+    fn test_anchor_divergence() {
+        let anchor = Anchor { on_chain: 1_000, ledger: -300 };
+        assert_eq!(anchor.divergence(1_500, 200), 0);
+        // On-chain lost 100 more than the ledger booked
+        assert_eq!(anchor.divergence(1_400, 200), -100);
+        assert!(!confirmed(true, false));
+        assert!(confirmed(true, true));
+    }
+}
@@ -1,23 +1,54 @@
 // Library interface for backend - exposes modules for testing
 
+pub mod auth;
+pub mod batch_claims;
+pub mod bet_archival;
+pub mod bet_expiry;
+pub mod bet_metadata;
+pub mod cache;
+pub mod claim_signals;
 pub mod config;
+pub mod daily_report;
+pub mod deposits;
+pub mod disputes;
 pub mod domain;
 pub mod errors;
 pub mod extractors;
 pub mod handlers;
+pub mod indexer_client;
+pub mod jurisdiction;
+pub mod fairness;
+pub mod games;
+pub mod killswitch;
+pub mod ledger;
+pub mod load_shedding;
 pub mod middleware;
+pub mod notifications;
+pub mod payout_holds;
+pub mod pipeline_latency;
+pub mod processor_stats;
+pub mod receipts;
+pub mod refunds;
+pub mod reports;
 pub mod repository;
+pub mod retry_budget;
+pub mod sessions;
+pub mod settlements;
+pub mod slo;
+pub mod stale_claims;
 pub mod state;
+pub mod telemetry;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod token_registry;
+pub mod vault_transactions;
 
 use axum::{
     routing::{get, post},
     Router,
 };
 use state::AppState;
-use tower_http::{
-    cors::{Any, CorsLayer},
-    trace::TraceLayer,
-};
+use tower_http::cors::{Any, CorsLayer};
 
 /// Build the application router
 pub fn build_router(state: AppState) -> Router {
@@ -26,17 +57,93 @@ pub fn build_router(state: AppState) -> Router {
         .route("/health", get(handlers::health::health_check))
         .route("/health/detailed", get(handlers::health::detailed_health))
         // Bets
-        .route("/api/bets", post(handlers::bets::create_bet))
+        .route(
+            "/api/bets",
+            post(handlers::bets::create_bet).route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                middleware::jurisdiction_gate,
+            )),
+        )
         .route("/api/bets/:bet_id", get(handlers::bets::get_bet))
         .route("/api/bets", get(handlers::bets::list_user_bets))
+        .route("/api/bets/:bet_id/dispute", post(handlers::disputes::open_dispute))
+        .route("/api/auth/token", post(handlers::auth::issue_token))
+        .route("/api/sessions", post(handlers::sessions::create_session))
+        .route("/api/sessions/:session_id", get(handlers::sessions::get_session))
+        .route("/api/receipts/:bet_id/verify", get(handlers::receipts::verify_receipt))
+        .route("/api/tokens", get(handlers::tokens::list_tokens))
+        .route("/api/games", get(handlers::games::list_games))
+        .route("/api/errors", get(handlers::error_catalog::list_error_codes))
+        // Provably-fair seeds
+        .route("/api/fairness/seeds", get(handlers::fairness::list_seeds))
         // External processor endpoints
         .route("/api/external/bets/pending", get(handlers::external::get_pending_bets))
+        .route("/api/external/bets/subscribe", get(handlers::external::subscribe_claimable))
         .route("/api/external/batches/:batch_id", post(handlers::external::update_batch))
+        .route("/api/external/refunds/pending", get(handlers::refunds::get_pending_refunds))
+        .route("/api/external/refunds/:bet_id", post(handlers::refunds::report_refund))
+        // User vault transactions
+        .route(
+            "/api/users/:wallet/withdrawals/prepare",
+            post(handlers::withdrawals::prepare_withdrawal),
+        )
+        .route(
+            "/api/users/:wallet/withdrawals/submit",
+            post(handlers::withdrawals::submit_withdrawal),
+        )
+        .route("/api/users/:wallet/vault", get(handlers::vaults::get_vault))
+        .route("/api/users/:wallet/deposits", get(handlers::vaults::list_deposits))
+        .route(
+            "/api/users/:wallet/payout-address/prepare",
+            post(handlers::vaults::prepare_payout_address),
+        )
+        .route(
+            "/api/users/:wallet/notifications",
+            get(handlers::notifications::get_preferences).post(handlers::notifications::set_preferences),
+        )
+        .route("/api/users/:wallet/notifications/ws", get(handlers::notifications::subscribe))
+        .route("/api/users/:wallet/allowances", get(handlers::allowances::list_allowances))
+        .route(
+            "/api/users/:wallet/allowances/prepare",
+            post(handlers::allowances::prepare_allowance),
+        )
+        // Admin endpoints
+        .route("/api/admin/bets/:bet_id", get(handlers::admin::get_bet))
+        .route("/api/admin/bets/:bet_id/settle", post(handlers::admin::settle_bet))
+        .route("/api/admin/bets/:bet_id/audit", get(handlers::admin::get_audit_trail))
+        .route("/api/admin/bets/:bet_id/client", get(handlers::admin::get_client_info))
+        .route("/api/admin/bets/:bet_id/at", get(handlers::admin::get_bet_at_version))
+        .route("/api/admin/bets/by-tx/:signature", get(handlers::admin::get_bets_by_tx))
+        .route("/api/admin/bets/stream", get(handlers::export::stream_bets))
+        .route("/api/admin/bets/search", get(handlers::admin::search_bets))
+        .route("/api/admin/bets/:bet_id/void", post(handlers::refunds::void_bet))
+        .route("/api/admin/refunds", get(handlers::refunds::list_refunds))
+        .route("/api/admin/payouts/held", get(handlers::admin::list_payout_holds))
+        .route("/api/admin/payouts/:transaction_id/approve", post(handlers::admin::approve_payout))
+        .route("/api/admin/retry-budget", get(handlers::admin::get_retry_budget))
+        .route("/api/admin/retry-budget/acknowledge", post(handlers::admin::acknowledge_retry_budget))
+        .route(
+            "/api/admin/killswitch",
+            get(handlers::admin::get_killswitch).post(handlers::admin::set_killswitch),
+        )
+        .route("/api/admin/disputes", get(handlers::disputes::list_disputes))
+        .route(
+            "/api/admin/disputes/:bet_id/resolve",
+            post(handlers::disputes::resolve_dispute),
+        )
+        .route("/api/admin/pipeline/latency", get(handlers::admin::get_pipeline_latency))
+        .route("/api/admin/slo", get(handlers::admin::get_slo))
+        .route("/api/admin/processors", get(handlers::admin::list_processors))
+        .route("/api/admin/ledger/check", get(handlers::ledger::get_last_check))
+        .route("/api/admin/ledger/adjustments", post(handlers::ledger::adjust))
+        .route("/api/admin/reports/bets", get(handlers::reports::bet_report))
+        .route("/api/admin/reports/daily/:date", get(handlers::reports::daily_report))
+        .route("/api/admin/export/wallet/:wallet", get(handlers::export::export_wallet))
         // Metrics
         .route("/metrics", get(handlers::metrics::metrics_handler))
         // State
         .with_state(state)
         // Middleware
         .layer(CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any))
-        .layer(TraceLayer::new_for_http())
+        .layer(axum::middleware::from_fn(middleware::request_logging))
 }
@@ -0,0 +1,111 @@
+//! Bet intake load shedding
+//!
+//! With processors down or saturated, claimable bets pile up and new ones
+//! would only time out in the queue. `create_bet` checks the claimable depth
+//! before accepting a bet and refuses it with a 503 and `Retry-After` while the
+//! depth is over `BET_INTAKE_MAX_QUEUE_DEPTH`. The depth is cached for a
+//! second, so the check costs one queue query per second per instance rather
+//! than one per bet; when the reading expires, concurrent checks wait on the
+//! one that refreshes it.
+
+use moka::future::Cache;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::errors::{AppError, Result};
+
+/// How long a queue depth reading is reused
+const DEPTH_CACHE_TTL: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct LoadShedder {
+    depth: Cache<(), u64>,
+    max_depth: u64,
+    retry_after_seconds: u64,
+}
+
+impl LoadShedder {
+    /// `max_depth` 0 accepts every bet
+    pub fn new(max_depth: u64, retry_after_seconds: u64) -> Self {
+        Self {
+            depth: Cache::builder().max_capacity(1).time_to_live(DEPTH_CACHE_TTL).build(),
+            max_depth,
+            retry_after_seconds,
+        }
+    }
+
+    /// Refuse a bet while the queue is too deep, reading the depth with `fetch`
+    /// when the cached reading has expired
+    pub async fn check(&self, fetch: impl Future<Output = Result<u64>>) -> Result<()> {
+        if self.max_depth == 0 {
+            return Ok(());
+        }
+        let depth = self
+            .depth
+            .try_get_with((), async {
+                let depth = fetch.await?;
+                metrics::gauge!("bet_intake_queue_depth").set(depth as f64);
+                Ok::<_, AppError>(depth)
+            })
+            .await
+            .map_err(|e| AppError::Service(e.to_service_error()))?;
+        if depth <= self.max_depth {
+            return Ok(());
+        }
+
+        metrics::counter!("bet_intake_shed_total").increment(1);
+        tracing::warn!(queue_depth = depth, max_depth = self.max_depth, "Shedding bet intake");
+        Err(AppError::Overloaded { queue_depth: depth, retry_after_seconds: self.retry_after_seconds })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_sheds_over_max_depth() {
+        let shedder = LoadShedder::new(100, 5);
+        assert!(shedder.check(async { Ok(100) }).await.is_ok());
+
+        // The cached reading stands until it expires
+        assert!(shedder.check(async { Ok(1_000) }).await.is_ok());
+        shedder.depth.invalidate(&()).await;
+        match shedder.check(async { Ok(101) }).await {
+            Err(AppError::Overloaded { queue_depth, retry_after_seconds }) => {
+                assert_eq!((queue_depth, retry_after_seconds), (101, 5));
+            }
+            other => panic!("expected Overloaded, got {:?}", other),
+        }
+
+        // Disabled: the queue is never read
+        let disabled = LoadShedder::new(0, 5);
+        assert!(disabled.check(async { unreachable!() }).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_expired_depth_is_read_once() {
+        let shedder = LoadShedder::new(100, 5);
+        let reads = AtomicUsize::new(0);
+        let check = || {
+            shedder.check(async {
+                reads.fetch_add(1, Ordering::SeqCst);
+                // Hold the read open so the other checks arrive while it runs
+                for _ in 0..10 {
+                    tokio::task::yield_now().await;
+                }
+                Ok(10)
+            })
+        };
+
+        let results = futures_util::future::join_all((0..20).map(|_| check())).await;
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+        // A failed read is not cached
+        shedder.depth.invalidate(&()).await;
+        assert!(shedder.check(async { Err(AppError::invalid_input("queue down")) }).await.is_err());
+        assert!(shedder.check(async { Ok(10) }).await.is_ok());
+    }
+}
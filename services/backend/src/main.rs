@@ -1,28 +1,60 @@
-use axum::{
-    routing::{get, post},
-    Router,
+use axum::{routing::get, Router};
+use backend::{
+    bet_archival::run_archiver, bet_expiry::run_expiry_sweeper, build_router, claim_signals, config::Config,
+    daily_report::run_daily_reports, deposits::run_deposit_notifier, jurisdiction::JurisdictionGate, ledger::run_ledger_checker,
+    notifications::{run_delivery_worker, run_stream_tailer},
+    receipts::ReceiptSigner,
+    settlements::run_reconciler,
+    slo::run_evaluator,
+    stale_claims::run_stale_claim_reaper,
+    repository::{record_schema_version, FieldCipher, MigrationOptions, RedisBetRepository, RedisOpsRepository, CURRENT_SCHEMA_VERSION},
+    state::{AppState, Storage},
+    telemetry,
 };
+use clap::{Args, Parser, Subcommand};
+use metrics_exporter_prometheus::PrometheusHandle;
+use shared::clock::SystemClock;
+use shared::profile::{Profile, ProfileDefaults};
+use solana_sdk::pubkey::Pubkey;
 use std::net::SocketAddr;
-use tower_http::{
-    cors::{Any, CorsLayer},
-    trace::TraceLayer,
-};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-mod config;
-mod domain;
-mod errors;
-mod extractors;
-mod handlers;
-mod middleware;
-mod repository;
-mod state;
+/// Bet intake API
+#[derive(Debug, Parser)]
+#[command(name = "backend", version)]
+struct Cli {
+    /// Defaults for local, devnet or mainnet; set variables still win (default: CONFIG_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<Profile>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-use config::Config;
-use state::AppState;
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the API server (default)
+    Serve,
+    /// Convert legacy JSON bet records to hashes, upgrade hash schema versions and rebuild bet indexes
+    Migrate(MigrateArgs),
+}
+
+#[derive(Debug, Args)]
+struct MigrateArgs {
+    /// Report what would change without writing
+    #[arg(long)]
+    dry_run: bool,
+    /// Also re-add pending and retryable bets to the claimable queue
+    #[arg(long)]
+    requeue_pending: bool,
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
     // Initialize structured logging with JSON formatting (configurable via env)
     let use_json = std::env::var("LOG_FORMAT")
         .unwrap_or_else(|_| "text".to_string())
@@ -53,7 +85,14 @@ async fn main() -> anyhow::Result<()> {
     );
 
     // Load configuration
-    let config = Config::load()?;
+    let mut config = Config::load(cli.profile)?;
+    tracing::info!(
+        profile = config.profile.map_or("none", Profile::as_str),
+        overrides = ?ProfileDefaults::overrides(),
+        config = %config.effective(),
+        "Effective configuration"
+    );
+    config.resolve_secrets().await?;
     tracing::info!("Configuration loaded");
 
     // Initialize Redis connection
@@ -64,48 +103,196 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Redis connected");
 
+    // Bet records written by a newer release may have fields this one would misread
+    let highest_schema = record_schema_version(&mut redis_conn.clone()).await?;
+    if highest_schema > CURRENT_SCHEMA_VERSION {
+        if config.redis.refuse_future_schema {
+            anyhow::bail!(
+                "Bet records use schema version {} but this release supports up to {}; \
+                 upgrade, or set BET_SCHEMA_REFUSE_FUTURE_VERSIONS=false to start anyway",
+                highest_schema,
+                CURRENT_SCHEMA_VERSION
+            );
+        }
+        tracing::warn!(
+            highest_schema,
+            supported = CURRENT_SCHEMA_VERSION,
+            "Newer bet schema version in Redis; records it wrote will fail to load"
+        );
+    }
+
+    // Initialize queue backend for claimable/processing indexes
+    let queue = backend::repository::queue_backend::connect(&config.queue, redis_conn.clone()).await?;
+    tracing::info!(queue_backend = queue.name(), "Queue backend ready");
+
+    if let Some(Command::Migrate(args)) = cli.command {
+        return run_migration(RedisBetRepository::new(redis_conn, queue), args).await;
+    }
+
+    let clock = SystemClock::shared();
+
+    // Start bet expiry sweeper
+    let expiry_repo = Arc::new(RedisBetRepository::new(redis_conn.clone(), queue.clone()).with_clock(clock.clone()));
+    tokio::spawn(run_expiry_sweeper(
+        expiry_repo,
+        Arc::new(RedisOpsRepository::new(redis_conn.clone())),
+        clock.clone(),
+        config.betting.bet_ttl_seconds,
+        Duration::from_secs(config.betting.bet_expiry_sweep_interval_seconds),
+        config.slo.clone(),
+        config.notifications.enabled,
+    ));
+
+    // Start the reaper for claims whose processor stopped reporting
+    let reaper_repo = Arc::new(RedisBetRepository::new(redis_conn.clone(), queue.clone()).with_clock(clock.clone()));
+    tokio::spawn(run_stale_claim_reaper(
+        reaper_repo,
+        clock.clone(),
+        config.betting.stale_claim_seconds,
+        Duration::from_secs(config.betting.stale_claim_sweep_interval_seconds),
+    ));
+
+    // Connect the bet archive and start moving old terminal bets out of Redis
+    let archive = backend::repository::bet_archive::connect(&config.archive).await?;
+    if let Some(archive) = &archive {
+        tracing::info!(bet_archive = archive.name(), "Bet archive connected");
+        let archive_repo = Arc::new(
+            RedisBetRepository::new(redis_conn.clone(), queue.clone())
+                .with_archive(Some(archive.clone()))
+                .with_clock(clock.clone()),
+        );
+        tokio::spawn(run_archiver(
+            archive_repo,
+            config.archive.retention_days,
+            config.archive.batch_size,
+            Duration::from_secs(config.archive.interval_seconds),
+        ));
+    }
+
+    // Start daily settlement report job
+    let report_repo = Arc::new(RedisBetRepository::new(redis_conn.clone(), queue.clone()));
+    tokio::spawn(run_daily_reports(
+        redis_conn.clone(),
+        report_repo,
+        config.reports.daily_webhook_url.clone(),
+        Duration::from_secs(config.reports.daily_check_interval_seconds),
+    ));
+
+    // Load the bet receipt signing key
+    let receipt_signer = ReceiptSigner::from_config(&config.receipts)?;
+    tracing::info!(receipt_signer = %receipt_signer.pubkey(), "Receipt signer ready");
+
+    // Load jurisdiction rules for bet intake
+    let jurisdiction = JurisdictionGate::from_config(&config.jurisdiction)?;
+    tracing::info!(enabled = jurisdiction.is_enabled(), "Jurisdiction gate ready");
+
+    // Key ring for the client info stored with bets
+    let pii_cipher = FieldCipher::from_config(&config.pii)?;
+    match &pii_cipher {
+        Some(cipher) => tracing::info!(active_key_id = cipher.active_key_id(), "Bet PII encryption ready"),
+        None => tracing::info!("BET_PII_KEYS not set; client info is not stored with bets"),
+    }
+
+    // One recorder for the whole process, shared by both /metrics endpoints
+    let prometheus = telemetry::install_recorder()?;
+
     // Initialize application state
-    let app_state = AppState::new(config.clone(), redis_conn);
+    let storage = Storage::redis(&config, redis_conn.clone(), queue, archive, pii_cipher, clock.clone());
+    let app_state = AppState::new(config.clone(), storage, receipt_signer, jurisdiction)
+        .with_metrics(prometheus.clone())
+        .with_clock(clock);
+
+    // Start the ledger invariant checker against on-chain vaults
+    match Pubkey::from_str(&config.solana.vault_program_id) {
+        Ok(program_id) => {
+            tokio::spawn(run_ledger_checker(
+                redis_conn.clone(),
+                app_state.solana.clone(),
+                program_id,
+                config.ledger.tolerance_lamports,
+                Duration::from_secs(config.ledger.check_interval_seconds),
+            ));
+        }
+        Err(e) => tracing::warn!(error = %e, "Invalid VAULT_PROGRAM_ID; ledger invariant checker not started"),
+    }
+
+    tokio::spawn(run_reconciler(
+        redis_conn.clone(),
+        app_state.solana.clone(),
+        Duration::from_secs(config.settlements.reconcile_interval_seconds),
+    ));
+
+    // Export settlement SLO burn rates and raise burn alerts
+    tokio::spawn(run_evaluator(
+        redis_conn.clone(),
+        config.slo.clone(),
+        Duration::from_secs(config.slo.evaluate_interval_seconds),
+    ));
+
+    // Deliver user notifications (including detected deposits) to webhooks and this instance's websockets
+    if config.notifications.enabled {
+        tokio::spawn(run_delivery_worker(redis_conn.clone(), config.notifications.clone()));
+        tokio::spawn(run_stream_tailer(
+            redis_conn.clone(),
+            app_state.notifications.clone(),
+            Duration::from_millis(config.notifications.poll_interval_ms),
+        ));
+        tokio::spawn(run_deposit_notifier(
+            redis_conn.clone(),
+            Duration::from_millis(config.notifications.poll_interval_ms),
+        ));
+    }
+
+    // Relay claim signals from every instance to this one's subscribed processors
+    if config.queue.claim_signals {
+        tokio::spawn(claim_signals::run_relay(
+            redis_client.clone(),
+            app_state.claim_signals.clone(),
+            Duration::from_secs(1),
+        ));
+    }
 
     // Build router
-    let app = Router::new()
-        // Health check
-        .route("/health", get(handlers::health::health_check))
-        .route("/health/detailed", get(handlers::health::detailed_health))
-        // Bets
-        .route("/api/bets", post(handlers::bets::create_bet))
-        .route("/api/bets/:bet_id", get(handlers::bets::get_bet))
-        .route("/api/bets", get(handlers::bets::list_user_bets))
-        // External processor endpoints
-        .route("/api/external/bets/pending", get(handlers::external::get_pending_bets))
-        .route("/api/external/batches/:batch_id", post(handlers::external::update_batch))
-        // Metrics
-        .route("/metrics", get(handlers::metrics::metrics_handler))
-        // State
-        .with_state(app_state)
-        // Middleware
-        .layer(CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any))
-        .layer(TraceLayer::new_for_http());
+    let app = build_router(app_state);
 
     // Start metrics server
-    let metrics_handle = tokio::spawn(start_metrics_server(config.metrics_port));
+    let metrics_handle = tokio::spawn(start_metrics_server(config.metrics_port, prometheus));
 
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], config.api_port));
     tracing::info!("Backend API listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     metrics_handle.await??;
 
     Ok(())
 }
 
-async fn start_metrics_server(port: u16) -> anyhow::Result<()> {
-    let builder = metrics_exporter_prometheus::PrometheusBuilder::new();
-    let handle = builder.install_recorder()?;
+async fn run_migration(repo: RedisBetRepository, args: MigrateArgs) -> anyhow::Result<()> {
+    let report = repo
+        .migrate_legacy(MigrationOptions { dry_run: args.dry_run, requeue_pending: args.requeue_pending })
+        .await?;
+
+    for (key, problem) in &report.problems {
+        tracing::warn!(key = %key, problem = %problem, "Bet key not migrated");
+    }
+    tracing::info!(
+        dry_run = args.dry_run,
+        scanned = report.scanned,
+        converted = report.converted,
+        upgraded = report.upgraded,
+        indexed = report.indexed,
+        requeued = report.requeued,
+        tombstones = report.tombstones,
+        problems = report.problems.len(),
+        "Bet migration finished"
+    );
+    Ok(())
+}
 
+async fn start_metrics_server(port: u16, handle: PrometheusHandle) -> anyhow::Result<()> {
     let app = Router::new().route(
         "/metrics",
         get(|| async move { handle.render() }),
@@ -1,2 +1,172 @@
 // Middleware for authentication, rate limiting, etc.
 // TODO: Implement Privy authentication middleware
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Query, RawPathParams, Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use redis::aio::ConnectionManager;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::time::Instant;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{errors::AppError, state::AppState, telemetry};
+
+/// Request header carrying the caller's correlation id; echoed on the response
+pub const CORRELATION_ID_HEADER: HeaderName = HeaderName::from_static("x-correlation-id");
+
+/// Query parameters that identify who is calling
+#[derive(Debug, Default, Deserialize)]
+struct CallerQuery {
+    user_wallet: Option<String>,
+    processor_id: Option<String>,
+}
+
+/// Wallet a request acts for: the `:wallet` path segment, else `?user_wallet=`
+fn request_wallet(params: Option<&RawPathParams>, query: &CallerQuery) -> Option<String> {
+    params
+        .and_then(|params| params.iter().find(|(key, _)| *key == "wallet").map(|(_, value)| value.to_string()))
+        .or_else(|| query.user_wallet.clone())
+}
+
+/// Log every request with its route template, status, latency and caller, and
+/// record per-route latency. Handler logs inherit the request span, so they
+/// carry the correlation id too.
+pub async fn request_logging(
+    matched_path: Option<MatchedPath>,
+    params: Option<RawPathParams>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let method = request.method().clone();
+    // The template, not the concrete path, so ids don't explode label cardinality
+    let route = matched_path
+        .as_ref()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let query = Query::<CallerQuery>::try_from_uri(request.uri())
+        .map(|Query(query)| query)
+        .unwrap_or_default();
+    let wallet = request_wallet(params.as_ref(), &query);
+    let processor_id = query.processor_id.filter(|_| route.starts_with("/api/external/"));
+
+    let correlation_id = request
+        .headers()
+        .get(&CORRELATION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+        request.headers_mut().insert(CORRELATION_ID_HEADER, value);
+    }
+
+    let span = tracing::info_span!(
+        "request",
+        method = %method,
+        route = %route,
+        correlation_id = %correlation_id,
+        wallet = wallet.as_deref(),
+        processor_id = processor_id.as_deref(),
+    );
+    let mut response = next.run(request).instrument(span.clone()).await;
+
+    let latency = started.elapsed();
+    let status = response.status();
+    span.in_scope(|| {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        if status.is_server_error() {
+            tracing::warn!(status = status.as_u16(), latency_ms, "Request failed");
+        } else {
+            tracing::info!(status = status.as_u16(), latency_ms, "Request completed");
+        }
+    });
+    telemetry::record_request(method.as_str(), &route, status.as_u16(), latency);
+
+    if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+        response.headers_mut().insert(CORRELATION_ID_HEADER, value);
+    }
+    response
+}
+
+/// Redis list of refused bet attempts, newest last
+const JURISDICTION_AUDIT_KEY: &str = "audit:jurisdiction";
+
+/// Entries kept in the jurisdiction audit list
+const JURISDICTION_AUDIT_MAX: isize = 10_000;
+
+/// Append a refused attempt to the jurisdiction audit log, keeping the newest entries
+pub(crate) async fn audit_jurisdiction_block(redis: &mut ConnectionManager, entry: &serde_json::Value) -> Result<(), AppError> {
+    let _: () = redis::pipe()
+        .rpush(JURISDICTION_AUDIT_KEY, entry.to_string())
+        .ignore()
+        .ltrim(JURISDICTION_AUDIT_KEY, -JURISDICTION_AUDIT_MAX, -1)
+        .ignore()
+        .query_async(redis)
+        .await?;
+    Ok(())
+}
+
+/// Reject requests from blocked jurisdictions before they reach the handler
+pub async fn jurisdiction_gate(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let ip = state.jurisdiction.client_ip(request.headers(), peer);
+
+    let denial = match state.jurisdiction.check(ip) {
+        Ok(()) => {
+            metrics::counter!("jurisdiction_checks_total", "decision" => "allow").increment(1);
+            return Ok(next.run(request).await);
+        }
+        Err(denial) => denial,
+    };
+
+    metrics::counter!(
+        "jurisdiction_checks_total",
+        "decision" => "deny",
+        "reason" => denial.reason()
+    )
+    .increment(1);
+    tracing::warn!(client_ip = ?ip, reason = denial.reason(), denial = ?denial, "Bet attempt from blocked jurisdiction");
+
+    // Best-effort audit; the request is refused either way
+    let entry = serde_json::json!({
+        "action": "jurisdiction_blocked",
+        "client_ip": ip.map(|ip| ip.to_string()),
+        "reason": denial.reason(),
+        "country": match &denial {
+            crate::jurisdiction::Denial::BlockedCountry(country) => Some(country.as_str()),
+            _ => None,
+        },
+        "path": request.uri().path(),
+        "created_at": chrono::Utc::now(),
+    });
+    if let Err(e) = state.ops.audit_jurisdiction_block(&entry).await {
+        tracing::warn!(error = %e, "Failed to audit blocked jurisdiction attempt");
+    }
+
+    Err(AppError::jurisdiction_blocked())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wallet_from_query_without_path_param() {
+        let query = CallerQuery { user_wallet: Some("W".to_string()), processor_id: None };
+        assert_eq!(request_wallet(None, &query), Some("W".to_string()));
+        assert_eq!(request_wallet(None, &CallerQuery::default()), None);
+    }
+}
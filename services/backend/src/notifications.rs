@@ -0,0 +1,526 @@
+//! User notifications
+//!
+//! Users choose which events they hear about and how: a webhook the backend
+//! POSTs to, websocket push on `/api/users/:wallet/notifications/ws`, or both.
+//! Handlers only append events to a Redis stream, so a slow webhook never holds
+//! up a bet or batch update. One delivery worker per backend instance reads the
+//! stream through a shared consumer group and calls webhooks; every instance
+//! also tails the stream into its own websocket subscribers.
+//!
+//! Delivery is best-effort: a failed webhook call is counted and logged, not
+//! retried.
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamMaxlen, StreamRangeReply, StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use shared::indexer::IndexedDeposit;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::config::NotificationsConfig;
+use crate::domain::{Bet, Refund};
+use crate::errors::{AppError, Result};
+use crate::handlers::allowances::AllowanceWarning;
+
+/// Redis key prefix for a wallet's preferences
+const PREFS_PREFIX: &str = "notifications:prefs:";
+
+/// Redis stream of undelivered notifications
+const STREAM_KEY: &str = "notifications:stream";
+
+/// Consumer group shared by the delivery workers of every instance
+const CONSUMER_GROUP: &str = "notifiers";
+
+/// Entries kept in the stream; older ones are trimmed
+const STREAM_MAXLEN: usize = 100_000;
+
+/// Entries read per poll
+const READ_COUNT: usize = 100;
+
+/// Redis key prefix marking an allowance whose expiry was already announced
+const EXPIRING_SENT_PREFIX: &str = "notifications:allowance_expiring:";
+
+/// One expiry notice per allowance within this window
+const EXPIRING_SENT_TTL_SECONDS: u64 = 86_400;
+
+/// Notifications buffered per websocket subscriber before it starts missing some
+const HUB_CAPACITY: usize = 1024;
+
+fn prefs_key(wallet: &str) -> String {
+    format!("{}{}", PREFS_PREFIX, wallet)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    BetCompleted,
+    /// A completed bet paying out at least `NOTIFICATIONS_BIG_WIN_AMOUNT`
+    BigWin,
+    AllowanceExpiring,
+    RefundCompleted,
+    /// A deposit into the vault was seen on chain
+    DepositCredited,
+    /// A bet went unclaimed past `BET_TTL_SECONDS` and its stake was released
+    BetExpired,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 6] = [
+        NotificationEvent::BetCompleted,
+        NotificationEvent::BigWin,
+        NotificationEvent::AllowanceExpiring,
+        NotificationEvent::RefundCompleted,
+        NotificationEvent::DepositCredited,
+        NotificationEvent::BetExpired,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationEvent::BetCompleted => "bet_completed",
+            NotificationEvent::BigWin => "big_win",
+            NotificationEvent::AllowanceExpiring => "allowance_expiring",
+            NotificationEvent::RefundCompleted => "refund_completed",
+            NotificationEvent::DepositCredited => "deposit_credited",
+            NotificationEvent::BetExpired => "bet_expired",
+        }
+    }
+}
+
+fn all_events() -> Vec<NotificationEvent> {
+    NotificationEvent::ALL.to_vec()
+}
+
+/// What a wallet is notified about, and where
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationPreferences {
+    /// Every event when omitted; empty mutes the wallet
+    #[serde(default = "all_events")]
+    pub events: Vec<NotificationEvent>,
+    /// http(s) endpoint receiving each notification as a JSON POST
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Push over `/api/users/:wallet/notifications/ws`
+    #[serde(default)]
+    pub websocket: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self { events: all_events(), webhook_url: None, websocket: false }
+    }
+}
+
+impl NotificationPreferences {
+    pub fn wants(&self, event: NotificationEvent) -> bool {
+        self.events.contains(&event)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(url) = &self.webhook_url {
+            let parsed = reqwest::Url::parse(url)
+                .map_err(|_| AppError::invalid_input("webhook_url is not a valid URL"))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(AppError::invalid_input("webhook_url must be an http or https URL"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub user_wallet: String,
+    pub bet_id: Option<Uuid>,
+    /// Event details, e.g. the payout of a completed bet
+    pub data: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Notification {
+    /// `bet_completed`, plus `big_win` when the payout reaches `big_win_amount` (0 disables)
+    pub fn bet_completed(bet: &Bet, big_win_amount: i64) -> Vec<Notification> {
+        let data = serde_json::json!({
+            "game_type": bet.game_type,
+            "stake_amount": bet.stake_amount,
+            "stake_token": bet.stake_token,
+            "won": bet.won,
+            "payout_amount": bet.payout_amount,
+            "solana_tx_id": bet.solana_tx_id,
+        });
+        let mut notifications = vec![Self::for_bet(NotificationEvent::BetCompleted, bet, data.clone())];
+        let payout = bet.payout_amount.unwrap_or(0);
+        if big_win_amount > 0 && bet.won == Some(true) && payout >= big_win_amount {
+            notifications.push(Self::for_bet(NotificationEvent::BigWin, bet, data));
+        }
+        notifications
+    }
+
+    /// `allowance_expiring` when the warning returned with `bet` says so
+    pub fn allowance_expiring(bet: &Bet, warning: &AllowanceWarning) -> Option<Notification> {
+        warning.reasons.contains(&"expiring_soon").then(|| {
+            Self::for_bet(
+                NotificationEvent::AllowanceExpiring,
+                bet,
+                serde_json::json!({
+                    "allowance_pda": warning.allowance_pda,
+                    "remaining_after_bet": warning.remaining_after_bet,
+                    "expires_at": warning.expires_at,
+                }),
+            )
+        })
+    }
+
+    pub fn bet_expired(bet: &Bet) -> Notification {
+        Self::for_bet(
+            NotificationEvent::BetExpired,
+            bet,
+            serde_json::json!({
+                "game_type": bet.game_type,
+                "stake_amount": bet.stake_amount,
+                "stake_token": bet.stake_token,
+                "error_code": bet.last_error_code,
+            }),
+        )
+    }
+
+    pub fn refund_completed(refund: &Refund) -> Notification {
+        Notification {
+            event: NotificationEvent::RefundCompleted,
+            user_wallet: refund.user_wallet.clone(),
+            bet_id: Some(refund.bet_id),
+            data: serde_json::json!({
+                "amount": refund.amount,
+                "stake_token": refund.stake_token,
+                "reason": refund.reason,
+                "solana_tx_id": refund.solana_tx_id,
+            }),
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn deposit_credited(deposit: &IndexedDeposit) -> Notification {
+        Notification {
+            event: NotificationEvent::DepositCredited,
+            user_wallet: deposit.owner.clone(),
+            bet_id: None,
+            data: serde_json::json!({
+                "vault": deposit.vault,
+                "amount": deposit.amount,
+                "token_mint": deposit.token_mint,
+                "signature": deposit.signature,
+                "slot": deposit.slot,
+            }),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn for_bet(event: NotificationEvent, bet: &Bet, data: serde_json::Value) -> Notification {
+        Notification { event, user_wallet: bet.user_wallet.clone(), bet_id: Some(bet.bet_id), data, created_at: Utc::now() }
+    }
+
+    /// Key that lets only the first of a run of repeats through
+    pub(crate) fn dedupe_key(&self) -> Option<String> {
+        match self.event {
+            NotificationEvent::AllowanceExpiring => self
+                .data
+                .get("allowance_pda")
+                .and_then(|pda| pda.as_str())
+                .map(|pda| format!("{}{}", EXPIRING_SENT_PREFIX, pda)),
+            _ => None,
+        }
+    }
+}
+
+pub async fn load_preferences(redis: &mut ConnectionManager, wallet: &str) -> Result<NotificationPreferences> {
+    let stored: Option<String> = redis.get(prefs_key(wallet)).await?;
+    match stored {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Corrupt notification preferences for {}: {}", wallet, e))),
+        None => Ok(NotificationPreferences::default()),
+    }
+}
+
+pub async fn save_preferences(
+    redis: &mut ConnectionManager,
+    wallet: &str,
+    preferences: &NotificationPreferences,
+) -> Result<()> {
+    let json = serde_json::to_string(preferences).map_err(anyhow::Error::from)?;
+    let _: () = redis.set(prefs_key(wallet), json).await?;
+    Ok(())
+}
+
+async fn publish(redis: &mut ConnectionManager, notification: &Notification) -> Result<()> {
+    if let Some(key) = notification.dedupe_key() {
+        let first: bool = redis::cmd("SET")
+            .arg(&key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(EXPIRING_SENT_TTL_SECONDS)
+            .query_async::<Option<String>>(redis)
+            .await?
+            .is_some();
+        if !first {
+            return Ok(());
+        }
+    }
+    let payload = serde_json::to_string(notification).map_err(anyhow::Error::from)?;
+    let _: String = redis
+        .xadd_maxlen(STREAM_KEY, StreamMaxlen::Approx(STREAM_MAXLEN), "*", &[("payload", payload)])
+        .await?;
+    Ok(())
+}
+
+/// Queue notifications for delivery; failures are logged, never returned
+pub async fn publish_or_warn(redis: &mut ConnectionManager, notifications: &[Notification]) {
+    for notification in notifications {
+        if let Err(e) = publish(redis, notification).await {
+            tracing::warn!(
+                event = notification.event.as_str(),
+                bet_id = ?notification.bet_id,
+                error = %e,
+                "Failed to queue notification"
+            );
+            metrics::counter!("notification_publish_failures_total").increment(1);
+        }
+    }
+}
+
+fn parse_entries(reply: StreamReadReply) -> Vec<(String, Option<Notification>)> {
+    reply
+        .keys
+        .into_iter()
+        .flat_map(|key| key.ids)
+        .map(|entry| {
+            let notification = entry
+                .get::<String>("payload")
+                .and_then(|payload| serde_json::from_str(&payload).ok());
+            (entry.id, notification)
+        })
+        .collect()
+}
+
+/// Fan-out of notifications to this instance's websocket subscribers
+#[derive(Clone)]
+pub struct NotificationHub {
+    sender: broadcast::Sender<Arc<Notification>>,
+}
+
+impl Default for NotificationHub {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(HUB_CAPACITY).0 }
+    }
+}
+
+impl NotificationHub {
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Notification>> {
+        self.sender.subscribe()
+    }
+}
+
+/// Forward new stream entries to websocket subscribers; polls every `interval`
+pub async fn run_stream_tailer(mut redis: ConnectionManager, hub: NotificationHub, interval: Duration) {
+    // Only what arrives from now on; subscribers aren't sent history
+    let mut last_id = loop {
+        match redis.xrevrange_count::<_, _, _, _, StreamRangeReply>(STREAM_KEY, "+", "-", 1).await {
+            Ok(reply) => break reply.ids.first().map(|entry| entry.id.clone()).unwrap_or_else(|| "0-0".to_string()),
+            Err(e) => {
+                tracing::warn!(error = %e, "Notification stream lookup failed");
+                tokio::time::sleep(interval).await;
+            }
+        }
+    };
+
+    loop {
+        tokio::time::sleep(interval).await;
+        // Redis reads stay non-blocking: the connection is shared with request handlers
+        let options = StreamReadOptions::default().count(READ_COUNT);
+        let reply: StreamReadReply = match redis.xread_options(&[STREAM_KEY], &[&last_id], &options).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                tracing::warn!(error = %e, "Notification stream read failed");
+                continue;
+            }
+        };
+        for (id, notification) in parse_entries(reply) {
+            if let Some(notification) = notification {
+                // Err only means no one is subscribed right now
+                let _ = hub.sender.send(Arc::new(notification));
+            }
+            last_id = id;
+        }
+    }
+}
+
+async fn deliver_webhook(client: &reqwest::Client, url: &str, notification: &Notification) -> anyhow::Result<()> {
+    client.post(url).json(notification).send().await?.error_for_status()?;
+    Ok(())
+}
+
+async fn deliver(redis: &mut ConnectionManager, client: &reqwest::Client, notification: &Notification) {
+    let preferences = match load_preferences(redis, &notification.user_wallet).await {
+        Ok(preferences) => preferences,
+        Err(e) => {
+            tracing::warn!(user_wallet = %notification.user_wallet, error = %e, "Failed to load notification preferences");
+            return;
+        }
+    };
+    let Some(url) = preferences.webhook_url.as_deref().filter(|_| preferences.wants(notification.event)) else {
+        return;
+    };
+
+    let outcome = match deliver_webhook(client, url, notification).await {
+        Ok(()) => "ok",
+        Err(e) => {
+            tracing::warn!(
+                user_wallet = %notification.user_wallet,
+                event = notification.event.as_str(),
+                error = %e,
+                "Notification webhook delivery failed"
+            );
+            "error"
+        }
+    };
+    metrics::counter!(
+        "notifications_delivered_total",
+        "channel" => "webhook",
+        "event" => notification.event.as_str(),
+        "outcome" => outcome
+    )
+    .increment(1);
+}
+
+/// Deliver queued notifications to webhooks; polls every `poll_interval_ms`
+pub async fn run_delivery_worker(mut redis: ConnectionManager, config: NotificationsConfig) {
+    let interval = Duration::from_millis(config.poll_interval_ms);
+    loop {
+        let created: redis::RedisResult<()> = redis.xgroup_create_mkstream(STREAM_KEY, CONSUMER_GROUP, "$").await;
+        match created {
+            Ok(()) => break,
+            Err(e) if e.code() == Some("BUSYGROUP") => break,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to create notification consumer group");
+                tokio::time::sleep(interval).await;
+            }
+        }
+    }
+
+    let consumer = format!("backend-{}", Uuid::new_v4());
+    let client = match reqwest::Client::builder().timeout(Duration::from_millis(config.webhook_timeout_ms)).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to build notification HTTP client; delivery worker not started");
+            return;
+        }
+    };
+    tracing::info!(
+        %consumer,
+        poll_interval_ms = config.poll_interval_ms,
+        webhook_timeout_ms = config.webhook_timeout_ms,
+        "Notification delivery worker started"
+    );
+
+    loop {
+        tokio::time::sleep(interval).await;
+        let options = StreamReadOptions::default().group(CONSUMER_GROUP, &consumer).count(READ_COUNT);
+        let reply: StreamReadReply = match redis.xread_options(&[STREAM_KEY], &[">"], &options).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                tracing::warn!(error = %e, "Notification stream read failed");
+                continue;
+            }
+        };
+        for (id, notification) in parse_entries(reply) {
+            match notification {
+                Some(notification) => deliver(&mut redis, &client, &notification).await,
+                None => tracing::warn!(%id, "Skipping malformed notification"),
+            }
+            if let Err(e) = redis.xack::<_, _, _, ()>(STREAM_KEY, CONSUMER_GROUP, &[&id]).await {
+                tracing::warn!(%id, error = %e, "Failed to acknowledge notification");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::BetStatus;
+
+    fn bet(won: bool, payout_amount: i64) -> Bet {
+        Bet {
+            bet_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            user_wallet: "wallet".to_string(),
+            vault_address: "vault".to_string(),
+            allowance_pda: None,
+            casino_id: None,
+            game_type: "coinflip".to_string(),
+            stake_amount: 1_000,
+            stake_token: "SOL".to_string(),
+            choice: "heads".to_string(),
+            status: BetStatus::Completed,
+            external_batch_id: None,
+            solana_tx_id: None,
+            retry_count: 0,
+            processor_id: None,
+            last_error_code: None,
+            last_error_message: None,
+            payout_amount: Some(payout_amount),
+            won: Some(won),
+            server_seed_hash: None,
+            client_seed: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_bet_completed_flags_big_wins_and_preferences_filter() {
+        let events = |notifications: Vec<Notification>| notifications.iter().map(|n| n.event).collect::<Vec<_>>();
+        assert_eq!(events(Notification::bet_completed(&bet(true, 2_000), 5_000)), vec![NotificationEvent::BetCompleted]);
+        assert_eq!(
+            events(Notification::bet_completed(&bet(true, 5_000), 5_000)),
+            vec![NotificationEvent::BetCompleted, NotificationEvent::BigWin]
+        );
+        assert_eq!(events(Notification::bet_completed(&bet(true, 5_000), 0)), vec![NotificationEvent::BetCompleted]);
+        assert_eq!(events(Notification::bet_completed(&bet(false, 0), 1)), vec![NotificationEvent::BetCompleted]);
+
+        let preferences: NotificationPreferences =
+            serde_json::from_str(r#"{"events": ["big_win"], "webhook_url": "https://example.com/hook"}"#).unwrap();
+        assert!(preferences.validate().is_ok());
+        assert!(preferences.wants(NotificationEvent::BigWin));
+        assert!(!preferences.wants(NotificationEvent::BetCompleted));
+        assert!(!preferences.websocket);
+
+        let defaults: NotificationPreferences = serde_json::from_str("{}").unwrap();
+        assert_eq!(defaults, NotificationPreferences::default());
+        let ftp = NotificationPreferences { webhook_url: Some("ftp://example.com".to_string()), ..defaults };
+        assert!(ftp.validate().is_err());
+    }
+
+    #[test]
+    fn test_deposit_credited_goes_to_vault_owner() {
+        let deposit = IndexedDeposit {
+            signature: "sig".to_string(),
+            instruction_index: 0,
+            vault: "Vault111".to_string(),
+            owner: "Owner111".to_string(),
+            token_mint: None,
+            amount: 250_000_000,
+            slot: 7,
+            block_time: None,
+        };
+        let notification = Notification::deposit_credited(&deposit);
+        assert_eq!(notification.event, NotificationEvent::DepositCredited);
+        assert_eq!(notification.user_wallet, "Owner111");
+        assert_eq!(notification.bet_id, None);
+        assert_eq!(notification.data["amount"], 250_000_000);
+        assert!(NotificationPreferences::default().wants(NotificationEvent::DepositCredited));
+    }
+}
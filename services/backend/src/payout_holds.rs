@@ -0,0 +1,52 @@
+//! Large payouts held for review
+//!
+//! Processors hold wins above their configured threshold under
+//! `shared::constants::PAYOUT_HOLD_PREFIX` and skip them until the hold delay
+//! passes or an operator approves them here. Holds are keyed by the
+//! settlement's transaction id, not a bet id.
+
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use shared::constants::{PAYOUT_HOLDS_PENDING_KEY, PAYOUT_HOLD_PREFIX};
+
+use crate::domain::PayoutHold;
+use crate::errors::{AppError, Result};
+
+fn hold_key(transaction_id: u64) -> String {
+    format!("{}{}", PAYOUT_HOLD_PREFIX, transaction_id)
+}
+
+pub async fn get(redis: &mut ConnectionManager, transaction_id: u64) -> Result<Option<PayoutHold>> {
+    let raw: Option<String> = redis.get(hold_key(transaction_id)).await?;
+    raw.map(|raw| serde_json::from_str(&raw))
+        .transpose()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Corrupt payout hold {}: {}", transaction_id, e)))
+}
+
+/// Store an approved hold; the processor releases it on its next cycle
+pub async fn save(redis: &mut ConnectionManager, hold: &PayoutHold) -> Result<()> {
+    let payload = serde_json::to_string(hold).map_err(anyhow::Error::from)?;
+    // The processor set the expiry when it created the hold
+    let _: () = redis::cmd("SET")
+        .arg(hold_key(hold.transaction_id))
+        .arg(payload)
+        .arg("KEEPTTL")
+        .query_async(redis)
+        .await?;
+    Ok(())
+}
+
+/// Payouts still awaiting review, oldest first
+pub async fn list_pending(redis: &mut ConnectionManager, limit: isize) -> Result<Vec<PayoutHold>> {
+    let ids: Vec<u64> = redis.zrange(PAYOUT_HOLDS_PENDING_KEY, 0, limit.max(1) - 1).await?;
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let keys: Vec<String> = ids.into_iter().map(hold_key).collect();
+    let raw: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(redis).await?;
+    Ok(raw
+        .into_iter()
+        .flatten()
+        .filter_map(|raw| serde_json::from_str(&raw).ok())
+        .collect())
+}
@@ -0,0 +1,209 @@
+//! Per-stage dwell time of bets in the settlement pipeline
+//!
+//! Every status write stamps the bet hash with `<status>_at_ms`. When a bet leaves
+//! one of the stages below, the time since the stage started is recorded as a
+//! histogram for `/metrics` and pushed onto a capped Redis list per stage, so
+//! `GET /api/admin/pipeline/latency` can summarize recent bets across instances.
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use serde::Serialize;
+
+use crate::domain::BetStatus;
+use crate::errors::Result;
+use crate::repository::status_to_string;
+use crate::telemetry;
+
+/// Redis key prefix for a stage's recent dwell samples, newest first
+const SAMPLES_PREFIX: &str = "pipeline:dwell:";
+
+/// Samples kept per stage for the summary endpoint
+const SAMPLES_PER_STAGE: isize = 1000;
+
+fn samples_key(stage: Stage) -> String {
+    format!("{}{}", SAMPLES_PREFIX, stage.as_str())
+}
+
+/// Hash field recording when a bet last moved to `status`
+pub fn status_at_field(status: &BetStatus) -> String {
+    format!("{}_at_ms", status_to_string(status))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Claimable, waiting for a processor to claim it
+    Pending,
+    /// Claimed, waiting for the processor to submit it
+    Batched,
+    /// Submitted, waiting for the settlement to complete
+    Submitted,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Pending, Stage::Batched, Stage::Submitted];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stage::Pending => "pending_to_batched",
+            Stage::Batched => "batched_to_submitted",
+            Stage::Submitted => "submitted_to_completed",
+        }
+    }
+
+    /// The stage a bet leaves when it moves from `from` to `to`, if any
+    pub fn ended_by(from: &BetStatus, to: &BetStatus) -> Option<Stage> {
+        match (from, to) {
+            (BetStatus::Pending | BetStatus::FailedRetryable, BetStatus::Batched) => Some(Stage::Pending),
+            (BetStatus::Batched, BetStatus::SubmittedToSolana) => Some(Stage::Batched),
+            (BetStatus::SubmittedToSolana | BetStatus::ConfirmedOnSolana, BetStatus::Completed) => {
+                Some(Stage::Submitted)
+            }
+            _ => None,
+        }
+    }
+
+    /// Hash fields that can mark the start of the stage; the latest one wins
+    ///
+    /// A retried bet becomes claimable again at `next_attempt_at_ms`, and a
+    /// manually settled one when it is put back to pending.
+    pub fn start_fields(self) -> Vec<String> {
+        match self {
+            Stage::Pending => vec![
+                "created_at_ms".to_string(),
+                "next_attempt_at_ms".to_string(),
+                status_at_field(&BetStatus::Pending),
+            ],
+            Stage::Batched => vec![status_at_field(&BetStatus::Batched)],
+            Stage::Submitted => vec![status_at_field(&BetStatus::SubmittedToSolana)],
+        }
+    }
+}
+
+/// Milliseconds spent in a stage given its start fields, `None` if it never started
+pub fn dwell_ms(starts: &[Option<i64>], now_ms: i64) -> Option<i64> {
+    starts
+        .iter()
+        .flatten()
+        .max()
+        .map(|started_at_ms| (now_ms - started_at_ms).max(0))
+}
+
+/// Record dwell times for bets that just left `stage`
+pub async fn record(redis: &mut ConnectionManager, stage: Stage, dwells_ms: &[i64]) -> Result<()> {
+    if dwells_ms.is_empty() {
+        return Ok(());
+    }
+    for dwell_ms in dwells_ms {
+        telemetry::record_stage_dwell(stage.as_str(), *dwell_ms);
+    }
+
+    let key = samples_key(stage);
+    let _: () = redis::pipe()
+        .lpush(&key, dwells_ms)
+        .ignore()
+        .ltrim(&key, 0, SAMPLES_PER_STAGE - 1)
+        .ignore()
+        .query_async(redis)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageLatency {
+    pub stage: &'static str,
+    pub samples: usize,
+    pub p50_ms: Option<i64>,
+    pub p95_ms: Option<i64>,
+    pub p99_ms: Option<i64>,
+    pub max_ms: Option<i64>,
+}
+
+impl StageLatency {
+    fn from_samples(stage: Stage, mut samples: Vec<i64>) -> Self {
+        samples.sort_unstable();
+        Self {
+            stage: stage.as_str(),
+            samples: samples.len(),
+            p50_ms: percentile(&samples, 50),
+            p95_ms: percentile(&samples, 95),
+            p99_ms: percentile(&samples, 99),
+            max_ms: samples.last().copied(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineLatencyReport {
+    pub stages: Vec<StageLatency>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl PipelineLatencyReport {
+    /// Build from each stage's samples, in `Stage::ALL` order
+    pub(crate) fn new(samples: Vec<Vec<i64>>) -> Self {
+        Self {
+            stages: Stage::ALL
+                .into_iter()
+                .zip(samples)
+                .map(|(stage, samples)| StageLatency::from_samples(stage, samples))
+                .collect(),
+            generated_at: Utc::now(),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+pub(crate) fn percentile(sorted: &[i64], pct: usize) -> Option<i64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+/// Summarize the recent samples of every stage
+pub async fn summary(redis: &mut ConnectionManager) -> Result<PipelineLatencyReport> {
+    let mut pipe = redis::pipe();
+    for stage in Stage::ALL {
+        pipe.lrange(samples_key(stage), 0, -1);
+    }
+    let samples: Vec<Vec<i64>> = pipe.query_async(redis).await?;
+    Ok(PipelineLatencyReport::new(samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_transitions() {
+        assert_eq!(Stage::ended_by(&BetStatus::Pending, &BetStatus::Batched), Some(Stage::Pending));
+        assert_eq!(Stage::ended_by(&BetStatus::FailedRetryable, &BetStatus::Batched), Some(Stage::Pending));
+        assert_eq!(Stage::ended_by(&BetStatus::Batched, &BetStatus::SubmittedToSolana), Some(Stage::Batched));
+        assert_eq!(Stage::ended_by(&BetStatus::ConfirmedOnSolana, &BetStatus::Completed), Some(Stage::Submitted));
+        // Skipping submission, or a repeated update, ends no stage
+        assert_eq!(Stage::ended_by(&BetStatus::Batched, &BetStatus::Completed), None);
+        assert_eq!(Stage::ended_by(&BetStatus::Completed, &BetStatus::Completed), None);
+        assert_eq!(status_at_field(&BetStatus::SubmittedToSolana), "submitted_to_solana_at_ms");
+    }
+
+    #[test]
+    fn test_dwell_uses_latest_start() {
+        assert_eq!(dwell_ms(&[Some(1_000), None, Some(4_000)], 5_500), Some(1_500));
+        assert_eq!(dwell_ms(&[Some(6_000)], 5_500), Some(0));
+        assert_eq!(dwell_ms(&[None, None], 5_500), None);
+    }
+
+    #[test]
+    fn test_stage_summary() {
+        let summary = StageLatency::from_samples(Stage::Batched, (1..=100).rev().collect());
+        assert_eq!(summary.samples, 100);
+        assert_eq!(summary.p50_ms, Some(50));
+        assert_eq!(summary.p95_ms, Some(95));
+        assert_eq!(summary.p99_ms, Some(99));
+        assert_eq!(summary.max_ms, Some(100));
+
+        let empty = StageLatency::from_samples(Stage::Pending, Vec::new());
+        assert_eq!((empty.samples, empty.p50_ms, empty.max_ms), (0, None, None));
+    }
+}
@@ -0,0 +1,185 @@
+//! Claim statistics per processor
+//!
+//! Claims and batch updates bump per-processor counters in Redis, so
+//! `GET /api/admin/processors` can point at an instance that claims bets and then
+//! fails or stalls on them. Updates are attributed to the processor in the
+//! batch's claim record, and completions timed from its claim.
+
+use chrono::{DateTime, TimeZone, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::batch_claims::BatchClaim;
+use crate::errors::Result;
+
+/// Redis key prefix for a processor's counters
+const STATS_PREFIX: &str = "processors:stats:";
+
+/// Sorted set of processor ids by last-seen time (ms)
+const SEEN_KEY: &str = "processors:seen";
+
+/// Processors silent this long drop out of the summary
+const STATS_TTL_SECONDS: i64 = 30 * 86_400;
+
+/// Processor id claims are recorded under when the caller sends none
+pub const UNKNOWN_PROCESSOR: &str = "processor-unknown";
+
+fn stats_key(processor_id: &str) -> String {
+    format!("{}{}", STATS_PREFIX, processor_id)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessorStats {
+    pub processor_id: String,
+    pub claim_requests: i64,
+    pub bets_claimed: i64,
+    pub completed: i64,
+    pub failed: i64,
+    /// Failed share of the bets this processor reported on; 0 before any report
+    pub failure_rate: f64,
+    /// Mean time from claim to completion; `None` before any timed completion
+    pub avg_completion_latency_ms: Option<f64>,
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+impl ProcessorStats {
+    pub(crate) fn from_counters(processor_id: String, counters: &HashMap<String, i64>) -> Self {
+        let counter = |name: &str| counters.get(name).copied().unwrap_or(0);
+
+        let completed = counter("completed");
+        let failed = counter("failed");
+        let reported = completed + failed;
+        let latency_samples = counter("latency_samples");
+        Self {
+            processor_id,
+            claim_requests: counter("claim_requests"),
+            bets_claimed: counter("claimed"),
+            completed,
+            failed,
+            failure_rate: if reported > 0 { failed as f64 / reported as f64 } else { 0.0 },
+            avg_completion_latency_ms: (latency_samples > 0)
+                .then(|| counter("latency_ms_total") as f64 / latency_samples as f64),
+            last_seen: counters
+                .get("last_seen_ms")
+                .and_then(|ms| Utc.timestamp_millis_opt(*ms).single()),
+        }
+    }
+}
+
+/// Counters a claim request handing out `claimed` bets adds to
+pub(crate) fn claim_increments(claimed: usize) -> Vec<(&'static str, i64)> {
+    vec![("claim_requests", 1), ("claimed", claimed as i64)]
+}
+
+/// Count a claim request and the bets it handed out
+pub async fn record_claim(redis: &mut ConnectionManager, processor_id: &str, claimed: usize) -> Result<()> {
+    bump(redis, processor_id, &claim_increments(claimed), Utc::now().timestamp_millis()).await
+}
+
+/// Add `increments` to a processor's counters and mark it seen at `now_ms`
+async fn bump(
+    redis: &mut ConnectionManager,
+    processor_id: &str,
+    increments: &[(&'static str, i64)],
+    now_ms: i64,
+) -> Result<()> {
+    let key = stats_key(processor_id);
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    for (field, by) in increments {
+        pipe.hincr(&key, *field, *by).ignore();
+    }
+    pipe.hset(&key, "last_seen_ms", now_ms)
+        .ignore()
+        .expire(&key, STATS_TTL_SECONDS)
+        .ignore()
+        .zadd(SEEN_KEY, processor_id, now_ms)
+        .ignore();
+    let _: () = pipe.query_async(redis).await?;
+    Ok(())
+}
+
+/// Outcomes of one batch update, recorded against the processor that claimed it
+#[derive(Debug, Default)]
+pub struct BatchOutcomes {
+    completed: i64,
+    failed: i64,
+}
+
+impl BatchOutcomes {
+    pub fn add_completed(&mut self) {
+        self.completed += 1;
+    }
+
+    pub fn add_failed(&mut self) {
+        self.failed += 1;
+    }
+
+    /// Counters the batch adds to when reported at `now_ms`
+    pub(crate) fn increments(&self, claim: &BatchClaim, now_ms: i64) -> Vec<(&'static str, i64)> {
+        let mut increments = vec![("completed", self.completed), ("failed", self.failed)];
+        // Every completion in the batch took from the claim until now
+        let latency_ms = claim.claimed_at_ms.map(|claimed_at_ms| (now_ms - claimed_at_ms).max(0));
+        if let Some(latency_ms) = latency_ms.filter(|_| self.completed > 0) {
+            increments.push(("latency_ms_total", latency_ms.saturating_mul(self.completed)));
+            increments.push(("latency_samples", self.completed));
+        }
+        increments
+    }
+
+    pub async fn record(&self, redis: &mut ConnectionManager, claim: &BatchClaim) -> Result<()> {
+        let now_ms = Utc::now().timestamp_millis();
+        bump(redis, &claim.processor_id, &self.increments(claim, now_ms), now_ms).await
+    }
+}
+
+/// The `limit` most recently seen processors, most recent first
+pub async fn list(redis: &mut ConnectionManager, limit: isize) -> Result<Vec<ProcessorStats>> {
+    let cutoff_ms = Utc::now().timestamp_millis() - STATS_TTL_SECONDS * 1000;
+    let _: () = redis.zrembyscore(SEEN_KEY, "-inf", cutoff_ms).await?;
+    let processor_ids: Vec<String> = redis.zrevrange(SEEN_KEY, 0, limit - 1).await?;
+    if processor_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut pipe = redis::pipe();
+    for processor_id in &processor_ids {
+        pipe.hgetall(stats_key(processor_id));
+    }
+    let counters: Vec<HashMap<String, i64>> = pipe.query_async(redis).await?;
+    Ok(processor_ids
+        .into_iter()
+        .zip(counters)
+        .map(|(processor_id, counters)| ProcessorStats::from_counters(processor_id, &counters))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_from_counters() {
+        let counters = HashMap::from([
+            ("claim_requests".to_string(), 12),
+            ("claimed".to_string(), 40),
+            ("completed".to_string(), 30),
+            ("failed".to_string(), 10),
+            ("latency_ms_total".to_string(), 45_000),
+            ("latency_samples".to_string(), 30),
+            ("last_seen_ms".to_string(), 1_700_000_000_000),
+        ]);
+        let stats = ProcessorStats::from_counters("processor-1".to_string(), &counters);
+        assert_eq!(stats.bets_claimed, 40);
+        assert_eq!(stats.failure_rate, 0.25);
+        assert_eq!(stats.avg_completion_latency_ms, Some(1500.0));
+        assert_eq!(stats.last_seen.map(|t| t.timestamp_millis()), Some(1_700_000_000_000));
+
+        let idle = ProcessorStats::from_counters("processor-2".to_string(), &HashMap::new());
+        assert_eq!(idle.failure_rate, 0.0);
+        assert_eq!(idle.avg_completion_latency_ms, None);
+        assert_eq!(idle.last_seen, None);
+    }
+}
@@ -0,0 +1,109 @@
+//! Signed bet receipts
+//!
+//! When a bet is accepted the backend signs its terms with an ed25519 key. The
+//! detached signature lets a user prove what the operator agreed to before the
+//! bet is settled; anyone can check it against the published signer key.
+
+use anyhow::Context;
+use solana_sdk::pubkey::Pubkey;
+use shared::secrets::read_keypair;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+
+use crate::config::ReceiptConfig;
+use crate::domain::Bet;
+
+pub use shared::api::{BetReceipt, ReceiptPayload};
+
+pub struct ReceiptSigner {
+    keypair: Keypair,
+}
+
+impl ReceiptSigner {
+    /// Load the signing key, or generate a throwaway one when none is configured
+    pub fn from_config(config: &ReceiptConfig) -> anyhow::Result<Self> {
+        let keypair = match &config.keypair_path {
+            Some(keypair) => read_keypair(keypair).context("Failed to read receipt keypair")?,
+            None => {
+                tracing::warn!("RECEIPT_KEYPAIR_PATH not set; receipts are signed with an ephemeral key");
+                Keypair::new()
+            }
+        };
+        Ok(Self { keypair })
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    pub fn sign(&self, bet: &Bet) -> BetReceipt {
+        let payload = ReceiptPayload::from_bet(bet);
+        let signature = self.keypair.sign_message(&payload.signing_bytes());
+        BetReceipt {
+            payload,
+            signature: signature.to_string(),
+            signer: self.pubkey().to_string(),
+        }
+    }
+
+    /// Base58 detached signature over arbitrary bytes (e.g. an export digest)
+    pub fn sign_bytes(&self, bytes: &[u8]) -> String {
+        self.keypair.sign_message(bytes).to_string()
+    }
+
+    /// Check a base58 signature over `payload` against this signer's key
+    pub fn verify(&self, payload: &ReceiptPayload, signature: &str) -> bool {
+        signature
+            .parse::<Signature>()
+            .map(|sig| sig.verify(self.pubkey().as_ref(), &payload.signing_bytes()))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::BetStatus;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn bet() -> Bet {
+        Bet {
+            bet_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            user_wallet: "8JQCVcxGMN2kQKXDzgCEJN8AawnQskWU4ha6NqZ83uDm".to_string(),
+            vault_address: String::new(),
+            allowance_pda: None,
+            casino_id: None,
+            game_type: "coinflip".to_string(),
+            stake_amount: 100_000_000,
+            stake_token: "SOL".to_string(),
+            choice: "heads".to_string(),
+            status: BetStatus::Pending,
+            external_batch_id: None,
+            solana_tx_id: None,
+            retry_count: 0,
+            processor_id: None,
+            last_error_code: None,
+            last_error_message: None,
+            payout_amount: None,
+            won: None,
+            server_seed_hash: None,
+            client_seed: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_receipt_sign_and_verify() {
+        let signer = ReceiptSigner::from_config(&ReceiptConfig { keypair_path: None }).unwrap();
+        let bet = bet();
+        let receipt = signer.sign(&bet);
+
+        assert!(signer.verify(&ReceiptPayload::from_bet(&bet), &receipt.signature));
+
+        let mut tampered = receipt.payload.clone();
+        tampered.stake += 1;
+        assert!(!signer.verify(&tampered, &receipt.signature));
+        assert!(!signer.verify(&receipt.payload, "not-a-signature"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Stake refunds for voided bets
+//!
+//! An operator voids a bet that settled as a loss on-chain; its stake then has
+//! to go back through the vault program's `refund_bet`. The refund is stored
+//! next to the bet in Redis and queued for processors, which lease it, run the
+//! instruction and report the result. A lease that is never reported lapses
+//! and the refund is handed out again; `refund_bet` rejects a second refund of
+//! the same bet, so a retry cannot pay twice.
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Script};
+use uuid::Uuid;
+
+use crate::domain::{Bet, BetStatus, Refund, RefundResult, RefundStatus};
+use crate::errors::{AppError, Result};
+
+/// Redis key prefix for a bet's refund record
+const REFUND_PREFIX: &str = "refunds:bet:";
+
+/// Redis key for refunds awaiting a processor, scored by when they may next be claimed
+const QUEUE_INDEX: &str = "refunds:queue";
+
+/// Redis key for completed and failed refunds, scored by `completed_at` ms
+const DONE_INDEX: &str = "refunds:done";
+
+/// How long a claimed refund stays hidden from other processors
+pub const REFUND_LEASE_MS: i64 = 120_000;
+
+/// Delay before a refund whose attempt failed transiently is handed out again
+pub const REFUND_RETRY_DELAY_MS: i64 = 30_000;
+
+/// Lease due refunds by pushing their score past the lease
+///
+/// Keys: [queue_index]
+/// Args: [limit, now_ms, lease_ms]
+///
+/// Returns: Array of leased bet IDs, oldest first
+const CLAIM_REFUNDS_SCRIPT: &str = r#"
+local queue = KEYS[1]
+local limit = tonumber(ARGV[1])
+local now_ms = tonumber(ARGV[2])
+local lease_ms = tonumber(ARGV[3])
+
+local due = redis.call('ZRANGEBYSCORE', queue, '-inf', now_ms, 'LIMIT', 0, limit)
+for _, bet_id in ipairs(due) do
+  redis.call('ZADD', queue, now_ms + lease_ms, bet_id)
+end
+return due
+"#;
+
+fn refund_key(bet_id: Uuid) -> String {
+    format!("{}{}", REFUND_PREFIX, bet_id)
+}
+
+/// Only a loss settled on-chain has a stake to return; unsettled bets are
+/// handled by manual settlement and wins already paid out more than the stake
+pub fn can_refund(bet: &Bet) -> bool {
+    bet.status == BetStatus::Completed && bet.won == Some(false) && bet.solana_tx_id.is_some()
+}
+
+/// Pending refund of `bet`'s full stake
+pub fn for_bet(bet: &Bet, operator: &str, reason: &str, now: DateTime<Utc>) -> Refund {
+    Refund {
+        bet_id: bet.bet_id,
+        user_wallet: bet.user_wallet.clone(),
+        vault_address: bet.vault_address.clone(),
+        casino_id: bet.casino_id.clone(),
+        stake_token: bet.stake_token.clone(),
+        amount: bet.stake_amount,
+        status: RefundStatus::Pending,
+        requested_by: operator.to_string(),
+        reason: reason.to_string(),
+        requested_at: now,
+        attempts: 0,
+        solana_tx_id: None,
+        last_error: None,
+        completed_at: None,
+    }
+}
+
+pub async fn get(redis: &mut ConnectionManager, bet_id: Uuid) -> Result<Option<Refund>> {
+    let raw: Option<String> = redis.get(refund_key(bet_id)).await?;
+    Ok(raw.and_then(|raw| serde_json::from_str(&raw).ok()))
+}
+
+/// Store and queue a new refund; errors if the bet was already voided
+pub async fn request(redis: &mut ConnectionManager, refund: &Refund) -> Result<()> {
+    let payload = serde_json::to_string(refund).map_err(anyhow::Error::from)?;
+    let created: bool = redis.set_nx(refund_key(refund.bet_id), payload).await?;
+    if !created {
+        return Err(AppError::invalid_status(format!("Bet {} has already been voided", refund.bet_id)));
+    }
+    let _: () = redis
+        .zadd(QUEUE_INDEX, refund.bet_id.to_string(), refund.requested_at.timestamp_millis())
+        .await?;
+    Ok(())
+}
+
+/// Lease up to `limit` due refunds for a processor
+pub async fn claim(redis: &mut ConnectionManager, limit: i64, now_ms: i64) -> Result<Vec<Refund>> {
+    let ids: Vec<String> = Script::new(CLAIM_REFUNDS_SCRIPT)
+        .key(QUEUE_INDEX)
+        .arg(limit)
+        .arg(now_ms)
+        .arg(REFUND_LEASE_MS)
+        .invoke_async(redis)
+        .await?;
+    load(redis, &ids).await
+}
+
+/// Apply a processor's result: finish the refund or queue it for another attempt
+///
+/// Returns the refund and whether this result finished it; results for a
+/// refund that is already final change nothing.
+pub async fn record_result(
+    redis: &mut ConnectionManager,
+    bet_id: Uuid,
+    result: &RefundResult,
+) -> Result<(Refund, bool)> {
+    let mut refund = get(redis, bet_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("No refund for bet {}", bet_id)))?;
+    if refund.status != RefundStatus::Pending {
+        return Ok((refund, false));
+    }
+
+    let now = Utc::now();
+    let finished = refund.apply(result, now);
+    let payload = serde_json::to_string(&refund).map_err(anyhow::Error::from)?;
+    let mut pipe = redis::pipe();
+    pipe.atomic().set(refund_key(bet_id), payload).ignore();
+    if finished {
+        pipe.zrem(QUEUE_INDEX, bet_id.to_string())
+            .ignore()
+            .zadd(DONE_INDEX, bet_id.to_string(), now.timestamp_millis())
+            .ignore();
+    } else {
+        pipe.zadd(QUEUE_INDEX, bet_id.to_string(), now.timestamp_millis() + REFUND_RETRY_DELAY_MS)
+            .ignore();
+    }
+    let _: () = pipe.query_async(redis).await?;
+    Ok((refund, finished))
+}
+
+/// Refunds still queued (oldest first) or finished ones (newest first)
+pub async fn list(redis: &mut ConnectionManager, pending: bool, limit: isize) -> Result<Vec<Refund>> {
+    let stop = limit.max(1) - 1;
+    let ids: Vec<String> = if pending {
+        redis.zrange(QUEUE_INDEX, 0, stop).await?
+    } else {
+        redis.zrevrange(DONE_INDEX, 0, stop).await?
+    };
+    load(redis, &ids).await
+}
+
+async fn load(redis: &mut ConnectionManager, ids: &[String]) -> Result<Vec<Refund>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let keys: Vec<String> = ids
+        .iter()
+        .filter_map(|id| Uuid::parse_str(id).ok())
+        .map(refund_key)
+        .collect();
+    let raw: Vec<Option<String>> = redis::cmd("MGET").arg(&keys).query_async(redis).await?;
+    Ok(raw
+        .into_iter()
+        .flatten()
+        .filter_map(|raw| serde_json::from_str(&raw).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_settled_losses_are_refundable() {
+        let mut bet = Bet {
+            bet_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            user_wallet: "8JQCVcxGMN2kQKXDzgCEJN8AawnQskWU4ha6NqZ83uDm".to_string(),
+            vault_address: "vault".to_string(),
+            allowance_pda: None,
+            casino_id: Some("main".to_string()),
+            game_type: "coinflip".to_string(),
+            stake_amount: 100_000_000,
+            stake_token: "SOL".to_string(),
+            choice: "heads".to_string(),
+            status: BetStatus::Completed,
+            external_batch_id: None,
+            solana_tx_id: Some("5sig".to_string()),
+            retry_count: 0,
+            processor_id: None,
+            last_error_code: None,
+            last_error_message: None,
+            payout_amount: Some(0),
+            won: Some(false),
+            server_seed_hash: None,
+            client_seed: None,
+            metadata: None,
+        };
+        assert!(can_refund(&bet));
+
+        let refund = for_bet(&bet, "ops@casino", "Game voided", Utc::now());
+        assert_eq!(refund.amount, 100_000_000);
+        assert_eq!(refund.casino_id.as_deref(), Some("main"));
+        assert_eq!(refund.status, RefundStatus::Pending);
+
+        bet.won = Some(true);
+        assert!(!can_refund(&bet));
+        bet.won = Some(false);
+        bet.status = BetStatus::FailedManualReview;
+        assert!(!can_refund(&bet));
+    }
+}
@@ -0,0 +1,219 @@
+//! Aggregated bet reports for finance and compliance
+//!
+//! Live bets are aggregated in process while archived bets are aggregated by the
+//! archive itself; both produce `BetAggregate`s keyed by the same group labels so
+//! the two halves can simply be merged.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::domain::{Bet, BetStatus};
+
+/// Report grouping dimension
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// UTC calendar day of `created_at` (`YYYY-MM-DD`)
+    #[default]
+    Day,
+    Token,
+    Game,
+}
+
+impl GroupBy {
+    pub fn key(&self, bet: &Bet) -> String {
+        match self {
+            GroupBy::Day => bet.created_at.format("%Y-%m-%d").to_string(),
+            GroupBy::Token => bet.stake_token.clone(),
+            GroupBy::Game => bet.game_type.clone(),
+        }
+    }
+}
+
+/// Half-open `[from, to)` window over bet `created_at`
+#[derive(Debug, Clone, Copy)]
+pub struct ReportRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// Additive totals for one group
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BetAggregate {
+    pub bets: i64,
+    /// Bets that reached `Completed`
+    pub settled: i64,
+    pub wins: i64,
+    pub stake_volume: i64,
+    /// Stake of settled bets only, the denominator of the payout ratio
+    pub settled_stake: i64,
+    pub payout_total: i64,
+}
+
+impl BetAggregate {
+    pub fn add(&mut self, bet: &Bet) {
+        self.bets += 1;
+        self.stake_volume = self.stake_volume.saturating_add(bet.stake_amount);
+        if bet.status == BetStatus::Completed {
+            self.settled += 1;
+            self.settled_stake = self.settled_stake.saturating_add(bet.stake_amount);
+            self.payout_total = self.payout_total.saturating_add(bet.payout_amount.unwrap_or(0));
+            if bet.won == Some(true) {
+                self.wins += 1;
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &BetAggregate) {
+        self.bets += other.bets;
+        self.settled += other.settled;
+        self.wins += other.wins;
+        self.stake_volume = self.stake_volume.saturating_add(other.stake_volume);
+        self.settled_stake = self.settled_stake.saturating_add(other.settled_stake);
+        self.payout_total = self.payout_total.saturating_add(other.payout_total);
+    }
+}
+
+/// One output row of a bet report
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRow {
+    pub group: String,
+    pub bets: i64,
+    pub settled: i64,
+    pub wins: i64,
+    pub stake_volume: i64,
+    pub payout_total: i64,
+    /// Wins over settled bets
+    pub win_rate: f64,
+    /// Payouts over settled stake
+    pub payout_ratio: f64,
+}
+
+pub const CSV_HEADER: &str = "group,bets,settled,wins,stake_volume,payout_total,win_rate,payout_ratio\n";
+
+impl ReportRow {
+    pub fn to_csv_line(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{:.6},{:.6}\n",
+            csv_field(&self.group),
+            self.bets,
+            self.settled,
+            self.wins,
+            self.stake_volume,
+            self.payout_total,
+            self.win_rate,
+            self.payout_ratio
+        )
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or newline
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn ratio(numerator: i64, denominator: i64) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// Aggregates keyed by group label, in label order
+#[derive(Debug, Default)]
+pub struct BetReport {
+    groups: BTreeMap<String, BetAggregate>,
+}
+
+impl BetReport {
+    pub fn add_bet(&mut self, group_by: GroupBy, bet: &Bet) {
+        self.groups.entry(group_by.key(bet)).or_default().add(bet);
+    }
+
+    pub fn merge(&mut self, group: String, aggregate: &BetAggregate) {
+        self.groups.entry(group).or_default().merge(aggregate);
+    }
+
+    pub fn rows(self) -> Vec<ReportRow> {
+        self.groups
+            .into_iter()
+            .map(|(group, agg)| ReportRow {
+                group,
+                win_rate: ratio(agg.wins, agg.settled),
+                payout_ratio: ratio(agg.payout_total, agg.settled_stake),
+                bets: agg.bets,
+                settled: agg.settled,
+                wins: agg.wins,
+                stake_volume: agg.stake_volume,
+                payout_total: agg.payout_total,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn bet(token: &str, status: BetStatus, won: Option<bool>, payout: Option<i64>) -> Bet {
+        Bet {
+            bet_id: Uuid::new_v4(),
+            created_at: "2026-03-01T12:00:00Z".parse().unwrap(),
+            user_wallet: "wallet".to_string(),
+            vault_address: "vault".to_string(),
+            allowance_pda: None,
+            casino_id: None,
+            game_type: "coinflip".to_string(),
+            stake_amount: 100,
+            stake_token: token.to_string(),
+            choice: "heads".to_string(),
+            status,
+            external_batch_id: None,
+            solana_tx_id: None,
+            retry_count: 0,
+            processor_id: None,
+            last_error_code: None,
+            last_error_message: None,
+            payout_amount: payout,
+            won,
+            server_seed_hash: None,
+            client_seed: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_report_merges_live_and_archived() {
+        let mut report = BetReport::default();
+        report.add_bet(GroupBy::Token, &bet("SOL", BetStatus::Completed, Some(true), Some(200)));
+        report.add_bet(GroupBy::Token, &bet("SOL", BetStatus::Completed, Some(false), Some(0)));
+        report.add_bet(GroupBy::Token, &bet("SOL", BetStatus::Pending, None, None));
+        report.merge(
+            "SOL".to_string(),
+            &BetAggregate { bets: 2, settled: 2, wins: 0, stake_volume: 200, settled_stake: 200, payout_total: 0 },
+        );
+
+        let rows = report.rows();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].bets, 5);
+        assert_eq!(rows[0].settled, 4);
+        assert_eq!(rows[0].stake_volume, 500);
+        assert_eq!(rows[0].win_rate, 0.25);
+        assert_eq!(rows[0].payout_ratio, 0.5);
+        assert_eq!(GroupBy::Day.key(&bet("SOL", BetStatus::Pending, None, None)), "2026-03-01");
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("SOL"), "SOL");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
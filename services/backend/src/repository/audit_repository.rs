@@ -0,0 +1,19 @@
+//! Admin audit trail of bets
+//!
+//! Every operator action on a bet (manual settlement, voids, dispute
+//! resolutions) appends an entry here before it takes effect.
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::AuditEntry;
+use crate::errors::Result;
+
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    /// Append an entry to a bet's admin audit trail
+    async fn append_audit(&self, entry: &AuditEntry) -> Result<()>;
+
+    /// Admin audit trail for a bet, oldest first
+    async fn audit_trail(&self, bet_id: Uuid) -> Result<Vec<AuditEntry>>;
+}
@@ -0,0 +1,99 @@
+//! Settlement batches reported by processors
+//!
+//! Covers who claimed each batch (see `batch_claims`), the summary of its last
+//! update, and the on-chain settlement reported for each of its bets (see
+//! `settlements`).
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+use crate::batch_claims::{self, BatchClaim, ClaimCheck};
+use crate::domain::{BatchStatus, SettlementReport};
+use crate::errors::{AppError, Result};
+use crate::settlements;
+
+#[async_trait]
+pub trait BatchRepository: Send + Sync {
+    /// Record `processor_id` as the owner of a freshly claimed batch; returns its version
+    async fn record_claim(&self, batch_id: Uuid, processor_id: &str) -> Result<u64>;
+
+    /// Accept an update from `processor_id` at `version`, or a resend at an earlier one, before
+    /// writing it; fails with 409 otherwise (404 for unknown batches)
+    async fn check_claim(&self, batch_id: Uuid, processor_id: &str, version: u64) -> Result<ClaimCheck>;
+
+    /// Bump the version once every bet of the update at `version` is written; 409 if another update finished first
+    async fn advance_claim(&self, batch_id: Uuid, processor_id: &str, version: u64) -> Result<BatchClaim>;
+
+    /// Keep the status, transaction and error of the batch's latest update
+    async fn save_summary(
+        &self,
+        batch_id: Uuid,
+        status: &BatchStatus,
+        solana_tx_id: Option<&str>,
+        error_message: Option<&str>,
+    ) -> Result<()>;
+
+    /// On-chain settlement reported for a bet
+    async fn settlement(&self, bet_id: Uuid) -> Result<Option<SettlementReport>>;
+
+    /// Merge `report` into the bet's settlement
+    async fn record_settlement(&self, bet_id: Uuid, report: SettlementReport) -> Result<()>;
+}
+
+pub struct RedisBatchRepository {
+    redis: ConnectionManager,
+}
+
+impl RedisBatchRepository {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+}
+
+#[async_trait]
+impl BatchRepository for RedisBatchRepository {
+    async fn record_claim(&self, batch_id: Uuid, processor_id: &str) -> Result<u64> {
+        batch_claims::record(&mut self.redis.clone(), batch_id, processor_id).await
+    }
+
+    async fn check_claim(&self, batch_id: Uuid, processor_id: &str, version: u64) -> Result<ClaimCheck> {
+        batch_claims::check(&mut self.redis.clone(), batch_id, processor_id, version).await
+    }
+
+    async fn advance_claim(&self, batch_id: Uuid, processor_id: &str, version: u64) -> Result<BatchClaim> {
+        batch_claims::advance(&mut self.redis.clone(), batch_id, processor_id, version).await
+    }
+
+    async fn save_summary(
+        &self,
+        batch_id: Uuid,
+        status: &BatchStatus,
+        solana_tx_id: Option<&str>,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let _: () = redis_conn
+            .hset_multiple(
+                format!("batch:{}", batch_id),
+                &[
+                    ("status", format!("{:?}", status).to_lowercase()),
+                    ("solana_tx_id", solana_tx_id.unwrap_or_default().to_string()),
+                    ("last_error_message", error_message.unwrap_or_default().to_string()),
+                    ("updated_at_ms", chrono::Utc::now().timestamp_millis().to_string()),
+                ],
+            )
+            .await
+            .map_err(AppError::Redis)?;
+        Ok(())
+    }
+
+    async fn settlement(&self, bet_id: Uuid) -> Result<Option<SettlementReport>> {
+        settlements::load(&mut self.redis.clone(), bet_id).await
+    }
+
+    async fn record_settlement(&self, bet_id: Uuid, report: SettlementReport) -> Result<()> {
+        settlements::record(&mut self.redis.clone(), bet_id, report).await
+    }
+}
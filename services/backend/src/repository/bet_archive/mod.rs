@@ -0,0 +1,46 @@
+//! Cold storage for terminal bets
+//!
+//! The archival worker moves old terminal bets out of Redis into a `BetArchive`
+//! and leaves a tombstone behind. `RedisBetRepository` falls back to the archive
+//! when it meets a tombstone, so reads stay transparent to handlers.
+
+mod postgres;
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::ArchiveConfig;
+use crate::domain::Bet;
+use crate::errors::Result;
+use crate::reports::{BetAggregate, GroupBy, ReportRange};
+
+pub use postgres::PostgresBetArchive;
+
+#[async_trait]
+pub trait BetArchive: Send + Sync {
+    /// Archive name for logs and metrics
+    fn name(&self) -> &'static str;
+
+    /// Store terminal bets; re-archiving a bet overwrites its previous row
+    async fn store(&self, bets: &[Bet]) -> Result<()>;
+
+    async fn find_by_id(&self, bet_id: Uuid) -> Result<Option<Bet>>;
+
+    /// Fetch archived bets by ID; IDs that are not archived are skipped
+    async fn find_many(&self, bet_ids: &[Uuid]) -> Result<Vec<Bet>>;
+
+    /// Aggregate archived bets created within `range`, keyed by `GroupBy::key` labels
+    async fn aggregate(&self, range: ReportRange, group_by: GroupBy) -> Result<Vec<(String, BetAggregate)>>;
+}
+
+/// Connect the configured archive; `None` when archival is disabled
+pub async fn connect(config: &ArchiveConfig) -> anyhow::Result<Option<Arc<dyn BetArchive>>> {
+    match &config.database_url {
+        Some(url) => {
+            let archive = PostgresBetArchive::connect(url).await?;
+            Ok(Some(Arc::new(archive)))
+        }
+        None => Ok(None),
+    }
+}
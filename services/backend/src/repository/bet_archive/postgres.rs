@@ -0,0 +1,176 @@
+//! Postgres bet archive
+//!
+//! One row per bet: the queryable columns analytics need plus the full bet as
+//! JSONB, so the schema does not have to chase every `Bet` field.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use tokio_postgres::{Client, NoTls, Row};
+use uuid::Uuid;
+
+use super::BetArchive;
+use crate::domain::Bet;
+use crate::errors::{AppError, Result};
+use crate::reports::{BetAggregate, GroupBy, ReportRange};
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS archived_bets (
+    bet_id        UUID PRIMARY KEY,
+    user_wallet   TEXT NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL,
+    status        TEXT NOT NULL,
+    game_type     TEXT NOT NULL,
+    stake_token   TEXT NOT NULL,
+    stake_amount  BIGINT NOT NULL,
+    payout_amount BIGINT,
+    won           BOOLEAN,
+    archived_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    bet           JSONB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS archived_bets_user_created ON archived_bets (user_wallet, created_at DESC);
+CREATE INDEX IF NOT EXISTS archived_bets_created ON archived_bets (created_at);
+"#;
+
+const UPSERT: &str = r#"
+INSERT INTO archived_bets
+    (bet_id, user_wallet, created_at, status, game_type, stake_token, stake_amount, payout_amount, won, bet)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+ON CONFLICT (bet_id) DO UPDATE SET
+    status = EXCLUDED.status,
+    payout_amount = EXCLUDED.payout_amount,
+    won = EXCLUDED.won,
+    archived_at = now(),
+    bet = EXCLUDED.bet
+"#;
+
+/// SQL expression producing the same labels as `GroupBy::key`
+fn group_expr(group_by: GroupBy) -> &'static str {
+    match group_by {
+        GroupBy::Day => "to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD')",
+        GroupBy::Token => "stake_token",
+        GroupBy::Game => "game_type",
+    }
+}
+
+pub struct PostgresBetArchive {
+    client: Client,
+}
+
+impl PostgresBetArchive {
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        let (client, connection) = tokio_postgres::connect(url, NoTls)
+            .await
+            .context("Failed to connect to archive database")?;
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::error!(error = %e, "Archive database connection closed");
+            }
+        });
+
+        client
+            .batch_execute(SCHEMA)
+            .await
+            .context("Failed to create archive schema")?;
+
+        Ok(Self { client })
+    }
+
+    /// Shared connection for report queries over the archive
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+fn bet_from_row(row: &Row) -> Result<Bet> {
+    let value: serde_json::Value = row.try_get("bet")?;
+    serde_json::from_value(value)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Corrupt archived bet: {}", e)))
+}
+
+#[async_trait]
+impl BetArchive for PostgresBetArchive {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn store(&self, bets: &[Bet]) -> Result<()> {
+        let statement = self.client.prepare(UPSERT).await?;
+        for bet in bets {
+            let status = serde_json::to_value(&bet.status).map_err(anyhow::Error::from)?;
+            let document = serde_json::to_value(bet).map_err(anyhow::Error::from)?;
+            self.client
+                .execute(
+                    &statement,
+                    &[
+                        &bet.bet_id,
+                        &bet.user_wallet,
+                        &bet.created_at,
+                        &status.as_str().unwrap_or_default(),
+                        &bet.game_type,
+                        &bet.stake_token,
+                        &bet.stake_amount,
+                        &bet.payout_amount,
+                        &bet.won,
+                        &document,
+                    ],
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn find_by_id(&self, bet_id: Uuid) -> Result<Option<Bet>> {
+        let row = self
+            .client
+            .query_opt("SELECT bet FROM archived_bets WHERE bet_id = $1", &[&bet_id])
+            .await?;
+        row.as_ref().map(bet_from_row).transpose()
+    }
+
+    async fn find_many(&self, bet_ids: &[Uuid]) -> Result<Vec<Bet>> {
+        if bet_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let rows = self
+            .client
+            .query("SELECT bet FROM archived_bets WHERE bet_id = ANY($1)", &[&bet_ids])
+            .await?;
+        rows.iter().map(bet_from_row).collect()
+    }
+
+    async fn aggregate(&self, range: ReportRange, group_by: GroupBy) -> Result<Vec<(String, BetAggregate)>> {
+        let query = format!(
+            r#"
+SELECT {group} AS grp,
+    COUNT(*) AS bets,
+    COUNT(*) FILTER (WHERE status = 'completed') AS settled,
+    COUNT(*) FILTER (WHERE status = 'completed' AND won) AS wins,
+    COALESCE(SUM(stake_amount), 0)::BIGINT AS stake_volume,
+    COALESCE(SUM(stake_amount) FILTER (WHERE status = 'completed'), 0)::BIGINT AS settled_stake,
+    COALESCE(SUM(payout_amount) FILTER (WHERE status = 'completed'), 0)::BIGINT AS payout_total
+FROM archived_bets
+WHERE created_at >= $1 AND created_at < $2
+GROUP BY grp
+"#,
+            group = group_expr(group_by)
+        );
+
+        let rows = self.client.query(&query, &[&range.from, &range.to]).await?;
+        rows.iter()
+            .map(|row| {
+                Ok((
+                    row.try_get("grp")?,
+                    BetAggregate {
+                        bets: row.try_get("bets")?,
+                        settled: row.try_get("settled")?,
+                        wins: row.try_get("wins")?,
+                        stake_volume: row.try_get("stake_volume")?,
+                        settled_stake: row.try_get("settled_stake")?,
+                        payout_total: row.try_get("payout_total")?,
+                    },
+                ))
+            })
+            .collect()
+    }
+}
@@ -6,32 +6,112 @@
 mod redis_bet_repository;
 
 // Re-export everything publicly
-pub use redis_bet_repository::RedisBetRepository;
+pub use redis_bet_repository::{
+    record_schema_version, ClaimFairness, FieldCipher, MigrationOptions, MigrationReport, RedisBetRepository,
+    CURRENT_SCHEMA_VERSION,
+};
+pub(crate) use redis_bet_repository::{
+    claimable_index_key, priority_index_key, processing_index_key, replay_events, status_to_string,
+};
+#[cfg(any(test, feature = "testing"))]
+pub(crate) use redis_bet_repository::{bet_hash_fields, check_transition};
 
 use async_trait::async_trait;
 use uuid::Uuid;
 
-use crate::domain::{Bet, BetStatus, CreateBetRequest};
+use crate::domain::{Bet, BetError, BetEvent, BetStatus, ClientInfo, CreateBetRequest};
 use crate::errors::Result;
 
 /// Repository trait for bet storage and retrieval
 #[async_trait]
 pub trait BetRepository: Send + Sync {
-    /// Create a new bet
-    async fn create(&self, user_wallet: &str, vault_address: &str, req: CreateBetRequest) -> Result<Bet>;
+    /// Create a new bet, keeping `client` encrypted alongside it
+    async fn create(&self, user_wallet: &str, vault_address: &str, req: CreateBetRequest, client: &ClientInfo) -> Result<Bet>;
     
     /// Find a bet by ID
     async fn find_by_id(&self, bet_id: Uuid) -> Result<Option<Bet>>;
+
+    /// Decrypted client info of a bet; `None` when none was stored or the bet is archived
+    async fn client_info(&self, bet_id: Uuid) -> Result<Option<ClientInfo>>;
     
     /// Find bets by user wallet with pagination
     async fn find_by_user(&self, user_wallet: &str, limit: i64, offset: i64) -> Result<Vec<Bet>>;
+
+    /// Bets placed in a session, newest first
+    async fn find_by_session(&self, session_id: Uuid, limit: i64) -> Result<Vec<Bet>>;
+
+    /// Bets whose metadata has `value` under the indexed `key`, newest first
+    async fn find_by_metadata(&self, key: &str, value: &str, limit: i64) -> Result<Vec<Bet>>;
     
+    /// Live (unarchived) bets created in `[from_ms, to_ms)`, oldest first
+    ///
+    /// `offset` counts index entries, so a short page does not mean the range is exhausted
+    /// unless it is empty.
+    async fn find_created_between(&self, from_ms: i64, to_ms: i64, offset: i64, limit: i64) -> Result<Vec<Bet>>;
+
+    /// One step of a scan over every live bet, including those the creation-time
+    /// index misses, returning the next cursor and the bets created in
+    /// `[from_ms, to_ms)` it visited
+    ///
+    /// Start at cursor 0 and stop when the returned cursor is 0 again. Bets come in
+    /// no particular order and may be returned twice.
+    async fn scan_created_between(&self, cursor: u64, count: usize, from_ms: i64, to_ms: i64) -> Result<(u64, Vec<Bet>)>;
+
     /// Claim pending bets for batch processing
     async fn claim_pending(&self, limit: i64, processor_id: &str) -> Result<(Uuid, Vec<Bet>)>;
     
+    /// Bets recorded with Solana signature `signature`, in no particular order
+    async fn find_by_solana_tx(&self, signature: &str) -> Result<Vec<Bet>>;
+
     /// Update bet status
     async fn update_status(&self, bet_id: Uuid, status: BetStatus, solana_tx_id: Option<String>) -> Result<()>;
     
+    /// Set the result fields a processor reported, leaving the status alone
+    async fn update_bet_fields(
+        &self,
+        bet_id: Uuid,
+        won: Option<bool>,
+        payout_amount: Option<i64>,
+        error_message: Option<String>,
+        error_code: Option<String>,
+        server_seed_hash: Option<String>,
+    ) -> Result<()>;
+
     /// Update bet status with optimistic locking (compare-and-swap)
     async fn update_status_with_version(&self, bet_id: Uuid, expected_version: i32, status: BetStatus) -> Result<bool>;
+
+    /// Expire pending bets created before `created_before_ms` that were never claimed
+    ///
+    /// Looks at most at `scan_limit` of the oldest never-claimed bets, so a full
+    /// result means more may be due. Returns the bets that were moved to `Expired`.
+    async fn expire_unclaimed(&self, created_before_ms: i64, scan_limit: i64) -> Result<Vec<Bet>>;
+
+    /// Fail bets batched before `claimed_before_ms` whose processor never reported back
+    ///
+    /// Each goes through `FailedRetryable` with its usual backoff, so another
+    /// processor can claim it; the on-chain processed-bet record stops a bet from
+    /// paying out twice. Looks at most at `scan_limit` of the oldest batched bets.
+    /// Returns the bets that were failed.
+    async fn reap_stale_claims(&self, claimed_before_ms: i64, scan_limit: i64) -> Result<Vec<Bet>>;
+
+    /// Record an operator-chosen outcome and queue the bet on the priority lane
+    async fn enqueue_manual_settlement(&self, bet_id: Uuid, won: bool, payout_amount: i64) -> Result<()>;
+
+    /// Move terminal bets created before `created_before_ms` to the bet archive
+    ///
+    /// Handles at most `limit` bets and leaves a tombstone in their place. Returns
+    /// how many were archived; always 0 when no archive is configured.
+    async fn archive_terminal(&self, created_before_ms: i64, limit: i64) -> Result<usize>;
+
+    /// Number of bets currently waiting for manual review
+    async fn count_manual_review(&self) -> Result<u64>;
+
+    /// Every write to a bet, oldest first; empty for bets created before the log existed
+    async fn events(&self, bet_id: Uuid) -> Result<Vec<BetEvent>>;
+
+    /// Append a failure to a bet's error history, dropping the oldest past the cap
+    async fn record_error(&self, bet_id: Uuid, error: &BetError) -> Result<()>;
+
+    /// A bet's most recent failures, oldest first
+    async fn error_history(&self, bet_id: Uuid) -> Result<Vec<BetError>>;
 }
@@ -1,2 +1,12 @@
+pub mod audit_repository;
+pub mod bet_archive;
 pub mod bet_repository;
+pub mod batch_repository;
+pub mod ops_repository;
+pub mod queue_backend;
+pub use audit_repository::AuditRepository;
 pub use bet_repository::*;
+pub use bet_archive::BetArchive;
+pub use batch_repository::{BatchRepository, RedisBatchRepository};
+pub use ops_repository::{OpsRepository, RedisOpsRepository};
+pub use queue_backend::QueueBackend;
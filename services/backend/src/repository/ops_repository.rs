@@ -0,0 +1,364 @@
+//! Operational state handlers keep next to bets
+//!
+//! The kill switch, sessions, refunds, disputes, the ledger, wallet tokens,
+//! notification preferences, processor and SLO statistics, daily reports and
+//! fairness seeds. Each lives in its own module over Redis; this trait puts
+//! them behind one object so handlers never hold a Redis connection and run
+//! against `testing::InMemoryOpsRepository` in unit tests. The expiry sweeper
+//! goes through it too; other background workers call the modules directly.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use simulation::ServerSeed;
+use uuid::Uuid;
+
+use crate::batch_claims::BatchClaim;
+use crate::config::SloConfig;
+use crate::daily_report::{self, DailyReport, DailyTally};
+use crate::disputes::{self, Dispute};
+use crate::domain::{
+    BetStatus, ClaimableEvent, PayoutHold, Refund, RefundResult, RetryBudgetTrip, Session, WalletTokenRequest,
+};
+use crate::errors::Result;
+use crate::fairness::{self, DailySeed};
+use crate::killswitch::{self, KillSwitchState};
+use crate::ledger::{self, InvariantReport, Posting};
+use crate::notifications::{self, Notification, NotificationPreferences};
+use crate::pipeline_latency::{self, PipelineLatencyReport};
+use crate::processor_stats::{self, BatchOutcomes, ProcessorStats};
+use crate::repository::BetRepository;
+use crate::sessions::{self, SessionUsage};
+use crate::slo::{self, SloReport};
+use crate::{auth, claim_signals, middleware, payout_holds, refunds, retry_budget};
+
+#[async_trait]
+pub trait OpsRepository: Send + Sync {
+    /// Round trip to the store, for health checks
+    async fn ping(&self) -> Result<()>;
+
+    /// Current kill switch state; `None` when the system is running normally
+    async fn kill_switch(&self) -> Result<Option<KillSwitchState>>;
+
+    async fn is_halted(&self) -> Result<bool>;
+
+    async fn engage_kill_switch(&self, state: &KillSwitchState) -> Result<()>;
+
+    async fn release_kill_switch(&self) -> Result<()>;
+
+    /// The unacknowledged retry budget trip, if any
+    async fn retry_budget_trip(&self) -> Result<Option<RetryBudgetTrip>>;
+
+    /// Clear the retry budget trip, returning it; `None` if nothing was tripped
+    async fn acknowledge_retry_budget(&self) -> Result<Option<RetryBudgetTrip>>;
+
+    async fn payout_hold(&self, transaction_id: u64) -> Result<Option<PayoutHold>>;
+
+    /// Store an approved hold; the processor releases it on its next cycle
+    async fn save_payout_hold(&self, hold: &PayoutHold) -> Result<()>;
+
+    /// Payouts still awaiting review, oldest first
+    async fn pending_payout_holds(&self, limit: isize) -> Result<Vec<PayoutHold>>;
+
+    async fn create_session(&self, session: &Session) -> Result<()>;
+
+    async fn session(&self, session_id: Uuid) -> Result<Option<Session>>;
+
+    async fn session_usage(&self, session_id: Uuid) -> Result<SessionUsage>;
+
+    /// Reserve a round and `stake` for a bet, failing when the session's limits are reached
+    async fn reserve_session(&self, session: &Session, stake: u64) -> Result<()>;
+
+    /// Give back a reservation whose bet was never created
+    async fn release_session(&self, session_id: Uuid, stake: u64) -> Result<()>;
+
+    /// Store and queue a new refund; errors if the bet was already voided
+    async fn request_refund(&self, refund: &Refund) -> Result<()>;
+
+    /// Lease up to `limit` due refunds for a processor
+    async fn claim_refunds(&self, limit: i64, now_ms: i64) -> Result<Vec<Refund>>;
+
+    /// Apply a processor's result; returns the refund and whether this result finished it
+    async fn record_refund_result(&self, bet_id: Uuid, result: &RefundResult) -> Result<(Refund, bool)>;
+
+    /// Refunds still queued (oldest first) or finished ones (newest first)
+    async fn refunds(&self, pending: bool, limit: isize) -> Result<Vec<Refund>>;
+
+    async fn dispute(&self, bet_id: Uuid) -> Result<Option<Dispute>>;
+
+    /// Store a new dispute; `false` if the bet was already disputed
+    async fn open_dispute(&self, dispute: &Dispute) -> Result<bool>;
+
+    /// Persist a resolved dispute and move it to the resolved queue
+    async fn save_resolved_dispute(&self, dispute: &Dispute) -> Result<()>;
+
+    /// Disputes in the open (oldest first) or resolved (newest first) queue
+    async fn disputes(&self, open: bool, limit: isize) -> Result<Vec<Dispute>>;
+
+    /// Book `postings`, skipping those already posted; returns how many were new
+    async fn post_ledger(&self, postings: &[Posting]) -> Result<usize>;
+
+    /// Book `postings` without failing the caller; the checker surfaces anything missed
+    async fn post_ledger_or_warn(&self, postings: &[Posting]);
+
+    /// Latest comparison of the ledger with on-chain vault balances
+    async fn last_ledger_check(&self) -> Result<Option<InvariantReport>>;
+
+    /// Store a new read token for the request's wallet, once per signature; returns it with its expiry
+    async fn issue_token(
+        &self,
+        req: &WalletTokenRequest,
+        ttl_seconds: u64,
+        signature_max_age_seconds: i64,
+    ) -> Result<(String, DateTime<Utc>)>;
+
+    /// Wallet a token grants read access to, `None` if unknown or expired
+    async fn token_wallet(&self, token: &str) -> Result<Option<String>>;
+
+    /// A wallet's notification preferences; defaults when it never set any
+    async fn notification_preferences(&self, wallet: &str) -> Result<NotificationPreferences>;
+
+    async fn save_notification_preferences(&self, wallet: &str, preferences: &NotificationPreferences) -> Result<()>;
+
+    /// Queue notifications for delivery; failures are logged, never returned
+    async fn publish_notifications(&self, notifications: &[Notification]);
+
+    /// Tell every instance's subscribers a bet became claimable; failures are logged, never returned
+    async fn signal_claimable(&self, event: ClaimableEvent);
+
+    /// Count a claim request and the bets it handed out
+    async fn record_processor_claim(&self, processor_id: &str, claimed: usize) -> Result<()>;
+
+    /// Count a batch update's outcomes against the processor that claimed it
+    async fn record_batch_outcomes(&self, outcomes: &BatchOutcomes, claim: &BatchClaim) -> Result<()>;
+
+    /// The `limit` most recently seen processors, most recent first
+    async fn processor_stats(&self, limit: isize) -> Result<Vec<ProcessorStats>>;
+
+    /// Count a bet that just reached `status` against the SLO; failures are logged, never returned
+    async fn record_slo(&self, objective: &SloConfig, created_at: DateTime<Utc>, status: &BetStatus);
+
+    async fn slo_summary(&self, objective: &SloConfig) -> Result<SloReport>;
+
+    /// Recent dwell times of every settlement pipeline stage
+    async fn pipeline_latency(&self) -> Result<PipelineLatencyReport>;
+
+    /// Add a batch update's settlements to `date`'s counters
+    async fn record_daily_tally(&self, tally: &DailyTally, date: NaiveDate) -> Result<()>;
+
+    /// Stored report for `date`, or a provisional one built from the live counters
+    async fn daily_report(&self, bets: &dyn BetRepository, date: NaiveDate) -> Result<DailyReport>;
+
+    /// Fairness commitment for `day`, creating the day's seed on first use
+    async fn fairness_commitment(&self, day: NaiveDate) -> Result<String>;
+
+    /// Server seed behind a commitment, if this backend issued it
+    async fn fairness_seed(&self, commitment: &str) -> Result<Option<ServerSeed>>;
+
+    /// Most recent daily seeds, newest first; seeds of days before `today` are revealed
+    async fn fairness_seeds(&self, today: NaiveDate, limit: usize) -> Result<Vec<DailySeed>>;
+
+    /// Keep a refused bet attempt in the jurisdiction audit log
+    async fn audit_jurisdiction_block(&self, entry: &serde_json::Value) -> Result<()>;
+}
+
+/// `OpsRepository` over the Redis modules
+pub struct RedisOpsRepository {
+    redis: ConnectionManager,
+}
+
+impl RedisOpsRepository {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+
+    fn conn(&self) -> ConnectionManager {
+        self.redis.clone()
+    }
+}
+
+#[async_trait]
+impl OpsRepository for RedisOpsRepository {
+    async fn ping(&self) -> Result<()> {
+        let _: Option<String> = self.conn().get("_health_check").await?;
+        Ok(())
+    }
+
+    async fn kill_switch(&self) -> Result<Option<KillSwitchState>> {
+        killswitch::current(&mut self.conn()).await
+    }
+
+    async fn is_halted(&self) -> Result<bool> {
+        killswitch::is_engaged(&mut self.conn()).await
+    }
+
+    async fn engage_kill_switch(&self, state: &KillSwitchState) -> Result<()> {
+        killswitch::engage(&mut self.conn(), state).await
+    }
+
+    async fn release_kill_switch(&self) -> Result<()> {
+        killswitch::release(&mut self.conn()).await
+    }
+
+    async fn retry_budget_trip(&self) -> Result<Option<RetryBudgetTrip>> {
+        retry_budget::current(&mut self.conn()).await
+    }
+
+    async fn acknowledge_retry_budget(&self) -> Result<Option<RetryBudgetTrip>> {
+        retry_budget::acknowledge(&mut self.conn()).await
+    }
+
+    async fn payout_hold(&self, transaction_id: u64) -> Result<Option<PayoutHold>> {
+        payout_holds::get(&mut self.conn(), transaction_id).await
+    }
+
+    async fn save_payout_hold(&self, hold: &PayoutHold) -> Result<()> {
+        payout_holds::save(&mut self.conn(), hold).await
+    }
+
+    async fn pending_payout_holds(&self, limit: isize) -> Result<Vec<PayoutHold>> {
+        payout_holds::list_pending(&mut self.conn(), limit).await
+    }
+
+    async fn create_session(&self, session: &Session) -> Result<()> {
+        sessions::create(&mut self.conn(), session).await
+    }
+
+    async fn session(&self, session_id: Uuid) -> Result<Option<Session>> {
+        sessions::get(&mut self.conn(), session_id).await
+    }
+
+    async fn session_usage(&self, session_id: Uuid) -> Result<SessionUsage> {
+        sessions::usage(&mut self.conn(), session_id).await
+    }
+
+    async fn reserve_session(&self, session: &Session, stake: u64) -> Result<()> {
+        sessions::reserve(&mut self.conn(), session, stake).await
+    }
+
+    async fn release_session(&self, session_id: Uuid, stake: u64) -> Result<()> {
+        sessions::release(&mut self.conn(), session_id, stake).await
+    }
+
+    async fn request_refund(&self, refund: &Refund) -> Result<()> {
+        refunds::request(&mut self.conn(), refund).await
+    }
+
+    async fn claim_refunds(&self, limit: i64, now_ms: i64) -> Result<Vec<Refund>> {
+        refunds::claim(&mut self.conn(), limit, now_ms).await
+    }
+
+    async fn record_refund_result(&self, bet_id: Uuid, result: &RefundResult) -> Result<(Refund, bool)> {
+        refunds::record_result(&mut self.conn(), bet_id, result).await
+    }
+
+    async fn refunds(&self, pending: bool, limit: isize) -> Result<Vec<Refund>> {
+        refunds::list(&mut self.conn(), pending, limit).await
+    }
+
+    async fn dispute(&self, bet_id: Uuid) -> Result<Option<Dispute>> {
+        disputes::get(&mut self.conn(), bet_id).await
+    }
+
+    async fn open_dispute(&self, dispute: &Dispute) -> Result<bool> {
+        disputes::open(&mut self.conn(), dispute).await
+    }
+
+    async fn save_resolved_dispute(&self, dispute: &Dispute) -> Result<()> {
+        disputes::save_resolved(&mut self.conn(), dispute).await
+    }
+
+    async fn disputes(&self, open: bool, limit: isize) -> Result<Vec<Dispute>> {
+        disputes::list(&mut self.conn(), open, limit).await
+    }
+
+    async fn post_ledger(&self, postings: &[Posting]) -> Result<usize> {
+        ledger::post(&mut self.conn(), postings).await
+    }
+
+    async fn post_ledger_or_warn(&self, postings: &[Posting]) {
+        ledger::post_or_warn(&mut self.conn(), postings).await
+    }
+
+    async fn last_ledger_check(&self) -> Result<Option<InvariantReport>> {
+        ledger::load_last_check(&mut self.conn()).await
+    }
+
+    async fn issue_token(
+        &self,
+        req: &WalletTokenRequest,
+        ttl_seconds: u64,
+        signature_max_age_seconds: i64,
+    ) -> Result<(String, DateTime<Utc>)> {
+        auth::issue(&mut self.conn(), req, ttl_seconds, signature_max_age_seconds).await
+    }
+
+    async fn token_wallet(&self, token: &str) -> Result<Option<String>> {
+        auth::wallet_for(&mut self.conn(), token).await
+    }
+
+    async fn notification_preferences(&self, wallet: &str) -> Result<NotificationPreferences> {
+        notifications::load_preferences(&mut self.conn(), wallet).await
+    }
+
+    async fn save_notification_preferences(&self, wallet: &str, preferences: &NotificationPreferences) -> Result<()> {
+        notifications::save_preferences(&mut self.conn(), wallet, preferences).await
+    }
+
+    async fn publish_notifications(&self, notifications: &[Notification]) {
+        notifications::publish_or_warn(&mut self.conn(), notifications).await
+    }
+
+    async fn signal_claimable(&self, event: ClaimableEvent) {
+        claim_signals::signal_or_warn(&mut self.conn(), event).await
+    }
+
+    async fn record_processor_claim(&self, processor_id: &str, claimed: usize) -> Result<()> {
+        processor_stats::record_claim(&mut self.conn(), processor_id, claimed).await
+    }
+
+    async fn record_batch_outcomes(&self, outcomes: &BatchOutcomes, claim: &BatchClaim) -> Result<()> {
+        outcomes.record(&mut self.conn(), claim).await
+    }
+
+    async fn processor_stats(&self, limit: isize) -> Result<Vec<ProcessorStats>> {
+        processor_stats::list(&mut self.conn(), limit).await
+    }
+
+    async fn record_slo(&self, objective: &SloConfig, created_at: DateTime<Utc>, status: &BetStatus) {
+        slo::record_or_warn(&mut self.conn(), objective, created_at, status).await
+    }
+
+    async fn slo_summary(&self, objective: &SloConfig) -> Result<SloReport> {
+        slo::summary(&mut self.conn(), objective).await
+    }
+
+    async fn pipeline_latency(&self) -> Result<PipelineLatencyReport> {
+        pipeline_latency::summary(&mut self.conn()).await
+    }
+
+    async fn record_daily_tally(&self, tally: &DailyTally, date: NaiveDate) -> Result<()> {
+        tally.record(&mut self.conn(), date).await
+    }
+
+    async fn daily_report(&self, bets: &dyn BetRepository, date: NaiveDate) -> Result<DailyReport> {
+        daily_report::load_report(&mut self.conn(), bets, date).await
+    }
+
+    async fn fairness_commitment(&self, day: NaiveDate) -> Result<String> {
+        fairness::commitment_for(&mut self.conn(), day).await
+    }
+
+    async fn fairness_seed(&self, commitment: &str) -> Result<Option<ServerSeed>> {
+        fairness::seed_for_commitment(&mut self.conn(), commitment).await
+    }
+
+    async fn fairness_seeds(&self, today: NaiveDate, limit: usize) -> Result<Vec<DailySeed>> {
+        fairness::list_seeds(&mut self.conn(), today, limit).await
+    }
+
+    async fn audit_jurisdiction_block(&self, entry: &serde_json::Value) -> Result<()> {
+        middleware::audit_jurisdiction_block(&mut self.conn(), entry).await
+    }
+}
@@ -0,0 +1,75 @@
+//! Queue backends for bet scheduling
+//!
+//! Bets themselves live in Redis hashes, but the claimable/processing indexes and
+//! the pending-bet stream go through a `QueueBackend` so deployments can swap Redis
+//! for another broker. Each backend must make the claim step atomic: a bet handed
+//! to one processor must never be handed to another.
+
+mod redis;
+#[cfg(feature = "nats")]
+mod nats;
+#[cfg(feature = "nats")]
+mod nats_protocol;
+
+pub use self::redis::RedisQueueBackend;
+#[cfg(feature = "nats")]
+pub use self::nats::NatsQueueBackend;
+
+use async_trait::async_trait;
+use ::redis::aio::ConnectionManager;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::QueueConfig;
+use crate::errors::Result;
+
+/// Scheduling operations for claimable/processing bets and the pending stream
+#[async_trait]
+pub trait QueueBackend: Send + Sync {
+    /// Short backend name for logs and health output
+    fn name(&self) -> &'static str;
+
+    /// Make a bet claimable once `available_at_ms` has passed (removes it from processing)
+    async fn make_claimable(&self, bet_id: Uuid, available_at_ms: i64) -> Result<()>;
+
+    /// Make a bet claimable ahead of all regular bets (admin-initiated settlements)
+    async fn make_priority_claimable(&self, bet_id: Uuid, now_ms: i64) -> Result<()>;
+
+    /// Atomically move up to `limit` due bets from claimable to processing,
+    /// draining the priority lane first
+    async fn claim_due(&self, limit: i64, now_ms: i64) -> Result<Vec<Uuid>>;
+
+    /// Up to `limit` due bets in claim order after skipping the first `offset`,
+    /// each with whether it is on the priority lane, without claiming them
+    async fn peek_due(&self, offset: i64, limit: i64, now_ms: i64) -> Result<Vec<(Uuid, bool)>>;
+
+    /// Atomically move those of `bet_ids` still claimable to processing; returns the ones moved
+    async fn claim_ids(&self, bet_ids: &[Uuid]) -> Result<Vec<Uuid>>;
+
+    /// Move a bet into processing (removes it from claimable)
+    async fn mark_processing(&self, bet_id: Uuid, now_ms: i64) -> Result<()>;
+
+    /// Drop a bet from both claimable and processing
+    async fn remove(&self, bet_id: Uuid) -> Result<()>;
+
+    /// Claimable bets in both lanes, due or not
+    async fn claimable_depth(&self) -> Result<u64>;
+
+    /// Atomically remove a bet from claimable; false if it was already claimed or gone
+    async fn take_claimable(&self, bet_id: Uuid) -> Result<bool>;
+
+    /// Announce a newly created bet to processors
+    async fn publish_pending(&self, bet_id: Uuid) -> Result<()>;
+}
+
+/// Build the queue backend selected by `QUEUE_BACKEND`
+pub async fn connect(config: &QueueConfig, redis: ConnectionManager) -> anyhow::Result<Arc<dyn QueueBackend>> {
+    match config.backend.as_str() {
+        "redis" => Ok(Arc::new(RedisQueueBackend::new(redis))),
+        #[cfg(feature = "nats")]
+        "nats" => Ok(Arc::new(NatsQueueBackend::connect(&config.nats_url).await?)),
+        #[cfg(not(feature = "nats"))]
+        "nats" => anyhow::bail!("QUEUE_BACKEND=nats requires building with the `nats` feature"),
+        other => anyhow::bail!("Unknown QUEUE_BACKEND: {}", other),
+    }
+}
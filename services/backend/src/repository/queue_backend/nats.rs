@@ -0,0 +1,589 @@
+//! NATS JetStream queue backend
+//!
+//! Claimable/processing state is kept in a JetStream key-value bucket keyed by bet ID.
+//! The Redis Lua claim becomes a compare-and-swap per entry: an entry is only moved to
+//! processing if its subject sequence is unchanged since we read it, so two backends
+//! racing for the same bet cannot both win. New bets are published to a JetStream
+//! stream that processors can consume.
+//!
+//! Finding claimable bets never lists the bucket. Making a bet claimable also
+//! publishes its ID to a work-queue stream with one durable pull consumer per
+//! lane, so a claim fetches only as many messages as it wants. A fetched bet that
+//! is not due yet is nak'd with a delay until it is; one whose entry was claimed,
+//! taken or removed since is acked and dropped. Unacked fetches are redelivered
+//! after `CLAIM_ACK_WAIT`, so a backend that dies mid-claim loses nothing. Depth
+//! comes from the consumers' pending counts.
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::nats_protocol::NatsConnection;
+use super::QueueBackend;
+use crate::errors::{AppError, Result};
+
+/// JetStream KV bucket holding claimable/processing entries
+const QUEUE_BUCKET: &str = "bets_queue";
+
+/// Stream and subject for newly created bets
+const PENDING_STREAM: &str = "BETS_PENDING";
+const PENDING_SUBJECT: &str = "bets.pending";
+
+/// Work-queue stream announcing claimable bets, one subject per lane
+const CLAIMABLE_STREAM: &str = "BETS_CLAIMABLE";
+
+/// How long a fetched claimable message may go unacked before redelivery
+const CLAIM_ACK_WAIT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// JetStream error code for a failed `Nats-Expected-Last-Subject-Sequence` check
+const WRONG_LAST_SEQUENCE: u64 = 10071;
+
+/// JetStream error code for a message get on a subject with no messages
+const NO_MESSAGE_FOUND: u64 = 10037;
+
+/// JetStream error code for creating a stream that already exists
+const STREAM_NAME_IN_USE: u64 = 10058;
+
+/// JetStream error code for creating a consumer that exists with another config
+const CONSUMER_ALREADY_EXISTS: u64 = 10148;
+
+/// Claim lanes, each with its own subject and durable consumer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lane {
+    Priority,
+    Regular,
+}
+
+impl Lane {
+    fn subject(self) -> &'static str {
+        match self {
+            Lane::Priority => "bets.claimable.priority",
+            Lane::Regular => "bets.claimable.regular",
+        }
+    }
+
+    fn consumer(self) -> &'static str {
+        match self {
+            Lane::Priority => "claim_priority",
+            Lane::Regular => "claim_regular",
+        }
+    }
+}
+
+/// Body of a claimable-stream message
+#[derive(Debug, Serialize, Deserialize)]
+struct ClaimableMessage {
+    bet_id: Uuid,
+}
+
+/// A claimable message fetched from a lane consumer, not yet acked
+struct Delivery {
+    bet_id: Uuid,
+    /// Ack subject; `None` for a message we could not parse
+    reply: Option<String>,
+}
+
+/// What to tell JetStream about a fetched message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ack {
+    /// Done with it: the bet was claimed or the message is stale
+    Ack,
+    /// Redeliver after the delay
+    Nak(std::time::Duration),
+}
+
+impl Ack {
+    fn payload(self) -> Vec<u8> {
+        match self {
+            Ack::Ack => b"+ACK".to_vec(),
+            Ack::Nak(delay) => format!("-NAK {}", json!({ "delay": delay.as_nanos() as u64 })).into_bytes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum QueueState {
+    Claimable,
+    Processing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueueEntry {
+    state: QueueState,
+    available_at_ms: i64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    priority: bool,
+}
+
+/// Queue backend on NATS JetStream
+pub struct NatsQueueBackend {
+    conn: NatsConnection,
+}
+
+impl NatsQueueBackend {
+    /// Connect and make sure the KV bucket and pending stream exist
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        let conn = NatsConnection::connect(url).await?;
+        let backend = Self { conn };
+
+        backend
+            .ensure_stream(json!({
+                "name": kv_stream(),
+                "subjects": [format!("$KV.{}.>", QUEUE_BUCKET)],
+                "max_msgs_per_subject": 1,
+                "discard": "new",
+                "allow_rollup_hdrs": true,
+                "deny_delete": true,
+                "allow_direct": true,
+                "storage": "file",
+            }))
+            .await?;
+        backend
+            .ensure_stream(json!({
+                "name": PENDING_STREAM,
+                "subjects": [PENDING_SUBJECT],
+                "storage": "file",
+            }))
+            .await?;
+        backend
+            .ensure_stream(json!({
+                "name": CLAIMABLE_STREAM,
+                "subjects": [Lane::Priority.subject(), Lane::Regular.subject()],
+                "retention": "workqueue",
+                "storage": "file",
+            }))
+            .await?;
+        for lane in [Lane::Priority, Lane::Regular] {
+            backend.ensure_consumer(lane).await?;
+        }
+
+        Ok(backend)
+    }
+
+    async fn ensure_consumer(&self, lane: Lane) -> anyhow::Result<()> {
+        let reply = self
+            .api(
+                &format!("$JS.API.CONSUMER.CREATE.{}.{}", CLAIMABLE_STREAM, lane.consumer()),
+                &json!({
+                    "stream_name": CLAIMABLE_STREAM,
+                    "config": {
+                        "durable_name": lane.consumer(),
+                        "filter_subject": lane.subject(),
+                        "ack_policy": "explicit",
+                        "ack_wait": CLAIM_ACK_WAIT.as_nanos() as u64,
+                        "deliver_policy": "all",
+                    },
+                }),
+            )
+            .await;
+
+        match reply {
+            Ok(_) => Ok(()),
+            Err(JsError::Api { code, .. }) if code == CONSUMER_ALREADY_EXISTS => Ok(()),
+            Err(e) => Err(anyhow!("Failed to create JetStream consumer {}: {}", lane.consumer(), e)),
+        }
+    }
+
+    async fn ensure_stream(&self, config: Value) -> anyhow::Result<()> {
+        let name = config["name"].as_str().unwrap_or_default().to_string();
+        let reply = self
+            .api(&format!("$JS.API.STREAM.CREATE.{}", name), &config)
+            .await;
+
+        match reply {
+            Ok(_) => Ok(()),
+            Err(JsError::Api { code, .. }) if code == STREAM_NAME_IN_USE => Ok(()),
+            Err(e) => Err(anyhow!("Failed to create JetStream stream {}: {}", name, e)),
+        }
+    }
+
+    /// Call a JetStream API subject and surface API-level errors
+    async fn api(&self, subject: &str, body: &Value) -> std::result::Result<Value, JsError> {
+        let payload = serde_json::to_vec(body).map_err(|e| JsError::Transport(e.into()))?;
+        self.request(subject, &[], &payload).await
+    }
+
+    async fn request(
+        &self,
+        subject: &str,
+        headers: &[(&str, &str)],
+        payload: &[u8],
+    ) -> std::result::Result<Value, JsError> {
+        let msg = self
+            .conn
+            .request(subject, headers, payload)
+            .await
+            .map_err(JsError::Transport)?;
+        let value: Value = serde_json::from_slice(&msg.payload)
+            .map_err(|e| JsError::Transport(anyhow!("Invalid JetStream reply: {}", e)))?;
+
+        if let Some(error) = value.get("error") {
+            return Err(JsError::Api {
+                code: error["err_code"].as_u64().unwrap_or_default(),
+                description: error["description"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+
+        Ok(value)
+    }
+
+    async fn put(&self, bet_id: Uuid, entry: &QueueEntry) -> Result<()> {
+        let payload = serde_json::to_vec(entry).map_err(anyhow::Error::from)?;
+        self.request(&kv_subject(bet_id), &[], &payload).await?;
+        Ok(())
+    }
+
+    /// Write an entry only if the key has not changed since `revision`
+    async fn compare_and_put(&self, bet_id: Uuid, entry: &QueueEntry, revision: u64) -> Result<bool> {
+        let payload = serde_json::to_vec(entry).map_err(anyhow::Error::from)?;
+        let revision = revision.to_string();
+        let reply = self
+            .request(
+                &kv_subject(bet_id),
+                &[("Nats-Expected-Last-Subject-Sequence", revision.as_str())],
+                &payload,
+            )
+            .await;
+
+        match reply {
+            Ok(_) => Ok(true),
+            Err(JsError::Api { code, .. }) if code == WRONG_LAST_SEQUENCE => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Latest entry for a bet with its revision; `None` if missing or purged
+    async fn entry(&self, bet_id: Uuid) -> Result<Option<(QueueEntry, u64)>> {
+        let reply = self
+            .api(
+                &format!("$JS.API.STREAM.MSG.GET.{}", kv_stream()),
+                &json!({ "last_by_subj": kv_subject(bet_id) }),
+            )
+            .await;
+
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(JsError::Api { code, .. }) if code == NO_MESSAGE_FOUND => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let message = &reply["message"];
+        let revision = message["seq"].as_u64().unwrap_or_default();
+        let Some(data) = message["data"].as_str().filter(|d| !d.is_empty()) else {
+            // Purge markers carry no data
+            return Ok(None);
+        };
+
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| anyhow!("Invalid KV payload for {}: {}", bet_id, e))?;
+
+        Ok(serde_json::from_slice::<QueueEntry>(&bytes)
+            .ok()
+            .map(|entry| (entry, revision)))
+    }
+
+    /// Purge a key only if it has not changed since `revision`
+    async fn compare_and_purge(&self, bet_id: Uuid, revision: u64) -> Result<bool> {
+        let revision = revision.to_string();
+        let reply = self
+            .request(
+                &kv_subject(bet_id),
+                &[
+                    ("KV-Operation", "PURGE"),
+                    ("Nats-Rollup", "sub"),
+                    ("Nats-Expected-Last-Subject-Sequence", revision.as_str()),
+                ],
+                b"",
+            )
+            .await;
+
+        match reply {
+            Ok(_) => Ok(true),
+            Err(JsError::Api { code, .. }) if code == WRONG_LAST_SEQUENCE => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write a claimable entry and announce it on its lane
+    async fn put_claimable(&self, bet_id: Uuid, entry: &QueueEntry) -> Result<()> {
+        self.put(bet_id, entry).await?;
+        let lane = if entry.priority { Lane::Priority } else { Lane::Regular };
+        let payload = serde_json::to_vec(&ClaimableMessage { bet_id }).map_err(anyhow::Error::from)?;
+        self.request(lane.subject(), &[], &payload).await?;
+        Ok(())
+    }
+
+    /// Fetch up to `batch` undelivered messages from a lane without waiting for more
+    async fn fetch(&self, lane: Lane, batch: usize) -> Result<Vec<Delivery>> {
+        if batch == 0 {
+            return Ok(Vec::new());
+        }
+        let body = serde_json::to_vec(&json!({ "batch": batch, "no_wait": true })).map_err(anyhow::Error::from)?;
+        let messages = self
+            .conn
+            .request_many(
+                &format!("$JS.API.CONSUMER.MSG.NEXT.{}.{}", CLAIMABLE_STREAM, lane.consumer()),
+                &body,
+                batch,
+            )
+            .await
+            .map_err(AppError::Internal)?;
+
+        let mut deliveries = Vec::with_capacity(messages.len());
+        for message in messages {
+            match serde_json::from_slice::<ClaimableMessage>(&message.payload) {
+                Ok(parsed) => deliveries.push(Delivery { bet_id: parsed.bet_id, reply: message.reply }),
+                Err(e) => {
+                    // Nothing can ever claim it; drop it rather than redeliver it forever
+                    tracing::warn!(error = %e, "Dropping malformed claimable message");
+                    if let Some(reply) = &message.reply {
+                        self.conn.publish(reply, &Ack::Ack.payload()).await.map_err(AppError::Internal)?;
+                    }
+                }
+            }
+        }
+        Ok(deliveries)
+    }
+
+    async fn ack(&self, delivery: &Delivery, ack: Ack) -> Result<()> {
+        if let Some(reply) = &delivery.reply {
+            self.conn.publish(reply, &ack.payload()).await.map_err(AppError::Internal)?;
+        }
+        Ok(())
+    }
+
+    /// Fetch up to `limit` messages from a lane and pair each with its bet's
+    /// entry. Stale messages are acked away and claimable bets that are not
+    /// due by `now_ms` are nak'd until they are; the rest are returned unacked.
+    async fn fetch_due(&self, lane: Lane, limit: usize, now_ms: i64) -> Result<Vec<(Delivery, QueueEntry, u64)>> {
+        let mut due = Vec::new();
+        for delivery in self.fetch(lane, limit).await? {
+            match self.entry(delivery.bet_id).await? {
+                Some((entry, revision)) if entry.state == QueueState::Claimable => {
+                    if entry.available_at_ms > now_ms {
+                        self.ack(&delivery, Ack::Nak(not_due_delay(entry.available_at_ms, now_ms))).await?;
+                    } else {
+                        due.push((delivery, entry, revision));
+                    }
+                }
+                // Claimed, taken or removed since it was announced
+                _ => self.ack(&delivery, Ack::Ack).await?,
+            }
+        }
+        Ok(due)
+    }
+
+    /// Due bets from `lanes` in order, left unclaimed for other callers
+    ///
+    /// A consumer cannot seek, so reaching `offset` fetches the bets before it too.
+    async fn peek_lanes(&self, lanes: &[Lane], offset: usize, limit: usize, now_ms: i64) -> Result<Vec<(Uuid, QueueEntry)>> {
+        let wanted = offset.saturating_add(limit);
+        let mut due = Vec::new();
+        for lane in lanes {
+            let fetched = self.fetch_due(*lane, wanted.saturating_sub(due.len()), now_ms).await?;
+            for (delivery, entry, _) in fetched {
+                self.ack(&delivery, Ack::Nak(std::time::Duration::ZERO)).await?;
+                due.push((delivery.bet_id, entry));
+            }
+        }
+        Ok(due.into_iter().skip(offset).collect())
+    }
+
+    /// Messages waiting on a lane, delivered or not
+    async fn lane_depth(&self, lane: Lane) -> Result<u64> {
+        let info = self
+            .api(&format!("$JS.API.CONSUMER.INFO.{}.{}", CLAIMABLE_STREAM, lane.consumer()), &json!({}))
+            .await?;
+        Ok(info["num_pending"].as_u64().unwrap_or_default() + info["num_ack_pending"].as_u64().unwrap_or_default())
+    }
+}
+
+/// How long to hold back a bet that becomes due at `available_at_ms`
+fn not_due_delay(available_at_ms: i64, now_ms: i64) -> std::time::Duration {
+    std::time::Duration::from_millis(available_at_ms.saturating_sub(now_ms).max(0) as u64)
+}
+
+#[async_trait]
+impl QueueBackend for NatsQueueBackend {
+    fn name(&self) -> &'static str {
+        "nats"
+    }
+
+    async fn make_claimable(&self, bet_id: Uuid, available_at_ms: i64) -> Result<()> {
+        self.put_claimable(
+            bet_id,
+            &QueueEntry {
+                state: QueueState::Claimable,
+                available_at_ms,
+                priority: false,
+            },
+        )
+        .await
+    }
+
+    async fn make_priority_claimable(&self, bet_id: Uuid, now_ms: i64) -> Result<()> {
+        self.put_claimable(
+            bet_id,
+            &QueueEntry {
+                state: QueueState::Claimable,
+                available_at_ms: now_ms,
+                priority: true,
+            },
+        )
+        .await
+    }
+
+    async fn claim_due(&self, limit: i64, now_ms: i64) -> Result<Vec<Uuid>> {
+        let limit = limit.max(0) as usize;
+        let mut claimed = Vec::new();
+        for lane in [Lane::Priority, Lane::Regular] {
+            for (delivery, entry, revision) in self.fetch_due(lane, limit.saturating_sub(claimed.len()), now_ms).await? {
+                let processing = QueueEntry { state: QueueState::Processing, ..entry };
+                // Losing the CAS means another backend instance claimed it first;
+                // if the bet was made claimable again, that published a new message
+                if self.compare_and_put(delivery.bet_id, &processing, revision).await? {
+                    claimed.push(delivery.bet_id);
+                }
+                self.ack(&delivery, Ack::Ack).await?;
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    async fn peek_due(&self, offset: i64, limit: i64, now_ms: i64) -> Result<Vec<(Uuid, bool)>> {
+        Ok(self
+            .peek_lanes(&[Lane::Priority, Lane::Regular], offset.max(0) as usize, limit.max(0) as usize, now_ms)
+            .await?
+            .into_iter()
+            .map(|(bet_id, entry)| (bet_id, entry.priority))
+            .collect())
+    }
+
+    async fn claim_ids(&self, bet_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        let mut claimed = Vec::with_capacity(bet_ids.len());
+        for bet_id in bet_ids {
+            let Some((entry, revision)) = self.entry(*bet_id).await? else {
+                continue;
+            };
+            if entry.state != QueueState::Claimable {
+                continue;
+            }
+            let processing = QueueEntry { state: QueueState::Processing, ..entry };
+            if self.compare_and_put(*bet_id, &processing, revision).await? {
+                claimed.push(*bet_id);
+            }
+        }
+        Ok(claimed)
+    }
+
+    async fn mark_processing(&self, bet_id: Uuid, now_ms: i64) -> Result<()> {
+        self.put(
+            bet_id,
+            &QueueEntry {
+                state: QueueState::Processing,
+                available_at_ms: now_ms,
+                priority: false,
+            },
+        )
+        .await
+    }
+
+    async fn remove(&self, bet_id: Uuid) -> Result<()> {
+        self.request(
+            &kv_subject(bet_id),
+            &[("KV-Operation", "PURGE"), ("Nats-Rollup", "sub")],
+            b"",
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Messages still waiting on either lane; a message whose bet was claimed or
+    /// removed some other way counts until a fetch drops it
+    async fn claimable_depth(&self) -> Result<u64> {
+        Ok(self.lane_depth(Lane::Priority).await? + self.lane_depth(Lane::Regular).await?)
+    }
+
+    async fn take_claimable(&self, bet_id: Uuid) -> Result<bool> {
+        match self.entry(bet_id).await? {
+            Some((entry, revision)) if entry.state == QueueState::Claimable => {
+                self.compare_and_purge(bet_id, revision).await
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn publish_pending(&self, bet_id: Uuid) -> Result<()> {
+        let payload = serde_json::to_vec(&json!({ "bet_id": bet_id })).map_err(anyhow::Error::from)?;
+        self.request(PENDING_SUBJECT, &[("Nats-Msg-Id", &bet_id.to_string())], &payload)
+            .await
+            .context("Failed to publish pending bet to JetStream")
+            .map_err(AppError::Internal)?;
+        Ok(())
+    }
+}
+
+fn kv_stream() -> String {
+    format!("KV_{}", QUEUE_BUCKET)
+}
+
+fn kv_subject(bet_id: Uuid) -> String {
+    format!("$KV.{}.{}", QUEUE_BUCKET, bet_id)
+}
+
+#[derive(Debug, thiserror::Error)]
+enum JsError {
+    #[error("JetStream API error {code}: {description}")]
+    Api { code: u64, description: String },
+
+    #[error(transparent)]
+    Transport(anyhow::Error),
+}
+
+impl From<JsError> for AppError {
+    fn from(e: JsError) -> Self {
+        AppError::Internal(anyhow!(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_entry_round_trip() {
+        let entry = QueueEntry {
+            state: QueueState::Claimable,
+            available_at_ms: 1_700_000_000_000,
+            priority: false,
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(json, r#"{"state":"claimable","available_at_ms":1700000000000}"#);
+
+        let parsed: QueueEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.state, QueueState::Claimable);
+    }
+
+    #[test]
+    fn test_ack_payloads() {
+        assert_eq!(Ack::Ack.payload(), b"+ACK");
+        assert_eq!(
+            String::from_utf8(Ack::Nak(std::time::Duration::from_millis(1_500)).payload()).unwrap(),
+            r#"-NAK {"delay":1500000000}"#
+        );
+        assert_eq!(not_due_delay(1_000, 400), std::time::Duration::from_millis(600));
+        assert_eq!(not_due_delay(400, 1_000), std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn test_kv_subject_format() {
+        let id = Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap();
+        assert_eq!(kv_subject(id), "$KV.bets_queue.550e8400-e29b-41d4-a716-446655440000");
+    }
+}
@@ -0,0 +1,306 @@
+//! Minimal NATS client for the JetStream queue backend
+//!
+//! Only what the queue backend needs: request/reply with optional headers over a
+//! single connection, plus plain publishes for message acks. Every JetStream API
+//! call and acknowledged publish is a request to a unique inbox subscription; a
+//! pull consumer fetch is the one request whose inbox takes several replies.
+
+use anyhow::{anyhow, bail, Context};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Mutex};
+
+/// How long to wait for a reply before giving up
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A message delivered to one of our inboxes
+#[derive(Debug, Clone)]
+pub struct NatsMessage {
+    pub status: Option<u16>,
+    /// Reply subject; a JetStream delivery is acked by publishing to it
+    pub reply: Option<String>,
+    pub payload: Vec<u8>,
+}
+
+type PendingReplies = Arc<Mutex<HashMap<u64, mpsc::UnboundedSender<NatsMessage>>>>;
+
+pub struct NatsConnection {
+    writer: Arc<Mutex<OwnedWriteHalf>>,
+    pending: PendingReplies,
+    inbox_prefix: String,
+    next_sid: AtomicU64,
+}
+
+impl NatsConnection {
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        let addr = url.trim_start_matches("nats://");
+        let stream = TcpStream::connect(addr)
+            .await
+            .with_context(|| format!("Failed to connect to NATS at {}", addr))?;
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = BufReader::new(read_half);
+
+        let mut info = String::new();
+        reader.read_line(&mut info).await?;
+        if !info.starts_with("INFO") {
+            bail!("Unexpected NATS greeting: {}", info.trim_end());
+        }
+
+        write_half
+            .write_all(
+                b"CONNECT {\"verbose\":false,\"pedantic\":false,\"headers\":true,\"no_responders\":true}\r\nPING\r\n",
+            )
+            .await?;
+
+        let mut pong = String::new();
+        reader.read_line(&mut pong).await?;
+        if !pong.starts_with("PONG") {
+            bail!("NATS handshake failed: {}", pong.trim_end());
+        }
+
+        let writer = Arc::new(Mutex::new(write_half));
+        let pending: PendingReplies = Arc::new(Mutex::new(HashMap::new()));
+
+        tokio::spawn(read_loop(reader, writer.clone(), pending.clone()));
+
+        Ok(Self {
+            writer,
+            pending,
+            inbox_prefix: format!("_INBOX.{}", uuid::Uuid::new_v4().simple()),
+            next_sid: AtomicU64::new(1),
+        })
+    }
+
+    /// Send a request and wait for the single reply
+    pub async fn request(
+        &self,
+        subject: &str,
+        headers: &[(&str, &str)],
+        payload: &[u8],
+    ) -> anyhow::Result<NatsMessage> {
+        let (sid, mut rx) = self.send_request(subject, headers, payload, 1).await?;
+        let reply = tokio::time::timeout(REQUEST_TIMEOUT, rx.recv()).await;
+        self.pending.lock().await.remove(&sid);
+
+        match reply {
+            Ok(Some(msg)) => {
+                if msg.status == Some(503) {
+                    bail!("No responders for NATS subject {}", subject);
+                }
+                Ok(msg)
+            }
+            Ok(None) => bail!("NATS connection closed"),
+            Err(_) => bail!("NATS request to {} timed out", subject),
+        }
+    }
+
+    /// Send a request and collect up to `max` replies, stopping early at the
+    /// first status-only reply (JetStream's end-of-batch marker)
+    pub async fn request_many(&self, subject: &str, payload: &[u8], max: usize) -> anyhow::Result<Vec<NatsMessage>> {
+        let (sid, mut rx) = self.send_request(subject, &[], payload, max + 1).await?;
+        let deadline = tokio::time::Instant::now() + REQUEST_TIMEOUT;
+        let mut replies = Vec::with_capacity(max);
+
+        while replies.len() < max {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(msg)) if msg.status == Some(503) => {
+                    self.pending.lock().await.remove(&sid);
+                    bail!("No responders for NATS subject {}", subject);
+                }
+                Ok(Some(msg)) if msg.status.is_some() => break,
+                Ok(Some(msg)) => replies.push(msg),
+                Ok(None) => bail!("NATS connection closed"),
+                // Whatever arrived before the deadline is the batch
+                Err(_) => break,
+            }
+        }
+
+        self.pending.lock().await.remove(&sid);
+        let _ = self.writer.lock().await.write_all(format!("UNSUB {}\r\n", sid).as_bytes()).await;
+        Ok(replies)
+    }
+
+    /// Publish without waiting for a reply
+    pub async fn publish(&self, subject: &str, payload: &[u8]) -> anyhow::Result<()> {
+        self.writer
+            .lock()
+            .await
+            .write_all(&encode_publish(subject, None, &[], payload))
+            .await?;
+        Ok(())
+    }
+
+    /// Subscribe a fresh inbox for at most `max_replies` replies and send the request to it
+    async fn send_request(
+        &self,
+        subject: &str,
+        headers: &[(&str, &str)],
+        payload: &[u8],
+        max_replies: usize,
+    ) -> anyhow::Result<(u64, mpsc::UnboundedReceiver<NatsMessage>)> {
+        let sid = self.next_sid.fetch_add(1, Ordering::SeqCst);
+        let inbox = format!("{}.{}", self.inbox_prefix, sid);
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.pending.lock().await.insert(sid, tx);
+
+        let mut frame = format!("SUB {} {}\r\nUNSUB {} {}\r\n", inbox, sid, sid, max_replies).into_bytes();
+        frame.extend_from_slice(&encode_publish(subject, Some(&inbox), headers, payload));
+
+        if let Err(e) = self.writer.lock().await.write_all(&frame).await {
+            self.pending.lock().await.remove(&sid);
+            return Err(e.into());
+        }
+        Ok((sid, rx))
+    }
+}
+
+/// Encode a PUB or HPUB frame
+pub fn encode_publish(
+    subject: &str,
+    reply: Option<&str>,
+    headers: &[(&str, &str)],
+    payload: &[u8],
+) -> Vec<u8> {
+    let reply = reply.map(|r| format!(" {}", r)).unwrap_or_default();
+    let mut frame = Vec::with_capacity(payload.len() + 64);
+
+    if headers.is_empty() {
+        frame.extend_from_slice(format!("PUB {}{} {}\r\n", subject, reply, payload.len()).as_bytes());
+    } else {
+        let mut header_block = String::from("NATS/1.0\r\n");
+        for (name, value) in headers {
+            header_block.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        header_block.push_str("\r\n");
+
+        frame.extend_from_slice(
+            format!(
+                "HPUB {}{} {} {}\r\n",
+                subject,
+                reply,
+                header_block.len(),
+                header_block.len() + payload.len()
+            )
+            .as_bytes(),
+        );
+        frame.extend_from_slice(header_block.as_bytes());
+    }
+
+    frame.extend_from_slice(payload);
+    frame.extend_from_slice(b"\r\n");
+    frame
+}
+
+/// Parse the status code from an HMSG header block (`NATS/1.0 503` for no responders)
+pub fn parse_status(block: &str) -> Option<u16> {
+    block
+        .split("\r\n")
+        .next()
+        .and_then(|first| first.strip_prefix("NATS/1.0"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|code| code.parse().ok())
+}
+
+async fn read_loop(
+    mut reader: BufReader<OwnedReadHalf>,
+    writer: Arc<Mutex<OwnedWriteHalf>>,
+    pending: PendingReplies,
+) {
+    if let Err(e) = read_messages(&mut reader, &writer, &pending).await {
+        tracing::error!(error = %e, "NATS connection lost");
+    }
+    // Dropping the senders wakes every waiter with a closed-connection error
+    pending.lock().await.clear();
+}
+
+async fn read_messages(
+    reader: &mut BufReader<OwnedReadHalf>,
+    writer: &Mutex<OwnedWriteHalf>,
+    pending: &PendingReplies,
+) -> anyhow::Result<()> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            bail!("NATS server closed the connection");
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        match parts.first().copied() {
+            Some("PING") => writer.lock().await.write_all(b"PONG\r\n").await?,
+            Some("PONG") | Some("+OK") | Some("INFO") => {}
+            Some("-ERR") => tracing::warn!(error = %line.trim_end(), "NATS server error"),
+            Some("MSG") => {
+                // MSG <subject> <sid> [reply] <bytes>
+                let sid = parse_field(&parts, 2)?;
+                let reply = (parts.len() == 5).then(|| parts[3].to_string());
+                let size: usize = parse_field(&parts, parts.len() - 1)?;
+                let body = read_body(reader, size).await?;
+                deliver(pending, sid, NatsMessage { status: None, reply, payload: body }).await;
+            }
+            Some("HMSG") => {
+                // HMSG <subject> <sid> [reply] <header bytes> <total bytes>
+                let sid = parse_field(&parts, 2)?;
+                let reply = (parts.len() == 6).then(|| parts[3].to_string());
+                let header_size: usize = parse_field(&parts, parts.len() - 2)?;
+                let total_size: usize = parse_field(&parts, parts.len() - 1)?;
+                let body = read_body(reader, total_size).await?;
+                let header_size = header_size.min(body.len());
+                let status = parse_status(&String::from_utf8_lossy(&body[..header_size]));
+                let payload = body[header_size..].to_vec();
+                deliver(pending, sid, NatsMessage { status, reply, payload }).await;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(parts: &[&str], index: usize) -> anyhow::Result<T> {
+    parts
+        .get(index)
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| anyhow!("Malformed NATS frame: {}", parts.join(" ")))
+}
+
+async fn read_body(reader: &mut BufReader<OwnedReadHalf>, size: usize) -> anyhow::Result<Vec<u8>> {
+    // Payload is followed by a trailing CRLF
+    let mut body = vec![0u8; size + 2];
+    reader.read_exact(&mut body).await?;
+    body.truncate(size);
+    Ok(body)
+}
+
+async fn deliver(pending: &PendingReplies, sid: u64, msg: NatsMessage) {
+    if let Some(tx) = pending.lock().await.get(&sid) {
+        let _ = tx.send(msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_plain_publish() {
+        let frame = encode_publish("bets.pending", Some("_INBOX.1"), &[], b"hello");
+        assert_eq!(frame, b"PUB bets.pending _INBOX.1 5\r\nhello\r\n");
+    }
+
+    #[test]
+    fn test_encode_publish_with_headers() {
+        let frame = encode_publish("$KV.q.k", None, &[("KV-Operation", "PURGE")], b"");
+        let expected = "HPUB $KV.q.k 33 33\r\nNATS/1.0\r\nKV-Operation: PURGE\r\n\r\n\r\n";
+        assert_eq!(String::from_utf8(frame).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(parse_status("NATS/1.0 503\r\n\r\n"), Some(503));
+        assert_eq!(parse_status("NATS/1.0\r\nKV-Operation: DEL\r\n\r\n"), None);
+    }
+}
@@ -0,0 +1,249 @@
+//! Redis queue backend
+//!
+//! Claimable and processing indexes are sorted sets scored by `available_at_ms`;
+//! pending bets are announced on a Redis stream. Claims run as a Lua script so the
+//! move between sets is atomic.
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Script};
+use uuid::Uuid;
+
+use super::QueueBackend;
+use crate::errors::Result;
+use crate::repository::bet_repository::{
+    claimable_index_key, priority_index_key, processing_index_key,
+};
+
+/// Redis stream that processors listen on for new bets
+const PENDING_STREAM: &str = "bets:pending";
+
+/// Lua script to atomically move due bets from claimable to processing
+///
+/// Keys: [priority_index, claimable_index, processing_index]
+/// Args: [limit, now_ms]
+///
+/// Returns: Array of claimed bet IDs, priority lane first
+pub const CLAIM_DUE_SCRIPT: &str = r#"
+local priority = KEYS[1]
+local claimable = KEYS[2]
+local processing = KEYS[3]
+local limit = tonumber(ARGV[1])
+local now_ms = tonumber(ARGV[2])
+local claimed = {}
+
+-- Claim only bets that are due (score <= now_ms). Score is treated as "available_at_ms".
+for _, lane in ipairs({ priority, claimable }) do
+  local remaining = limit - #claimed
+  if remaining <= 0 then
+    break
+  end
+
+  local entries = redis.call('ZRANGEBYSCORE', lane, '-inf', now_ms, 'WITHSCORES', 'LIMIT', 0, remaining)
+  for i = 1, #entries, 2 do
+    local bet_id = entries[i]
+    local score = entries[i + 1]
+    redis.call('ZREM', lane, bet_id)
+    redis.call('ZADD', processing, score, bet_id)
+    table.insert(claimed, bet_id)
+  end
+end
+
+return claimed
+"#;
+
+/// Lua script to atomically move the listed bets from claimable to processing
+///
+/// Keys: [priority_index, claimable_index, processing_index]
+/// Args: bet IDs
+///
+/// Returns: Array of the bet IDs that were still claimable
+pub const CLAIM_IDS_SCRIPT: &str = r#"
+local priority = KEYS[1]
+local claimable = KEYS[2]
+local processing = KEYS[3]
+local claimed = {}
+
+for _, bet_id in ipairs(ARGV) do
+  local score = redis.call('ZSCORE', priority, bet_id) or redis.call('ZSCORE', claimable, bet_id)
+  if score then
+    redis.call('ZREM', priority, bet_id)
+    redis.call('ZREM', claimable, bet_id)
+    redis.call('ZADD', processing, score, bet_id)
+    table.insert(claimed, bet_id)
+  end
+end
+
+return claimed
+"#;
+
+/// Queue backend on Redis sorted sets and streams
+pub struct RedisQueueBackend {
+    redis: ConnectionManager,
+}
+
+impl RedisQueueBackend {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+}
+
+#[async_trait]
+impl QueueBackend for RedisQueueBackend {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn make_claimable(&self, bet_id: Uuid, available_at_ms: i64) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let _: () = redis::pipe()
+            .atomic()
+            .zadd(claimable_index_key(), bet_id.to_string(), available_at_ms)
+            .ignore()
+            .zrem(processing_index_key(), bet_id.to_string())
+            .ignore()
+            .query_async(&mut redis_conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn make_priority_claimable(&self, bet_id: Uuid, now_ms: i64) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let _: () = redis::pipe()
+            .atomic()
+            .zadd(priority_index_key(), bet_id.to_string(), now_ms)
+            .ignore()
+            .zrem(claimable_index_key(), bet_id.to_string())
+            .ignore()
+            .zrem(processing_index_key(), bet_id.to_string())
+            .ignore()
+            .query_async(&mut redis_conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn claim_due(&self, limit: i64, now_ms: i64) -> Result<Vec<Uuid>> {
+        let mut redis_conn = self.redis.clone();
+        let claimed_ids: Vec<String> = Script::new(CLAIM_DUE_SCRIPT)
+            .key(priority_index_key())
+            .key(claimable_index_key())
+            .key(processing_index_key())
+            .arg(limit)
+            .arg(now_ms)
+            .invoke_async(&mut redis_conn)
+            .await?;
+
+        Ok(claimed_ids
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect())
+    }
+
+    async fn peek_due(&self, offset: i64, limit: i64, now_ms: i64) -> Result<Vec<(Uuid, bool)>> {
+        if limit <= 0 {
+            return Ok(Vec::new());
+        }
+        let offset = offset.max(0);
+
+        let mut redis_conn = self.redis.clone();
+        let (priority_due, priority): (i64, Vec<String>) = redis::pipe()
+            .zcount(priority_index_key(), "-inf", now_ms)
+            .zrangebyscore_limit(priority_index_key(), "-inf", now_ms, offset as isize, limit as isize)
+            .query_async(&mut redis_conn)
+            .await?;
+
+        // The regular lane continues where the priority lane's due bets end
+        let remaining = limit - priority.len() as i64;
+        let regular: Vec<String> = if remaining > 0 {
+            let regular_offset = (offset - priority_due).max(0);
+            redis_conn
+                .zrangebyscore_limit(claimable_index_key(), "-inf", now_ms, regular_offset as isize, remaining as isize)
+                .await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(priority
+            .iter()
+            .map(|id| (id, true))
+            .chain(regular.iter().map(|id| (id, false)))
+            .filter_map(|(id, priority)| Uuid::parse_str(id).ok().map(|id| (id, priority)))
+            .collect())
+    }
+
+    async fn claim_ids(&self, bet_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        if bet_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut redis_conn = self.redis.clone();
+        let script = Script::new(CLAIM_IDS_SCRIPT);
+        let mut script = script.prepare_invoke();
+        script.key(priority_index_key()).key(claimable_index_key()).key(processing_index_key());
+        for bet_id in bet_ids {
+            script.arg(bet_id.to_string());
+        }
+        let claimed_ids: Vec<String> = script.invoke_async(&mut redis_conn).await?;
+
+        Ok(claimed_ids
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect())
+    }
+
+    async fn mark_processing(&self, bet_id: Uuid, now_ms: i64) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let _: () = redis::pipe()
+            .atomic()
+            .zrem(priority_index_key(), bet_id.to_string())
+            .ignore()
+            .zrem(claimable_index_key(), bet_id.to_string())
+            .ignore()
+            .zadd(processing_index_key(), bet_id.to_string(), now_ms)
+            .ignore()
+            .query_async(&mut redis_conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, bet_id: Uuid) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let _: () = redis::pipe()
+            .atomic()
+            .zrem(priority_index_key(), bet_id.to_string())
+            .ignore()
+            .zrem(claimable_index_key(), bet_id.to_string())
+            .ignore()
+            .zrem(processing_index_key(), bet_id.to_string())
+            .ignore()
+            .query_async(&mut redis_conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn claimable_depth(&self) -> Result<u64> {
+        let mut redis_conn = self.redis.clone();
+        let (priority, claimable): (u64, u64) = redis::pipe()
+            .zcard(priority_index_key())
+            .zcard(claimable_index_key())
+            .query_async(&mut redis_conn)
+            .await?;
+        Ok(priority + claimable)
+    }
+
+    async fn take_claimable(&self, bet_id: Uuid) -> Result<bool> {
+        let mut redis_conn = self.redis.clone();
+        let removed: i32 = redis_conn
+            .zrem(claimable_index_key(), bet_id.to_string())
+            .await?;
+        Ok(removed == 1)
+    }
+
+    async fn publish_pending(&self, bet_id: Uuid) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let _: String = redis_conn
+            .xadd(PENDING_STREAM, "*", &[("bet_id", bet_id.to_string())])
+            .await?;
+        Ok(())
+    }
+}
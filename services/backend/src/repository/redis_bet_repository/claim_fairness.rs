@@ -0,0 +1,225 @@
+//! Fair claim scheduling across wallets
+//!
+//! Claims take the oldest due bets, so one wallet that submits hundreds of
+//! bets at once fills every batch until they are all settled. With fair claims
+//! `claim_pending` looks at a window of due bets and takes them round-robin by
+//! `user_wallet`: one bet from each wallet in order of its oldest bet, then a
+//! second from each, and so on, optionally capped per wallet. Priority-lane
+//! bets are taken first, as before.
+//!
+//! The window keeps no more of a wallet's bets than one claim could take, and
+//! pages further down the queue to fill the rest, so a wallet with a deep
+//! backlog cannot push everyone queued behind it out of view. Paging stops
+//! after `MAX_SCAN_PAGES` pages; bets further back wait for those ahead.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use uuid::Uuid;
+
+use crate::config::QueueConfig;
+use crate::errors::Result;
+use crate::repository::queue_backend::QueueBackend;
+
+/// Pages of `scan_limit` due bets a claim reads at most to fill its window
+const MAX_SCAN_PAGES: i64 = 10;
+
+/// A due bet considered for a claim
+#[derive(Debug, Clone)]
+pub struct DueBet {
+    pub bet_id: Uuid,
+    pub user_wallet: String,
+    pub priority: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClaimFairness {
+    /// Window of due bets per claim, as a multiple of the claim limit
+    pub scan_multiplier: i64,
+    /// Bets of one wallet per claim; 0 leaves only the round-robin
+    pub max_per_wallet: usize,
+}
+
+impl ClaimFairness {
+    /// `None` when fair claims are off
+    pub fn from_config(config: &QueueConfig) -> Option<Self> {
+        config.fair_claims.then_some(Self {
+            scan_multiplier: config.fair_claim_scan_multiplier.max(1),
+            max_per_wallet: config.max_claim_per_wallet,
+        })
+    }
+
+    /// Window size, and page size, for a claim of `limit`
+    pub fn scan_limit(&self, limit: i64) -> i64 {
+        limit.saturating_mul(self.scan_multiplier)
+    }
+
+    /// Due bets for a claim of `limit` to select from, in claim order;
+    /// `load_wallets` looks up the wallet of each peeked bet
+    pub async fn window<F, Fut>(
+        &self,
+        queue: &dyn QueueBackend,
+        limit: i64,
+        now_ms: i64,
+        mut load_wallets: F,
+    ) -> Result<Vec<DueBet>>
+    where
+        F: FnMut(Vec<(Uuid, bool)>) -> Fut,
+        Fut: Future<Output = Result<Vec<DueBet>>>,
+    {
+        let page_len = self.scan_limit(limit);
+        let limit = limit.max(0) as usize;
+        // Bets past what a claim can take from a wallet only crowd out others
+        let per_wallet = match self.max_per_wallet {
+            0 => limit,
+            cap => cap.min(limit),
+        };
+
+        let mut window = Vec::new();
+        let mut kept: HashMap<String, usize> = HashMap::new();
+        for page in 0..MAX_SCAN_PAGES {
+            let candidates = queue.peek_due(page * page_len, page_len, now_ms).await?;
+            let exhausted = (candidates.len() as i64) < page_len;
+            for bet in load_wallets(candidates).await? {
+                let count = kept.entry(bet.user_wallet.clone()).or_default();
+                if bet.priority || *count < per_wallet {
+                    *count += 1;
+                    window.push(bet);
+                }
+            }
+            if exhausted || window.len() as i64 >= page_len {
+                break;
+            }
+        }
+        Ok(window)
+    }
+
+    /// Up to `limit` of `due`, oldest first, priority bets first, then round-robin by wallet
+    pub fn select(&self, due: Vec<DueBet>, limit: usize) -> Vec<Uuid> {
+        let mut selected = Vec::with_capacity(limit.min(due.len()));
+        let mut taken: HashMap<String, usize> = HashMap::new();
+        let mut wallets: Vec<String> = Vec::new();
+        let mut queues: HashMap<String, VecDeque<Uuid>> = HashMap::new();
+
+        for bet in due {
+            if bet.priority {
+                if selected.len() < limit {
+                    *taken.entry(bet.user_wallet).or_default() += 1;
+                    selected.push(bet.bet_id);
+                }
+                continue;
+            }
+            if !queues.contains_key(&bet.user_wallet) {
+                wallets.push(bet.user_wallet.clone());
+            }
+            queues.entry(bet.user_wallet).or_default().push_back(bet.bet_id);
+        }
+
+        while selected.len() < limit {
+            let mut progressed = false;
+            for wallet in &wallets {
+                if selected.len() >= limit {
+                    break;
+                }
+                let count = taken.entry(wallet.clone()).or_default();
+                if self.max_per_wallet > 0 && *count >= self.max_per_wallet {
+                    continue;
+                }
+                if let Some(bet_id) = queues.get_mut(wallet).and_then(VecDeque::pop_front) {
+                    *count += 1;
+                    selected.push(bet_id);
+                    progressed = true;
+                }
+            }
+            if !progressed {
+                break;
+            }
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::InMemoryQueueBackend;
+
+    fn due(wallet: &str, count: usize) -> Vec<DueBet> {
+        (0..count)
+            .map(|_| DueBet { bet_id: Uuid::new_v4(), user_wallet: wallet.to_string(), priority: false })
+            .collect()
+    }
+
+    /// Look wallets up the way `claim_fair` does, from what the bets were queued with
+    fn wallet_lookup(wallets: &HashMap<Uuid, String>) -> impl FnMut(Vec<(Uuid, bool)>) -> std::future::Ready<Result<Vec<DueBet>>> + '_ {
+        move |candidates| {
+            std::future::ready(Ok(candidates
+                .into_iter()
+                .map(|(bet_id, priority)| DueBet { bet_id, user_wallet: wallets[&bet_id].clone(), priority })
+                .collect()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_small_wallets_settle_under_whale_load() {
+        let fairness = ClaimFairness { scan_multiplier: 10, max_per_wallet: 0 };
+        let queue = InMemoryQueueBackend::new();
+        let mut wallets = HashMap::new();
+
+        // A whale's 500 bets queued ahead of ten small wallets' two bets each,
+        // filling the first 500-bet page on their own
+        let mut queued = due("whale", 500);
+        let small: Vec<DueBet> = (0..10).flat_map(|i| due(&format!("small-{}", i), 2)).collect();
+        queued.extend(small.clone());
+        for (at_ms, bet) in queued.iter().enumerate() {
+            queue.make_claimable(bet.bet_id, at_ms as i64).await.unwrap();
+            wallets.insert(bet.bet_id, bet.user_wallet.clone());
+        }
+        let now_ms = queued.len() as i64;
+
+        let window = fairness.window(&queue, 50, now_ms, wallet_lookup(&wallets)).await.unwrap();
+        // The whale keeps what one claim could take from it; the page after holds the rest
+        assert_eq!(window.len(), 70);
+        let claimed = fairness.select(window, 50);
+        assert_eq!(claimed.len(), 50);
+        assert!(small.iter().all(|bet| claimed.contains(&bet.bet_id)));
+        assert_eq!(claimed.iter().filter(|id| queued[..500].iter().any(|bet| bet.bet_id == **id)).count(), 30);
+        // The whale's oldest bet still goes first
+        assert_eq!(claimed[0], queued[0].bet_id);
+
+        // Without fairness the same claim would have been all whale
+        let oldest = queue.claim_due(50, now_ms).await.unwrap();
+        assert!(oldest.iter().all(|id| wallets[id] == "whale"));
+    }
+
+    #[tokio::test]
+    async fn test_window_stops_after_max_pages() {
+        let fairness = ClaimFairness { scan_multiplier: 1, max_per_wallet: 1 };
+        let queue = InMemoryQueueBackend::new();
+        let mut wallets = HashMap::new();
+
+        // With 5-bet pages the whale fills every page read and the small bet behind them is never seen
+        let mut queued = due("whale", 5 * MAX_SCAN_PAGES as usize);
+        queued.extend(due("small", 1));
+        for (at_ms, bet) in queued.iter().enumerate() {
+            queue.make_claimable(bet.bet_id, at_ms as i64).await.unwrap();
+            wallets.insert(bet.bet_id, bet.user_wallet.clone());
+        }
+
+        let window = fairness.window(&queue, 5, queued.len() as i64, wallet_lookup(&wallets)).await.unwrap();
+        assert_eq!(window.iter().map(|bet| bet.bet_id).collect::<Vec<_>>(), vec![queued[0].bet_id]);
+    }
+
+    #[test]
+    fn test_priority_first_and_wallet_cap() {
+        let fairness = ClaimFairness { scan_multiplier: 10, max_per_wallet: 2 };
+        let mut queue = due("whale", 10);
+        queue.extend(due("small", 1));
+        let urgent = DueBet { bet_id: Uuid::new_v4(), user_wallet: "whale".to_string(), priority: true };
+        queue.push(urgent.clone());
+
+        let claimed = fairness.select(queue.clone(), 10);
+        assert_eq!(claimed[0], urgent.bet_id);
+        // The priority bet counts against the whale's cap
+        assert_eq!(claimed, vec![urgent.bet_id, queue[0].bet_id, queue[10].bet_id]);
+    }
+}
@@ -10,9 +10,15 @@ use uuid::Uuid;
 
 use crate::domain::Bet;
 use crate::errors::{AppError, Result};
-use super::keys::bet_key;
+use super::keys::{bet_key, ARCHIVED_AT_FIELD};
+use super::schema::upgrade_hash;
 use super::status::status_from_string;
 
+/// Whether the bet hash has been replaced by an archive tombstone
+pub async fn is_archived(redis: &mut ConnectionManager, bet_id: Uuid) -> Result<bool> {
+    Ok(redis.hexists(bet_key(bet_id), ARCHIVED_AT_FIELD).await?)
+}
+
 /// Load a bet from Redis hash storage
 ///
 /// # Arguments
@@ -21,19 +27,31 @@ use super::status::status_from_string;
 ///
 /// # Returns
 /// * `Ok(Some(bet))` - Bet found and parsed successfully
-/// * `Ok(None)` - Bet not found
-/// * `Err(...)` - Redis error or parsing error
+/// * `Ok(None)` - Bet not found, or only an archive tombstone remains
+/// * `Err(...)` - Redis error, parsing error, or a schema version from a newer release
 pub async fn load_bet_from_hash(
     redis: &mut ConnectionManager,
     bet_id: Uuid,
 ) -> Result<Option<Bet>> {
     let key = bet_key(bet_id);
-    let map: HashMap<String, String> = redis.hgetall(&key).await?;
+    let mut map: HashMap<String, String> = redis.hgetall(&key).await?;
     
-    if map.is_empty() {
+    if map.is_empty() || map.contains_key(ARCHIVED_AT_FIELD) {
         return Ok(None);
     }
 
+    // Lazily bring older records up to the current schema
+    let upgraded = upgrade_hash(bet_id, &mut map)?;
+    if !upgraded.is_empty() {
+        let _: () = redis.hset_multiple(&key, &upgraded).await?;
+        metrics::counter!("bet_schema_upgrades_total").increment(1);
+    }
+
+    bet_from_map(bet_id, &map).map(Some)
+}
+
+/// Parse the fields of a bet hash
+pub fn bet_from_map(bet_id: Uuid, map: &HashMap<String, String>) -> Result<Bet> {
     let created_at_ms: i64 = map
         .get("created_at_ms")
         .and_then(|v| v.parse::<i64>().ok())
@@ -68,7 +86,7 @@ pub async fn load_bet_from_hash(
         .get("won")
         .and_then(|v| if v.is_empty() { None } else { v.parse::<bool>().ok() });
 
-    Ok(Some(Bet {
+    Ok(Bet {
         bet_id,
         created_at,
         user_wallet: map.get("user_wallet").cloned().unwrap_or_default(),
@@ -91,5 +109,10 @@ pub async fn load_bet_from_hash(
         last_error_message: map.get("last_error_message").cloned().filter(|v| !v.is_empty()),
         payout_amount,
         won,
-    }))
+        server_seed_hash: map.get("server_seed_hash").cloned().filter(|v| !v.is_empty()),
+        client_seed: map.get("client_seed").cloned().filter(|v| !v.is_empty()),
+        metadata: map
+            .get("metadata")
+            .and_then(|v| if v.is_empty() { None } else { serde_json::from_str(v).ok() }),
+    })
 }
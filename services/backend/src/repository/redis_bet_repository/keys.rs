@@ -10,22 +10,86 @@ const BET_KEY_PREFIX: &str = "bet:";
 /// Redis key prefix for user-bet index
 const USER_INDEX_PREFIX: &str = "bets:user:";
 
+/// Redis key prefix for session-bet index
+const SESSION_INDEX_PREFIX: &str = "bets:session:";
+
+/// Redis key prefix for the bets-by-metadata-value indexes
+const METADATA_INDEX_PREFIX: &str = "bets:metadata:";
+
 /// Redis key for claimable bets sorted set
 const CLAIMABLE_INDEX: &str = "bets:claimable";
 
 /// Redis key for processing bets sorted set
 const PROCESSING_INDEX: &str = "bets:processing";
 
+/// Redis key for the priority (admin-initiated) claimable sorted set
+const PRIORITY_INDEX: &str = "bets:claimable:priority";
+
+/// Redis key for terminal bets awaiting archival, scored by `created_at_ms`
+const TERMINAL_INDEX: &str = "bets:terminal";
+
+/// Redis key for live bets by creation time, scored by `created_at_ms`
+const CREATED_INDEX: &str = "bets:created";
+
+/// Redis key for pending bets no processor has claimed yet, scored by `created_at_ms`
+const UNCLAIMED_INDEX: &str = "bets:unclaimed";
+
+/// Redis key for bets batched to a processor, scored by when they were batched
+const CLAIMED_INDEX: &str = "bets:claimed";
+
+/// Redis key for bets awaiting manual review, scored by `created_at_ms`
+const MANUAL_REVIEW_INDEX: &str = "bets:manual_review";
+
+/// Redis key for the highest bet schema version written by any instance
+const SCHEMA_MAX_VERSION: &str = "bets:schema:max_version";
+
+/// Hash field marking a bet hash as an archive tombstone
+pub const ARCHIVED_AT_FIELD: &str = "archived_at_ms";
+
+/// Redis key prefix for per-bet admin audit trail
+const AUDIT_PREFIX: &str = "audit:bet:";
+
+/// Redis key prefix for per-bet event logs
+const EVENTS_PREFIX: &str = "events:bet:";
+
+/// Redis key prefix for per-bet error histories
+const ERRORS_PREFIX: &str = "errors:bet:";
+
+/// Redis key prefix for the Solana signature -> bet ids reverse index
+const TX_INDEX_PREFIX: &str = "tx:";
+
 /// Generate Redis key for a bet
 pub fn bet_key(bet_id: Uuid) -> String {
     format!("{}{}", BET_KEY_PREFIX, bet_id)
 }
 
+/// Prefix of every bet key, for SCAN-based maintenance
+pub fn bet_key_prefix() -> &'static str {
+    BET_KEY_PREFIX
+}
+
+/// SCAN pattern matching every bet key
+pub fn bet_key_pattern() -> String {
+    format!("{}*", BET_KEY_PREFIX)
+}
+
 /// Generate Redis key for user's bet index
 pub fn user_index_key(user_wallet: &str) -> String {
     format!("{}{}", USER_INDEX_PREFIX, user_wallet)
 }
 
+/// Generate Redis key for a session's bet index
+pub fn session_index_key(session_id: Uuid) -> String {
+    format!("{}{}", SESSION_INDEX_PREFIX, session_id)
+}
+
+/// Generate Redis key for the bets whose metadata has `value` under `key`
+///
+/// Metadata keys can't contain ':', so the key/value split is unambiguous.
+pub fn metadata_index_key(key: &str, value: &str) -> String {
+    format!("{}{}:{}", METADATA_INDEX_PREFIX, key, value)
+}
+
 /// Get Redis key for claimable bets index
 pub fn claimable_index_key() -> &'static str {
     CLAIMABLE_INDEX
@@ -36,6 +100,61 @@ pub fn processing_index_key() -> &'static str {
     PROCESSING_INDEX
 }
 
+/// Get Redis key for priority claimable bets index
+pub fn priority_index_key() -> &'static str {
+    PRIORITY_INDEX
+}
+
+/// Get Redis key for terminal bets index
+pub fn terminal_index_key() -> &'static str {
+    TERMINAL_INDEX
+}
+
+/// Get Redis key for the live bets creation-time index
+pub fn created_index_key() -> &'static str {
+    CREATED_INDEX
+}
+
+/// Get Redis key for the never-claimed pending bets index
+pub fn unclaimed_index_key() -> &'static str {
+    UNCLAIMED_INDEX
+}
+
+/// Get Redis key for the batched bets index
+pub fn claimed_index_key() -> &'static str {
+    CLAIMED_INDEX
+}
+
+/// Get Redis key for the manual review index
+pub fn manual_review_index_key() -> &'static str {
+    MANUAL_REVIEW_INDEX
+}
+
+/// Get Redis key for the highest bet schema version written
+pub fn schema_max_version_key() -> &'static str {
+    SCHEMA_MAX_VERSION
+}
+
+/// Generate Redis key for a bet's admin audit trail
+pub fn audit_key(bet_id: Uuid) -> String {
+    format!("{}{}", AUDIT_PREFIX, bet_id)
+}
+
+/// Generate Redis key for a bet's event log
+pub fn events_key(bet_id: Uuid) -> String {
+    format!("{}{}", EVENTS_PREFIX, bet_id)
+}
+
+/// Generate Redis key for a bet's error history
+pub fn errors_key(bet_id: Uuid) -> String {
+    format!("{}{}", ERRORS_PREFIX, bet_id)
+}
+
+/// Generate Redis key for the set of bets recorded with a Solana signature
+pub fn tx_index_key(signature: &str) -> String {
+    format!("{}{}", TX_INDEX_PREFIX, signature)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,6 +163,7 @@ mod tests {
     fn test_bet_key_format() {
         let id = Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap();
         assert_eq!(bet_key(id), "bet:550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(events_key(id), "events:bet:550e8400-e29b-41d4-a716-446655440000");
     }
 
     #[test]
@@ -54,9 +174,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_session_index_key_format() {
+        let id = Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap();
+        assert_eq!(session_index_key(id), "bets:session:550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(metadata_index_key("campaign", "spring:v2"), "bets:metadata:campaign:spring:v2");
+    }
+
     #[test]
     fn test_index_keys_are_constants() {
         assert_eq!(claimable_index_key(), "bets:claimable");
         assert_eq!(processing_index_key(), "bets:processing");
+        assert_eq!(priority_index_key(), "bets:claimable:priority");
+        assert_eq!(terminal_index_key(), "bets:terminal");
+        assert_eq!(created_index_key(), "bets:created");
+        assert_eq!(manual_review_index_key(), "bets:manual_review");
+        assert_eq!(tx_index_key("5sig"), "tx:5sig");
     }
 }
@@ -2,56 +2,26 @@
 //!
 //! Contains Lua script constants used for complex Redis transactions.
 
-/// Lua script to atomically claim pending bets for batch processing
-///
-/// Keys: [claimable_index, processing_index]
-/// Args: [limit, batch_id, processor_id, now_ms]
-///
-/// Returns: Array of claimed bet IDs
-pub const CLAIM_PENDING_SCRIPT: &str = r#"
-local claimable = KEYS[1]
-local processing = KEYS[2]
-local limit = tonumber(ARGV[1])
-local batch_id = ARGV[2]
-local processor_id = ARGV[3]
-local now_ms = tonumber(ARGV[4])
-
--- Claim only bets that are due (score <= now_ms). Score is treated as "available_at_ms".
-local entries = redis.call('ZRANGEBYSCORE', claimable, '-inf', now_ms, 'WITHSCORES', 'LIMIT', 0, limit)
-local claimed = {}
-
-for i = 1, #entries, 2 do
-  local bet_id = entries[i]
-  local score = entries[i + 1]
-  redis.call('ZREM', claimable, bet_id)
-  redis.call('ZADD', processing, score, bet_id)
-  redis.call('HSET', 'bet:' .. bet_id,
-    'status', 'batched',
-    'external_batch_id', batch_id,
-    'processor_id', processor_id
-  )
-  table.insert(claimed, bet_id)
-end
-
-return claimed
-"#;
-
 /// Lua script for handling failed retryable bet status updates
 ///
-/// Keys: [bet_key, claimable_index, processing_index]
-/// Args: [bet_id, now_ms, max_retries, backoff_ms]
+/// Keys: [bet_key]
+/// Args: [now_ms, max_retries, backoff_ms, required_status]
 ///
-/// Returns: [new_status, new_retry_count]
+/// Returns: [new_status, new_retry_count, next_attempt_at_ms], or nothing when
+/// `required_status` is non-empty and the bet is not in it
 ///
-/// Increments retry count, applies backoff, or escalates to manual review
+/// Increments retry count, applies backoff, or escalates to manual review.
+/// Queue index changes are left to the caller's `QueueBackend`.
 pub const FAIL_RETRYABLE_SCRIPT: &str = r#"
 local bet_key = KEYS[1]
-local claimable = KEYS[2]
-local processing = KEYS[3]
-local bet_id = ARGV[1]
-local now_ms = tonumber(ARGV[2])
-local max_retries = tonumber(ARGV[3])
-local backoff_ms = tonumber(ARGV[4])
+local now_ms = tonumber(ARGV[1])
+local max_retries = tonumber(ARGV[2])
+local backoff_ms = tonumber(ARGV[3])
+local required_status = ARGV[4]
+
+if required_status ~= '' and redis.call('HGET', bet_key, 'status') ~= required_status then
+    return {}
+end
 
 local current_retry = tonumber(redis.call('HGET', bet_key, 'retry_count') or '0')
 local new_retry = current_retry + 1
@@ -64,43 +34,89 @@ redis.call('HSET', bet_key,
 -- If exceeded retry budget, stop retrying.
 if new_retry > max_retries then
     redis.call('HSET', bet_key,
-        'status', 'failed_manual_review'
+        'status', 'failed_manual_review',
+        'failed_manual_review_at_ms', tostring(now_ms)
     )
-    redis.call('ZREM', claimable, bet_id)
-    redis.call('ZREM', processing, bet_id)
-    return { 'failed_manual_review', tostring(new_retry) }
+    return { 'failed_manual_review', tostring(new_retry), '0' }
 end
 
 local next_attempt_at = now_ms + backoff_ms
 
 redis.call('HSET', bet_key,
     'status', 'failed_retryable',
+    'failed_retryable_at_ms', tostring(now_ms),
     'next_attempt_at_ms', tostring(next_attempt_at)
 )
 
-redis.call('ZADD', claimable, next_attempt_at, bet_id)
-redis.call('ZREM', processing, bet_id)
-
-return { 'failed_retryable', tostring(new_retry) }
+return { 'failed_retryable', tostring(new_retry), tostring(next_attempt_at) }
 "#;
 
 /// Lua script for compare-and-swap status update with versioning
 ///
-/// Keys: [bet_key]
-/// Args: [expected_version, new_status]
+/// Keys: [bet_key, events_key]
+/// Args: [expected_version, new_status, status_at_field, now_ms, event]
 ///
-/// Returns: 1 if updated, 0 if version mismatch
+/// Returns: 1 if updated (and `event` appended to the log), 0 if version mismatch
 pub const CAS_UPDATE_SCRIPT: &str = r#"
 local bet_key = KEYS[1]
+local events_key = KEYS[2]
 local expected = tonumber(ARGV[1])
 local new_status = ARGV[2]
+local status_at_field = ARGV[3]
+local now_ms = ARGV[4]
+local event = ARGV[5]
 
 local current = tonumber(redis.call('HGET', bet_key, 'version') or '0')
 if current ~= expected then
   return 0
 end
 
-redis.call('HSET', bet_key, 'status', new_status)
+redis.call('HSET', bet_key, 'status', new_status, status_at_field, now_ms)
 redis.call('HINCRBY', bet_key, 'version', 1)
+redis.call('RPUSH', events_key, event)
 return 1
 "#;
+
+/// Lua script to replace an archived bet hash with a tombstone
+///
+/// Keys: [bet_key, terminal_index, created_index]
+/// Args: [bet_id, archived_at_ms, archived_status]
+///
+/// Returns: 1 if the hash was replaced, 0 if the bet changed status since it was
+/// archived (it is then dropped from the terminal index and left alone)
+pub const ARCHIVE_TOMBSTONE_SCRIPT: &str = r#"
+local bet_key = KEYS[1]
+local terminal = KEYS[2]
+local created = KEYS[3]
+local bet_id = ARGV[1]
+local archived_at = ARGV[2]
+local archived_status = ARGV[3]
+
+redis.call('ZREM', terminal, bet_id)
+
+if redis.call('HGET', bet_key, 'status') ~= archived_status then
+  return 0
+end
+
+local user_wallet = redis.call('HGET', bet_key, 'user_wallet') or ''
+redis.call('ZREM', created, bet_id)
+redis.call('DEL', bet_key)
+redis.call('HSET', bet_key, 'archived_at_ms', archived_at, 'user_wallet', user_wallet)
+return 1
+"#;
+
+/// Lua script recording the highest bet schema version written by any instance
+///
+/// Keys: [schema_max_version_key]
+/// Args: [schema_version]
+///
+/// Returns: the highest recorded schema version after the update
+pub const RECORD_SCHEMA_VERSION_SCRIPT: &str = r#"
+local current = tonumber(redis.call('GET', KEYS[1]) or '0')
+local version = tonumber(ARGV[1])
+if version > current then
+    redis.call('SET', KEYS[1], tostring(version))
+    return version
+end
+return current
+"#;
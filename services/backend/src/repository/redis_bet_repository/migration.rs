@@ -0,0 +1,252 @@
+//! Migration of legacy bet records to the hash schema
+//!
+//! Older deployments (and older tests) stored bets as JSON strings under
+//! `bet:{id}`. `find_by_id` only reads hashes, so those bets silently disappear.
+//! The migration converts JSON blobs to hashes, eagerly upgrades hashes to the
+//! current schema version, and rebuilds the user, creation-time, unclaimed and
+//! terminal indexes.
+
+use redis::AsyncCommands;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::{Bet, BetStatus};
+use crate::errors::Result;
+use crate::pipeline_latency::status_at_field;
+use super::deserialization::load_bet_from_hash;
+use super::keys::*;
+use super::schema::upgrade_hash;
+use super::serialization::bet_hash_fields;
+use super::status::status_from_string;
+use super::RedisBetRepository;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MigrationOptions {
+    /// Report what would change without writing
+    pub dry_run: bool,
+    /// Also re-add pending and retryable hash bets to the claimable queue
+    pub requeue_pending: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    pub scanned: usize,
+    /// JSON records rewritten as hashes
+    pub converted: usize,
+    /// Hashes upgraded to the current schema version
+    pub upgraded: usize,
+    /// Live bets whose indexes were rebuilt
+    pub indexed: usize,
+    pub requeued: usize,
+    pub tombstones: usize,
+    /// Keys that could not be migrated, with the reason
+    pub problems: Vec<(String, String)>,
+}
+
+/// Keys scanned per SCAN call
+const SCAN_COUNT: usize = 500;
+
+impl RedisBetRepository {
+    /// Convert legacy JSON bets to hashes and rebuild indexes for every bet key
+    pub async fn migrate_legacy(&self, options: MigrationOptions) -> Result<MigrationReport> {
+        let mut redis_conn = self.redis.clone();
+        let mut report = MigrationReport::default();
+        let mut cursor: u64 = 0;
+
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(bet_key_pattern())
+                .arg("COUNT")
+                .arg(SCAN_COUNT)
+                .query_async(&mut redis_conn)
+                .await?;
+
+            for key in keys {
+                report.scanned += 1;
+                if let Err(e) = self.migrate_key(&key, options, &mut report).await {
+                    report.problems.push((key, e.to_string()));
+                }
+            }
+
+            cursor = next;
+            if cursor == 0 {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn migrate_key(&self, key: &str, options: MigrationOptions, report: &mut MigrationReport) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let Some(bet_id) = key.strip_prefix(bet_key_prefix()).and_then(|id| Uuid::parse_str(id).ok()) else {
+            report.problems.push((key.to_string(), "key suffix is not a bet id".to_string()));
+            return Ok(());
+        };
+
+        let key_type: String = redis::cmd("TYPE").arg(key).query_async(&mut redis_conn).await?;
+        match key_type.as_str() {
+            "string" => {
+                let json: String = redis_conn.get(key).await?;
+                let (bet, version) = match parse_legacy_bet(&json) {
+                    Ok(parsed) if parsed.0.bet_id == bet_id => parsed,
+                    Ok(_) => {
+                        report.problems.push((key.to_string(), "bet_id does not match key".to_string()));
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        report.problems.push((key.to_string(), format!("unparseable JSON: {}", e)));
+                        return Ok(());
+                    }
+                };
+
+                report.converted += 1;
+                if !options.dry_run {
+                    let _: () = redis::pipe()
+                        .atomic()
+                        .del(key)
+                        .ignore()
+                        .hset_multiple(key, &bet_hash_fields(&bet, version))
+                        .ignore()
+                        .query_async(&mut redis_conn)
+                        .await?;
+                }
+                self.rebuild_indexes(&bet, options.dry_run, report).await?;
+
+                // A converted bet was never in the queue; pending ones must become claimable
+                if matches!(bet.status, BetStatus::Pending | BetStatus::FailedRetryable) {
+                    report.requeued += 1;
+                    if !options.dry_run {
+                        self.queue.make_claimable(bet.bet_id, bet.created_at.timestamp_millis()).await?;
+                    }
+                }
+            }
+            "hash" => {
+                let mut map: HashMap<String, String> = redis_conn.hgetall(key).await?;
+                if map.contains_key(ARCHIVED_AT_FIELD) {
+                    report.tombstones += 1;
+                    return Ok(());
+                }
+
+                let upgrades = match upgrade_hash(bet_id, &mut map) {
+                    Ok(upgrades) => upgrades,
+                    Err(e) => {
+                        report.problems.push((key.to_string(), e.to_string()));
+                        return Ok(());
+                    }
+                };
+                if map.get("created_at_ms").and_then(|v| v.parse::<i64>().ok()).is_none() {
+                    report.problems.push((key.to_string(), "no usable created_at_ms or created_at".to_string()));
+                    return Ok(());
+                }
+                if !upgrades.is_empty() {
+                    report.upgraded += 1;
+                }
+
+                if options.dry_run {
+                    report.indexed += 1;
+                    let status = map.get("status").and_then(|s| status_from_string(s));
+                    if options.requeue_pending && matches!(status, Some(BetStatus::Pending | BetStatus::FailedRetryable)) {
+                        report.requeued += 1;
+                    }
+                    return Ok(());
+                }
+
+                // Reading upgrades the record in place
+                let Some(bet) = load_bet_from_hash(&mut redis_conn, bet_id).await? else {
+                    return Ok(());
+                };
+                self.rebuild_indexes(&bet, false, report).await?;
+
+                if options.requeue_pending && matches!(bet.status, BetStatus::Pending | BetStatus::FailedRetryable) {
+                    report.requeued += 1;
+                    self.queue.make_claimable(bet.bet_id, self.clock.now().timestamp_millis()).await?;
+                }
+            }
+            other => {
+                report.problems.push((key.to_string(), format!("unexpected key type '{}'", other)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-add the bet to its user and creation-time indexes, the unclaimed one
+    /// while no processor has had it, the claimed one while batched, and the
+    /// terminal ones when settled
+    async fn rebuild_indexes(&self, bet: &Bet, dry_run: bool, report: &mut MigrationReport) -> Result<()> {
+        report.indexed += 1;
+        if dry_run {
+            return Ok(());
+        }
+
+        let mut redis_conn = self.redis.clone();
+        let id = bet.bet_id.to_string();
+        let created_at_ms = bet.created_at.timestamp_millis();
+        let mut pipe = redis::pipe();
+        pipe.zadd(user_index_key(&bet.user_wallet), &id, created_at_ms).ignore();
+        pipe.zadd(created_index_key(), &id, created_at_ms).ignore();
+        // Bets queued by hand after expiring are settled by their operator, not expired again
+        let manual: Option<String> = redis_conn.hget(bet_key(bet.bet_id), "manual_settlement").await?;
+        if bet.status == BetStatus::Pending && bet.external_batch_id.is_none() && manual.as_deref() != Some("true") {
+            pipe.zadd(unclaimed_index_key(), &id, created_at_ms).ignore();
+        }
+        if bet.status == BetStatus::Batched {
+            let batched_at_ms: Option<i64> = redis_conn.hget(bet_key(bet.bet_id), status_at_field(&BetStatus::Batched)).await?;
+            let claimed_at_ms = batched_at_ms.unwrap_or_else(|| self.clock.now().timestamp_millis());
+            pipe.zadd(claimed_index_key(), &id, claimed_at_ms).ignore();
+        }
+        if let Some(signature) = bet.solana_tx_id.as_deref().filter(|s| !s.is_empty()) {
+            pipe.sadd(tx_index_key(signature), &id).ignore();
+        }
+        if bet.status.is_terminal() {
+            pipe.zadd(terminal_index_key(), &id, created_at_ms).ignore();
+            if bet.status == BetStatus::FailedManualReview {
+                pipe.zadd(manual_review_index_key(), &id, created_at_ms).ignore();
+            }
+        }
+        let _: () = pipe.query_async(&mut redis_conn).await?;
+
+        // Terminal bets left in the queue by older code must never be claimed
+        if bet.status.is_terminal() {
+            self.queue.remove(bet.bet_id).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a legacy JSON bet; `version` was stored alongside the bet fields when present
+fn parse_legacy_bet(json: &str) -> serde_json::Result<(Bet, i64)> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let version = value.get("version").and_then(|v| v.as_i64()).unwrap_or(0);
+    Ok((serde_json::from_value(value)?, version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_legacy_bet() {
+        let bet_id = Uuid::new_v4();
+        let json = format!(
+            r#"{{"bet_id":"{}","created_at":"2025-01-01T00:00:00Z","user_wallet":"W","vault_address":"V",
+            "game_type":"coinflip","stake_amount":100,"stake_token":"SOL","choice":"heads","status":"completed",
+            "retry_count":0,"won":true,"payout_amount":200,"version":3}}"#,
+            bet_id
+        );
+        let (bet, version) = parse_legacy_bet(&json).unwrap();
+        assert_eq!(bet.bet_id, bet_id);
+        assert_eq!(bet.status, BetStatus::Completed);
+        assert_eq!(bet.casino_id, None);
+        assert_eq!(version, 3);
+
+        // Converted records are written at the current schema version
+        let mut fields: HashMap<String, String> = bet_hash_fields(&bet, version)
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        assert!(upgrade_hash(bet_id, &mut fields).unwrap().is_empty());
+    }
+}
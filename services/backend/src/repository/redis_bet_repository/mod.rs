@@ -1,23 +1,36 @@
 //! Redis-based BetRepository implementation
 //!
 //! This module provides a Redis-backed implementation of the BetRepository trait
-//! for storing and managing bets. It uses Redis hashes for bet storage and sorted
-//! sets for indexing.
+//! for storing and managing bets. It uses Redis hashes for bet storage and a
+//! `QueueBackend` for the claimable/processing indexes.
 
 mod keys;
 mod status;
 mod retry;
 mod lua_scripts;
 mod deserialization;
+mod events;
+mod serialization;
+mod migration;
+mod scan;
+mod schema;
+mod pii;
+mod claim_fairness;
 
 use async_trait::async_trait;
-use chrono::Utc;
 use redis::aio::ConnectionManager;
 use redis::{AsyncCommands, Script};
+use shared::clock::{Clock, SystemClock};
+use shared::errors::ErrorCode;
+use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::domain::{Bet, BetStatus, CreateBetRequest};
-use crate::errors::Result;
+use crate::domain::{AuditEntry, Bet, BetError, BetEvent, BetStatus, ClientInfo, CreateBetRequest};
+use crate::errors::{AppError, Result};
+use crate::pipeline_latency::{self, status_at_field, Stage};
+use crate::repository::audit_repository::AuditRepository;
+use crate::repository::bet_archive::BetArchive;
+use crate::repository::queue_backend::QueueBackend;
 
 // Re-export submodules
 pub use keys::*;
@@ -25,60 +38,280 @@ pub use status::*;
 pub use retry::*;
 pub use lua_scripts::*;
 pub use deserialization::*;
+pub use events::*;
+pub use serialization::*;
+pub use migration::{MigrationOptions, MigrationReport};
+pub use schema::*;
+pub use pii::{FieldCipher, CLIENT_INFO_FIELDS};
+pub use claim_fairness::{ClaimFairness, DueBet};
+
+/// Failures kept in a bet's error history
+const ERROR_HISTORY_LIMIT: isize = 20;
+
+fn error_payload(error: &BetError) -> Result<String> {
+    Ok(serde_json::to_string(error).map_err(anyhow::Error::from)?)
+}
 
 /// Redis-based implementation of BetRepository
 pub struct RedisBetRepository {
     redis: ConnectionManager,
+    queue: Arc<dyn QueueBackend>,
+    archive: Option<Arc<dyn BetArchive>>,
+    /// Encrypts client info; without it none is stored
+    cipher: Option<Arc<FieldCipher>>,
+    /// Round-robin claims across wallets; without it claims take the oldest bets
+    claim_fairness: Option<ClaimFairness>,
+    /// Metadata keys whose values are indexed on create
+    indexed_metadata_keys: Arc<[String]>,
+    clock: Arc<dyn Clock>,
 }
 
 impl RedisBetRepository {
     /// Create a new RedisBetRepository
-    pub fn new(redis: ConnectionManager) -> Self {
-        Self { redis }
+    pub fn new(redis: ConnectionManager, queue: Arc<dyn QueueBackend>) -> Self {
+        Self {
+            redis,
+            queue,
+            archive: None,
+            cipher: None,
+            claim_fairness: None,
+            indexed_metadata_keys: Arc::from([]),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Read archived bets from `archive` when Redis only holds a tombstone
+    pub fn with_archive(mut self, archive: Option<Arc<dyn BetArchive>>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Store the client info of new bets, encrypted with `cipher`
+    pub fn with_cipher(mut self, cipher: Option<Arc<FieldCipher>>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Share claims fairly between wallets
+    pub fn with_claim_fairness(mut self, claim_fairness: Option<ClaimFairness>) -> Self {
+        self.claim_fairness = claim_fairness;
+        self
     }
 
-    /// Update bet fields (won, payout_amount, error_message)
+    /// Index new bets by the values of these metadata keys
+    pub fn with_indexed_metadata_keys(mut self, keys: Arc<[String]>) -> Self {
+        self.indexed_metadata_keys = keys;
+        self
+    }
+
+    /// Read claim, status change and expiry times from `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Move a bet to `FailedRetryable` with backoff, or to manual review once its
+    /// retries are spent
     ///
-    /// This is a helper method for updating specific bet fields
-    /// without changing the status.
-    pub async fn update_bet_fields(
-        &self,
-        bet_id: Uuid,
-        won: Option<bool>,
-        payout_amount: Option<i64>,
-        error_message: Option<String>,
-    ) -> Result<()> {
+    /// With `required` set, only a bet still in that status is moved. Returns the
+    /// status the bet ended in, or None when it was not in `required`.
+    async fn fail_retryable(&self, bet_id: Uuid, required: Option<&BetStatus>) -> Result<Option<BetStatus>> {
         let mut redis_conn = self.redis.clone();
-        let key = bet_key(bet_id);
+        let bet_key_str = bet_key(bet_id);
+        let now_ms = self.clock.now().timestamp_millis();
+        let max_retries = max_retry_count();
+
+        // We base the backoff on the *next* retry count (after increment).
+        // Compute a conservative backoff using the current retry_count if present.
+        // If missing, treat as first retry.
+        let current_retry: i32 = redis_conn
+            .hget(&bet_key_str, "retry_count")
+            .await
+            .unwrap_or(0);
+        let backoff_ms = compute_backoff_ms(current_retry.saturating_add(1));
+
+        let script = Script::new(FAIL_RETRYABLE_SCRIPT);
+        let outcome: Vec<String> = script
+            .key(&bet_key_str)
+            .arg(now_ms)
+            .arg(max_retries)
+            .arg(backoff_ms)
+            .arg(required.map(status_to_string).unwrap_or_default())
+            .invoke_async(&mut redis_conn)
+            .await?;
+        let Some(new_status) = outcome.first().cloned() else {
+            return Ok(None);
+        };
 
-        if let Some(won) = won {
-            let _: () = redis_conn.hset(&key, "won", won.to_string()).await?;
+        let _: () = redis::pipe()
+            .zrem(unclaimed_index_key(), bet_id.to_string())
+            .ignore()
+            .zrem(claimed_index_key(), bet_id.to_string())
+            .ignore()
+            .query_async(&mut redis_conn)
+            .await?;
+        let next_attempt_at_ms = outcome.get(2).and_then(|v| v.parse::<i64>().ok());
+        let mut fields = vec![
+            ("status".to_string(), new_status.clone()),
+            (format!("{}_at_ms", new_status), now_ms.to_string()),
+            ("retry_count".to_string(), outcome.get(1).cloned().unwrap_or_default()),
+            ("solana_tx_id".to_string(), String::new()),
+        ];
+        if let Some(next_attempt_at_ms) = next_attempt_at_ms.filter(|at| *at > 0) {
+            fields.push(("next_attempt_at_ms".to_string(), next_attempt_at_ms.to_string()));
         }
-        if let Some(payout_amount) = payout_amount {
-            let _: () = redis_conn
-                .hset(&key, "payout_amount", payout_amount.to_string())
-                .await?;
+        self.append_event(bet_id, &new_status, &fields).await?;
+
+        match new_status.as_str() {
+            "failed_retryable" => {
+                self.queue
+                    .make_claimable(bet_id, next_attempt_at_ms.unwrap_or(now_ms + backoff_ms))
+                    .await?;
+            }
+            _ => {
+                self.queue.remove(bet_id).await?;
+                self.index_terminal(bet_id, &BetStatus::FailedManualReview).await?;
+            }
         }
-        if let Some(error_message) = error_message {
-            let _: () = redis_conn
-                .hset(&key, "last_error_message", error_message)
-                .await?;
+
+        Ok(status_from_string(&new_status))
+    }
+
+    /// Claim up to `limit` due bets, round-robin by wallet over a window of due bets
+    async fn claim_fair(&self, fairness: &ClaimFairness, limit: i64, now_ms: i64) -> Result<Vec<Uuid>> {
+        let due = fairness
+            .window(self.queue.as_ref(), limit, now_ms, |candidates| async move {
+                if candidates.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let mut redis_conn = self.redis.clone();
+                let mut pipe = redis::pipe();
+                for (bet_id, _) in &candidates {
+                    pipe.hget(bet_key(*bet_id), "user_wallet");
+                }
+                let wallets: Vec<Option<String>> = pipe.query_async(&mut redis_conn).await?;
+                Ok(candidates
+                    .into_iter()
+                    .zip(wallets)
+                    .map(|((bet_id, priority), wallet)| DueBet { bet_id, user_wallet: wallet.unwrap_or_default(), priority })
+                    .collect())
+            })
+            .await?;
+        if due.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let selected = fairness.select(due, limit.max(0) as usize);
+        // Bets claimed by another processor since the peek are simply left out
+        self.queue.claim_ids(&selected).await
+    }
+
+    /// Add a bet to the terminal index so the archival worker can find it, and to
+    /// the manual review index when it needs an operator
+    async fn index_terminal(&self, bet_id: Uuid, status: &BetStatus) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let created_at_ms: Option<i64> = redis_conn.hget(bet_key(bet_id), "created_at_ms").await?;
+        if let Some(created_at_ms) = created_at_ms {
+            let mut pipe = redis::pipe();
+            pipe.zadd(terminal_index_key(), bet_id.to_string(), created_at_ms).ignore();
+            if *status == BetStatus::FailedManualReview {
+                pipe.zadd(manual_review_index_key(), bet_id.to_string(), created_at_ms).ignore();
+            }
+            let _: () = pipe.query_async(&mut redis_conn).await?;
         }
+        Ok(())
+    }
 
+    /// Append a write made outside a transaction to the bet's event log
+    async fn append_event<K: AsRef<str>, V: AsRef<str>>(&self, bet_id: Uuid, kind: &str, fields: &[(K, V)]) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let _: () = redis_conn.rpush(events_key(bet_id), event_payload(kind, fields)).await?;
         Ok(())
     }
+
+    /// Record how long each bet spent in `stage`, which it left at `now_ms`
+    ///
+    /// Best-effort: the status change has already been written.
+    async fn record_dwell(&self, stage: Stage, bet_ids: &[Uuid], now_ms: i64) {
+        let mut redis_conn = self.redis.clone();
+        let fields = stage.start_fields();
+        let mut pipe = redis::pipe();
+        for id in bet_ids {
+            pipe.cmd("HMGET").arg(bet_key(*id)).arg(&fields);
+        }
+        let starts: Vec<Vec<Option<i64>>> = match pipe.query_async(&mut redis_conn).await {
+            Ok(starts) => starts,
+            Err(e) => {
+                tracing::warn!(stage = stage.as_str(), error = %e, "Failed to read stage start times");
+                return;
+            }
+        };
+
+        let dwells: Vec<i64> = starts
+            .iter()
+            .filter_map(|starts| pipeline_latency::dwell_ms(starts, now_ms))
+            .collect();
+        if let Err(e) = pipeline_latency::record(&mut redis_conn, stage, &dwells).await {
+            tracing::warn!(stage = stage.as_str(), error = %e, "Failed to record stage dwell times");
+        }
+    }
+
+    /// Bets in a sorted-set index, newest first, reading tombstoned ones from the archive
+    async fn find_indexed(&self, key: &str, limit: i64, offset: i64) -> Result<Vec<Bet>> {
+        let mut redis_conn = self.redis.clone();
+
+        let start = offset.max(0) as isize;
+        let end = (offset + limit - 1).max(-1) as isize;
+        let bet_ids: Vec<String> = redis_conn.zrevrange(key, start, end).await?;
+
+        let mut found = Vec::new();
+        let mut archived = Vec::new();
+        for id_str in bet_ids {
+            if let Ok(id) = Uuid::parse_str(&id_str) {
+                match load_bet_from_hash(&mut redis_conn, id).await? {
+                    Some(bet) => found.push((id, Some(bet))),
+                    None => {
+                        archived.push(id);
+                        found.push((id, None));
+                    }
+                }
+            }
+        }
+
+        // Fill tombstoned entries from the archive in one query, keeping index order
+        let mut from_archive: std::collections::HashMap<Uuid, Bet> = match &self.archive {
+            Some(archive) if !archived.is_empty() => archive
+                .find_many(&archived)
+                .await?
+                .into_iter()
+                .map(|bet| (bet.bet_id, bet))
+                .collect(),
+            _ => Default::default(),
+        };
+
+        Ok(found
+            .into_iter()
+            .filter_map(|(id, bet)| bet.or_else(|| from_archive.remove(&id)))
+            .collect())
+    }
 }
 
 #[async_trait]
 impl super::BetRepository for RedisBetRepository {
-    async fn create(&self, user_wallet: &str, vault_address: &str, req: CreateBetRequest) -> Result<Bet> {
+    async fn create(&self, user_wallet: &str, vault_address: &str, req: CreateBetRequest, client: &ClientInfo) -> Result<Bet> {
         let bet_id = Uuid::new_v4();
-        let now = Utc::now();
+        let now = self.clock.now();
         let now_ms = now.timestamp_millis();
 
         // Convert LamportAmount to i64 for storage
         let stake_amount_i64 = req.stake_amount.as_u64() as i64;
 
+        // Commit to today's server seed before the outcome can be drawn
+        let mut redis_conn = self.redis.clone();
+        let server_seed_hash = crate::fairness::commitment_for(&mut redis_conn, now.date_naive()).await?;
+        let client_seed = req.client_seed.filter(|s| !s.is_empty()).unwrap_or_else(simulation::generate_client_seed);
+        let session_id = req.session_id;
+
         let bet = Bet {
             bet_id,
             created_at: now,
@@ -86,7 +319,7 @@ impl super::BetRepository for RedisBetRepository {
             vault_address: vault_address.to_string(),
             allowance_pda: req.allowance_pda.clone().filter(|v| !v.is_empty()),
             casino_id: None,
-            game_type: "coinflip".to_string(),
+            game_type: req.game_type,
             stake_amount: stake_amount_i64,
             stake_token: req.stake_token,
             choice: req.choice,
@@ -99,6 +332,9 @@ impl super::BetRepository for RedisBetRepository {
             last_error_message: None,
             payout_amount: None,
             won: None,
+            server_seed_hash: Some(server_seed_hash),
+            client_seed: Some(client_seed),
+            metadata: req.metadata.filter(|metadata| !metadata.is_empty()),
         };
 
         let mut pipe = redis::pipe();
@@ -107,57 +343,100 @@ impl super::BetRepository for RedisBetRepository {
         let bet_key = bet_key(bet_id);
         let user_index = user_index_key(user_wallet);
 
-        let mut redis_conn = self.redis.clone();
-
-        let _: () = pipe
-            .hset_multiple(
-                &bet_key,
-                &[
-                    ("bet_id", bet.bet_id.to_string()),
-                    ("created_at_ms", now_ms.to_string()),
-                    ("user_wallet", bet.user_wallet.clone()),
-                    ("vault_address", bet.vault_address.clone()),
-                    ("allowance_pda", bet.allowance_pda.clone().unwrap_or_default()),
-                    ("casino_id", "".to_string()),
-                    ("game_type", bet.game_type.clone()),
-                    ("stake_amount", bet.stake_amount.to_string()),
-                    ("stake_token", bet.stake_token.clone()),
-                    ("choice", bet.choice.clone()),
-                    ("status", status_to_string(&bet.status)),
-                    ("external_batch_id", "".to_string()),
-                    ("solana_tx_id", "".to_string()),
-                    ("retry_count", bet.retry_count.to_string()),
-                    ("processor_id", "".to_string()),
-                    ("last_error_code", "".to_string()),
-                    ("last_error_message", "".to_string()),
-                    ("payout_amount", "".to_string()),
-                    ("won", "".to_string()),
-                    ("version", "0".to_string()),
-                ],
-            )
+        let fields = bet_hash_fields(&bet, 0);
+        pipe.hset_multiple(&bet_key, &fields)
+            .ignore()
+            .rpush(events_key(bet_id), event_payload("created", &fields))
             .ignore()
             .zadd(&user_index, bet.bet_id.to_string(), now_ms)
             .ignore()
-            .zadd(claimable_index_key(), bet.bet_id.to_string(), now_ms)
+            .zadd(created_index_key(), bet.bet_id.to_string(), now_ms)
             .ignore()
-            .query_async(&mut redis_conn)
-            .await?;
+            .zadd(unclaimed_index_key(), bet.bet_id.to_string(), now_ms)
+            .ignore();
+        if let Some(session_id) = session_id {
+            pipe.zadd(session_index_key(session_id), bet.bet_id.to_string(), now_ms).ignore();
+        }
+        if let Some(metadata) = &bet.metadata {
+            for (key, value) in crate::bet_metadata::indexed_entries(metadata, &self.indexed_metadata_keys) {
+                pipe.zadd(metadata_index_key(key, value), bet.bet_id.to_string(), now_ms).ignore();
+            }
+        }
+        // Kept out of the event log, which is never encrypted
+        match &self.cipher {
+            Some(cipher) if !client.is_empty() => {
+                pipe.hset_multiple(&bet_key, &cipher.client_info_fields(bet_id, client)?).ignore();
+            }
+            _ => {}
+        }
+        let _: () = pipe.query_async(&mut redis_conn).await?;
+
+        self.queue.make_claimable(bet.bet_id, now_ms).await?;
 
         Ok(bet)
     }
 
     async fn find_by_id(&self, bet_id: Uuid) -> Result<Option<Bet>> {
         let mut redis_conn = self.redis.clone();
-        load_bet_from_hash(&mut redis_conn, bet_id).await
+        if let Some(bet) = load_bet_from_hash(&mut redis_conn, bet_id).await? {
+            return Ok(Some(bet));
+        }
+
+        match &self.archive {
+            Some(archive) if is_archived(&mut redis_conn, bet_id).await? => {
+                archive.find_by_id(bet_id).await
+            }
+            _ => Ok(None),
+        }
     }
 
-    async fn find_by_user(&self, user_wallet: &str, limit: i64, offset: i64) -> Result<Vec<Bet>> {
+    async fn client_info(&self, bet_id: Uuid) -> Result<Option<ClientInfo>> {
         let mut redis_conn = self.redis.clone();
-        let key = user_index_key(user_wallet);
+        let stored: Vec<Option<String>> =
+            redis::cmd("HMGET").arg(bet_key(bet_id)).arg(&CLIENT_INFO_FIELDS).query_async(&mut redis_conn).await?;
+        if stored.iter().all(|value| value.as_deref().unwrap_or_default().is_empty()) {
+            return Ok(None);
+        }
+        let cipher = self
+            .cipher
+            .as_ref()
+            .ok_or_else(|| AppError::Internal(anyhow::anyhow!("BET_PII_KEYS is not configured")))?;
+
+        let (client, rewrapped) = cipher.client_info_from_fields(bet_id, &stored)?;
+        if !rewrapped.is_empty() {
+            let _: () = redis_conn.hset_multiple(bet_key(bet_id), &rewrapped).await?;
+            metrics::counter!("bet_pii_rewrapped_total").increment(rewrapped.len() as u64);
+        }
+        Ok(Some(client))
+    }
 
-        let start = offset.max(0) as isize;
-        let end = (offset + limit - 1).max(-1) as isize;
-        let bet_ids: Vec<String> = redis_conn.zrevrange(&key, start, end).await?;
+    async fn find_by_user(&self, user_wallet: &str, limit: i64, offset: i64) -> Result<Vec<Bet>> {
+        self.find_indexed(&user_index_key(user_wallet), limit, offset).await
+    }
+
+    async fn find_by_session(&self, session_id: Uuid, limit: i64) -> Result<Vec<Bet>> {
+        self.find_indexed(&session_index_key(session_id), limit, 0).await
+    }
+
+    async fn find_by_metadata(&self, key: &str, value: &str, limit: i64) -> Result<Vec<Bet>> {
+        self.find_indexed(&metadata_index_key(key, value), limit, 0).await
+    }
+
+    async fn scan_created_between(&self, cursor: u64, count: usize, from_ms: i64, to_ms: i64) -> Result<(u64, Vec<Bet>)> {
+        self.scan_hashes_created_between(cursor, count, from_ms, to_ms).await
+    }
+
+    async fn find_created_between(&self, from_ms: i64, to_ms: i64, offset: i64, limit: i64) -> Result<Vec<Bet>> {
+        let mut redis_conn = self.redis.clone();
+        let bet_ids: Vec<String> = redis_conn
+            .zrangebyscore_limit(
+                created_index_key(),
+                from_ms,
+                format!("({}", to_ms),
+                offset.max(0) as isize,
+                limit.max(0) as isize,
+            )
+            .await?;
 
         let mut bets = Vec::new();
         for id_str in bet_ids {
@@ -172,117 +451,465 @@ impl super::BetRepository for RedisBetRepository {
     }
 
     async fn claim_pending(&self, limit: i64, processor_id: &str) -> Result<(Uuid, Vec<Bet>)> {
-        let limit = limit.max(0).min(500) as i64;
+        let limit = limit.clamp(0, 500);
         let batch_id = Uuid::new_v4();
 
         let mut redis_conn = self.redis.clone();
-        let script = Script::new(CLAIM_PENDING_SCRIPT);
-        let now_ms = Utc::now().timestamp_millis();
-        
-        let claimed_ids: Vec<String> = script
-            .key(claimable_index_key())
-            .key(processing_index_key())
-            .arg(limit)
-            .arg(batch_id.to_string())
-            .arg(processor_id)
-            .arg(now_ms)
-            .invoke_async(&mut redis_conn)
-            .await?;
+        let now_ms = self.clock.now().timestamp_millis();
+
+        let claimed_ids = match &self.claim_fairness {
+            Some(fairness) => self.claim_fair(fairness, limit, now_ms).await?,
+            None => self.queue.claim_due(limit, now_ms).await?,
+        };
+        if claimed_ids.is_empty() {
+            return Ok((batch_id, Vec::new()));
+        }
+
+        let batched = status_to_string(&BetStatus::Batched);
+        let batched_at_field = status_at_field(&BetStatus::Batched);
+        let batch_id_str = batch_id.to_string();
+        let now_ms_str = now_ms.to_string();
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        let fields = [
+            ("status", batched.as_str()),
+            ("external_batch_id", batch_id_str.as_str()),
+            ("processor_id", processor_id),
+            (batched_at_field.as_str(), now_ms_str.as_str()),
+        ];
+        let event = event_payload(&batched, &fields);
+        for id in &claimed_ids {
+            pipe.hset_multiple(bet_key(*id), &fields)
+                .ignore()
+                .rpush(events_key(*id), &event)
+                .ignore()
+                .zrem(unclaimed_index_key(), id.to_string())
+                .ignore()
+                .zadd(claimed_index_key(), id.to_string(), now_ms)
+                .ignore();
+        }
+        let _: () = pipe.query_async(&mut redis_conn).await?;
+
+        // Only pending and retryable bets are claimable
+        self.record_dwell(Stage::Pending, &claimed_ids, now_ms).await;
 
         let mut bets = Vec::new();
-        for id_str in claimed_ids {
-            if let Ok(id) = Uuid::parse_str(&id_str) {
-                if let Some(bet) = load_bet_from_hash(&mut redis_conn, id).await? {
-                    bets.push(bet);
-                }
+        for id in claimed_ids {
+            if let Some(bet) = load_bet_from_hash(&mut redis_conn, id).await? {
+                bets.push(bet);
             }
         }
 
         Ok((batch_id, bets))
     }
 
+    async fn find_by_solana_tx(&self, signature: &str) -> Result<Vec<Bet>> {
+        let mut redis_conn = self.redis.clone();
+        let ids: Vec<String> = redis_conn.smembers(tx_index_key(signature)).await?;
+
+        let mut bets = Vec::with_capacity(ids.len());
+        for id in ids {
+            let Ok(bet_id) = Uuid::parse_str(&id) else {
+                continue;
+            };
+            // Reads through to the archive for bets archived since settlement
+            if let Some(bet) = self.find_by_id(bet_id).await? {
+                bets.push(bet);
+            }
+        }
+        Ok(bets)
+    }
+
     async fn update_status(&self, bet_id: Uuid, status: BetStatus, solana_tx_id: Option<String>) -> Result<()> {
         let mut redis_conn = self.redis.clone();
         let bet_key_str = bet_key(bet_id);
 
+        let previous: Option<String> = redis_conn.hget(&bet_key_str, "status").await?;
+        check_transition(bet_id, previous.as_deref(), &status)?;
+
         // Special handling: FailedRetryable implies retries + backoff and can graduate to manual review.
         if matches!(status, BetStatus::FailedRetryable) {
-            let now_ms = Utc::now().timestamp_millis();
-            let max_retries = max_retry_count();
-
-            // We base the backoff on the *next* retry count (after increment).
-            // Compute a conservative backoff using the current retry_count if present.
-            // If missing, treat as first retry.
-            let current_retry: i32 = redis_conn
-                .hget(&bet_key_str, "retry_count")
-                .await
-                .unwrap_or(0);
-            let backoff_ms = compute_backoff_ms(current_retry.saturating_add(1));
-
-            let script = Script::new(FAIL_RETRYABLE_SCRIPT);
-            let _: Vec<String> = script
-                .key(&bet_key_str)
-                .key(claimable_index_key())
-                .key(processing_index_key())
-                .arg(bet_id.to_string())
-                .arg(now_ms)
-                .arg(max_retries)
-                .arg(backoff_ms)
-                .invoke_async(&mut redis_conn)
-                .await?;
-
+            self.fail_retryable(bet_id, None).await?;
             return Ok(());
         }
 
+        let now_ms = self.clock.now().timestamp_millis();
+
         let status_str = status_to_string(&status);
+        let mut fields = vec![
+            ("status".to_string(), status_str.clone()),
+            (status_at_field(&status), now_ms.to_string()),
+        ];
         let mut pipe = redis::pipe();
         pipe.atomic();
-        pipe.hset(&bet_key_str, "status", status_str).ignore();
-        
+
         if let Some(tx) = solana_tx_id {
-            pipe.hset(&bet_key_str, "solana_tx_id", tx).ignore();
+            pipe.sadd(tx_index_key(&tx), bet_id.to_string()).ignore();
+            fields.push(("solana_tx_id".to_string(), tx));
         }
 
         // Clear stale error fields when transitioning out of failure states.
         match status {
             BetStatus::FailedRetryable | BetStatus::FailedManualReview => {}
             _ => {
-                pipe.hset(&bet_key_str, "last_error_code", "").ignore();
-                pipe.hset(&bet_key_str, "last_error_message", "").ignore();
+                fields.push(("last_error_code".to_string(), String::new()));
+                fields.push(("last_error_message".to_string(), String::new()));
             }
         }
+        pipe.hset_multiple(&bet_key_str, &fields).ignore();
+        pipe.rpush(events_key(bet_id), event_payload(&status_str, &fields)).ignore();
+        if status != BetStatus::FailedManualReview {
+            pipe.zrem(manual_review_index_key(), bet_id.to_string()).ignore();
+        }
+        if status != BetStatus::Pending {
+            pipe.zrem(unclaimed_index_key(), bet_id.to_string()).ignore();
+        }
+        if status == BetStatus::Batched {
+            pipe.zadd(claimed_index_key(), bet_id.to_string(), now_ms).ignore();
+        } else {
+            pipe.zrem(claimed_index_key(), bet_id.to_string()).ignore();
+        }
+
+        let _: () = pipe.query_async(&mut redis_conn).await?;
+
+        let stage = previous
+            .as_deref()
+            .and_then(status_from_string)
+            .and_then(|from| Stage::ended_by(&from, &status));
+        if let Some(stage) = stage {
+            self.record_dwell(stage, &[bet_id], now_ms).await;
+        }
 
         match status {
             BetStatus::FailedRetryable | BetStatus::Pending => {
-                pipe.zadd(claimable_index_key(), bet_id.to_string(), Utc::now().timestamp_millis())
-                    .ignore();
-                pipe.zrem(processing_index_key(), bet_id.to_string()).ignore();
-            }
-            BetStatus::Batched => {
-                pipe.zrem(claimable_index_key(), bet_id.to_string()).ignore();
-                pipe.zadd(processing_index_key(), bet_id.to_string(), Utc::now().timestamp_millis())
-                    .ignore();
-            }
-            _ => {
-                pipe.zrem(claimable_index_key(), bet_id.to_string()).ignore();
-                pipe.zrem(processing_index_key(), bet_id.to_string()).ignore();
+                self.queue.make_claimable(bet_id, now_ms).await?
             }
+            BetStatus::Batched => self.queue.mark_processing(bet_id, now_ms).await?,
+            _ => self.queue.remove(bet_id).await?,
         }
 
-        let _: () = pipe.query_async(&mut redis_conn).await?;
+        if status.is_terminal() {
+            self.index_terminal(bet_id, &status).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn update_bet_fields(
+        &self,
+        bet_id: Uuid,
+        won: Option<bool>,
+        payout_amount: Option<i64>,
+        error_message: Option<String>,
+        error_code: Option<String>,
+        server_seed_hash: Option<String>,
+    ) -> Result<()> {
+        let fields: Vec<(&str, String)> = [
+            ("won", won.map(|won| won.to_string())),
+            ("payout_amount", payout_amount.map(|payout| payout.to_string())),
+            ("last_error_message", error_message),
+            ("last_error_code", error_code),
+            ("server_seed_hash", server_seed_hash),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| value.map(|value| (field, value)))
+        .collect();
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        let mut redis_conn = self.redis.clone();
+        let _: () = redis::pipe()
+            .atomic()
+            .hset_multiple(bet_key(bet_id), &fields)
+            .ignore()
+            .rpush(events_key(bet_id), event_payload("fields_updated", &fields))
+            .ignore()
+            .query_async(&mut redis_conn)
+            .await?;
+
         Ok(())
     }
 
     async fn update_status_with_version(&self, bet_id: Uuid, expected_version: i32, status: BetStatus) -> Result<bool> {
         let mut redis_conn = self.redis.clone();
+        let previous: Option<String> = redis_conn.hget(bet_key(bet_id), "status").await?;
+        check_transition(bet_id, previous.as_deref(), &status)?;
+        let now_ms = self.clock.now().timestamp_millis();
+
+        let status_str = status_to_string(&status);
+        let status_at = status_at_field(&status);
+        let event = event_payload(&status_str, &[("status", status_str.clone()), (status_at.as_str(), now_ms.to_string())]);
         let script = Script::new(CAS_UPDATE_SCRIPT);
         let updated: i32 = script
             .key(bet_key(bet_id))
+            .key(events_key(bet_id))
             .arg(expected_version)
-            .arg(status_to_string(&status))
+            .arg(&status_str)
+            .arg(&status_at)
+            .arg(now_ms)
+            .arg(event)
             .invoke_async(&mut redis_conn)
             .await?;
+        if updated == 1 {
+            let mut pipe = redis::pipe();
+            if status != BetStatus::Pending {
+                pipe.zrem(unclaimed_index_key(), bet_id.to_string()).ignore();
+            }
+            if status == BetStatus::Batched {
+                pipe.zadd(claimed_index_key(), bet_id.to_string(), now_ms).ignore();
+            } else {
+                pipe.zrem(claimed_index_key(), bet_id.to_string()).ignore();
+            }
+            let _: () = pipe.query_async(&mut redis_conn).await?;
+        }
+
+        // The status read above may be stale by now; good enough for metrics
+        let stage = previous
+            .as_deref()
+            .and_then(status_from_string)
+            .and_then(|from| Stage::ended_by(&from, &status));
+        if let (1, Some(stage)) = (updated, stage) {
+            self.record_dwell(stage, &[bet_id], now_ms).await;
+        }
 
         Ok(updated == 1)
     }
+
+    async fn expire_unclaimed(&self, created_before_ms: i64, scan_limit: i64) -> Result<Vec<Bet>> {
+        let mut redis_conn = self.redis.clone();
+        // Retried bets were claimed before and may have on-chain state; only
+        // never-claimed bets are in this index
+        let candidates: Vec<String> = redis_conn
+            .zrangebyscore_limit(unclaimed_index_key(), "-inf", created_before_ms, 0, scan_limit as isize)
+            .await?;
+
+        let mut expired = Vec::new();
+        for bet_id in candidates.iter().filter_map(|id| Uuid::parse_str(id).ok()) {
+            let bet = match load_bet_from_hash(&mut redis_conn, bet_id).await? {
+                Some(bet) if bet.status == BetStatus::Pending => bet,
+                // Gone or moved on since it was indexed; don't let it hold up the scan
+                _ => {
+                    let _: () = redis_conn.zrem(unclaimed_index_key(), bet_id.to_string()).await?;
+                    continue;
+                }
+            };
+
+            // Lost the race to a processor claim - it will settle normally
+            if !self.queue.take_claimable(bet_id).await? {
+                continue;
+            }
+
+            let error_code = ErrorCode::VALIDATION_BET_EXPIRED.as_str();
+            let error_message = "Bet was not settled before its TTL elapsed";
+            let expired_status = status_to_string(&BetStatus::Expired);
+            let error = BetError {
+                at: self.clock.now(),
+                code: Some(error_code.to_string()),
+                message: error_message.to_string(),
+                processor_id: None,
+            };
+            let fields = [
+                ("status", expired_status.clone()),
+                ("last_error_code", error_code.to_string()),
+                ("last_error_message", error_message.to_string()),
+                ("expired_at_ms", self.clock.now().timestamp_millis().to_string()),
+            ];
+            let _: () = redis::pipe()
+                .atomic()
+                .hset_multiple(bet_key(bet_id), &fields)
+                .ignore()
+                .rpush(events_key(bet_id), event_payload(&expired_status, &fields))
+                .ignore()
+                .zadd(terminal_index_key(), bet_id.to_string(), bet.created_at.timestamp_millis())
+                .ignore()
+                .zrem(unclaimed_index_key(), bet_id.to_string())
+                .ignore()
+                .rpush(errors_key(bet_id), error_payload(&error)?)
+                .ignore()
+                .ltrim(errors_key(bet_id), -ERROR_HISTORY_LIMIT, -1)
+                .ignore()
+                .query_async(&mut redis_conn)
+                .await?;
+
+            expired.push(Bet {
+                status: BetStatus::Expired,
+                last_error_code: Some(error_code.to_string()),
+                last_error_message: Some(error_message.to_string()),
+                ..bet
+            });
+        }
+
+        Ok(expired)
+    }
+
+    async fn reap_stale_claims(&self, claimed_before_ms: i64, scan_limit: i64) -> Result<Vec<Bet>> {
+        let mut redis_conn = self.redis.clone();
+        let candidates: Vec<String> = redis_conn
+            .zrangebyscore_limit(claimed_index_key(), "-inf", claimed_before_ms, 0, scan_limit as isize)
+            .await?;
+
+        let mut reaped = Vec::new();
+        for bet_id in candidates.iter().filter_map(|id| Uuid::parse_str(id).ok()) {
+            let processor_id: Option<String> = redis_conn.hget(bet_key(bet_id), "processor_id").await?;
+            // Only bets still batched are failed; one its processor reported on in the meantime stays put
+            match self.fail_retryable(bet_id, Some(&BetStatus::Batched)).await? {
+                Some(_) => {}
+                None => {
+                    let _: () = redis_conn.zrem(claimed_index_key(), bet_id.to_string()).await?;
+                    continue;
+                }
+            }
+
+            let error = BetError {
+                at: self.clock.now(),
+                code: Some(ErrorCode::NETWORK_PROCESSOR_TIMEOUT.as_str().to_string()),
+                message: "Processor did not report on the batch before its claim went stale".to_string(),
+                processor_id,
+            };
+            self.record_error(bet_id, &error).await?;
+            if let Some(bet) = load_bet_from_hash(&mut redis_conn, bet_id).await? {
+                reaped.push(bet);
+            }
+        }
+
+        Ok(reaped)
+    }
+
+    async fn enqueue_manual_settlement(&self, bet_id: Uuid, won: bool, payout_amount: i64) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let pending_at_field = status_at_field(&BetStatus::Pending);
+        let fields = [
+            ("status", status_to_string(&BetStatus::Pending)),
+            (pending_at_field.as_str(), self.clock.now().timestamp_millis().to_string()),
+            ("won", won.to_string()),
+            ("payout_amount", payout_amount.to_string()),
+            ("manual_settlement", "true".to_string()),
+            ("last_error_code", "".to_string()),
+            ("last_error_message", "".to_string()),
+        ];
+        let _: () = redis::pipe()
+            .atomic()
+            .hset_multiple(bet_key(bet_id), &fields)
+            .ignore()
+            .rpush(events_key(bet_id), event_payload("manual_settlement", &fields))
+            .ignore()
+            .zrem(terminal_index_key(), bet_id.to_string())
+            .ignore()
+            .zrem(manual_review_index_key(), bet_id.to_string())
+            .ignore()
+            // The operator has decided it; expiry no longer applies
+            .zrem(unclaimed_index_key(), bet_id.to_string())
+            .ignore()
+            .query_async(&mut redis_conn)
+            .await?;
+
+        self.queue
+            .make_priority_claimable(bet_id, self.clock.now().timestamp_millis())
+            .await
+    }
+
+    async fn archive_terminal(&self, created_before_ms: i64, limit: i64) -> Result<usize> {
+        let Some(archive) = &self.archive else {
+            return Ok(0);
+        };
+
+        let mut redis_conn = self.redis.clone();
+        let candidates: Vec<String> = redis_conn
+            .zrangebyscore_limit(terminal_index_key(), "-inf", created_before_ms, 0, limit.max(0) as isize)
+            .await?;
+
+        let mut bets = Vec::new();
+        for id_str in candidates {
+            let bet = match Uuid::parse_str(&id_str) {
+                Ok(id) => load_bet_from_hash(&mut redis_conn, id).await?,
+                Err(_) => None,
+            };
+            match bet {
+                Some(bet) if bet.status.is_terminal() => bets.push(bet),
+                // Gone, already archived, or reopened by an admin
+                _ => {
+                    let _: () = redis_conn.zrem(terminal_index_key(), &id_str).await?;
+                }
+            }
+        }
+        if bets.is_empty() {
+            return Ok(0);
+        }
+
+        // Write to the archive before touching Redis so a failure loses nothing
+        archive.store(&bets).await?;
+
+        let archived_at_ms = self.clock.now().timestamp_millis();
+        let script = Script::new(ARCHIVE_TOMBSTONE_SCRIPT);
+        let mut archived = 0;
+        for bet in &bets {
+            let replaced: i32 = script
+                .key(bet_key(bet.bet_id))
+                .key(terminal_index_key())
+                .key(created_index_key())
+                .arg(bet.bet_id.to_string())
+                .arg(archived_at_ms)
+                .arg(status_to_string(&bet.status))
+                .invoke_async(&mut redis_conn)
+                .await?;
+            archived += replaced as usize;
+        }
+
+        Ok(archived)
+    }
+
+    async fn count_manual_review(&self) -> Result<u64> {
+        let mut redis_conn = self.redis.clone();
+        Ok(redis_conn.zcard(manual_review_index_key()).await?)
+    }
+
+    async fn events(&self, bet_id: Uuid) -> Result<Vec<BetEvent>> {
+        let mut redis_conn = self.redis.clone();
+        let raw: Vec<String> = redis_conn.lrange(events_key(bet_id), 0, -1).await?;
+        raw.iter()
+            .map(|event| serde_json::from_str(event).map_err(|e| anyhow::anyhow!("Unreadable event of bet {}: {}", bet_id, e).into()))
+            .collect()
+    }
+
+    async fn record_error(&self, bet_id: Uuid, error: &BetError) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let key = errors_key(bet_id);
+        let _: () = redis::pipe()
+            .atomic()
+            .rpush(&key, error_payload(error)?)
+            .ignore()
+            .ltrim(&key, -ERROR_HISTORY_LIMIT, -1)
+            .ignore()
+            .query_async(&mut redis_conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn error_history(&self, bet_id: Uuid) -> Result<Vec<BetError>> {
+        let mut redis_conn = self.redis.clone();
+        let raw: Vec<String> = redis_conn.lrange(errors_key(bet_id), 0, -1).await?;
+        raw.iter()
+            .map(|error| serde_json::from_str(error).map_err(|e| anyhow::anyhow!("Unreadable error of bet {}: {}", bet_id, e).into()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AuditRepository for RedisBetRepository {
+    async fn append_audit(&self, entry: &AuditEntry) -> Result<()> {
+        let mut redis_conn = self.redis.clone();
+        let payload = serde_json::to_string(entry).map_err(anyhow::Error::from)?;
+        let _: () = redis_conn.rpush(audit_key(entry.bet_id), payload).await?;
+        Ok(())
+    }
+
+    async fn audit_trail(&self, bet_id: Uuid) -> Result<Vec<AuditEntry>> {
+        let mut redis_conn = self.redis.clone();
+        let raw: Vec<String> = redis_conn.lrange(audit_key(bet_id), 0, -1).await?;
+        Ok(raw
+            .iter()
+            .filter_map(|entry| serde_json::from_str(entry).ok())
+            .collect())
+    }
 }
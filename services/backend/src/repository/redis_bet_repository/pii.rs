@@ -0,0 +1,254 @@
+//! Envelope encryption of personal data in bet hashes
+//!
+//! Client info kept with a bet (IP, user agent, later KYC ids) goes into
+//! designated hash fields and never in plaintext. Each value is sealed with
+//! AES-256-GCM under a fresh data key, bound to its bet and field so it can't
+//! be moved to another one; the data key is sealed under a key-encryption key
+//! from `BET_PII_KEYS` (or the secret manager it names), whose id is stored
+//! alongside:
+//!
+//! `enc1:<key id>:<wrapped data key>:<sealed value>`
+//!
+//! To rotate, add a key and make it active. Values under older keys still
+//! decrypt and are rewrapped under the active key when read; only the data key
+//! is resealed. These fields are invisible to `bet_from_map`, so they need no
+//! schema migration, and archival drops them with the rest of the hash.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{bail, ensure, Context};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::config::PiiConfig;
+use crate::domain::ClientInfo;
+use crate::errors::{AppError, Result};
+
+/// Tag of the envelope format above
+const ENVELOPE_TAG: &str = "enc1";
+
+const NONCE_LEN: usize = 12;
+
+/// Hash field holding the encrypted client IP
+pub const CLIENT_IP_FIELD: &str = "pii_client_ip";
+
+/// Hash field holding the encrypted client user agent
+pub const CLIENT_USER_AGENT_FIELD: &str = "pii_user_agent";
+
+/// Hash fields holding encrypted client info
+pub const CLIENT_INFO_FIELDS: [&str; 2] = [CLIENT_IP_FIELD, CLIENT_USER_AGENT_FIELD];
+
+/// Key ring for the PII fields of bet hashes
+pub struct FieldCipher {
+    keys: HashMap<String, Aes256Gcm>,
+    active: String,
+}
+
+/// A stored value split into its parts
+struct Envelope<'a> {
+    key_id: &'a str,
+    wrapped_key: Vec<u8>,
+    sealed: &'a str,
+}
+
+impl<'a> Envelope<'a> {
+    fn parse(stored: &'a str) -> Result<Self> {
+        let mut parts = stored.splitn(4, ':');
+        let (Some(ENVELOPE_TAG), Some(key_id), Some(wrapped_key), Some(sealed)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(AppError::Internal(anyhow::anyhow!("Malformed encrypted field")));
+        };
+        let wrapped_key = STANDARD
+            .decode(wrapped_key)
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Malformed wrapped data key: {}", e)))?;
+        Ok(Self { key_id, wrapped_key, sealed })
+    }
+}
+
+impl FieldCipher {
+    /// The key ring in `config`; `None` when no keys are configured
+    pub fn from_config(config: &PiiConfig) -> anyhow::Result<Option<Self>> {
+        let Some(spec) = config.keys.as_deref() else {
+            return Ok(None);
+        };
+        let mut keys = HashMap::new();
+        let mut first = None;
+        for (i, entry) in spec.split(',').map(str::trim).filter(|e| !e.is_empty()).enumerate() {
+            // Never echo the entry itself: it holds the key
+            let (id, key) = entry
+                .split_once(':')
+                .with_context(|| format!("BET_PII_KEYS entry {} is not <id>:<base64 key>", i + 1))?;
+            ensure!(!id.is_empty(), "BET_PII_KEYS entry {} has an empty key id", i + 1);
+            let key = STANDARD
+                .decode(key)
+                .ok()
+                .filter(|key| key.len() == 32)
+                .with_context(|| format!("BET_PII_KEYS key {} is not 32 base64-encoded bytes", id))?;
+            let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| anyhow::anyhow!("Invalid key length"))?;
+            if keys.insert(id.to_string(), cipher).is_some() {
+                bail!("BET_PII_KEYS lists key {} twice", id);
+            }
+            first.get_or_insert_with(|| id.to_string());
+        }
+        let active = config.active_key_id.clone().or(first).context("BET_PII_KEYS lists no keys")?;
+        ensure!(keys.contains_key(&active), "BET_PII_ACTIVE_KEY_ID {} is not in BET_PII_KEYS", active);
+        Ok(Some(Self { keys, active }))
+    }
+
+    /// Id of the key new values are encrypted under
+    pub fn active_key_id(&self) -> &str {
+        &self.active
+    }
+
+    /// Seal `plaintext` for `field` of bet `bet_id`
+    pub fn encrypt(&self, bet_id: Uuid, field: &str, plaintext: &str) -> Result<String> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let sealed = seal(&Aes256Gcm::new(&data_key), plaintext.as_bytes(), field_aad(bet_id, field).as_bytes())?;
+        self.envelope(&data_key, &STANDARD.encode(sealed))
+    }
+
+    /// Open a value sealed by `encrypt` for the same bet and field
+    pub fn decrypt(&self, bet_id: Uuid, field: &str, stored: &str) -> Result<String> {
+        let envelope = Envelope::parse(stored)?;
+        let data_key = self.unwrap_key(&envelope)?;
+        let sealed = STANDARD
+            .decode(envelope.sealed)
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Malformed encrypted field: {}", e)))?;
+        let cipher = Aes256Gcm::new_from_slice(&data_key)
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Malformed data key")))?;
+        let plaintext = open(&cipher, &sealed, field_aad(bet_id, field).as_bytes())?;
+        String::from_utf8(plaintext).map_err(|e| AppError::Internal(e.into()))
+    }
+
+    /// `stored` with its data key wrapped under the active key; `None` if it already is
+    pub fn rewrap(&self, stored: &str) -> Result<Option<String>> {
+        let envelope = Envelope::parse(stored)?;
+        if envelope.key_id == self.active {
+            return Ok(None);
+        }
+        let data_key = self.unwrap_key(&envelope)?;
+        self.envelope(&data_key, envelope.sealed).map(Some)
+    }
+
+    fn envelope(&self, data_key: &[u8], sealed: &str) -> Result<String> {
+        let wrapped_key = seal(&self.keys[&self.active], data_key, self.active.as_bytes())?;
+        Ok(format!("{}:{}:{}:{}", ENVELOPE_TAG, self.active, STANDARD.encode(wrapped_key), sealed))
+    }
+
+    fn unwrap_key(&self, envelope: &Envelope) -> Result<Vec<u8>> {
+        let key = self.keys.get(envelope.key_id).ok_or_else(|| {
+            AppError::Internal(anyhow::anyhow!("Field encrypted under unknown PII key {}", envelope.key_id))
+        })?;
+        open(key, &envelope.wrapped_key, envelope.key_id.as_bytes())
+    }
+
+    /// Encrypted hash fields for `client`, leaving out what it doesn't have
+    pub fn client_info_fields(&self, bet_id: Uuid, client: &ClientInfo) -> Result<Vec<(&'static str, String)>> {
+        [(CLIENT_IP_FIELD, &client.ip), (CLIENT_USER_AGENT_FIELD, &client.user_agent)]
+            .into_iter()
+            .filter_map(|(field, value)| value.as_deref().map(|value| (field, value)))
+            .map(|(field, value)| Ok((field, self.encrypt(bet_id, field, value)?)))
+            .collect()
+    }
+
+    /// Client info from the values of `CLIENT_INFO_FIELDS`, with the fields to
+    /// write back rewrapped under the active key
+    pub fn client_info_from_fields(
+        &self,
+        bet_id: Uuid,
+        stored: &[Option<String>],
+    ) -> Result<(ClientInfo, Vec<(&'static str, String)>)> {
+        let mut values = Vec::with_capacity(CLIENT_INFO_FIELDS.len());
+        let mut rewrapped = Vec::new();
+        for (field, stored) in CLIENT_INFO_FIELDS.iter().zip(stored) {
+            let value = match stored.as_deref().filter(|v| !v.is_empty()) {
+                Some(stored) => {
+                    if let Some(rewrapped_value) = self.rewrap(stored)? {
+                        rewrapped.push((*field, rewrapped_value));
+                    }
+                    Some(self.decrypt(bet_id, field, stored)?)
+                }
+                None => None,
+            };
+            values.push(value);
+        }
+        let mut values = values.into_iter();
+        let client = ClientInfo { ip: values.next().flatten(), user_agent: values.next().flatten() };
+        Ok((client, rewrapped))
+    }
+}
+
+fn field_aad(bet_id: Uuid, field: &str) -> String {
+    format!("{}:{}", bet_id, field)
+}
+
+/// Random nonce followed by the ciphertext
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to encrypt PII field")))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+fn open(cipher: &Aes256Gcm, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(AppError::Internal(anyhow::anyhow!("Encrypted PII field is truncated")));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("split at NONCE_LEN");
+    cipher
+        .decrypt(&Nonce::from(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| AppError::Internal(anyhow::anyhow!("Failed to decrypt PII field")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(keys: &str, active: Option<&str>) -> PiiConfig {
+        PiiConfig { keys: Some(keys.to_string()), active_key_id: active.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_and_rotate() {
+        let (k1, k2) = (STANDARD.encode([1u8; 32]), STANDARD.encode([2u8; 32]));
+        let bet_id = Uuid::new_v4();
+        let client = ClientInfo { ip: Some("203.0.113.7".to_string()), user_agent: None };
+
+        let old = FieldCipher::from_config(&config(&format!("k1:{}", k1), None)).unwrap().unwrap();
+        let fields = old.client_info_fields(bet_id, &client).unwrap();
+        assert_eq!(fields.len(), 1);
+        let (field, stored) = &fields[0];
+        assert!(!stored.contains("203.0.113.7"));
+        assert!(stored.starts_with("enc1:k1:"));
+
+        // Bound to its bet and field
+        assert_eq!(old.decrypt(bet_id, field, stored).unwrap(), "203.0.113.7");
+        assert!(old.decrypt(Uuid::new_v4(), field, stored).is_err());
+        assert!(old.decrypt(bet_id, CLIENT_USER_AGENT_FIELD, stored).is_err());
+
+        // After rotation the old value still reads, and is rewrapped under k2
+        let rotated = FieldCipher::from_config(&config(&format!("k1:{},k2:{}", k1, k2), Some("k2")))
+            .unwrap()
+            .unwrap();
+        let (read, rewrapped) = rotated.client_info_from_fields(bet_id, &[Some(stored.clone()), None]).unwrap();
+        assert_eq!(read, client);
+        assert_eq!(rewrapped.len(), 1);
+        assert!(rewrapped[0].1.starts_with("enc1:k2:"));
+        let k2_only = FieldCipher::from_config(&config(&format!("k2:{}", k2), None)).unwrap().unwrap();
+        assert_eq!(k2_only.decrypt(bet_id, field, &rewrapped[0].1).unwrap(), "203.0.113.7");
+        assert!(k2_only.decrypt(bet_id, field, stored).is_err());
+
+        // Unusable key rings
+        assert!(FieldCipher::from_config(&PiiConfig { keys: None, active_key_id: None }).unwrap().is_none());
+        assert!(FieldCipher::from_config(&config("k1:c2hvcnQ=", None)).is_err());
+        assert!(FieldCipher::from_config(&config(&format!("k1:{}", k1), Some("k2"))).is_err());
+        assert!(FieldCipher::from_config(&config(&format!("k1:{},k1:{}", k1, k2), None)).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! Bet hash schema versions
+//!
+//! Every bet hash carries a `schema_version`. Readers upgrade older hashes in
+//! place through the migration registry below, and refuse hashes written by a
+//! newer release instead of misreading (and then rewriting) fields they don't
+//! know. Add a migration here whenever a field is added, renamed or reinterpreted,
+//! and bump `CURRENT_SCHEMA_VERSION`.
+
+use chrono::DateTime;
+use redis::aio::ConnectionManager;
+use redis::Script;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::errors::{AppError, Result};
+use super::keys::schema_max_version_key;
+use super::lua_scripts::RECORD_SCHEMA_VERSION_SCRIPT;
+
+/// Hash field holding the record's schema version; absent means version 0
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+/// Schema version written by this release
+pub const CURRENT_SCHEMA_VERSION: u32 = 4;
+
+type Upgrade = fn(Uuid, &HashMap<String, String>) -> Vec<(&'static str, String)>;
+
+/// Upgrades a hash from `from` to `from + 1`, returning the fields to write
+struct SchemaMigration {
+    from: u32,
+    upgrade: Upgrade,
+}
+
+const MIGRATIONS: &[SchemaMigration] = &[
+    SchemaMigration { from: 0, upgrade: upgrade_v0_to_v1 },
+    SchemaMigration { from: 1, upgrade: upgrade_v1_to_v2 },
+    SchemaMigration { from: 2, upgrade: upgrade_v2_to_v3 },
+    SchemaMigration { from: 3, upgrade: upgrade_v3_to_v4 },
+];
+
+/// v0: hashes written before versioning, including test fixtures that stored an
+/// RFC 3339 `created_at` and omitted optional fields
+fn upgrade_v0_to_v1(bet_id: Uuid, map: &HashMap<String, String>) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+    if map.get("created_at_ms").and_then(|v| v.parse::<i64>().ok()).is_none() {
+        if let Some(created_at) = map.get("created_at").and_then(|v| DateTime::parse_from_rfc3339(v).ok()) {
+            fields.push(("created_at_ms", created_at.timestamp_millis().to_string()));
+        }
+    }
+    if map.get("bet_id").map(String::as_str) != Some(&bet_id.to_string()) {
+        fields.push(("bet_id", bet_id.to_string()));
+    }
+    for (field, default) in [
+        ("version", "0"),
+        ("retry_count", "0"),
+        ("game_type", "coinflip"),
+        ("status", "pending"),
+        ("allowance_pda", ""),
+        ("casino_id", ""),
+        ("external_batch_id", ""),
+        ("solana_tx_id", ""),
+        ("processor_id", ""),
+        ("last_error_code", ""),
+        ("last_error_message", ""),
+        ("payout_amount", ""),
+        ("won", ""),
+    ] {
+        if !map.contains_key(field) {
+            fields.push((field, default.to_string()));
+        }
+    }
+    fields
+}
+
+/// v2 adds the provably-fair `server_seed_hash`; older bets have none
+fn upgrade_v1_to_v2(_bet_id: Uuid, map: &HashMap<String, String>) -> Vec<(&'static str, String)> {
+    if map.contains_key("server_seed_hash") {
+        Vec::new()
+    } else {
+        vec![("server_seed_hash", String::new())]
+    }
+}
+
+/// v3 adds the player's `client_seed` for commit-reveal outcomes
+fn upgrade_v2_to_v3(_bet_id: Uuid, map: &HashMap<String, String>) -> Vec<(&'static str, String)> {
+    if map.contains_key("client_seed") {
+        Vec::new()
+    } else {
+        vec![("client_seed", String::new())]
+    }
+}
+
+/// v4 adds client-supplied `metadata`, stored as JSON
+fn upgrade_v3_to_v4(_bet_id: Uuid, map: &HashMap<String, String>) -> Vec<(&'static str, String)> {
+    if map.contains_key("metadata") {
+        Vec::new()
+    } else {
+        vec![("metadata", String::new())]
+    }
+}
+
+/// Schema version of a bet hash
+pub fn schema_version(map: &HashMap<String, String>) -> Result<u32> {
+    match map.get(SCHEMA_VERSION_FIELD) {
+        None => Ok(0),
+        Some(v) => v
+            .parse()
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Invalid {} '{}'", SCHEMA_VERSION_FIELD, v))),
+    }
+}
+
+/// Upgrade `map` to the current schema in place, returning the fields that
+/// changed (empty when already current). Fails for versions from a newer release.
+pub fn upgrade_hash(bet_id: Uuid, map: &mut HashMap<String, String>) -> Result<Vec<(&'static str, String)>> {
+    let mut version = schema_version(map)?;
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(AppError::Internal(anyhow::anyhow!(
+            "Bet {} has schema_version {}, newer than supported {}",
+            bet_id,
+            version,
+            CURRENT_SCHEMA_VERSION
+        )));
+    }
+
+    let mut changed = Vec::new();
+    while version < CURRENT_SCHEMA_VERSION {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| AppError::Internal(anyhow::anyhow!("No bet schema migration from version {}", version)))?;
+        for (field, value) in (migration.upgrade)(bet_id, map) {
+            map.insert(field.to_string(), value.clone());
+            changed.push((field, value));
+        }
+        version += 1;
+    }
+
+    if !changed.is_empty() || !map.contains_key(SCHEMA_VERSION_FIELD) {
+        map.insert(SCHEMA_VERSION_FIELD.to_string(), version.to_string());
+        changed.push((SCHEMA_VERSION_FIELD, version.to_string()));
+    }
+    Ok(changed)
+}
+
+/// Record this release's schema version and return the highest version any
+/// instance has written. A result above `CURRENT_SCHEMA_VERSION` means a newer
+/// release has written records this one can't read safely.
+pub async fn record_schema_version(redis: &mut ConnectionManager) -> Result<u32> {
+    let highest: u32 = Script::new(RECORD_SCHEMA_VERSION_SCRIPT)
+        .key(schema_max_version_key())
+        .arg(CURRENT_SCHEMA_VERSION)
+        .invoke_async(redis)
+        .await?;
+    Ok(highest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_v0_hash() {
+        let bet_id = Uuid::new_v4();
+        let mut map: HashMap<String, String> = [
+            ("user_wallet", "W"),
+            ("status", "completed"),
+            ("created_at", "2025-01-01T00:00:00Z"),
+            ("stake_amount", "100"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let changed = upgrade_hash(bet_id, &mut map).unwrap();
+        assert!(changed.contains(&("created_at_ms", "1735689600000".to_string())));
+        assert_eq!(map["status"], "completed");
+        assert_eq!(map["casino_id"], "");
+        assert_eq!(map["server_seed_hash"], "");
+        assert_eq!(map["client_seed"], "");
+        assert_eq!(map["metadata"], "");
+        assert_eq!(map[SCHEMA_VERSION_FIELD], CURRENT_SCHEMA_VERSION.to_string());
+
+        // Already current: nothing to write
+        assert!(upgrade_hash(bet_id, &mut map).unwrap().is_empty());
+
+        map.insert(SCHEMA_VERSION_FIELD.to_string(), (CURRENT_SCHEMA_VERSION + 1).to_string());
+        assert!(upgrade_hash(bet_id, &mut map).is_err());
+    }
+}
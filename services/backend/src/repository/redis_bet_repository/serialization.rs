@@ -0,0 +1,37 @@
+//! Serialization of bets into Redis hash storage
+//!
+//! The inverse of `deserialization`: optional fields are stored as empty strings.
+
+use crate::domain::Bet;
+use super::schema::{CURRENT_SCHEMA_VERSION, SCHEMA_VERSION_FIELD};
+use super::status::status_to_string;
+
+/// Hash fields for a bet, as written by `create` and the legacy migration
+pub fn bet_hash_fields(bet: &Bet, version: i64) -> Vec<(&'static str, String)> {
+    vec![
+        ("bet_id", bet.bet_id.to_string()),
+        ("created_at_ms", bet.created_at.timestamp_millis().to_string()),
+        ("user_wallet", bet.user_wallet.clone()),
+        ("vault_address", bet.vault_address.clone()),
+        ("allowance_pda", bet.allowance_pda.clone().unwrap_or_default()),
+        ("casino_id", bet.casino_id.clone().unwrap_or_default()),
+        ("game_type", bet.game_type.clone()),
+        ("stake_amount", bet.stake_amount.to_string()),
+        ("stake_token", bet.stake_token.clone()),
+        ("choice", bet.choice.clone()),
+        ("status", status_to_string(&bet.status)),
+        ("external_batch_id", bet.external_batch_id.map(|id| id.to_string()).unwrap_or_default()),
+        ("solana_tx_id", bet.solana_tx_id.clone().unwrap_or_default()),
+        ("retry_count", bet.retry_count.to_string()),
+        ("processor_id", bet.processor_id.clone().unwrap_or_default()),
+        ("last_error_code", bet.last_error_code.clone().unwrap_or_default()),
+        ("last_error_message", bet.last_error_message.clone().unwrap_or_default()),
+        ("payout_amount", bet.payout_amount.map(|v| v.to_string()).unwrap_or_default()),
+        ("won", bet.won.map(|v| v.to_string()).unwrap_or_default()),
+        ("server_seed_hash", bet.server_seed_hash.clone().unwrap_or_default()),
+        ("client_seed", bet.client_seed.clone().unwrap_or_default()),
+        ("metadata", bet.metadata.as_ref().and_then(|m| serde_json::to_string(m).ok()).unwrap_or_default()),
+        ("version", version.to_string()),
+        (SCHEMA_VERSION_FIELD, CURRENT_SCHEMA_VERSION.to_string()),
+    ]
+}
@@ -2,7 +2,10 @@
 //!
 //! Converts between BetStatus enum and Redis string representations.
 
+use uuid::Uuid;
+
 use crate::domain::BetStatus;
+use crate::errors::{AppError, Result};
 
 /// Convert BetStatus to Redis string
 pub fn status_to_string(status: &BetStatus) -> String {
@@ -14,6 +17,7 @@ pub fn status_to_string(status: &BetStatus) -> String {
         BetStatus::Completed => "completed",
         BetStatus::FailedRetryable => "failed_retryable",
         BetStatus::FailedManualReview => "failed_manual_review",
+        BetStatus::Expired => "expired",
     }
     .to_string()
 }
@@ -28,10 +32,24 @@ pub fn status_from_string(s: &str) -> Option<BetStatus> {
         "completed" => Some(BetStatus::Completed),
         "failed_retryable" => Some(BetStatus::FailedRetryable),
         "failed_manual_review" => Some(BetStatus::FailedManualReview),
+        "expired" => Some(BetStatus::Expired),
         _ => None,
     }
 }
 
+/// Refuse to move a bet stored as `previous` to `next` unless the pipeline allows it
+///
+/// Bets without a readable stored status are let through, as before the check existed.
+pub fn check_transition(bet_id: Uuid, previous: Option<&str>, next: &BetStatus) -> Result<()> {
+    match previous.and_then(status_from_string) {
+        Some(from) if !from.can_transition_to(next) => Err(AppError::invalid_status(format!(
+            "Cannot move bet {} from {:?} to {:?}",
+            bet_id, from, next
+        ))),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,6 +64,7 @@ mod tests {
             BetStatus::Completed,
             BetStatus::FailedRetryable,
             BetStatus::FailedManualReview,
+            BetStatus::Expired,
         ];
 
         for status in statuses {
@@ -55,6 +74,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_check_transition() {
+        let bet_id = Uuid::new_v4();
+        assert!(check_transition(bet_id, Some("batched"), &BetStatus::Completed).is_ok());
+        assert!(check_transition(bet_id, Some("completed"), &BetStatus::FailedRetryable).is_err());
+        assert!(check_transition(bet_id, Some("pending"), &BetStatus::Completed).is_err());
+        assert!(check_transition(bet_id, None, &BetStatus::Completed).is_ok());
+    }
+
     #[test]
     fn test_invalid_status_string() {
         assert_eq!(status_from_string("invalid"), None);
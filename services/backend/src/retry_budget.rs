@@ -0,0 +1,29 @@
+//! Permanent-failure retry budget
+//!
+//! When too many settlements fail permanently at once, processors stop trusting
+//! the classification: the first one to notice records the trip under
+//! `shared::constants::RETRY_BUDGET_TRIPPED_KEY` and pages, and every processor
+//! keeps further failures retryable until an operator acknowledges it here.
+
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use shared::constants::RETRY_BUDGET_TRIPPED_KEY;
+
+use crate::domain::RetryBudgetTrip;
+use crate::errors::{AppError, Result};
+
+fn parse(raw: &str) -> Result<RetryBudgetTrip> {
+    serde_json::from_str(raw).map_err(|e| AppError::Internal(anyhow::anyhow!("Corrupt retry budget trip: {}", e)))
+}
+
+/// The unacknowledged trip, if any
+pub async fn current(redis: &mut ConnectionManager) -> Result<Option<RetryBudgetTrip>> {
+    let raw: Option<String> = redis.get(RETRY_BUDGET_TRIPPED_KEY).await?;
+    raw.as_deref().map(parse).transpose()
+}
+
+/// Clear the trip, returning it; `None` if nothing was tripped
+pub async fn acknowledge(redis: &mut ConnectionManager) -> Result<Option<RetryBudgetTrip>> {
+    let raw: Option<String> = redis::cmd("GETDEL").arg(RETRY_BUDGET_TRIPPED_KEY).query_async(redis).await?;
+    raw.as_deref().map(parse).transpose()
+}
@@ -0,0 +1,272 @@
+//! Game sessions
+//!
+//! A session groups a user's bets against one allowance so a client can offer
+//! "play N rounds" after a single approval. It stores the wallet, vault and
+//! allowance PDA once; bets that name the session inherit them. Each bet
+//! reserves a round and its stake against the session's limits before it is
+//! created, atomically, so concurrent bets can't overshoot them. The bet
+//! repository indexes bets by session, and results are aggregated from those
+//! bets when the session is read.
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Script};
+use uuid::Uuid;
+
+use crate::domain::{Bet, BetStatus, CreateBetRequest, Session, SessionResults};
+use crate::errors::{AppError, Result};
+
+/// Redis key prefix for a session record
+const SESSION_PREFIX: &str = "sessions:";
+
+/// Redis key prefix for a session's reserved rounds and stake
+const USAGE_PREFIX: &str = "sessions:usage:";
+
+/// Reserve one round and `ARGV[1]` stake unless that exceeds `ARGV[2]` rounds or
+/// `ARGV[3]` total stake (0 = no cap). Returns 0 on success, 1 or 2 for the limit hit.
+const RESERVE_SCRIPT: &str = r#"
+local rounds = tonumber(redis.call('HGET', KEYS[1], 'rounds') or '0')
+local staked = tonumber(redis.call('HGET', KEYS[1], 'staked') or '0')
+local stake = tonumber(ARGV[1])
+local max_rounds = tonumber(ARGV[2])
+if max_rounds > 0 and rounds + 1 > max_rounds then
+    return 1
+end
+local max_total = tonumber(ARGV[3])
+if max_total > 0 and staked + stake > max_total then
+    return 2
+end
+redis.call('HINCRBY', KEYS[1], 'rounds', 1)
+redis.call('HINCRBY', KEYS[1], 'staked', stake)
+return 0
+"#;
+
+fn session_key(session_id: Uuid) -> String {
+    format!("{}{}", SESSION_PREFIX, session_id)
+}
+
+fn usage_key(session_id: Uuid) -> String {
+    format!("{}{}", USAGE_PREFIX, session_id)
+}
+
+/// Rounds and stake reserved by a session's bets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionUsage {
+    pub rounds: u32,
+    pub staked: u64,
+}
+
+/// Fill a bet request from its session and check it against the per-bet rules
+///
+/// Wallet, vault and allowance the client did send must match the session's.
+pub fn bind_bet(session: &Session, req: &mut CreateBetRequest, stake: u64, now: DateTime<Utc>) -> Result<()> {
+    for (field, sent, expected) in [
+        ("user_wallet", &mut req.user_wallet, &session.user_wallet),
+        ("vault_address", &mut req.vault_address, &session.vault_address),
+        ("allowance_pda", &mut req.allowance_pda, &session.allowance_pda),
+    ] {
+        if sent.as_ref().is_some_and(|sent| sent != expected) {
+            return Err(AppError::invalid_input(format!("{} does not match session {}", field, session.session_id)));
+        }
+        *sent = Some(expected.clone());
+    }
+
+    if req.stake_token != session.stake_token {
+        return Err(AppError::invalid_input(format!(
+            "Session {} only accepts {} stakes",
+            session.session_id, session.stake_token
+        )));
+    }
+    if now >= session.expires_at {
+        return Err(AppError::session_limit(format!("Session {} has expired", session.session_id)));
+    }
+    if let Some(max) = session.limits.max_stake_per_bet.filter(|max| stake > *max) {
+        return Err(AppError::session_limit(format!(
+            "Stake {} exceeds session {}'s per-bet limit of {}",
+            stake, session.session_id, max
+        )));
+    }
+    Ok(())
+}
+
+/// Aggregate outcomes of a session's bets
+pub fn results(bets: &[Bet]) -> SessionResults {
+    let mut results = SessionResults { rounds: bets.len() as u32, ..Default::default() };
+    for bet in bets {
+        match (&bet.status, bet.won) {
+            (BetStatus::Completed, Some(won)) => {
+                if won {
+                    results.won += 1;
+                    results.total_payout += bet.payout_amount.unwrap_or(0);
+                } else {
+                    results.lost += 1;
+                }
+                results.total_staked += bet.stake_amount;
+            }
+            (status, _) if status.is_terminal() => results.unsettled += 1,
+            _ => results.pending += 1,
+        }
+    }
+    results.net = results.total_payout - results.total_staked;
+    results
+}
+
+pub async fn create(redis: &mut ConnectionManager, session: &Session) -> Result<()> {
+    let payload = serde_json::to_string(session).map_err(anyhow::Error::from)?;
+    let _: () = redis.set(session_key(session.session_id), payload).await?;
+    Ok(())
+}
+
+pub async fn get(redis: &mut ConnectionManager, session_id: Uuid) -> Result<Option<Session>> {
+    let raw: Option<String> = redis.get(session_key(session_id)).await?;
+    Ok(raw.and_then(|raw| serde_json::from_str(&raw).ok()))
+}
+
+pub async fn usage(redis: &mut ConnectionManager, session_id: Uuid) -> Result<SessionUsage> {
+    let (rounds, staked): (Option<u32>, Option<u64>) = redis::cmd("HMGET")
+        .arg(usage_key(session_id))
+        .arg("rounds")
+        .arg("staked")
+        .query_async(redis)
+        .await?;
+    Ok(SessionUsage { rounds: rounds.unwrap_or(0), staked: staked.unwrap_or(0) })
+}
+
+/// Why a bet of `stake` can't be reserved on top of `usage`; `None` if it fits
+///
+/// Mirrors `RESERVE_SCRIPT`, for stores that reserve without it.
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn exceeded_limit(session: &Session, usage: SessionUsage, stake: u64) -> Option<AppError> {
+    let max_rounds = session.limits.max_rounds.unwrap_or(0);
+    let max_total = session.limits.max_total_stake.unwrap_or(0);
+    if max_rounds > 0 && usage.rounds + 1 > max_rounds {
+        Some(limit_error(session, stake, 1))
+    } else if max_total > 0 && usage.staked + stake > max_total {
+        Some(limit_error(session, stake, 2))
+    } else {
+        None
+    }
+}
+
+/// Error for the limit `RESERVE_SCRIPT` reported hitting
+fn limit_error(session: &Session, stake: u64, outcome: i64) -> AppError {
+    match outcome {
+        1 => AppError::session_limit(format!(
+            "Session {} has used all {} rounds",
+            session.session_id,
+            session.limits.max_rounds.unwrap_or(0)
+        )),
+        _ => AppError::session_limit(format!(
+            "Stake {} would exceed session {}'s total stake limit",
+            stake, session.session_id
+        )),
+    }
+}
+
+/// Reserve a round and `stake` for a bet, failing when the session's limits are reached
+pub async fn reserve(redis: &mut ConnectionManager, session: &Session, stake: u64) -> Result<()> {
+    let outcome: i64 = Script::new(RESERVE_SCRIPT)
+        .key(usage_key(session.session_id))
+        .arg(stake)
+        .arg(session.limits.max_rounds.unwrap_or(0))
+        .arg(session.limits.max_total_stake.unwrap_or(0))
+        .invoke_async(redis)
+        .await?;
+    match outcome {
+        0 => Ok(()),
+        outcome => Err(limit_error(session, stake, outcome)),
+    }
+}
+
+/// Give back a reservation whose bet was never created
+pub async fn release(redis: &mut ConnectionManager, session_id: Uuid, stake: u64) -> Result<()> {
+    let key = usage_key(session_id);
+    let _: () = redis::pipe()
+        .atomic()
+        .hincr(&key, "rounds", -1)
+        .ignore()
+        .hincr(&key, "staked", -(stake as i64))
+        .ignore()
+        .query_async(redis)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::SessionLimits;
+    use shared::LamportAmount;
+
+    fn bet(status: BetStatus, won: Option<bool>, payout_amount: Option<i64>) -> Bet {
+        Bet {
+            bet_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            user_wallet: "W".to_string(),
+            vault_address: "V".to_string(),
+            allowance_pda: Some("A".to_string()),
+            casino_id: None,
+            game_type: "coinflip".to_string(),
+            stake_amount: 100,
+            stake_token: "SOL".to_string(),
+            choice: "heads".to_string(),
+            status,
+            external_batch_id: None,
+            solana_tx_id: None,
+            retry_count: 0,
+            processor_id: None,
+            last_error_code: None,
+            last_error_message: None,
+            payout_amount,
+            won,
+            server_seed_hash: None,
+            client_seed: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_bind_bet_and_results() {
+        let now = Utc::now();
+        let session = Session {
+            session_id: Uuid::new_v4(),
+            user_wallet: "W".to_string(),
+            vault_address: "V".to_string(),
+            allowance_pda: "A".to_string(),
+            stake_token: "SOL".to_string(),
+            limits: SessionLimits { max_rounds: Some(10), max_total_stake: None, max_stake_per_bet: Some(500) },
+            created_at: now,
+            expires_at: now + chrono::Duration::hours(1),
+        };
+        let request = CreateBetRequest {
+            user_wallet: None,
+            vault_address: None,
+            allowance_pda: None,
+            stake_amount: LamportAmount::new(100_000_000).unwrap(),
+            stake_token: "SOL".to_string(),
+            game_type: "coinflip".to_string(),
+            choice: "heads".to_string(),
+            client_seed: None,
+            session_id: Some(session.session_id),
+            metadata: None,
+        };
+
+        let mut req = request.clone();
+        bind_bet(&session, &mut req, 400, now).unwrap();
+        assert_eq!(req.allowance_pda.as_deref(), Some("A"));
+        assert!(bind_bet(&session, &mut request.clone(), 600, now).is_err());
+        assert!(bind_bet(&session, &mut request.clone(), 400, session.expires_at).is_err());
+        let mut other_wallet = CreateBetRequest { user_wallet: Some("X".to_string()), ..request };
+        assert!(bind_bet(&session, &mut other_wallet, 400, now).is_err());
+
+        let results = results(&[
+            bet(BetStatus::Completed, Some(true), Some(200)),
+            bet(BetStatus::Completed, Some(false), None),
+            bet(BetStatus::Batched, None, None),
+            bet(BetStatus::Expired, None, None),
+        ]);
+        assert_eq!((results.rounds, results.won, results.lost), (4, 1, 1));
+        assert_eq!((results.pending, results.unsettled), (1, 1));
+        assert_eq!((results.total_staked, results.total_payout, results.net), (200, 200, 0));
+    }
+}
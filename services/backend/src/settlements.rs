@@ -0,0 +1,205 @@
+//! On-chain settlement of bets
+//!
+//! Batch updates may carry the transactions that spent a bet's stake and paid
+//! it out; they are kept per bet so bet reads can say whether funds moved yet.
+//! Processors report what they saw when they sent, so a reconciler re-reads
+//! every settlement that isn't finalized from RPC until it is, fails, or has
+//! been tracked longer than [`RECONCILE_WINDOW`].
+
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::domain::{ConfirmationStatus, SettlementReport};
+use crate::errors::{AppError, Result};
+
+/// Redis key prefix for a bet's settlement report
+const SETTLEMENT_PREFIX: &str = "settlement:";
+
+/// Redis sorted set of bets whose settlement isn't final, scored by when it was first reported
+const UNFINALIZED_KEY: &str = "settlements:unfinalized";
+
+/// Reports outlive the bets' own cache; archived bets fall back to their stored signature
+const SETTLEMENT_TTL_SECONDS: u64 = 30 * 86_400;
+
+/// Signatures `getSignatureStatuses` accepts per call
+const RECONCILE_BATCH: isize = 256;
+
+/// Settlements still unknown to RPC after this long are no longer followed
+pub const RECONCILE_WINDOW: Duration = Duration::from_secs(3600);
+
+fn settlement_key(bet_id: Uuid) -> String {
+    format!("{}{}", SETTLEMENT_PREFIX, bet_id)
+}
+
+pub async fn load(redis: &mut ConnectionManager, bet_id: Uuid) -> Result<Option<SettlementReport>> {
+    let stored: Option<String> = redis.get(settlement_key(bet_id)).await?;
+    stored
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Corrupt settlement report for bet {}: {}", bet_id, e)))
+}
+
+async fn save(redis: &mut ConnectionManager, bet_id: Uuid, report: &SettlementReport) -> Result<()> {
+    let json = serde_json::to_string(report).map_err(anyhow::Error::from)?;
+    let _: () = redis.set_ex(settlement_key(bet_id), json, SETTLEMENT_TTL_SECONDS).await?;
+    Ok(())
+}
+
+/// Merge `report` into the bet's settlement and follow it until final
+pub async fn record(redis: &mut ConnectionManager, bet_id: Uuid, report: SettlementReport) -> Result<()> {
+    let merged = load(redis, bet_id).await?.unwrap_or_default().merge(report);
+    save(redis, bet_id, &merged).await?;
+    let tracked = merged.spend_tx.is_some() || merged.payout_tx.is_some();
+    if tracked && !merged.confirmation_status.is_some_and(|status| status.is_final()) {
+        let _: () = redis::cmd("ZADD")
+            .arg(UNFINALIZED_KEY)
+            .arg("NX")
+            .arg(chrono::Utc::now().timestamp_millis())
+            .arg(bet_id.to_string())
+            .query_async(redis)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Status of a transaction RPC knows about
+fn confirmation_of(failed: bool, finalized: bool, confirmed: bool) -> ConfirmationStatus {
+    if failed {
+        ConfirmationStatus::Failed
+    } else if finalized {
+        ConfirmationStatus::Finalized
+    } else if confirmed {
+        ConfirmationStatus::Confirmed
+    } else {
+        ConfirmationStatus::Processed
+    }
+}
+
+/// Re-read the oldest unfinalized settlements from RPC; returns how many changed
+pub async fn reconcile(redis: &mut ConnectionManager, solana: &RpcClient) -> Result<usize> {
+    let bet_ids: Vec<String> = redis.zrange(UNFINALIZED_KEY, 0, RECONCILE_BATCH - 1).await?;
+    let mut reports = Vec::with_capacity(bet_ids.len());
+    for raw in bet_ids {
+        let bet_id = Uuid::parse_str(&raw).ok();
+        let report = match bet_id {
+            Some(bet_id) => load(redis, bet_id).await?,
+            None => None,
+        };
+        match (bet_id, report) {
+            (Some(bet_id), Some(report)) => reports.push((bet_id, report)),
+            // Expired or unreadable; nothing left to follow
+            _ => {
+                let _: () = redis.zrem(UNFINALIZED_KEY, &raw).await?;
+            }
+        }
+    }
+
+    let signatures: Vec<Signature> = reports
+        .iter()
+        .flat_map(|(_, report)| [&report.spend_tx, &report.payout_tx])
+        .flatten()
+        .filter_map(|tx| Signature::from_str(tx).ok())
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+    if signatures.is_empty() {
+        return Ok(0);
+    }
+    let statuses = solana
+        .get_signature_statuses_with_history(&signatures)
+        .await
+        .map_err(AppError::rpc_unavailable)?
+        .value;
+    let known: HashMap<String, (ConfirmationStatus, u64)> = signatures
+        .iter()
+        .zip(statuses)
+        .filter_map(|(signature, status)| {
+            let status = status?;
+            let confirmation = confirmation_of(
+                status.err.is_some(),
+                status.satisfies_commitment(CommitmentConfig::finalized()),
+                status.satisfies_commitment(CommitmentConfig::confirmed()),
+            );
+            Some((signature.to_string(), (confirmation, status.slot)))
+        })
+        .collect();
+
+    let cutoff = chrono::Utc::now().timestamp_millis() - RECONCILE_WINDOW.as_millis() as i64;
+    let mut changed = 0;
+    for (bet_id, report) in reports {
+        let seen: Vec<(ConfirmationStatus, u64)> = [&report.spend_tx, &report.payout_tx]
+            .into_iter()
+            .flatten()
+            .filter_map(|tx| known.get(tx).copied())
+            .collect();
+        // The settlement is only as far along as its least confirmed transaction
+        let confirmation_status = seen.iter().map(|(status, _)| *status).min();
+        let slot = seen.iter().map(|(_, slot)| *slot).max();
+
+        let moved = (confirmation_status, slot) != (report.confirmation_status, report.slot);
+        if let Some(status) = confirmation_status.filter(|_| moved) {
+            let update = SettlementReport { confirmation_status, slot, ..SettlementReport::default() };
+            save(redis, bet_id, &report.merge(update)).await?;
+            metrics::counter!("settlement_reconciler_updates_total", "status" => status.as_str()).increment(1);
+            changed += 1;
+        }
+
+        let done = confirmation_status.is_some_and(|status| status.is_final());
+        let first_seen: Option<f64> = redis.zscore(UNFINALIZED_KEY, bet_id.to_string()).await?;
+        let stale = first_seen.is_some_and(|at| (at as i64) < cutoff);
+        if done || stale {
+            if stale && !done {
+                tracing::warn!(%bet_id, "Settlement still not final after the reconcile window; no longer followed");
+            }
+            let _: () = redis.zrem(UNFINALIZED_KEY, bet_id.to_string()).await?;
+        }
+    }
+    Ok(changed)
+}
+
+/// Run the settlement reconciler forever; returns immediately if `interval` is zero
+pub async fn run_reconciler(mut redis: ConnectionManager, solana: Arc<RpcClient>, interval: Duration) {
+    if interval.is_zero() {
+        tracing::info!("Settlement reconcile interval is 0; reconciler not started");
+        return;
+    }
+    tracing::info!(interval_seconds = interval.as_secs(), "Settlement reconciler started");
+
+    loop {
+        tokio::time::sleep(interval).await;
+        match reconcile(&mut redis, &solana).await {
+            Ok(0) => {}
+            Ok(changed) => tracing::debug!(changed, "Settlement statuses reconciled"),
+            Err(e) => tracing::warn!(error = %e, "Settlement reconcile failed"),
+        }
+        if let Ok(backlog) = redis.zcard::<_, u64>(UNFINALIZED_KEY).await {
+            metrics::gauge!("settlement_reconciler_backlog").set(backlog as f64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_outranks_commitment() {
+        assert_eq!(confirmation_of(true, true, true), ConfirmationStatus::Failed);
+        assert_eq!(confirmation_of(false, true, true), ConfirmationStatus::Finalized);
+        assert_eq!(confirmation_of(false, false, true), ConfirmationStatus::Confirmed);
+        assert_eq!(confirmation_of(false, false, false), ConfirmationStatus::Processed);
+        // One failed transaction fails the whole settlement
+        assert_eq!(
+            [ConfirmationStatus::Finalized, ConfirmationStatus::Failed].into_iter().min(),
+            Some(ConfirmationStatus::Failed)
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Settlement service level objective
+//!
+//! The objective is that `SLO_TARGET` of bets (99% by default) settle within
+//! `SLO_LATENCY_SECONDS` (60) of being placed. Every bet reaching a terminal
+//! status counts as good (completed in time) or bad (completed late, failed to
+//! manual review, or expired) in per-minute and per-hour Redis buckets shared by
+//! all instances. The burn rate of a window is its bad share divided by the
+//! error budget `1 - target`: 1.0 spends the budget exactly over the SLO period,
+//! 14.4 spends a 30-day budget in about two days.
+//!
+//! The evaluator exports burn rates and the remaining budget as gauges and
+//! alerts the multi-window way: a fast burn over the last hour (confirmed over
+//! 5 minutes) pages, a slow burn over 6 hours (confirmed over 30 minutes)
+//! warns. `GET /api/admin/slo` reports the same numbers with the p95 of recent
+//! settlement latencies.
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::config::SloConfig;
+use crate::domain::BetStatus;
+use crate::errors::Result;
+use crate::pipeline_latency::percentile;
+
+const MINUTE_PREFIX: &str = "slo:minute:";
+const HOUR_PREFIX: &str = "slo:hour:";
+
+/// Settlement latencies of recent completed bets, newest first
+const LATENCY_SAMPLES_KEY: &str = "slo:latency_ms";
+const LATENCY_SAMPLES: isize = 1000;
+
+/// Windows burn rates are computed over, in minutes
+const WINDOWS: [(&str, usize); 4] = [("5m", 5), ("30m", 30), ("1h", 60), ("6h", 360)];
+
+/// Minute buckets a summary reads: enough for the longest window
+pub(crate) fn summary_minutes() -> usize {
+    WINDOWS.iter().map(|&(_, length)| length).max().unwrap_or(0)
+}
+
+/// Minute buckets outlive the longest window by a margin
+const MINUTE_BUCKET_TTL_SECONDS: i64 = 7 * 3600;
+
+/// Good and bad settlements counted in a bucket or window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Counts {
+    pub total: u64,
+    pub bad: u64,
+}
+
+impl Counts {
+    fn add(self, other: Counts) -> Counts {
+        Counts { total: self.total + other.total, bad: self.bad + other.bad }
+    }
+
+    /// Share of good settlements; `None` without any
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.total > 0).then(|| 1.0 - self.bad as f64 / self.total as f64)
+    }
+
+    /// How many times faster than sustainable the error budget is being spent
+    pub fn burn_rate(&self, target: f64) -> Option<f64> {
+        self.success_rate().map(|rate| (1.0 - rate) / (1.0 - target))
+    }
+}
+
+/// Whether a bet that reached `status` after `latency_ms` met the objective; `None` if not terminal
+pub fn is_good(status: &BetStatus, latency_ms: i64, objective: &SloConfig) -> Option<bool> {
+    match status {
+        BetStatus::Completed => Some(latency_ms <= (objective.latency_seconds as i64).saturating_mul(1000)),
+        BetStatus::FailedManualReview | BetStatus::Expired => Some(false),
+        _ => None,
+    }
+}
+
+fn minute_key(at_ms: i64) -> String {
+    format!("{}{}", MINUTE_PREFIX, at_ms.div_euclid(60_000))
+}
+
+fn hour_key(at_ms: i64) -> String {
+    format!("{}{}", HOUR_PREFIX, at_ms.div_euclid(3_600_000))
+}
+
+/// Count a bet that just reached `status`; failures are logged, never returned
+pub async fn record_or_warn(
+    redis: &mut ConnectionManager,
+    objective: &SloConfig,
+    created_at: DateTime<Utc>,
+    status: &BetStatus,
+) {
+    let now_ms = Utc::now().timestamp_millis();
+    let latency_ms = (now_ms - created_at.timestamp_millis()).max(0);
+    let Some(good) = is_good(status, latency_ms, objective) else {
+        return;
+    };
+    metrics::counter!("slo_settlements_total", "outcome" => if good { "good" } else { "bad" }).increment(1);
+
+    let mut pipe = redis::pipe();
+    let hour_ttl = (objective.period_days as i64 * 24 + 1) * 3600;
+    for (key, ttl) in [(minute_key(now_ms), MINUTE_BUCKET_TTL_SECONDS), (hour_key(now_ms), hour_ttl)] {
+        pipe.hincr(&key, "total", 1).ignore();
+        if !good {
+            pipe.hincr(&key, "bad", 1).ignore();
+        }
+        pipe.expire(&key, ttl).ignore();
+    }
+    if *status == BetStatus::Completed {
+        pipe.lpush(LATENCY_SAMPLES_KEY, latency_ms)
+            .ignore()
+            .ltrim(LATENCY_SAMPLES_KEY, 0, LATENCY_SAMPLES - 1)
+            .ignore();
+    }
+    if let Err(e) = pipe.query_async::<()>(redis).await {
+        tracing::warn!(error = %e, "Failed to record SLO settlement");
+    }
+}
+
+/// Counts of the last `count` buckets of `key_of`'s size, newest first
+async fn load_buckets(
+    redis: &mut ConnectionManager,
+    key_of: fn(i64) -> String,
+    bucket_ms: i64,
+    count: usize,
+    now_ms: i64,
+) -> Result<Vec<Counts>> {
+    let mut pipe = redis::pipe();
+    for i in 0..count as i64 {
+        pipe.hget(key_of(now_ms - i * bucket_ms), &["total", "bad"]);
+    }
+    let raw: Vec<(Option<u64>, Option<u64>)> = pipe.query_async(redis).await?;
+    Ok(raw
+        .into_iter()
+        .map(|(total, bad)| Counts { total: total.unwrap_or(0), bad: bad.unwrap_or(0) })
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BurnAlert {
+    /// Budget gone within days; page
+    Fast,
+    /// Budget gone well before the period ends; investigate
+    Slow,
+}
+
+impl BurnAlert {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BurnAlert::Fast => "fast",
+            BurnAlert::Slow => "slow",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowReport {
+    pub window: &'static str,
+    #[serde(flatten)]
+    pub counts: Counts,
+    pub success_rate: Option<f64>,
+    pub burn_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SloReport {
+    pub target: f64,
+    pub latency_objective_seconds: u64,
+    pub windows: Vec<WindowReport>,
+    pub period_days: u64,
+    pub period: Counts,
+    /// Share of the period's error budget left; negative once overspent
+    pub error_budget_remaining: Option<f64>,
+    pub latency_samples: usize,
+    pub p95_latency_ms: Option<i64>,
+    pub alert: Option<BurnAlert>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl SloReport {
+    /// Build from minute buckets (newest first, covering the longest window) and the period's total
+    pub(crate) fn new(objective: &SloConfig, minutes: &[Counts], period: Counts, mut latencies: Vec<i64>) -> Self {
+        let windows: Vec<WindowReport> = WINDOWS
+            .iter()
+            .map(|&(window, length)| {
+                let counts = minutes.iter().take(length).fold(Counts::default(), |sum, c| sum.add(*c));
+                WindowReport {
+                    window,
+                    counts,
+                    success_rate: counts.success_rate(),
+                    burn_rate: counts.burn_rate(objective.target),
+                }
+            })
+            .collect();
+        latencies.sort_unstable();
+
+        Self {
+            target: objective.target,
+            latency_objective_seconds: objective.latency_seconds,
+            alert: alert(&windows, objective),
+            windows,
+            period_days: objective.period_days,
+            period,
+            error_budget_remaining: period.burn_rate(objective.target).map(|burn| 1.0 - burn),
+            latency_samples: latencies.len(),
+            p95_latency_ms: percentile(&latencies, 95),
+            generated_at: Utc::now(),
+        }
+    }
+
+    fn burn_rate(&self, window: &str) -> Option<f64> {
+        self.windows.iter().find(|w| w.window == window).and_then(|w| w.burn_rate)
+    }
+}
+
+/// Both the long and the short window must burn, so an alert fires fast and clears fast
+fn alert(windows: &[WindowReport], objective: &SloConfig) -> Option<BurnAlert> {
+    let burning = |long: &str, short: &str, threshold: f64| {
+        [long, short].iter().all(|name| {
+            windows
+                .iter()
+                .find(|w| w.window == *name)
+                .and_then(|w| w.burn_rate)
+                .is_some_and(|burn| burn >= threshold)
+        })
+    };
+    if burning("1h", "5m", objective.fast_burn_threshold) {
+        Some(BurnAlert::Fast)
+    } else if burning("6h", "30m", objective.slow_burn_threshold) {
+        Some(BurnAlert::Slow)
+    } else {
+        None
+    }
+}
+
+/// Summarize the objective across every instance's settlements
+pub async fn summary(redis: &mut ConnectionManager, objective: &SloConfig) -> Result<SloReport> {
+    let now_ms = Utc::now().timestamp_millis();
+    let minutes = load_buckets(redis, minute_key, 60_000, summary_minutes(), now_ms).await?;
+    let hours = load_buckets(redis, hour_key, 3_600_000, objective.period_days as usize * 24, now_ms).await?;
+    let latencies: Vec<i64> = redis::cmd("LRANGE")
+        .arg(LATENCY_SAMPLES_KEY)
+        .arg(0)
+        .arg(-1)
+        .query_async(redis)
+        .await?;
+
+    let period = hours.into_iter().fold(Counts::default(), Counts::add);
+    Ok(SloReport::new(objective, &minutes, period, latencies))
+}
+
+/// Export burn rates and raise burn alerts forever; returns immediately if `interval` is zero
+pub async fn run_evaluator(mut redis: ConnectionManager, objective: SloConfig, interval: Duration) {
+    if interval.is_zero() {
+        tracing::info!("SLO evaluate interval is 0; burn alerts not evaluated");
+        return;
+    }
+    tracing::info!(
+        target = objective.target,
+        latency_seconds = objective.latency_seconds,
+        interval_seconds = interval.as_secs(),
+        "SLO evaluator started"
+    );
+
+    let mut raised: Option<BurnAlert> = None;
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let report = match summary(&mut redis, &objective).await {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::warn!(error = %e, "SLO evaluation failed");
+                continue;
+            }
+        };
+
+        for window in &report.windows {
+            metrics::gauge!("slo_burn_rate", "window" => window.window).set(window.burn_rate.unwrap_or(0.0));
+        }
+        if let Some(remaining) = report.error_budget_remaining {
+            metrics::gauge!("slo_error_budget_remaining").set(remaining);
+        }
+        if let Some(p95) = report.p95_latency_ms {
+            metrics::gauge!("slo_settlement_latency_p95_seconds").set(p95 as f64 / 1000.0);
+        }
+
+        if report.alert != raised {
+            match report.alert {
+                Some(BurnAlert::Fast) => {
+                    metrics::counter!("slo_burn_alerts_total", "severity" => BurnAlert::Fast.as_str()).increment(1);
+                    tracing::error!(
+                        burn_rate_1h = report.burn_rate("1h"),
+                        burn_rate_5m = report.burn_rate("5m"),
+                        error_budget_remaining = report.error_budget_remaining,
+                        "Settlement SLO error budget burning fast"
+                    );
+                }
+                Some(BurnAlert::Slow) => {
+                    metrics::counter!("slo_burn_alerts_total", "severity" => BurnAlert::Slow.as_str()).increment(1);
+                    tracing::warn!(
+                        burn_rate_6h = report.burn_rate("6h"),
+                        burn_rate_30m = report.burn_rate("30m"),
+                        error_budget_remaining = report.error_budget_remaining,
+                        "Settlement SLO error budget burning"
+                    );
+                }
+                None => tracing::info!("Settlement SLO burn alert cleared"),
+            }
+            raised = report.alert;
+        }
+        metrics::gauge!("slo_burn_alert").set(match report.alert {
+            Some(BurnAlert::Fast) => 2.0,
+            Some(BurnAlert::Slow) => 1.0,
+            None => 0.0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn objective() -> SloConfig {
+        SloConfig {
+            target: 0.99,
+            latency_seconds: 60,
+            period_days: 30,
+            fast_burn_threshold: 14.4,
+            slow_burn_threshold: 6.0,
+            evaluate_interval_seconds: 60,
+        }
+    }
+
+    #[test]
+    fn test_settlement_classification() {
+        let objective = objective();
+        assert_eq!(is_good(&BetStatus::Completed, 60_000, &objective), Some(true));
+        assert_eq!(is_good(&BetStatus::Completed, 60_001, &objective), Some(false));
+        assert_eq!(is_good(&BetStatus::FailedManualReview, 1_000, &objective), Some(false));
+        assert_eq!(is_good(&BetStatus::Expired, 1_000, &objective), Some(false));
+        // Retries are not an outcome yet
+        assert_eq!(is_good(&BetStatus::FailedRetryable, 1_000, &objective), None);
+    }
+
+    #[test]
+    fn test_burn_rate_and_alerts() {
+        let objective = objective();
+        assert_eq!(Counts::default().burn_rate(objective.target), None);
+        let burn = Counts { total: 100, bad: 1 }.burn_rate(objective.target).unwrap();
+        assert!((burn - 1.0).abs() < 1e-9);
+
+        // Failing 20% of the last 5 minutes only: the hour hasn't burned enough to page
+        let mut minutes = vec![Counts { total: 100, bad: 0 }; 360];
+        minutes[..5].fill(Counts { total: 100, bad: 20 });
+        let report = SloReport::new(&objective, &minutes, Counts::default(), Vec::new());
+        assert_eq!(report.alert, None);
+
+        // ...over the whole last hour it does
+        minutes[..60].fill(Counts { total: 100, bad: 20 });
+        let report = SloReport::new(&objective, &minutes, Counts { total: 1000, bad: 5 }, (1..=100).collect());
+        assert_eq!(report.alert, Some(BurnAlert::Fast));
+        assert!((report.error_budget_remaining.unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!(report.p95_latency_ms, Some(95));
+
+        // A lower sustained failure rate over 6 hours is a slow burn
+        let minutes = vec![Counts { total: 100, bad: 8 }; 360];
+        let report = SloReport::new(&objective, &minutes, Counts::default(), Vec::new());
+        assert_eq!(report.alert, Some(BurnAlert::Slow));
+        assert_eq!(report.error_budget_remaining, None);
+    }
+}
@@ -0,0 +1,138 @@
+//! Stale claim reaper
+//!
+//! A processor that dies after claiming a batch leaves its bets `Batched` with
+//! nobody settling them. The reaper fails bets batched more than
+//! `STALE_CLAIM_SECONDS` ago through `FailedRetryable`, so after the usual
+//! backoff another processor claims them, or an operator reviews them once their
+//! retries are spent. A processor that was only slow cannot pay a reaped bet out
+//! twice: the vault's processed-bet account rejects the second settlement.
+
+use shared::clock::Clock;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::domain::Bet;
+use crate::errors::Result;
+use crate::repository::BetRepository;
+
+/// Batched bets inspected per repository call
+const REAP_SCAN_LIMIT: i64 = 500;
+
+/// Fail the bets batched at least `stale_seconds` ago by `clock`
+async fn reap_due(repo: &dyn BetRepository, clock: &dyn Clock, stale_seconds: u64) -> Result<Vec<Bet>> {
+    let cutoff_ms = clock.now().timestamp_millis() - (stale_seconds as i64).saturating_mul(1000);
+    let mut reaped = Vec::new();
+    loop {
+        let page = repo.reap_stale_claims(cutoff_ms, REAP_SCAN_LIMIT).await?;
+        // Each full page left the index, so the next call sees later claims
+        let more = page.len() as i64 == REAP_SCAN_LIMIT;
+        reaped.extend(page);
+        if !more {
+            return Ok(reaped);
+        }
+    }
+}
+
+/// Run the reaper forever; returns immediately if `stale_seconds` is 0
+pub async fn run_stale_claim_reaper(
+    repo: Arc<dyn BetRepository>,
+    clock: Arc<dyn Clock>,
+    stale_seconds: u64,
+    interval: Duration,
+) {
+    if stale_seconds == 0 {
+        tracing::info!("Stale claim timeout disabled; reaper not started");
+        return;
+    }
+
+    tracing::info!(
+        stale_seconds,
+        interval_seconds = interval.as_secs(),
+        "Stale claim reaper started"
+    );
+
+    loop {
+        clock.sleep(interval).await;
+
+        match reap_due(repo.as_ref(), clock.as_ref(), stale_seconds).await {
+            Ok(reaped) if reaped.is_empty() => {}
+            Ok(reaped) => {
+                for bet in &reaped {
+                    tracing::warn!(
+                        bet_id = %bet.bet_id,
+                        batch_id = ?bet.external_batch_id,
+                        processor_id = ?bet.processor_id,
+                        status = ?bet.status,
+                        "Reaped bet from a stale processor claim"
+                    );
+                }
+                metrics::counter!("bets_stale_claims_reaped_total").increment(reaped.len() as u64);
+                tracing::warn!(count = reaped.len(), "Reaped stale claims");
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Stale claim sweep failed");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::BetStatus;
+    use crate::testing::{self, InMemoryBetRepository};
+    use shared::clock::ManualClock;
+    use shared::errors::ErrorCode;
+
+    #[tokio::test]
+    async fn test_reaps_once_claim_goes_stale_on_the_clock() {
+        let clock = Arc::new(ManualClock::at(chrono::Utc::now()));
+        let repo = InMemoryBetRepository::new().with_clock(clock.clone());
+        let bet = testing::pending_bet("W1");
+        repo.insert(bet.clone());
+        let (_, claimed) = repo.claim_pending(10, "processor-1").await.unwrap();
+        assert_eq!(claimed.len(), 1);
+
+        // A bet the processor reported on is left alone
+        let settled = testing::pending_bet("W2");
+        repo.insert(settled.clone());
+        repo.claim_pending(10, "processor-1").await.unwrap();
+        repo.update_status(settled.bet_id, BetStatus::SubmittedToSolana, None).await.unwrap();
+
+        clock.advance(Duration::from_secs(599));
+        assert!(reap_due(&repo, clock.as_ref(), 600).await.unwrap().is_empty());
+
+        clock.advance(Duration::from_secs(1));
+        let reaped = reap_due(&repo, clock.as_ref(), 600).await.unwrap();
+        assert_eq!(reaped.iter().map(|bet| bet.bet_id).collect::<Vec<_>>(), vec![bet.bet_id]);
+        assert_eq!(repo.find_by_id(bet.bet_id).await.unwrap().unwrap().status, BetStatus::FailedRetryable);
+        assert_eq!(repo.find_by_id(settled.bet_id).await.unwrap().unwrap().status, BetStatus::SubmittedToSolana);
+        let errors = repo.error_history(bet.bet_id).await.unwrap();
+        assert_eq!(errors[0].code.as_deref(), Some(ErrorCode::NETWORK_PROCESSOR_TIMEOUT.as_str()));
+        assert_eq!(errors[0].processor_id.as_deref(), Some("processor-1"));
+
+        // Reclaimed, it is timed from the new claim
+        repo.claim_pending(10, "processor-2").await.unwrap();
+        clock.advance(Duration::from_secs(599));
+        assert!(reap_due(&repo, clock.as_ref(), 600).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reaper_runs_on_the_clock() {
+        let clock = Arc::new(ManualClock::at(chrono::Utc::now()));
+        let repo = Arc::new(InMemoryBetRepository::new().with_clock(clock.clone()));
+        let bet = testing::pending_bet("W1");
+        repo.insert(bet.clone());
+        repo.claim_pending(10, "processor-1").await.unwrap();
+
+        let reaper = tokio::spawn(run_stale_claim_reaper(repo.clone(), clock.clone(), 600, Duration::from_secs(60)));
+        for sweep in 1..=10 {
+            clock.until_started(sweep).await;
+            clock.advance(Duration::from_secs(60));
+        }
+        // The tenth sweep ran at 600s and the reaper went back to sleep
+        clock.until_started(11).await;
+        reaper.abort();
+        assert_eq!(repo.find_by_id(bet.bet_id).await.unwrap().unwrap().status, BetStatus::FailedRetryable);
+    }
+}
@@ -1,18 +1,164 @@
+use crate::cache::BetCache;
+use crate::claim_signals::ClaimSignalHub;
 use crate::config::Config;
+use crate::games::GameRegistry;
+use crate::indexer_client::IndexerClient;
+use crate::jurisdiction::JurisdictionGate;
+use crate::load_shedding::LoadShedder;
+use crate::notifications::NotificationHub;
+use crate::receipts::ReceiptSigner;
+use crate::repository::{
+    AuditRepository, BatchRepository, BetArchive, BetRepository, ClaimFairness, FieldCipher, OpsRepository,
+    QueueBackend, RedisBatchRepository, RedisBetRepository, RedisOpsRepository,
+};
+use crate::token_registry::TokenRegistry;
+use axum::extract::FromRef;
+use metrics_exporter_prometheus::PrometheusHandle;
+use shared::clock::{Clock, SystemClock};
 use redis::aio::ConnectionManager;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::str::FromStr;
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub config: Arc<Config>,
-    pub redis: ConnectionManager,
+    pub queue: Arc<dyn QueueBackend>,
+    /// Reads through to the archive for archived bets
+    pub bets: Arc<dyn BetRepository>,
+    pub batches: Arc<dyn BatchRepository>,
+    pub audit: Arc<dyn AuditRepository>,
+    /// Kill switch, sessions, refunds, disputes, ledger and the other operational state
+    pub ops: Arc<dyn OpsRepository>,
+    pub bet_cache: BetCache,
+    /// Used to fetch blockhashes and relay user-signed transactions
+    pub solana: Arc<RpcClient>,
+    pub receipts: Arc<ReceiptSigner>,
+    /// Cached per-mint bet limits read from the vault program
+    pub tokens: TokenRegistry,
+    /// Cold storage for old terminal bets; `None` when archival is disabled
+    pub archive: Option<Arc<dyn BetArchive>>,
+    pub jurisdiction: Arc<JurisdictionGate>,
+    /// Rendered by `/metrics`; `None` when no recorder was installed
+    pub metrics: Option<PrometheusHandle>,
+    /// Mirror of on-chain vault accounts; `None` when `INDEXER_URL` is unset
+    pub indexer: Option<Arc<IndexerClient>>,
+    /// Websocket subscribers to user notifications on this instance
+    pub notifications: NotificationHub,
+    /// Processors subscribed to claimable bets on this instance
+    pub claim_signals: ClaimSignalHub,
+    /// Games from `GAMES`, checked on bet creation
+    pub games: Arc<GameRegistry>,
+    /// Refuses bets while the claimable queue is too deep
+    pub load_shedder: LoadShedder,
+    /// Time allowance expiry is checked against
+    pub clock: Arc<dyn Clock>,
+}
+
+/// Everything `AppState` reads and writes through
+pub struct Storage {
+    pub queue: Arc<dyn QueueBackend>,
+    pub bets: Arc<dyn BetRepository>,
+    pub batches: Arc<dyn BatchRepository>,
+    pub audit: Arc<dyn AuditRepository>,
+    pub ops: Arc<dyn OpsRepository>,
+    pub archive: Option<Arc<dyn BetArchive>>,
+}
+
+impl Storage {
+    /// Repositories over Redis; bet claims are timed by `clock` and client
+    /// info is only stored with a `cipher`
+    pub fn redis(
+        config: &Config,
+        redis: ConnectionManager,
+        queue: Arc<dyn QueueBackend>,
+        archive: Option<Arc<dyn BetArchive>>,
+        cipher: Option<FieldCipher>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let bets = Arc::new(
+            RedisBetRepository::new(redis.clone(), queue.clone())
+                .with_archive(archive.clone())
+                .with_cipher(cipher.map(Arc::new))
+                .with_clock(clock)
+                .with_claim_fairness(ClaimFairness::from_config(&config.queue))
+                .with_indexed_metadata_keys(config.metadata.indexed_keys.clone().into()),
+        );
+        Self {
+            queue,
+            bets: bets.clone(),
+            batches: Arc::new(RedisBatchRepository::new(redis.clone())),
+            audit: bets,
+            ops: Arc::new(RedisOpsRepository::new(redis)),
+            archive,
+        }
+    }
 }
 
 impl AppState {
-    pub fn new(config: Config, redis: ConnectionManager) -> Self {
+    pub fn new(config: Config, storage: Storage, receipts: ReceiptSigner, jurisdiction: JurisdictionGate) -> Self {
+        let commitment = CommitmentConfig::from_str(&config.solana.commitment)
+            .unwrap_or_else(|_| CommitmentConfig::confirmed());
+        let indexer = config.indexer.url.as_deref().map(|url| Arc::new(IndexerClient::new(url)));
         Self {
+            bet_cache: BetCache::new(&config.cache),
+            tokens: TokenRegistry::new(config.cache.token_registry_ttl_seconds),
+            games: Arc::new(GameRegistry::new(&config.games)),
+            load_shedder: LoadShedder::new(
+                config.betting.intake_max_queue_depth,
+                config.betting.intake_retry_after_seconds,
+            ),
+            solana: Arc::new(RpcClient::new_with_commitment(config.solana.rpc_url.clone(), commitment)),
             config: Arc::new(config),
-            redis,
+            queue: storage.queue,
+            bets: storage.bets,
+            batches: storage.batches,
+            audit: storage.audit,
+            ops: storage.ops,
+            receipts: Arc::new(receipts),
+            archive: storage.archive,
+            jurisdiction: Arc::new(jurisdiction),
+            metrics: None,
+            indexer,
+            notifications: NotificationHub::default(),
+            claim_signals: ClaimSignalHub::default(),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    pub fn with_metrics(mut self, handle: PrometheusHandle) -> Self {
+        self.metrics = Some(handle);
+        self
+    }
+
+    /// Check allowance expiry against `clock`; repositories take their own clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+/// What handlers that only read and write repositories need
+///
+/// Such handlers take `State<Repositories>` rather than `State<AppState>`, so
+/// their unit tests run against the in-memory repositories of `testing`
+/// without Redis.
+#[derive(Clone)]
+pub struct Repositories {
+    pub config: Arc<Config>,
+    pub bets: Arc<dyn BetRepository>,
+    pub batches: Arc<dyn BatchRepository>,
+    pub audit: Arc<dyn AuditRepository>,
+}
+
+impl FromRef<AppState> for Repositories {
+    fn from_ref(state: &AppState) -> Self {
+        Self {
+            config: state.config.clone(),
+            bets: state.bets.clone(),
+            batches: state.batches.clone(),
+            audit: state.audit.clone(),
         }
     }
 }
@@ -0,0 +1,107 @@
+//! Prometheus recorder and business metrics
+//!
+//! The process installs a single recorder with [`install_recorder`]; its handle is
+//! rendered both by `/metrics` on the API port and by the metrics port. Business
+//! metrics go through the helpers below so names and labels stay the same at
+//! every call site.
+
+use chrono::{DateTime, Utc};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::domain::BetStatus;
+
+/// Seconds from bet creation to a terminal status
+pub const BET_LIFECYCLE_METRIC: &str = "bet_lifecycle_duration_seconds";
+
+/// Seconds a bet spent in one pipeline stage, by stage
+pub const BET_STAGE_DWELL_METRIC: &str = "bet_stage_dwell_seconds";
+
+/// Seconds spent serving an HTTP request, by route template
+pub const HTTP_REQUEST_DURATION_METRIC: &str = "http_request_duration_seconds";
+
+const HTTP_REQUEST_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Settlement normally takes seconds; expiry and manual review take hours
+const BET_LIFECYCLE_BUCKETS: &[f64] = &[
+    1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 900.0, 3600.0, 21600.0, 86400.0,
+];
+
+/// Claims and submissions take seconds; a stalled processor leaves bets for minutes
+const BET_STAGE_DWELL_BUCKETS: &[f64] = &[
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0,
+];
+
+/// Install the process-wide Prometheus recorder; call once at startup
+pub fn install_recorder() -> anyhow::Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(BET_LIFECYCLE_METRIC.to_string()), BET_LIFECYCLE_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Full(BET_STAGE_DWELL_METRIC.to_string()), BET_STAGE_DWELL_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Full(HTTP_REQUEST_DURATION_METRIC.to_string()), HTTP_REQUEST_BUCKETS)?
+        .install_recorder()?;
+    Ok(handle)
+}
+
+/// `stake_token` is client supplied; only SOL and mint addresses become label values
+fn token_label(stake_token: &str) -> String {
+    if stake_token.eq_ignore_ascii_case("sol") {
+        "SOL".to_string()
+    } else if Pubkey::from_str(stake_token).is_ok() {
+        stake_token.to_string()
+    } else {
+        "other".to_string()
+    }
+}
+
+pub fn record_bet_created(stake_token: &str) {
+    metrics::counter!("bets_created_total", "token" => token_label(stake_token)).increment(1);
+}
+
+pub fn record_validation_rejection(code: &str) {
+    metrics::counter!("validation_rejections_total", "code" => code.to_string()).increment(1);
+}
+
+pub fn record_claim(processor_id: &str, claimed: usize) {
+    metrics::counter!("claim_requests_total", "processor_id" => processor_id.to_string()).increment(1);
+    metrics::counter!("bets_claimed_total", "processor_id" => processor_id.to_string()).increment(claimed as u64);
+}
+
+pub fn record_request(method: &str, route: &str, status: u16, latency: Duration) {
+    metrics::histogram!(
+        HTTP_REQUEST_DURATION_METRIC,
+        "method" => method.to_string(),
+        "route" => route.to_string(),
+        "status" => status.to_string()
+    )
+    .record(latency.as_secs_f64());
+}
+
+/// Record how long a bet took to reach `status`
+pub fn record_bet_finished(created_at: DateTime<Utc>, status: &BetStatus) {
+    let seconds = (Utc::now() - created_at).num_milliseconds().max(0) as f64 / 1000.0;
+    let status = serde_json::to_value(status)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string());
+    metrics::histogram!(BET_LIFECYCLE_METRIC, "status" => status).record(seconds);
+}
+
+/// Record how long a bet spent in a pipeline stage before leaving it
+pub fn record_stage_dwell(stage: &'static str, dwell_ms: i64) {
+    metrics::histogram!(BET_STAGE_DWELL_METRIC, "stage" => stage).record(dwell_ms.max(0) as f64 / 1000.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_label_is_bounded() {
+        assert_eq!(token_label("sol"), "SOL");
+        let mint = "So11111111111111111111111111111111111111112";
+        assert_eq!(token_label(mint), mint);
+        assert_eq!(token_label("anything-a-client-sends"), "other");
+    }
+}
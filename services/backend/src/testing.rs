@@ -0,0 +1,1195 @@
+//! In-memory repositories for handler unit tests
+//!
+//! Handlers run against these without Redis: those taking `State<Repositories>`
+//! through `InMemoryRepositories::state`, the rest through `app_state`.
+//! They keep what the Redis repositories keep and enforce the same status
+//! transitions and batch claim checks, but have no indexes, TTLs or
+//! encryption: claims take the oldest pending bets, with no lanes or fairness,
+//! and nothing is ever archived. `InMemoryQueueBackend` keeps the Redis queue's
+//! lanes for code that schedules through a `QueueBackend` directly.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use shared::clock::{Clock, SystemClock};
+use shared::errors::ErrorCode;
+use shared::profile::Profile;
+use simulation::ServerSeed;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+use crate::auth;
+use crate::batch_claims::{self, BatchClaim, ClaimCheck};
+use crate::config::{Config, SloConfig};
+use crate::daily_report::{DailyReport, DailyTally};
+use crate::disputes::Dispute;
+use crate::domain::{
+    AuditEntry, BatchStatus, Bet, BetError, BetEvent, BetStatus, ClaimableEvent, ClientInfo, CreateBetRequest,
+    PayoutHold, Refund, RefundResult, RefundStatus, RetryBudgetTrip, Session, SettlementReport, WalletTokenRequest,
+};
+use crate::errors::{AppError, Result};
+use crate::fairness::DailySeed;
+use crate::jurisdiction::JurisdictionGate;
+use crate::killswitch::KillSwitchState;
+use crate::ledger::{InvariantReport, Posting, PostingKind};
+use crate::notifications::{Notification, NotificationPreferences};
+use crate::pipeline_latency::{PipelineLatencyReport, Stage};
+use crate::processor_stats::{self, BatchOutcomes, ProcessorStats};
+use crate::receipts::ReceiptSigner;
+use crate::refunds;
+use crate::repository::{
+    bet_hash_fields, check_transition, queue_backend::QueueBackend, status_to_string, AuditRepository,
+    BatchRepository, BetRepository, OpsRepository,
+};
+use crate::sessions::{self, SessionUsage};
+use crate::slo::{self, Counts, SloReport};
+use crate::state::{AppState, Repositories, Storage};
+
+/// Admin key `config()` sets
+pub const ADMIN_KEY: &str = "test-admin-key";
+
+/// Failures kept per bet, as in Redis
+const ERROR_HISTORY_LIMIT: usize = 20;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Local profile defaults with the admin API enabled under `ADMIN_KEY`
+pub fn config() -> Config {
+    let mut config = Config::load(Some(Profile::Local)).expect("local profile config loads");
+    config.admin.api_key = Some(ADMIN_KEY.to_string());
+    config
+}
+
+/// A pending bet as `create` would store it
+pub fn pending_bet(user_wallet: &str) -> Bet {
+    Bet {
+        bet_id: Uuid::new_v4(),
+        created_at: Utc::now(),
+        user_wallet: user_wallet.to_string(),
+        vault_address: format!("vault-{}", user_wallet),
+        allowance_pda: None,
+        casino_id: None,
+        game_type: "coinflip".to_string(),
+        stake_amount: 100_000_000,
+        stake_token: "SOL".to_string(),
+        choice: "heads".to_string(),
+        status: BetStatus::Pending,
+        external_batch_id: None,
+        solana_tx_id: None,
+        retry_count: 0,
+        processor_id: None,
+        last_error_code: None,
+        last_error_message: None,
+        payout_amount: None,
+        won: None,
+        server_seed_hash: None,
+        client_seed: None,
+        metadata: None,
+    }
+}
+
+struct StoredBet {
+    bet: Bet,
+    version: i32,
+    /// Pending since creation with no processor having claimed it; the Redis unclaimed index
+    unclaimed: bool,
+    /// When the bet was batched, while it is; the Redis claimed index
+    claimed_at: Option<DateTime<Utc>>,
+    session_id: Option<Uuid>,
+    client: ClientInfo,
+    events: Vec<BetEvent>,
+    errors: Vec<BetError>,
+}
+
+impl StoredBet {
+    fn new(bet: Bet, session_id: Option<Uuid>, client: ClientInfo) -> Self {
+        let unclaimed = bet.status == BetStatus::Pending;
+        let mut stored = Self {
+            bet,
+            version: 0,
+            unclaimed,
+            claimed_at: None,
+            session_id,
+            client,
+            events: Vec::new(),
+            errors: Vec::new(),
+        };
+        stored.record_event("created");
+        stored
+    }
+
+    /// Log the bet as it now stands, so `replay_events` rebuilds every version
+    fn record_event(&mut self, kind: &str) {
+        self.events.push(BetEvent {
+            kind: kind.to_string(),
+            fields: bet_hash_fields(&self.bet, self.version as i64)
+                .into_iter()
+                .map(|(field, value)| (field.to_string(), value))
+                .collect(),
+            recorded_at: Utc::now(),
+        });
+    }
+
+    fn set_status(&mut self, status: BetStatus, now: DateTime<Utc>) -> Result<()> {
+        check_transition(self.bet.bet_id, Some(&status_to_string(&self.bet.status)), &status)?;
+        if status == BetStatus::FailedRetryable {
+            self.bet.retry_count += 1;
+        }
+        self.unclaimed &= status == BetStatus::Pending;
+        self.claimed_at = (status == BetStatus::Batched).then_some(now);
+        self.bet.status = status;
+        self.version += 1;
+        self.record_event(&status_to_string(&self.bet.status));
+        Ok(())
+    }
+}
+
+/// `BetRepository` over a map
+pub struct InMemoryBetRepository {
+    bets: Mutex<HashMap<Uuid, StoredBet>>,
+    indexed_metadata_keys: Vec<String>,
+    clock: Arc<dyn Clock>,
+    /// Bets whose status writes fail, as they would with Redis down
+    failing: Mutex<HashSet<Uuid>>,
+}
+
+impl Default for InMemoryBetRepository {
+    fn default() -> Self {
+        Self {
+            bets: Mutex::default(),
+            indexed_metadata_keys: Vec::new(),
+            clock: SystemClock::shared(),
+            failing: Mutex::default(),
+        }
+    }
+}
+
+impl InMemoryBetRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time claims and status changes by `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Index these metadata keys for `find_by_metadata`, like `BET_METADATA_INDEXED_KEYS`
+    pub fn with_indexed_metadata_keys(mut self, keys: Vec<String>) -> Self {
+        self.indexed_metadata_keys = keys;
+        self
+    }
+
+    /// Store `bet` as it is, e.g. one that already settled
+    pub fn insert(&self, bet: Bet) {
+        lock(&self.bets).insert(bet.bet_id, StoredBet::new(bet, None, ClientInfo::default()));
+    }
+
+    /// Make status writes of `bet_id` fail until called again with `false`
+    pub fn fail_status_updates(&self, bet_id: Uuid, fail: bool) {
+        let mut failing = lock(&self.failing);
+        if fail {
+            failing.insert(bet_id);
+        } else {
+            failing.remove(&bet_id);
+        }
+    }
+
+    /// Store `bet` with the client info it was placed from
+    pub fn insert_with_client(&self, bet: Bet, client: ClientInfo) {
+        lock(&self.bets).insert(bet.bet_id, StoredBet::new(bet, None, client));
+    }
+
+    fn update<T>(&self, bet_id: Uuid, f: impl FnOnce(&mut StoredBet) -> Result<T>) -> Result<T> {
+        let mut bets = lock(&self.bets);
+        let stored = bets
+            .get_mut(&bet_id)
+            .ok_or_else(|| AppError::not_found(format!("Bet {} not found", bet_id)))?;
+        f(stored)
+    }
+
+    /// Bets matching `filter`, newest first
+    fn newest_first(&self, filter: impl Fn(&StoredBet) -> bool) -> Vec<Bet> {
+        let mut bets: Vec<Bet> = lock(&self.bets).values().filter(|s| filter(s)).map(|s| s.bet.clone()).collect();
+        bets.sort_by_key(|bet| std::cmp::Reverse(bet.created_at));
+        bets
+    }
+
+    fn created_between(&self, from_ms: i64, to_ms: i64) -> Vec<Bet> {
+        let mut bets = self.newest_first(|s| {
+            let created_ms = s.bet.created_at.timestamp_millis();
+            created_ms >= from_ms && created_ms < to_ms
+        });
+        bets.reverse();
+        bets
+    }
+}
+
+fn page(bets: Vec<Bet>, offset: i64, limit: i64) -> Vec<Bet> {
+    bets.into_iter().skip(offset.max(0) as usize).take(limit.max(0) as usize).collect()
+}
+
+#[async_trait]
+impl BetRepository for InMemoryBetRepository {
+    async fn create(&self, user_wallet: &str, vault_address: &str, req: CreateBetRequest, client: &ClientInfo) -> Result<Bet> {
+        let bet = Bet {
+            vault_address: vault_address.to_string(),
+            allowance_pda: req.allowance_pda.filter(|v| !v.is_empty()),
+            game_type: req.game_type,
+            stake_amount: req.stake_amount.as_u64() as i64,
+            stake_token: req.stake_token,
+            choice: req.choice,
+            client_seed: req.client_seed.filter(|s| !s.is_empty()),
+            metadata: req.metadata.filter(|metadata| !metadata.is_empty()),
+            ..pending_bet(user_wallet)
+        };
+        lock(&self.bets).insert(bet.bet_id, StoredBet::new(bet.clone(), req.session_id, client.clone()));
+        Ok(bet)
+    }
+
+    async fn find_by_id(&self, bet_id: Uuid) -> Result<Option<Bet>> {
+        Ok(lock(&self.bets).get(&bet_id).map(|s| s.bet.clone()))
+    }
+
+    async fn client_info(&self, bet_id: Uuid) -> Result<Option<ClientInfo>> {
+        Ok(lock(&self.bets).get(&bet_id).map(|s| s.client.clone()).filter(|client| !client.is_empty()))
+    }
+
+    async fn find_by_user(&self, user_wallet: &str, limit: i64, offset: i64) -> Result<Vec<Bet>> {
+        Ok(page(self.newest_first(|s| s.bet.user_wallet == user_wallet), offset, limit))
+    }
+
+    async fn find_by_session(&self, session_id: Uuid, limit: i64) -> Result<Vec<Bet>> {
+        Ok(page(self.newest_first(|s| s.session_id == Some(session_id)), 0, limit))
+    }
+
+    async fn find_by_metadata(&self, key: &str, value: &str, limit: i64) -> Result<Vec<Bet>> {
+        let matches = |s: &StoredBet| {
+            s.bet.metadata.as_ref().is_some_and(|metadata| {
+                crate::bet_metadata::indexed_entries(metadata, &self.indexed_metadata_keys).contains(&(key, value))
+            })
+        };
+        Ok(page(self.newest_first(matches), 0, limit))
+    }
+
+    async fn find_created_between(&self, from_ms: i64, to_ms: i64, offset: i64, limit: i64) -> Result<Vec<Bet>> {
+        Ok(page(self.created_between(from_ms, to_ms), offset, limit))
+    }
+
+    async fn scan_created_between(&self, _cursor: u64, _count: usize, from_ms: i64, to_ms: i64) -> Result<(u64, Vec<Bet>)> {
+        Ok((0, self.created_between(from_ms, to_ms)))
+    }
+
+    async fn claim_pending(&self, limit: i64, processor_id: &str) -> Result<(Uuid, Vec<Bet>)> {
+        let batch_id = Uuid::new_v4();
+        let now = self.clock.now();
+        let mut bets = lock(&self.bets);
+        let mut pending: Vec<&mut StoredBet> = bets.values_mut().filter(|s| s.bet.status == BetStatus::Pending).collect();
+        pending.sort_by_key(|s| s.bet.created_at);
+
+        let mut claimed = Vec::new();
+        for stored in pending.into_iter().take(limit.max(0) as usize) {
+            stored.bet.external_batch_id = Some(batch_id);
+            stored.bet.processor_id = Some(processor_id.to_string());
+            stored.set_status(BetStatus::Batched, now)?;
+            claimed.push(stored.bet.clone());
+        }
+        Ok((batch_id, claimed))
+    }
+
+    async fn find_by_solana_tx(&self, signature: &str) -> Result<Vec<Bet>> {
+        Ok(self.newest_first(|s| s.bet.solana_tx_id.as_deref() == Some(signature)))
+    }
+
+    async fn update_status(&self, bet_id: Uuid, status: BetStatus, solana_tx_id: Option<String>) -> Result<()> {
+        if lock(&self.failing).contains(&bet_id) {
+            return Err(AppError::Internal(anyhow::anyhow!("Injected write failure for bet {}", bet_id)));
+        }
+        self.update(bet_id, |stored| {
+            if solana_tx_id.is_some() {
+                stored.bet.solana_tx_id = solana_tx_id;
+            }
+            stored.set_status(status, self.clock.now())
+        })
+    }
+
+    async fn update_bet_fields(
+        &self,
+        bet_id: Uuid,
+        won: Option<bool>,
+        payout_amount: Option<i64>,
+        error_message: Option<String>,
+        error_code: Option<String>,
+        server_seed_hash: Option<String>,
+    ) -> Result<()> {
+        self.update(bet_id, |stored| {
+            let bet = &mut stored.bet;
+            bet.won = won.or(bet.won);
+            bet.payout_amount = payout_amount.or(bet.payout_amount);
+            bet.last_error_message = error_message.or(bet.last_error_message.take());
+            bet.last_error_code = error_code.or(bet.last_error_code.take());
+            bet.server_seed_hash = server_seed_hash.or(bet.server_seed_hash.take());
+            stored.record_event("result");
+            Ok(())
+        })
+    }
+
+    async fn update_status_with_version(&self, bet_id: Uuid, expected_version: i32, status: BetStatus) -> Result<bool> {
+        self.update(bet_id, |stored| {
+            if stored.version != expected_version {
+                return Ok(false);
+            }
+            stored.set_status(status, self.clock.now())?;
+            Ok(true)
+        })
+    }
+
+    async fn expire_unclaimed(&self, created_before_ms: i64, scan_limit: i64) -> Result<Vec<Bet>> {
+        let now = self.clock.now();
+        let mut bets = lock(&self.bets);
+        let mut due: Vec<&mut StoredBet> = bets
+            .values_mut()
+            .filter(|s| s.unclaimed && s.bet.created_at.timestamp_millis() <= created_before_ms)
+            .collect();
+        due.sort_by_key(|s| s.bet.created_at);
+
+        let mut expired = Vec::new();
+        for stored in due.into_iter().take(scan_limit.max(0) as usize) {
+            stored.set_status(BetStatus::Expired, now)?;
+            expired.push(stored.bet.clone());
+        }
+        Ok(expired)
+    }
+
+    async fn reap_stale_claims(&self, claimed_before_ms: i64, scan_limit: i64) -> Result<Vec<Bet>> {
+        let now = self.clock.now();
+        let mut bets = lock(&self.bets);
+        let mut stale: Vec<&mut StoredBet> = bets
+            .values_mut()
+            .filter(|s| s.claimed_at.is_some_and(|at| at.timestamp_millis() <= claimed_before_ms))
+            .collect();
+        stale.sort_by_key(|s| s.claimed_at);
+
+        let mut reaped = Vec::new();
+        for stored in stale.into_iter().take(scan_limit.max(0) as usize) {
+            stored.set_status(BetStatus::FailedRetryable, now)?;
+            stored.errors.push(BetError {
+                at: now,
+                code: Some(ErrorCode::NETWORK_PROCESSOR_TIMEOUT.as_str().to_string()),
+                message: "Processor did not report on the batch before its claim went stale".to_string(),
+                processor_id: stored.bet.processor_id.clone(),
+            });
+            reaped.push(stored.bet.clone());
+        }
+        Ok(reaped)
+    }
+
+    async fn enqueue_manual_settlement(&self, bet_id: Uuid, won: bool, payout_amount: i64) -> Result<()> {
+        self.update(bet_id, |stored| {
+            stored.bet.won = Some(won);
+            stored.bet.payout_amount = Some(payout_amount);
+            stored.bet.status = BetStatus::Pending;
+            stored.unclaimed = false;
+            stored.version += 1;
+            stored.record_event("manual_settle");
+            Ok(())
+        })
+    }
+
+    async fn archive_terminal(&self, _created_before_ms: i64, _limit: i64) -> Result<usize> {
+        Ok(0)
+    }
+
+    async fn count_manual_review(&self) -> Result<u64> {
+        Ok(lock(&self.bets).values().filter(|s| s.bet.status == BetStatus::FailedManualReview).count() as u64)
+    }
+
+    async fn events(&self, bet_id: Uuid) -> Result<Vec<BetEvent>> {
+        Ok(lock(&self.bets).get(&bet_id).map(|s| s.events.clone()).unwrap_or_default())
+    }
+
+    async fn record_error(&self, bet_id: Uuid, error: &BetError) -> Result<()> {
+        self.update(bet_id, |stored| {
+            stored.errors.push(error.clone());
+            let excess = stored.errors.len().saturating_sub(ERROR_HISTORY_LIMIT);
+            stored.errors.drain(..excess);
+            Ok(())
+        })
+    }
+
+    async fn error_history(&self, bet_id: Uuid) -> Result<Vec<BetError>> {
+        Ok(lock(&self.bets).get(&bet_id).map(|s| s.errors.clone()).unwrap_or_default())
+    }
+}
+
+/// `AuditRepository` over a list
+#[derive(Default)]
+pub struct InMemoryAuditRepository {
+    entries: Mutex<Vec<AuditEntry>>,
+}
+
+impl InMemoryAuditRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AuditRepository for InMemoryAuditRepository {
+    async fn append_audit(&self, entry: &AuditEntry) -> Result<()> {
+        lock(&self.entries).push(entry.clone());
+        Ok(())
+    }
+
+    async fn audit_trail(&self, bet_id: Uuid) -> Result<Vec<AuditEntry>> {
+        Ok(lock(&self.entries).iter().filter(|entry| entry.bet_id == bet_id).cloned().collect())
+    }
+}
+
+/// `BatchRepository` over maps
+#[derive(Default)]
+pub struct InMemoryBatchRepository {
+    /// `(processor_id, version, claimed_at_ms)` per batch
+    claims: Mutex<HashMap<Uuid, (String, u64, i64)>>,
+    statuses: Mutex<HashMap<Uuid, BatchStatus>>,
+    settlements: Mutex<HashMap<Uuid, SettlementReport>>,
+}
+
+impl InMemoryBatchRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Status of the batch's latest update
+    pub fn status(&self, batch_id: Uuid) -> Option<BatchStatus> {
+        lock(&self.statuses).get(&batch_id).cloned()
+    }
+
+    /// Version the batch's next update must carry
+    pub fn version(&self, batch_id: Uuid) -> Option<u64> {
+        lock(&self.claims).get(&batch_id).map(|(_, version, _)| *version)
+    }
+
+    /// The replies `ADVANCE_SCRIPT` gives, so conflicts read the same
+    fn claim_script_reply(&self, batch_id: Uuid, processor_id: &str, version: u64, bump: bool) -> Vec<String> {
+        match lock(&self.claims).get_mut(&batch_id) {
+            None => vec!["unknown".to_string()],
+            Some((owner, _, _)) if owner != processor_id => vec!["processor".to_string(), owner.clone()],
+            Some((_, current, _)) if *current != version => vec!["version".to_string(), current.to_string()],
+            Some((_, current, claimed_at_ms)) => {
+                if bump {
+                    *current += 1;
+                }
+                vec!["ok".to_string(), current.to_string(), claimed_at_ms.to_string()]
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl BatchRepository for InMemoryBatchRepository {
+    async fn record_claim(&self, batch_id: Uuid, processor_id: &str) -> Result<u64> {
+        let claim = (processor_id.to_string(), batch_claims::INITIAL_VERSION, Utc::now().timestamp_millis());
+        lock(&self.claims).insert(batch_id, claim);
+        Ok(batch_claims::INITIAL_VERSION)
+    }
+
+    async fn check_claim(&self, batch_id: Uuid, processor_id: &str, version: u64) -> Result<ClaimCheck> {
+        let reply = self.claim_script_reply(batch_id, processor_id, version, false);
+        batch_claims::parse_check_reply(batch_id, processor_id, version, &reply)
+    }
+
+    async fn advance_claim(&self, batch_id: Uuid, processor_id: &str, version: u64) -> Result<BatchClaim> {
+        let reply = self.claim_script_reply(batch_id, processor_id, version, true);
+        batch_claims::parse_reply(batch_id, processor_id, version, &reply)
+    }
+
+    async fn save_summary(
+        &self,
+        batch_id: Uuid,
+        status: &BatchStatus,
+        _solana_tx_id: Option<&str>,
+        _error_message: Option<&str>,
+    ) -> Result<()> {
+        lock(&self.statuses).insert(batch_id, status.clone());
+        Ok(())
+    }
+
+    async fn settlement(&self, bet_id: Uuid) -> Result<Option<SettlementReport>> {
+        Ok(lock(&self.settlements).get(&bet_id).cloned())
+    }
+
+    async fn record_settlement(&self, bet_id: Uuid, report: SettlementReport) -> Result<()> {
+        let mut settlements = lock(&self.settlements);
+        let merged = settlements.remove(&bet_id).unwrap_or_default().merge(report);
+        settlements.insert(bet_id, merged);
+        Ok(())
+    }
+}
+
+/// `QueueBackend` over maps of `available_at_ms` per bet, one per Redis sorted set
+#[derive(Default)]
+pub struct InMemoryQueueBackend {
+    lanes: Mutex<QueueLanes>,
+    published: Mutex<Vec<Uuid>>,
+}
+
+#[derive(Default)]
+struct QueueLanes {
+    priority: HashMap<Uuid, i64>,
+    claimable: HashMap<Uuid, i64>,
+    processing: HashMap<Uuid, i64>,
+}
+
+/// Bets of a lane due by `now_ms`, ordered as a sorted set ranges them
+fn due_in(lane: &HashMap<Uuid, i64>, now_ms: i64) -> Vec<Uuid> {
+    let mut due: Vec<(i64, Uuid)> = lane.iter().filter(|(_, at)| **at <= now_ms).map(|(id, at)| (*at, *id)).collect();
+    due.sort();
+    due.into_iter().map(|(_, id)| id).collect()
+}
+
+impl InMemoryQueueBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bets announced with `publish_pending`, oldest first
+    pub fn published(&self) -> Vec<Uuid> {
+        lock(&self.published).clone()
+    }
+
+    /// Whether the bet is in the processing set
+    pub fn is_processing(&self, bet_id: Uuid) -> bool {
+        lock(&self.lanes).processing.contains_key(&bet_id)
+    }
+}
+
+#[async_trait]
+impl QueueBackend for InMemoryQueueBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn make_claimable(&self, bet_id: Uuid, available_at_ms: i64) -> Result<()> {
+        let mut lanes = lock(&self.lanes);
+        lanes.claimable.insert(bet_id, available_at_ms);
+        lanes.processing.remove(&bet_id);
+        Ok(())
+    }
+
+    async fn make_priority_claimable(&self, bet_id: Uuid, now_ms: i64) -> Result<()> {
+        let mut lanes = lock(&self.lanes);
+        lanes.priority.insert(bet_id, now_ms);
+        lanes.claimable.remove(&bet_id);
+        lanes.processing.remove(&bet_id);
+        Ok(())
+    }
+
+    async fn claim_due(&self, limit: i64, now_ms: i64) -> Result<Vec<Uuid>> {
+        let due = self.peek_due(0, limit, now_ms).await?;
+        self.claim_ids(&due.into_iter().map(|(id, _)| id).collect::<Vec<_>>()).await
+    }
+
+    async fn peek_due(&self, offset: i64, limit: i64, now_ms: i64) -> Result<Vec<(Uuid, bool)>> {
+        let lanes = lock(&self.lanes);
+        Ok(due_in(&lanes.priority, now_ms)
+            .into_iter()
+            .map(|id| (id, true))
+            .chain(due_in(&lanes.claimable, now_ms).into_iter().map(|id| (id, false)))
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn claim_ids(&self, bet_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        let mut lanes = lock(&self.lanes);
+        let mut claimed = Vec::new();
+        for bet_id in bet_ids {
+            let priority = lanes.priority.remove(bet_id);
+            let regular = lanes.claimable.remove(bet_id);
+            if let Some(at) = priority.or(regular) {
+                lanes.processing.insert(*bet_id, at);
+                claimed.push(*bet_id);
+            }
+        }
+        Ok(claimed)
+    }
+
+    async fn mark_processing(&self, bet_id: Uuid, now_ms: i64) -> Result<()> {
+        let mut lanes = lock(&self.lanes);
+        lanes.priority.remove(&bet_id);
+        lanes.claimable.remove(&bet_id);
+        lanes.processing.insert(bet_id, now_ms);
+        Ok(())
+    }
+
+    async fn remove(&self, bet_id: Uuid) -> Result<()> {
+        let mut lanes = lock(&self.lanes);
+        lanes.priority.remove(&bet_id);
+        lanes.claimable.remove(&bet_id);
+        lanes.processing.remove(&bet_id);
+        Ok(())
+    }
+
+    async fn claimable_depth(&self) -> Result<u64> {
+        let lanes = lock(&self.lanes);
+        Ok((lanes.priority.len() + lanes.claimable.len()) as u64)
+    }
+
+    async fn take_claimable(&self, bet_id: Uuid) -> Result<bool> {
+        Ok(lock(&self.lanes).claimable.remove(&bet_id).is_some())
+    }
+
+    async fn publish_pending(&self, bet_id: Uuid) -> Result<()> {
+        lock(&self.published).push(bet_id);
+        Ok(())
+    }
+}
+
+/// Refund records and the two Redis queues, as scores per bet
+#[derive(Default)]
+struct RefundQueues {
+    refunds: HashMap<Uuid, Refund>,
+    /// When each queued refund may next be claimed, in ms
+    queue: HashMap<Uuid, i64>,
+    /// `completed_at` ms of finished refunds
+    done: HashMap<Uuid, i64>,
+}
+
+/// Postings booked so far and the balances they moved
+#[derive(Default)]
+struct Ledger {
+    booked: HashSet<(String, PostingKind)>,
+    balances: HashMap<String, i64>,
+}
+
+/// Settlements counted against the SLO
+#[derive(Default)]
+struct SloCounts {
+    minutes: HashMap<i64, Counts>,
+    hours: HashMap<i64, Counts>,
+    /// Newest first
+    latencies: Vec<i64>,
+}
+
+/// `OpsRepository` over maps
+///
+/// Mirrors what the Redis modules store and the checks their scripts make:
+/// session limits, one refund and one dispute per bet, idempotent ledger
+/// postings, single-use token signatures and notification dedupe. Nothing
+/// expires except wallet tokens.
+#[derive(Default)]
+pub struct InMemoryOpsRepository {
+    kill_switch: Mutex<Option<KillSwitchState>>,
+    retry_trip: Mutex<Option<RetryBudgetTrip>>,
+    /// Holds by settlement, and the pending index in hold order
+    holds: Mutex<(HashMap<u64, PayoutHold>, Vec<u64>)>,
+    sessions: Mutex<HashMap<Uuid, Session>>,
+    session_usage: Mutex<HashMap<Uuid, SessionUsage>>,
+    refunds: Mutex<RefundQueues>,
+    disputes: Mutex<HashMap<Uuid, Dispute>>,
+    ledger: Mutex<Ledger>,
+    last_ledger_check: Mutex<Option<InvariantReport>>,
+    token_signatures: Mutex<HashSet<String>>,
+    /// Wallet and expiry per token
+    tokens: Mutex<HashMap<String, (String, DateTime<Utc>)>>,
+    preferences: Mutex<HashMap<String, NotificationPreferences>>,
+    published: Mutex<Vec<Notification>>,
+    dedupe_keys: Mutex<HashSet<String>>,
+    signals: Mutex<Vec<ClaimableEvent>>,
+    processors: Mutex<HashMap<String, HashMap<String, i64>>>,
+    slo: Mutex<SloCounts>,
+    pipeline: Mutex<HashMap<&'static str, Vec<i64>>>,
+    daily: Mutex<HashMap<NaiveDate, HashMap<String, i64>>>,
+    seeds: Mutex<BTreeMap<NaiveDate, ServerSeed>>,
+    jurisdiction_audit: Mutex<Vec<serde_json::Value>>,
+}
+
+impl InMemoryOpsRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold a payout for review, as a processor would
+    pub fn hold_payout(&self, hold: PayoutHold) {
+        let mut holds = lock(&self.holds);
+        holds.1.push(hold.transaction_id);
+        holds.0.insert(hold.transaction_id, hold);
+    }
+
+    /// Trip the retry budget, as a processor would
+    pub fn trip_retry_budget(&self, trip: RetryBudgetTrip) {
+        *lock(&self.retry_trip) = Some(trip);
+    }
+
+    /// Store a ledger check, as the checker would
+    pub fn set_last_ledger_check(&self, report: InvariantReport) {
+        *lock(&self.last_ledger_check) = Some(report);
+    }
+
+    /// Add dwell samples to a pipeline stage, as status changes would
+    pub fn record_pipeline(&self, stage: Stage, dwells_ms: &[i64]) {
+        lock(&self.pipeline).entry(stage.as_str()).or_default().extend_from_slice(dwells_ms);
+    }
+
+    /// Balance of a ledger account; 0 when nothing was posted to it
+    pub fn balance(&self, account: &str) -> i64 {
+        lock(&self.ledger).balances.get(account).copied().unwrap_or(0)
+    }
+
+    /// Notifications queued for delivery, oldest first
+    pub fn published(&self) -> Vec<Notification> {
+        lock(&self.published).clone()
+    }
+
+    /// Claim signals sent, oldest first
+    pub fn signals(&self) -> Vec<ClaimableEvent> {
+        lock(&self.signals).clone()
+    }
+
+    /// Refused bet attempts audited, oldest first
+    pub fn jurisdiction_audit(&self) -> Vec<serde_json::Value> {
+        lock(&self.jurisdiction_audit).clone()
+    }
+
+    fn bump_processor(&self, processor_id: &str, increments: &[(&'static str, i64)], now_ms: i64) {
+        let mut processors = lock(&self.processors);
+        let counters = processors.entry(processor_id.to_string()).or_default();
+        for (field, by) in increments {
+            *counters.entry(field.to_string()).or_default() += by;
+        }
+        counters.insert("last_seen_ms".to_string(), now_ms);
+    }
+}
+
+fn count(buckets: &mut HashMap<i64, Counts>, bucket: i64, good: bool) {
+    let counts = buckets.entry(bucket).or_default();
+    counts.total += 1;
+    if !good {
+        counts.bad += 1;
+    }
+}
+
+#[async_trait]
+impl OpsRepository for InMemoryOpsRepository {
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn kill_switch(&self) -> Result<Option<KillSwitchState>> {
+        Ok(lock(&self.kill_switch).clone())
+    }
+
+    async fn is_halted(&self) -> Result<bool> {
+        Ok(lock(&self.kill_switch).is_some())
+    }
+
+    async fn engage_kill_switch(&self, state: &KillSwitchState) -> Result<()> {
+        *lock(&self.kill_switch) = Some(state.clone());
+        Ok(())
+    }
+
+    async fn release_kill_switch(&self) -> Result<()> {
+        *lock(&self.kill_switch) = None;
+        Ok(())
+    }
+
+    async fn retry_budget_trip(&self) -> Result<Option<RetryBudgetTrip>> {
+        Ok(lock(&self.retry_trip).clone())
+    }
+
+    async fn acknowledge_retry_budget(&self) -> Result<Option<RetryBudgetTrip>> {
+        Ok(lock(&self.retry_trip).take())
+    }
+
+    async fn payout_hold(&self, transaction_id: u64) -> Result<Option<PayoutHold>> {
+        Ok(lock(&self.holds).0.get(&transaction_id).cloned())
+    }
+
+    async fn save_payout_hold(&self, hold: &PayoutHold) -> Result<()> {
+        lock(&self.holds).0.insert(hold.transaction_id, hold.clone());
+        Ok(())
+    }
+
+    async fn pending_payout_holds(&self, limit: isize) -> Result<Vec<PayoutHold>> {
+        let holds = lock(&self.holds);
+        Ok(holds.1.iter().take(limit.max(1) as usize).filter_map(|id| holds.0.get(id).cloned()).collect())
+    }
+
+    async fn create_session(&self, session: &Session) -> Result<()> {
+        lock(&self.sessions).insert(session.session_id, session.clone());
+        Ok(())
+    }
+
+    async fn session(&self, session_id: Uuid) -> Result<Option<Session>> {
+        Ok(lock(&self.sessions).get(&session_id).cloned())
+    }
+
+    async fn session_usage(&self, session_id: Uuid) -> Result<SessionUsage> {
+        Ok(lock(&self.session_usage).get(&session_id).copied().unwrap_or_default())
+    }
+
+    async fn reserve_session(&self, session: &Session, stake: u64) -> Result<()> {
+        let mut usage = lock(&self.session_usage);
+        let current = usage.entry(session.session_id).or_default();
+        if let Some(e) = sessions::exceeded_limit(session, *current, stake) {
+            return Err(e);
+        }
+        current.rounds += 1;
+        current.staked += stake;
+        Ok(())
+    }
+
+    async fn release_session(&self, session_id: Uuid, stake: u64) -> Result<()> {
+        let mut usage = lock(&self.session_usage);
+        let current = usage.entry(session_id).or_default();
+        current.rounds = current.rounds.saturating_sub(1);
+        current.staked = current.staked.saturating_sub(stake);
+        Ok(())
+    }
+
+    async fn request_refund(&self, refund: &Refund) -> Result<()> {
+        let mut queues = lock(&self.refunds);
+        if queues.refunds.contains_key(&refund.bet_id) {
+            return Err(AppError::invalid_status(format!("Bet {} has already been voided", refund.bet_id)));
+        }
+        queues.refunds.insert(refund.bet_id, refund.clone());
+        queues.queue.insert(refund.bet_id, refund.requested_at.timestamp_millis());
+        Ok(())
+    }
+
+    async fn claim_refunds(&self, limit: i64, now_ms: i64) -> Result<Vec<Refund>> {
+        let mut queues = lock(&self.refunds);
+        let mut due: Vec<(i64, Uuid)> = queues
+            .queue
+            .iter()
+            .filter(|(_, due_ms)| **due_ms <= now_ms)
+            .map(|(bet_id, due_ms)| (*due_ms, *bet_id))
+            .collect();
+        due.sort();
+        due.truncate(limit.max(0) as usize);
+        let mut claimed = Vec::with_capacity(due.len());
+        for (_, bet_id) in due {
+            queues.queue.insert(bet_id, now_ms + refunds::REFUND_LEASE_MS);
+            claimed.extend(queues.refunds.get(&bet_id).cloned());
+        }
+        Ok(claimed)
+    }
+
+    async fn record_refund_result(&self, bet_id: Uuid, result: &RefundResult) -> Result<(Refund, bool)> {
+        let mut queues = lock(&self.refunds);
+        let refund = queues
+            .refunds
+            .get_mut(&bet_id)
+            .ok_or_else(|| AppError::not_found(format!("No refund for bet {}", bet_id)))?;
+        if refund.status != RefundStatus::Pending {
+            return Ok((refund.clone(), false));
+        }
+
+        let now = Utc::now();
+        let finished = refund.apply(result, now);
+        let refund = refund.clone();
+        if finished {
+            queues.queue.remove(&bet_id);
+            queues.done.insert(bet_id, now.timestamp_millis());
+        } else {
+            queues.queue.insert(bet_id, now.timestamp_millis() + refunds::REFUND_RETRY_DELAY_MS);
+        }
+        Ok((refund, finished))
+    }
+
+    async fn refunds(&self, pending: bool, limit: isize) -> Result<Vec<Refund>> {
+        let queues = lock(&self.refunds);
+        let mut ids: Vec<(i64, Uuid)> = if pending {
+            queues.queue.iter().map(|(bet_id, score)| (*score, *bet_id)).collect()
+        } else {
+            queues.done.iter().map(|(bet_id, score)| (-*score, *bet_id)).collect()
+        };
+        ids.sort();
+        Ok(ids
+            .into_iter()
+            .take(limit.max(1) as usize)
+            .filter_map(|(_, bet_id)| queues.refunds.get(&bet_id).cloned())
+            .collect())
+    }
+
+    async fn dispute(&self, bet_id: Uuid) -> Result<Option<Dispute>> {
+        Ok(lock(&self.disputes).get(&bet_id).cloned())
+    }
+
+    async fn open_dispute(&self, dispute: &Dispute) -> Result<bool> {
+        let mut disputes = lock(&self.disputes);
+        if disputes.contains_key(&dispute.bet_id) {
+            return Ok(false);
+        }
+        disputes.insert(dispute.bet_id, dispute.clone());
+        Ok(true)
+    }
+
+    async fn save_resolved_dispute(&self, dispute: &Dispute) -> Result<()> {
+        lock(&self.disputes).insert(dispute.bet_id, dispute.clone());
+        Ok(())
+    }
+
+    async fn disputes(&self, open: bool, limit: isize) -> Result<Vec<Dispute>> {
+        let mut disputes: Vec<Dispute> =
+            lock(&self.disputes).values().filter(|dispute| dispute.is_open() == open).cloned().collect();
+        if open {
+            disputes.sort_by_key(|dispute| dispute.created_at);
+        } else {
+            disputes.sort_by_key(|dispute| std::cmp::Reverse(dispute.resolved_at));
+        }
+        disputes.truncate(limit.max(1) as usize);
+        Ok(disputes)
+    }
+
+    async fn post_ledger(&self, postings: &[Posting]) -> Result<usize> {
+        let mut ledger = lock(&self.ledger);
+        let mut booked = 0;
+        for posting in postings.iter().filter(|posting| posting.amount > 0) {
+            if !ledger.booked.insert((posting.reference.clone(), posting.kind)) {
+                continue;
+            }
+            let debit = match &posting.fallback {
+                Some((kind, debit)) if ledger.booked.contains(&(posting.reference.clone(), *kind)) => debit,
+                _ => &posting.debit,
+            };
+            *ledger.balances.entry(debit.clone()).or_default() -= posting.amount;
+            *ledger.balances.entry(posting.credit.clone()).or_default() += posting.amount;
+            booked += 1;
+        }
+        Ok(booked)
+    }
+
+    async fn post_ledger_or_warn(&self, postings: &[Posting]) {
+        let _ = self.post_ledger(postings).await;
+    }
+
+    async fn last_ledger_check(&self) -> Result<Option<InvariantReport>> {
+        Ok(lock(&self.last_ledger_check).clone())
+    }
+
+    async fn issue_token(
+        &self,
+        req: &WalletTokenRequest,
+        ttl_seconds: u64,
+        _signature_max_age_seconds: i64,
+    ) -> Result<(String, DateTime<Utc>)> {
+        if !lock(&self.token_signatures).insert(req.signature.clone()) {
+            return Err(AppError::unauthorized("Signature has already been used"));
+        }
+        let token = auth::new_token();
+        let expires_at = Utc::now() + chrono::Duration::seconds(ttl_seconds as i64);
+        lock(&self.tokens).insert(token.clone(), (req.wallet.clone(), expires_at));
+        Ok((token, expires_at))
+    }
+
+    async fn token_wallet(&self, token: &str) -> Result<Option<String>> {
+        Ok(lock(&self.tokens)
+            .get(token)
+            .filter(|(_, expires_at)| *expires_at > Utc::now())
+            .map(|(wallet, _)| wallet.clone()))
+    }
+
+    async fn notification_preferences(&self, wallet: &str) -> Result<NotificationPreferences> {
+        Ok(lock(&self.preferences).get(wallet).cloned().unwrap_or_default())
+    }
+
+    async fn save_notification_preferences(&self, wallet: &str, preferences: &NotificationPreferences) -> Result<()> {
+        lock(&self.preferences).insert(wallet.to_string(), preferences.clone());
+        Ok(())
+    }
+
+    async fn publish_notifications(&self, notifications: &[Notification]) {
+        for notification in notifications {
+            if let Some(key) = notification.dedupe_key() {
+                if !lock(&self.dedupe_keys).insert(key) {
+                    continue;
+                }
+            }
+            lock(&self.published).push(notification.clone());
+        }
+    }
+
+    async fn signal_claimable(&self, event: ClaimableEvent) {
+        lock(&self.signals).push(event);
+    }
+
+    async fn record_processor_claim(&self, processor_id: &str, claimed: usize) -> Result<()> {
+        self.bump_processor(processor_id, &processor_stats::claim_increments(claimed), Utc::now().timestamp_millis());
+        Ok(())
+    }
+
+    async fn record_batch_outcomes(&self, outcomes: &BatchOutcomes, claim: &BatchClaim) -> Result<()> {
+        let now_ms = Utc::now().timestamp_millis();
+        self.bump_processor(&claim.processor_id, &outcomes.increments(claim, now_ms), now_ms);
+        Ok(())
+    }
+
+    async fn processor_stats(&self, limit: isize) -> Result<Vec<ProcessorStats>> {
+        let mut stats: Vec<ProcessorStats> = lock(&self.processors)
+            .iter()
+            .map(|(processor_id, counters)| ProcessorStats::from_counters(processor_id.clone(), counters))
+            .collect();
+        stats.sort_by_key(|stats| std::cmp::Reverse(stats.last_seen));
+        stats.truncate(limit.max(1) as usize);
+        Ok(stats)
+    }
+
+    async fn record_slo(&self, objective: &SloConfig, created_at: DateTime<Utc>, status: &BetStatus) {
+        let now_ms = Utc::now().timestamp_millis();
+        let latency_ms = (now_ms - created_at.timestamp_millis()).max(0);
+        let Some(good) = slo::is_good(status, latency_ms, objective) else {
+            return;
+        };
+        let mut slo = lock(&self.slo);
+        count(&mut slo.minutes, now_ms.div_euclid(60_000), good);
+        count(&mut slo.hours, now_ms.div_euclid(3_600_000), good);
+        if *status == BetStatus::Completed {
+            slo.latencies.insert(0, latency_ms);
+        }
+    }
+
+    async fn slo_summary(&self, objective: &SloConfig) -> Result<SloReport> {
+        let now_ms = Utc::now().timestamp_millis();
+        let slo = lock(&self.slo);
+        let minute = now_ms.div_euclid(60_000);
+        let minutes: Vec<Counts> = (0..slo::summary_minutes() as i64)
+            .map(|i| slo.minutes.get(&(minute - i)).copied().unwrap_or_default())
+            .collect();
+        let hour = now_ms.div_euclid(3_600_000);
+        let period = (0..objective.period_days as i64 * 24)
+            .filter_map(|i| slo.hours.get(&(hour - i)))
+            .fold(Counts::default(), |sum, counts| Counts { total: sum.total + counts.total, bad: sum.bad + counts.bad });
+        Ok(SloReport::new(objective, &minutes, period, slo.latencies.clone()))
+    }
+
+    async fn pipeline_latency(&self) -> Result<PipelineLatencyReport> {
+        let pipeline = lock(&self.pipeline);
+        Ok(PipelineLatencyReport::new(
+            Stage::ALL.iter().map(|stage| pipeline.get(stage.as_str()).cloned().unwrap_or_default()).collect(),
+        ))
+    }
+
+    async fn record_daily_tally(&self, tally: &DailyTally, date: NaiveDate) -> Result<()> {
+        let mut daily = lock(&self.daily);
+        let counters = daily.entry(date).or_default();
+        for (field, by) in tally.increments() {
+            *counters.entry(field).or_default() += by;
+        }
+        Ok(())
+    }
+
+    async fn daily_report(&self, bets: &dyn BetRepository, date: NaiveDate) -> Result<DailyReport> {
+        let counters = lock(&self.daily).get(&date).cloned().unwrap_or_default();
+        let outstanding = bets.count_manual_review().await?;
+        Ok(DailyReport::from_counters(date, &counters, outstanding, false))
+    }
+
+    async fn fairness_commitment(&self, day: NaiveDate) -> Result<String> {
+        Ok(lock(&self.seeds).entry(day).or_insert_with(ServerSeed::generate).commitment())
+    }
+
+    async fn fairness_seed(&self, commitment: &str) -> Result<Option<ServerSeed>> {
+        Ok(lock(&self.seeds).values().find(|seed| seed.commitment() == commitment).cloned())
+    }
+
+    async fn fairness_seeds(&self, today: NaiveDate, limit: usize) -> Result<Vec<DailySeed>> {
+        Ok(lock(&self.seeds)
+            .iter()
+            .rev()
+            .take(limit)
+            .map(|(day, seed)| DailySeed {
+                day: *day,
+                server_seed_hash: seed.commitment(),
+                server_seed: (*day < today).then(|| seed.to_hex()),
+            })
+            .collect())
+    }
+
+    async fn audit_jurisdiction_block(&self, entry: &serde_json::Value) -> Result<()> {
+        lock(&self.jurisdiction_audit).push(entry.clone());
+        Ok(())
+    }
+}
+
+/// One set of in-memory repositories, kept typed so tests can seed and inspect them
+#[derive(Clone)]
+pub struct InMemoryRepositories {
+    pub bets: Arc<InMemoryBetRepository>,
+    pub batches: Arc<InMemoryBatchRepository>,
+    pub audit: Arc<InMemoryAuditRepository>,
+    pub ops: Arc<InMemoryOpsRepository>,
+    pub queue: Arc<InMemoryQueueBackend>,
+}
+
+impl InMemoryRepositories {
+    /// Empty repositories indexing the metadata keys `config` names
+    pub fn new(config: &Config) -> Self {
+        Self {
+            bets: Arc::new(InMemoryBetRepository::new().with_indexed_metadata_keys(config.metadata.indexed_keys.clone())),
+            batches: Arc::new(InMemoryBatchRepository::new()),
+            audit: Arc::new(InMemoryAuditRepository::new()),
+            ops: Arc::new(InMemoryOpsRepository::new()),
+            queue: Arc::new(InMemoryQueueBackend::new()),
+        }
+    }
+
+    /// Handler state backed by these repositories
+    pub fn state(&self, config: Config) -> Repositories {
+        Repositories {
+            config: Arc::new(config),
+            bets: self.bets.clone(),
+            batches: self.batches.clone(),
+            audit: self.audit.clone(),
+        }
+    }
+
+    /// Full application state backed by these repositories
+    ///
+    /// Solana calls go to a mock RPC client that reports success and the
+    /// indexer is off, so only handlers that read accounts need more setup.
+    pub fn app_state(&self, config: Config) -> AppState {
+        let storage = Storage {
+            queue: self.queue.clone(),
+            bets: self.bets.clone(),
+            batches: self.batches.clone(),
+            audit: self.audit.clone(),
+            ops: self.ops.clone(),
+            archive: None,
+        };
+        let receipts = ReceiptSigner::from_config(&config.receipts).expect("receipt signer from test config");
+        let jurisdiction = JurisdictionGate::from_config(&config.jurisdiction).expect("jurisdiction gate from test config");
+        let mut state = AppState::new(config, storage, receipts, jurisdiction);
+        state.solana = Arc::new(RpcClient::new_mock("succeeds".to_string()));
+        state.indexer = None;
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_batch_claims_advance_like_redis() {
+        let batches = InMemoryBatchRepository::new();
+        let batch_id = Uuid::new_v4();
+        assert!(batches.advance_claim(batch_id, "p1", 1).await.is_err());
+
+        assert_eq!(batches.record_claim(batch_id, "p1").await.unwrap(), 1);
+        assert!(batches.advance_claim(batch_id, "p2", 1).await.is_err());
+        // Checking leaves the version for the update's own bump
+        assert!(matches!(batches.check_claim(batch_id, "p1", 1).await.unwrap(), ClaimCheck::Current(BatchClaim { version: 1, .. })));
+        assert!(batches.check_claim(batch_id, "p2", 1).await.is_err());
+        assert_eq!(batches.advance_claim(batch_id, "p1", 1).await.unwrap().version, 2);
+        // A second report for the same claim can't bump again, and is checked as a resend
+        assert!(batches.advance_claim(batch_id, "p1", 1).await.is_err());
+        assert!(matches!(batches.check_claim(batch_id, "p1", 1).await.unwrap(), ClaimCheck::Stale(BatchClaim { version: 2, .. })));
+        assert!(batches.check_claim(batch_id, "p1", 3).await.is_err());
+    }
+}
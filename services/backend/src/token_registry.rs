@@ -0,0 +1,166 @@
+//! Stake tokens and their bet limits
+//!
+//! The vault program checks each stake against the casino's `TokenConfig`
+//! account for its mint, which the casino authority manages with
+//! `set_token_config`. The registry lists those accounts for clients. They only
+//! change through admin transactions, so the list is cached briefly rather than
+//! read from the chain on every request. Native SOL without a `TokenConfig`
+//! falls back to the program's built-in limits, mirrored by `MIN_BET_LAMPORTS`
+//! and `MAX_BET_LAMPORTS`.
+
+use moka::future::Cache;
+use shared::vault::{account_discriminator, casino_pda, TokenConfigAccount};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{pubkey::Pubkey, system_program};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::BettingConfig;
+use crate::domain::{TokenInfo, TokenLimitSource};
+use crate::errors::{AppError, Result};
+
+#[derive(Clone)]
+pub struct TokenRegistry {
+    inner: Cache<(), Arc<Vec<TokenInfo>>>,
+}
+
+impl TokenRegistry {
+    pub fn new(ttl_seconds: u64) -> Self {
+        Self {
+            inner: Cache::builder()
+                .max_capacity(1)
+                .time_to_live(Duration::from_secs(ttl_seconds))
+                .build(),
+        }
+    }
+
+    /// Every configured token, native SOL first
+    pub async fn tokens(
+        &self,
+        rpc: &RpcClient,
+        program_id: &Pubkey,
+        betting: &BettingConfig,
+    ) -> Result<Arc<Vec<TokenInfo>>> {
+        if let Some(tokens) = self.inner.get(&()).await {
+            return Ok(tokens);
+        }
+
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                0,
+                &account_discriminator("TokenConfig"),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(rpc.commitment()),
+                ..Default::default()
+            },
+            with_context: None,
+        };
+        let accounts = rpc
+            .get_program_accounts_with_config(program_id, config)
+            .await
+            .map_err(AppError::rpc_unavailable)?;
+
+        let configs = accounts
+            .iter()
+            .filter_map(|(address, account)| match TokenConfigAccount::decode(&account.data) {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    tracing::warn!(%address, error = %e, "Skipping undecodable TokenConfig account");
+                    None
+                }
+            })
+            .collect();
+        let tokens = Arc::new(registry_entries(&casino_pda(program_id), configs, betting));
+        self.inner.insert((), tokens.clone()).await;
+        Ok(tokens)
+    }
+}
+
+/// The casino's token configs as registry entries, adding native SOL's defaults
+/// when it has no config of its own
+fn registry_entries(casino: &Pubkey, configs: Vec<TokenConfigAccount>, betting: &BettingConfig) -> Vec<TokenInfo> {
+    let mut tokens: Vec<TokenInfo> = configs
+        .into_iter()
+        .filter(|config| config.casino == *casino)
+        .map(|config| TokenInfo {
+            stake_token: if config.is_native_sol() { "SOL".to_string() } else { config.mint.to_string() },
+            mint: config.mint.to_string(),
+            min_bet: config.min_bet,
+            max_bet: config.max_bet,
+            bet_enabled: config.bet_enabled,
+            source: TokenLimitSource::OnChain,
+            updated_at: Some(config.updated_at),
+        })
+        .collect();
+
+    if !tokens.iter().any(|token| token.stake_token == "SOL") {
+        tokens.push(TokenInfo {
+            stake_token: "SOL".to_string(),
+            mint: system_program::ID.to_string(),
+            min_bet: betting.min_bet_lamports,
+            max_bet: betting.max_bet_lamports,
+            bet_enabled: true,
+            source: TokenLimitSource::Default,
+            updated_at: None,
+        });
+    }
+    tokens.sort_by(|a, b| (a.stake_token != "SOL", &a.mint).cmp(&(b.stake_token != "SOL", &b.mint)));
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(casino: Pubkey, mint: Pubkey, min_bet: u64, max_bet: u64) -> TokenConfigAccount {
+        TokenConfigAccount {
+            casino,
+            mint,
+            min_bet,
+            max_bet,
+            bet_enabled: true,
+            updated_at: 1_700_000_000,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_registry_entries() {
+        let betting = BettingConfig {
+            min_bet_lamports: 100_000_000,
+            max_bet_lamports: 1_000_000_000_000,
+            bet_ttl_seconds: 0,
+            bet_expiry_sweep_interval_seconds: 60,
+            stale_claim_seconds: 0,
+            stale_claim_sweep_interval_seconds: 60,
+            allowance_warning_remaining_lamports: 0,
+            allowance_warning_expiry_seconds: 0,
+            session_max_rounds: 1000,
+            intake_max_queue_depth: 0,
+            intake_retry_after_seconds: 5,
+        };
+        let casino = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+
+        // A 6-decimals token gets its own limits; SOL keeps the defaults
+        let tokens = registry_entries(
+            &casino,
+            vec![config(casino, usdc, 10_000, 5_000_000_000), config(Pubkey::new_unique(), usdc, 1, 2)],
+            &betting,
+        );
+        assert_eq!(tokens.len(), 2);
+        assert_eq!((tokens[0].stake_token.as_str(), tokens[0].source), ("SOL", TokenLimitSource::Default));
+        assert_eq!(tokens[0].min_bet, 100_000_000);
+        assert_eq!(tokens[1].stake_token, usdc.to_string());
+        assert_eq!((tokens[1].min_bet, tokens[1].source), (10_000, TokenLimitSource::OnChain));
+
+        let tokens = registry_entries(&casino, vec![config(casino, system_program::ID, 1_000, 2_000)], &betting);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!((tokens[0].stake_token.as_str(), tokens[0].min_bet), ("SOL", 1_000));
+    }
+}
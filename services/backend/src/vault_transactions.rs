@@ -0,0 +1,308 @@
+//! Unsigned vault program transactions for wallets to sign
+//!
+//! The backend never holds user keys: it builds the instruction with the right
+//! PDAs and a recent blockhash, hands the serialized transaction to the wallet,
+//! and relays whatever the user signs back.
+
+use anyhow::{bail, Context};
+use base64::Engine;
+use shared::program_ids::{spl_ata_program_id, spl_token_program_id};
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    system_program,
+    transaction::Transaction,
+};
+
+pub use shared::vault::{
+    allowance_nonce_registry_pda, allowance_pda, anchor_discriminator, build_set_paused_instruction, casino_pda,
+    rate_limiter_pda, user_vault_pda,
+};
+
+/// Parse `next_nonce` from nonce registry account data
+pub fn parse_next_nonce(data: &[u8]) -> anyhow::Result<u64> {
+    // Layout: discriminator (8) | user (32) | casino (32) | next_nonce (8) | bump (1)
+    let offset = 8 + 32 + 32;
+    let bytes = data
+        .get(offset..offset + 8)
+        .with_context(|| format!("Nonce registry data too short: {} bytes", data.len()))?;
+    Ok(u64::from_le_bytes(bytes.try_into().expect("slice is 8 bytes")))
+}
+
+/// Associated token account for `owner` and `mint`
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), spl_token_program_id().as_ref(), mint.as_ref()],
+        &spl_ata_program_id(),
+    )
+    .0
+}
+
+/// Build withdraw_sol instruction
+pub fn build_withdraw_sol_instruction(program_id: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+    let casino = casino_pda(program_id);
+    let vault = user_vault_pda(user, &casino, program_id);
+
+    let mut data = anchor_discriminator("withdraw_sol").to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Build withdraw_spl instruction; token accounts are the vault's and user's ATAs
+pub fn build_withdraw_spl_instruction(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let casino = casino_pda(program_id);
+    let vault = user_vault_pda(user, &casino, program_id);
+
+    let mut data = anchor_discriminator("withdraw_spl").to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(associated_token_address(&vault, mint), false),
+            AccountMeta::new(associated_token_address(user, mint), false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(spl_token_program_id(), false),
+        ],
+        data,
+    }
+}
+
+/// Build set_payout_address instruction; `None` sends winnings back to the vault.
+/// The owner pays rent for the 32 bytes the vault grows by on first use.
+pub fn build_set_payout_address_instruction(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    payout_address: Option<&Pubkey>,
+) -> Instruction {
+    let casino = casino_pda(program_id);
+    let vault = user_vault_pda(owner, &casino, program_id);
+
+    // Borsh Option<Pubkey>
+    let mut data = anchor_discriminator("set_payout_address").to_vec();
+    match payout_address {
+        Some(address) => {
+            data.push(1);
+            data.extend_from_slice(address.as_ref());
+        }
+        None => data.push(0),
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Build approve_allowance_v2 instruction; returns it with the allowance PDA it creates
+pub fn build_approve_allowance_v2_instruction(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    amount: u64,
+    duration_seconds: i64,
+    token_mint: &Pubkey,
+    nonce: u64,
+) -> (Instruction, Pubkey) {
+    let data = approve_allowance_data("approve_allowance_v2", amount, duration_seconds, token_mint, nonce);
+    approve_allowance_instruction(program_id, user, nonce, data)
+}
+
+/// Build approve_allowance_v3 instruction, v2 plus spend velocity limits (0 = unlimited)
+#[allow(clippy::too_many_arguments)]
+pub fn build_approve_allowance_v3_instruction(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    amount: u64,
+    duration_seconds: i64,
+    token_mint: &Pubkey,
+    nonce: u64,
+    max_single_spend: u64,
+    max_spend_per_hour: u64,
+) -> (Instruction, Pubkey) {
+    let mut data = approve_allowance_data("approve_allowance_v3", amount, duration_seconds, token_mint, nonce);
+    data.extend_from_slice(&max_single_spend.to_le_bytes());
+    data.extend_from_slice(&max_spend_per_hour.to_le_bytes());
+    approve_allowance_instruction(program_id, user, nonce, data)
+}
+
+fn approve_allowance_data(name: &str, amount: u64, duration_seconds: i64, token_mint: &Pubkey, nonce: u64) -> Vec<u8> {
+    let mut data = anchor_discriminator(name).to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&duration_seconds.to_le_bytes());
+    data.extend_from_slice(token_mint.as_ref());
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data
+}
+
+/// v2 and v3 share the `ApproveAllowanceV2` accounts
+fn approve_allowance_instruction(program_id: &Pubkey, user: &Pubkey, nonce: u64, data: Vec<u8>) -> (Instruction, Pubkey) {
+    let casino = casino_pda(program_id);
+    let vault = user_vault_pda(user, &casino, program_id);
+    let allowance = allowance_pda(user, &casino, nonce, program_id);
+
+    let instruction = Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(allowance_nonce_registry_pda(user, &casino, program_id), false),
+            AccountMeta::new(allowance, false),
+            AccountMeta::new(rate_limiter_pda(user, program_id), false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    };
+
+    (instruction, allowance)
+}
+
+/// Serialize an unsigned transaction paid for by `payer` as base64
+pub fn encode_unsigned_transaction(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    recent_blockhash: Hash,
+) -> anyhow::Result<String> {
+    let message = Message::new_with_blockhash(instructions, Some(payer), &recent_blockhash);
+    let transaction = Transaction::new_unsigned(message);
+    let bytes = bincode::serialize(&transaction).context("Failed to serialize transaction")?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+/// Decode a base64 signed transaction and check it is safe to relay
+///
+/// The transaction must be fully signed, paid for by `payer`, and only call
+/// `program_id` with one of `allowed` instruction names, so the relay cannot be
+/// used to push arbitrary transactions.
+pub fn decode_signed_transaction(
+    encoded: &str,
+    payer: &Pubkey,
+    program_id: &Pubkey,
+    allowed: &[&str],
+) -> anyhow::Result<Transaction> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .context("Transaction is not valid base64")?;
+    let transaction: Transaction =
+        bincode::deserialize(&bytes).context("Transaction could not be deserialized")?;
+
+    let keys = &transaction.message.account_keys;
+    if keys.first() != Some(payer) {
+        bail!("Transaction fee payer must be {}", payer);
+    }
+
+    let discriminators: Vec<[u8; 8]> = allowed.iter().map(|name| anchor_discriminator(name)).collect();
+    for ix in &transaction.message.instructions {
+        if keys.get(ix.program_id_index as usize) != Some(program_id) {
+            bail!("Transaction may only call the vault program");
+        }
+        if ix.data.len() < 8 || !discriminators.iter().any(|d| ix.data[..8] == d[..]) {
+            bail!("Transaction contains a disallowed vault instruction");
+        }
+    }
+
+    transaction
+        .verify()
+        .context("Transaction signatures are missing or invalid")?;
+
+    Ok(transaction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[test]
+    fn test_parse_next_nonce() {
+        let mut data = vec![0u8; 81];
+        data[72..80].copy_from_slice(&7u64.to_le_bytes());
+        assert_eq!(parse_next_nonce(&data).unwrap(), 7);
+        assert!(parse_next_nonce(&data[..50]).is_err());
+    }
+
+    #[test]
+    fn test_approve_allowance_v2_layout() {
+        let program_id = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let (ix, allowance) =
+            build_approve_allowance_v2_instruction(&program_id, &user, 5_000, 3_600, &system_program::ID, 3);
+
+        assert_eq!(ix.data.len(), 8 + 8 + 8 + 32 + 8);
+        assert_eq!(ix.accounts[3].pubkey, allowance);
+        assert_eq!(allowance, allowance_pda(&user, &casino_pda(&program_id), 3, &program_id));
+
+        let (v3, v3_allowance) = build_approve_allowance_v3_instruction(
+            &program_id, &user, 5_000, 3_600, &system_program::ID, 3, 100, 1_000,
+        );
+        assert_eq!(v3.data.len(), ix.data.len() + 16);
+        assert_eq!(v3.data[8..ix.data.len()], ix.data[8..]);
+        assert_eq!(v3.accounts, ix.accounts);
+        assert_eq!(v3_allowance, allowance);
+    }
+
+    #[test]
+    fn test_set_payout_address_layout() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let payout_address = Pubkey::new_unique();
+
+        let ix = build_set_payout_address_instruction(&program_id, &owner, Some(&payout_address));
+        assert_eq!(ix.data[..8], anchor_discriminator("set_payout_address"));
+        assert_eq!(ix.data[8], 1);
+        assert_eq!(ix.data[9..], payout_address.to_bytes());
+        assert_eq!(ix.accounts[0].pubkey, user_vault_pda(&owner, &casino_pda(&program_id), &program_id));
+        assert!(ix.accounts[2].is_signer && ix.accounts[2].is_writable);
+
+        let cleared = build_set_payout_address_instruction(&program_id, &owner, None);
+        assert_eq!(cleared.data.len(), 9);
+        assert_eq!(cleared.data[8], 0);
+        assert_eq!(cleared.accounts, ix.accounts);
+    }
+
+    #[test]
+    fn test_withdraw_round_trip() {
+        let program_id = Pubkey::new_unique();
+        let user = Keypair::new();
+        let ix = build_withdraw_sol_instruction(&program_id, &user.pubkey(), 1_000);
+        let blockhash = Hash::new_unique();
+
+        let encoded = encode_unsigned_transaction(&[ix], &user.pubkey(), blockhash).unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD.decode(&encoded).unwrap();
+        let mut tx: Transaction = bincode::deserialize(&bytes).unwrap();
+
+        // Unsigned transactions are rejected
+        assert!(decode_signed_transaction(&encoded, &user.pubkey(), &program_id, &["withdraw_sol"]).is_err());
+
+        tx.sign(&[&user], blockhash);
+        let signed = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&tx).unwrap());
+        assert!(decode_signed_transaction(&signed, &user.pubkey(), &program_id, &["withdraw_sol"]).is_ok());
+        assert!(decode_signed_transaction(&signed, &user.pubkey(), &program_id, &["withdraw_spl"]).is_err());
+        assert!(decode_signed_transaction(&signed, &Pubkey::new_unique(), &program_id, &["withdraw_sol"]).is_err());
+    }
+}
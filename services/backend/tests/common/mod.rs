@@ -18,9 +18,6 @@ impl TestContext {
         let redis_client = RedisClient::open(redis_url.clone())
             .expect("Failed to create Redis client");
         
-        // Clear test data before each test (specific keys only to not interfere with backend)
-        let mut conn = redis_client.get_connection().expect("Failed to connect to Redis");
-        
         // Use running backend instance
         let base_url = std::env::var("BACKEND_URL")
             .unwrap_or_else(|_| "http://localhost:3001".to_string());
@@ -96,6 +93,7 @@ impl TestContext {
     }
     
     /// Get bet from Redis using the new hash structure
+    #[allow(dead_code)]
     pub fn get_bet(&self, bet_id: &str) -> Option<Value> {
         let mut conn = self.redis_client.get_connection().expect("Failed to connect to Redis");
         let key = format!("bet:{}", bet_id);
@@ -142,7 +142,7 @@ async fn test_list_user_bets() {
     let user_wallet = "TEST_WALLET_123";
     
     // Create multiple test bets
-    for i in 0..3 {
+    for _ in 0..3 {
         let bet_id = Uuid::new_v4().to_string();
         ctx.create_test_bet(&bet_id, user_wallet, "pending");
     }
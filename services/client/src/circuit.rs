@@ -0,0 +1,106 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Fails backend calls fast after repeated transient failures
+///
+/// After `failure_threshold` consecutive failed attempts the circuit opens and
+/// calls return [`crate::ClientError::CircuitOpen`] without touching the network.
+/// Once `open_for` has passed, one call goes through as a probe: success closes
+/// the circuit, failure opens it again.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    state: Mutex<CircuitState>,
+}
+
+#[derive(Debug, Default)]
+struct CircuitState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probing: bool,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        Self { failure_threshold: failure_threshold.max(1), open_for, state: Mutex::new(CircuitState::default()) }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, CircuitState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// `Err` with the time left while open; otherwise the call may proceed
+    pub(crate) fn try_acquire(&self, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state();
+        match state.open_until {
+            None => Ok(()),
+            Some(until) if now < until => Err(until - now),
+            // Half-open: a single probe at a time
+            Some(_) if state.probing => Err(Duration::ZERO),
+            Some(_) => {
+                state.probing = true;
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn record_success(&self) {
+        let mut state = self.state();
+        if state.open_until.is_some() {
+            tracing::info!("Backend circuit closed");
+        }
+        *state = CircuitState::default();
+    }
+
+    pub(crate) fn record_failure(&self, now: Instant) {
+        let mut state = self.state();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.probing || state.consecutive_failures >= self.failure_threshold {
+            if state.open_until.is_none_or(|until| until <= now) {
+                tracing::warn!(
+                    consecutive_failures = state.consecutive_failures,
+                    open_for_ms = self.open_for.as_millis() as u64,
+                    "Backend circuit opened"
+                );
+            }
+            state.open_until = Some(now + self.open_for);
+            state.probing = false;
+        }
+    }
+
+    /// Whether calls are currently failing fast
+    pub fn is_open(&self) -> bool {
+        self.state().open_until.is_some_and(|until| Instant::now() < until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_probes_once() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let now = Instant::now();
+
+        breaker.record_failure(now);
+        breaker.record_failure(now);
+        assert!(breaker.try_acquire(now).is_ok());
+        breaker.record_failure(now);
+        assert_eq!(breaker.try_acquire(now + Duration::from_secs(4)), Err(Duration::from_secs(6)));
+
+        let later = now + Duration::from_secs(10);
+        assert!(breaker.try_acquire(later).is_ok());
+        assert!(breaker.try_acquire(later).is_err());
+
+        // A failed probe reopens immediately
+        breaker.record_failure(later);
+        assert!(breaker.try_acquire(later + Duration::from_secs(1)).is_err());
+
+        assert!(breaker.try_acquire(later + Duration::from_secs(10)).is_ok());
+        breaker.record_success();
+        assert!(breaker.try_acquire(later + Duration::from_secs(10)).is_ok());
+        assert!(!breaker.is_open());
+    }
+}
@@ -0,0 +1,71 @@
+use shared::errors::ErrorCode;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    /// The backend answered with an error status
+    #[error("Backend returned {status} {code}: {message}")]
+    Api {
+        status: u16,
+        /// Shared `ErrorCode` string; empty when the body was not the standard error shape
+        code: String,
+        message: String,
+    },
+
+    /// The request never got an answer, or the answer could not be decoded
+    #[error("Backend request failed: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    /// Not sent: the backend circuit is open after repeated failures
+    #[error("Backend circuit open; retry in {retry_in_ms}ms")]
+    CircuitOpen { retry_in_ms: u64 },
+}
+
+impl ClientError {
+    /// Worth retrying: timeouts, connection failures, 5xx and 429
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::Api { status, .. } => *status >= 500 || *status == 429,
+            ClientError::Transport(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            ClientError::CircuitOpen { .. } => false,
+        }
+    }
+
+    /// The backend turned the request away unhandled, so even a call that is
+    /// not idempotent can be sent again: connection refused, 429, or a bet
+    /// refused while intake sheds load
+    pub fn was_not_handled(&self) -> bool {
+        match self {
+            ClientError::Api { status, code, .. } => {
+                *status == 429 || code == ErrorCode::NETWORK_INTAKE_OVERLOADED.as_str()
+            }
+            ClientError::Transport(e) => e.is_connect(),
+            ClientError::CircuitOpen { .. } => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_errors_retry_only_when_transient() {
+        let api = |status| ClientError::Api { status, code: String::new(), message: String::new() };
+        assert!(api(503).is_retryable());
+        assert!(api(429).is_retryable());
+        assert!(!api(400).is_retryable());
+        assert!(!api(404).is_retryable());
+        assert!(api(429).was_not_handled());
+        assert!(!api(503).was_not_handled());
+        let shed = ClientError::Api {
+            status: 503,
+            code: ErrorCode::NETWORK_INTAKE_OVERLOADED.as_str().to_string(),
+            message: String::new(),
+        };
+        assert!(shed.was_not_handled());
+        assert!(!ClientError::CircuitOpen { retry_in_ms: 0 }.is_retryable());
+    }
+}
@@ -0,0 +1,305 @@
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use shared::api::ErrorResponse;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::circuit::CircuitBreaker;
+use crate::error::{ClientError, Result};
+use crate::retry::RetryPolicy;
+use crate::signals::ClaimSignals;
+use crate::{
+    BackendApi, Bet, BetUpdateOutcome, CreateBetRequest, CreateBetResponse, PendingBetsResponse, PendingRefundsResponse,
+    Refund, RefundResult, UpdateBatchRequest, UpdateBatchResponse, WalletTokenRequest, WalletTokenResponse,
+};
+
+/// Connect timeout of the client built by [`HttpBackend::new`]
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Per-attempt timeouts by kind of endpoint
+#[derive(Debug, Clone)]
+pub struct Timeouts {
+    /// `get_bet`, `list_bets`
+    pub read: Duration,
+    /// `claim_pending`, `claim_refunds`
+    pub claim: Duration,
+    /// `update_batch`, `report_refund`
+    pub update: Duration,
+    /// `create_bet`
+    pub create: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            read: Duration::from_secs(5),
+            claim: Duration::from_secs(5),
+            update: Duration::from_secs(10),
+            create: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Whether a call can be replayed after an answer that may have been lost
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Replay {
+    /// Any transient failure is retried
+    Safe,
+    /// Retried only when the backend turned the request away unhandled
+    Unhandled,
+}
+
+/// [`BackendApi`] over HTTP
+///
+/// Every attempt is bounded by the endpoint's timeout. Idempotent calls retry
+/// transient failures with jittered backoff, as do batch updates, whose resend
+/// succeeds once applied; claims and bet creation retry only failures that
+/// never reached a handler, since a lost answer may hide a claim or a new bet.
+/// With a circuit breaker set, repeated failures make calls fail fast until the
+/// backend recovers.
+#[derive(Clone)]
+pub struct HttpBackend {
+    http: Client,
+    base_url: String,
+    retry: RetryPolicy,
+    timeouts: Timeouts,
+    circuit: Option<Arc<CircuitBreaker>>,
+    /// Wallet token sent with `get_bet` and `list_bets`
+    bearer_token: Option<String>,
+}
+
+impl HttpBackend {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = Client::builder()
+            .connect_timeout(DEFAULT_CONNECT_TIMEOUT)
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            retry: RetryPolicy::default(),
+            timeouts: Timeouts::default(),
+            circuit: None,
+            bearer_token: None,
+        }
+    }
+
+    /// Use a preconfigured client, e.g. one with timeouts and pooling set
+    pub fn with_http_client(mut self, http: Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Fail fast for `open_for` after `failure_threshold` consecutive transient failures
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, open_for: Duration) -> Self {
+        self.circuit = Some(Arc::new(CircuitBreaker::new(failure_threshold, open_for)));
+        self
+    }
+
+    /// Read bets with a token from [`HttpBackend::issue_wallet_token`]
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// `POST /api/auth/token`; exchanges a signed request for the wallet's read token
+    pub async fn issue_wallet_token(&self, req: &WalletTokenRequest) -> Result<WalletTokenResponse> {
+        // Each signature is accepted once, so a lost response needs a new one
+        let url = self.url("/api/auth/token");
+        self.send_json(self.timeouts.create, Replay::Unhandled, || self.http.post(&url).json(req))
+            .await
+    }
+
+    /// `GET /api/external/bets/subscribe`; a push of each bet as it becomes claimable.
+    /// Not retried and not bounded by a timeout: callers reconnect, and keep polling
+    /// `claim_pending` while disconnected.
+    pub async fn subscribe_claimable(&self, processor_id: &str) -> Result<ClaimSignals> {
+        let url = self.url("/api/external/bets/subscribe");
+        let response = self
+            .http
+            .get(&url)
+            .query(&[("processor_id", processor_id)])
+            .send()
+            .await?;
+        Ok(ClaimSignals::new(check_status(response).await?))
+    }
+
+    /// Whether backend calls are currently failing fast
+    pub fn circuit_open(&self) -> bool {
+        self.circuit.as_ref().is_some_and(|circuit| circuit.is_open())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// A bet read, authorized by the bearer token if there is one
+    fn read(&self, url: &str) -> RequestBuilder {
+        let request = self.http.get(url);
+        match &self.bearer_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// One attempt, gated by the circuit breaker
+    async fn attempt(&self, timeout: Duration, build: &impl Fn() -> RequestBuilder) -> Result<Response> {
+        if let Some(circuit) = &self.circuit {
+            circuit
+                .try_acquire(Instant::now())
+                .map_err(|retry_in| ClientError::CircuitOpen { retry_in_ms: retry_in.as_millis() as u64 })?;
+        }
+        let result = match build().timeout(timeout).send().await {
+            Ok(response) => check_status(response).await,
+            Err(e) => Err(ClientError::Transport(e)),
+        };
+        if let Some(circuit) = &self.circuit {
+            match &result {
+                Err(e) if e.is_retryable() => circuit.record_failure(Instant::now()),
+                _ => circuit.record_success(),
+            }
+        }
+        result
+    }
+
+    /// Send the request built by `build`, retrying transient failures `replay` allows
+    async fn send(&self, timeout: Duration, replay: Replay, build: impl Fn() -> RequestBuilder) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            match self.attempt(timeout, &build).await {
+                Err(e)
+                    if e.is_retryable()
+                        && (replay == Replay::Safe || e.was_not_handled())
+                        && attempt < self.retry.max_retries =>
+                {
+                    attempt += 1;
+                    let backoff = self.retry.jittered_backoff(attempt);
+                    tracing::debug!(attempt, backoff_ms = backoff.as_millis() as u64, error = %e, "Retrying backend request");
+                    tokio::time::sleep(backoff).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        timeout: Duration,
+        replay: Replay,
+        build: impl Fn() -> RequestBuilder,
+    ) -> Result<T> {
+        Ok(self.send(timeout, replay, build).await?.json().await?)
+    }
+}
+
+/// Turn an error status into [`ClientError::Api`], keeping the backend's error code
+async fn check_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(match serde_json::from_str::<ErrorResponse>(&body) {
+        Ok(parsed) => ClientError::Api {
+            status: status.as_u16(),
+            code: parsed.error.code,
+            message: parsed.error.message,
+        },
+        Err(_) => ClientError::Api { status: status.as_u16(), code: String::new(), message: body },
+    })
+}
+
+#[async_trait]
+impl BackendApi for HttpBackend {
+    async fn create_bet(&self, req: &CreateBetRequest) -> Result<CreateBetResponse> {
+        // A lost response may still have created the bet
+        let url = self.url("/api/bets");
+        self.send_json(self.timeouts.create, Replay::Unhandled, || self.http.post(&url).json(req))
+            .await
+    }
+
+    async fn get_bet(&self, bet_id: Uuid) -> Result<Option<Bet>> {
+        let url = self.url(&format!("/api/bets/{}", bet_id));
+        match self.send_json(self.timeouts.read, Replay::Safe, || self.read(&url)).await {
+            Ok(bet) => Ok(Some(bet)),
+            Err(ClientError::Api { status, .. }) if status == StatusCode::NOT_FOUND.as_u16() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn list_bets(&self, user_wallet: &str, limit: i64, offset: i64) -> Result<Vec<Bet>> {
+        let url = self.url("/api/bets");
+        self.send_json(self.timeouts.read, Replay::Safe, || {
+            self.read(&url).query(&[
+                ("user_wallet", user_wallet.to_string()),
+                ("limit", limit.to_string()),
+                ("offset", offset.to_string()),
+            ])
+        })
+        .await
+    }
+
+    async fn claim_pending(&self, limit: usize, processor_id: &str) -> Result<PendingBetsResponse> {
+        // A lost response leaves its bets claimed until the claim expires
+        let url = self.url("/api/external/bets/pending");
+        self.send_json(self.timeouts.claim, Replay::Unhandled, || {
+            self.http
+                .get(&url)
+                .query(&[("limit", limit.to_string()), ("processor_id", processor_id.to_string())])
+        })
+        .await
+    }
+
+    async fn update_batch(&self, batch_id: Uuid, req: &UpdateBatchRequest) -> Result<UpdateBatchResponse> {
+        // A replay of an applied update is answered from the bets it already settled
+        let url = self.url(&format!("/api/external/batches/{}", batch_id));
+        let response: UpdateBatchResponse =
+            self.send_json(self.timeouts.update, Replay::Safe, || self.http.post(&url).json(req)).await?;
+        for result in response.results.iter().filter(|r| r.outcome != BetUpdateOutcome::Applied) {
+            if result.outcome == BetUpdateOutcome::SkippedTerminal {
+                tracing::debug!(%batch_id, bet_id = %result.bet_id, "Bet result already applied");
+            } else {
+                tracing::warn!(
+                    %batch_id,
+                    bet_id = %result.bet_id,
+                    outcome = ?result.outcome,
+                    status = ?result.status,
+                    reason = ?result.reason,
+                    retryable = result.outcome.is_retryable(),
+                    "Bet result not applied"
+                );
+            }
+        }
+        Ok(response)
+    }
+
+    async fn claim_refunds(&self, limit: usize, processor_id: &str) -> Result<PendingRefundsResponse> {
+        // A lost response leaves its refunds leased until the lease lapses
+        let url = self.url("/api/external/refunds/pending");
+        self.send_json(self.timeouts.claim, Replay::Unhandled, || {
+            self.http
+                .get(&url)
+                .query(&[("limit", limit.to_string()), ("processor_id", processor_id.to_string())])
+        })
+        .await
+    }
+
+    async fn report_refund(&self, bet_id: Uuid, result: &RefundResult) -> Result<Refund> {
+        // Reports for a final refund are ignored, so replays are safe
+        let url = self.url(&format!("/api/external/refunds/{}", bet_id));
+        self.send_json(self.timeouts.update, Replay::Safe, || self.http.post(&url).json(result))
+            .await
+    }
+}
@@ -0,0 +1,65 @@
+//! Typed client for the backend REST API
+//!
+//! [`BackendApi`] covers the bet endpoints used by frontends and the external
+//! endpoints used by processors, including voided-bet refunds. [`HttpBackend`] talks to a running backend with
+//! per-endpoint timeouts, retries transient failures and can trip a circuit breaker; [`MockBackend`] keeps bets in memory so
+//! downstream crates can test against the API without Redis or a server.
+
+mod circuit;
+mod error;
+mod http;
+mod mock;
+mod retry;
+mod signals;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub use error::{ClientError, Result};
+pub use http::{HttpBackend, Timeouts};
+pub use mock::MockBackend;
+pub use retry::RetryPolicy;
+pub use signals::{ClaimSignal, ClaimSignals};
+pub use shared::api::{
+    BatchStatus, Bet, BetReceipt, BetResult, BetStatus, BetUpdateOutcome, BetUpdateResult, ClaimableEvent, CreateBetRequest, PendingBetsResponse, PendingRefundsResponse,
+    ReceiptPayload, Refund, RefundResult, RefundStatus, UpdateBatchRequest, UpdateBatchResponse, WalletTokenRequest,
+    WalletTokenResponse,
+};
+
+/// Response of `POST /api/bets`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateBetResponse {
+    pub bet: Bet,
+    pub receipt: BetReceipt,
+    /// Renewal suggestion for the bet's allowance, passed through as sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowance_warning: Option<serde_json::Value>,
+}
+
+#[async_trait]
+pub trait BackendApi: Send + Sync {
+    /// `POST /api/bets`
+    async fn create_bet(&self, req: &CreateBetRequest) -> Result<CreateBetResponse>;
+
+    /// `GET /api/bets/:bet_id`; `None` when the bet does not exist
+    async fn get_bet(&self, bet_id: Uuid) -> Result<Option<Bet>>;
+
+    /// `GET /api/bets`, newest first
+    async fn list_bets(&self, user_wallet: &str, limit: i64, offset: i64) -> Result<Vec<Bet>>;
+
+    /// `GET /api/external/bets/pending`; claims up to `limit` bets for `processor_id`
+    async fn claim_pending(&self, limit: usize, processor_id: &str) -> Result<PendingBetsResponse>;
+
+    /// `POST /api/external/batches/:batch_id`; `req` must carry the claiming
+    /// `processor_id` and the batch's current `batch_version` (409 otherwise). A
+    /// resend of an applied update at its old version succeeds without changes
+    /// if the bets still hold its results.
+    async fn update_batch(&self, batch_id: Uuid, req: &UpdateBatchRequest) -> Result<UpdateBatchResponse>;
+
+    /// `GET /api/external/refunds/pending`; leases up to `limit` voided-bet refunds
+    async fn claim_refunds(&self, limit: usize, processor_id: &str) -> Result<PendingRefundsResponse>;
+
+    /// `POST /api/external/refunds/:bet_id`
+    async fn report_refund(&self, bet_id: Uuid, result: &RefundResult) -> Result<Refund>;
+}
@@ -0,0 +1,362 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use shared::errors::ErrorCode;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::error::{ClientError, Result};
+use crate::{
+    BackendApi, Bet, BetReceipt, BetStatus, BetUpdateOutcome, BetUpdateResult, CreateBetRequest, CreateBetResponse, PendingBetsResponse,
+    PendingRefundsResponse, ReceiptPayload, Refund, RefundResult, RefundStatus, UpdateBatchRequest, UpdateBatchResponse,
+};
+
+/// In-memory [`BackendApi`] for tests
+///
+/// Follows the backend's status transitions: claims move pending and retryable
+/// bets to `batched`, and batch updates from the claiming processor at the
+/// current batch version apply each bet result. Retry backoff,
+/// expiry, refund leases and the kill switch are not modelled.
+pub struct MockBackend {
+    bets: Mutex<Vec<Bet>>,
+    refunds: Mutex<Vec<Refund>>,
+    /// Claiming processor and current version per batch
+    batches: Mutex<HashMap<Uuid, (String, u64)>>,
+    signer: Keypair,
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self {
+            bets: Mutex::new(Vec::new()),
+            refunds: Mutex::new(Vec::new()),
+            batches: Mutex::new(HashMap::new()),
+            signer: Keypair::new(),
+        }
+    }
+
+    /// Seed a bet as if it had been created earlier
+    pub fn insert(&self, bet: Bet) {
+        let mut bets = self.lock();
+        bets.retain(|b| b.bet_id != bet.bet_id);
+        bets.push(bet);
+    }
+
+    /// Every bet, oldest first
+    pub fn bets(&self) -> Vec<Bet> {
+        self.lock().clone()
+    }
+
+    /// Queue a refund as if an operator had voided the bet
+    pub fn insert_refund(&self, refund: Refund) {
+        let mut refunds = self.lock_refunds();
+        refunds.retain(|r| r.bet_id != refund.bet_id);
+        refunds.push(refund);
+    }
+
+    /// Every refund, oldest first
+    pub fn refunds(&self) -> Vec<Refund> {
+        self.lock_refunds().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Bet>> {
+        self.bets.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_refunds(&self) -> std::sync::MutexGuard<'_, Vec<Refund>> {
+        self.refunds.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl BackendApi for MockBackend {
+    async fn create_bet(&self, req: &CreateBetRequest) -> Result<CreateBetResponse> {
+        let bet = Bet {
+            bet_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            user_wallet: req.user_wallet.clone().unwrap_or_default(),
+            vault_address: req.vault_address.clone().unwrap_or_default(),
+            allowance_pda: req.allowance_pda.clone().filter(|v| !v.is_empty()),
+            casino_id: None,
+            game_type: "coinflip".to_string(),
+            stake_amount: req.stake_amount.as_u64() as i64,
+            stake_token: req.stake_token.clone(),
+            choice: req.choice.clone(),
+            status: BetStatus::Pending,
+            external_batch_id: None,
+            solana_tx_id: None,
+            retry_count: 0,
+            processor_id: None,
+            last_error_code: None,
+            last_error_message: None,
+            payout_amount: None,
+            won: None,
+            server_seed_hash: None,
+            client_seed: req.client_seed.clone(),
+            metadata: req.metadata.clone(),
+        };
+        self.lock().push(bet.clone());
+
+        let payload = ReceiptPayload::from_bet(&bet);
+        let receipt = BetReceipt {
+            signature: self.signer.sign_message(&payload.signing_bytes()).to_string(),
+            signer: self.signer.pubkey().to_string(),
+            payload,
+        };
+        Ok(CreateBetResponse { bet, receipt, allowance_warning: None })
+    }
+
+    async fn get_bet(&self, bet_id: Uuid) -> Result<Option<Bet>> {
+        Ok(self.lock().iter().find(|b| b.bet_id == bet_id).cloned())
+    }
+
+    async fn list_bets(&self, user_wallet: &str, limit: i64, offset: i64) -> Result<Vec<Bet>> {
+        Ok(self
+            .lock()
+            .iter()
+            .rev()
+            .filter(|b| b.user_wallet == user_wallet)
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn claim_pending(&self, limit: usize, processor_id: &str) -> Result<PendingBetsResponse> {
+        let batch_id = Uuid::new_v4();
+        let mut bets = self.lock();
+        let claimed: Vec<Bet> = bets
+            .iter_mut()
+            .filter(|b| b.status.can_transition_to(&BetStatus::Batched))
+            .take(limit.min(500))
+            .map(|bet| {
+                bet.status = BetStatus::Batched;
+                bet.external_batch_id = Some(batch_id);
+                bet.processor_id = Some(processor_id.to_string());
+                bet.clone()
+            })
+            .collect();
+
+        let batch_version = if claimed.is_empty() {
+            0
+        } else {
+            self.batches.lock().unwrap_or_else(|e| e.into_inner()).insert(batch_id, (processor_id.to_string(), 1));
+            1
+        };
+
+        Ok(PendingBetsResponse { batch_id, processor_id: processor_id.to_string(), bets: claimed, batch_version })
+    }
+
+    async fn update_batch(&self, batch_id: Uuid, req: &UpdateBatchRequest) -> Result<UpdateBatchResponse> {
+        let conflict = |owner: &str, version: u64| ClientError::Api {
+            status: 409,
+            code: ErrorCode::CONFLICT_BATCH_CLAIM.as_str().to_string(),
+            message: format!("Batch {} is claimed by {} at version {}", batch_id, owner, version),
+        };
+        // A resend of an applied update carries an older version and changes nothing
+        let (owner, batch_version, resent) = {
+            let mut batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+            let Some((owner, version)) = batches.get_mut(&batch_id) else {
+                return Err(ClientError::Api {
+                    status: 404,
+                    code: ErrorCode::NOT_FOUND_BATCH.as_str().to_string(),
+                    message: format!("Batch not found: {}", batch_id),
+                });
+            };
+            match req.batch_version {
+                _ if req.processor_id.as_deref() != Some(owner.as_str()) => return Err(conflict(owner, *version)),
+                Some(sent) if sent == *version => {
+                    *version += 1;
+                    (owner.clone(), *version, false)
+                }
+                Some(sent) if sent < *version => (owner.clone(), *version, true),
+                _ => return Err(conflict(owner, *version)),
+            }
+        };
+
+        let mut bets = self.lock();
+        let mut updated_count = 0;
+        let mut results = Vec::with_capacity(req.bet_results.len());
+        for result in &req.bet_results {
+            let Some(bet) = bets.iter_mut().find(|b| b.bet_id == result.bet_id) else {
+                results.push(BetUpdateResult {
+                    bet_id: result.bet_id,
+                    outcome: BetUpdateOutcome::Conflict,
+                    status: None,
+                    reason: Some("Unknown bet".to_string()),
+                });
+                continue;
+            };
+            if resent {
+                if !bet.reflects_result(batch_id, result) {
+                    return Err(conflict(&owner, batch_version));
+                }
+                let status = Some(bet.status.clone());
+                results.push(BetUpdateResult { bet_id: bet.bet_id, outcome: BetUpdateOutcome::SkippedTerminal, status, reason: None });
+                continue;
+            }
+            let (outcome, reason) = bet.diff_result(batch_id, result);
+            if outcome == BetUpdateOutcome::Applied {
+                if result.status == BetStatus::FailedRetryable {
+                    bet.retry_count += 1;
+                }
+                bet.status = result.status.clone();
+                if result.solana_tx_id.is_some() {
+                    bet.solana_tx_id = result.solana_tx_id.clone();
+                }
+                bet.won = result.won.or(bet.won);
+                bet.payout_amount = result.payout_amount.or(bet.payout_amount);
+                if result.error_message.is_some() {
+                    bet.last_error_message = result.error_message.clone();
+                }
+                if result.server_seed_hash.is_some() {
+                    bet.server_seed_hash = result.server_seed_hash.clone();
+                }
+                updated_count += 1;
+            }
+            results.push(BetUpdateResult { bet_id: bet.bet_id, outcome, status: Some(bet.status.clone()), reason });
+        }
+
+        Ok(UpdateBatchResponse {
+            success: true,
+            batch_id,
+            updated_count,
+            error_count: results
+                .iter()
+                .filter(|r| matches!(r.outcome, BetUpdateOutcome::Conflict | BetUpdateOutcome::Error))
+                .count(),
+            batch_version,
+            results,
+        })
+    }
+
+    async fn claim_refunds(&self, limit: usize, processor_id: &str) -> Result<PendingRefundsResponse> {
+        let refunds = self
+            .lock_refunds()
+            .iter()
+            .filter(|r| r.status == RefundStatus::Pending)
+            .take(limit.min(100))
+            .cloned()
+            .collect();
+        Ok(PendingRefundsResponse { processor_id: processor_id.to_string(), refunds })
+    }
+
+    async fn report_refund(&self, bet_id: Uuid, result: &RefundResult) -> Result<Refund> {
+        let mut refunds = self.lock_refunds();
+        let refund = refunds.iter_mut().find(|r| r.bet_id == bet_id).ok_or_else(|| ClientError::Api {
+            status: 404,
+            code: ErrorCode::NOT_FOUND_BET.as_str().to_string(),
+            message: format!("No refund for bet {}", bet_id),
+        })?;
+        refund.apply(result, Utc::now());
+        Ok(refund.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BatchStatus, BetResult};
+    use shared::LamportAmount;
+
+    fn request(wallet: &str) -> CreateBetRequest {
+        CreateBetRequest {
+            user_wallet: Some(wallet.to_string()),
+            vault_address: None,
+            allowance_pda: None,
+            stake_amount: LamportAmount::new(100_000_000).unwrap(),
+            stake_token: "SOL".to_string(),
+            game_type: "coinflip".to_string(),
+            choice: "heads".to_string(),
+            client_seed: None,
+            session_id: None,
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bet_lifecycle() {
+        let backend = MockBackend::new();
+        let created = backend.create_bet(&request("alice")).await.unwrap();
+        backend.create_bet(&request("bob")).await.unwrap();
+        assert_eq!(created.receipt.payload.bet_id, created.bet.bet_id);
+
+        let claimed = backend.claim_pending(1, "processor-1").await.unwrap();
+        assert_eq!(claimed.bets.len(), 1);
+        assert_eq!(claimed.bets[0].bet_id, created.bet.bet_id);
+        assert_eq!(claimed.bets[0].status, BetStatus::Batched);
+
+        let update = UpdateBatchRequest {
+            status: BatchStatus::Confirmed,
+            solana_tx_id: Some("5sig".to_string()),
+            bet_results: vec![
+                BetResult {
+                    bet_id: created.bet.bet_id,
+                    status: BetStatus::Completed,
+                    solana_tx_id: Some("5sig".to_string()),
+                    error_message: None,
+                    error_code: None,
+                    won: Some(true),
+                    payout_amount: Some(200_000_000),
+                    server_seed_hash: None,
+                    settlement: None,
+                },
+                BetResult {
+                    bet_id: Uuid::new_v4(),
+                    status: BetStatus::Completed,
+                    solana_tx_id: None,
+                    error_message: None,
+                    error_code: None,
+                    won: None,
+                    payout_amount: None,
+                    server_seed_hash: None,
+                    settlement: None,
+                },
+            ],
+            error_message: None,
+            fee_lamports: None,
+            processor_id: Some("processor-1".to_string()),
+            batch_version: Some(claimed.batch_version),
+        };
+        let response = backend.update_batch(claimed.batch_id, &update).await.unwrap();
+        assert_eq!((response.updated_count, response.error_count), (1, 1));
+        let outcomes: Vec<_> = response.results.iter().map(|r| r.outcome).collect();
+        assert_eq!(outcomes, vec![BetUpdateOutcome::Applied, BetUpdateOutcome::Conflict]);
+
+        // Resent with the new version, the settled bet is skipped rather than overwritten
+        let resent = UpdateBatchRequest { batch_version: Some(response.batch_version), ..update.clone() };
+        let resent = backend.update_batch(claimed.batch_id, &resent).await.unwrap();
+        assert_eq!(resent.results[0].outcome, BetUpdateOutcome::SkippedTerminal);
+        let response = resent;
+
+        // The first update resent at its old version succeeds without changing anything
+        let replayed = backend.update_batch(claimed.batch_id, &update).await.unwrap();
+        assert_eq!((replayed.updated_count, replayed.batch_version), (0, response.batch_version));
+        assert_eq!(replayed.results[0].outcome, BetUpdateOutcome::SkippedTerminal);
+
+        // A different result at an old version, or a report from another processor, is rejected
+        let conflict = |result: Result<UpdateBatchResponse>| matches!(result, Err(ClientError::Api { status: 409, .. }));
+        let mut different = update.clone();
+        different.bet_results[0].payout_amount = Some(300_000_000);
+        assert!(conflict(backend.update_batch(claimed.batch_id, &different).await));
+        let foreign = UpdateBatchRequest {
+            processor_id: Some("processor-2".to_string()),
+            batch_version: Some(response.batch_version),
+            ..update.clone()
+        };
+        assert!(conflict(backend.update_batch(claimed.batch_id, &foreign).await));
+
+        let bet = backend.get_bet(created.bet.bet_id).await.unwrap().unwrap();
+        assert_eq!(bet.status, BetStatus::Completed);
+        assert_eq!(bet.payout_amount, Some(200_000_000));
+        assert_eq!(backend.list_bets("alice", 10, 0).await.unwrap().len(), 1);
+        assert!(backend.get_bet(Uuid::new_v4()).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+/// Exponential backoff between attempts of a retryable request
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Share of each backoff taken off at random (0.0–1.0), so callers that
+    /// failed together don't retry together
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    /// Delay before retry number `retry` (starting at 1)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    /// [`Self::backoff`] shortened by up to `jitter` of itself
+    pub fn jittered_backoff(&self, retry: u32) -> Duration {
+        let cut = self.jitter.clamp(0.0, 1.0) * rand::random::<f64>();
+        self.backoff(retry).mul_f64(1.0 - cut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(1600));
+        assert_eq!(policy.backoff(10), Duration::from_secs(5));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(5));
+
+        for retry in 1..5 {
+            let jittered = policy.jittered_backoff(retry);
+            assert!(jittered <= policy.backoff(retry));
+            assert!(jittered >= policy.backoff(retry) / 2);
+        }
+        let exact = RetryPolicy { jitter: 0.0, ..RetryPolicy::default() };
+        assert_eq!(exact.jittered_backoff(2), Duration::from_millis(400));
+    }
+}
@@ -0,0 +1,89 @@
+//! Server-sent claim signals of `GET /api/external/bets/subscribe`
+//!
+//! Only the subset of the SSE format the backend sends is understood: `event`
+//! and `data` fields, comment lines (keep-alives) and blank-line separators.
+
+use reqwest::Response;
+
+use crate::error::Result;
+use crate::ClaimableEvent;
+use shared::api::CLAIMABLE_EVENT;
+
+/// One event of a claim signal stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimSignal {
+    /// A bet became claimable
+    Claimable(ClaimableEvent),
+    /// The backend dropped this many signals for us; claim anyway
+    Lagged(u64),
+}
+
+/// Open subscription returned by [`HttpBackend::subscribe_claimable`](crate::HttpBackend::subscribe_claimable)
+pub struct ClaimSignals {
+    response: Response,
+    buffer: String,
+}
+
+impl ClaimSignals {
+    pub(crate) fn new(response: Response) -> Self {
+        Self { response, buffer: String::new() }
+    }
+
+    /// The next signal; `None` once the backend closes the stream
+    pub async fn next(&mut self) -> Result<Option<ClaimSignal>> {
+        loop {
+            while let Some(end) = self.buffer.find("\n\n") {
+                let block: String = self.buffer.drain(..end + 2).collect();
+                if let Some(signal) = parse_event(&block) {
+                    return Ok(Some(signal));
+                }
+            }
+            match self.response.chunk().await? {
+                // SSE allows CRLF; normalize so blocks split on "\n\n"
+                Some(chunk) => self.buffer.push_str(&String::from_utf8_lossy(&chunk).replace("\r\n", "\n")),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Decode one blank-line-terminated event; `None` for keep-alives and unknown events
+fn parse_event(block: &str) -> Option<ClaimSignal> {
+    let mut event = "message";
+    let mut data = String::new();
+    for line in block.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            event = value.trim();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    match event {
+        CLAIMABLE_EVENT => serde_json::from_str(&data).ok().map(ClaimSignal::Claimable),
+        "lagged" => data.trim().parse().ok().map(ClaimSignal::Lagged),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_parse_claim_signals() {
+        let bet_id = Uuid::new_v4();
+        let block = format!("event: claimable\ndata: {{\"bet_id\":\"{}\",\"priority\":true}}\n\n", bet_id);
+        assert_eq!(
+            parse_event(&block),
+            Some(ClaimSignal::Claimable(ClaimableEvent { bet_id, priority: true }))
+        );
+        assert_eq!(parse_event("event: lagged\ndata: 12\n\n"), Some(ClaimSignal::Lagged(12)));
+        // Keep-alive comments and events this client doesn't know
+        assert_eq!(parse_event(":\n\n"), None);
+        assert_eq!(parse_event("data: hello\n\n"), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Classify vault program accounts into the records the indexer keeps
+//!
+//! Accounts are recognised by their Anchor discriminator. Casino-level accounts
+//! (`Casino`, `CasinoVault`, `RateLimiter`) are not mirrored.
+
+use anyhow::Result;
+use shared::indexer::{IndexedAllowance, IndexedNonceRegistry, IndexedProcessedBet, IndexedVault};
+use shared::vault::{
+    account_discriminator, AllowanceAccount, BetOutcome, NonceRegistryAccount, ProcessedBetAccount, VaultAccount,
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+/// Account types the indexer mirrors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountKind {
+    Vault,
+    Allowance,
+    NonceRegistry,
+    ProcessedBet,
+}
+
+impl AccountKind {
+    pub const ALL: [AccountKind; 4] = [
+        AccountKind::Vault,
+        AccountKind::Allowance,
+        AccountKind::NonceRegistry,
+        AccountKind::ProcessedBet,
+    ];
+
+    /// Anchor account name, as hashed into the discriminator
+    pub fn account_name(&self) -> &'static str {
+        match self {
+            AccountKind::Vault => "Vault",
+            AccountKind::Allowance => "Allowance",
+            AccountKind::NonceRegistry => "AllowanceNonceRegistry",
+            AccountKind::ProcessedBet => "ProcessedBet",
+        }
+    }
+
+    pub fn discriminator(&self) -> [u8; 8] {
+        account_discriminator(self.account_name())
+    }
+
+    fn of(data: &[u8]) -> Option<Self> {
+        let prefix = data.get(..8)?;
+        AccountKind::ALL.into_iter().find(|kind| kind.discriminator() == prefix)
+    }
+}
+
+/// A decoded account as stored in the mirror
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexedAccount {
+    Vault(IndexedVault),
+    Allowance(IndexedAllowance),
+    NonceRegistry(IndexedNonceRegistry),
+    ProcessedBet(IndexedProcessedBet),
+}
+
+/// Decode a program account observed at `slot`; `Ok(None)` for accounts the indexer ignores
+pub fn classify(address: &Pubkey, account: &Account, slot: u64) -> Result<Option<IndexedAccount>> {
+    let Some(kind) = AccountKind::of(&account.data) else {
+        return Ok(None);
+    };
+    let address = address.to_string();
+    let data = &account.data;
+
+    let indexed = match kind {
+        AccountKind::Vault => {
+            let vault = VaultAccount::decode(data)?;
+            IndexedAccount::Vault(IndexedVault {
+                address,
+                owner: vault.owner.to_string(),
+                casino: vault.casino.to_string(),
+                sol_balance: vault.sol_balance,
+                lamports: account.lamports,
+                last_activity: vault.last_activity,
+                payout_address: vault.payout_address.map(|address| address.to_string()),
+                slot,
+            })
+        }
+        AccountKind::Allowance => {
+            let allowance = AllowanceAccount::decode(data)?;
+            IndexedAccount::Allowance(IndexedAllowance::from_account(address, &allowance, slot))
+        }
+        AccountKind::NonceRegistry => {
+            let registry = NonceRegistryAccount::decode(data)?;
+            IndexedAccount::NonceRegistry(IndexedNonceRegistry {
+                address,
+                user: registry.user.to_string(),
+                next_nonce: registry.next_nonce,
+                slot,
+            })
+        }
+        AccountKind::ProcessedBet => {
+            let bet = ProcessedBetAccount::decode(data)?;
+            IndexedAccount::ProcessedBet(IndexedProcessedBet {
+                address,
+                bet_id: bet.bet_id,
+                user: bet.user.to_string(),
+                amount: bet.amount,
+                processed_at: bet.processed_at,
+                signature: bet.signature,
+                won: match bet.outcome {
+                    BetOutcome::Unrecorded => None,
+                    BetOutcome::Lost => Some(false),
+                    BetOutcome::Won => Some(true),
+                    // A lost bet voided later; `payout` is the returned stake
+                    BetOutcome::Refunded => Some(false),
+                },
+                payout: bet.payout,
+                slot,
+            })
+        }
+    };
+    Ok(Some(indexed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(data: Vec<u8>) -> Account {
+        Account { lamports: 2_000_000, data, owner: Pubkey::new_unique(), executable: false, rent_epoch: 0 }
+    }
+
+    #[test]
+    fn test_classify_by_discriminator() {
+        let owner = Pubkey::new_unique();
+        let mut data = AccountKind::Vault.discriminator().to_vec();
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.push(254);
+        data.extend_from_slice(&500u64.to_le_bytes());
+        data.extend_from_slice(&1i64.to_le_bytes());
+        data.extend_from_slice(&2i64.to_le_bytes());
+
+        let address = Pubkey::new_unique();
+        let Some(IndexedAccount::Vault(vault)) = classify(&address, &account(data), 42).unwrap() else {
+            panic!("expected a vault");
+        };
+        assert_eq!(vault.owner, owner.to_string());
+        assert_eq!(vault.sol_balance, 500);
+        assert_eq!(vault.lamports, 2_000_000);
+        assert_eq!(vault.slot, 42);
+
+        // Casino accounts are not mirrored; garbage with a known discriminator is an error
+        let mut casino = account_discriminator("Casino").to_vec();
+        casino.extend_from_slice(&[0u8; 120]);
+        assert_eq!(classify(&address, &account(casino), 1).unwrap(), None);
+        assert!(classify(&address, &account(AccountKind::Allowance.discriminator().to_vec()), 1).is_err());
+    }
+}
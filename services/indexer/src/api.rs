@@ -0,0 +1,110 @@
+//! Internal HTTP API over the mirror, consumed by the backend
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use shared::indexer::{
+    IndexedAllowance, IndexedDeposit, IndexedProcessedBet, IndexedVault, IndexerStatus, UserAllowances,
+};
+
+use crate::store::Store;
+
+const DEFAULT_ALLOWANCE_LIMIT: usize = 20;
+const MAX_ALLOWANCE_LIMIT: usize = 100;
+const DEFAULT_DEPOSIT_LIMIT: usize = 20;
+const MAX_DEPOSIT_LIMIT: usize = 100;
+
+pub enum ApiError {
+    NotFound(&'static str),
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError::Internal(e)
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            ApiError::NotFound(what) => (StatusCode::NOT_FOUND, format!("{} not indexed", what)),
+            ApiError::Internal(e) => {
+                tracing::error!(error = %e, "Indexer API request failed");
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            }
+        };
+        (status, Json(ErrorBody { error })).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AllowanceQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DepositQuery {
+    pub limit: Option<usize>,
+}
+
+async fn health(State(store): State<Store>) -> Result<Json<IndexerStatus>, ApiError> {
+    Ok(Json(store.status().await?))
+}
+
+async fn get_vault(State(store): State<Store>, Path(owner): Path<String>) -> Result<Json<IndexedVault>, ApiError> {
+    store.vault(&owner).await?.map(Json).ok_or(ApiError::NotFound("Vault"))
+}
+
+async fn get_user_allowances(
+    State(store): State<Store>,
+    Path(owner): Path<String>,
+    Query(query): Query<AllowanceQuery>,
+) -> Result<Json<UserAllowances>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_ALLOWANCE_LIMIT).clamp(1, MAX_ALLOWANCE_LIMIT);
+    Ok(Json(store.user_allowances(&owner, limit).await?))
+}
+
+async fn get_user_deposits(
+    State(store): State<Store>,
+    Path(owner): Path<String>,
+    Query(query): Query<DepositQuery>,
+) -> Result<Json<Vec<IndexedDeposit>>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_DEPOSIT_LIMIT).clamp(1, MAX_DEPOSIT_LIMIT);
+    Ok(Json(store.user_deposits(&owner, limit).await?))
+}
+
+async fn get_allowance(
+    State(store): State<Store>,
+    Path(address): Path<String>,
+) -> Result<Json<IndexedAllowance>, ApiError> {
+    store.allowance(&address).await?.map(Json).ok_or(ApiError::NotFound("Allowance"))
+}
+
+async fn get_processed_bet(
+    State(store): State<Store>,
+    Path(bet_id): Path<String>,
+) -> Result<Json<IndexedProcessedBet>, ApiError> {
+    store.processed_bet(&bet_id).await?.map(Json).ok_or(ApiError::NotFound("Processed bet"))
+}
+
+pub fn router(store: Store) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/vaults/:owner", get(get_vault))
+        .route("/users/:owner/allowances", get(get_user_allowances))
+        .route("/users/:owner/deposits", get(get_user_deposits))
+        .route("/allowances/:address", get(get_allowance))
+        .route("/processed-bets/:bet_id", get(get_processed_bet))
+        .with_state(store)
+}
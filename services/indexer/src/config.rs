@@ -0,0 +1,72 @@
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::env;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub rpc_url: String,
+    /// Websocket endpoint for program and logs subscriptions
+    pub ws_url: String,
+    pub commitment: CommitmentConfig,
+    pub program_id: Pubkey,
+    pub redis_url: String,
+    /// Internal HTTP API port
+    pub port: u16,
+    /// Re-snapshot mutable accounts this often to heal missed updates; 0 disables
+    pub resync_interval_seconds: u64,
+}
+
+/// `https://host` -> `wss://host`, `http://host` -> `ws://host`
+pub fn websocket_url(rpc_url: &str) -> String {
+    if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        rpc_url.to_string()
+    }
+}
+
+impl Config {
+    pub fn load() -> anyhow::Result<Self> {
+        dotenvy::dotenv().ok();
+
+        let rpc_url = env::var("INDEXER_RPC_URL")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .or_else(|| env::var("SOLANA_RPC_URL").ok())
+            .expect("INDEXER_RPC_URL or SOLANA_RPC_URL must be set");
+        let program_id = env::var("VAULT_PROGRAM_ID").expect("VAULT_PROGRAM_ID must be set");
+
+        Ok(Config {
+            ws_url: env::var("INDEXER_WS_URL")
+                .ok()
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| websocket_url(&rpc_url)),
+            rpc_url,
+            commitment: CommitmentConfig::from_str(
+                &env::var("SOLANA_COMMITMENT").unwrap_or_else(|_| "confirmed".to_string()),
+            )?,
+            program_id: Pubkey::from_str(&program_id)?,
+            redis_url: env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+            port: env::var("INDEXER_PORT")
+                .unwrap_or_else(|_| "3100".to_string())
+                .parse()?,
+            resync_interval_seconds: env::var("INDEXER_RESYNC_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_url_from_rpc_url() {
+        assert_eq!(websocket_url("https://api.devnet.solana.com"), "wss://api.devnet.solana.com");
+        assert_eq!(websocket_url("http://localhost:8899"), "ws://localhost:8899");
+        assert_eq!(websocket_url("ws://localhost:8900"), "ws://localhost:8900");
+    }
+}
@@ -0,0 +1,131 @@
+//! Deposits into user vaults
+//!
+//! Deposits change no account the backend could tell apart from a payout, so
+//! they are read from transactions: the logs subscription flags transactions
+//! announcing `DepositSol` or `DepositSpl`, and each deposit instruction into
+//! the program is decoded from the fetched transaction. Deposits made while the
+//! logs subscription was down are not backfilled.
+
+use shared::indexer::IndexedDeposit;
+use shared::vault::anchor_discriminator;
+use solana_sdk::{instruction::CompiledInstruction, pubkey::Pubkey};
+use std::collections::HashMap;
+
+/// Log lines Anchor writes for the deposit instructions
+const DEPOSIT_LOGS: [&str; 2] = ["Program log: Instruction: DepositSol", "Program log: Instruction: DepositSpl"];
+
+/// Whether a transaction's logs announce a deposit worth fetching it for
+pub fn mentions_deposit(logs: &[String]) -> bool {
+    logs.iter().any(|line| DEPOSIT_LOGS.contains(&line.as_str()))
+}
+
+/// Where the vault, the owner and, for SPL, the vault token account sit in a deposit instruction
+struct Layout {
+    owner: usize,
+    vault_token_account: Option<usize>,
+}
+
+fn layout(data: &[u8]) -> Option<Layout> {
+    let discriminator = data.get(..8)?;
+    if discriminator == anchor_discriminator("deposit_sol") {
+        // vault, casino, user, system_program
+        Some(Layout { owner: 2, vault_token_account: None })
+    } else if discriminator == anchor_discriminator("deposit_spl") {
+        // vault, casino, user_token_account, vault_token_account, user, token_program
+        Some(Layout { owner: 4, vault_token_account: Some(3) })
+    } else {
+        None
+    }
+}
+
+/// The transaction a deposit was seen in
+pub struct DepositTransaction<'a> {
+    pub signature: &'a str,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// Static keys followed by any loaded from lookup tables
+    pub account_keys: &'a [Pubkey],
+    pub instructions: &'a [CompiledInstruction],
+    /// Mint of each token account by its index in `account_keys`
+    pub token_mints: &'a HashMap<u8, String>,
+}
+
+/// Every deposit into `program_id` in the transaction's top-level instructions
+pub fn parse_deposits(program_id: &Pubkey, tx: &DepositTransaction) -> Vec<IndexedDeposit> {
+    let key = |index: u8| tx.account_keys.get(index as usize);
+
+    tx.instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, ix)| key(ix.program_id_index) == Some(program_id))
+        .filter_map(|(position, ix)| {
+            let layout = layout(&ix.data)?;
+            let amount = u64::from_le_bytes(ix.data.get(8..16)?.try_into().ok()?);
+            let vault = key(*ix.accounts.first()?)?;
+            let owner = key(*ix.accounts.get(layout.owner)?)?;
+            let token_mint = match layout.vault_token_account {
+                Some(position) => Some(tx.token_mints.get(ix.accounts.get(position)?)?.clone()),
+                None => None,
+            };
+            Some(IndexedDeposit {
+                signature: tx.signature.to_string(),
+                instruction_index: u8::try_from(position).ok()?,
+                vault: vault.to_string(),
+                owner: owner.to_string(),
+                token_mint,
+                amount,
+                slot: tx.slot,
+                block_time: tx.block_time,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit_data(name: &str, amount: u64) -> Vec<u8> {
+        let mut data = anchor_discriminator(name).to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_sol_and_spl_deposits() {
+        let program_id = Pubkey::new_unique();
+        let keys: Vec<Pubkey> = (0..7).map(|_| Pubkey::new_unique()).collect();
+        let mut account_keys = keys.clone();
+        account_keys.push(program_id);
+        let program = account_keys.len() as u8 - 1;
+        let token_mints = HashMap::from([(3u8, "Mint111".to_string())]);
+
+        let instructions = vec![
+            // Compute budget or any other program is skipped
+            CompiledInstruction::new_from_raw_parts(6, deposit_data("deposit_sol", 1), vec![0, 1, 2]),
+            CompiledInstruction::new_from_raw_parts(program, deposit_data("deposit_sol", 500), vec![0, 1, 2, 5]),
+            CompiledInstruction::new_from_raw_parts(program, deposit_data("deposit_spl", 70), vec![0, 1, 4, 3, 2, 5]),
+            CompiledInstruction::new_from_raw_parts(program, deposit_data("withdraw_sol", 9), vec![0, 1, 2, 5]),
+        ];
+        let tx = DepositTransaction {
+            signature: "sig",
+            slot: 42,
+            block_time: Some(1_700_000_000),
+            account_keys: &account_keys,
+            instructions: &instructions,
+            token_mints: &token_mints,
+        };
+
+        let deposits = parse_deposits(&program_id, &tx);
+        assert_eq!(deposits.len(), 2);
+        assert_eq!(deposits[0].instruction_index, 1);
+        assert_eq!(deposits[0].vault, keys[0].to_string());
+        assert_eq!(deposits[0].owner, keys[2].to_string());
+        assert_eq!((deposits[0].amount, deposits[0].token_mint.as_deref()), (500, None));
+        assert_eq!(deposits[1].owner, keys[2].to_string());
+        assert_eq!((deposits[1].amount, deposits[1].token_mint.as_deref()), (70, Some("Mint111")));
+
+        assert!(mentions_deposit(&["Program log: Instruction: DepositSpl".to_string()]));
+        assert!(!mentions_deposit(&["Program log: Instruction: WithdrawSol".to_string()]));
+    }
+}
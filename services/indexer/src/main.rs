@@ -0,0 +1,62 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+mod accounts;
+mod api;
+mod config;
+mod deposits;
+mod store;
+mod sync;
+
+use config::Config;
+use store::Store;
+use sync::Indexer;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Initialize structured logging with JSON formatting (configurable via env)
+    let use_json = std::env::var("LOG_FORMAT")
+        .unwrap_or_else(|_| "json".to_string())
+        .eq_ignore_ascii_case("json");
+
+    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "indexer=info".into());
+
+    if use_json {
+        tracing_subscriber::registry()
+            .with(env_filter)
+            .with(tracing_subscriber::fmt::layer().json())
+            .init();
+    } else {
+        tracing_subscriber::registry()
+            .with(env_filter)
+            .with(tracing_subscriber::fmt::layer())
+            .init();
+    }
+
+    let config = Config::load()?;
+    tracing::info!(
+        service = "indexer",
+        version = env!("CARGO_PKG_VERSION"),
+        program_id = %config.program_id,
+        ws_url = %config.ws_url,
+        "Starting indexer service"
+    );
+
+    let redis = redis::Client::open(config.redis_url.as_str())?;
+    let store = Store::new(redis::aio::ConnectionManager::new(redis).await?);
+    let port = config.port;
+
+    let indexer = Arc::new(Indexer::new(config, store));
+    tokio::spawn(indexer.clone().follow_accounts());
+    tokio::spawn(indexer.clone().follow_logs());
+    tokio::spawn(indexer.clone().resync());
+
+    let app = api::router(indexer.store().clone());
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    tracing::info!(port, "Indexer API listening");
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
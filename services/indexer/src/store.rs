@@ -0,0 +1,265 @@
+//! Redis mirror of indexed accounts
+//!
+//! Each record is a hash `{slot, data}` with `data` the JSON record. Writes
+//! carrying an older slot than the stored one are dropped, so a late snapshot
+//! never overwrites a newer subscription update. `indexer:addresses` maps an
+//! account address to its record key so closed accounts can be removed.
+//! Deposits are kept per owner, newest first, and each is appended once to
+//! `DEPOSITS_STREAM_KEY` for the backend to notify the user.
+
+use anyhow::{Context, Result};
+use redis::aio::ConnectionManager;
+use redis::streams::StreamMaxlen;
+use redis::{AsyncCommands, Script};
+use serde::de::DeserializeOwned;
+use shared::indexer::{
+    IndexedAllowance, IndexedDeposit, IndexedNonceRegistry, IndexedProcessedBet, IndexedVault, IndexerStatus,
+    UserAllowances, DEPOSITS_STREAM_KEY,
+};
+
+use crate::accounts::IndexedAccount;
+
+const ADDRESS_INDEX_KEY: &str = "indexer:addresses";
+const STATUS_KEY: &str = "indexer:status";
+
+/// Deposits kept per owner
+const DEPOSITS_PER_USER: isize = 100;
+
+/// Entries kept in the deposit stream
+const DEPOSITS_STREAM_MAXLEN: usize = 100_000;
+
+/// A deposit seen again within this window (e.g. a replayed log) is not re-announced
+const DEPOSIT_SEEN_TTL_SECONDS: u64 = 7 * 86_400;
+
+/// KEYS[1] record, KEYS[2] address index; ARGV slot, json, address.
+/// Returns 1 when written, 0 when the stored record is newer.
+const UPSERT_SCRIPT: &str = r#"
+local current = redis.call('HGET', KEYS[1], 'slot')
+if current and tonumber(current) > tonumber(ARGV[1]) then
+  return 0
+end
+redis.call('HSET', KEYS[1], 'slot', ARGV[1], 'data', ARGV[2])
+redis.call('HSET', KEYS[2], ARGV[3], KEYS[1])
+return 1
+"#;
+
+pub fn vault_key(owner: &str) -> String {
+    format!("indexer:vault:{}", owner)
+}
+
+pub fn allowance_key(address: &str) -> String {
+    format!("indexer:allowance:{}", address)
+}
+
+/// Sorted set of a user's allowance addresses, scored by nonce
+pub fn user_allowances_key(user: &str) -> String {
+    format!("indexer:allowances:{}", user)
+}
+
+pub fn nonce_registry_key(user: &str) -> String {
+    format!("indexer:nonce_registry:{}", user)
+}
+
+pub fn processed_bet_key(bet_id: &str) -> String {
+    format!("indexer:processed_bet:{}", bet_id)
+}
+
+/// Sorted set of a user's deposit records, scored by slot
+pub fn user_deposits_key(owner: &str) -> String {
+    format!("indexer:deposits:{}", owner)
+}
+
+fn deposit_seen_key(deposit: &IndexedDeposit) -> String {
+    format!("indexer:deposit_seen:{}:{}", deposit.signature, deposit.instruction_index)
+}
+
+/// Record key, JSON and slot of an indexed account
+fn record_parts(account: &IndexedAccount) -> Result<(String, String, &str, u64)> {
+    Ok(match account {
+        IndexedAccount::Vault(v) => (vault_key(&v.owner), serde_json::to_string(v)?, v.address.as_str(), v.slot),
+        IndexedAccount::Allowance(a) => (allowance_key(&a.address), serde_json::to_string(a)?, a.address.as_str(), a.slot),
+        IndexedAccount::NonceRegistry(r) => {
+            (nonce_registry_key(&r.user), serde_json::to_string(r)?, r.address.as_str(), r.slot)
+        }
+        IndexedAccount::ProcessedBet(b) => {
+            (processed_bet_key(&b.bet_id), serde_json::to_string(b)?, b.address.as_str(), b.slot)
+        }
+    })
+}
+
+#[derive(Clone)]
+pub struct Store {
+    redis: ConnectionManager,
+}
+
+impl Store {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self { redis }
+    }
+
+    /// Write an account unless a newer version is already stored; returns whether it was written
+    pub async fn upsert(&self, account: &IndexedAccount) -> Result<bool> {
+        let mut redis = self.redis.clone();
+        let (key, json, address, slot) = record_parts(account)?;
+        let written: i32 = Script::new(UPSERT_SCRIPT)
+            .key(&key)
+            .key(ADDRESS_INDEX_KEY)
+            .arg(slot)
+            .arg(json)
+            .arg(address)
+            .invoke_async(&mut redis)
+            .await?;
+
+        if let IndexedAccount::Allowance(allowance) = account {
+            let _: () = redis
+                .zadd(user_allowances_key(&allowance.user), &allowance.address, allowance.nonce)
+                .await?;
+        }
+        Ok(written == 1)
+    }
+
+    /// Drop the record of a closed account
+    pub async fn remove(&self, address: &str) -> Result<()> {
+        let mut redis = self.redis.clone();
+        let key: Option<String> = redis.hget(ADDRESS_INDEX_KEY, address).await?;
+        let Some(key) = key else {
+            return Ok(());
+        };
+
+        let mut pipe = redis::pipe();
+        if key.starts_with("indexer:allowance:") {
+            if let Some(allowance) = self.get::<IndexedAllowance>(&key).await? {
+                pipe.zrem(user_allowances_key(&allowance.user), address).ignore();
+            }
+        }
+        pipe.del(&key).ignore().hdel(ADDRESS_INDEX_KEY, address).ignore();
+        let _: () = pipe.query_async(&mut redis).await?;
+        Ok(())
+    }
+
+    /// Store a deposit and announce it on the stream; returns false if it was already seen
+    pub async fn record_deposit(&self, deposit: &IndexedDeposit) -> Result<bool> {
+        let mut redis = self.redis.clone();
+        let first: Option<String> = redis::cmd("SET")
+            .arg(deposit_seen_key(deposit))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(DEPOSIT_SEEN_TTL_SECONDS)
+            .query_async(&mut redis)
+            .await?;
+        if first.is_none() {
+            return Ok(false);
+        }
+
+        let json = serde_json::to_string(deposit)?;
+        let key = user_deposits_key(&deposit.owner);
+        let _: () = redis::pipe()
+            .zadd(&key, &json, deposit.slot)
+            .ignore()
+            .zremrangebyrank(&key, 0, -DEPOSITS_PER_USER - 1)
+            .ignore()
+            .xadd_maxlen(DEPOSITS_STREAM_KEY, StreamMaxlen::Approx(DEPOSITS_STREAM_MAXLEN), "*", &[("payload", &json)])
+            .ignore()
+            .query_async(&mut redis)
+            .await?;
+        Ok(true)
+    }
+
+    /// The user's newest `limit` deposits
+    pub async fn user_deposits(&self, owner: &str, limit: usize) -> Result<Vec<IndexedDeposit>> {
+        let mut redis = self.redis.clone();
+        let records: Vec<String> = redis
+            .zrevrange(user_deposits_key(owner), 0, limit.max(1) as isize - 1)
+            .await?;
+        records
+            .iter()
+            .map(|record| serde_json::from_str(record).with_context(|| format!("Corrupt deposit record of {}", owner)))
+            .collect()
+    }
+
+    async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let mut redis = self.redis.clone();
+        let data: Option<String> = redis.hget(key, "data").await?;
+        data.map(|d| serde_json::from_str(&d).with_context(|| format!("Corrupt indexer record {}", key)))
+            .transpose()
+    }
+
+    pub async fn vault(&self, owner: &str) -> Result<Option<IndexedVault>> {
+        self.get(&vault_key(owner)).await
+    }
+
+    pub async fn allowance(&self, address: &str) -> Result<Option<IndexedAllowance>> {
+        self.get(&allowance_key(address)).await
+    }
+
+    pub async fn processed_bet(&self, bet_id: &str) -> Result<Option<IndexedProcessedBet>> {
+        self.get(&processed_bet_key(bet_id)).await
+    }
+
+    /// The user's newest `limit` allowances and their nonce registry
+    pub async fn user_allowances(&self, user: &str, limit: usize) -> Result<UserAllowances> {
+        let mut redis = self.redis.clone();
+        let addresses: Vec<String> = redis
+            .zrevrange(user_allowances_key(user), 0, limit.max(1) as isize - 1)
+            .await?;
+
+        let mut allowances = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            if let Some(allowance) = self.get::<IndexedAllowance>(&allowance_key(&address)).await? {
+                allowances.push(allowance);
+            }
+        }
+        let registry = self.get::<IndexedNonceRegistry>(&nonce_registry_key(user)).await?;
+        Ok(UserAllowances { next_nonce: registry.map(|r| r.next_nonce), allowances })
+    }
+
+    pub async fn set_status_slot(&self, field: &str, slot: u64) -> Result<()> {
+        let mut redis = self.redis.clone();
+        let _: () = redis.hset(STATUS_KEY, field, slot).await?;
+        Ok(())
+    }
+
+    pub async fn status(&self) -> Result<IndexerStatus> {
+        let mut redis = self.redis.clone();
+        let (snapshot_slot, last_account_slot, last_log_slot): (Option<u64>, Option<u64>, Option<u64>) = redis::cmd("HMGET")
+            .arg(STATUS_KEY)
+            .arg(&["snapshot_slot", "last_account_slot", "last_log_slot"])
+            .query_async(&mut redis)
+            .await?;
+        Ok(IndexerStatus { snapshot_slot, last_account_slot, last_log_slot })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_keyed_by_lookup_field() {
+        let allowance = IndexedAccount::Allowance(IndexedAllowance {
+            address: "A".to_string(),
+            user: "U".to_string(),
+            token_mint: "M".to_string(),
+            amount: 10,
+            spent: 0,
+            expires_at: 0,
+            nonce: 3,
+            revoked: false,
+            spend_count: 0,
+            max_single_spend: 0,
+            max_spend_per_hour: 0,
+            slot: 9,
+        });
+        let (key, json, address, slot) = record_parts(&allowance).unwrap();
+        assert_eq!(key, "indexer:allowance:A");
+        assert_eq!((address, slot), ("A", 9));
+        assert!(json.contains("\"nonce\":3"));
+
+        assert_eq!(vault_key("U"), "indexer:vault:U");
+        assert_eq!(user_allowances_key("U"), "indexer:allowances:U");
+        assert_eq!(nonce_registry_key("U"), "indexer:nonce_registry:U");
+        assert_eq!(processed_bet_key("bet-1"), "indexer:processed_bet:bet-1");
+        assert_eq!(user_deposits_key("U"), "indexer:deposits:U");
+    }
+}
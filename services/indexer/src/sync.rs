@@ -0,0 +1,278 @@
+//! Keep the mirror in step with the chain
+//!
+//! Every (re)connect subscribes to program account changes first and then
+//! snapshots the program with `getProgramAccounts`; the store's slot guard
+//! makes the overlap harmless. Closed accounts are only seen by the
+//! subscription, so the periodic resync refreshes live accounts but cannot
+//! notice accounts closed while disconnected. The logs subscription picks out
+//! deposit transactions (see `deposits`) and re-reads the credited vault at
+//! once, so the new balance doesn't wait on the account notification.
+
+use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient};
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig, RpcTransactionLogsConfig,
+    RpcTransactionLogsFilter,
+};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{account::Account, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{UiLoadedAddresses, UiTransactionEncoding, UiTransactionTokenBalance};
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::accounts::{classify, AccountKind};
+use crate::config::Config;
+use crate::deposits::{self, DepositTransaction};
+use crate::store::Store;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Accounts refreshed by the periodic resync; processed bets never change once written
+const RESYNC_KINDS: [AccountKind; 3] = [AccountKind::Vault, AccountKind::Allowance, AccountKind::NonceRegistry];
+
+pub struct Indexer {
+    config: Config,
+    rpc: RpcClient,
+    store: Store,
+}
+
+impl Indexer {
+    pub fn new(config: Config, store: Store) -> Self {
+        let rpc = RpcClient::new_with_commitment(config.rpc_url.clone(), config.commitment);
+        Self { config, rpc, store }
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    async fn apply(&self, address: &Pubkey, account: &Account, slot: u64) -> Result<()> {
+        if account.lamports == 0 || account.data.is_empty() {
+            return self.store.remove(&address.to_string()).await;
+        }
+        match classify(address, account, slot) {
+            Ok(Some(indexed)) => {
+                self.store.upsert(&indexed).await?;
+            }
+            Ok(None) => {}
+            Err(e) => warn!(%address, error = %e, "Skipping undecodable program account"),
+        }
+        Ok(())
+    }
+
+    /// Re-read one account so the mirror doesn't wait for its notification
+    async fn refresh(&self, address: &Pubkey) -> Result<()> {
+        let response = self.rpc.get_account_with_commitment(address, self.config.commitment).await?;
+        match response.value {
+            Some(account) => self.apply(address, &account, response.context.slot).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Record the deposits of a transaction whose logs announced one
+    async fn record_deposits(&self, signature: &str) -> Result<()> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(self.config.commitment),
+            max_supported_transaction_version: Some(0),
+        };
+        let fetched = self
+            .rpc
+            .get_transaction_with_config(&Signature::from_str(signature)?, config)
+            .await
+            .with_context(|| format!("Failed to fetch deposit transaction {}", signature))?;
+        let transaction = fetched
+            .transaction
+            .transaction
+            .decode()
+            .with_context(|| format!("Undecodable deposit transaction {}", signature))?;
+        let meta = fetched.transaction.meta;
+
+        let (loaded, token_balances): (Option<UiLoadedAddresses>, Option<Vec<UiTransactionTokenBalance>>) = meta
+            .map(|meta| (meta.loaded_addresses.into(), meta.post_token_balances.into()))
+            .unwrap_or_default();
+        // Lookup table addresses follow the static keys, writable first
+        let mut account_keys = transaction.message.static_account_keys().to_vec();
+        for address in loaded.iter().flat_map(|loaded| loaded.writable.iter().chain(&loaded.readonly)) {
+            account_keys.push(Pubkey::from_str(address)?);
+        }
+        let token_mints: HashMap<u8, String> = token_balances
+            .unwrap_or_default()
+            .into_iter()
+            .map(|balance| (balance.account_index, balance.mint))
+            .collect();
+
+        let found = deposits::parse_deposits(
+            &self.config.program_id,
+            &DepositTransaction {
+                signature,
+                slot: fetched.slot,
+                block_time: fetched.block_time,
+                account_keys: &account_keys,
+                instructions: transaction.message.instructions(),
+                token_mints: &token_mints,
+            },
+        );
+        for deposit in &found {
+            if !self.store.record_deposit(deposit).await? {
+                continue;
+            }
+            info!(
+                signature,
+                owner = %deposit.owner,
+                amount = deposit.amount,
+                token_mint = ?deposit.token_mint,
+                "Deposit detected"
+            );
+            if let Err(e) = self.refresh(&Pubkey::from_str(&deposit.vault)?).await {
+                warn!(vault = %deposit.vault, error = %e, "Failed to refresh credited vault");
+            }
+        }
+        Ok(())
+    }
+
+    /// Load every account of `kinds` from `getProgramAccounts`
+    pub async fn snapshot(&self, kinds: &[AccountKind]) -> Result<usize> {
+        // Read the slot first so every snapshot record is at most as new as its true slot
+        let slot = self.rpc.get_slot().await.context("Failed to fetch slot")?;
+        let mut count = 0;
+
+        for kind in kinds {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &kind.discriminator()))]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(self.config.commitment),
+                    ..Default::default()
+                },
+                with_context: None,
+            };
+            let accounts = self
+                .rpc
+                .get_program_accounts_with_config(&self.config.program_id, config)
+                .await
+                .with_context(|| format!("Failed to snapshot {} accounts", kind.account_name()))?;
+
+            for (address, account) in &accounts {
+                self.apply(address, account, slot).await?;
+            }
+            count += accounts.len();
+        }
+
+        self.store.set_status_slot("snapshot_slot", slot).await?;
+        info!(slot, accounts = count, "Program account snapshot loaded");
+        Ok(count)
+    }
+
+    async fn stream_accounts(&self) -> Result<()> {
+        let client = PubsubClient::new(&self.config.ws_url).await?;
+        let config = RpcProgramAccountsConfig {
+            filters: None,
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(self.config.commitment),
+                ..Default::default()
+            },
+            with_context: Some(true),
+        };
+        let (mut updates, unsubscribe) = client.program_subscribe(&self.config.program_id, Some(config)).await?;
+
+        // Subscribed first: anything changed during the snapshot arrives on the stream
+        self.snapshot(&AccountKind::ALL).await?;
+
+        while let Some(update) = updates.next().await {
+            let slot = update.context.slot;
+            let address = Pubkey::from_str(&update.value.pubkey)?;
+            let Some(account) = update.value.account.decode::<Account>() else {
+                warn!(%address, "Undecodable account notification");
+                continue;
+            };
+            self.apply(&address, &account, slot).await?;
+            self.store.set_status_slot("last_account_slot", slot).await?;
+        }
+
+        unsubscribe().await;
+        Ok(())
+    }
+
+    async fn stream_logs(&self) -> Result<()> {
+        let client = PubsubClient::new(&self.config.ws_url).await?;
+        let (mut logs, unsubscribe) = client
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![self.config.program_id.to_string()]),
+                RpcTransactionLogsConfig { commitment: Some(self.config.commitment) },
+            )
+            .await?;
+
+        while let Some(entry) = logs.next().await {
+            let slot = entry.context.slot;
+            match &entry.value.err {
+                None => debug!(slot, signature = %entry.value.signature, "Vault program transaction"),
+                Some(err) => debug!(slot, signature = %entry.value.signature, error = %err, "Failed vault program transaction"),
+            }
+            if entry.value.err.is_none() && deposits::mentions_deposit(&entry.value.logs) {
+                if let Err(e) = self.record_deposits(&entry.value.signature).await {
+                    warn!(signature = %entry.value.signature, error = %e, "Failed to record deposit");
+                }
+            }
+            self.store.set_status_slot("last_log_slot", slot).await?;
+        }
+
+        unsubscribe().await;
+        Ok(())
+    }
+
+    /// Follow program account changes, re-snapshotting on every reconnect
+    pub async fn follow_accounts(self: Arc<Self>) {
+        reconnect_forever("program accounts", || self.stream_accounts()).await
+    }
+
+    /// Follow transaction logs mentioning the program
+    pub async fn follow_logs(self: Arc<Self>) {
+        reconnect_forever("program logs", || self.stream_logs()).await
+    }
+
+    /// Periodically re-snapshot mutable accounts to heal missed notifications
+    pub async fn resync(self: Arc<Self>) {
+        if self.config.resync_interval_seconds == 0 {
+            return;
+        }
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.resync_interval_seconds));
+        // The first tick completes immediately and the subscription already snapshots on connect
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.snapshot(&RESYNC_KINDS).await {
+                warn!(error = %e, "Periodic resync failed");
+            }
+        }
+    }
+}
+
+async fn reconnect_forever<F, Fut>(name: &'static str, mut connect: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        match connect().await {
+            Ok(()) => {
+                warn!(subscription = name, "Subscription stream ended, reconnecting");
+                backoff = INITIAL_BACKOFF;
+            }
+            Err(e) => {
+                warn!(subscription = name, error = %e, backoff_secs = backoff.as_secs(), "Subscription failed, reconnecting");
+            }
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
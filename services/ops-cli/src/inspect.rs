@@ -0,0 +1,107 @@
+//! Read-only views of casino and per-user vault program state
+
+use anyhow::{Context, Result};
+use shared::vault::{
+    allowance_nonce_registry_pda, allowance_pda, casino_pda, casino_vault_pda, user_vault_pda, AllowanceAccount,
+    CasinoAccount, CasinoVaultAccount, NonceRegistryAccount,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn fetch(client: &RpcClient, address: &Pubkey) -> Result<Option<Account>> {
+    Ok(client
+        .get_account_with_commitment(address, client.commitment())
+        .with_context(|| format!("Failed to fetch account {}", address))?
+        .value)
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
+
+/// Casino config, vault balances and how far the tracked balance has drifted
+pub fn casino(client: &RpcClient, program_id: &Pubkey) -> Result<()> {
+    let casino = casino_pda(program_id);
+    let Some(account) = fetch(client, &casino)? else {
+        println!("Casino {} is not initialized", casino);
+        return Ok(());
+    };
+    let state = CasinoAccount::decode(&account.data)?;
+    println!("Casino {}", casino);
+    println!("  authority:  {}", state.authority);
+    println!("  processor:  {}", state.processor);
+    for extra in &state.extra_processors {
+        println!("              {}", extra);
+    }
+    println!("  treasury:   {}", state.treasury);
+    println!("  paused:     {}", state.paused);
+    println!("  total bets: {} ({} lamports volume)", state.total_bets, state.total_volume);
+    println!("  version:    {}", state.program_version);
+    if CasinoAccount::needs_migration(&account.data) {
+        println!("  layout:     outdated; run `ops-cli migrate`");
+    }
+
+    let vault = casino_vault_pda(&casino, program_id);
+    let Some(account) = fetch(client, &vault)? else {
+        println!("Casino vault {} is not initialized", vault);
+        return Ok(());
+    };
+    let state = CasinoVaultAccount::decode(&account.data)?;
+    let rent_reserve = client
+        .get_minimum_balance_for_rent_exemption(CasinoVaultAccount::LEN)
+        .context("Failed to fetch rent exemption")?;
+    let available = account.lamports.saturating_sub(rent_reserve);
+    println!("Casino vault {}", vault);
+    println!("  lamports:   {} (rent reserve {})", account.lamports, rent_reserve);
+    println!("  tracked:    {}", state.sol_balance);
+    println!("  available:  {}", available);
+    if available != state.sol_balance {
+        println!(
+            "  drift:      {} lamports; run `ops-cli reconcile` to sync",
+            available as i128 - state.sol_balance as i128
+        );
+    }
+    Ok(())
+}
+
+/// A user's vault, nonce registry and most recent allowances
+pub fn user(client: &RpcClient, program_id: &Pubkey, user: &Pubkey, allowances: u64) -> Result<()> {
+    let casino = casino_pda(program_id);
+
+    let vault = user_vault_pda(user, &casino, program_id);
+    match fetch(client, &vault)? {
+        Some(account) => println!("Vault {}: {} lamports", vault, account.lamports),
+        None => println!("Vault {}: not initialized", vault),
+    }
+
+    let registry = allowance_nonce_registry_pda(user, &casino, program_id);
+    let Some(account) = fetch(client, &registry)? else {
+        println!("Nonce registry {}: not initialized (no allowances yet)", registry);
+        return Ok(());
+    };
+    let state = NonceRegistryAccount::decode(&account.data)?;
+    println!("Nonce registry {}: next_nonce={}", registry, state.next_nonce);
+
+    let now = now();
+    for nonce in (0..state.next_nonce).rev().take(allowances as usize) {
+        let address = allowance_pda(user, &casino, nonce, program_id);
+        let Some(account) = fetch(client, &address)? else {
+            println!("  allowance #{} {}: closed", nonce, address);
+            continue;
+        };
+        let allowance = AllowanceAccount::decode(&account.data)?;
+        println!(
+            "  allowance #{} {}: {} remaining of {}, spent {} times, expires_at={}, revoked={}, active={}",
+            nonce,
+            address,
+            allowance.remaining(),
+            allowance.amount,
+            allowance.spend_count,
+            allowance.expires_at,
+            allowance.revoked,
+            allowance.is_active(now)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//! Vault program administration
+//!
+//! Wraps the casino admin instructions (initialize, pause, processor rotation
+//! and authorization, withdrawals, treasury top-ups, reconciliation, authority transfer, per-token
+//! bet limits, post-upgrade migration) and
+//! read-only state inspection. Every transaction can be sent with a local keypair, simulated
+//! with `--dry-run`, or exported with `--export` for offline co-signing. With
+//! `--multisig` the casino authority is a Squads vault and instructions are
+//! submitted as a multisig proposal instead.
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use shared::vault::{
+    build_add_processor_instruction, build_fund_casino_vault_instruction, build_initialize_casino_vault_instruction,
+    build_migrate_casino_instruction, build_migrate_instruction, build_reconcile_casino_vault_instruction,
+    build_remove_processor_instruction, build_set_paused_instruction, build_set_authority_instruction,
+    build_set_processor_instruction, build_set_token_config_instruction, build_withdraw_casino_funds_instruction,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, write_keypair_file, Keypair, Signer},
+    system_program,
+};
+use std::io::Read;
+use std::path::PathBuf;
+
+mod inspect;
+mod squads;
+mod tx;
+
+use squads::MultisigTarget;
+use tx::{Mode, Submitter};
+
+#[derive(Debug, Parser)]
+#[command(name = "ops-cli", version, about = "Vault program administration")]
+struct Cli {
+    #[arg(long, env = "SOLANA_RPC_URL", default_value = "http://localhost:8899")]
+    rpc_url: String,
+    #[arg(long, env = "VAULT_PROGRAM_ID")]
+    program_id: Pubkey,
+    /// Casino authority keypair used to sign, or a member keypair with --multisig
+    #[arg(long, env = "CASINO_AUTHORITY_KEYPAIR_PATH")]
+    keypair: Option<PathBuf>,
+    /// Authority pubkey when it is not the local keypair (e.g. an offline signer)
+    #[arg(long)]
+    authority: Option<Pubkey>,
+    /// Squads v4 multisig whose vault is the casino authority
+    #[arg(long, env = "CASINO_AUTHORITY_MULTISIG")]
+    multisig: Option<Pubkey>,
+    #[arg(long, default_value_t = 0, requires = "multisig")]
+    vault_index: u8,
+    /// Create the proposal without casting the member's approval
+    #[arg(long, requires = "multisig")]
+    no_approve: bool,
+    /// Fee payer, if not the authority (or the proposing member)
+    #[arg(long)]
+    fee_payer: Option<Pubkey>,
+    /// Durable nonce account, so exported transactions don't expire before co-signing
+    #[arg(long)]
+    nonce_account: Option<Pubkey>,
+    /// Simulate the transaction without sending it
+    #[arg(long, global = true, conflicts_with = "export")]
+    dry_run: bool,
+    /// Print the transaction, signed by --keypair where required, instead of sending it
+    #[arg(long, global = true)]
+    export: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// One-time casino and casino vault setup; the signer pays rent
+    InitCasino {
+        /// Casino admin, if different from the signer
+        #[arg(long)]
+        casino_authority: Option<Pubkey>,
+    },
+    /// Rotate the processor key allowed to settle bets
+    RotateProcessor {
+        #[arg(long, required_unless_present = "generate")]
+        new_processor: Option<Pubkey>,
+        /// Generate a new processor keypair at this path and rotate to it
+        #[arg(long, conflicts_with = "new_processor")]
+        generate: Option<PathBuf>,
+    },
+    /// Authorize an additional processor key, e.g. before a rotation or for another instance
+    AddProcessor {
+        #[arg(long, required_unless_present = "generate")]
+        processor: Option<Pubkey>,
+        /// Generate a new processor keypair at this path and authorize it
+        #[arg(long, conflicts_with = "processor")]
+        generate: Option<PathBuf>,
+    },
+    /// Revoke a processor key; removing the primary promotes the next one
+    RemoveProcessor {
+        #[arg(long)]
+        processor: Pubkey,
+    },
+    /// Resize a casino account created before multiple processors were supported
+    MigrateCasino,
+    /// Bring the casino account to the upgraded program's layout and stamp its version
+    Migrate,
+    /// Emergency pause: stops settlements on-chain
+    Pause,
+    /// Lift an emergency pause
+    Unpause,
+    /// Withdraw lamports from the casino vault to the authority
+    Withdraw {
+        #[arg(long)]
+        lamports: u64,
+    },
+    /// Top up the casino vault from the treasury; the signer (or --authority) is the treasury
+    Fund {
+        #[arg(long)]
+        lamports: u64,
+    },
+    /// Sync the casino vault's tracked balance with its actual lamports
+    Reconcile,
+    /// Create or update a mint's bet limits, in the mint's base units
+    SetTokenConfig {
+        /// Token mint; native SOL when omitted
+        #[arg(long)]
+        mint: Option<Pubkey>,
+        #[arg(long)]
+        min_bet: u64,
+        #[arg(long)]
+        max_bet: u64,
+        /// Reject new bets in this token while keeping its limits
+        #[arg(long)]
+        disable: bool,
+    },
+    /// Hand the casino authority to another key, e.g. a multisig vault
+    TransferAuthority {
+        #[arg(long)]
+        new_authority: Pubkey,
+    },
+    /// Print the vault PDA of --multisig, to use as the casino authority
+    MultisigVault,
+    /// Add the --keypair signature to an exported transaction and re-export it
+    Cosign {
+        /// Base64 transaction or export JSON; read from stdin when omitted
+        transaction: Option<String>,
+    },
+    /// Send a fully signed exported transaction
+    SubmitSigned {
+        /// Base64 transaction or export JSON; read from stdin when omitted
+        transaction: Option<String>,
+    },
+    /// Show casino config, vault balances and balance drift
+    InspectCasino,
+    /// Show a user's vault, allowance nonce registry and recent allowances
+    InspectUser {
+        user: Pubkey,
+        /// Most recent allowances to show
+        #[arg(long, default_value_t = 5)]
+        allowances: u64,
+    },
+}
+
+fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+    let client = RpcClient::new_with_commitment(cli.rpc_url.clone(), CommitmentConfig::confirmed());
+    let program_id = cli.program_id;
+
+    let multisig = cli.multisig.map(|multisig| MultisigTarget { multisig, vault_index: cli.vault_index });
+
+    match &cli.command {
+        Command::InspectCasino => return inspect::casino(&client, &program_id),
+        Command::InspectUser { user, allowances } => return inspect::user(&client, &program_id, user, *allowances),
+        Command::MultisigVault => {
+            let target = multisig.context("--multisig is required")?;
+            println!("{}", target.vault());
+            return Ok(());
+        }
+        Command::Cosign { transaction } => {
+            let mut transaction = tx::decode_transaction(&read_input(transaction.as_deref())?)?;
+            let path = cli.keypair.as_ref().context("--keypair is required to co-sign")?;
+            if !tx::partial_sign(&mut transaction, &read_keypair(path)?)? {
+                bail!("Keypair {} is not a signer of this transaction", path.display());
+            }
+            println!("{}", serde_json::to_string_pretty(&tx::export_json(&transaction)?)?);
+            return Ok(());
+        }
+        Command::SubmitSigned { transaction } => {
+            let transaction = tx::decode_transaction(&read_input(transaction.as_deref())?)?;
+            transaction.verify().context("Transaction is not fully signed")?;
+            return tx::send(&client, &transaction);
+        }
+        _ => {}
+    }
+
+    let mode = match (cli.dry_run, cli.export) {
+        (true, _) => Mode::DryRun,
+        (_, true) => Mode::Export,
+        _ => Mode::Send,
+    };
+    let signer = cli.keypair.as_ref().map(read_keypair).transpose()?;
+    let submitter = Submitter::new(&client, mode, signer, cli.authority, multisig)?
+        .with_approve(!cli.no_approve)
+        .with_fee_payer(cli.fee_payer)
+        .with_nonce_account(cli.nonce_account);
+    let authority = submitter.authority();
+
+    let instruction = match cli.command {
+        Command::InitCasino { casino_authority } => build_initialize_casino_vault_instruction(
+            &program_id,
+            &authority,
+            &casino_authority.unwrap_or(authority),
+        ),
+        Command::RotateProcessor { new_processor, generate } => {
+            let new_processor = match (new_processor, generate) {
+                (Some(pubkey), _) => pubkey,
+                (None, Some(path)) => generate_keypair(&path, mode)?,
+                (None, None) => bail!("--new-processor or --generate is required"),
+            };
+            println!("Rotating processor to {}", new_processor);
+            build_set_processor_instruction(&program_id, &authority, &new_processor)
+        }
+        Command::AddProcessor { processor, generate } => {
+            let processor = match (processor, generate) {
+                (Some(pubkey), _) => pubkey,
+                (None, Some(path)) => generate_keypair(&path, mode)?,
+                (None, None) => bail!("--processor or --generate is required"),
+            };
+            println!("Authorizing processor {}", processor);
+            build_add_processor_instruction(&program_id, &authority, &processor)
+        }
+        Command::RemoveProcessor { processor } => {
+            println!("Revoking processor {}", processor);
+            build_remove_processor_instruction(&program_id, &authority, &processor)
+        }
+        Command::MigrateCasino => build_migrate_casino_instruction(&program_id, &authority),
+        Command::Migrate => build_migrate_instruction(&program_id, &authority),
+        Command::Pause => build_set_paused_instruction(&program_id, &authority, true),
+        Command::Unpause => build_set_paused_instruction(&program_id, &authority, false),
+        Command::Withdraw { lamports } => build_withdraw_casino_funds_instruction(&program_id, &authority, lamports),
+        Command::Fund { lamports } => {
+            if lamports == 0 {
+                bail!("--lamports must be positive");
+            }
+            build_fund_casino_vault_instruction(&program_id, &authority, lamports)
+        }
+        Command::Reconcile => build_reconcile_casino_vault_instruction(&program_id, &authority),
+        Command::SetTokenConfig { mint, min_bet, max_bet, disable } => {
+            if min_bet == 0 || min_bet > max_bet {
+                bail!("--min-bet must be positive and at most --max-bet");
+            }
+            let mint = mint.unwrap_or(system_program::ID);
+            println!(
+                "Setting bet limits for {}: {}..={}{}",
+                mint,
+                min_bet,
+                max_bet,
+                if disable { " (bets disabled)" } else { "" }
+            );
+            build_set_token_config_instruction(&program_id, &authority, &mint, min_bet, max_bet, !disable)
+        }
+        Command::TransferAuthority { new_authority } => {
+            println!("Transferring casino authority to {}", new_authority);
+            build_set_authority_instruction(&program_id, &authority, &new_authority)
+        }
+        Command::InspectCasino
+        | Command::InspectUser { .. }
+        | Command::MultisigVault
+        | Command::Cosign { .. }
+        | Command::SubmitSigned { .. } => unreachable!("handled above"),
+    };
+
+    submitter.submit(&[instruction])
+}
+
+fn read_keypair(path: &PathBuf) -> Result<Keypair> {
+    read_keypair_file(path).map_err(|e| anyhow::anyhow!("Failed to read keypair {}: {}", path.display(), e))
+}
+
+fn read_input(arg: Option<&str>) -> Result<String> {
+    match arg {
+        Some(value) if value != "-" => Ok(value.to_string()),
+        _ => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input).context("Failed to read stdin")?;
+            Ok(input)
+        }
+    }
+}
+
+/// Write a fresh processor keypair; never overwrites and is skipped on dry runs
+fn generate_keypair(path: &PathBuf, mode: Mode) -> Result<Pubkey> {
+    let keypair = Keypair::new();
+    if mode == Mode::DryRun {
+        println!("Dry run: keypair not written to {}", path.display());
+        return Ok(keypair.pubkey());
+    }
+    if path.exists() {
+        bail!("{} already exists; refusing to overwrite", path.display());
+    }
+    write_keypair_file(&keypair, path)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .with_context(|| format!("Failed to write keypair to {}", path.display()))?;
+    println!("New processor keypair written to {}", path.display());
+    Ok(keypair.pubkey())
+}
@@ -0,0 +1,207 @@
+//! Squads v4 multisig proposals for vault admin instructions
+//!
+//! When the casino authority is a Squads vault PDA, admin instructions cannot be
+//! signed directly. Instead a member creates a vault transaction holding them,
+//! opens a proposal and (optionally) casts the first approval. Remaining
+//! members approve and execute from the Squads app once the threshold is met.
+
+use anyhow::{bail, Context, Result};
+use shared::vault::anchor_discriminator;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub const SQUADS_PROGRAM_ID: Pubkey = pubkey!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+
+const SEED_PREFIX: &[u8] = b"multisig";
+const SEED_VAULT: &[u8] = b"vault";
+const SEED_TRANSACTION: &[u8] = b"transaction";
+const SEED_PROPOSAL: &[u8] = b"proposal";
+
+/// Offset of `transaction_index` in the Multisig account:
+/// discriminator (8) | create_key (32) | config_authority (32) | threshold (2) | time_lock (4)
+const TRANSACTION_INDEX_OFFSET: usize = 8 + 32 + 32 + 2 + 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MultisigTarget {
+    pub multisig: Pubkey,
+    pub vault_index: u8,
+}
+
+impl MultisigTarget {
+    /// The vault PDA that acts as casino authority
+    pub fn vault(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[SEED_PREFIX, self.multisig.as_ref(), SEED_VAULT, &[self.vault_index]],
+            &SQUADS_PROGRAM_ID,
+        )
+        .0
+    }
+
+    fn transaction_pda(&self, index: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[SEED_PREFIX, self.multisig.as_ref(), SEED_TRANSACTION, &index.to_le_bytes()],
+            &SQUADS_PROGRAM_ID,
+        )
+        .0
+    }
+
+    pub fn proposal_pda(&self, index: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                SEED_PREFIX,
+                self.multisig.as_ref(),
+                SEED_TRANSACTION,
+                &index.to_le_bytes(),
+                SEED_PROPOSAL,
+            ],
+            &SQUADS_PROGRAM_ID,
+        )
+        .0
+    }
+
+    /// Index the next vault transaction will get
+    pub fn next_transaction_index(&self, client: &RpcClient) -> Result<u64> {
+        let account = client
+            .get_account(&self.multisig)
+            .with_context(|| format!("Failed to fetch multisig {}", self.multisig))?;
+        if account.owner != SQUADS_PROGRAM_ID {
+            bail!("{} is not a Squads v4 multisig", self.multisig);
+        }
+        let bytes = account
+            .data
+            .get(TRANSACTION_INDEX_OFFSET..TRANSACTION_INDEX_OFFSET + 8)
+            .context("Multisig account data too short")?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("slice is 8 bytes")) + 1)
+    }
+
+    /// Wrap `instructions` in vault_transaction_create + proposal_create, plus
+    /// proposal_approve when the creating member also approves
+    pub fn propose(
+        &self,
+        instructions: &[Instruction],
+        member: &Pubkey,
+        index: u64,
+        approve: bool,
+    ) -> Result<Vec<Instruction>> {
+        let transaction = self.transaction_pda(index);
+        let proposal = self.proposal_pda(index);
+
+        let mut create_data = anchor_discriminator("vault_transaction_create").to_vec();
+        create_data.push(self.vault_index);
+        create_data.push(0); // ephemeral_signers
+        let message = encode_transaction_message(instructions, &self.vault())?;
+        create_data.extend_from_slice(&(message.len() as u32).to_le_bytes());
+        create_data.extend_from_slice(&message);
+        create_data.push(0); // memo: None
+
+        let mut proposal_data = anchor_discriminator("proposal_create").to_vec();
+        proposal_data.extend_from_slice(&index.to_le_bytes());
+        proposal_data.push(0); // draft: false
+
+        let mut result = vec![
+            Instruction {
+                program_id: SQUADS_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new(self.multisig, false),
+                    AccountMeta::new(transaction, false),
+                    AccountMeta::new_readonly(*member, true),
+                    AccountMeta::new(*member, true),
+                    AccountMeta::new_readonly(system_program::ID, false),
+                ],
+                data: create_data,
+            },
+            Instruction {
+                program_id: SQUADS_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new_readonly(self.multisig, false),
+                    AccountMeta::new(proposal, false),
+                    AccountMeta::new_readonly(*member, true),
+                    AccountMeta::new(*member, true),
+                    AccountMeta::new_readonly(system_program::ID, false),
+                ],
+                data: proposal_data,
+            },
+        ];
+
+        if approve {
+            let mut approve_data = anchor_discriminator("proposal_approve").to_vec();
+            approve_data.push(0); // memo: None
+            result.push(Instruction {
+                program_id: SQUADS_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new_readonly(self.multisig, false),
+                    AccountMeta::new(*member, true),
+                    AccountMeta::new(proposal, false),
+                ],
+                data: approve_data,
+            });
+        }
+
+        Ok(result)
+    }
+}
+
+/// Squads `TransactionMessage`: the compiled message with u8/u16 length prefixes
+/// instead of Solana's compact-u16, and no blockhash
+fn encode_transaction_message(instructions: &[Instruction], vault: &Pubkey) -> Result<Vec<u8>> {
+    let message = Message::new(instructions, Some(vault));
+    let header = message.header;
+    let num_keys = message.account_keys.len();
+    let num_signers = header.num_required_signatures as usize;
+
+    let mut out = vec![
+        header.num_required_signatures,
+        header.num_required_signatures - header.num_readonly_signed_accounts,
+        u8::try_from(num_keys - num_signers - header.num_readonly_unsigned_accounts as usize)?,
+        u8::try_from(num_keys).context("Too many accounts for a vault transaction")?,
+    ];
+    for key in &message.account_keys {
+        out.extend_from_slice(key.as_ref());
+    }
+
+    out.push(u8::try_from(message.instructions.len()).context("Too many instructions")?);
+    for ix in &message.instructions {
+        out.push(ix.program_id_index);
+        out.push(u8::try_from(ix.accounts.len())?);
+        out.extend_from_slice(&ix.accounts);
+        out.extend_from_slice(&u16::try_from(ix.data.len())?.to_le_bytes());
+        out.extend_from_slice(&ix.data);
+    }
+
+    out.push(0); // address_table_lookups
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::vault::build_set_paused_instruction;
+
+    #[test]
+    fn test_transaction_message_layout() {
+        let target = MultisigTarget { multisig: Pubkey::new_unique(), vault_index: 0 };
+        let program_id = Pubkey::new_unique();
+        let vault = target.vault();
+        let ix = build_set_paused_instruction(&program_id, &vault, true);
+
+        let encoded = encode_transaction_message(&[ix], &vault).unwrap();
+        // One writable signer (the vault), one writable non-signer (casino), program id
+        assert_eq!(&encoded[..4], &[1, 1, 1, 3]);
+        assert_eq!(&encoded[4..36], vault.as_ref());
+        let ix_start = 4 + 3 * 32;
+        assert_eq!(encoded[ix_start], 1);
+        assert_eq!(encoded.len(), ix_start + 1 + 1 + 1 + 2 + 2 + 8 + 1);
+
+        let member = Pubkey::new_unique();
+        let proposal = target.propose(&[], &member, 5, true).unwrap();
+        assert_eq!(proposal.len(), 3);
+        assert_eq!(&proposal[1].data[8..16], &5u64.to_le_bytes());
+        assert_eq!(proposal[2].accounts[2].pubkey, target.proposal_pda(5));
+    }
+}
@@ -0,0 +1,304 @@
+//! Sign-and-send, dry-run simulation, or (partially signed) export of admin transactions
+
+use crate::squads::MultisigTarget;
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use serde_json::json;
+use solana_client::nonce_utils;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Sign with the local keypair and send
+    Send,
+    /// Simulate without signatures; nothing is sent
+    DryRun,
+    /// Print the transaction, signed by the local keypair where it can, for co-signers
+    Export,
+}
+
+struct Proposal {
+    target: MultisigTarget,
+    approve: bool,
+}
+
+pub struct Submitter<'a> {
+    client: &'a RpcClient,
+    mode: Mode,
+    signer: Option<Keypair>,
+    authority: Pubkey,
+    fee_payer: Pubkey,
+    proposal: Option<Proposal>,
+    nonce_account: Option<Pubkey>,
+}
+
+impl<'a> Submitter<'a> {
+    /// `authority` overrides the keypair's pubkey, e.g. an offline signer when exporting.
+    /// With `multisig` the authority is the Squads vault and the keypair is a member
+    /// that creates the proposal.
+    pub fn new(
+        client: &'a RpcClient,
+        mode: Mode,
+        signer: Option<Keypair>,
+        authority: Option<Pubkey>,
+        multisig: Option<MultisigTarget>,
+    ) -> Result<Self> {
+        let signer_pubkey = signer.as_ref().map(|s| s.pubkey());
+        let (authority, fee_payer) = match multisig {
+            Some(target) => {
+                let vault = target.vault();
+                if let Some(authority) = authority.filter(|a| *a != vault) {
+                    bail!("--authority {} is not the vault {} of multisig {}", authority, vault, target.multisig);
+                }
+                let Some(member) = signer_pubkey else {
+                    bail!("--keypair of a multisig member is required with --multisig");
+                };
+                (vault, member)
+            }
+            None => {
+                let authority = match (signer_pubkey, authority) {
+                    (_, Some(authority)) => authority,
+                    (Some(pubkey), None) => pubkey,
+                    (None, None) => bail!("Either --keypair or --authority is required"),
+                };
+                (authority, authority)
+            }
+        };
+        if mode == Mode::Send && signer.is_none() {
+            bail!("--keypair is required to send; use --export for offline signing");
+        }
+        Ok(Self {
+            client,
+            mode,
+            signer,
+            authority,
+            fee_payer,
+            proposal: multisig.map(|target| Proposal { target, approve: true }),
+            nonce_account: None,
+        })
+    }
+
+    /// Skip the creating member's approval on new proposals
+    pub fn with_approve(mut self, approve: bool) -> Self {
+        if let Some(proposal) = &mut self.proposal {
+            proposal.approve = approve;
+        }
+        self
+    }
+
+    pub fn with_fee_payer(mut self, fee_payer: Option<Pubkey>) -> Self {
+        if let Some(fee_payer) = fee_payer {
+            self.fee_payer = fee_payer;
+        }
+        self
+    }
+
+    /// Use a durable nonce so exported transactions don't expire while co-signers sign
+    pub fn with_nonce_account(mut self, nonce_account: Option<Pubkey>) -> Self {
+        self.nonce_account = nonce_account;
+        self
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        self.authority
+    }
+
+    pub fn submit(&self, instructions: &[Instruction]) -> Result<()> {
+        let mut instructions = match &self.proposal {
+            Some(proposal) => {
+                let member = self.signer.as_ref().expect("checked in Submitter::new").pubkey();
+                let index = proposal.target.next_transaction_index(self.client)?;
+                println!(
+                    "Proposing vault transaction #{} on multisig {} (proposal {})",
+                    index,
+                    proposal.target.multisig,
+                    proposal.target.proposal_pda(index)
+                );
+                proposal.target.propose(instructions, &member, index, proposal.approve)?
+            }
+            None => instructions.to_vec(),
+        };
+
+        let blockhash = match self.nonce_account {
+            Some(nonce_account) => {
+                let account = nonce_utils::get_account_with_commitment(self.client, &nonce_account, self.client.commitment())
+                    .with_context(|| format!("Failed to fetch nonce account {}", nonce_account))?;
+                let data = nonce_utils::data_from_account(&account)
+                    .with_context(|| format!("{} is not an initialized nonce account", nonce_account))?;
+                instructions.insert(0, system_instruction::advance_nonce_account(&nonce_account, &data.authority));
+                data.blockhash()
+            }
+            None => self.client.get_latest_blockhash().context("Failed to fetch blockhash")?,
+        };
+        let message = Message::new_with_blockhash(&instructions, Some(&self.fee_payer), &blockhash);
+        let mut transaction = Transaction::new_unsigned(message);
+
+        match self.mode {
+            Mode::Export => {
+                if let Some(signer) = &self.signer {
+                    partial_sign(&mut transaction, signer)?;
+                }
+                println!("{}", serde_json::to_string_pretty(&export_json(&transaction)?)?);
+            }
+            Mode::DryRun => {
+                let result = self
+                    .client
+                    .simulate_transaction_with_config(
+                        &transaction,
+                        RpcSimulateTransactionConfig {
+                            sig_verify: false,
+                            replace_recent_blockhash: self.nonce_account.is_none(),
+                            ..Default::default()
+                        },
+                    )
+                    .context("Simulation request failed")?
+                    .value;
+                for line in result.logs.unwrap_or_default() {
+                    println!("  {}", line);
+                }
+                match result.err {
+                    Some(err) => bail!("Simulation failed: {}", err),
+                    None => println!(
+                        "Simulation succeeded ({} compute units); nothing was sent",
+                        result.units_consumed.unwrap_or_default()
+                    ),
+                }
+            }
+            Mode::Send => {
+                let signer = self.signer.as_ref().expect("checked in Submitter::new");
+                partial_sign(&mut transaction, signer)?;
+                let missing = missing_signers(&transaction);
+                if !missing.is_empty() {
+                    bail!("Missing signatures from {:?}; use --export and co-sign", missing);
+                }
+                send(self.client, &transaction)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sign with `signer` if it is one of the transaction's required signers
+pub fn partial_sign(transaction: &mut Transaction, signer: &Keypair) -> Result<bool> {
+    if !transaction.message.signer_keys().contains(&&signer.pubkey()) {
+        return Ok(false);
+    }
+    let blockhash = transaction.message.recent_blockhash;
+    transaction.try_partial_sign(&[signer], blockhash).context("Failed to sign transaction")?;
+    Ok(true)
+}
+
+pub fn send(client: &RpcClient, transaction: &Transaction) -> Result<()> {
+    let signature = client.send_and_confirm_transaction(transaction).context("Transaction failed")?;
+    println!("Confirmed: {}", signature);
+    Ok(())
+}
+
+fn missing_signers(transaction: &Transaction) -> Vec<Pubkey> {
+    transaction
+        .message
+        .signer_keys()
+        .into_iter()
+        .zip(&transaction.signatures)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .map(|(pubkey, _)| *pubkey)
+        .collect()
+}
+
+/// Accepts either raw base64 or the JSON printed by `--export`
+pub fn decode_transaction(input: &str) -> Result<Transaction> {
+    let input = input.trim();
+    let encoded = if input.starts_with('{') {
+        let exported: serde_json::Value = serde_json::from_str(input).context("Invalid export JSON")?;
+        exported["transaction_base64"]
+            .as_str()
+            .context("Export JSON has no transaction_base64")?
+            .to_string()
+    } else {
+        input.to_string()
+    };
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .context("Transaction is not valid base64")?;
+    bincode::deserialize(&bytes).context("Failed to deserialize transaction")
+}
+
+/// Transaction with its signature status and decoded instructions, for co-signers
+/// and Squads-style import
+pub fn export_json(transaction: &Transaction) -> Result<serde_json::Value> {
+    let bytes = bincode::serialize(transaction).context("Failed to serialize transaction")?;
+    let message = &transaction.message;
+    let instructions: Vec<_> = message
+        .instructions
+        .iter()
+        .map(|ix| {
+            json!({
+                "program_id": message.account_keys[ix.program_id_index as usize].to_string(),
+                "accounts": ix.accounts.iter().map(|&index| json!({
+                    "pubkey": message.account_keys[index as usize].to_string(),
+                    "is_signer": message.is_signer(index as usize),
+                    "is_writable": message.is_writable(index as usize),
+                })).collect::<Vec<_>>(),
+                "data_base58": solana_sdk::bs58::encode(&ix.data).into_string(),
+            })
+        })
+        .collect();
+    let missing = missing_signers(transaction);
+    let signers: Vec<_> = message
+        .signer_keys()
+        .into_iter()
+        .map(|pubkey| json!({ "pubkey": pubkey.to_string(), "signed": !missing.contains(pubkey) }))
+        .collect();
+
+    Ok(json!({
+        "fee_payer": message.account_keys.first().map(|k| k.to_string()),
+        "recent_blockhash": message.recent_blockhash.to_string(),
+        "signers": signers,
+        "complete": missing.is_empty(),
+        "transaction_base64": base64::engine::general_purpose::STANDARD.encode(bytes),
+        "message_base58": solana_sdk::bs58::encode(message.serialize()).into_string(),
+        "instructions": instructions,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::vault::build_set_paused_instruction;
+    use solana_sdk::hash::Hash;
+
+    #[test]
+    fn test_export_round_trip() {
+        let program_id = Pubkey::new_unique();
+        let authority = Keypair::new();
+        let fee_payer = Keypair::new();
+        let ix = build_set_paused_instruction(&program_id, &authority.pubkey(), true);
+        let blockhash = Hash::new_unique();
+        let message = Message::new_with_blockhash(&[ix], Some(&fee_payer.pubkey()), &blockhash);
+        let mut transaction = Transaction::new_unsigned(message);
+        assert!(partial_sign(&mut transaction, &fee_payer).unwrap());
+        assert!(!partial_sign(&mut transaction, &Keypair::new()).unwrap());
+
+        let exported = export_json(&transaction).unwrap();
+        assert_eq!(exported["fee_payer"], fee_payer.pubkey().to_string());
+        assert_eq!(exported["instructions"][0]["accounts"][1]["is_signer"], true);
+        assert_eq!(exported["signers"][0]["signed"], true);
+        assert_eq!(exported["signers"][1]["signed"], false);
+        assert_eq!(exported["complete"], false);
+
+        // A co-signer adds their signature to the exported bytes as-is
+        let mut decoded = decode_transaction(&exported.to_string()).unwrap();
+        assert!(partial_sign(&mut decoded, &authority).unwrap());
+        assert!(missing_signers(&decoded).is_empty());
+        assert!(decoded.verify().is_ok());
+    }
+}
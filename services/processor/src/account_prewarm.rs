@@ -0,0 +1,292 @@
+//! Account pre-warm for fetched settlements
+//!
+//! A settlement transaction needs the user's vault and, for SPL allowances,
+//! the user's and the casino's token accounts. Found missing while a worker
+//! builds the batch, a vault or user token account fails the whole batch and
+//! a casino token account costs batch space to create. When the coordinator
+//! fetches a page it hands the settlements here; one `getMultipleAccounts`
+//! pass in the background looks those accounts up, and the missing ones are
+//! created in their own transactions, paid by the processor, well before a
+//! worker gets to them. Vaults are only created with `PROCESSOR_AUTO_INIT_VAULTS`,
+//! as for the inline `initialize_vault_for`.
+
+use anyhow::{Context, Result};
+use shared::vault::AllowanceAccount;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+
+use crate::blockchain_client::GameSettlementInfo;
+use crate::config::SolanaConfig;
+use crate::solana_client::SolanaClientPool;
+use crate::solana_instructions::{build_create_ata_instruction, build_initialize_vault_for_instruction};
+use crate::solana_pda::{derive_casino_pda, derive_user_vault_pda};
+
+/// `getMultipleAccounts` accepts at most 100 addresses
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+/// Account creations sent per transaction
+const MAX_CREATIONS_PER_TX: usize = 4;
+
+/// A user whose settlement is about to be built
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Target {
+    deployment: usize,
+    owner: Pubkey,
+    allowance: Option<Pubkey>,
+}
+
+/// An account to create ahead of the settlement that needs it
+#[derive(Debug, Clone)]
+struct Creation {
+    address: Pubkey,
+    kind: &'static str,
+    instruction: Instruction,
+}
+
+pub struct AccountPrewarm {
+    solana_client: Arc<SolanaClientPool>,
+    processor_keypair: Arc<Keypair>,
+    solana: SolanaConfig,
+    auto_init_vaults: bool,
+    /// Accounts whose creation is under way, so overlapping pages don't repeat it
+    in_flight: Mutex<HashSet<Pubkey>>,
+}
+
+impl AccountPrewarm {
+    pub fn new(
+        solana_client: Arc<SolanaClientPool>,
+        processor_keypair: Arc<Keypair>,
+        solana: SolanaConfig,
+        auto_init_vaults: bool,
+    ) -> Self {
+        Self {
+            solana_client,
+            processor_keypair,
+            solana,
+            auto_init_vaults,
+            in_flight: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Look up and create the accounts `settlements` need, without waiting for it
+    pub fn spawn(self: &Arc<Self>, settlements: &[GameSettlementInfo]) {
+        let targets = self.targets(settlements);
+        if targets.is_empty() {
+            return;
+        }
+        let prewarm = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = prewarm.prewarm(targets).await {
+                warn!(error = %e, "Account pre-warm failed");
+                metrics::counter!("account_prewarm_failures_total").increment(1);
+            }
+        });
+    }
+
+    fn targets(&self, settlements: &[GameSettlementInfo]) -> Vec<Target> {
+        let mut targets: Vec<Target> = settlements
+            .iter()
+            .filter_map(|s| {
+                let owner = Pubkey::from_str(&s.player_address).ok()?;
+                let deployment = self.solana.deployment_for(s.casino_id.as_deref());
+                Some(Target {
+                    deployment: self.solana.deployments.iter().position(|d| d.name == deployment.name)?,
+                    owner,
+                    allowance: s.allowance_pda.as_deref().and_then(|pda| Pubkey::from_str(pda).ok()),
+                })
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        targets.sort_by_key(|target| (target.deployment, target.owner));
+        targets
+    }
+
+    async fn prewarm(&self, targets: Vec<Target>) -> Result<()> {
+        let client = self
+            .solana_client
+            .get_healthy_client_or_any()
+            .await
+            .context("No Solana RPC clients configured")?;
+        let processor = self.processor_keypair.pubkey();
+
+        // Vaults and allowances first: the allowance names the mint, and so the token accounts
+        let mut first_pass = Vec::with_capacity(targets.len() * 2);
+        for target in &targets {
+            first_pass.push(self.user_vault(target));
+            first_pass.extend(target.allowance);
+        }
+        let accounts = self.fetch(&client, first_pass).await?;
+
+        let mut creations = Vec::new();
+        let mut token_accounts = Vec::new();
+        for target in &targets {
+            let deployment = &self.solana.deployments[target.deployment];
+            let user_vault = self.user_vault(target);
+            if accounts.get(&user_vault).is_some_and(Option::is_none) {
+                let discriminator = deployment.discriminators.initialize_vault_for.filter(|_| self.auto_init_vaults);
+                metrics::counter!(
+                    "account_prewarm_missing_total",
+                    "kind" => "vault",
+                    "action" => if discriminator.is_some() { "create" } else { "none" }
+                )
+                .increment(1);
+                if let Some(discriminator) = discriminator {
+                    let (casino, _) = derive_casino_pda(&deployment.program_id);
+                    creations.push(Creation {
+                        address: user_vault,
+                        kind: "vault",
+                        instruction: build_initialize_vault_for_instruction(
+                            &deployment.program_id,
+                            &discriminator,
+                            &user_vault,
+                            &casino,
+                            &processor,
+                            &target.owner,
+                        ),
+                    });
+                }
+            }
+
+            let mint = target
+                .allowance
+                .and_then(|allowance| accounts.get(&allowance).cloned().flatten())
+                .and_then(|account| AllowanceAccount::decode(&account.data).ok())
+                .map(|allowance| allowance.token_mint);
+            if let Some(mint) = mint {
+                token_accounts.extend(required_token_accounts(&target.owner, &deployment.program_id, &mint));
+            }
+        }
+
+        let token_accounts: Vec<_> =
+            token_accounts.into_iter().collect::<HashSet<_>>().into_iter().collect();
+        let existing = self.fetch(&client, token_accounts.iter().map(|(address, _, _)| *address).collect()).await?;
+        for (address, owner, mint) in token_accounts {
+            if existing.get(&address).is_some_and(Option::is_none) {
+                metrics::counter!("account_prewarm_missing_total", "kind" => "token_account", "action" => "create")
+                    .increment(1);
+                creations.push(Creation {
+                    address,
+                    kind: "token_account",
+                    instruction: build_create_ata_instruction(&processor, &owner, &mint)?,
+                });
+            }
+        }
+
+        debug!(users = targets.len(), creations = creations.len(), "Pre-warmed settlement accounts");
+        self.create(client, creations).await;
+        Ok(())
+    }
+
+    fn user_vault(&self, target: &Target) -> Pubkey {
+        let program_id = self.solana.deployments[target.deployment].program_id;
+        let (casino, _) = derive_casino_pda(&program_id);
+        derive_user_vault_pda(&target.owner, &casino, &program_id).0
+    }
+
+    /// Each address's account, `None` where it doesn't exist
+    async fn fetch(&self, client: &Arc<RpcClient>, addresses: Vec<Pubkey>) -> Result<HashMap<Pubkey, Option<Account>>> {
+        if addresses.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let client = client.clone();
+        let commitment = self.solana_client.commitments().read;
+        tokio::task::spawn_blocking(move || {
+            let mut accounts = HashMap::with_capacity(addresses.len());
+            for chunk in addresses.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+                let response = client
+                    .get_multiple_accounts_with_commitment(chunk, commitment)
+                    .context("Failed to fetch settlement accounts")?;
+                accounts.extend(chunk.iter().copied().zip(response.value));
+            }
+            Ok(accounts)
+        })
+        .await
+        .context("Account fetch task panicked")?
+    }
+
+    async fn create(&self, client: Arc<RpcClient>, creations: Vec<Creation>) {
+        let creations: Vec<Creation> = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            creations.into_iter().filter(|creation| in_flight.insert(creation.address)).collect()
+        };
+
+        for chunk in creations.chunks(MAX_CREATIONS_PER_TX) {
+            let instructions: Vec<Instruction> = chunk.iter().map(|creation| creation.instruction.clone()).collect();
+            let client = client.clone();
+            let keypair = self.processor_keypair.clone();
+            let blockhash_commitment = self.solana_client.commitments().blockhash;
+            let sent = tokio::task::spawn_blocking(move || -> Result<String> {
+                let (blockhash, _) = client.get_latest_blockhash_with_commitment(blockhash_commitment)?;
+                let transaction =
+                    Transaction::new_signed_with_payer(&instructions, Some(&keypair.pubkey()), &[keypair.as_ref()], blockhash);
+                Ok(client.send_and_confirm_transaction(&transaction)?.to_string())
+            })
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+
+            let result = if sent.is_ok() { "success" } else { "failure" };
+            for creation in chunk {
+                metrics::counter!("account_prewarm_created_total", "kind" => creation.kind, "result" => result)
+                    .increment(1);
+            }
+            let addresses: Vec<String> = chunk.iter().map(|creation| creation.address.to_string()).collect();
+            match sent {
+                Ok(signature) => info!(%signature, accounts = ?addresses, "Created accounts ahead of settlement"),
+                // The worker still finds them missing and handles it as before
+                Err(e) => warn!(error = %e, accounts = ?addresses, "Failed to create accounts ahead of settlement"),
+            }
+
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            for creation in chunk {
+                in_flight.remove(&creation.address);
+            }
+        }
+    }
+}
+
+/// Token accounts an SPL settlement touches, as (address, owner, mint); none for native SOL
+fn required_token_accounts(user: &Pubkey, program_id: &Pubkey, mint: &Pubkey) -> Vec<(Pubkey, Pubkey, Pubkey)> {
+    if *mint == system_program::ID || *mint == Pubkey::default() {
+        return Vec::new();
+    }
+    let (casino, _) = derive_casino_pda(program_id);
+    [*user, casino]
+        .into_iter()
+        .map(|owner| (get_associated_token_address(&owner, mint), owner, *mint))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_token_accounts() {
+        let (user, program_id, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(required_token_accounts(&user, &program_id, &system_program::ID).is_empty());
+        assert!(required_token_accounts(&user, &program_id, &Pubkey::default()).is_empty());
+
+        let accounts = required_token_accounts(&user, &program_id, &mint);
+        let (casino, _) = derive_casino_pda(&program_id);
+        assert_eq!(
+            accounts,
+            vec![
+                (get_associated_token_address(&user, &mint), user, mint),
+                (get_associated_token_address(&casino, &mint), casino, mint),
+            ]
+        );
+    }
+}
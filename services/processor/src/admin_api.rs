@@ -0,0 +1,60 @@
+//! Read-only admin endpoints, served alongside `/metrics`
+//!
+//! - `GET /health`: `paused` while any deployment's casino is paused on-chain
+//! - `GET /admin/workers`: per-worker progress plus coordinator cycle stats
+//! - `GET /admin/batches/inflight`: batches currently being processed, oldest first
+
+use axum::{extract::State, routing::get, Json, Router};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::progress::{CoordinatorStats, InflightBatch, ProgressRegistry, WorkerSnapshot};
+
+#[derive(Debug, Serialize)]
+struct WorkersResponse {
+    workers: Vec<WorkerSnapshot>,
+    coordinator: CoordinatorStats,
+}
+
+#[derive(Debug, Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    paused_deployments: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct InflightResponse {
+    count: usize,
+    batches: Vec<InflightBatch>,
+}
+
+pub fn router(progress: Arc<ProgressRegistry>) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/admin/workers", get(workers))
+        .route("/admin/batches/inflight", get(inflight_batches))
+        .with_state(progress)
+}
+
+async fn health(State(progress): State<Arc<ProgressRegistry>>) -> Json<HealthResponse> {
+    let paused_deployments = progress.coordinator_stats().paused_deployments;
+    Json(HealthResponse {
+        status: if paused_deployments.is_empty() { "healthy" } else { "paused" },
+        paused_deployments,
+    })
+}
+
+async fn workers(State(progress): State<Arc<ProgressRegistry>>) -> Json<WorkersResponse> {
+    Json(WorkersResponse {
+        workers: progress.workers_snapshot(),
+        coordinator: progress.coordinator_stats(),
+    })
+}
+
+async fn inflight_batches(State(progress): State<Arc<ProgressRegistry>>) -> Json<InflightResponse> {
+    let batches = progress.inflight_batches();
+    Json(InflightResponse {
+        count: batches.len(),
+        batches,
+    })
+}
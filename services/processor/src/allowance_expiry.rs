@@ -0,0 +1,104 @@
+//! Allowance expiry lookups for settlement prioritization
+//!
+//! A loss whose allowance expires before the stake is spent can no longer be
+//! settled, so the coordinator dispatches those first. The blockchain API may
+//! send `allowance_expires_at`; otherwise it is read from the allowance account.
+//! An allowance's expiry never changes, so lookups are cached per PDA.
+
+use anyhow::{Context, Result};
+use shared::vault::AllowanceAccount;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+use crate::blockchain_client::GameSettlementInfo;
+use crate::solana_client::SolanaClientPool;
+
+/// `getMultipleAccounts` accepts at most 100 addresses
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+pub struct AllowanceExpiryCache {
+    solana_client: Arc<SolanaClientPool>,
+    expiries: Mutex<HashMap<String, i64>>,
+}
+
+impl AllowanceExpiryCache {
+    pub fn new(solana_client: Arc<SolanaClientPool>) -> Self {
+        Self { solana_client, expiries: Mutex::new(HashMap::new()) }
+    }
+
+    /// Fill in `allowance_expires_at` where the blockchain API left it out
+    ///
+    /// Only PDAs of the given settlements stay cached, which bounds the cache
+    /// to the pending set.
+    pub async fn annotate(&self, settlements: &mut [GameSettlementInfo]) -> Result<()> {
+        let pending: HashSet<String> = settlements
+            .iter()
+            .filter_map(|s| s.allowance_pda.clone())
+            .collect();
+        let missing: Vec<String> = {
+            let mut expiries = self.expiries.lock().unwrap_or_else(|e| e.into_inner());
+            expiries.retain(|pda, _| pending.contains(pda));
+            settlements
+                .iter()
+                .filter(|s| s.allowance_expires_at.is_none())
+                .filter_map(|s| s.allowance_pda.as_ref())
+                .filter(|pda| !expiries.contains_key(*pda))
+                .cloned()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect()
+        };
+
+        let fetched = if missing.is_empty() { HashMap::new() } else { self.fetch(&missing).await? };
+
+        let mut expiries = self.expiries.lock().unwrap_or_else(|e| e.into_inner());
+        expiries.extend(fetched);
+        for settlement in settlements.iter_mut().filter(|s| s.allowance_expires_at.is_none()) {
+            settlement.allowance_expires_at = settlement
+                .allowance_pda
+                .as_ref()
+                .and_then(|pda| expiries.get(pda).copied());
+        }
+        Ok(())
+    }
+
+    async fn fetch(&self, pdas: &[String]) -> Result<HashMap<String, i64>> {
+        let addresses: Vec<Pubkey> = pdas.iter().filter_map(|pda| Pubkey::from_str(pda).ok()).collect();
+        let client = self
+            .solana_client
+            .get_healthy_client_or_any()
+            .await
+            .context("No Solana RPC clients configured")?;
+        let commitment = self.solana_client.commitments().read;
+
+        let accounts = tokio::task::spawn_blocking(move || -> Result<Vec<_>> {
+            let mut accounts = Vec::with_capacity(addresses.len());
+            for chunk in addresses.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+                let response = client
+                    .get_multiple_accounts_with_commitment(chunk, commitment)
+                    .context("Failed to fetch allowance accounts")?;
+                accounts.extend(chunk.iter().copied().zip(response.value));
+            }
+            Ok(accounts)
+        })
+        .await
+        .context("Allowance fetch task panicked")??;
+
+        let mut expiries = HashMap::with_capacity(accounts.len());
+        for (address, account) in accounts {
+            // Closed allowances can't be spent from anyway; leave them unprioritized
+            let Some(account) = account else { continue };
+            match AllowanceAccount::decode(&account.data) {
+                Ok(allowance) => {
+                    expiries.insert(address.to_string(), allowance.expires_at);
+                }
+                Err(e) => warn!(%address, error = %e, "Undecodable allowance account"),
+            }
+        }
+        debug!(requested = pdas.len(), found = expiries.len(), "Fetched allowance expiries");
+        Ok(expiries)
+    }
+}
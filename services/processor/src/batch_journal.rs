@@ -0,0 +1,79 @@
+//! Failed batch journal
+//!
+//! When a coordinator batch finishes with failed settlements, the worker records
+//! its membership in Redis under `settlement:batch:{batch_id}` so `processor replay
+//! --batch <id>` can find the transactions later. Entries expire after the TTL.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::coordinator::SettlementBatch;
+
+const BATCH_KEY_PREFIX: &str = "settlement:batch:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRecord {
+    pub batch_id: String,
+    pub batch_type: String,
+    pub lane: String,
+    pub worker_id: usize,
+    pub tx_ids: Vec<u64>,
+    pub failed_tx_ids: Vec<u64>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl BatchRecord {
+    pub fn new(batch: &SettlementBatch, worker_id: usize, failed_tx_ids: Vec<u64>) -> Self {
+        Self {
+            batch_id: batch.batch_id.clone(),
+            batch_type: format!("{:?}", batch.batch_type),
+            lane: batch.lane.as_str().to_string(),
+            worker_id,
+            tx_ids: batch.settlements.iter().map(|s| s.transaction_id).collect(),
+            failed_tx_ids,
+            recorded_at: Utc::now(),
+        }
+    }
+}
+
+pub struct BatchJournal {
+    redis: ConnectionManager,
+    ttl: Duration,
+}
+
+impl BatchJournal {
+    pub async fn connect(redis_url: &str, ttl: Duration) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("Invalid Redis URL")?;
+        let redis = client
+            .get_connection_manager()
+            .await
+            .context("Failed to connect to Redis for batch journal")?;
+
+        Ok(Self { redis, ttl })
+    }
+
+    pub async fn record(&self, record: &BatchRecord) -> Result<()> {
+        let payload = serde_json::to_string(record)?;
+        let mut conn = self.redis.clone();
+        let _: () = conn
+            .set_ex(batch_key(&record.batch_id), payload, self.ttl.as_secs().max(1))
+            .await
+            .context("Failed to record batch")?;
+        Ok(())
+    }
+
+    pub async fn load(&self, batch_id: &str) -> Result<Option<BatchRecord>> {
+        let mut conn = self.redis.clone();
+        let raw: Option<String> = conn.get(batch_key(batch_id)).await.context("Failed to load batch")?;
+        raw.map(|raw| serde_json::from_str(&raw).context("Corrupt batch record"))
+            .transpose()
+    }
+}
+
+fn batch_key(batch_id: &str) -> String {
+    format!("{}{}", BATCH_KEY_PREFIX, batch_id)
+}
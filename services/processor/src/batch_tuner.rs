@@ -0,0 +1,152 @@
+//! Batch size autotuning
+//!
+//! A fixed `COORDINATOR_BATCH_MAX_SIZE` wastes throughput when settlement
+//! transactions are small and fails when they are large. The tuner keeps a
+//! moving average of serialized bytes and compute units per bet (from preflight
+//! simulation) and sizes batches to fit both the packet size and the compute
+//! budget, within the configured min/max bounds.
+
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use std::sync::Mutex;
+
+/// Fraction of the packet and compute limits a batch may use
+const TARGET_UTILIZATION: f64 = 0.9;
+/// Weight of the newest observation in the moving averages
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Resource usage of one submitted batch
+#[derive(Debug, Clone, Copy)]
+pub struct BatchObservation {
+    pub bet_count: usize,
+    /// Serialized transaction size in bytes
+    pub tx_bytes: usize,
+    /// Compute units consumed in simulation, when the RPC reported them
+    pub compute_units: Option<u64>,
+}
+
+#[derive(Debug)]
+struct TunerState {
+    size: usize,
+    bytes_per_bet: Option<f64>,
+    compute_units_per_bet: Option<f64>,
+}
+
+#[derive(Debug)]
+pub struct BatchSizeTuner {
+    enabled: bool,
+    min: usize,
+    max: usize,
+    compute_unit_limit: u64,
+    state: Mutex<TunerState>,
+}
+
+fn ewma(current: Option<f64>, sample: f64) -> f64 {
+    current.map_or(sample, |avg| avg + EWMA_ALPHA * (sample - avg))
+}
+
+impl BatchSizeTuner {
+    /// Starts at `max`; when disabled the size never moves
+    pub fn new(enabled: bool, min: usize, max: usize, compute_unit_limit: u64) -> Self {
+        let min = min.max(1);
+        let max = max.max(min);
+        metrics::gauge!("batch_autotune_size").set(max as f64);
+        Self {
+            enabled,
+            min,
+            max,
+            compute_unit_limit,
+            state: Mutex::new(TunerState {
+                size: max,
+                bytes_per_bet: None,
+                compute_units_per_bet: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TunerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Bets per batch to use now
+    pub fn current(&self) -> usize {
+        self.lock().size
+    }
+
+    /// Feed back a successfully simulated batch; returns the new size.
+    /// Shrinks straight to the target, but grows one bet at a time.
+    pub fn record(&self, observation: BatchObservation) -> usize {
+        let mut state = self.lock();
+        if !self.enabled || observation.bet_count == 0 {
+            return state.size;
+        }
+
+        let bets = observation.bet_count as f64;
+        let bytes_per_bet = ewma(state.bytes_per_bet, observation.tx_bytes as f64 / bets);
+        state.bytes_per_bet = Some(bytes_per_bet);
+        if let Some(units) = observation.compute_units {
+            state.compute_units_per_bet = Some(ewma(state.compute_units_per_bet, units as f64 / bets));
+        }
+
+        let by_size = PACKET_DATA_SIZE as f64 * TARGET_UTILIZATION / bytes_per_bet;
+        let by_compute = state
+            .compute_units_per_bet
+            .map_or(f64::INFINITY, |per_bet| self.compute_unit_limit as f64 * TARGET_UTILIZATION / per_bet);
+        let target = (by_size.min(by_compute).floor() as usize).clamp(self.min, self.max);
+
+        state.size = if target > state.size { state.size + 1 } else { target };
+
+        metrics::gauge!("batch_autotune_size").set(state.size as f64);
+        metrics::gauge!("batch_autotune_bytes_per_bet").set(bytes_per_bet);
+        if let Some(per_bet) = state.compute_units_per_bet {
+            metrics::gauge!("batch_autotune_compute_units_per_bet").set(per_bet);
+        }
+        state.size
+    }
+
+    /// A batch of `bet_count` did not fit (packet size or compute budget); halve it
+    pub fn record_too_large(&self, bet_count: usize) -> usize {
+        let mut state = self.lock();
+        if !self.enabled {
+            return state.size;
+        }
+        state.size = (bet_count / 2).clamp(self.min, self.max).min(state.size);
+        metrics::gauge!("batch_autotune_size").set(state.size as f64);
+        metrics::counter!("batch_autotune_too_large_total").increment(1);
+        state.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tuner_adapts_within_bounds() {
+        let tuner = BatchSizeTuner::new(true, 2, 12, 1_400_000);
+        assert_eq!(tuner.current(), 12);
+
+        // ~200 bytes per bet fits 5 bets in a packet at 90%
+        let observe = |bets: usize| BatchObservation { bet_count: bets, tx_bytes: bets * 200, compute_units: None };
+        assert_eq!(tuner.record(observe(12)), 5);
+
+        // Cheap bets grow back one at a time, never past max
+        let small = |bets: usize| BatchObservation { bet_count: bets, tx_bytes: bets * 40, compute_units: Some(bets as u64 * 1_000) };
+        for _ in 0..20 {
+            let size = tuner.current();
+            tuner.record(small(size));
+        }
+        assert_eq!(tuner.current(), 12);
+
+        // Compute-heavy bets bind on the compute budget
+        let heavy = BatchObservation { bet_count: 12, tx_bytes: 480, compute_units: Some(12 * 400_000) };
+        for _ in 0..10 {
+            tuner.record(heavy);
+        }
+        assert_eq!(tuner.current(), 3);
+
+        assert_eq!(tuner.record_too_large(3), 2);
+
+        let fixed = BatchSizeTuner::new(false, 2, 12, 1_400_000);
+        assert_eq!(fixed.record(observe(12)), 12);
+    }
+}
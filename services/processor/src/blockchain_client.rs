@@ -1,26 +1,105 @@
 //! HTTP client for querying the blockchain API
 //! 
 //! Polls for pending settlements and updates settlement status
+//!
+//! A 429 from the API starts a pause shared by every clone of the client, as
+//! long as its `Retry-After` asks. Pending-settlement fetches fail fast with
+//! [`RateLimited`] during the pause so the coordinator can sit it out, and
+//! single-settlement reads and status updates wait for it to end rather than
+//! retrying into it.
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use shared::clock::{Clock, SystemClock};
+use shared::errors::ServiceError;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{debug, warn, info};
 
-const DEFAULT_TIMEOUT_SECS: u64 = 10;
+use crate::config::BlockchainConfig;
+use crate::domain::BetStatus;
+
 const MAX_RETRIES: u32 = 3;
 
+/// Wait after the `attempt`th failed request: 1s, 2s, 4s, ...
+fn retry_backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1u64 << attempt.saturating_sub(1).min(16))
+}
+/// Pause after a 429 without a usable `Retry-After`
+const DEFAULT_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(5);
+/// Longest pause a `Retry-After` can ask for
+const MAX_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(300);
+
+/// The blockchain API rate-limited us; nothing should be sent to it before the time
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimited(pub DateTime<Utc>);
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Blockchain API rate limited until {}", self.0.to_rfc3339())
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Failed attempts after which a retryable settlement failure becomes permanent
+const MAX_SETTLEMENT_ATTEMPTS: u32 = 3;
+
+/// Blockchain API name of the settlement status a bet status is reported as
+fn settlement_status(status: &BetStatus) -> Option<&'static str> {
+    match status {
+        BetStatus::SubmittedToSolana => Some("SubmittedToSolana"),
+        BetStatus::Completed => Some("SettlementComplete"),
+        BetStatus::FailedRetryable => Some("SettlementFailed"),
+        BetStatus::FailedManualReview => Some("SettlementFailedPermanent"),
+        _ => None,
+    }
+}
+
+/// Status a settlement moves to after its `retry_count`th failed attempt; errors
+/// that can never succeed fail it permanently straight away
+pub fn failed_settlement_status(retryable: bool, retry_count: u32) -> BetStatus {
+    if retryable && retry_count < MAX_SETTLEMENT_ATTEMPTS {
+        BetStatus::FailedRetryable
+    } else {
+        BetStatus::FailedManualReview
+    }
+}
+
+/// Pause a `Retry-After` value asks for, in delay-seconds or HTTP-date form
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    let pause = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => chrono::DateTime::parse_from_rfc2822(value)
+            .ok()?
+            .signed_duration_since(now)
+            .to_std()
+            .unwrap_or_default(),
+    };
+    Some(pause.min(MAX_RATE_LIMIT_PAUSE))
+}
+
+/// Shared across the coordinator, settlement workers and worker pool so that
+/// connections to the blockchain API are pooled and kept alive between cycles.
 #[derive(Clone)]
 pub struct BlockchainClient {
     http_client: Client,
     base_url: String,
     api_key: String,
+    /// End of the current rate-limit pause, shared by all clones
+    rate_limited_until: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// Paces retries and rate-limit pauses
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PendingSettlementResponse {
     pub games: Vec<GameSettlementInfo>,
+    #[serde(default)]
     pub next_cursor: Option<String>,
 }
 
@@ -33,18 +112,34 @@ pub struct GameSettlementInfo {
     pub token: String,
     pub outcome: String, // "Win" | "Loss"
     pub payout: u64,
+    #[allow(dead_code)]
     pub vrf_proof: String,
+    #[allow(dead_code)]
     pub vrf_output: String,
+    #[allow(dead_code)]
     pub block_height: u64,
     pub version: u64,
     pub solana_tx_id: Option<String>, // For idempotency check - if already settled
     #[serde(default)]
     pub retry_count: u32,
     #[serde(default)]
+    #[allow(dead_code)]
     pub next_retry_after: Option<i64>,
     /// Solana allowance PDA for gasless transactions
     #[serde(default)]
     pub allowance_pda: Option<String>,
+    /// Casino the bet belongs to; selects the vault deployment to settle against
+    #[serde(default)]
+    pub casino_id: Option<String>,
+    /// Flagged by an operator for expedited settlement
+    #[serde(default)]
+    pub priority: bool,
+    /// Current settlement status; only sent when a single settlement is fetched
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Unix expiry of `allowance_pda`; looked up on chain by the coordinator when absent
+    #[serde(default)]
+    pub allowance_expires_at: Option<i64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -52,6 +147,9 @@ pub struct UpdateSettlementRequest {
     pub status: String,
     pub solana_tx_id: Option<String>,
     pub error_message: Option<String>,
+    /// Shared `ErrorCode` for the failure, stored as the settlement's `last_error_code`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
     pub expected_version: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry_count: Option<u32>,
@@ -61,51 +159,123 @@ pub struct UpdateSettlementRequest {
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateSettlementResponse {
+    #[allow(dead_code)]
     pub success: bool,
     pub new_version: u64,
 }
 
 impl BlockchainClient {
-    pub fn new(base_url: String, api_key: String) -> Self {
+    pub fn from_config(config: &BlockchainConfig) -> Result<Self> {
+        let http = &config.http;
         let http_client = Client::builder()
-            .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
+            .timeout(Duration::from_millis(http.request_timeout_ms))
+            .connect_timeout(Duration::from_millis(http.connect_timeout_ms))
+            .pool_max_idle_per_host(http.pool_max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(http.pool_idle_timeout_seconds))
+            .tcp_keepalive(Duration::from_secs(http.tcp_keepalive_seconds))
             .build()
-            .expect("Failed to build HTTP client");
+            .context("Failed to build HTTP client")?;
 
-        Self {
+        Ok(Self {
             http_client,
-            base_url,
-            api_key,
+            base_url: config.api_base_url.clone(),
+            api_key: config.api_key.clone(),
+            rate_limited_until: Arc::new(Mutex::new(None)),
+            clock: SystemClock::shared(),
+        })
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// End of the rate-limit pause, if one is still running
+    pub fn rate_limited_until(&self) -> Option<DateTime<Utc>> {
+        let until = *self.rate_limited_until.lock().unwrap_or_else(|e| e.into_inner());
+        until.filter(|until| *until > self.clock.now())
+    }
+
+    /// Time left in the rate-limit pause; zero when none is running
+    pub fn rate_limit_pause(&self) -> Duration {
+        self.rate_limited_until()
+            .and_then(|until| (until - self.clock.now()).to_std().ok())
+            .unwrap_or_default()
+    }
+
+    /// Sit out the rate-limit pause, if one is running
+    async fn wait_for_rate_limit(&self) {
+        let pause = self.rate_limit_pause();
+        if !pause.is_zero() {
+            self.clock.sleep(pause).await;
         }
     }
 
-    /// Fetch pending settlements from blockchain API
+    /// Start or extend the pause a 429 `response` asks for
+    fn rate_limited(&self, endpoint: &'static str, response: &reqwest::Response) -> RateLimited {
+        let pause = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, self.clock.now()))
+            .unwrap_or(DEFAULT_RATE_LIMIT_PAUSE);
+        let until = self.pause_for(pause);
+        metrics::counter!("blockchain_api_rate_limited_total", "endpoint" => endpoint).increment(1);
+        warn!(endpoint, pause_ms = pause.as_millis() as u64, "Blockchain API rate limited; pausing requests");
+        RateLimited(until)
+    }
+
+    /// Start a pause of `pause` from now, or keep the current one if it ends later
+    fn pause_for(&self, pause: Duration) -> DateTime<Utc> {
+        let now = self.clock.now();
+        let mut current = self.rate_limited_until.lock().unwrap_or_else(|e| e.into_inner());
+        let pause = chrono::Duration::from_std(pause.min(MAX_RATE_LIMIT_PAUSE)).unwrap_or_default();
+        let until = (now + pause).max(current.unwrap_or(now));
+        *current = Some(until);
+        until
+    }
+
+    /// Fetch the first page of pending settlements from blockchain API
     pub async fn fetch_pending_settlements(&self, limit: usize) -> Result<Vec<GameSettlementInfo>> {
+        Ok(self.fetch_pending_page(limit, None).await?.games)
+    }
+
+    /// Fetch one page of pending settlements from `cursor`, with retries
+    ///
+    /// An empty `next_cursor` from the API is treated as the last page. While
+    /// rate limited this fails with [`RateLimited`] without retrying.
+    pub async fn fetch_pending_page(&self, limit: usize, cursor: Option<&str>) -> Result<PendingSettlementResponse> {
         let url = format!("{}/api/settlement/pending", self.base_url);
         
         for attempt in 1..=MAX_RETRIES {
-            match self.fetch_pending_settlements_once(&url, limit).await {
-                Ok(games) => {
+            if let Some(until) = self.rate_limited_until() {
+                return Err(RateLimited(until).into());
+            }
+            match self.fetch_pending_settlements_once(&url, limit, cursor).await {
+                Ok(mut page) => {
+                    page.next_cursor = page.next_cursor.filter(|c| !c.is_empty());
                     debug!(
-                        games_count = games.len(),
+                        games_count = page.games.len(),
+                        has_more = page.next_cursor.is_some(),
                         attempt,
                         "Fetched pending settlements"
                     );
-                    return Ok(games);
+                    return Ok(page);
                 }
+                Err(e) if e.is::<RateLimited>() => return Err(e),
                 Err(e) => {
                     if attempt == MAX_RETRIES {
                         return Err(e).context("Failed to fetch pending settlements after retries");
                     }
                     
-                    let backoff_ms = 2u64.pow(attempt - 1) * 1000;
+                    let backoff = retry_backoff(attempt);
                     warn!(
                         attempt,
                         error = %e,
-                        backoff_ms,
+                        backoff_ms = backoff.as_millis() as u64,
                         "Fetch failed, retrying"
                     );
-                    tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+                    self.clock.sleep(backoff).await;
                 }
             }
         }
@@ -113,18 +283,31 @@ impl BlockchainClient {
         unreachable!()
     }
 
-    async fn fetch_pending_settlements_once(&self, url: &str, limit: usize) -> Result<Vec<GameSettlementInfo>> {
-        info!("Fetching pending settlements from {} with limit={}", url, limit);
+    async fn fetch_pending_settlements_once(
+        &self,
+        url: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<PendingSettlementResponse> {
+        info!("Fetching pending settlements from {} with limit={} cursor={:?}", url, limit, cursor);
         
-        let response = self.http_client
+        let mut request = self.http_client
             .get(url)
             .header("X-API-Key", &self.api_key)
-            .query(&[("limit", limit)])
-            .send()
-            .await
-            .context("HTTP request failed")?;
+            .query(&[("limit", limit)]);
+        if let Some(cursor) = cursor {
+            request = request.query(&[("cursor", cursor)]);
+        }
+
+        let started = Instant::now();
+        let response = request.send().await;
+        record_latency("pending_settlements", started, &response);
+        let response = response.context("HTTP request failed")?;
 
         let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(self.rate_limited("pending_settlements", &response).into());
+        }
         if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
             anyhow::bail!("Blockchain API error {}: {}", status, body);
@@ -136,26 +319,65 @@ impl BlockchainClient {
             .context("Failed to parse response")?;
 
         info!("Received {} pending settlements from API", data.games.len());
-        Ok(data.games)
+        Ok(data)
+    }
+
+    /// Fetch the current state of a single settlement
+    pub async fn fetch_settlement(&self, tx_id: u64) -> Result<GameSettlementInfo> {
+        let url = format!("{}/api/settlement/games/{}", self.base_url, tx_id);
+
+        self.wait_for_rate_limit().await;
+        let started = Instant::now();
+        let response = self.http_client
+            .get(&url)
+            .header("X-API-Key", &self.api_key)
+            .send()
+            .await;
+        record_latency("get_settlement", started, &response);
+        let response = response.context("HTTP request failed")?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(self.rate_limited("get_settlement", &response).into());
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Blockchain API error {}: {}", status, body);
+        }
+
+        response
+            .json()
+            .await
+            .context("Failed to parse response")
     }
 
-    /// Update settlement status on blockchain
+    /// Move a settlement from `from` to `to` on blockchain
+    ///
+    /// Transitions `BetStatus::can_transition_to` rejects fail without a request.
+    #[allow(clippy::too_many_arguments)]
     pub async fn update_settlement_status(
         &self,
         tx_id: u64,
-        status: &str,
+        from: &BetStatus,
+        to: &BetStatus,
         solana_tx_id: Option<String>,
-        error_message: Option<String>,
+        error: Option<ServiceError>,
         expected_version: u64,
         retry_count: Option<u32>,
         next_retry_after: Option<i64>,
     ) -> Result<u64> {
+        if !from.can_transition_to(to) {
+            anyhow::bail!("Illegal settlement transition from {:?} to {:?} for {}", from, to, tx_id);
+        }
+        let status = settlement_status(to)
+            .with_context(|| format!("Blockchain API has no settlement status for {:?}", to))?;
         let url = format!("{}/api/settlement/games/{}", self.base_url, tx_id);
         
         let request = UpdateSettlementRequest {
             status: status.to_string(),
             solana_tx_id,
-            error_message,
+            error_message: error.as_ref().map(|e| e.to_string()),
+            error_code: error.map(|e| e.code),
             expected_version,
             retry_count,
             next_retry_after,
@@ -173,6 +395,10 @@ impl BlockchainClient {
                     );
                     return Ok(new_version);
                 }
+                // The next attempt waits out the pause instead of backing off
+                Err(e) if e.is::<RateLimited>() && attempt < MAX_RETRIES => {
+                    debug!(tx_id, attempt, "Update rate limited, retrying after the pause");
+                }
                 Err(e) => {
                     let error_str = e.to_string();
                     
@@ -197,15 +423,15 @@ impl BlockchainClient {
                         return Err(e).context("Failed to update settlement status after retries");
                     }
                     
-                    let backoff_ms = 2u64.pow(attempt - 1) * 1000;
+                    let backoff = retry_backoff(attempt);
                     warn!(
                         tx_id,
                         attempt,
                         error = %e,
-                        backoff_ms,
+                        backoff_ms = backoff.as_millis() as u64,
                         "Update failed, retrying"
                     );
-                    tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+                    self.clock.sleep(backoff).await;
                 }
             }
         }
@@ -214,16 +440,22 @@ impl BlockchainClient {
     }
 
     async fn update_settlement_status_once(&self, url: &str, request: &UpdateSettlementRequest) -> Result<u64> {
+        self.wait_for_rate_limit().await;
+        let started = Instant::now();
         let response = self.http_client
             .post(url)
             .header("X-API-Key", &self.api_key)
             .header("Content-Type", "application/json")
             .json(request)
             .send()
-            .await
-            .context("HTTP request failed")?;
+            .await;
+        record_latency("update_settlement", started, &response);
+        let response = response.context("HTTP request failed")?;
 
         let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(self.rate_limited("update_settlement", &response).into());
+        }
         if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
             tracing::error!(
@@ -259,16 +491,141 @@ impl BlockchainClient {
     }
 }
 
+/// Record request latency per endpoint, labelled with the HTTP status or "error"
+fn record_latency(endpoint: &'static str, started: Instant, response: &reqwest::Result<reqwest::Response>) {
+    let status = match response {
+        Ok(resp) => resp.status().as_u16().to_string(),
+        Err(e) if e.is_timeout() => "timeout".to_string(),
+        Err(_) => "error".to_string(),
+    };
+    metrics::histogram!(
+        "blockchain_api_request_duration_seconds",
+        "endpoint" => endpoint,
+        "status" => status
+    )
+    .record(started.elapsed().as_secs_f64());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::HttpClientConfig;
+    use shared::clock::ManualClock;
 
     #[test]
     fn test_client_creation() {
-        let client = BlockchainClient::new(
-            "http://localhost:8080".to_string(),
-            "test_key".to_string(),
-        );
+        let config = BlockchainConfig {
+            api_base_url: "http://localhost:8080".to_string(),
+            api_key: "test_key".to_string(),
+            poll_interval_seconds: 10,
+            settlement_batch_size: 50,
+            max_pages_per_cycle: 10,
+            http: HttpClientConfig {
+                request_timeout_ms: 10_000,
+                connect_timeout_ms: 2_000,
+                pool_max_idle_per_host: 32,
+                pool_idle_timeout_seconds: 90,
+                tcp_keepalive_seconds: 30,
+            },
+        };
+        let client = BlockchainClient::from_config(&config).unwrap();
         assert_eq!(client.base_url, "http://localhost:8080");
     }
+
+    #[tokio::test]
+    async fn test_fetch_retries_back_off_on_the_clock() {
+        let config = BlockchainConfig {
+            // Nothing listens here, so every attempt fails at once
+            api_base_url: "http://127.0.0.1:1".to_string(),
+            api_key: "test_key".to_string(),
+            poll_interval_seconds: 10,
+            settlement_batch_size: 50,
+            max_pages_per_cycle: 10,
+            http: HttpClientConfig {
+                request_timeout_ms: 1_000,
+                connect_timeout_ms: 1_000,
+                pool_max_idle_per_host: 1,
+                pool_idle_timeout_seconds: 90,
+                tcp_keepalive_seconds: 30,
+            },
+        };
+        let start = chrono::Utc::now();
+        let clock = Arc::new(ManualClock::at(start));
+        let client = BlockchainClient::from_config(&config).unwrap().with_clock(clock.clone());
+        let fetch = tokio::spawn(async move { client.fetch_pending_page(10, None).await });
+
+        clock.until_started(1).await;
+        clock.advance(Duration::from_millis(999));
+        tokio::task::yield_now().await;
+        assert!(!fetch.is_finished());
+        clock.advance(Duration::from_millis(1));
+
+        // The second backoff doubles; the third attempt is the last
+        clock.until_started(2).await;
+        clock.advance(Duration::from_secs(2));
+        assert!(fetch.await.unwrap().is_err());
+        assert_eq!(clock.now() - start, chrono::Duration::seconds(3));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_pause_runs_on_the_clock() {
+        let config = BlockchainConfig {
+            api_base_url: "http://127.0.0.1:1".to_string(),
+            api_key: "test_key".to_string(),
+            poll_interval_seconds: 10,
+            settlement_batch_size: 50,
+            max_pages_per_cycle: 10,
+            http: HttpClientConfig {
+                request_timeout_ms: 1_000,
+                connect_timeout_ms: 1_000,
+                pool_max_idle_per_host: 1,
+                pool_idle_timeout_seconds: 90,
+                tcp_keepalive_seconds: 30,
+            },
+        };
+        let clock = Arc::new(ManualClock::at(chrono::Utc::now()));
+        let client = BlockchainClient::from_config(&config).unwrap().with_clock(clock.clone());
+        let until = client.pause_for(DEFAULT_RATE_LIMIT_PAUSE);
+
+        // A shorter pause never cuts the running one short
+        assert_eq!(client.pause_for(Duration::from_secs(1)), until);
+        assert_eq!(client.rate_limit_pause(), DEFAULT_RATE_LIMIT_PAUSE);
+        let err = client.fetch_pending_page(10, None).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<RateLimited>().unwrap().0, until);
+
+        let waiter = client.clone();
+        let wait = tokio::spawn(async move { waiter.wait_for_rate_limit().await });
+        clock.until_started(1).await;
+        clock.advance(DEFAULT_RATE_LIMIT_PAUSE - Duration::from_millis(1));
+        tokio::task::yield_now().await;
+        assert!(!wait.is_finished());
+        clock.advance(Duration::from_millis(1));
+        wait.await.unwrap();
+        assert_eq!(client.rate_limited_until(), None);
+        assert_eq!(client.rate_limit_pause(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_failed_settlement_status() {
+        assert_eq!(failed_settlement_status(true, 1), BetStatus::FailedRetryable);
+        assert_eq!(failed_settlement_status(true, MAX_SETTLEMENT_ATTEMPTS), BetStatus::FailedManualReview);
+        assert_eq!(failed_settlement_status(false, 1), BetStatus::FailedManualReview);
+        for status in [failed_settlement_status(true, 1), failed_settlement_status(false, 1)] {
+            assert!(BetStatus::SubmittedToSolana.can_transition_to(&status));
+            assert!(settlement_status(&status).is_some());
+        }
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT").unwrap().to_utc();
+        assert_eq!(parse_retry_after("12", now), Some(Duration::from_secs(12)));
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2026 07:28:30 GMT", now), Some(Duration::from_secs(30)));
+
+        // Dates already past pause for nothing; long pauses are capped
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2026 07:27:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("86400", now), Some(MAX_RATE_LIMIT_PAUSE));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
 }
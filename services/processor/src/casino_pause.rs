@@ -0,0 +1,166 @@
+//! On-chain casino pause check before dispatch
+//!
+//! A paused casino rejects every settlement with `CasinoPaused`, so dispatching
+//! against it only burns fees and retries. The coordinator reads each
+//! deployment's Casino account at most once per check interval and leaves the
+//! settlements of paused deployments pending in the blockchain API; they go out
+//! on the first cycle after the casino is unpaused. While every deployment is
+//! paused, cycles are skipped without fetching anything.
+
+use anyhow::{Context, Result};
+use shared::vault::{casino_pda, CasinoAccount};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::blockchain_client::GameSettlementInfo;
+use crate::config::SolanaConfig;
+use crate::solana_client::SolanaClientPool;
+
+/// Deployments paused in `after` but not `before`, and those unpaused since
+fn transitions(before: &BTreeSet<String>, after: &BTreeSet<String>) -> (Vec<String>, Vec<String>) {
+    (after.difference(before).cloned().collect(), before.difference(after).cloned().collect())
+}
+
+#[derive(Default)]
+struct PauseState {
+    checked_at: Option<Instant>,
+    paused: BTreeSet<String>,
+}
+
+pub struct CasinoPause {
+    solana_client: Arc<SolanaClientPool>,
+    solana: SolanaConfig,
+    /// How long pause flags read from chain are reused
+    check_interval: Duration,
+    state: Mutex<PauseState>,
+}
+
+impl CasinoPause {
+    pub fn new(solana_client: Arc<SolanaClientPool>, solana: SolanaConfig, check_interval: Duration) -> Self {
+        Self { solana_client, solana, check_interval, state: Mutex::new(PauseState::default()) }
+    }
+
+    /// Read the pause flags again once the cached ones are older than the check
+    /// interval; when they can't be read the last known ones stand
+    pub async fn refresh(&self) {
+        let due = self
+            .state()
+            .checked_at
+            .is_none_or(|checked_at| checked_at.elapsed() >= self.check_interval);
+        if !due {
+            return;
+        }
+
+        let paused = match self.fetch_paused().await {
+            Ok(paused) => paused,
+            Err(e) => {
+                warn!(error = %e, "Failed to read casino pause state; keeping last known state");
+                metrics::counter!("casino_pause_check_failures_total").increment(1);
+                return;
+            }
+        };
+
+        let mut state = self.state();
+        let (newly_paused, resumed) = transitions(&state.paused, &paused);
+        for deployment in &newly_paused {
+            warn!(deployment = %deployment, "Casino paused on-chain - holding its settlements");
+        }
+        for deployment in &resumed {
+            info!(deployment = %deployment, "Casino unpaused on-chain - settlement dispatch resumed");
+        }
+        for deployment in &self.solana.deployments {
+            let value = if paused.contains(&deployment.name) { 1.0 } else { 0.0 };
+            metrics::gauge!("casino_paused", "deployment" => deployment.name.clone()).set(value);
+        }
+        state.paused = paused;
+        state.checked_at = Some(Instant::now());
+    }
+
+    async fn fetch_paused(&self) -> Result<BTreeSet<String>> {
+        let casinos: Vec<_> = self
+            .solana
+            .deployments
+            .iter()
+            .map(|deployment| (deployment.name.clone(), casino_pda(&deployment.program_id)))
+            .collect();
+        let client = self.solana_client.get_client().await;
+        let commitment = self.solana_client.commitments().read;
+        tokio::task::spawn_blocking(move || {
+            let addresses: Vec<_> = casinos.iter().map(|(_, casino)| *casino).collect();
+            let accounts = client
+                .get_multiple_accounts_with_commitment(&addresses, commitment)
+                .context("Failed to fetch casino accounts")?
+                .value;
+            let mut paused = BTreeSet::new();
+            for ((deployment, casino), account) in casinos.into_iter().zip(accounts) {
+                let Some(account) = account else {
+                    warn!(deployment = %deployment, %casino, "Casino account not found; treating it as running");
+                    continue;
+                };
+                if CasinoAccount::decode(&account.data)?.paused {
+                    paused.insert(deployment);
+                }
+            }
+            Ok(paused)
+        })
+        .await
+        .context("Casino pause check task panicked")?
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, PauseState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Deployments whose casino was paused at the last check
+    pub fn paused_deployments(&self) -> Vec<String> {
+        self.state().paused.iter().cloned().collect()
+    }
+
+    /// Whether no deployment can be settled against
+    pub fn all_paused(&self) -> bool {
+        let state = self.state();
+        !self.solana.deployments.is_empty()
+            && self.solana.deployments.iter().all(|deployment| state.paused.contains(&deployment.name))
+    }
+
+    /// Drop the settlements of paused deployments; they stay pending until the casino is unpaused
+    pub fn retain_running(&self, settlements: Vec<GameSettlementInfo>) -> Vec<GameSettlementInfo> {
+        let state = self.state();
+        if state.paused.is_empty() {
+            return settlements;
+        }
+        settlements
+            .into_iter()
+            .filter(|settlement| {
+                let deployment = &self.solana.deployment_for(settlement.casino_id.as_deref()).name;
+                let paused = state.paused.contains(deployment);
+                if paused {
+                    metrics::counter!("casino_paused_settlements_skipped_total", "deployment" => deployment.clone())
+                        .increment(1);
+                }
+                !paused
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        let set = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<BTreeSet<_>>();
+
+        let (paused, resumed) = transitions(&set(&[]), &set(&["default"]));
+        assert_eq!((paused, resumed), (vec!["default".to_string()], vec![]));
+
+        let (paused, resumed) = transitions(&set(&["default", "v2"]), &set(&["v2", "v3"]));
+        assert_eq!((paused, resumed), (vec!["v3".to_string()], vec!["default".to_string()]));
+
+        let (paused, resumed) = transitions(&set(&["default"]), &set(&["default"]));
+        assert!(paused.is_empty() && resumed.is_empty());
+    }
+}
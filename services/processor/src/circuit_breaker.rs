@@ -1,24 +1,31 @@
+use chrono::{DateTime, Utc};
+use shared::clock::{Clock, SystemClock};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 #[derive(Clone)]
+#[allow(dead_code)]
 pub struct CircuitBreaker {
     failure_count: Arc<AtomicU64>,
-    last_failure_time: Arc<RwLock<Option<Instant>>>,
+    last_failure_time: Arc<RwLock<Option<DateTime<Utc>>>>,
     state: Arc<RwLock<CircuitState>>,
     failure_threshold: u64,
     reset_timeout: Duration,
+    /// Times the reset timeout
+    clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
 pub enum CircuitState {
     Closed,
     Open,
     HalfOpen,
 }
 
+#[allow(dead_code)]
 impl CircuitBreaker {
     pub fn new(failure_threshold: u64, reset_timeout_seconds: u64) -> Self {
         Self {
@@ -27,9 +34,15 @@ impl CircuitBreaker {
             state: Arc::new(RwLock::new(CircuitState::Closed)),
             failure_threshold,
             reset_timeout: Duration::from_secs(reset_timeout_seconds),
+            clock: SystemClock::shared(),
         }
     }
 
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub async fn call<F, T, E>(&self, operation: F) -> Result<T, CircuitBreakerError<E>>
     where
         F: FnOnce() -> Result<T, E>,
@@ -41,7 +54,8 @@ impl CircuitBreaker {
                 // Check if we should try half-open
                 let last_failure = self.last_failure_time.read().await;
                 if let Some(last_time) = *last_failure {
-                    if last_time.elapsed() > self.reset_timeout {
+                    let elapsed = (self.clock.now() - last_time).to_std().unwrap_or_default();
+                    if elapsed > self.reset_timeout {
                         drop(state);
                         drop(last_failure);
                         let mut state = self.state.write().await;
@@ -81,7 +95,7 @@ impl CircuitBreaker {
     async fn on_failure(&self) {
         let failures = self.failure_count.fetch_add(1, Ordering::SeqCst) + 1;
         let mut last_failure = self.last_failure_time.write().await;
-        *last_failure = Some(Instant::now());
+        *last_failure = Some(self.clock.now());
 
         if failures >= self.failure_threshold {
             let mut state = self.state.write().await;
@@ -97,6 +111,7 @@ impl CircuitBreaker {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub enum CircuitBreakerError<E> {
     Open,
     OperationFailed(E),
@@ -112,3 +127,26 @@ impl<E: std::fmt::Display> std::fmt::Display for CircuitBreakerError<E> {
 }
 
 impl<E: std::error::Error> std::error::Error for CircuitBreakerError<E> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::clock::ManualClock;
+
+    #[tokio::test]
+    async fn test_half_opens_once_the_reset_timeout_passes_on_the_clock() {
+        let clock = Arc::new(ManualClock::at(Utc::now()));
+        let breaker = CircuitBreaker::new(2, 60).with_clock(clock.clone());
+        for _ in 0..2 {
+            assert!(breaker.call(|| Err::<(), _>("down")).await.is_err());
+        }
+        assert!(breaker.is_open().await);
+
+        clock.advance(Duration::from_secs(60));
+        assert!(matches!(breaker.call(|| Ok::<_, &str>(())).await, Err(CircuitBreakerError::Open)));
+
+        clock.advance(Duration::from_secs(1));
+        assert!(breaker.call(|| Ok::<_, &str>(())).await.is_ok());
+        assert!(!breaker.is_open().await);
+    }
+}
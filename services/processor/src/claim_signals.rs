@@ -0,0 +1,130 @@
+//! Push-driven claiming
+//!
+//! With `CLAIM_SIGNALS_ENABLED` the processor holds the backend's
+//! `/api/external/bets/subscribe` stream open and wakes the coordinator and the
+//! worker pool as soon as a bet becomes claimable, instead of leaving it for the
+//! next poll. Polling carries on underneath at its usual interval, so while the
+//! stream is down (it is reopened every `CLAIM_SIGNALS_RECONNECT_SECONDS`) bets
+//! are still picked up, only later.
+
+use atomiq_client::{ClaimSignal, HttpBackend};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Holds the subscription and counts the signals received on it
+pub struct ClaimSignalSource {
+    signals: watch::Sender<u64>,
+}
+
+impl ClaimSignalSource {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self { signals: watch::channel(0).0 })
+    }
+
+    /// A waiter that sees every signal sent after this call, even while busy
+    pub fn waiter(&self) -> ClaimWaiter {
+        let mut receiver = self.signals.subscribe();
+        receiver.mark_unchanged();
+        ClaimWaiter { receiver }
+    }
+
+    /// Keep the subscription open, reconnecting after `reconnect` whenever it drops
+    pub async fn run(self: Arc<Self>, backend: HttpBackend, processor_id: String, reconnect: Duration) {
+        loop {
+            match backend.subscribe_claimable(&processor_id).await {
+                Ok(mut stream) => {
+                    self.set_connected(true);
+                    tracing::info!(processor_id = %processor_id, "Subscribed to claim signals");
+                    loop {
+                        match stream.next().await {
+                            Ok(Some(signal)) => self.signal(signal),
+                            Ok(None) => {
+                                tracing::warn!("Claim signal stream closed by the backend; polling until it reopens");
+                                break;
+                            }
+                            Err(e) => {
+                                tracing::warn!(error = %e, "Claim signal stream failed; polling until it reopens");
+                                break;
+                            }
+                        }
+                    }
+                    self.set_connected(false);
+                }
+                Err(e) => tracing::warn!(error = %e, "Claim signal subscription failed; polling until it opens"),
+            }
+            metrics::counter!("claim_signal_reconnects_total").increment(1);
+            tokio::time::sleep(reconnect).await;
+        }
+    }
+
+    fn signal(&self, signal: ClaimSignal) {
+        match signal {
+            ClaimSignal::Claimable(event) => {
+                tracing::debug!(bet_id = %event.bet_id, priority = event.priority, "Claim signal received");
+            }
+            ClaimSignal::Lagged(skipped) => {
+                tracing::debug!(skipped, "Claim signals dropped by the backend; claiming anyway");
+            }
+        }
+        metrics::counter!("claim_signals_received_total").increment(1);
+        self.signals.send_modify(|count| *count = count.wrapping_add(1));
+    }
+
+    /// `claim_signal_connected` is 0 while bets are only picked up by polling
+    fn set_connected(&self, connected: bool) {
+        metrics::gauge!("claim_signal_connected").set(if connected { 1.0 } else { 0.0 });
+    }
+}
+
+/// One loop's view of the signals
+pub struct ClaimWaiter {
+    receiver: watch::Receiver<u64>,
+}
+
+impl ClaimWaiter {
+    /// Resolves on the next signal, or at once if one arrived since the last call
+    pub async fn signalled(&mut self) {
+        if self.receiver.changed().await.is_err() {
+            // The source is gone; only polling is left
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Sleep for `delay`, cut short by a claim signal when there is a waiter
+pub async fn sleep_or_signal(delay: Duration, waiter: Option<&mut ClaimWaiter>) {
+    match waiter {
+        Some(waiter) => {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = waiter.signalled() => {}
+            }
+        }
+        None => tokio::time::sleep(delay).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atomiq_client::ClaimableEvent;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_signal_cuts_poll_short() {
+        let source = ClaimSignalSource::new();
+        let mut waiter = source.waiter();
+
+        // Nothing signalled: the full delay passes
+        let started = tokio::time::Instant::now();
+        sleep_or_signal(Duration::from_millis(50), Some(&mut waiter)).await;
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        // A signal sent while the loop was busy still wakes the next wait
+        source.signal(ClaimSignal::Claimable(ClaimableEvent { bet_id: Uuid::new_v4(), priority: false }));
+        let started = tokio::time::Instant::now();
+        sleep_or_signal(Duration::from_secs(30), Some(&mut waiter)).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
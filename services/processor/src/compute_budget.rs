@@ -0,0 +1,127 @@
+//! Compute unit limits for batch transactions
+//!
+//! Without a `SetComputeUnitLimit` instruction a transaction gets the default
+//! per-instruction budget: too much for small batches (priority fees are paid
+//! per requested unit) and too little for large ones. Batches are simulated
+//! once under the maximum limit; the units consumed plus a margin become the
+//! limit, cached per transaction shape so later batches skip the probe.
+
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, pubkey::Pubkey,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Highest compute unit limit a transaction may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Instruction mix of a transaction: count of each (program, discriminator, account count)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TxShape(BTreeMap<(Pubkey, [u8; 8], usize), usize>);
+
+impl TxShape {
+    pub fn of(instructions: &[Instruction]) -> Self {
+        let mut mix = BTreeMap::new();
+        for ix in instructions {
+            let mut discriminator = [0u8; 8];
+            let len = ix.data.len().min(8);
+            discriminator[..len].copy_from_slice(&ix.data[..len]);
+            *mix.entry((ix.program_id, discriminator, ix.accounts.len())).or_insert(0) += 1;
+        }
+        Self(mix)
+    }
+}
+
+/// `SetComputeUnitLimit` followed by the batch instructions
+pub fn with_compute_unit_limit(limit: u32, instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut all = Vec::with_capacity(instructions.len() + 1);
+    all.push(ComputeBudgetInstruction::set_compute_unit_limit(limit));
+    all.extend_from_slice(instructions);
+    all
+}
+
+/// `SetComputeUnitPrice` ahead of `instructions`, unless the price is 0
+pub fn with_compute_unit_price(micro_lamports: u64, mut instructions: Vec<Instruction>) -> Vec<Instruction> {
+    if micro_lamports > 0 {
+        instructions.insert(0, ComputeBudgetInstruction::set_compute_unit_price(micro_lamports));
+    }
+    instructions
+}
+
+#[derive(Debug)]
+pub struct ComputeUnitEstimator {
+    margin_percent: u32,
+    /// Highest units consumed seen per shape
+    observed: Mutex<HashMap<TxShape, u64>>,
+}
+
+impl ComputeUnitEstimator {
+    pub fn new(margin_percent: u32) -> Self {
+        Self {
+            margin_percent,
+            observed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn limit_for(&self, units: u64) -> u32 {
+        let with_margin = units.saturating_mul(100 + self.margin_percent as u64).div_ceil(100);
+        with_margin.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+    }
+
+    /// Limit for a shape seen before
+    pub fn cached(&self, shape: &TxShape) -> Option<u32> {
+        let observed = self.observed.lock().unwrap_or_else(|e| e.into_inner());
+        let limit = observed.get(shape).map(|units| self.limit_for(*units));
+        let result = if limit.is_some() { "hit" } else { "miss" };
+        metrics::counter!("compute_unit_estimate_cache_total", "result" => result).increment(1);
+        limit
+    }
+
+    /// Record units consumed by a simulation of `shape`; returns the limit to request
+    pub fn record(&self, shape: TxShape, units_consumed: u64) -> u32 {
+        let mut observed = self.observed.lock().unwrap_or_else(|e| e.into_inner());
+        let units = observed.entry(shape).or_insert(0);
+        *units = (*units).max(units_consumed);
+        let limit = self.limit_for(*units);
+        metrics::histogram!("compute_unit_limit_requested").record(limit as f64);
+        limit
+    }
+
+    /// Forget a shape whose cached limit turned out too low
+    pub fn invalidate(&self, shape: &TxShape) {
+        self.observed.lock().unwrap_or_else(|e| e.into_inner()).remove(shape);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    #[test]
+    fn test_estimates_cached_per_shape() {
+        let program = Pubkey::new_unique();
+        let ix = |tag: u8| Instruction::new_with_bytes(program, &[tag; 8], vec![AccountMeta::new(program, false)]);
+        let spend_payout = TxShape::of(&[ix(1), ix(2)]);
+        assert_eq!(spend_payout, TxShape::of(&[ix(2), ix(1)]));
+        assert_ne!(spend_payout, TxShape::of(&[ix(1), ix(1)]));
+
+        let estimator = ComputeUnitEstimator::new(10);
+        assert_eq!(estimator.cached(&spend_payout), None);
+        assert_eq!(estimator.record(spend_payout.clone(), 50_000), 55_000);
+        // A lower reading never shrinks the limit below what was already needed
+        assert_eq!(estimator.record(spend_payout.clone(), 40_000), 55_000);
+        assert_eq!(estimator.cached(&spend_payout), Some(55_000));
+        assert_eq!(estimator.record(TxShape::of(&[ix(3)]), 1_399_000), MAX_COMPUTE_UNIT_LIMIT);
+
+        estimator.invalidate(&spend_payout);
+        assert_eq!(estimator.cached(&spend_payout), None);
+
+        let with_limit = with_compute_unit_limit(55_000, &[ix(1)]);
+        assert_eq!(with_limit.len(), 2);
+        assert_eq!(with_limit[0].program_id, solana_sdk::compute_budget::id());
+        assert_eq!(with_compute_unit_price(0, vec![ix(1)]).len(), 1);
+        let with_price = with_compute_unit_price(5_000, vec![ix(1)]);
+        assert_eq!(with_price[0], ComputeBudgetInstruction::set_compute_unit_price(5_000));
+    }
+}
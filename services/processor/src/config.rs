@@ -1,53 +1,367 @@
-use serde::Deserialize;
+use crate::coordinator::BatchType;
+use crate::deployments::{self, VaultDeployment};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use shared::profile::{self, Profile, ProfileDefaults};
+use shared::secrets::Resolver;
+use simulation::{BehaviorProfile, ServerSeed};
 use std::env;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Profile the defaults came from (`--profile` / `CONFIG_PROFILE`)
+    pub profile: Option<Profile>,
     pub processor: ProcessorConfig,
     pub solana: SolanaConfig,
     pub blockchain: BlockchainConfig,
+    pub leader_election: LeaderElectionConfig,
+    pub kill_switch: KillSwitchConfig,
+    pub batch_journal: BatchJournalConfig,
+    pub refund: RefundConfig,
+    pub claim_signals: ClaimSignalsConfig,
+    pub settlement_webhook: SettlementWebhookConfig,
+    pub payout_hold: PayoutHoldConfig,
+    pub retry_budget: RetryBudgetConfig,
+    pub backend: BackendClientConfig,
+    pub casino_topup: CasinoTopUpConfig,
+    pub drain: DrainConfig,
+    pub simulation: SimulationConfig,
     pub metrics_port: u16,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessorConfig {
     pub worker_count: usize,
     pub settlement_worker_count: usize,
+    /// Settlements of one coordinator batch submitted and confirmed concurrently
+    pub settlement_batch_parallelism: usize,
     pub batch_interval_seconds: u64,
+    #[allow(dead_code)]
     pub batch_size: usize,
     pub max_bets_per_tx: usize,
     pub max_retries: u32,
+    #[serde(serialize_with = "profile::redact_keypair")]
     pub keypair_path: String,
+    #[allow(dead_code)]
     pub max_stuck_time_seconds: i64,
     pub coordinator_enabled: bool,
     pub coordinator_channel_buffer_size: usize,
     pub coordinator_batch_min_size: usize,
     pub coordinator_batch_max_size: usize,
+    /// Settlements a coordinator cycle takes in before resuming from the cursor next cycle (0 = page cap only)
+    pub coordinator_max_settlements_per_cycle: usize,
+    /// Adapt batch size to observed transaction size and compute units
+    pub batch_autotune_enabled: bool,
+    /// How long bets of a wallet whose simulation failed are left out of batches (0 disables)
+    pub simulation_failure_ttl_seconds: u64,
+    /// Compute units a batch transaction may consume
+    pub batch_compute_unit_limit: u64,
+    /// Headroom added to simulated compute units when setting a transaction's limit
+    pub compute_unit_margin_percent: u32,
+    /// How often an unconfirmed settlement transaction is re-broadcast
+    pub rebroadcast_interval_ms: u64,
+    /// Blockhashes a settlement may go through before it counts as failed
+    pub max_blockhash_attempts: u32,
+    /// Payouts at or above this go on the high-priority lane
+    pub priority_payout_threshold_lamports: u64,
+    /// Settlements pending at least this long go on the high-priority lane
+    pub priority_age_threshold_seconds: u64,
+    /// Settlements whose allowance expires within this many seconds go on the high-priority lane
+    pub priority_allowance_expiry_seconds: u64,
+    /// Read allowance expiry from chain when the blockchain API doesn't send it
+    pub allowance_expiry_lookup_enabled: bool,
+    /// Create missing user vaults with `initialize_vault_for`, paid by the processor
+    pub auto_init_vaults: bool,
+    /// Look up fetched settlements' accounts and create missing ones before workers build them
+    pub account_prewarm_enabled: bool,
+    /// Defer payout batches the casino vault balance can't cover
+    pub payout_liability_check_enabled: bool,
+    /// Casino vault balance payout batches may not dip into
+    pub payout_liability_reserve_lamports: u64,
+    /// Hold settlements back from casinos paused on-chain
+    pub casino_pause_check_enabled: bool,
+    /// How long a casino's on-chain pause flag is reused before it is read again
+    pub casino_pause_check_interval_seconds: u64,
+    /// Compute unit price of settlement transactions before each type's multiplier (0 for none)
+    pub priority_fee_micro_lamports: u64,
+    /// Payouts all write the casino vault, so they contend for its lock
+    pub payout_limits: BatchTypeLimits,
+    /// Spends write each user's own allowance and parallelize well
+    pub spend_limits: BatchTypeLimits,
+    /// Least time between submissions writing the same casino vault or allowance (0 disables)
+    pub write_lock_stagger_ms: u64,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl ProcessorConfig {
+    pub fn limits_for(&self, batch_type: BatchType) -> &BatchTypeLimits {
+        match batch_type {
+            BatchType::Payout => &self.payout_limits,
+            BatchType::Spend => &self.spend_limits,
+        }
+    }
+}
+
+/// Submission settings of one batch type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchTypeLimits {
+    /// Transactions of one coordinator batch submitted and confirmed concurrently
+    pub max_parallel_transactions: usize,
+    /// Bets packed into one worker pool transaction
+    pub max_per_tx: usize,
+    /// Scales the priority fee (`PROCESSOR_PRIORITY_FEE_MICRO_LAMPORTS`, or the drain fee while draining)
+    pub priority_fee_multiplier: f64,
+}
+
+impl BatchTypeLimits {
+    /// Compute unit price for a transaction of this type
+    pub fn priority_fee(&self, base_micro_lamports: u64) -> u64 {
+        (base_micro_lamports as f64 * self.priority_fee_multiplier).round() as u64
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SolanaConfig {
     pub rpc_urls: Vec<String>,
-    pub commitment: String,
-    pub vault_program_id: String,
+    /// Request budget of each of `rpc_urls`, per second; 0 is unlimited
+    pub rpc_requests_per_second: Vec<f64>,
+    /// `sendTransaction` budget shared by all workers, per second; 0 is unlimited
+    pub transactions_per_second: f64,
+    /// Secondary RPCs each settlement transaction is also broadcast to, at most
+    /// 2; 0 disables. Shadow sends count against `transactions_per_second`
+    pub shadow_broadcast_count: usize,
+    /// Account reads while building transactions
+    pub read_commitment: String,
+    /// Blockhash a transaction is signed with
+    pub blockhash_commitment: String,
+    /// Preflight and confirmation of sent transactions
+    pub confirmation_commitment: String,
+    /// How often each deployment's `Casino::program_version` is read again
+    pub program_version_check_seconds: u64,
+    /// Default deployment (`VAULT_PROGRAM_ID`) first, then `VAULT_DEPLOYMENTS`
+    pub deployments: Vec<VaultDeployment>,
+}
+
+impl SolanaConfig {
+    /// Vault deployment a settlement for `casino_id` goes to
+    pub fn deployment_for(&self, casino_id: Option<&str>) -> &VaultDeployment {
+        deployments::resolve(&self.deployments, casino_id)
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockchainConfig {
     pub api_base_url: String,
+    #[serde(serialize_with = "profile::redact")]
     pub api_key: String,
     pub poll_interval_seconds: u64,
     pub settlement_batch_size: usize,
+    /// Safety cap on cursor pages followed per coordinator cycle
+    pub max_pages_per_cycle: usize,
+    pub http: HttpClientConfig,
+}
+
+/// Connection pool and timeout settings for the shared blockchain API client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpClientConfig {
+    pub request_timeout_ms: u64,
+    pub connect_timeout_ms: u64,
+    pub pool_max_idle_per_host: usize,
+    pub pool_idle_timeout_seconds: u64,
+    pub tcp_keepalive_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderElectionConfig {
+    pub enabled: bool,
+    #[serde(serialize_with = "profile::redact_url")]
+    pub redis_url: String,
+    pub lease_key: String,
+    pub instance_id: String,
+    pub lease_ttl_seconds: u64,
+    pub renew_interval_seconds: u64,
+    /// Warm standby: start fully but stay idle until this instance holds the lease
+    pub standby: bool,
+    /// How often a non-leader checks whether the lease has lapsed
+    pub standby_check_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillSwitchConfig {
+    /// Poll the shared Redis kill switch flag before dispatching settlements
+    pub enabled: bool,
+    #[serde(serialize_with = "profile::redact_url")]
+    pub redis_url: String,
+    pub poll_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchJournalConfig {
+    /// Record failed coordinator batches in Redis for `processor replay --batch`
+    pub enabled: bool,
+    #[serde(serialize_with = "profile::redact_url")]
+    pub redis_url: String,
+    pub ttl_seconds: u64,
+}
+
+/// Signed POSTs of completed and permanently failed settlements
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutHoldConfig {
+    /// Wins paying at least this many lamports are held for review; 0 disables holds
+    pub threshold_lamports: u64,
+    /// How long a hold lasts without approval; 0 waits for an admin
+    pub delay_seconds: u64,
+    /// Redis shared with the backend, which approves holds
+    #[serde(serialize_with = "profile::redact_url")]
+    pub redis_url: String,
+}
+
+/// Cap on the share of settlements classified as permanent failures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryBudgetConfig {
+    /// Percent of settlement outcomes in the window that may fail permanently; 0 disables the budget
+    pub threshold_percent: f64,
+    pub window_seconds: u64,
+    /// Outcomes the window needs before the budget can trip
+    pub min_samples: usize,
+    /// Receiver of the critical alert when the budget trips
+    #[serde(serialize_with = "profile::redact_optional_url")]
+    pub alert_url: Option<String>,
+    /// Send the alert as a PagerDuty Events v2 trigger with this routing key
+    #[serde(serialize_with = "profile::redact_optional")]
+    pub pagerduty_routing_key: Option<String>,
+    /// Redis shared with the backend, which acknowledges trips
+    #[serde(serialize_with = "profile::redact_url")]
+    pub redis_url: String,
+    pub poll_interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementWebhookConfig {
+    /// Receiver of the webhooks; unset disables them
+    #[serde(serialize_with = "profile::redact_optional_url")]
+    pub url: Option<String>,
+    /// HMAC-SHA256 key the payloads are signed with; required with `url`
+    #[serde(serialize_with = "profile::redact_optional")]
+    pub secret: Option<String>,
+    /// Redis holding the retry queue
+    #[serde(serialize_with = "profile::redact_url")]
+    pub redis_url: String,
+    pub timeout_ms: u64,
+    /// Attempts per delivery, the first included, before it is dropped
+    pub max_attempts: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefundConfig {
+    /// Claim voided-bet refunds from the backend and run `refund_bet` for them
+    pub enabled: bool,
+    pub backend_url: String,
+    pub poll_interval_seconds: u64,
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimSignalsConfig {
+    /// Wake the coordinator and workers on the backend's claimable-bet stream
+    pub enabled: bool,
+    pub backend_url: String,
+    /// Wait before reopening a dropped stream; polling continues meanwhile
+    pub reconnect_seconds: u64,
+}
+
+/// Timeouts, retries and circuit breaking for calls to the backend API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendClientConfig {
+    pub connect_timeout_ms: u64,
+    /// Per-attempt timeout of claim calls
+    pub claim_timeout_ms: u64,
+    /// Per-attempt timeout of update and report calls
+    pub update_timeout_ms: u64,
+    /// Retries after the first attempt, for calls safe to replay
+    pub max_retries: u32,
+    /// Consecutive transient failures that open the circuit (0 disables it)
+    pub circuit_failure_threshold: u32,
+    /// How long calls fail fast once the circuit opens
+    pub circuit_open_seconds: u64,
+}
+
+/// Casino vault top-ups from the treasury with `fund_casino_vault`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CasinoTopUpConfig {
+    pub enabled: bool,
+    /// Tracked casino vault balance that triggers a top-up
+    pub floor_lamports: u64,
+    /// Balance a top-up brings the casino vault back to
+    pub target_lamports: u64,
+    pub check_interval_seconds: u64,
+    /// Treasury keypair the processor signs top-ups with; unset exports them for signing instead
+    #[serde(serialize_with = "profile::redact_optional_keypair")]
+    pub treasury_keypair_path: Option<String>,
+    /// Treasury balance below which every check alerts
+    pub treasury_low_balance_lamports: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrainConfig {
+    /// Settlements fetched in one cycle that switch the coordinator to drain mode (0 disables)
+    pub threshold: usize,
+    /// Page size multiplier while draining
+    pub fetch_multiplier: usize,
+    /// Per-worker batch parallelism while draining; never lowers `SETTLEMENT_BATCH_PARALLELISM`
+    pub batch_parallelism: usize,
+    /// Compute unit price added to settlement transactions while draining (0 for none)
+    pub priority_fee_micro_lamports: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    /// Seed bet outcomes are drawn from; only its commitment is stored on bets
+    #[serde(skip_serializing)]
+    pub server_seed: ServerSeed,
+    /// No `SIMULATION_SERVER_SEED` was set, so the seed is random per process
+    /// and can never be revealed for verification
+    pub seed_generated: bool,
+    /// Latency, failures and win ratio of the simulated path (`SIMULATION_PROFILE`)
+    pub behavior: BehaviorProfile,
 }
 
 impl Config {
-    pub fn load() -> anyhow::Result<Self> {
+    /// Load from the environment, with defaults from `profile` (else `CONFIG_PROFILE`)
+    pub fn load(profile: Option<Profile>) -> anyhow::Result<Self> {
         dotenvy::dotenv().ok();
 
-        let rpc_primary = env::var("SOLANA_RPC_URL").expect("SOLANA_RPC_URL must be set");
+        let profile = match profile {
+            Some(profile) => Some(profile),
+            None => Profile::from_env()?,
+        };
+        let defaults = ProfileDefaults::of(profile);
+
+        let rpc_primary = profile::setting("SOLANA_RPC_URL", defaults.rpc_url)?;
         let rpc_fallback = env::var("SOLANA_RPC_FALLBACK_URL").unwrap_or_else(|_| rpc_primary.clone());
+        let rps_primary = env::var("SOLANA_RPC_REQUESTS_PER_SECOND")
+            .unwrap_or_else(|_| defaults.rpc_requests_per_second.to_string());
+        let rps_fallback = env::var("SOLANA_RPC_FALLBACK_REQUESTS_PER_SECOND").unwrap_or_else(|_| rps_primary.clone());
+        let vault_deployments = deployments::parse_deployments(
+            &profile::setting("VAULT_PROGRAM_ID", defaults.vault_program_id)?,
+            env::var("VAULT_SETTLE_BET")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()?,
+            env::var("VAULT_DEPLOYMENTS").ok().as_deref(),
+        )?;
         
-        Ok(Config {
+        // SOLANA_COMMITMENT is the default for each operation's own setting
+        let commitment = env::var("SOLANA_COMMITMENT").unwrap_or_else(|_| defaults.commitment.to_string());
+
+        // Per-batch-type limits default to the global ones
+        let settlement_batch_parallelism: usize = env::var("SETTLEMENT_BATCH_PARALLELISM")
+            .unwrap_or_else(|_| "4".to_string())
+            .parse()?;
+        let max_bets_per_tx: usize = env::var("PROCESSOR_MAX_BETS_PER_TX")
+            .unwrap_or_else(|_| "12".to_string())
+            .parse()?;
+
+        let config = Config {
+            profile,
             processor: ProcessorConfig {
                 worker_count: env::var("PROCESSOR_WORKER_COUNT")
                     .unwrap_or_else(|_| "10".to_string())
@@ -55,15 +369,14 @@ impl Config {
                 settlement_worker_count: env::var("SETTLEMENT_WORKER_COUNT")
                     .unwrap_or_else(|_| "4".to_string())
                     .parse()?,
+                settlement_batch_parallelism,
                 batch_interval_seconds: env::var("PROCESSOR_BATCH_INTERVAL_SECONDS")
                     .unwrap_or_else(|_| "30".to_string())
                     .parse()?,
                 batch_size: env::var("PROCESSOR_BATCH_SIZE")
                     .unwrap_or_else(|_| "100".to_string())
                     .parse()?,
-                max_bets_per_tx: env::var("PROCESSOR_MAX_BETS_PER_TX")
-                    .unwrap_or_else(|_| "12".to_string())
-                    .parse()?,
+                max_bets_per_tx,
                 max_retries: env::var("PROCESSOR_MAX_RETRIES")
                     .unwrap_or_else(|_| "5".to_string())
                     .parse()?,
@@ -84,13 +397,86 @@ impl Config {
                 coordinator_batch_max_size: env::var("COORDINATOR_BATCH_MAX_SIZE")
                     .unwrap_or_else(|_| "12".to_string())
                     .parse()?,
+                coordinator_max_settlements_per_cycle: env::var("COORDINATOR_MAX_SETTLEMENTS_PER_CYCLE")
+                    .unwrap_or_else(|_| "5000".to_string())
+                    .parse()?,
+                batch_autotune_enabled: env::var("COORDINATOR_BATCH_AUTOTUNE")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
+                simulation_failure_ttl_seconds: env::var("PROCESSOR_SIMULATION_FAILURE_TTL_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()?,
+                batch_compute_unit_limit: env::var("COORDINATOR_BATCH_COMPUTE_UNIT_LIMIT")
+                    .unwrap_or_else(|_| "1400000".to_string())
+                    .parse()?,
+                compute_unit_margin_percent: env::var("PROCESSOR_COMPUTE_UNIT_MARGIN_PERCENT")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()?,
+                rebroadcast_interval_ms: env::var("PROCESSOR_REBROADCAST_INTERVAL_MS")
+                    .unwrap_or_else(|_| "2000".to_string())
+                    .parse()?,
+                max_blockhash_attempts: env::var("PROCESSOR_MAX_BLOCKHASH_ATTEMPTS")
+                    .unwrap_or_else(|_| "3".to_string())
+                    .parse()?,
+                priority_payout_threshold_lamports: env::var("COORDINATOR_PRIORITY_PAYOUT_THRESHOLD_LAMPORTS")
+                    .unwrap_or_else(|_| "10000000000".to_string())
+                    .parse()?,
+                priority_age_threshold_seconds: env::var("COORDINATOR_PRIORITY_AGE_THRESHOLD_SECONDS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()?,
+                priority_allowance_expiry_seconds: env::var("COORDINATOR_PRIORITY_ALLOWANCE_EXPIRY_SECONDS")
+                    .unwrap_or_else(|_| "600".to_string())
+                    .parse()?,
+                allowance_expiry_lookup_enabled: env::var("COORDINATOR_ALLOWANCE_EXPIRY_LOOKUP")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
+                auto_init_vaults: env::var("PROCESSOR_AUTO_INIT_VAULTS")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()?,
+                account_prewarm_enabled: env::var("COORDINATOR_ACCOUNT_PREWARM")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
+                payout_liability_check_enabled: env::var("COORDINATOR_PAYOUT_LIABILITY_CHECK")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
+                payout_liability_reserve_lamports: env::var("COORDINATOR_PAYOUT_LIABILITY_RESERVE_LAMPORTS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()?,
+                casino_pause_check_enabled: env::var("COORDINATOR_CASINO_PAUSE_CHECK")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()?,
+                casino_pause_check_interval_seconds: env::var("COORDINATOR_CASINO_PAUSE_CHECK_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()?,
+                priority_fee_micro_lamports: env::var("PROCESSOR_PRIORITY_FEE_MICRO_LAMPORTS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()?,
+                payout_limits: load_batch_type_limits("PAYOUT", settlement_batch_parallelism, max_bets_per_tx)?,
+                spend_limits: load_batch_type_limits("SPEND", settlement_batch_parallelism, max_bets_per_tx)?,
+                write_lock_stagger_ms: env::var("PROCESSOR_WRITE_LOCK_STAGGER_MS")
+                    .unwrap_or_else(|_| "100".to_string())
+                    .parse()?,
             },
             solana: SolanaConfig {
                 rpc_urls: vec![rpc_primary, rpc_fallback],
-                commitment: env::var("SOLANA_COMMITMENT")
-                    .unwrap_or_else(|_| "confirmed".to_string()),
-                vault_program_id: env::var("VAULT_PROGRAM_ID")
-                    .expect("VAULT_PROGRAM_ID must be set"),
+                rpc_requests_per_second: vec![rps_primary.parse()?, rps_fallback.parse()?],
+                transactions_per_second: env::var("SOLANA_TRANSACTIONS_PER_SECOND")
+                    .unwrap_or_else(|_| defaults.transactions_per_second.to_string())
+                    .parse()?,
+                shadow_broadcast_count: env::var("SOLANA_SHADOW_BROADCAST_COUNT")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse::<usize>()?
+                    .min(crate::shadow_broadcast::MAX_SHADOW_ENDPOINTS),
+                read_commitment: env::var("SOLANA_READ_COMMITMENT")
+                    .unwrap_or_else(|_| commitment.clone()),
+                blockhash_commitment: env::var("SOLANA_BLOCKHASH_COMMITMENT")
+                    .unwrap_or_else(|_| commitment.clone()),
+                confirmation_commitment: env::var("SOLANA_CONFIRMATION_COMMITMENT")
+                    .unwrap_or_else(|_| commitment.clone()),
+                program_version_check_seconds: env::var("SOLANA_PROGRAM_VERSION_CHECK_SECONDS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()?,
+                deployments: vault_deployments,
             },
             blockchain: BlockchainConfig {
                 api_base_url: env::var("BLOCKCHAIN_API_URL")
@@ -103,11 +489,303 @@ impl Config {
                 settlement_batch_size: env::var("BLOCKCHAIN_SETTLEMENT_BATCH_SIZE")
                     .unwrap_or_else(|_| "50".to_string())
                     .parse()?,
+                max_pages_per_cycle: env::var("BLOCKCHAIN_MAX_PAGES_PER_CYCLE")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()?,
+                http: HttpClientConfig {
+                    request_timeout_ms: env::var("BLOCKCHAIN_HTTP_REQUEST_TIMEOUT_MS")
+                        .unwrap_or_else(|_| "10000".to_string())
+                        .parse()?,
+                    connect_timeout_ms: env::var("BLOCKCHAIN_HTTP_CONNECT_TIMEOUT_MS")
+                        .unwrap_or_else(|_| "2000".to_string())
+                        .parse()?,
+                    pool_max_idle_per_host: env::var("BLOCKCHAIN_HTTP_POOL_MAX_IDLE_PER_HOST")
+                        .unwrap_or_else(|_| "32".to_string())
+                        .parse()?,
+                    pool_idle_timeout_seconds: env::var("BLOCKCHAIN_HTTP_POOL_IDLE_TIMEOUT_SECONDS")
+                        .unwrap_or_else(|_| "90".to_string())
+                        .parse()?,
+                    tcp_keepalive_seconds: env::var("BLOCKCHAIN_HTTP_TCP_KEEPALIVE_SECONDS")
+                        .unwrap_or_else(|_| "30".to_string())
+                        .parse()?,
+                },
+            },
+            leader_election: LeaderElectionConfig {
+                enabled: env::var("LEADER_ELECTION_ENABLED")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()?,
+                redis_url: env::var("REDIS_URL")
+                    .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+                lease_key: env::var("LEADER_LEASE_KEY")
+                    .unwrap_or_else(|_| "processor:coordinator:leader".to_string()),
+                instance_id: env::var("PROCESSOR_INSTANCE_ID")
+                    .or_else(|_| env::var("HOSTNAME"))
+                    .unwrap_or_else(|_| uuid::Uuid::new_v4().to_string()),
+                lease_ttl_seconds: env::var("LEADER_LEASE_TTL_SECONDS")
+                    .unwrap_or_else(|_| "15".to_string())
+                    .parse()?,
+                renew_interval_seconds: env::var("LEADER_RENEW_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()?,
+                standby: env::var("PROCESSOR_STANDBY")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()?,
+                standby_check_interval_ms: env::var("LEADER_STANDBY_CHECK_INTERVAL_MS")
+                    .unwrap_or_else(|_| "1000".to_string())
+                    .parse()?,
+            },
+            kill_switch: KillSwitchConfig {
+                enabled: env::var("KILL_SWITCH_ENABLED")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()?,
+                redis_url: env::var("REDIS_URL")
+                    .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+                poll_interval_ms: env::var("KILL_SWITCH_POLL_INTERVAL_MS")
+                    .unwrap_or_else(|_| "2000".to_string())
+                    .parse()?,
+            },
+            batch_journal: BatchJournalConfig {
+                enabled: env::var("BATCH_JOURNAL_ENABLED")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()?,
+                redis_url: env::var("REDIS_URL")
+                    .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+                ttl_seconds: env::var("BATCH_JOURNAL_TTL_SECONDS")
+                    .unwrap_or_else(|_| "604800".to_string())
+                    .parse()?,
+            },
+            refund: RefundConfig {
+                enabled: env::var("REFUND_WORKER_ENABLED")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()?,
+                backend_url: env::var("BACKEND_API_URL")
+                    .unwrap_or_else(|_| "http://localhost:3001".to_string()),
+                poll_interval_seconds: env::var("REFUND_POLL_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "15".to_string())
+                    .parse()?,
+                batch_size: env::var("REFUND_BATCH_SIZE")
+                    .unwrap_or_else(|_| "20".to_string())
+                    .parse()?,
+            },
+            claim_signals: ClaimSignalsConfig {
+                enabled: env::var("CLAIM_SIGNALS_ENABLED")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()?,
+                backend_url: env::var("BACKEND_API_URL")
+                    .unwrap_or_else(|_| "http://localhost:3001".to_string()),
+                reconnect_seconds: env::var("CLAIM_SIGNALS_RECONNECT_SECONDS")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()?,
+            },
+            settlement_webhook: SettlementWebhookConfig {
+                url: env::var("SETTLEMENT_WEBHOOK_URL").ok().filter(|v| !v.is_empty()),
+                secret: env::var("SETTLEMENT_WEBHOOK_SECRET").ok().filter(|v| !v.is_empty()),
+                redis_url: env::var("REDIS_URL")
+                    .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+                timeout_ms: env::var("SETTLEMENT_WEBHOOK_TIMEOUT_MS")
+                    .unwrap_or_else(|_| "5000".to_string())
+                    .parse()?,
+                max_attempts: env::var("SETTLEMENT_WEBHOOK_MAX_ATTEMPTS")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()?,
+            },
+            payout_hold: PayoutHoldConfig {
+                threshold_lamports: env::var("PAYOUT_HOLD_THRESHOLD_LAMPORTS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()?,
+                delay_seconds: env::var("PAYOUT_HOLD_DELAY_SECONDS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()?,
+                redis_url: env::var("REDIS_URL")
+                    .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+            },
+            retry_budget: RetryBudgetConfig {
+                threshold_percent: env::var("RETRY_BUDGET_THRESHOLD_PERCENT")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()?,
+                window_seconds: env::var("RETRY_BUDGET_WINDOW_SECONDS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()?,
+                min_samples: env::var("RETRY_BUDGET_MIN_SAMPLES")
+                    .unwrap_or_else(|_| "20".to_string())
+                    .parse()?,
+                alert_url: env::var("RETRY_BUDGET_ALERT_URL").ok().filter(|v| !v.is_empty()),
+                pagerduty_routing_key: env::var("RETRY_BUDGET_PAGERDUTY_ROUTING_KEY").ok().filter(|v| !v.is_empty()),
+                redis_url: env::var("REDIS_URL")
+                    .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+                poll_interval_ms: env::var("RETRY_BUDGET_POLL_INTERVAL_MS")
+                    .unwrap_or_else(|_| "2000".to_string())
+                    .parse()?,
+            },
+            backend: BackendClientConfig {
+                connect_timeout_ms: env::var("BACKEND_CONNECT_TIMEOUT_MS")
+                    .unwrap_or_else(|_| "2000".to_string())
+                    .parse()?,
+                claim_timeout_ms: env::var("BACKEND_CLAIM_TIMEOUT_MS")
+                    .unwrap_or_else(|_| "5000".to_string())
+                    .parse()?,
+                update_timeout_ms: env::var("BACKEND_UPDATE_TIMEOUT_MS")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()?,
+                max_retries: env::var("BACKEND_MAX_RETRIES")
+                    .unwrap_or_else(|_| "3".to_string())
+                    .parse()?,
+                circuit_failure_threshold: env::var("BACKEND_CIRCUIT_FAILURE_THRESHOLD")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()?,
+                circuit_open_seconds: env::var("BACKEND_CIRCUIT_OPEN_SECONDS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()?,
+            },
+            casino_topup: CasinoTopUpConfig {
+                enabled: env::var("CASINO_TOPUP_ENABLED")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()?,
+                floor_lamports: env::var("CASINO_TOPUP_FLOOR_LAMPORTS")
+                    .unwrap_or_else(|_| "10000000000".to_string())
+                    .parse()?,
+                target_lamports: env::var("CASINO_TOPUP_TARGET_LAMPORTS")
+                    .unwrap_or_else(|_| "50000000000".to_string())
+                    .parse()?,
+                check_interval_seconds: env::var("CASINO_TOPUP_CHECK_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()?,
+                treasury_keypair_path: env::var("TREASURY_KEYPAIR_PATH").ok().filter(|path| !path.is_empty()),
+                treasury_low_balance_lamports: env::var("TREASURY_LOW_BALANCE_LAMPORTS")
+                    .unwrap_or_else(|_| "100000000000".to_string())
+                    .parse()?,
+            },
+            drain: DrainConfig {
+                threshold: env::var("COORDINATOR_DRAIN_THRESHOLD")
+                    .unwrap_or_else(|_| "1000".to_string())
+                    .parse()?,
+                fetch_multiplier: env::var("COORDINATOR_DRAIN_FETCH_MULTIPLIER")
+                    .unwrap_or_else(|_| "4".to_string())
+                    .parse()?,
+                batch_parallelism: env::var("COORDINATOR_DRAIN_BATCH_PARALLELISM")
+                    .unwrap_or_else(|_| "8".to_string())
+                    .parse()?,
+                priority_fee_micro_lamports: env::var("COORDINATOR_DRAIN_PRIORITY_FEE_MICRO_LAMPORTS")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()?,
+            },
+            simulation: SimulationConfig {
+                server_seed: match env::var("SIMULATION_SERVER_SEED") {
+                    Ok(seed) if !seed.trim().is_empty() => ServerSeed::from_hex(&seed)
+                        .map_err(|e| anyhow::anyhow!("Invalid SIMULATION_SERVER_SEED: {}", e))?,
+                    _ => ServerSeed::generate(),
+                },
+                seed_generated: env::var("SIMULATION_SERVER_SEED").map_or(true, |seed| seed.trim().is_empty()),
+                behavior: load_behavior_profile()?,
             },
             metrics_port: env::var("PROCESSOR_METRICS_PORT")
                 .unwrap_or_else(|_| "9091".to_string())
                 .parse()?,
-        })
+        };
+        if config.leader_election.standby && !(config.leader_election.enabled && config.processor.coordinator_enabled) {
+            anyhow::bail!("PROCESSOR_STANDBY needs LEADER_ELECTION_ENABLED and COORDINATOR_ENABLED");
+        }
+        if config.profile == Some(Profile::Mainnet) && !config.simulation.behavior.is_instant() {
+            anyhow::bail!(
+                "SIMULATION_PROFILE {} injects latency, failures or fixed outcomes; not allowed on mainnet",
+                config.simulation.behavior.name
+            );
+        }
+        Ok(config)
+    }
+
+    /// This configuration as JSON, secrets redacted, for the startup audit log
+    pub fn effective(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|e| format!("<unprintable: {}>", e))
+    }
+
+    /// Fetch the secrets config values refer to (see `shared::secrets`)
+    ///
+    /// Runs before anything connects. `PROCESSOR_KEYPAIR` and
+    /// `TREASURY_KEYPAIR_PATH` may come back as keypair JSON rather than a path.
+    pub async fn resolve_secrets(&mut self) -> anyhow::Result<()> {
+        let mut resolver = Resolver::new();
+        resolver.resolve_in_place(&mut self.processor.keypair_path).await.context("PROCESSOR_KEYPAIR")?;
+        resolver.resolve_in_place(&mut self.blockchain.api_key).await.context("BLOCKCHAIN_API_KEY")?;
+        for redis_url in [
+            &mut self.leader_election.redis_url,
+            &mut self.kill_switch.redis_url,
+            &mut self.batch_journal.redis_url,
+            &mut self.settlement_webhook.redis_url,
+            &mut self.payout_hold.redis_url,
+            &mut self.retry_budget.redis_url,
+        ] {
+            resolver.resolve_in_place(redis_url).await.context("REDIS_URL")?;
+        }
+        resolver
+            .resolve_optional(&mut self.settlement_webhook.secret)
+            .await
+            .context("SETTLEMENT_WEBHOOK_SECRET")?;
+        resolver
+            .resolve_optional(&mut self.retry_budget.pagerduty_routing_key)
+            .await
+            .context("RETRY_BUDGET_PAGERDUTY_ROUTING_KEY")?;
+        resolver
+            .resolve_optional(&mut self.casino_topup.treasury_keypair_path)
+            .await
+            .context("TREASURY_KEYPAIR_PATH")?;
+        Ok(())
     }
 }
 
+/// `PROCESSOR_<batch type>_MAX_PARALLEL_TX`, `_MAX_PER_TX` and
+/// `_PRIORITY_FEE_MULTIPLIER`, defaulting to `SETTLEMENT_BATCH_PARALLELISM`,
+/// `PROCESSOR_MAX_BETS_PER_TX` and 1
+fn load_batch_type_limits(batch_type: &str, max_parallel_transactions: usize, max_per_tx: usize) -> anyhow::Result<BatchTypeLimits> {
+    let mut limits = BatchTypeLimits { max_parallel_transactions, max_per_tx, priority_fee_multiplier: 1.0 };
+    let key = |name: &str| format!("PROCESSOR_{}_{}", batch_type, name);
+    if let Ok(value) = env::var(key("MAX_PARALLEL_TX")) {
+        limits.max_parallel_transactions = value.parse().with_context(|| key("MAX_PARALLEL_TX"))?;
+    }
+    if let Ok(value) = env::var(key("MAX_PER_TX")) {
+        limits.max_per_tx = value.parse().with_context(|| key("MAX_PER_TX"))?;
+    }
+    if let Ok(value) = env::var(key("PRIORITY_FEE_MULTIPLIER")) {
+        limits.priority_fee_multiplier = value.parse().with_context(|| key("PRIORITY_FEE_MULTIPLIER"))?;
+    }
+    if limits.max_per_tx == 0 {
+        anyhow::bail!("{} must be at least 1", key("MAX_PER_TX"));
+    }
+    if !limits.priority_fee_multiplier.is_finite() || limits.priority_fee_multiplier < 0.0 {
+        anyhow::bail!("{} must not be negative", key("PRIORITY_FEE_MULTIPLIER"));
+    }
+    Ok(limits)
+}
+
+/// `SIMULATION_PROFILE` (instant, realistic or degraded), with any of its
+/// settings overridden by `SIMULATION_LATENCY_*`, `SIMULATION_FAILURE_RATE` and
+/// `SIMULATION_WIN_RATIO`
+fn load_behavior_profile() -> anyhow::Result<BehaviorProfile> {
+    let mut behavior: BehaviorProfile = env::var("SIMULATION_PROFILE")
+        .unwrap_or_else(|_| "instant".to_string())
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    if let Ok(value) = env::var("SIMULATION_LATENCY_BASE_MS") {
+        behavior.latency.base_ms = value.parse().context("SIMULATION_LATENCY_BASE_MS")?;
+    }
+    if let Ok(value) = env::var("SIMULATION_LATENCY_JITTER_MS") {
+        behavior.latency.jitter_ms = value.parse().context("SIMULATION_LATENCY_JITTER_MS")?;
+    }
+    if let Ok(value) = env::var("SIMULATION_LATENCY_TAIL_MS") {
+        behavior.latency.tail_ms = value.parse().context("SIMULATION_LATENCY_TAIL_MS")?;
+    }
+    if let Ok(value) = env::var("SIMULATION_LATENCY_TAIL_RATE") {
+        behavior.latency.tail_rate = value.parse().context("SIMULATION_LATENCY_TAIL_RATE")?;
+    }
+    if let Ok(value) = env::var("SIMULATION_FAILURE_RATE") {
+        behavior.failure_rate = value.parse().context("SIMULATION_FAILURE_RATE")?;
+    }
+    match env::var("SIMULATION_WIN_RATIO") {
+        Ok(value) if !value.trim().is_empty() => {
+            behavior.win_ratio = Some(value.parse().context("SIMULATION_WIN_RATIO")?);
+        }
+        _ => {}
+    }
+    behavior.validate().map_err(|e| anyhow::anyhow!("Invalid simulation profile: {}", e))?;
+    Ok(behavior)
+}
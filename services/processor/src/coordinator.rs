@@ -1,17 +1,29 @@
 //! Settlement Coordinator
 //! 
-//! Fetches all pending settlements from blockchain API and distributes to workers
-//! via channels. Prevents duplicate processing and enables efficient batching.
+//! Streams pending settlements from the blockchain API page by page and distributes
+//! them to workers via channels. Prevents duplicate processing and enables efficient batching.
 
 use crate::{
-    blockchain_client::{BlockchainClient, GameSettlementInfo},
+    account_prewarm::AccountPrewarm,
+    allowance_expiry::AllowanceExpiryCache,
+    batch_tuner::BatchSizeTuner,
+    blockchain_client::{BlockchainClient, GameSettlementInfo, RateLimited},
+    casino_pause::CasinoPause,
+    claim_signals::{self, ClaimSignalSource},
     config::Config,
+    drain::{DrainMode, DrainTransition},
+    kill_switch::KillSwitch,
+    leader_election::LeaderElection,
+    payout_hold::PayoutHolds,
+    payout_liability::PayoutLiability,
+    progress::ProgressRegistry,
 };
 use anyhow::{Context, Result};
-use std::sync::Arc;
-use std::time::Duration;
+use shared::clock::{self, Clock, SystemClock};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
-use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
@@ -21,6 +33,9 @@ pub struct SettlementBatch {
     pub batch_id: String,
     pub settlements: Vec<GameSettlementInfo>,
     pub batch_type: BatchType,
+    pub lane: Lane,
+    /// When the coordinator dispatched the batch (for per-lane latency)
+    pub dispatched_at: Instant,
 }
 
 /// Type of settlement batch
@@ -30,27 +45,236 @@ pub enum BatchType {
     Spend,   // Loss - spend from user's allowance to casino
 }
 
+impl BatchType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatchType::Payout => "payout",
+            BatchType::Spend => "spend",
+        }
+    }
+}
+
+/// Dispatch lane; workers always drain `High` before `Normal`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    High,
+    Normal,
+}
+
+impl Lane {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Lane::High => "high",
+            Lane::Normal => "normal",
+        }
+    }
+}
+
+/// Coordinator side of a worker's lanes
+pub struct WorkerChannel {
+    pub high: mpsc::Sender<SettlementBatch>,
+    pub normal: mpsc::Sender<SettlementBatch>,
+}
+
+/// Worker side of its lanes
+pub struct WorkerInbox {
+    pub high: mpsc::Receiver<SettlementBatch>,
+    pub normal: mpsc::Receiver<SettlementBatch>,
+}
+
+/// Create the high/normal channel pair for one worker
+pub fn worker_channel(buffer_size: usize) -> (WorkerChannel, WorkerInbox) {
+    let (high_tx, high_rx) = mpsc::channel(buffer_size);
+    let (normal_tx, normal_rx) = mpsc::channel(buffer_size);
+    (
+        WorkerChannel { high: high_tx, normal: normal_tx },
+        WorkerInbox { high: high_rx, normal: normal_rx },
+    )
+}
+
+/// Thresholds that promote a settlement to the high-priority lane
+#[derive(Debug, Clone, Copy)]
+pub struct PriorityThresholds {
+    pub payout_lamports: u64,
+    pub age: Duration,
+    /// Allowances expiring within this window must be spent first
+    pub allowance_expiry: Duration,
+}
+
+/// Classify a settlement: admin-flagged, large payouts, long-waiting settlements
+/// and ones whose allowance is about to expire go first
+pub fn classify_lane(
+    settlement: &GameSettlementInfo,
+    age: Duration,
+    now_unix: i64,
+    thresholds: PriorityThresholds,
+) -> Lane {
+    let allowance_expiring = settlement
+        .allowance_expires_at
+        .is_some_and(|expires_at| expires_at - now_unix <= thresholds.allowance_expiry.as_secs() as i64);
+    if settlement.priority
+        || settlement.payout >= thresholds.payout_lamports
+        || age >= thresholds.age
+        || allowance_expiring
+    {
+        Lane::High
+    } else {
+        Lane::Normal
+    }
+}
+
+/// Dispatch order within a lane: soonest allowance expiry first, unknown expiry last
+fn expiry_key(settlement: &GameSettlementInfo) -> i64 {
+    settlement.allowance_expires_at.unwrap_or(i64::MAX)
+}
+
+/// How far one coordinator cycle follows the pending-settlements cursor
+#[derive(Debug, Clone, Copy)]
+struct CycleBudget {
+    max_pages: usize,
+    /// 0 leaves only the page cap
+    max_settlements: usize,
+}
+
+impl CycleBudget {
+    /// Whether another page may be fetched after `pages` pages holding `fetched` settlements
+    fn allows_more(&self, pages: usize, fetched: usize) -> bool {
+        pages < self.max_pages.max(1) && (self.max_settlements == 0 || fetched < self.max_settlements)
+    }
+
+    /// Page size for the next fetch, shrunk so the cycle stops at its settlement cap
+    fn page_limit(&self, limit: usize, fetched: usize) -> usize {
+        if self.max_settlements == 0 {
+            limit
+        } else {
+            limit.min(self.max_settlements.saturating_sub(fetched)).max(1)
+        }
+    }
+}
+
 pub struct Coordinator {
     blockchain_client: Arc<BlockchainClient>,
-    work_senders: Vec<mpsc::Sender<SettlementBatch>>,
+    work_senders: Vec<WorkerChannel>,
     config: Config,
     next_worker_index: std::sync::atomic::AtomicUsize,
+    leader_election: Option<Arc<LeaderElection>>,
+    kill_switch: Option<Arc<KillSwitch>>,
+    progress: Arc<ProgressRegistry>,
+    /// When each pending settlement was first fetched, for age-based priority
+    first_seen: Mutex<HashMap<u64, Instant>>,
+    /// Cursor to resume from when the previous cycle hit the page cap
+    resume_cursor: Mutex<Option<String>>,
+    /// Adaptive batch size; without one batches fill to `coordinator_batch_max_size`
+    batch_tuner: Option<Arc<BatchSizeTuner>>,
+    /// Reads allowance expiry from chain when the blockchain API doesn't send it
+    allowance_expiry: Option<Arc<AllowanceExpiryCache>>,
+    /// Higher-throughput settings while a backlog drains, shared with the workers
+    drain: Option<Arc<DrainMode>>,
+    /// Holds large payouts back until their delay passes or an admin approves them
+    payout_holds: Option<Arc<PayoutHolds>>,
+    /// Creates missing vaults and token accounts ahead of the workers
+    account_prewarm: Option<Arc<AccountPrewarm>>,
+    /// Defers payout batches the casino vault can't cover
+    payout_liability: Option<Arc<PayoutLiability>>,
+    /// Holds settlements back from casinos paused on-chain
+    casino_pause: Option<Arc<CasinoPause>>,
+    /// Cuts the wait between cycles short when the backend signals a claimable bet
+    claim_signals: Option<Arc<ClaimSignalSource>>,
+    /// Time allowance expiry is checked against; paces skipped cycles
+    clock: Arc<dyn Clock>,
 }
 
 impl Coordinator {
     pub fn new(
         blockchain_client: Arc<BlockchainClient>,
-        work_senders: Vec<mpsc::Sender<SettlementBatch>>,
+        work_senders: Vec<WorkerChannel>,
         config: Config,
+        leader_election: Option<Arc<LeaderElection>>,
+        kill_switch: Option<Arc<KillSwitch>>,
+        progress: Arc<ProgressRegistry>,
     ) -> Self {
         Self {
             blockchain_client,
             work_senders,
             config,
             next_worker_index: std::sync::atomic::AtomicUsize::new(0),
+            leader_election,
+            kill_switch,
+            progress,
+            first_seen: Mutex::new(HashMap::new()),
+            resume_cursor: Mutex::new(None),
+            batch_tuner: None,
+            allowance_expiry: None,
+            drain: None,
+            payout_holds: None,
+            account_prewarm: None,
+            payout_liability: None,
+            casino_pause: None,
+            claim_signals: None,
+            clock: SystemClock::shared(),
         }
     }
 
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Size batches from observed transaction size and compute units
+    pub fn with_batch_tuner(mut self, batch_tuner: Arc<BatchSizeTuner>) -> Self {
+        self.batch_tuner = Some(batch_tuner);
+        self
+    }
+
+    /// Look up allowance expiry on chain to dispatch expiring allowances first
+    pub fn with_allowance_expiry(mut self, allowance_expiry: Arc<AllowanceExpiryCache>) -> Self {
+        self.allowance_expiry = Some(allowance_expiry);
+        self
+    }
+
+    /// Switch to drain mode when a cycle fetches a large backlog
+    pub fn with_drain_mode(mut self, drain: Arc<DrainMode>) -> Self {
+        self.drain = Some(drain);
+        self
+    }
+
+    /// Hold payouts above the configured threshold for review
+    pub fn with_payout_holds(mut self, payout_holds: Arc<PayoutHolds>) -> Self {
+        self.payout_holds = Some(payout_holds);
+        self
+    }
+
+    /// Pre-warm the accounts of fetched settlements
+    pub fn with_account_prewarm(mut self, account_prewarm: Arc<AccountPrewarm>) -> Self {
+        self.account_prewarm = Some(account_prewarm);
+        self
+    }
+
+    /// Check payout batches against the casino vault balance before dispatch
+    pub fn with_payout_liability(mut self, payout_liability: Arc<PayoutLiability>) -> Self {
+        self.payout_liability = Some(payout_liability);
+        self
+    }
+
+    /// Skip dispatch to casinos paused on-chain
+    pub fn with_casino_pause(mut self, casino_pause: Arc<CasinoPause>) -> Self {
+        self.casino_pause = Some(casino_pause);
+        self
+    }
+
+    /// Start the next cycle as soon as the backend signals a claimable bet
+    pub fn with_claim_signals(mut self, claim_signals: Arc<ClaimSignalSource>) -> Self {
+        self.claim_signals = Some(claim_signals);
+        self
+    }
+
+    /// Only the lease holder dispatches; without leader election we always do
+    fn is_active(&self) -> bool {
+        self.leader_election
+            .as_ref()
+            .is_none_or(|election| election.is_leader())
+    }
+
     /// Main coordinator loop - fetches and distributes work
     pub async fn run(&self) {
         let poll_interval = Duration::from_secs(self.config.blockchain.poll_interval_seconds);
@@ -60,88 +284,259 @@ impl Coordinator {
             worker_count = self.work_senders.len(),
             batch_min = self.config.processor.coordinator_batch_min_size,
             batch_max = self.config.processor.coordinator_batch_max_size,
+            batch_autotune = self.batch_tuner.is_some() && self.config.processor.batch_autotune_enabled,
             "Coordinator starting"
         );
+        let mut claim_waiter = self.claim_signals.as_ref().map(|signals| signals.waiter());
 
         loop {
+            if !self.is_active() {
+                debug!("Coordinator on standby - not the leader");
+                // Wake as soon as the lease is ours rather than up to a poll interval later
+                if let Some(election) = &self.leader_election {
+                    let _ = clock::timeout(self.clock.as_ref(), poll_interval, election.wait_for_leadership()).await;
+                }
+                continue;
+            }
+
+            if self.kill_switch.as_ref().is_some_and(|ks| ks.is_engaged()) {
+                debug!("Kill switch engaged - skipping dispatch");
+                self.progress.cycle_skipped();
+                self.clock.sleep(poll_interval).await;
+                continue;
+            }
+
+            if let Some(casino_pause) = &self.casino_pause {
+                casino_pause.refresh().await;
+                self.progress.set_paused_deployments(casino_pause.paused_deployments());
+                if casino_pause.all_paused() {
+                    debug!("Casino paused on-chain - skipping dispatch");
+                    self.progress.cycle_skipped();
+                    self.clock.sleep(poll_interval).await;
+                    continue;
+                }
+            }
+
             let cycle_start = std::time::Instant::now();
             
-            if let Err(e) = self.process_cycle().await {
+            let outcome = self.process_cycle().await;
+            if let Err(e) = &outcome {
                 error!(error = %e, "Coordinator cycle failed");
             }
 
             let elapsed = cycle_start.elapsed();
+            self.progress.cycle_finished(elapsed, outcome.as_ref().copied(), self.queued_batches());
             info!(
                 cycle_duration_ms = elapsed.as_millis(),
                 "Coordinator cycle completed"
             );
 
-            sleep(poll_interval).await;
+            // A rate-limit pause is waited out even if bets arrive meanwhile
+            let delay = self.next_cycle_delay(poll_interval);
+            let waiter = claim_waiter.as_mut().filter(|_| delay <= poll_interval);
+            claim_signals::sleep_or_signal(delay, waiter).await;
         }
     }
 
-    async fn process_cycle(&self) -> Result<()> {
-        // 1. Fetch all pending settlements
-        let settlements = self.fetch_all_pending().await?;
+    /// The poll interval, stretched to the end of a blockchain API rate-limit pause
+    fn next_cycle_delay(&self, poll_interval: Duration) -> Duration {
+        let pause = self.blockchain_client.rate_limit_pause();
+        metrics::gauge!("coordinator_rate_limit_pause_seconds").set(pause.as_secs_f64());
+        if pause > poll_interval {
+            info!(pause_ms = pause.as_millis() as u64, "Blockchain API rate limited; delaying next cycle");
+            metrics::counter!("coordinator_rate_limited_cycles_total").increment(1);
+        }
+        pause.max(poll_interval)
+    }
 
-        if settlements.is_empty() {
+    /// Run one fetch/batch/dispatch pass; returns (settlements fetched, batches distributed)
+    ///
+    /// Pages are batched and dispatched as they arrive, so at most one page plus
+    /// the workers' channels are held at a time. High-priority batches go first
+    /// within each page; across pages workers drain the high lane first anyway.
+    async fn process_cycle(&self) -> Result<(usize, usize)> {
+        let budget = CycleBudget {
+            max_pages: self.config.blockchain.max_pages_per_cycle,
+            max_settlements: self.config.processor.coordinator_max_settlements_per_cycle,
+        };
+        let limit = self.config.blockchain.settlement_batch_size;
+        let limit = self.drain.as_ref().map_or(limit, |drain| drain.fetch_limit(limit));
+        let mut cursor = self
+            .resume_cursor
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+
+        if let Some(payout_liability) = &self.payout_liability {
+            payout_liability.refresh().await;
+        }
+
+        let mut seen = HashSet::new();
+        let mut pages = 0;
+        let mut fetched = 0;
+        let mut distributed = 0;
+        let mut rate_limited = false;
+
+        loop {
+            let page = match self
+                .blockchain_client
+                .fetch_pending_page(budget.page_limit(limit, fetched), cursor.as_deref())
+                .await
+            {
+                Ok(page) => page,
+                // Dispatch what was fetched and pick up from this page once the pause ends
+                Err(e) if e.is::<RateLimited>() => {
+                    if pages == 0 {
+                        *self.resume_cursor.lock().unwrap_or_else(|e| e.into_inner()) = cursor;
+                        return Err(e);
+                    }
+                    warn!(pages, fetched, error = %e, "Rate limited mid-cycle; resuming from cursor next cycle");
+                    rate_limited = true;
+                    break;
+                }
+                Err(e) => return Err(e).context("Failed to fetch pending settlements"),
+            };
+            pages += 1;
+            fetched += page.games.len();
+            cursor = page.next_cursor;
+
+            distributed += self.dispatch_page(page.games, &mut seen).await;
+
+            if cursor.is_none() || !budget.allows_more(pages, fetched) {
+                break;
+            }
+        }
+
+        self.forget_unseen(&seen);
+        metrics::histogram!("blockchain_pending_pages_fetched").record(pages as f64);
+        let more_pages = cursor.is_some();
+        if more_pages && !rate_limited {
+            warn!(pages, fetched, "Pending settlement cycle cap reached; resuming from cursor next cycle");
+            metrics::counter!("blockchain_pending_page_cap_reached_total").increment(1);
+        }
+        *self.resume_cursor.lock().unwrap_or_else(|e| e.into_inner()) = cursor;
+        self.observe_backlog(fetched, more_pages);
+
+        if fetched == 0 {
             debug!("No pending settlements found");
-            return Ok(());
+            return Ok((0, 0));
         }
 
         info!(
-            total_settlements = settlements.len(),
-            "Fetched pending settlements"
+            total_settlements = fetched,
+            pages,
+            distributed_batches = distributed,
+            resume = more_pages,
+            "Work distribution completed"
         );
 
-        // 2. Group by outcome type (Win vs Loss)
-        let (wins, losses) = self.group_by_outcome(settlements);
-        
-        info!(
-            wins = wins.len(),
-            losses = losses.len(),
-            "Grouped settlements by outcome"
-        );
+        Ok((fetched, distributed))
+    }
 
-        // 3. Create batches
-        let win_batches = self.create_batches(wins, BatchType::Payout);
-        let loss_batches = self.create_batches(losses, BatchType::Spend);
+    /// Batch one page of settlements and send its batches; returns batches distributed
+    async fn dispatch_page(&self, mut settlements: Vec<GameSettlementInfo>, seen: &mut HashSet<u64>) -> usize {
+        if settlements.is_empty() {
+            return 0;
+        }
 
-        info!(
-            win_batches = win_batches.len(),
-            loss_batches = loss_batches.len(),
-            total_batches = win_batches.len() + loss_batches.len(),
-            "Created settlement batches"
+        self.track_first_seen(&settlements, seen);
+
+        if let Some(account_prewarm) = &self.account_prewarm {
+            account_prewarm.spawn(&settlements);
+        }
+
+        if let Some(payout_holds) = &self.payout_holds {
+            settlements = payout_holds.release(settlements).await;
+        }
+
+        if let Some(casino_pause) = &self.casino_pause {
+            settlements = casino_pause.retain_running(settlements);
+        }
+
+        if let Some(allowance_expiry) = &self.allowance_expiry {
+            // Without expiries settlements still go out, just in the usual order
+            if let Err(e) = allowance_expiry.annotate(&mut settlements).await {
+                warn!(error = %e, "Failed to look up allowance expiry");
+            }
+        }
+
+        // Group by outcome type (Win vs Loss) and batch each lane
+        let (wins, losses) = self.group_by_outcome(settlements);
+        let win_count = wins.len();
+        let loss_count = losses.len();
+        let mut batches = self.create_batches(wins, BatchType::Payout);
+        batches.extend(self.create_batches(losses, BatchType::Spend));
+
+        debug!(
+            wins = win_count,
+            losses = loss_count,
+            batches = batches.len(),
+            "Created settlement batches for page"
         );
 
-        // 4. Distribute to workers (round-robin)
+        // Distribute to workers (round-robin), high-priority batches first and,
+        // within a lane, the batch holding the soonest-expiring allowance first
+        batches.sort_by_key(|batch| {
+            let soonest_expiry = batch.settlements.iter().map(expiry_key).min().unwrap_or(i64::MAX);
+            (batch.lane != Lane::High, soonest_expiry)
+        });
+        if let Some(payout_liability) = &self.payout_liability {
+            batches = payout_liability.admit(batches);
+        }
+
         let mut distributed = 0;
-        
-        for batch in win_batches.into_iter().chain(loss_batches.into_iter()) {
+        for batch in batches {
             if let Err(e) = self.send_to_worker(batch).await {
                 error!(error = %e, "Failed to send batch to worker");
             } else {
                 distributed += 1;
             }
         }
+        distributed
+    }
 
-        info!(
-            distributed_batches = distributed,
-            "Work distribution completed"
-        );
-
-        Ok(())
+    /// Enter or leave drain mode and report progress while draining
+    fn observe_backlog(&self, fetched: usize, more_pages: bool) {
+        let Some(drain) = &self.drain else {
+            return;
+        };
+        match drain.observe(fetched, more_pages) {
+            DrainTransition::Idle => return,
+            DrainTransition::Entered => warn!(
+                backlog = fetched,
+                more_pages,
+                threshold = self.config.drain.threshold,
+                "Settlement backlog detected; entering drain mode"
+            ),
+            DrainTransition::Draining { peak, elapsed } => info!(
+                backlog = fetched,
+                more_pages,
+                peak_backlog = peak,
+                elapsed_seconds = elapsed.as_secs(),
+                "Draining settlement backlog"
+            ),
+            DrainTransition::Exited { peak, elapsed } => info!(
+                backlog = fetched,
+                peak_backlog = peak,
+                elapsed_seconds = elapsed.as_secs(),
+                "Settlement backlog cleared; leaving drain mode"
+            ),
+        }
+        metrics::gauge!("coordinator_drain_active").set(if drain.is_active() { 1.0 } else { 0.0 });
+        metrics::gauge!("coordinator_drain_backlog").set(fetched as f64);
     }
 
-    /// Fetch all pending settlements from blockchain API
-    async fn fetch_all_pending(&self) -> Result<Vec<GameSettlementInfo>> {
-        // Fetch larger batch size to get all pending
-        let limit = self.config.blockchain.settlement_batch_size;
-        
-        self.blockchain_client
-            .fetch_pending_settlements(limit)
-            .await
-            .context("Failed to fetch pending settlements")
+    /// Batches still waiting in each worker's channels, keyed by worker id
+    fn queued_batches(&self) -> BTreeMap<usize, usize> {
+        self.work_senders
+            .iter()
+            .enumerate()
+            .map(|(index, channel)| {
+                let queued = (channel.high.max_capacity() - channel.high.capacity())
+                    + (channel.normal.max_capacity() - channel.normal.capacity());
+                (index + 1, queued)
+            })
+            .collect()
     }
 
     /// Group settlements by outcome type
@@ -166,19 +561,74 @@ impl Coordinator {
         (wins, losses)
     }
 
-    /// Create batches from settlements
-    /// 
+    /// Remember when each settlement was first seen, and that this cycle saw it
+    fn track_first_seen(&self, settlements: &[GameSettlementInfo], seen: &mut HashSet<u64>) {
+        let now = Instant::now();
+        let mut first_seen = self.first_seen.lock().unwrap_or_else(|e| e.into_inner());
+        for settlement in settlements {
+            seen.insert(settlement.transaction_id);
+            first_seen.entry(settlement.transaction_id).or_insert(now);
+        }
+    }
+
+    /// Forget settlements the cycle didn't see; they are no longer pending
+    fn forget_unseen(&self, seen: &HashSet<u64>) {
+        self.first_seen
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|tx_id, _| seen.contains(tx_id));
+    }
+
+    fn priority_thresholds(&self) -> PriorityThresholds {
+        PriorityThresholds {
+            payout_lamports: self.config.processor.priority_payout_threshold_lamports,
+            age: Duration::from_secs(self.config.processor.priority_age_threshold_seconds),
+            allowance_expiry: Duration::from_secs(self.config.processor.priority_allowance_expiry_seconds),
+        }
+    }
+
+    /// Create batches from settlements, split into high-priority and normal lanes
+    ///
     /// Strategy:
     /// - Min batch size: 3 (amortize TX cost)
     /// - Max batch size: 12 (Solana TX size limit)
     /// - Optimal: 8 (balance cost vs blast radius)
     fn create_batches(&self, settlements: Vec<GameSettlementInfo>, batch_type: BatchType) -> Vec<SettlementBatch> {
+        let thresholds = self.priority_thresholds();
+        let now = self.clock.now().timestamp();
+        let (mut high, mut normal): (Vec<_>, Vec<_>) = {
+            let first_seen = self.first_seen.lock().unwrap_or_else(|e| e.into_inner());
+            settlements.into_iter().partition(|settlement| {
+                let age = first_seen
+                    .get(&settlement.transaction_id)
+                    .map(|seen| seen.elapsed())
+                    .unwrap_or_default();
+                classify_lane(settlement, age, now, thresholds) == Lane::High
+            })
+        };
+        // Stable, so settlements without a known expiry keep the API's order
+        high.sort_by_key(expiry_key);
+        normal.sort_by_key(expiry_key);
+
+        let mut batches = self.create_lane_batches(high, batch_type, Lane::High);
+        batches.extend(self.create_lane_batches(normal, batch_type, Lane::Normal));
+        batches
+    }
+
+    fn create_lane_batches(
+        &self,
+        settlements: Vec<GameSettlementInfo>,
+        batch_type: BatchType,
+        lane: Lane,
+    ) -> Vec<SettlementBatch> {
         if settlements.is_empty() {
             return Vec::new();
         }
 
         let min_size = self.config.processor.coordinator_batch_min_size;
-        let max_size = self.config.processor.coordinator_batch_max_size;
+        let max_size = self.batch_tuner
+            .as_ref()
+            .map_or(self.config.processor.coordinator_batch_max_size, |tuner| tuner.current());
         
         let mut batches = Vec::new();
         let mut current_batch = Vec::new();
@@ -190,10 +640,11 @@ impl Coordinator {
             if current_batch.len() >= max_size {
                 batches.push(SettlementBatch {
                     batch_id: Uuid::new_v4().to_string(),
-                    settlements: current_batch.clone(),
+                    settlements: std::mem::take(&mut current_batch),
                     batch_type,
+                    lane,
+                    dispatched_at: Instant::now(),
                 });
-                current_batch.clear();
             }
         }
 
@@ -205,10 +656,16 @@ impl Coordinator {
                     batch_id: Uuid::new_v4().to_string(),
                     settlements: current_batch,
                     batch_type,
+                    lane,
+                    dispatched_at: Instant::now(),
                 });
             } else {
                 // Merge with last batch if too small
                 if let Some(last_batch) = batches.last_mut() {
+                    metrics::counter!("coordinator_batches_merged_total", "batch_type" => batch_type.as_str())
+                        .increment(1);
+                    metrics::counter!("coordinator_merged_settlements_total", "batch_type" => batch_type.as_str())
+                        .increment(current_batch.len() as u64);
                     last_batch.settlements.extend(current_batch);
                 } else {
                     // No batches yet, create one anyway
@@ -216,14 +673,31 @@ impl Coordinator {
                         batch_id: Uuid::new_v4().to_string(),
                         settlements: current_batch,
                         batch_type,
+                        lane,
+                        dispatched_at: Instant::now(),
                     });
                 }
             }
         }
 
+        // Composition of each batch as built, so the size knobs can be tuned from dashboards
+        metrics::gauge!("coordinator_batch_min_size").set(min_size as f64);
+        metrics::gauge!("coordinator_batch_effective_max_size").set(max_size as f64);
+        for batch in &batches {
+            metrics::counter!(
+                "coordinator_batches_created_total",
+                "batch_type" => batch_type.as_str(),
+                "lane" => lane.as_str()
+            )
+            .increment(1);
+            metrics::histogram!("coordinator_batch_settlements", "batch_type" => batch_type.as_str())
+                .record(batch.settlements.len() as f64);
+        }
+
         debug!(
             batch_count = batches.len(),
             batch_type = ?batch_type,
+            lane = lane.as_str(),
             avg_size = if batches.is_empty() { 0 } else { 
                 batches.iter().map(|b| b.settlements.len()).sum::<usize>() / batches.len()
             },
@@ -233,27 +707,122 @@ impl Coordinator {
         batches
     }
 
-    /// Send batch to next available worker (round-robin)
-    async fn send_to_worker(&self, batch: SettlementBatch) -> Result<()> {
+    /// Send batch to next available worker (round-robin) on the batch's lane
+    async fn send_to_worker(&self, mut batch: SettlementBatch) -> Result<()> {
         let worker_index = self.next_worker_index
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed) % self.work_senders.len();
 
-        let sender = &self.work_senders[worker_index];
+        let channel = &self.work_senders[worker_index];
+        let sender = match batch.lane {
+            Lane::High => &channel.high,
+            Lane::Normal => &channel.normal,
+        };
         let batch_id = batch.batch_id.clone();
         let settlement_count = batch.settlements.len();
+        let lane = batch.lane;
+        batch.dispatched_at = Instant::now();
 
         sender
             .send(batch)
             .await
             .context("Failed to send batch to worker")?;
 
+        metrics::counter!("coordinator_lane_batches_total", "lane" => lane.as_str()).increment(1);
+        metrics::counter!("coordinator_lane_settlements_total", "lane" => lane.as_str())
+            .increment(settlement_count as u64);
+
         debug!(
             worker_index,
             batch_id = %batch_id,
             settlement_count,
+            lane = lane.as_str(),
             "Batch sent to worker"
         );
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settlement(payout: u64, priority: bool) -> GameSettlementInfo {
+        GameSettlementInfo {
+            transaction_id: 1,
+            player_address: "player".to_string(),
+            game_type: "coinflip".to_string(),
+            bet_amount: 1_000_000,
+            token: "SOL".to_string(),
+            outcome: "Win".to_string(),
+            payout,
+            vrf_proof: String::new(),
+            vrf_output: String::new(),
+            block_height: 1,
+            version: 1,
+            solana_tx_id: None,
+            retry_count: 0,
+            next_retry_after: None,
+            allowance_pda: None,
+            casino_id: None,
+            priority,
+            status: None,
+            allowance_expires_at: None,
+        }
+    }
+
+    #[test]
+    fn test_classify_lane() {
+        let thresholds = PriorityThresholds {
+            payout_lamports: 10_000_000_000,
+            age: Duration::from_secs(300),
+            allowance_expiry: Duration::from_secs(600),
+        };
+        let now = 1_700_000_000;
+
+        assert_eq!(classify_lane(&settlement(2_000_000, false), Duration::ZERO, now, thresholds), Lane::Normal);
+        assert_eq!(classify_lane(&settlement(2_000_000, true), Duration::ZERO, now, thresholds), Lane::High);
+        assert_eq!(classify_lane(&settlement(10_000_000_000, false), Duration::ZERO, now, thresholds), Lane::High);
+        assert_eq!(
+            classify_lane(&settlement(2_000_000, false), Duration::from_secs(301), now, thresholds),
+            Lane::High
+        );
+
+        let mut expiring = settlement(2_000_000, false);
+        expiring.allowance_expires_at = Some(now + 601);
+        assert_eq!(classify_lane(&expiring, Duration::ZERO, now, thresholds), Lane::Normal);
+        expiring.allowance_expires_at = Some(now + 600);
+        assert_eq!(classify_lane(&expiring, Duration::ZERO, now, thresholds), Lane::High);
+    }
+
+    #[test]
+    fn test_expiry_key_orders_unknown_last() {
+        let mut settlements: Vec<_> = [None, Some(200), Some(100)]
+            .into_iter()
+            .enumerate()
+            .map(|(id, expires_at)| GameSettlementInfo {
+                transaction_id: id as u64,
+                allowance_expires_at: expires_at,
+                ..settlement(0, false)
+            })
+            .collect();
+        settlements.sort_by_key(expiry_key);
+        let order: Vec<_> = settlements.iter().map(|s| s.transaction_id).collect();
+        assert_eq!(order, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_cycle_budget_caps_pages_and_settlements() {
+        let budget = CycleBudget { max_pages: 10, max_settlements: 120 };
+        assert!(budget.allows_more(1, 50));
+        assert_eq!(budget.page_limit(50, 100), 20);
+        assert!(!budget.allows_more(3, 120));
+        assert!(!budget.allows_more(10, 50));
+
+        // Without a settlement cap only the page cap applies
+        let pages_only = CycleBudget { max_pages: 3, max_settlements: 0 };
+        assert_eq!(pages_only.page_limit(50, 10_000), 50);
+        assert!(pages_only.allows_more(2, 10_000));
+        assert!(!pages_only.allows_more(3, 0));
+    }
+}
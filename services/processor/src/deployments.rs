@@ -0,0 +1,208 @@
+//! Vault program deployments and casino routing
+//!
+//! A single processor can settle against several deployments of the vault
+//! program (e.g. playground and production). `VAULT_PROGRAM_ID` is always the
+//! default deployment; `VAULT_DEPLOYMENTS` adds more as a JSON array:
+//!
+//! ```text
+//! [{"name":"playground","program_id":"...","casino_ids":["playground"],
+//!   "instructions":{"payout":"payout","settle_bet":null}}]
+//! ```
+//!
+//! A `null` `settle_bet` marks a build without that instruction; its winning
+//! bets settle with the older `spend_from_allowance` + `payout` pair. A `null`
+//! `refund_bet` marks a build that cannot refund voided bets, a `null`
+//! `initialize_vault_for` one that cannot create vaults for users, a `null`
+//! `fund_casino_vault` one whose casino vault cannot be topped up from the
+//! treasury, and a `null` `migrate_allowance` one whose allowances predate
+//! velocity limits.
+//!
+//! Settlements are routed by `casino_id`; unknown or missing casino ids use the
+//! default deployment.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use shared::vault::anchor_discriminator;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+
+pub const DEFAULT_DEPLOYMENT: &str = "default";
+
+/// Instruction names a deployment was built with; older builds may differ
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct InstructionNames {
+    payout: String,
+    spend_from_allowance: String,
+    settle_bet: Option<String>,
+    refund_bet: Option<String>,
+    initialize_vault_for: Option<String>,
+    fund_casino_vault: Option<String>,
+    migrate_allowance: Option<String>,
+}
+
+impl Default for InstructionNames {
+    fn default() -> Self {
+        Self {
+            payout: "payout".to_string(),
+            spend_from_allowance: "spend_from_allowance".to_string(),
+            settle_bet: Some("settle_bet".to_string()),
+            refund_bet: Some("refund_bet".to_string()),
+            initialize_vault_for: Some("initialize_vault_for".to_string()),
+            fund_casino_vault: Some("fund_casino_vault".to_string()),
+            migrate_allowance: Some("migrate_allowance".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DeploymentSpec {
+    name: String,
+    program_id: String,
+    #[serde(default)]
+    casino_ids: Vec<String>,
+    #[serde(default)]
+    instructions: InstructionNames,
+}
+
+/// Anchor discriminators for the settlement instructions of one deployment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Discriminators {
+    pub payout: [u8; 8],
+    pub spend_from_allowance: [u8; 8],
+    /// `None` for builds that predate atomic settlement
+    pub settle_bet: Option<[u8; 8]>,
+    /// `None` for builds that cannot refund voided bets
+    pub refund_bet: Option<[u8; 8]>,
+    /// `None` for builds where only users can create their vaults
+    pub initialize_vault_for: Option<[u8; 8]>,
+    /// `None` for builds without treasury top-ups
+    pub fund_casino_vault: Option<[u8; 8]>,
+    /// `None` for builds whose allowances predate velocity limits, which read
+    /// older allowances as they are
+    pub migrate_allowance: Option<[u8; 8]>,
+}
+
+impl Default for Discriminators {
+    fn default() -> Self {
+        Self::from_names(&InstructionNames::default())
+    }
+}
+
+impl Discriminators {
+    fn from_names(names: &InstructionNames) -> Self {
+        Self {
+            payout: anchor_discriminator(&names.payout),
+            spend_from_allowance: anchor_discriminator(&names.spend_from_allowance),
+            settle_bet: names.settle_bet.as_deref().map(anchor_discriminator),
+            refund_bet: names.refund_bet.as_deref().map(anchor_discriminator),
+            initialize_vault_for: names.initialize_vault_for.as_deref().map(anchor_discriminator),
+            fund_casino_vault: names.fund_casino_vault.as_deref().map(anchor_discriminator),
+            migrate_allowance: names.migrate_allowance.as_deref().map(anchor_discriminator),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultDeployment {
+    /// Used as the `deployment` metrics label
+    pub name: String,
+    pub program_id: Pubkey,
+    pub casino_ids: Vec<String>,
+    pub discriminators: Discriminators,
+}
+
+/// Default deployment from `VAULT_PROGRAM_ID` followed by any `VAULT_DEPLOYMENTS`;
+/// `default_settle_bet` is false while the default deployment predates `settle_bet`
+/// (and so `refund_bet`, `initialize_vault_for`, `fund_casino_vault` and
+/// `migrate_allowance`, which came later)
+pub fn parse_deployments(
+    default_program_id: &str,
+    default_settle_bet: bool,
+    extra: Option<&str>,
+) -> Result<Vec<VaultDeployment>> {
+    let mut default_discriminators = Discriminators::default();
+    if !default_settle_bet {
+        default_discriminators.settle_bet = None;
+        default_discriminators.refund_bet = None;
+        default_discriminators.initialize_vault_for = None;
+        default_discriminators.fund_casino_vault = None;
+        default_discriminators.migrate_allowance = None;
+    }
+    let mut deployments = vec![VaultDeployment {
+        name: DEFAULT_DEPLOYMENT.to_string(),
+        program_id: default_program_id.parse().context("Invalid VAULT_PROGRAM_ID")?,
+        casino_ids: Vec::new(),
+        discriminators: default_discriminators,
+    }];
+
+    let specs: Vec<DeploymentSpec> = match extra.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(json) => serde_json::from_str(json).context("Invalid VAULT_DEPLOYMENTS")?,
+        None => Vec::new(),
+    };
+
+    let mut names = HashSet::from([DEFAULT_DEPLOYMENT.to_string()]);
+    let mut casino_ids = HashSet::new();
+    for spec in specs {
+        if !names.insert(spec.name.clone()) {
+            bail!("Duplicate vault deployment name '{}'", spec.name);
+        }
+        if let Some(dup) = spec.casino_ids.iter().find(|id| !casino_ids.insert(id.to_string())) {
+            bail!("Casino '{}' is mapped to more than one vault deployment", dup);
+        }
+        deployments.push(VaultDeployment {
+            program_id: spec
+                .program_id
+                .parse()
+                .with_context(|| format!("Invalid program_id for vault deployment '{}'", spec.name))?,
+            discriminators: Discriminators::from_names(&spec.instructions),
+            casino_ids: spec.casino_ids,
+            name: spec.name,
+        });
+    }
+
+    Ok(deployments)
+}
+
+/// Deployment a casino settles against; the first entry is the default
+pub fn resolve<'a>(deployments: &'a [VaultDeployment], casino_id: Option<&str>) -> &'a VaultDeployment {
+    casino_id
+        .and_then(|id| deployments.iter().find(|d| d.casino_ids.iter().any(|c| c == id)))
+        .unwrap_or(&deployments[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_resolve() {
+        let default_id = Pubkey::new_unique();
+        let playground_id = Pubkey::new_unique();
+        let json = format!(
+            r#"[{{"name":"playground","program_id":"{}","casino_ids":["pg"],"instructions":{{"payout":"payout_v1","settle_bet":null}}}}]"#,
+            playground_id
+        );
+        let deployments = parse_deployments(&default_id.to_string(), true, Some(&json)).unwrap();
+
+        assert_eq!(resolve(&deployments, None).program_id, default_id);
+        assert_eq!(resolve(&deployments, Some("unknown")).name, DEFAULT_DEPLOYMENT);
+        let playground = resolve(&deployments, Some("pg"));
+        assert_eq!(playground.program_id, playground_id);
+        assert_eq!(playground.discriminators.payout, anchor_discriminator("payout_v1"));
+        assert_eq!(
+            playground.discriminators.spend_from_allowance,
+            Discriminators::default().spend_from_allowance
+        );
+        assert_eq!(playground.discriminators.settle_bet, None);
+        assert_eq!(playground.discriminators.refund_bet, Some(anchor_discriminator("refund_bet")));
+        assert_eq!(playground.discriminators.migrate_allowance, Some(anchor_discriminator("migrate_allowance")));
+        assert_eq!(resolve(&deployments, None).discriminators.settle_bet, Some(anchor_discriminator("settle_bet")));
+
+        let dup = format!(
+            r#"[{{"name":"a","program_id":"{0}","casino_ids":["x"]}},{{"name":"b","program_id":"{0}","casino_ids":["x"]}}]"#,
+            playground_id
+        );
+        assert!(parse_deployments(&default_id.to_string(), true, Some(&dup)).is_err());
+    }
+}
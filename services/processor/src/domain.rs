@@ -2,51 +2,11 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
-pub enum BetStatus {
-    Pending,
-    Batched,
-    SubmittedToSolana,
-    ConfirmedOnSolana,
-    Completed,
-    FailedRetryable,
-    FailedManualReview,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Bet {
-    pub bet_id: Uuid,
-    pub created_at: DateTime<Utc>,
-    pub user_wallet: String,
-    pub vault_address: String,
-    pub allowance_pda: Option<String>,
-    pub casino_id: Option<String>,
-    pub game_type: String,
-    pub stake_amount: i64,
-    pub stake_token: String,
-    pub choice: String,
-    pub status: BetStatus,
-    pub external_batch_id: Option<Uuid>,
-    pub solana_tx_id: Option<String>,
-    pub retry_count: i32,
-    pub processor_id: Option<String>,
-    pub last_error_code: Option<String>,
-    pub last_error_message: Option<String>,
-    pub payout_amount: Option<i64>,
-    pub won: Option<bool>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum BatchStatus {
-    Created,
-    Submitted,
-    Confirmed,
-    Failed,
-}
+// Wire types of the backend API, shared with the backend and `atomiq-client`
+pub use shared::api::{BatchStatus, Bet, BetStatus};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct Batch {
     pub batch_id: Uuid,
     pub created_at: DateTime<Utc>,
@@ -61,6 +21,7 @@ pub struct Batch {
     pub last_error_message: Option<String>,
 }
 
+#[allow(dead_code)]
 impl Batch {
     pub fn new(processor_id: String, bet_count: i32) -> Self {
         Self {
@@ -78,28 +39,3 @@ impl Batch {
         }
     }
 }
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BetResult {
-    pub bet_id: Uuid,
-    pub status: BetStatus,
-    pub solana_tx_id: Option<String>,
-    pub error_message: Option<String>,
-    pub won: Option<bool>,
-    pub payout_amount: Option<i64>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UpdateBatchRequest {
-    pub status: BatchStatus,
-    pub solana_tx_id: Option<String>,
-    pub bet_results: Vec<BetResult>,
-    pub error_message: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PendingBetsResponse {
-    pub batch_id: Uuid,
-    pub processor_id: String,
-    pub bets: Vec<Bet>,
-}
@@ -0,0 +1,131 @@
+//! Cold-start backlog drain mode
+//!
+//! After downtime the first coordinator cycles can fetch thousands of pending
+//! settlements at once. When a cycle's fetch reaches the drain threshold the
+//! coordinator switches to drain mode until the backlog clears: it fetches
+//! larger pages, workers confirm more settlements of a batch concurrently and
+//! settlement transactions carry a priority fee. Drain mode ends once a cycle
+//! fetches less than half the threshold with no pages left over, so a backlog
+//! hovering around the threshold doesn't flap in and out of it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::DrainConfig;
+
+/// What a cycle's fetch did to drain mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrainTransition {
+    Idle,
+    Entered,
+    /// Still draining; `peak` is the largest backlog fetched since entering
+    Draining { peak: usize, elapsed: Duration },
+    Exited { peak: usize, elapsed: Duration },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DrainRun {
+    started: Instant,
+    peak: usize,
+}
+
+#[derive(Debug)]
+pub struct DrainMode {
+    config: DrainConfig,
+    active: AtomicBool,
+    run: Mutex<Option<DrainRun>>,
+}
+
+impl DrainMode {
+    pub fn new(config: DrainConfig) -> Self {
+        Self { config, active: AtomicBool::new(false), run: Mutex::new(None) }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Record a cycle's fetch: `backlog` settlements, with `more_pages` left behind the page cap
+    pub fn observe(&self, backlog: usize, more_pages: bool) -> DrainTransition {
+        if self.config.threshold == 0 {
+            return DrainTransition::Idle;
+        }
+
+        let mut run = self.run.lock().unwrap_or_else(|e| e.into_inner());
+        let transition = match run.as_mut() {
+            None if backlog >= self.config.threshold => {
+                *run = Some(DrainRun { started: Instant::now(), peak: backlog });
+                DrainTransition::Entered
+            }
+            None => DrainTransition::Idle,
+            Some(current) if more_pages || backlog >= self.config.threshold / 2 => {
+                current.peak = current.peak.max(backlog);
+                DrainTransition::Draining { peak: current.peak, elapsed: current.started.elapsed() }
+            }
+            Some(current) => {
+                let transition = DrainTransition::Exited { peak: current.peak, elapsed: current.started.elapsed() };
+                *run = None;
+                transition
+            }
+        };
+        self.active.store(run.is_some(), Ordering::Relaxed);
+        transition
+    }
+
+    /// Settlements to request per page
+    pub fn fetch_limit(&self, base: usize) -> usize {
+        if self.is_active() {
+            base.saturating_mul(self.config.fetch_multiplier.max(1))
+        } else {
+            base
+        }
+    }
+
+    /// Settlements of one batch a worker submits concurrently
+    pub fn batch_parallelism(&self, base: usize) -> usize {
+        if self.is_active() {
+            base.max(self.config.batch_parallelism)
+        } else {
+            base
+        }
+    }
+
+    /// Compute unit price while draining, 0 otherwise
+    pub fn priority_fee(&self) -> u64 {
+        if self.is_active() {
+            self.config.priority_fee_micro_lamports
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_enters_and_exits_with_hysteresis() {
+        let drain = DrainMode::new(DrainConfig {
+            threshold: 1000,
+            fetch_multiplier: 4,
+            batch_parallelism: 16,
+            priority_fee_micro_lamports: 5_000,
+        });
+        assert_eq!(drain.observe(200, false), DrainTransition::Idle);
+        assert_eq!(drain.fetch_limit(50), 50);
+        assert_eq!(drain.priority_fee(), 0);
+
+        assert_eq!(drain.observe(1500, true), DrainTransition::Entered);
+        assert_eq!((drain.fetch_limit(50), drain.batch_parallelism(4)), (200, 16));
+        assert_eq!(drain.priority_fee(), 5_000);
+
+        // Below the threshold but pages remain, then above half of it
+        assert!(matches!(drain.observe(100, true), DrainTransition::Draining { peak: 1500, .. }));
+        assert!(matches!(drain.observe(600, false), DrainTransition::Draining { .. }));
+        assert!(matches!(drain.observe(400, false), DrainTransition::Exited { peak: 1500, .. }));
+        assert!(!drain.is_active());
+        assert_eq!(drain.batch_parallelism(4), 4);
+    }
+}
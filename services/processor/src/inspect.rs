@@ -0,0 +1,238 @@
+//! `processor inspect`: decode a user's or a bet's on-chain vault program state
+//!
+//! Derives the PDAs the processor settles against, fetches them at the read
+//! commitment and pretty-prints them with the shared account decoders. Read only;
+//! nothing is signed or sent.
+
+use anyhow::{bail, Context, Result};
+use chrono::{TimeZone, Utc};
+use clap::Args;
+use shared::types::BetId;
+use shared::vault::{
+    allowance_nonce_registry_pda, allowance_pda, casino_pda, user_vault_pda, AllowanceAccount, NonceRegistryAccount,
+    ProcessedBetAccount, VaultAccount,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey, system_program};
+use std::str::FromStr;
+
+use crate::{
+    config::Config,
+    deployments::VaultDeployment,
+    settlement_worker::{legacy_settlement_bet_id, settlement_bet_id},
+    solana_client::{Commitments, RateLimits, SolanaClientPool},
+    solana_pda::derive_processed_bet_pda,
+};
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+#[derive(Debug, Args)]
+#[command(group(clap::ArgGroup::new("target").required(true).multiple(true).args(["user", "bet_id"])))]
+pub struct InspectArgs {
+    /// Wallet whose vault, nonce registry and allowances to show
+    #[arg(long)]
+    pub user: Option<String>,
+    /// Bet whose processed-bet PDA to show: a UUID, or a settlement tx id
+    #[arg(long)]
+    pub bet_id: Option<String>,
+    /// Newest allowances shown with --user
+    #[arg(long, default_value_t = 5)]
+    pub allowances: u64,
+    /// Only this vault deployment; all configured deployments by default
+    #[arg(long)]
+    pub deployment: Option<String>,
+}
+
+/// Processed-bet PDAs a bet id may have settled under, newest seed scheme first
+fn bet_seeds(bet_id: &str, program_id: &Pubkey) -> Result<Vec<Pubkey>> {
+    if let Ok(tx_id) = bet_id.parse::<u64>() {
+        let (legacy, _) =
+            Pubkey::find_program_address(&[b"processed-bet", legacy_settlement_bet_id(tx_id).as_bytes()], program_id);
+        return Ok(vec![derive_processed_bet_pda(&settlement_bet_id(tx_id), program_id), legacy]);
+    }
+    let bet_id = BetId::try_from(bet_id.to_string()).context("--bet-id is neither a UUID nor a settlement tx id")?;
+    Ok(vec![derive_processed_bet_pda(&bet_id, program_id)])
+}
+
+fn sol(lamports: u64) -> String {
+    format!("{:.9} SOL", lamports as f64 / LAMPORTS_PER_SOL)
+}
+
+/// Allowance amounts are lamports for SOL and base units of the mint otherwise
+fn amount(value: u64, mint: &Pubkey) -> String {
+    if *mint == system_program::ID || *mint == Pubkey::default() {
+        sol(value)
+    } else {
+        format!("{} (mint {})", value, mint)
+    }
+}
+
+fn timestamp(unix: i64) -> String {
+    match Utc.timestamp_opt(unix, 0).single() {
+        Some(at) if unix > 0 => at.to_rfc3339(),
+        _ => "never".to_string(),
+    }
+}
+
+/// `expires_at` relative to `now`, e.g. "in 1h 5m" or "expired 3m ago"
+fn expiry(expires_at: i64, now: i64) -> String {
+    let span = |seconds: i64| match seconds {
+        s if s >= 3600 => format!("{}h {}m", s / 3600, s % 3600 / 60),
+        s if s >= 60 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    };
+    if expires_at >= now {
+        format!("in {}", span(expires_at - now))
+    } else {
+        format!("expired {} ago", span(now - expires_at))
+    }
+}
+
+fn fetch(client: &RpcClient, commitments: Commitments, address: &Pubkey) -> Result<Option<Account>> {
+    Ok(client
+        .get_account_with_commitment(address, commitments.read)
+        .with_context(|| format!("Failed to fetch account {}", address))?
+        .value)
+}
+
+fn print_user(
+    client: &RpcClient,
+    commitments: Commitments,
+    deployment: &VaultDeployment,
+    user: &Pubkey,
+    allowances: u64,
+) -> Result<()> {
+    let program_id = &deployment.program_id;
+    let casino = casino_pda(program_id);
+
+    let vault = user_vault_pda(user, &casino, program_id);
+    match fetch(client, commitments, &vault)? {
+        Some(account) => {
+            let state = VaultAccount::decode(&account.data)?;
+            println!("  vault {}", vault);
+            println!("    balance:       {} (account holds {} lamports)", sol(state.sol_balance), account.lamports);
+            println!("    created:       {}", timestamp(state.created_at));
+            println!("    last activity: {}", timestamp(state.last_activity));
+            if let Some(payout_address) = state.payout_address {
+                println!("    payout to:     {}", payout_address);
+            }
+        }
+        None => println!("  vault {}: not initialized", vault),
+    }
+
+    let registry = allowance_nonce_registry_pda(user, &casino, program_id);
+    let Some(account) = fetch(client, commitments, &registry)? else {
+        println!("  nonce registry {}: not initialized (no allowances yet)", registry);
+        return Ok(());
+    };
+    let state = NonceRegistryAccount::decode(&account.data)?;
+    println!("  nonce registry {}: next_nonce={}", registry, state.next_nonce);
+
+    let now = Utc::now().timestamp();
+    for nonce in (0..state.next_nonce).rev().take(allowances as usize) {
+        let address = allowance_pda(user, &casino, nonce, program_id);
+        let Some(account) = fetch(client, commitments, &address)? else {
+            println!("  allowance #{} {}: closed", nonce, address);
+            continue;
+        };
+        let allowance = AllowanceAccount::decode(&account.data)?;
+        let mint = &allowance.token_mint;
+        let state = if allowance.revoked {
+            "revoked"
+        } else if allowance.is_active(now) {
+            "active"
+        } else {
+            "expired"
+        };
+        println!("  allowance #{} {} ({})", nonce, address, state);
+        println!("    approved:      {}", amount(allowance.amount, mint));
+        println!("    spent:         {} over {} spends", amount(allowance.spent, mint), allowance.spend_count);
+        println!("    remaining:     {}", amount(allowance.remaining(), mint));
+        println!("    expires:       {} ({})", timestamp(allowance.expires_at), expiry(allowance.expires_at, now));
+        println!("    last spent:    {}", timestamp(allowance.last_spent_at));
+        if allowance.max_single_spend > 0 {
+            println!("    max per spend: {}", amount(allowance.max_single_spend, mint));
+        }
+        if let Some(hourly) = allowance.hourly_remaining(now) {
+            println!(
+                "    hourly:        {} left of {}",
+                amount(hourly, mint),
+                amount(allowance.max_spend_per_hour, mint)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_bet(client: &RpcClient, commitments: Commitments, deployment: &VaultDeployment, bet_id: &str) -> Result<()> {
+    for pda in bet_seeds(bet_id, &deployment.program_id)? {
+        let Some(account) = fetch(client, commitments, &pda)? else {
+            continue;
+        };
+        let state = ProcessedBetAccount::decode(&account.data)?;
+        println!("  processed bet {}", pda);
+        println!("    bet id:        {}", state.bet_id);
+        println!("    user:          {}", state.user);
+        println!("    stake:         {}", sol(state.amount));
+        println!("    outcome:       {:?}", state.outcome);
+        println!("    payout:        {}", sol(state.payout));
+        println!("    processed:     {}", timestamp(state.processed_at));
+        println!("    signature:     {}", if state.signature.is_empty() { "-" } else { &state.signature });
+        return Ok(());
+    }
+    println!("  bet {}: no processed-bet PDA (not settled on this deployment)", bet_id);
+    Ok(())
+}
+
+pub async fn run(config: Config, args: InspectArgs) -> Result<()> {
+    let user = args
+        .user
+        .as_deref()
+        .map(|user| Pubkey::from_str(user).with_context(|| format!("Invalid --user pubkey {}", user)))
+        .transpose()?;
+    let deployments: Vec<&VaultDeployment> = config
+        .solana
+        .deployments
+        .iter()
+        .filter(|deployment| args.deployment.as_deref().is_none_or(|name| deployment.name == name))
+        .collect();
+    if deployments.is_empty() {
+        bail!("No vault deployment named {}", args.deployment.unwrap_or_default());
+    }
+
+    let commitments = Commitments::from_config(&config.solana);
+    let pool = SolanaClientPool::new(config.solana.rpc_urls.clone(), RateLimits::from_config(&config.solana), commitments)
+        .await?;
+    let client = pool.get_client().await;
+
+    for deployment in deployments {
+        println!("Deployment {} (program {})", deployment.name, deployment.program_id);
+        if let Some(user) = &user {
+            print_user(&client, commitments, deployment, user, args.allowances)?;
+        }
+        if let Some(bet_id) = &args.bet_id {
+            print_bet(&client, commitments, deployment, bet_id)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bet_ids_and_expiries() {
+        let program_id = Pubkey::new_unique();
+        let seeds = bet_seeds("42", &program_id).unwrap();
+        assert_eq!(seeds[0], derive_processed_bet_pda(&settlement_bet_id(42), &program_id));
+        assert_eq!(seeds.len(), 2);
+        let uuid = "6f9619ff-8b86-d011-b42d-00c04fc964ff";
+        assert_eq!(bet_seeds(uuid, &program_id).unwrap().len(), 1);
+        assert!(bet_seeds("not-a-bet", &program_id).is_err());
+
+        assert_eq!(expiry(1_000 + 3_900, 1_000), "in 1h 5m");
+        assert_eq!(expiry(1_000, 1_180), "expired 3m ago");
+        assert_eq!(sol(1_500_000_000), "1.500000000 SOL");
+    }
+}
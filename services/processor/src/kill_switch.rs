@@ -0,0 +1,68 @@
+//! Incident kill switch watcher
+//!
+//! Operators engage the kill switch through the backend admin API, which sets
+//! `shared::constants::KILL_SWITCH_KEY` in Redis. Each processor polls the key and
+//! stops dispatching new settlement batches while it is present. Batches already
+//! handed to workers are allowed to finish.
+
+use anyhow::{Context, Result};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use shared::constants::KILL_SWITCH_KEY;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+pub struct KillSwitch {
+    redis: ConnectionManager,
+    poll_interval: Duration,
+    engaged: AtomicBool,
+}
+
+impl KillSwitch {
+    pub async fn connect(redis_url: &str, poll_interval: Duration) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("Invalid Redis URL")?;
+        let redis = client
+            .get_connection_manager()
+            .await
+            .context("Failed to connect to Redis for kill switch")?;
+
+        Ok(Self {
+            redis,
+            poll_interval,
+            engaged: AtomicBool::new(false),
+        })
+    }
+
+    pub fn is_engaged(&self) -> bool {
+        self.engaged.load(Ordering::SeqCst)
+    }
+
+    /// Poll loop; on Redis errors the last known state is kept
+    pub async fn run(self: Arc<Self>) {
+        info!(poll_interval_ms = self.poll_interval.as_millis() as u64, "Kill switch watcher started");
+
+        loop {
+            let mut conn = self.redis.clone();
+            match conn.exists::<_, bool>(KILL_SWITCH_KEY).await {
+                Ok(engaged) => self.set_engaged(engaged),
+                Err(e) => warn!(error = %e, "Kill switch check failed; keeping last known state"),
+            }
+            sleep(self.poll_interval).await;
+        }
+    }
+
+    fn set_engaged(&self, engaged: bool) {
+        let was_engaged = self.engaged.swap(engaged, Ordering::SeqCst);
+        if was_engaged != engaged {
+            if engaged {
+                warn!("Kill switch engaged - settlement dispatch halted");
+            } else {
+                info!("Kill switch released - settlement dispatch resumed");
+            }
+        }
+        metrics::gauge!("processor_kill_switch_engaged").set(if engaged { 1.0 } else { 0.0 });
+    }
+}
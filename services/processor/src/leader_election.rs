@@ -0,0 +1,253 @@
+//! Coordinator leader election
+//!
+//! Only one coordinator across all processor instances may dispatch settlements.
+//! Leadership is a Redis lease (`SET NX PX`) that the holder renews periodically.
+//! If the leader dies or loses Redis, the lease expires and a standby takes over.
+//! Standbys note who holds the lease and when it is due to expire, so a takeover
+//! is logged and metered as a failover from that instance.
+
+use anyhow::{Context, Result};
+use redis::aio::ConnectionManager;
+use redis::Script;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// Acquire the lease if free, or extend it if we already hold it.
+/// KEYS[1] = lease key, ARGV[1] = instance id, ARGV[2] = ttl in ms
+/// Returns {acquired, holder, remaining ttl in ms}
+const ACQUIRE_OR_RENEW_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if current == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return {1, ARGV[1], tonumber(ARGV[2])}
+end
+if not current then
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+    return {1, ARGV[1], tonumber(ARGV[2])}
+end
+return {0, current, redis.call('PTTL', KEYS[1])}
+"#;
+
+/// Delete the lease only if we still own it.
+/// KEYS[1] = lease key, ARGV[1] = instance id
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Change in leadership observed after a lease attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeadershipTransition {
+    Acquired,
+    Lost,
+    Unchanged,
+}
+
+impl LeadershipTransition {
+    pub fn from_states(was_leader: bool, is_leader: bool) -> Self {
+        match (was_leader, is_leader) {
+            (false, true) => LeadershipTransition::Acquired,
+            (true, false) => LeadershipTransition::Lost,
+            _ => LeadershipTransition::Unchanged,
+        }
+    }
+}
+
+/// Another instance's lease, as last seen by a standby
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ObservedLease {
+    holder: String,
+    expires_at: Instant,
+}
+
+impl ObservedLease {
+    /// How long after this lease was due to expire it was taken over
+    fn takeover_delay(&self, taken_over_at: Instant) -> Duration {
+        taken_over_at.saturating_duration_since(self.expires_at)
+    }
+}
+
+pub struct LeaderElection {
+    redis: ConnectionManager,
+    lease_key: String,
+    instance_id: String,
+    lease_ttl: Duration,
+    renew_interval: Duration,
+    /// How often a non-leader checks the lease
+    check_interval: Duration,
+    is_leader: AtomicBool,
+    leader: watch::Sender<bool>,
+    active_lease: Mutex<Option<ObservedLease>>,
+}
+
+impl LeaderElection {
+    pub async fn connect(
+        redis_url: &str,
+        lease_key: String,
+        instance_id: String,
+        lease_ttl: Duration,
+        renew_interval: Duration,
+    ) -> Result<Self> {
+        let client = redis::Client::open(redis_url).context("Invalid Redis URL")?;
+        let redis = client
+            .get_connection_manager()
+            .await
+            .context("Failed to connect to Redis for leader election")?;
+
+        metrics::gauge!("coordinator_leader").set(0.0);
+
+        Ok(Self {
+            redis,
+            lease_key,
+            instance_id,
+            lease_ttl,
+            renew_interval,
+            check_interval: renew_interval,
+            is_leader: AtomicBool::new(false),
+            leader: watch::Sender::new(false),
+            active_lease: Mutex::new(None),
+        })
+    }
+
+    /// Check the lease every `interval` while another instance holds it, so a
+    /// warm standby takes over soon after the lease lapses
+    pub fn with_check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = interval;
+        self
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.is_leader.load(Ordering::SeqCst)
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Resolves once this instance holds the lease
+    pub async fn wait_for_leadership(&self) {
+        let mut leader = self.leader.subscribe();
+        // The sender lives as long as `self`, so this only returns once leader
+        let _ = leader.wait_for(|is_leader| *is_leader).await;
+    }
+
+    /// Renew loop - keeps trying to acquire or extend the lease
+    pub async fn run(self: Arc<Self>) {
+        info!(
+            instance_id = %self.instance_id,
+            lease_key = %self.lease_key,
+            lease_ttl_ms = self.lease_ttl.as_millis() as u64,
+            "Leader election started"
+        );
+
+        loop {
+            let acquired = match self.try_acquire().await {
+                Ok(acquired) => acquired,
+                Err(e) => {
+                    // We can't prove we still hold the lease, so step down
+                    warn!(error = %e, instance_id = %self.instance_id, "Leader lease check failed");
+                    false
+                }
+            };
+
+            self.set_leader(acquired);
+            sleep(if acquired { self.renew_interval } else { self.check_interval }).await;
+        }
+    }
+
+    /// Give up the lease so a standby can take over without waiting for expiry
+    pub async fn release(&self) {
+        let mut conn = self.redis.clone();
+        let result: redis::RedisResult<i32> = Script::new(RELEASE_SCRIPT)
+            .key(&self.lease_key)
+            .arg(&self.instance_id)
+            .invoke_async(&mut conn)
+            .await;
+
+        if let Err(e) = result {
+            warn!(error = %e, "Failed to release leader lease");
+        }
+
+        self.set_leader(false);
+    }
+
+    async fn try_acquire(&self) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let (acquired, holder, ttl_ms): (i32, String, i64) = Script::new(ACQUIRE_OR_RENEW_SCRIPT)
+            .key(&self.lease_key)
+            .arg(&self.instance_id)
+            .arg(self.lease_ttl.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await
+            .context("Leader lease script failed")?;
+
+        if acquired != 1 {
+            let expires_at = Instant::now() + Duration::from_millis(ttl_ms.max(0) as u64);
+            let mut active_lease = self.active_lease.lock().unwrap_or_else(|e| e.into_inner());
+            if active_lease.as_ref().is_none_or(|lease| lease.holder != holder) {
+                info!(instance_id = %self.instance_id, active_instance = %holder, "Standing by for active coordinator");
+            }
+            *active_lease = Some(ObservedLease { holder, expires_at });
+        }
+        Ok(acquired == 1)
+    }
+
+    fn set_leader(&self, is_leader: bool) {
+        let was_leader = self.is_leader.swap(is_leader, Ordering::SeqCst);
+
+        match LeadershipTransition::from_states(was_leader, is_leader) {
+            LeadershipTransition::Acquired => {
+                info!(instance_id = %self.instance_id, "Acquired coordinator leadership");
+                metrics::counter!("coordinator_leadership_changes_total", "transition" => "acquired")
+                    .increment(1);
+                let previous = self.active_lease.lock().unwrap_or_else(|e| e.into_inner()).take();
+                if let Some(previous) = previous {
+                    let delay = previous.takeover_delay(Instant::now());
+                    warn!(
+                        instance_id = %self.instance_id,
+                        previous_instance = %previous.holder,
+                        takeover_delay_ms = delay.as_millis() as u64,
+                        "Failover: took over the coordinator lease"
+                    );
+                    metrics::counter!("coordinator_failovers_total").increment(1);
+                    metrics::histogram!("coordinator_failover_delay_seconds").record(delay.as_secs_f64());
+                }
+            }
+            LeadershipTransition::Lost => {
+                warn!(instance_id = %self.instance_id, "Lost coordinator leadership");
+                metrics::counter!("coordinator_leadership_changes_total", "transition" => "lost")
+                    .increment(1);
+            }
+            LeadershipTransition::Unchanged => {}
+        }
+
+        metrics::gauge!("coordinator_leader").set(if is_leader { 1.0 } else { 0.0 });
+        self.leader.send_replace(is_leader);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leadership_transitions() {
+        assert_eq!(LeadershipTransition::from_states(false, true), LeadershipTransition::Acquired);
+        assert_eq!(LeadershipTransition::from_states(true, false), LeadershipTransition::Lost);
+        assert_eq!(LeadershipTransition::from_states(true, true), LeadershipTransition::Unchanged);
+        assert_eq!(LeadershipTransition::from_states(false, false), LeadershipTransition::Unchanged);
+
+        let now = Instant::now();
+        let lease = ObservedLease { holder: "processor-1".to_string(), expires_at: now };
+        assert_eq!(lease.takeover_delay(now + Duration::from_millis(400)), Duration::from_millis(400));
+        // Released early, so taken over before it would have expired
+        let lease = ObservedLease { expires_at: now + Duration::from_secs(5), ..lease };
+        assert_eq!(lease.takeover_delay(now), Duration::ZERO);
+    }
+}
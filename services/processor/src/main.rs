@@ -1,32 +1,107 @@
 use anyhow::Result;
+use clap::{Parser, Subcommand};
 use std::sync::Arc;
-use tracing::{info, error, warn};
+use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use solana_sdk::signature::{Signer, Keypair};
 
+mod account_prewarm;
+mod allowance_expiry;
 mod config;
+mod deployments;
+mod drain;
 mod circuit_breaker;
 mod domain;
 mod retry_strategy;
 mod solana_account_parsing;
 mod solana_client;
+mod solana_error_mapper;
 mod solana_instructions;
 mod solana_pda;
-mod solana_simulation;
 mod solana_tx;
 mod worker_pool;
 mod blockchain_client;
+mod settlement_webhook;
+mod retry_budget;
 mod settlement_worker;
 mod coordinator;
-
+mod leader_election;
+mod payout_hold;
+mod payout_liability;
+mod casino_pause;
+mod claim_signals;
+mod kill_switch;
+mod progress;
+mod program_version;
+mod admin_api;
+mod batch_journal;
+mod batch_tuner;
+mod compute_budget;
+mod inspect;
+mod refund_worker;
+mod replay;
+mod resubmit;
+mod rpc_rate_limit;
+mod shadow_broadcast;
+mod simulation_failures;
+mod standby;
+mod telemetry;
+mod treasury_topup;
+mod tx_packer;
+mod vault_init;
+mod write_locks;
+
+use account_prewarm::AccountPrewarm;
+use allowance_expiry::AllowanceExpiryCache;
+use batch_tuner::BatchSizeTuner;
 use config::Config;
+use payout_hold::PayoutHolds;
+use payout_liability::PayoutLiability;
+use casino_pause::CasinoPause;
 use worker_pool::WorkerPool;
 use blockchain_client::BlockchainClient;
 use settlement_worker::SettlementWorker;
 use coordinator::Coordinator;
+use drain::DrainMode;
+use leader_election::LeaderElection;
+use kill_switch::KillSwitch;
+use progress::ProgressRegistry;
+use refund_worker::{backend_client, RefundWorker};
+use claim_signals::ClaimSignalSource;
+use treasury_topup::TreasuryTopUp;
+use batch_journal::BatchJournal;
+use settlement_webhook::SettlementWebhook;
+use retry_budget::RetryBudget;
+use standby::WarmStandby;
+use write_locks::WriteLockScheduler;
+use shared::clock::SystemClock;
+use shared::profile::{Profile, ProfileDefaults};
+
+/// Settlement processor
+#[derive(Debug, Parser)]
+#[command(name = "processor", version)]
+struct Cli {
+    /// Defaults for local, devnet or mainnet; set variables still win (default: CONFIG_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<Profile>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the settlement processor (default)
+    Run,
+    /// Refetch failed settlements and re-run their reporting/settlement steps
+    Replay(replay::ReplayArgs),
+    /// Decode a user's vault and allowances or a bet's processed-bet PDA from chain
+    Inspect(inspect::InspectArgs),
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Initialize structured logging with JSON formatting (configurable via env)
     let use_json = std::env::var("LOG_FORMAT")
         .unwrap_or_else(|_| "json".to_string())
@@ -57,26 +132,56 @@ async fn main() -> Result<()> {
     );
 
     // Load configuration
-    let config = Config::load()?;
+    let mut config = Config::load(cli.profile)?;
+    tracing::info!(
+        profile = config.profile.map_or("none", Profile::as_str),
+        overrides = ?ProfileDefaults::overrides(),
+        config = %config.effective(),
+        "Effective configuration"
+    );
+    config.resolve_secrets().await?;
     tracing::info!(
         worker_count = config.processor.worker_count,
         batch_interval_seconds = config.processor.batch_interval_seconds,
         "Configuration loaded"
     );
 
+    match cli.command {
+        Some(Command::Replay(args)) => return replay::run(config, args).await,
+        Some(Command::Inspect(args)) => return inspect::run(config, args).await,
+        Some(Command::Run) | None => {}
+    }
+
     // Initialize Solana client pool
     let solana_client = Arc::new(
         solana_client::SolanaClientPool::new(
             config.solana.rpc_urls.clone(),
-            config.solana.commitment.clone(),
+            solana_client::RateLimits::from_config(&config.solana),
+            solana_client::Commitments::from_config(&config.solana),
         )
-        .await?,
+        .await?
+        .with_program_version_check(std::time::Duration::from_secs(config.solana.program_version_check_seconds))
+        .with_shadow_broadcast(config.solana.shadow_broadcast_count),
     );
     tracing::info!(
         rpc_count = config.solana.rpc_urls.len(),
+        shadow_broadcast_count = config.solana.shadow_broadcast_count,
         "Solana RPC pool initialized"
     );
 
+    // Report unsupported deployments up front; settlement against them stays
+    // paused until the processor is upgraded
+    for deployment in &config.solana.deployments {
+        match solana_client.ensure_program_supported(deployment).await {
+            Ok(version) => tracing::info!(
+                deployment = %deployment.name,
+                program_version = version,
+                "Vault program version supported"
+            ),
+            Err(e) => tracing::error!(deployment = %deployment.name, error = %e, "Vault program version unsupported"),
+        }
+    }
+
     // Load processor keypair
     let processor_keypair = solana_client::load_processor_keypair(&config.processor.keypair_path)?;
     let processor_keypair_arc = Arc::new(processor_keypair);
@@ -85,19 +190,89 @@ async fn main() -> Result<()> {
         "Processor keypair loaded"
     );
 
-    // Initialize worker pool
-    let worker_pool = Arc::new(WorkerPool::new(
-        config.clone(),
-        solana_client.clone(),
-        Keypair::from_bytes(&processor_keypair_arc.to_bytes()).unwrap(),
-    ));
+    let seed_commitment = config.simulation.server_seed.commitment();
+    if config.simulation.seed_generated {
+        tracing::warn!(
+            server_seed_hash = %seed_commitment,
+            "SIMULATION_SERVER_SEED not set; using a random seed that cannot be revealed for verification"
+        );
+    } else {
+        tracing::info!(server_seed_hash = %seed_commitment, "Simulation server seed loaded");
+    }
+    let behavior = &config.simulation.behavior;
+    if !behavior.is_instant() {
+        tracing::warn!(
+            profile = %behavior.name,
+            latency = ?behavior.latency,
+            failure_rate = behavior.failure_rate,
+            win_ratio = ?behavior.win_ratio,
+            "Simulation profile adds latency and failures to settlements; for load testing only"
+        );
+    }
 
-    // Initialize blockchain client and settlement workers
-    let blockchain_client = Arc::new(BlockchainClient::new(
-        config.blockchain.api_base_url.clone(),
-        config.blockchain.api_key.clone(),
+    // Shared blockchain API client; one connection pool for every worker
+    let clock = SystemClock::shared();
+    let blockchain_client = Arc::new(BlockchainClient::from_config(&config.blockchain)?.with_clock(clock.clone()));
+
+    // One tuner for coordinator batching and batch transactions
+    let batch_tuner = Arc::new(BatchSizeTuner::new(
+        config.processor.batch_autotune_enabled,
+        config.processor.coordinator_batch_min_size,
+        config.processor.coordinator_batch_max_size,
+        config.processor.batch_compute_unit_limit,
     ));
 
+    // Signed push of settlement outcomes, with a Redis retry queue
+    let settlement_webhook = SettlementWebhook::connect(&config.settlement_webhook).await?.map(Arc::new);
+    if let Some(webhook) = &settlement_webhook {
+        tokio::spawn(webhook.clone().run_retries());
+    }
+
+    // Holds permanent failures as retryable when too many settlements fail permanently
+    let retry_budget = RetryBudget::connect(&config.retry_budget, config.leader_election.instance_id.clone())
+        .await?
+        .map(Arc::new);
+    if let Some(retry_budget) = &retry_budget {
+        tokio::spawn(retry_budget.clone().run());
+    }
+
+    // Push of claimable bets from the backend, on top of polling
+    let claim_signals = if config.claim_signals.enabled {
+        let source = ClaimSignalSource::new();
+        tokio::spawn(source.clone().run(
+            backend_client(&config.backend, &config.claim_signals.backend_url)?,
+            config.leader_election.instance_id.clone(),
+            std::time::Duration::from_secs(config.claim_signals.reconnect_seconds),
+        ));
+        info!(backend_url = %config.claim_signals.backend_url, "Claim signal subscription spawned");
+        Some(source)
+    } else {
+        None
+    };
+
+    // Staggers submissions contending for the same write lock, across the pool and settlement workers
+    let write_locks = (config.processor.write_lock_stagger_ms > 0).then(|| {
+        Arc::new(WriteLockScheduler::new(std::time::Duration::from_millis(
+            config.processor.write_lock_stagger_ms,
+        )))
+    });
+
+    // Initialize worker pool
+    let worker_pool = Arc::new(
+        WorkerPool::new(
+            config.clone(),
+            solana_client.clone(),
+            Keypair::from_bytes(&processor_keypair_arc.to_bytes()).unwrap(),
+            blockchain_client.clone(),
+            batch_tuner.clone(),
+        )
+        .with_webhook(settlement_webhook.clone())
+        .with_retry_budget(retry_budget.clone())
+        .with_claim_signals(claim_signals.clone())
+        .with_write_locks(write_locks.clone())
+        .with_clock(clock.clone()),
+    );
+
     info!(
         settlement_worker_count = config.processor.settlement_worker_count,
         coordinator_enabled = config.processor.coordinator_enabled,
@@ -105,6 +280,39 @@ async fn main() -> Result<()> {
     );
 
     let mut settlement_handles = Vec::new();
+    let mut leader_election: Option<Arc<LeaderElection>> = None;
+    let mut standby: Option<Arc<WarmStandby>> = None;
+
+    // Worker/coordinator progress, exposed on the metrics port under /admin
+    let progress = Arc::new(ProgressRegistry::default());
+
+    // Failed batch membership, for `processor replay --batch <id>`
+    let batch_journal = if config.batch_journal.enabled {
+        Some(Arc::new(
+            BatchJournal::connect(
+                &config.batch_journal.redis_url,
+                std::time::Duration::from_secs(config.batch_journal.ttl_seconds),
+            )
+            .await?,
+        ))
+    } else {
+        None
+    };
+
+    // Incident kill switch shared with the backend; halts dispatch while engaged
+    let kill_switch = if config.kill_switch.enabled {
+        let kill_switch = Arc::new(
+            KillSwitch::connect(
+                &config.kill_switch.redis_url,
+                std::time::Duration::from_millis(config.kill_switch.poll_interval_ms),
+            )
+            .await?,
+        );
+        settlement_handles.push(tokio::spawn(kill_switch.clone().run()));
+        Some(kill_switch)
+    } else {
+        None
+    };
 
     if config.processor.coordinator_enabled {
         // NEW COORDINATOR MODE: Create channels and spawn coordinator
@@ -114,19 +322,98 @@ async fn main() -> Result<()> {
         let mut work_senders = Vec::new();
         let mut work_receivers = Vec::new();
 
-        // Create channels for each worker
+        // Create high/normal priority channels for each worker
         for _ in 0..config.processor.settlement_worker_count {
-            let (tx, rx) = tokio::sync::mpsc::channel(channel_buffer_size);
+            let (tx, rx) = coordinator::worker_channel(channel_buffer_size);
             work_senders.push(tx);
             work_receivers.push(rx);
         }
 
+        // Only one coordinator across instances may dispatch; the rest stand by
+        if config.leader_election.enabled {
+            let renew_interval = std::time::Duration::from_secs(config.leader_election.renew_interval_seconds);
+            let mut election = LeaderElection::connect(
+                &config.leader_election.redis_url,
+                config.leader_election.lease_key.clone(),
+                config.leader_election.instance_id.clone(),
+                std::time::Duration::from_secs(config.leader_election.lease_ttl_seconds),
+                renew_interval,
+            )
+            .await?;
+            if config.leader_election.standby {
+                election = election.with_check_interval(std::time::Duration::from_millis(
+                    config.leader_election.standby_check_interval_ms,
+                ));
+            }
+            let election = Arc::new(election);
+            info!(
+                instance_id = election.instance_id(),
+                "Leader election enabled for coordinator"
+            );
+
+            settlement_handles.push(tokio::spawn(election.clone().run()));
+            if config.leader_election.standby {
+                let warm_standby = Arc::new(WarmStandby::new(election.clone(), solana_client.clone(), renew_interval));
+                settlement_handles.push(tokio::spawn(warm_standby.clone().run()));
+                standby = Some(warm_standby);
+            }
+            leader_election = Some(election);
+        }
+
+        // Shared so workers follow the coordinator into and out of drain mode
+        let drain = (config.drain.threshold > 0).then(|| Arc::new(DrainMode::new(config.drain.clone())));
+
         // Spawn coordinator
-        let coordinator = Arc::new(Coordinator::new(
+        let mut coordinator = Coordinator::new(
             blockchain_client.clone(),
             work_senders,
             config.clone(),
-        ));
+            leader_election.clone(),
+            kill_switch.clone(),
+            progress.clone(),
+        )
+        .with_batch_tuner(batch_tuner.clone())
+        .with_clock(clock.clone());
+        if config.processor.allowance_expiry_lookup_enabled {
+            coordinator = coordinator.with_allowance_expiry(Arc::new(AllowanceExpiryCache::new(solana_client.clone())));
+        }
+        if config.processor.account_prewarm_enabled {
+            coordinator = coordinator.with_account_prewarm(Arc::new(AccountPrewarm::new(
+                solana_client.clone(),
+                processor_keypair_arc.clone(),
+                config.solana.clone(),
+                config.processor.auto_init_vaults,
+            )));
+        }
+        if config.processor.payout_liability_check_enabled {
+            coordinator = coordinator.with_payout_liability(Arc::new(PayoutLiability::new(
+                solana_client.clone(),
+                config.solana.clone(),
+                config.processor.payout_liability_reserve_lamports,
+            )));
+        }
+        if config.processor.casino_pause_check_enabled {
+            coordinator = coordinator.with_casino_pause(Arc::new(CasinoPause::new(
+                solana_client.clone(),
+                config.solana.clone(),
+                std::time::Duration::from_secs(config.processor.casino_pause_check_interval_seconds),
+            )));
+        }
+        if let Some(drain) = &drain {
+            coordinator = coordinator.with_drain_mode(drain.clone());
+        }
+        if let Some(claim_signals) = &claim_signals {
+            coordinator = coordinator.with_claim_signals(claim_signals.clone());
+        }
+        if let Some(payout_holds) = PayoutHolds::connect(&config.payout_hold).await? {
+            info!(
+                threshold_lamports = config.payout_hold.threshold_lamports,
+                delay_seconds = config.payout_hold.delay_seconds,
+                "Large payout holds enabled"
+            );
+            coordinator = coordinator.with_payout_holds(Arc::new(payout_holds.with_clock(clock.clone())));
+        }
+        let coordinator = Arc::new(coordinator);
 
         let coordinator_handle = tokio::spawn({
             let coordinator = coordinator.clone();
@@ -147,7 +434,14 @@ async fn main() -> Result<()> {
                 config.clone(),
                 worker_id,
                 receiver,
-            );
+            )
+            .with_progress(progress.clone())
+            .with_journal(batch_journal.clone())
+            .with_drain_mode(drain.clone())
+            .with_webhook(settlement_webhook.clone())
+            .with_retry_budget(retry_budget.clone())
+            .with_write_locks(write_locks.clone())
+            .with_clock(clock.clone());
 
             let handle = tokio::spawn(async move {
                 info!(worker_id, "Settlement worker started (coordinator mode)");
@@ -172,7 +466,13 @@ async fn main() -> Result<()> {
                 processor_keypair_arc.clone(),
                 config.clone(),
                 worker_id,
-            );
+            )
+            .with_kill_switch(kill_switch.clone())
+            .with_progress(progress.clone())
+            .with_webhook(settlement_webhook.clone())
+            .with_retry_budget(retry_budget.clone())
+            .with_write_locks(write_locks.clone())
+            .with_clock(clock.clone());
 
             let handle = tokio::spawn(async move {
                 info!(worker_id, "Settlement worker started (legacy mode)");
@@ -183,15 +483,49 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Refunds for bets voided through the backend admin API
+    if config.refund.enabled {
+        let refund_worker = Arc::new(RefundWorker::new(
+            Arc::new(backend_client(&config.backend, &config.refund.backend_url)?),
+            solana_client.clone(),
+            processor_keypair_arc.clone(),
+            config.clone(),
+        ));
+        let standby = standby.clone();
+        settlement_handles.push(tokio::spawn(async move {
+            if let Some(standby) = standby {
+                standby.wait_for_takeover().await;
+            }
+            refund_worker.run().await
+        }));
+        info!(backend_url = %config.refund.backend_url, "Refund worker spawned");
+    }
+
+    // Casino vault top-ups from the treasury; only the leader tops up when elected
+    if config.casino_topup.enabled {
+        let topup = Arc::new(TreasuryTopUp::new(
+            solana_client.clone(),
+            processor_keypair_arc.clone(),
+            config.clone(),
+            leader_election.clone(),
+        )?);
+        settlement_handles.push(tokio::spawn(topup.run()));
+        info!("Casino top-up task spawned");
+    }
+
     info!("All settlement components spawned");
 
     // Start metrics server
-    let metrics_handle = tokio::spawn(start_metrics_server(config.metrics_port));
+    let metrics_handle = tokio::spawn(start_metrics_server(config.metrics_port, progress.clone()));
 
     // Start worker pool
     let worker_handle = tokio::spawn({
         let worker_pool = worker_pool.clone();
         async move {
+            // A warm standby leaves Redis bets to the active instance until it takes over
+            if let Some(standby) = standby {
+                standby.wait_for_takeover().await;
+            }
             tracing::info!("WorkerPool starting (Redis-based bet processing)");
             worker_pool.start().await
         }
@@ -211,6 +545,11 @@ async fn main() -> Result<()> {
     for handle in settlement_handles {
         handle.abort();
     }
+
+    // Hand leadership over immediately instead of waiting for lease expiry
+    if let Some(election) = leader_election {
+        election.release().await;
+    }
     
     metrics_handle.abort();
 
@@ -219,17 +558,19 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn start_metrics_server(port: u16) -> Result<()> {
+async fn start_metrics_server(port: u16, progress: Arc<ProgressRegistry>) -> Result<()> {
     use std::net::SocketAddr;
     use axum::{routing::get, Router};
 
     let builder = metrics_exporter_prometheus::PrometheusBuilder::new();
     let handle = builder.install_recorder()?;
 
-    let app = Router::new().route(
-        "/metrics",
-        get(|| async move { handle.render() }),
-    );
+    let app = Router::new()
+        .route(
+            "/metrics",
+            get(|| async move { handle.render() }),
+        )
+        .merge(admin_api::router(progress));
 
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     tracing::info!("Processor metrics listening on {}", addr);
@@ -0,0 +1,203 @@
+//! Risk hold on large payouts
+//!
+//! Wins paying at least the configured threshold are not dispatched when the
+//! coordinator first sees them. They are recorded as `pending_review` under
+//! `shared::constants::PAYOUT_HOLD_PREFIX` and go out once the hold delay has
+//! passed or an operator approves them through
+//! `POST /api/admin/payouts/:transaction_id/approve`. Until then they stay
+//! pending in the blockchain API and are skipped every cycle.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use shared::api::{PayoutHold, PayoutHoldStatus};
+use shared::clock::{Clock, SystemClock};
+use shared::constants::{PAYOUT_HOLDS_PENDING_KEY, PAYOUT_HOLD_PREFIX};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::blockchain_client::GameSettlementInfo;
+use crate::config::PayoutHoldConfig;
+
+/// Released holds are kept this long so a retried payout isn't held again
+const HOLD_TTL_SECONDS: u64 = 30 * 86_400;
+
+pub struct PayoutHolds {
+    redis: ConnectionManager,
+    threshold_lamports: u64,
+    /// Zero holds until approved
+    delay: Duration,
+    /// Stamps new holds and decides when they expire
+    clock: Arc<dyn Clock>,
+}
+
+fn hold_key(transaction_id: u64) -> String {
+    format!("{}{}", PAYOUT_HOLD_PREFIX, transaction_id)
+}
+
+/// Whether `settlement` pays out enough to be held
+fn needs_hold(settlement: &GameSettlementInfo, threshold_lamports: u64) -> bool {
+    threshold_lamports > 0 && settlement.outcome == "Win" && settlement.payout >= threshold_lamports
+}
+
+fn new_hold(settlement: &GameSettlementInfo, delay: Duration, now: DateTime<Utc>) -> PayoutHold {
+    let release_at = (!delay.is_zero())
+        .then(|| chrono::Duration::from_std(delay).ok())
+        .flatten()
+        .map(|delay| now + delay);
+    PayoutHold {
+        transaction_id: settlement.transaction_id,
+        player_address: settlement.player_address.clone(),
+        payout: settlement.payout,
+        status: PayoutHoldStatus::PendingReview,
+        held_at: now,
+        release_at,
+        approved_by: None,
+        approved_at: None,
+    }
+}
+
+impl PayoutHolds {
+    /// `None` when the threshold is zero
+    pub async fn connect(config: &PayoutHoldConfig) -> Result<Option<Self>> {
+        if config.threshold_lamports == 0 {
+            return Ok(None);
+        }
+        let redis = redis::Client::open(config.redis_url.as_str())
+            .context("Invalid Redis URL")?
+            .get_connection_manager()
+            .await
+            .context("Failed to connect to Redis for payout holds")?;
+        Ok(Some(Self {
+            redis,
+            threshold_lamports: config.threshold_lamports,
+            delay: Duration::from_secs(config.delay_seconds),
+            clock: SystemClock::shared(),
+        }))
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Drop held payouts from `settlements`, holding newly seen large ones
+    ///
+    /// A payout whose hold can't be read stays held; it is retried next cycle.
+    pub async fn release(&self, settlements: Vec<GameSettlementInfo>) -> Vec<GameSettlementInfo> {
+        let mut released = Vec::with_capacity(settlements.len());
+        for settlement in settlements {
+            if !needs_hold(&settlement, self.threshold_lamports) {
+                released.push(settlement);
+                continue;
+            }
+            match self.check(&settlement).await {
+                Ok(true) => released.push(settlement),
+                Ok(false) => {}
+                Err(e) => warn!(
+                    tx_id = settlement.transaction_id,
+                    error = %e,
+                    "Failed to check payout hold; payout stays held"
+                ),
+            }
+        }
+        released
+    }
+
+    /// Whether the settlement's hold is released, creating the hold on first sight
+    async fn check(&self, settlement: &GameSettlementInfo) -> Result<bool> {
+        let mut redis = self.redis.clone();
+        let now = self.clock.now();
+        let key = hold_key(settlement.transaction_id);
+
+        let stored: Option<String> = redis.get(&key).await?;
+        let Some(stored) = stored else {
+            let hold = new_hold(settlement, self.delay, now);
+            let payload = serde_json::to_string(&hold)?;
+            let created: Option<String> = redis::cmd("SET")
+                .arg(&key)
+                .arg(payload)
+                .arg("NX")
+                .arg("EX")
+                .arg(HOLD_TTL_SECONDS)
+                .query_async(&mut redis)
+                .await?;
+            if created.is_some() {
+                let _: () = redis
+                    .zadd(PAYOUT_HOLDS_PENDING_KEY, settlement.transaction_id, now.timestamp_millis())
+                    .await?;
+                warn!(
+                    tx_id = settlement.transaction_id,
+                    payout = settlement.payout,
+                    release_at = ?hold.release_at,
+                    "Large payout held for review"
+                );
+                metrics::counter!("payout_holds_total", "action" => "held").increment(1);
+            }
+            return Ok(false);
+        };
+
+        let hold: PayoutHold = serde_json::from_str(&stored).context("Unreadable payout hold")?;
+        if !hold.is_released(now) {
+            return Ok(false);
+        }
+        let removed: i64 = redis.zrem(PAYOUT_HOLDS_PENDING_KEY, settlement.transaction_id).await?;
+        if removed > 0 {
+            info!(
+                tx_id = settlement.transaction_id,
+                approved_by = ?hold.approved_by,
+                "Held payout released"
+            );
+            let reason = if hold.status == PayoutHoldStatus::Approved { "approved" } else { "delay_elapsed" };
+            metrics::counter!("payout_holds_total", "action" => reason).increment(1);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_wins_are_held_until_released() {
+        let win = GameSettlementInfo {
+            transaction_id: 7,
+            player_address: "player".to_string(),
+            game_type: "coinflip".to_string(),
+            bet_amount: 1_000_000_000,
+            token: "SOL".to_string(),
+            outcome: "Win".to_string(),
+            payout: 50_000_000_000,
+            vrf_proof: String::new(),
+            vrf_output: String::new(),
+            block_height: 1,
+            version: 1,
+            solana_tx_id: None,
+            retry_count: 0,
+            next_retry_after: None,
+            allowance_pda: None,
+            casino_id: None,
+            priority: false,
+            status: None,
+            allowance_expires_at: None,
+        };
+        assert!(needs_hold(&win, 10_000_000_000));
+        assert!(!needs_hold(&win, 0));
+        assert!(!needs_hold(&GameSettlementInfo { outcome: "Loss".to_string(), ..win.clone() }, 10_000_000_000));
+
+        let now = Utc::now();
+        let mut hold = new_hold(&win, Duration::from_secs(600), now);
+        assert!(!hold.is_released(now));
+        assert!(hold.is_released(now + chrono::Duration::seconds(600)));
+
+        // Without a delay only an approval releases it
+        hold = new_hold(&win, Duration::ZERO, now);
+        assert!(!hold.is_released(now + chrono::Duration::days(365)));
+        hold.approve("ops@casino", now);
+        assert!(hold.is_released(now));
+        assert_eq!(serde_json::to_value(&hold).unwrap()["status"], "approved");
+    }
+}
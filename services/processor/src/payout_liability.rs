@@ -0,0 +1,182 @@
+//! Payout liability check before dispatch
+//!
+//! A payout batch whose wins add up to more than the casino vault holds fails
+//! every transaction in it on-chain, costing fees and retries for nothing. At
+//! the start of each cycle the coordinator reads every deployment's tracked
+//! casino vault balance; before a payout batch goes out, the SOL it pays is
+//! charged against that balance, and a batch the balance can't cover is
+//! deferred instead. Its settlements stay pending in the blockchain API and are
+//! fetched again once the vault is topped up. A deployment whose balance
+//! couldn't be read doesn't hold anything back.
+
+use anyhow::{Context, Result};
+use shared::vault::{casino_pda, casino_vault_pda, CasinoVaultAccount};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{error, warn};
+
+use crate::blockchain_client::GameSettlementInfo;
+use crate::config::SolanaConfig;
+use crate::coordinator::{BatchType, SettlementBatch};
+use crate::solana_client::SolanaClientPool;
+
+/// SOL each deployment's casino vault pays out for `settlements`; SPL payouts come from token accounts
+fn liability(solana: &SolanaConfig, settlements: &[GameSettlementInfo]) -> HashMap<String, u64> {
+    let mut liability = HashMap::new();
+    for settlement in settlements {
+        if settlement.outcome != "Win" || !settlement.token.eq_ignore_ascii_case("SOL") {
+            continue;
+        }
+        let deployment = solana.deployment_for(settlement.casino_id.as_deref());
+        *liability.entry(deployment.name.clone()).or_insert(0u64) += settlement.payout;
+    }
+    liability
+}
+
+/// Charge `liability` against `available` if every deployment can cover it
+///
+/// Deployments missing from `available` have no known balance and always cover it.
+fn charge(available: &mut HashMap<String, u64>, liability: &HashMap<String, u64>) -> bool {
+    let covered = liability
+        .iter()
+        .all(|(deployment, lamports)| available.get(deployment).is_none_or(|balance| balance >= lamports));
+    if covered {
+        for (deployment, lamports) in liability {
+            if let Some(balance) = available.get_mut(deployment) {
+                *balance -= lamports;
+            }
+        }
+    }
+    covered
+}
+
+pub struct PayoutLiability {
+    solana_client: Arc<SolanaClientPool>,
+    solana: SolanaConfig,
+    /// Left in each casino vault rather than paid out
+    reserve_lamports: u64,
+    /// Balance read this cycle less payouts dispatched since, by deployment
+    available: Mutex<HashMap<String, u64>>,
+}
+
+impl PayoutLiability {
+    pub fn new(solana_client: Arc<SolanaClientPool>, solana: SolanaConfig, reserve_lamports: u64) -> Self {
+        Self { solana_client, solana, reserve_lamports, available: Mutex::new(HashMap::new()) }
+    }
+
+    /// Read the casino vault balances for this cycle
+    pub async fn refresh(&self) {
+        let balances = match self.fetch_balances().await {
+            Ok(balances) => balances,
+            Err(e) => {
+                warn!(error = %e, "Failed to read casino vault balances; payouts are not checked this cycle");
+                metrics::counter!("payout_liability_refresh_failures_total").increment(1);
+                HashMap::new()
+            }
+        };
+        for deployment in &self.solana.deployments {
+            metrics::gauge!("payout_liability_deferred_lamports", "deployment" => deployment.name.clone()).set(0.0);
+            if let Some(balance) = balances.get(&deployment.name) {
+                metrics::gauge!("payout_liability_available_lamports", "deployment" => deployment.name.clone())
+                    .set(*balance as f64);
+            }
+        }
+        *self.available.lock().unwrap_or_else(|e| e.into_inner()) = balances;
+    }
+
+    async fn fetch_balances(&self) -> Result<HashMap<String, u64>> {
+        let vaults: Vec<_> = self
+            .solana
+            .deployments
+            .iter()
+            .map(|deployment| {
+                let casino = casino_pda(&deployment.program_id);
+                (deployment.name.clone(), casino_vault_pda(&casino, &deployment.program_id))
+            })
+            .collect();
+        let client = self.solana_client.get_client().await;
+        let commitment = self.solana_client.commitments().read;
+        let reserve = self.reserve_lamports;
+        tokio::task::spawn_blocking(move || {
+            let addresses: Vec<_> = vaults.iter().map(|(_, vault)| *vault).collect();
+            let accounts = client
+                .get_multiple_accounts_with_commitment(&addresses, commitment)
+                .context("Failed to fetch casino vaults")?
+                .value;
+            let mut balances = HashMap::with_capacity(vaults.len());
+            for ((deployment, vault), account) in vaults.into_iter().zip(accounts) {
+                let Some(account) = account else {
+                    warn!(deployment = %deployment, %vault, "Casino vault not found; its payouts are not checked");
+                    continue;
+                };
+                let balance = CasinoVaultAccount::decode(&account.data)?.sol_balance;
+                balances.insert(deployment, balance.saturating_sub(reserve));
+            }
+            Ok(balances)
+        })
+        .await
+        .context("Casino vault balance task panicked")?
+    }
+
+    /// Keep the batches the casino vaults can pay for, deferring the rest
+    ///
+    /// Batches are charged in order, so sort the ones that should be paid first to the front.
+    pub fn admit(&self, batches: Vec<SettlementBatch>) -> Vec<SettlementBatch> {
+        let mut available = self.available.lock().unwrap_or_else(|e| e.into_inner());
+        let mut admitted = Vec::with_capacity(batches.len());
+        for batch in batches {
+            if batch.batch_type != BatchType::Payout {
+                admitted.push(batch);
+                continue;
+            }
+            let liability = liability(&self.solana, &batch.settlements);
+            if charge(&mut available, &liability) {
+                admitted.push(batch);
+                continue;
+            }
+
+            for (deployment, lamports) in &liability {
+                metrics::counter!("payout_liability_deferred_batches_total", "deployment" => deployment.clone())
+                    .increment(1);
+                metrics::gauge!("payout_liability_deferred_lamports", "deployment" => deployment.clone())
+                    .increment(*lamports as f64);
+                error!(
+                    deployment = %deployment,
+                    batch_id = %batch.batch_id,
+                    settlements = batch.settlements.len(),
+                    required_lamports = lamports,
+                    available_lamports = available.get(deployment).copied().unwrap_or_default(),
+                    "Casino vault cannot cover payout batch; deferring it until the vault is funded"
+                );
+            }
+        }
+        admitted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charge_defers_what_balance_cannot_cover() {
+        let mut available = HashMap::from([("default".to_string(), 100)]);
+        let batch = |lamports: u64| HashMap::from([("default".to_string(), lamports)]);
+
+        assert!(charge(&mut available, &batch(60)));
+        assert_eq!(available["default"], 40);
+
+        // Too large for what is left; nothing is charged
+        assert!(!charge(&mut available, &batch(41)));
+        assert_eq!(available["default"], 40);
+        assert!(charge(&mut available, &batch(40)));
+
+        // A batch spanning deployments needs all of them to cover it
+        available.insert("other".to_string(), 10);
+        let spanning = HashMap::from([("default".to_string(), 0), ("other".to_string(), 11)]);
+        assert!(!charge(&mut available, &spanning));
+
+        // Unknown balances don't hold anything back
+        assert!(charge(&mut available, &HashMap::from([("unread".to_string(), u64::MAX)])));
+    }
+}
@@ -0,0 +1,138 @@
+//! Vault program version handshake
+//!
+//! The vault program stamps `Casino::program_version` when the authority runs
+//! `migrate` after an upgrade. Instructions built for an older layout then fail
+//! with cryptic simulation errors (unknown discriminators, short accounts), so
+//! before submitting against a deployment the processor reads its casino and
+//! refuses if the version is newer than `MAX_SUPPORTED_PROGRAM_VERSION`. The
+//! version is cached per program and read again once older than the check
+//! interval, so every settlement cycle sees an upgrade within that interval.
+
+use anyhow::{bail, Context, Result};
+use shared::vault::{casino_pda, CasinoAccount, MAX_SUPPORTED_PROGRAM_VERSION};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+use crate::deployments::VaultDeployment;
+
+/// Whether a casino stamped with `version` can be settled against
+pub fn is_supported(version: u32) -> bool {
+    version <= MAX_SUPPORTED_PROGRAM_VERSION
+}
+
+/// Last `Casino::program_version` read per vault program
+pub struct ProgramVersionGuard {
+    check_interval: Duration,
+    versions: Mutex<HashMap<Pubkey, (Instant, u32)>>,
+}
+
+impl ProgramVersionGuard {
+    pub fn new(check_interval: Duration) -> Self {
+        Self { check_interval, versions: Mutex::new(HashMap::new()) }
+    }
+
+    fn cached(&self, program_id: &Pubkey, now: Instant) -> Option<u32> {
+        let versions = self.versions.lock().unwrap_or_else(|e| e.into_inner());
+        versions
+            .get(program_id)
+            .filter(|(read_at, _)| now.saturating_duration_since(*read_at) < self.check_interval)
+            .map(|(_, version)| *version)
+    }
+
+    fn last_known(&self, program_id: &Pubkey) -> Option<u32> {
+        let versions = self.versions.lock().unwrap_or_else(|e| e.into_inner());
+        versions.get(program_id).map(|(_, version)| *version)
+    }
+
+    /// Error unless `deployment`'s program version is supported
+    ///
+    /// If the casino can't be read, the last known version decides, and a
+    /// deployment never read is let through. Its transactions would fail
+    /// anyway; RPC hiccups shouldn't stop settlement.
+    pub async fn ensure_supported(&self, client: Arc<RpcClient>, deployment: &VaultDeployment) -> Result<u32> {
+        let program_id = deployment.program_id;
+        let version = match self.cached(&program_id, Instant::now()) {
+            Some(version) => version,
+            None => match read_version(client, program_id).await {
+                Ok(Some(version)) => {
+                    metrics::gauge!("vault_program_version", "deployment" => deployment.name.clone())
+                        .set(version as f64);
+                    let previous = self
+                        .versions
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(program_id, (Instant::now(), version));
+                    if !is_supported(version) && previous.is_none_or(|(_, previous)| previous != version) {
+                        error!(
+                            deployment = %deployment.name,
+                            program_version = version,
+                            max_supported = MAX_SUPPORTED_PROGRAM_VERSION,
+                            "Vault program is newer than this processor supports; upgrade the processor"
+                        );
+                    }
+                    version
+                }
+                Ok(None) => return Ok(0),
+                Err(e) => {
+                    warn!(deployment = %deployment.name, error = %e, "Failed to read vault program version");
+                    match self.last_known(&program_id) {
+                        Some(version) => version,
+                        None => return Ok(0),
+                    }
+                }
+            },
+        };
+
+        if !is_supported(version) {
+            metrics::counter!("program_version_refusals_total", "deployment" => deployment.name.clone()).increment(1);
+            bail!(
+                "Vault deployment '{}' runs program version {}, newer than the supported {}; refusing to submit",
+                deployment.name,
+                version,
+                MAX_SUPPORTED_PROGRAM_VERSION
+            );
+        }
+        Ok(version)
+    }
+}
+
+/// `None` while the casino account doesn't exist
+async fn read_version(client: Arc<RpcClient>, program_id: Pubkey) -> Result<Option<u32>> {
+    let casino = casino_pda(&program_id);
+    let account = tokio::task::spawn_blocking(move || -> Result<_> {
+        let response = client
+            .get_account_with_commitment(&casino, client.commitment())
+            .context("Failed to fetch casino account")?;
+        Ok(response.value)
+    })
+    .await
+    .context("Program version lookup task failed")??;
+    account
+        .map(|account| CasinoAccount::decode(&account.data).map(|casino| casino.program_version))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_version_expires() {
+        assert!(is_supported(0));
+        assert!(is_supported(MAX_SUPPORTED_PROGRAM_VERSION));
+        assert!(!is_supported(MAX_SUPPORTED_PROGRAM_VERSION + 1));
+
+        let guard = ProgramVersionGuard::new(Duration::from_secs(30));
+        let program_id = Pubkey::new_unique();
+        let read_at = Instant::now();
+        guard.versions.lock().unwrap().insert(program_id, (read_at, 2));
+
+        assert_eq!(guard.cached(&program_id, read_at + Duration::from_secs(10)), Some(2));
+        assert_eq!(guard.cached(&program_id, read_at + Duration::from_secs(30)), None);
+        assert_eq!(guard.last_known(&program_id), Some(2));
+    }
+}
@@ -0,0 +1,256 @@
+//! Worker and coordinator progress tracking
+//!
+//! Workers and the coordinator record what they are doing into a shared
+//! `ProgressRegistry`; the admin endpoints on the metrics port render snapshots
+//! of it so operators can see what a stuck worker is working on.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::coordinator::{BatchType, Lane, SettlementBatch};
+
+/// Batch a worker is currently processing
+#[derive(Debug, Clone)]
+struct ActiveBatch {
+    batch_id: String,
+    batch_type: BatchType,
+    lane: Lane,
+    settlement_count: usize,
+    settled: usize,
+    started: Instant,
+    started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct WorkerState {
+    current_batch: Option<ActiveBatch>,
+    batches_processed: u64,
+    settlements_processed: u64,
+    settlements_failed: u64,
+    last_error: Option<String>,
+    last_error_at: Option<DateTime<Utc>>,
+    last_activity_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CoordinatorStats {
+    pub cycles: u64,
+    pub failed_cycles: u64,
+    pub skipped_cycles: u64,
+    pub last_cycle_at: Option<DateTime<Utc>>,
+    pub last_cycle_duration_ms: Option<u64>,
+    pub last_fetched: usize,
+    pub last_distributed: usize,
+    pub last_error: Option<String>,
+    /// Batches waiting in each worker's channels after the last cycle
+    pub queued_batches: BTreeMap<usize, usize>,
+    /// Deployments whose casino is paused on-chain; their settlements are not dispatched
+    pub paused_deployments: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InflightBatch {
+    pub worker_id: usize,
+    pub batch_id: String,
+    pub batch_type: String,
+    pub lane: &'static str,
+    pub settlement_count: usize,
+    pub settled: usize,
+    pub started_at: DateTime<Utc>,
+    pub age_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerSnapshot {
+    pub worker_id: usize,
+    pub current_batch: Option<InflightBatch>,
+    pub batches_processed: u64,
+    pub settlements_processed: u64,
+    pub settlements_failed: u64,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub last_activity_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProgressRegistry {
+    workers: Mutex<BTreeMap<usize, WorkerState>>,
+    coordinator: Mutex<CoordinatorStats>,
+}
+
+impl ActiveBatch {
+    fn snapshot(&self, worker_id: usize) -> InflightBatch {
+        InflightBatch {
+            worker_id,
+            batch_id: self.batch_id.clone(),
+            batch_type: format!("{:?}", self.batch_type),
+            lane: self.lane.as_str(),
+            settlement_count: self.settlement_count,
+            settled: self.settled,
+            started_at: self.started_at,
+            age_ms: self.started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+impl ProgressRegistry {
+    fn workers(&self) -> MutexGuard<'_, BTreeMap<usize, WorkerState>> {
+        self.workers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn coordinator(&self) -> MutexGuard<'_, CoordinatorStats> {
+        self.coordinator.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Make a worker visible before it receives any work
+    pub fn register_worker(&self, worker_id: usize) {
+        self.workers().entry(worker_id).or_default();
+    }
+
+    pub fn batch_started(&self, worker_id: usize, batch: &SettlementBatch) {
+        let mut workers = self.workers();
+        let worker = workers.entry(worker_id).or_default();
+        worker.current_batch = Some(ActiveBatch {
+            batch_id: batch.batch_id.clone(),
+            batch_type: batch.batch_type,
+            lane: batch.lane,
+            settlement_count: batch.settlements.len(),
+            settled: 0,
+            started: Instant::now(),
+            started_at: Utc::now(),
+        });
+        worker.last_activity_at = Some(Utc::now());
+    }
+
+    pub fn batch_finished(&self, worker_id: usize) {
+        let mut workers = self.workers();
+        let worker = workers.entry(worker_id).or_default();
+        if worker.current_batch.take().is_some() {
+            worker.batches_processed += 1;
+        }
+        worker.last_activity_at = Some(Utc::now());
+    }
+
+    /// Record one settlement outcome; errors are kept as the worker's last error
+    pub fn settlement_finished(&self, worker_id: usize, error: Option<&anyhow::Error>) {
+        let mut workers = self.workers();
+        let worker = workers.entry(worker_id).or_default();
+        match error {
+            None => worker.settlements_processed += 1,
+            Some(e) => {
+                worker.settlements_failed += 1;
+                worker.last_error = Some(format!("{:#}", e));
+                worker.last_error_at = Some(Utc::now());
+            }
+        }
+        if let Some(batch) = worker.current_batch.as_mut() {
+            batch.settled += 1;
+        }
+        worker.last_activity_at = Some(Utc::now());
+    }
+
+    pub fn cycle_skipped(&self) {
+        self.coordinator().skipped_cycles += 1;
+    }
+
+    pub fn set_paused_deployments(&self, paused_deployments: Vec<String>) {
+        self.coordinator().paused_deployments = paused_deployments;
+    }
+
+    pub fn cycle_finished(
+        &self,
+        duration: Duration,
+        outcome: Result<(usize, usize), &anyhow::Error>,
+        queued_batches: BTreeMap<usize, usize>,
+    ) {
+        let mut stats = self.coordinator();
+        stats.cycles += 1;
+        stats.last_cycle_at = Some(Utc::now());
+        stats.last_cycle_duration_ms = Some(duration.as_millis() as u64);
+        stats.queued_batches = queued_batches;
+        match outcome {
+            Ok((fetched, distributed)) => {
+                stats.last_fetched = fetched;
+                stats.last_distributed = distributed;
+            }
+            Err(e) => {
+                stats.failed_cycles += 1;
+                stats.last_error = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    pub fn workers_snapshot(&self) -> Vec<WorkerSnapshot> {
+        self.workers()
+            .iter()
+            .map(|(&worker_id, worker)| WorkerSnapshot {
+                worker_id,
+                current_batch: worker.current_batch.as_ref().map(|batch| batch.snapshot(worker_id)),
+                batches_processed: worker.batches_processed,
+                settlements_processed: worker.settlements_processed,
+                settlements_failed: worker.settlements_failed,
+                last_error: worker.last_error.clone(),
+                last_error_at: worker.last_error_at,
+                last_activity_at: worker.last_activity_at,
+            })
+            .collect()
+    }
+
+    /// Batches currently being processed, oldest first
+    pub fn inflight_batches(&self) -> Vec<InflightBatch> {
+        let mut batches: Vec<InflightBatch> = self
+            .workers()
+            .iter()
+            .filter_map(|(&worker_id, worker)| worker.current_batch.as_ref().map(|batch| batch.snapshot(worker_id)))
+            .collect();
+        batches.sort_by_key(|batch| std::cmp::Reverse(batch.age_ms));
+        batches
+    }
+
+    pub fn coordinator_stats(&self) -> CoordinatorStats {
+        self.coordinator().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(batch_id: &str) -> SettlementBatch {
+        SettlementBatch {
+            batch_id: batch_id.to_string(),
+            settlements: Vec::new(),
+            batch_type: BatchType::Payout,
+            lane: Lane::High,
+            dispatched_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_worker_progress_lifecycle() {
+        let progress = ProgressRegistry::default();
+        progress.register_worker(2);
+        progress.batch_started(1, &batch("b1"));
+        progress.settlement_finished(1, None);
+        progress.settlement_finished(1, Some(&anyhow::anyhow!("rpc timeout")));
+
+        let inflight = progress.inflight_batches();
+        assert_eq!(inflight.len(), 1);
+        assert_eq!(inflight[0].batch_id, "b1");
+        assert_eq!(inflight[0].settled, 2);
+
+        progress.batch_finished(1);
+        assert!(progress.inflight_batches().is_empty());
+
+        let workers = progress.workers_snapshot();
+        assert_eq!(workers.len(), 2);
+        assert_eq!(workers[0].batches_processed, 1);
+        assert_eq!(workers[0].settlements_processed, 1);
+        assert_eq!(workers[0].settlements_failed, 1);
+        assert_eq!(workers[0].last_error.as_deref(), Some("rpc timeout"));
+        assert!(workers[1].current_batch.is_none());
+    }
+}
@@ -0,0 +1,228 @@
+//! Voided-bet refunds
+//!
+//! Operators void a bet that settled as a loss through the backend admin API.
+//! This worker leases those refunds from the backend, runs the vault program's
+//! `refund_bet` for each and reports the result. `refund_bet` rejects a second
+//! refund of the same bet, so a lease that lapses mid-attempt is safe to retry.
+
+use anyhow::{bail, Context, Result};
+use atomiq_client::{BackendApi, ClientError, HttpBackend, Refund, RefundResult, RefundStatus, RetryPolicy, Timeouts};
+use shared::errors::ErrorCode;
+use shared::types::BetId;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+use crate::config::{BackendClientConfig, Config};
+use crate::solana_client::SolanaClientPool;
+use crate::solana_error_mapper::map_solana_error;
+use crate::solana_instructions::build_refund_bet_instruction;
+use crate::solana_pda::{derive_casino_pda, derive_processed_bet_pda, derive_user_vault_pda};
+
+pub struct RefundWorker {
+    backend: Arc<dyn BackendApi>,
+    solana_client: Arc<SolanaClientPool>,
+    processor_keypair: Arc<Keypair>,
+    config: Config,
+    processor_id: String,
+}
+
+impl RefundWorker {
+    pub fn new(
+        backend: Arc<dyn BackendApi>,
+        solana_client: Arc<SolanaClientPool>,
+        processor_keypair: Arc<Keypair>,
+        config: Config,
+    ) -> Self {
+        let processor_id = config.leader_election.instance_id.clone();
+        Self { backend, solana_client, processor_keypair, config, processor_id }
+    }
+
+    pub async fn run(self: Arc<Self>) {
+        let poll_interval = Duration::from_secs(self.config.refund.poll_interval_seconds);
+        info!(poll_interval_seconds = poll_interval.as_secs(), "Refund worker started");
+
+        loop {
+            match self.backend.claim_refunds(self.config.refund.batch_size, &self.processor_id).await {
+                Ok(claimed) => {
+                    for refund in &claimed.refunds {
+                        self.process(refund).await;
+                    }
+                }
+                Err(e) => {
+                    if matches!(e, ClientError::CircuitOpen { .. }) {
+                        metrics::counter!("backend_circuit_open_total", "call" => "claim_refunds").increment(1);
+                    }
+                    warn!(error = %e, "Failed to claim refunds")
+                }
+            }
+            sleep(poll_interval).await;
+        }
+    }
+
+    async fn process(&self, refund: &Refund) {
+        // Unreported, the lease lapses and the refund is claimed again later
+        let deployment = self.config.solana.deployment_for(refund.casino_id.as_deref());
+        if let Err(e) = self.solana_client.ensure_program_supported(deployment).await {
+            warn!(bet_id = %refund.bet_id, error = %e, "Refund deferred for unsupported vault program");
+            return;
+        }
+
+        let result = match self.instructions(refund) {
+            Ok(instructions) => refund_result(&self.processor_id, self.submit(instructions).await),
+            // Nothing a retry could fix: bad addresses or a deployment without the instruction
+            Err(e) => RefundResult {
+                processor_id: self.processor_id.clone(),
+                status: RefundStatus::Failed,
+                solana_tx_id: None,
+                error_message: Some(format!("{:#}", e)),
+            },
+        };
+
+        match result.status {
+            RefundStatus::Completed => info!(bet_id = %refund.bet_id, solana_tx_id = ?result.solana_tx_id, "Refund sent"),
+            RefundStatus::Pending => warn!(bet_id = %refund.bet_id, error = ?result.error_message, "Refund attempt failed; will retry"),
+            RefundStatus::Failed => error!(bet_id = %refund.bet_id, error = ?result.error_message, "Refund failed"),
+        }
+        metrics::counter!("refund_attempts_total", "status" => status_label(result.status)).increment(1);
+
+        // The lease lapses if this report is lost, and the retry is rejected on-chain
+        if let Err(e) = self.backend.report_refund(refund.bet_id, &result).await {
+            warn!(bet_id = %refund.bet_id, error = %e, "Failed to report refund result");
+        }
+    }
+
+    fn instructions(&self, refund: &Refund) -> Result<Vec<Instruction>> {
+        let deployment = self.config.solana.deployment_for(refund.casino_id.as_deref());
+        let Some(discriminator) = deployment.discriminators.refund_bet else {
+            bail!("Vault deployment '{}' has no refund_bet instruction", deployment.name);
+        };
+        let program_id = deployment.program_id;
+
+        let user: Pubkey = refund.user_wallet.parse().context("Invalid user wallet pubkey")?;
+        let (casino, _) = derive_casino_pda(&program_id);
+        let (user_vault, _) = derive_user_vault_pda(&user, &casino, &program_id);
+        let (casino_vault, _) = Pubkey::find_program_address(&[b"casino-vault", casino.as_ref()], &program_id);
+        let (vault_authority, _) = Pubkey::find_program_address(&[b"vault-authority", casino.as_ref()], &program_id);
+        let processed_bet = derive_processed_bet_pda(&BetId::new(refund.bet_id), &program_id);
+
+        // SPL stakes are refunded between the same ATAs settlement used
+        let token_accounts = if refund.stake_token.eq_ignore_ascii_case("SOL") {
+            None
+        } else {
+            let mint: Pubkey = refund.stake_token.parse().context("Invalid stake token mint")?;
+            Some((get_associated_token_address(&user, &mint), get_associated_token_address(&casino, &mint)))
+        };
+
+        Ok(vec![build_refund_bet_instruction(
+            &program_id,
+            &discriminator,
+            &user_vault,
+            &casino,
+            &processed_bet,
+            &casino_vault,
+            &vault_authority,
+            token_accounts.as_ref().map(|(user_ta, casino_ta)| (user_ta, casino_ta)),
+            &self.processor_keypair.pubkey(),
+            BetId::new(refund.bet_id).as_str(),
+        )])
+    }
+
+    async fn submit(&self, instructions: Vec<Instruction>) -> Result<String> {
+        let client = self.solana_client.get_client().await;
+        let payer = self.processor_keypair.clone();
+        let blockhash_commitment = self.solana_client.commitments().blockhash;
+        tokio::task::spawn_blocking(move || {
+            let (blockhash, _) = client
+                .get_latest_blockhash_with_commitment(blockhash_commitment)
+                .context("Failed to fetch blockhash")?;
+            let transaction = Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[&*payer], blockhash);
+            let signature = client
+                .send_and_confirm_transaction(&transaction)
+                .context("Failed to send and confirm refund transaction")?;
+            Ok(signature.to_string())
+        })
+        .await
+        .context("Refund submission task failed")?
+    }
+}
+
+/// Backend client with the configured per-endpoint timeouts, retries and circuit breaker
+pub fn backend_client(config: &BackendClientConfig, base_url: &str) -> Result<HttpBackend> {
+    let http = reqwest::Client::builder()
+        .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
+        .build()
+        .context("Failed to build backend HTTP client")?;
+    let timeouts = Timeouts {
+        claim: Duration::from_millis(config.claim_timeout_ms),
+        update: Duration::from_millis(config.update_timeout_ms),
+        ..Timeouts::default()
+    };
+    let backend = HttpBackend::new(base_url)
+        .with_http_client(http)
+        .with_timeouts(timeouts)
+        .with_retry(RetryPolicy { max_retries: config.max_retries, ..RetryPolicy::default() });
+    Ok(match config.circuit_failure_threshold {
+        0 => backend,
+        threshold => backend.with_circuit_breaker(threshold, Duration::from_secs(config.circuit_open_seconds)),
+    })
+}
+
+/// Result to report for one `refund_bet` attempt
+fn refund_result(processor_id: &str, outcome: Result<String>) -> RefundResult {
+    let (status, solana_tx_id, error_message) = match outcome {
+        Ok(signature) => (RefundStatus::Completed, Some(signature), None),
+        Err(e) => {
+            let failure = map_solana_error(&e);
+            let status = if failure.code() == ErrorCode::CONTRACT_DOUBLE_SPEND.as_str() {
+                // BetAlreadyRefunded: an earlier attempt landed but its report was lost
+                RefundStatus::Completed
+            } else if failure.retryable {
+                RefundStatus::Pending
+            } else {
+                RefundStatus::Failed
+            };
+            (status, None, Some(format!("{}: {:#}", failure.code(), e)))
+        }
+    };
+    RefundResult { processor_id: processor_id.to_string(), status, solana_tx_id, error_message }
+}
+
+fn status_label(status: RefundStatus) -> &'static str {
+    match status {
+        RefundStatus::Pending => "retry",
+        RefundStatus::Completed => "completed",
+        RefundStatus::Failed => "failed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refund_result_from_outcome() {
+        let result = refund_result("processor-1", Ok("5sig".to_string()));
+        assert_eq!(result.status, RefundStatus::Completed);
+        assert_eq!(result.solana_tx_id.as_deref(), Some("5sig"));
+
+        let already = anyhow::anyhow!("Error Code: BetAlreadyRefunded. Error Number: 6035.");
+        assert_eq!(refund_result("processor-1", Err(already)).status, RefundStatus::Completed);
+
+        let timeout = anyhow::anyhow!("RPC request timed out");
+        assert_eq!(refund_result("processor-1", Err(timeout)).status, RefundStatus::Pending);
+
+        let not_refundable = anyhow::anyhow!("custom program error: 0x1792"); // BetNotRefundable
+        let result = refund_result("processor-1", Err(not_refundable));
+        assert_eq!(result.status, RefundStatus::Failed);
+        assert!(result.error_message.unwrap().starts_with("CONTRACT_INVALID_BET"));
+    }
+}
@@ -0,0 +1,264 @@
+//! `processor replay`: manual recovery for failed settlements
+//!
+//! For each settlement the current state is refetched from the blockchain API and
+//! the processed-bet PDA is checked on-chain. Settlements that already landed
+//! on-chain are only reported as complete; the rest are settled again through
+//! the normal worker path. Each action is confirmed interactively unless `--yes`.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::{
+    batch_journal::BatchJournal,
+    blockchain_client::{BlockchainClient, GameSettlementInfo},
+    config::Config,
+    settlement_worker::{settlement_state, SettlementWorker},
+    solana_client::{load_processor_keypair, Commitments, RateLimits, SolanaClientPool},
+    solana_pda::OnChainState,
+};
+
+/// Worker id used for replayed settlements in logs and metrics
+const REPLAY_WORKER_ID: usize = 0;
+
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    /// Batch id from the failed batch journal; replays its failed settlements
+    #[arg(long, conflicts_with = "tx_ids", required_unless_present = "tx_ids")]
+    pub batch: Option<String>,
+    /// Settlement transaction ids (comma separated or repeated)
+    #[arg(long = "tx-id", value_delimiter = ',')]
+    pub tx_ids: Vec<u64>,
+    /// With --batch, replay every settlement in the batch, not just the failed ones
+    #[arg(long, requires = "batch")]
+    pub all: bool,
+    /// Print the plan without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Do not prompt before each action
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayAction {
+    AlreadyComplete,
+    /// Settled on-chain; only the SettlementComplete report is missing
+    Report(String),
+    /// Not settled on-chain; run the settlement again
+    Settle,
+    /// Needs a human; nothing is done automatically
+    Unresolved(String),
+}
+
+pub fn plan_replay(game: &GameSettlementInfo, on_chain: &OnChainState) -> ReplayAction {
+    if game.status.as_deref() == Some("SettlementComplete") {
+        return ReplayAction::AlreadyComplete;
+    }
+    if let Some(signature) = &game.solana_tx_id {
+        return ReplayAction::Report(signature.clone());
+    }
+    match (on_chain.processed, &on_chain.signature) {
+        (true, Some(signature)) => ReplayAction::Report(signature.clone()),
+        (true, None) => ReplayAction::Unresolved(
+            "processed-bet PDA exists but its transaction could not be found".to_string(),
+        ),
+        (false, _) => ReplayAction::Settle,
+    }
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+async fn resolve_tx_ids(config: &Config, args: &ReplayArgs) -> Result<Vec<u64>> {
+    let Some(batch_id) = &args.batch else {
+        return Ok(args.tx_ids.clone());
+    };
+
+    let journal = BatchJournal::connect(
+        &config.batch_journal.redis_url,
+        Duration::from_secs(config.batch_journal.ttl_seconds),
+    )
+    .await?;
+    let record = journal
+        .load(batch_id)
+        .await?
+        .with_context(|| format!("Batch {} not found in journal (expired or never failed)", batch_id))?;
+
+    println!(
+        "Batch {}: {} {} settlements on lane {}, worker {}, recorded {}, {} failed",
+        record.batch_id,
+        record.tx_ids.len(),
+        record.batch_type,
+        record.lane,
+        record.worker_id,
+        record.recorded_at,
+        record.failed_tx_ids.len()
+    );
+    Ok(if args.all { record.tx_ids } else { record.failed_tx_ids })
+}
+
+pub async fn run(config: Config, args: ReplayArgs) -> Result<()> {
+    let tx_ids = resolve_tx_ids(&config, &args).await?;
+    if tx_ids.is_empty() {
+        println!("Nothing to replay");
+        return Ok(());
+    }
+
+    let blockchain_client = Arc::new(BlockchainClient::from_config(&config.blockchain)?);
+    let solana_client = Arc::new(
+        SolanaClientPool::new(
+            config.solana.rpc_urls.clone(),
+            RateLimits::from_config(&config.solana),
+            Commitments::from_config(&config.solana),
+        )
+        .await?
+        .with_program_version_check(Duration::from_secs(config.solana.program_version_check_seconds)),
+    );
+    let processor_keypair = Arc::new(load_processor_keypair(&config.processor.keypair_path)?);
+    let worker = SettlementWorker::new(
+        blockchain_client.clone(),
+        solana_client.clone(),
+        processor_keypair,
+        config.clone(),
+        REPLAY_WORKER_ID,
+    );
+
+    let (mut replayed, mut skipped, mut failed) = (0, 0, 0);
+    for tx_id in tx_ids {
+        let mut game = match blockchain_client.fetch_settlement(tx_id).await {
+            Ok(game) => game,
+            Err(e) => {
+                println!("tx {}: failed to fetch settlement: {:#}", tx_id, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let deployment = config.solana.deployment_for(game.casino_id.as_deref());
+        let client = solana_client.get_client().await;
+        let (pda, on_chain) = match settlement_state(&client, tx_id, &deployment.program_id) {
+            Ok(found) => found,
+            Err(e) => {
+                println!("tx {}: failed to check processed-bet PDA: {:#}", tx_id, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let action = plan_replay(&game, &on_chain);
+        println!(
+            "tx {}: status={} outcome={} payout={} version={} processed_bet={} on_chain={} -> {:?}",
+            tx_id,
+            game.status.as_deref().unwrap_or("unknown"),
+            game.outcome,
+            game.payout,
+            game.version,
+            pda,
+            on_chain.processed,
+            action
+        );
+
+        match &action {
+            ReplayAction::AlreadyComplete | ReplayAction::Unresolved(_) => {
+                skipped += 1;
+                continue;
+            }
+            ReplayAction::Report(_) | ReplayAction::Settle if args.dry_run => {
+                skipped += 1;
+                continue;
+            }
+            ReplayAction::Report(_) | ReplayAction::Settle => {}
+        }
+        if !args.yes && !confirm(&format!("Apply {:?} to tx {}?", action, tx_id))? {
+            skipped += 1;
+            continue;
+        }
+
+        if let ReplayAction::Report(signature) = action {
+            game.solana_tx_id = Some(signature);
+        }
+        match worker.replay_settlement(game).await {
+            Ok(()) => {
+                info!(tx_id, "Settlement replayed");
+                println!("tx {}: done", tx_id);
+                replayed += 1;
+            }
+            Err(e) => {
+                error!(tx_id, error = %e, "Settlement replay failed");
+                println!("tx {}: failed: {:#}", tx_id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("Replay finished: {} replayed, {} skipped, {} failed", replayed, skipped, failed);
+    if failed > 0 {
+        anyhow::bail!("{} settlements could not be replayed", failed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(status: Option<&str>, solana_tx_id: Option<&str>) -> GameSettlementInfo {
+        GameSettlementInfo {
+            transaction_id: 7,
+            player_address: "player".to_string(),
+            game_type: "coinflip".to_string(),
+            bet_amount: 1_000_000,
+            token: "SOL".to_string(),
+            outcome: "Loss".to_string(),
+            payout: 0,
+            vrf_proof: String::new(),
+            vrf_output: String::new(),
+            block_height: 1,
+            version: 3,
+            solana_tx_id: solana_tx_id.map(str::to_string),
+            retry_count: 3,
+            next_retry_after: None,
+            allowance_pda: None,
+            casino_id: None,
+            priority: false,
+            status: status.map(str::to_string),
+            allowance_expires_at: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_replay() {
+        let not_processed = OnChainState::default();
+        let processed = OnChainState { processed: true, signature: Some("sig".to_string()) };
+
+        assert_eq!(
+            plan_replay(&game(Some("SettlementComplete"), Some("sig")), &processed),
+            ReplayAction::AlreadyComplete
+        );
+        assert_eq!(
+            plan_replay(&game(Some("SubmittedToSolana"), Some("known")), &not_processed),
+            ReplayAction::Report("known".to_string())
+        );
+        assert_eq!(
+            plan_replay(&game(Some("SettlementFailedPermanent"), None), &processed),
+            ReplayAction::Report("sig".to_string())
+        );
+        assert_eq!(
+            plan_replay(&game(Some("SettlementFailedPermanent"), None), &not_processed),
+            ReplayAction::Settle
+        );
+        assert!(matches!(
+            plan_replay(&game(None, None), &OnChainState { processed: true, signature: None }),
+            ReplayAction::Unresolved(_)
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+//! Retry-safe transaction submission
+//!
+//! A confirmation timeout does not mean a transaction failed: it can still land
+//! until its blockhash expires, and a freshly built replacement could then
+//! settle the same bet twice. The same signed transaction is re-broadcast until
+//! it confirms or its blockhash expires; only after expiry is the processed-bet
+//! PDA checked and, if the bet is still unsettled, a new transaction built.
+
+use anyhow::{bail, Context, Result};
+use solana_client::{client_error::ClientError, rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::solana_client::Commitments;
+use crate::solana_pda::processed_bet_state;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ResubmitPolicy {
+    /// Pause between status checks and re-broadcasts of the same transaction
+    pub rebroadcast_interval: Duration,
+    /// Fresh blockhashes to try before giving up on the settlement
+    pub max_blockhash_attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Broadcast {
+    Confirmed(Signature),
+    /// The blockhash expired without the transaction landing; it never can now
+    Expired(Signature),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Confirmed,
+    Failed,
+    Expired,
+    Rebroadcast,
+}
+
+/// Next step for a pending transaction given its status and the chain height
+fn next_step(status: Option<&Result<(), TransactionError>>, block_height: u64, last_valid_block_height: u64) -> Step {
+    match status {
+        Some(Ok(())) => Step::Confirmed,
+        Some(Err(_)) => Step::Failed,
+        None if block_height > last_valid_block_height => Step::Expired,
+        None => Step::Rebroadcast,
+    }
+}
+
+/// Send `transaction` and keep re-broadcasting it until it confirms or expires
+fn broadcast_until_expired(
+    client: &RpcClient,
+    transaction: &Transaction,
+    last_valid_block_height: u64,
+    commitments: Commitments,
+    interval: Duration,
+) -> Result<Broadcast> {
+    // Preflight only the first send so program errors surface immediately;
+    // re-broadcasts would fail it with `AlreadyProcessed` once the tx lands
+    let signature = client.send_transaction(transaction)?;
+    let rebroadcast = RpcSendTransactionConfig {
+        skip_preflight: true,
+        max_retries: Some(0),
+        ..Default::default()
+    };
+
+    loop {
+        std::thread::sleep(interval);
+        // Read the height first: a status of None afterwards still means "not landed by then"
+        let block_height = client.get_block_height().context("Failed to fetch block height")?;
+        let status = client
+            .get_signature_status_with_commitment(&signature, commitments.confirmation)
+            .context("Failed to fetch transaction status")?;
+
+        match next_step(status.as_ref(), block_height, last_valid_block_height) {
+            Step::Confirmed => return Ok(Broadcast::Confirmed(signature)),
+            Step::Failed => {
+                let error = status.and_then(|s| s.err()).expect("failed status carries an error");
+                return Err(ClientError::from(error).into());
+            }
+            Step::Expired => return Ok(Broadcast::Expired(signature)),
+            Step::Rebroadcast => {
+                metrics::counter!("transaction_rebroadcasts_total").increment(1);
+                if let Err(e) = client.send_transaction_with_config(transaction, rebroadcast) {
+                    warn!(%signature, error = %e, "Re-broadcast failed");
+                }
+            }
+        }
+    }
+}
+
+fn submit_blocking(
+    client: &RpcClient,
+    payer: &Keypair,
+    instructions: &[Instruction],
+    processed_bet_pda: &Pubkey,
+    commitments: Commitments,
+    policy: ResubmitPolicy,
+) -> Result<String> {
+    for attempt in 1..=policy.max_blockhash_attempts.max(1) {
+        let (blockhash, last_valid_block_height) = client
+            .get_latest_blockhash_with_commitment(commitments.blockhash)
+            .context("Failed to fetch blockhash")?;
+        let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], blockhash);
+
+        let signature = match broadcast_until_expired(client, &transaction, last_valid_block_height, commitments, policy.rebroadcast_interval)? {
+            Broadcast::Confirmed(signature) => return Ok(signature.to_string()),
+            Broadcast::Expired(signature) => signature,
+        };
+        metrics::counter!("transaction_blockhash_expired_total").increment(1);
+
+        // Something else (another attempt, another worker) may have settled the bet
+        let state = processed_bet_state(client, processed_bet_pda)?;
+        if state.processed {
+            return state.signature.with_context(|| {
+                format!("Processed-bet PDA {} exists but its transaction could not be found", processed_bet_pda)
+            });
+        }
+
+        warn!(
+            %signature,
+            attempt,
+            max_attempts = policy.max_blockhash_attempts,
+            "Blockhash expired before confirmation, building a new transaction"
+        );
+    }
+
+    bail!(
+        "Transaction not confirmed within {} blockhashes: block height exceeded",
+        policy.max_blockhash_attempts
+    )
+}
+
+/// Submit `instructions` for the bet behind `processed_bet_pda` and wait for
+/// confirmation. Runs on the blocking pool: confirmation takes seconds.
+pub async fn submit(
+    client: Arc<RpcClient>,
+    payer: Arc<Keypair>,
+    instructions: Vec<Instruction>,
+    processed_bet_pda: Pubkey,
+    commitments: Commitments,
+    policy: ResubmitPolicy,
+) -> Result<String> {
+    tokio::task::spawn_blocking(move || {
+        submit_blocking(&client, &payer, &instructions, &processed_bet_pda, commitments, policy)
+    })
+        .await
+        .context("Transaction submission task failed")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebroadcasts_until_blockhash_expires() {
+        let pending = None;
+        assert_eq!(next_step(pending, 100, 150), Step::Rebroadcast);
+        assert_eq!(next_step(pending, 150, 150), Step::Rebroadcast);
+        assert_eq!(next_step(pending, 151, 150), Step::Expired);
+
+        // A landed transaction wins over expiry
+        assert_eq!(next_step(Some(&Ok(())), 151, 150), Step::Confirmed);
+        assert_eq!(next_step(Some(&Err(TransactionError::AccountInUse)), 100, 150), Step::Failed);
+    }
+}
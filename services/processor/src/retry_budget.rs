@@ -0,0 +1,326 @@
+//! Permanent-failure retry budget
+//!
+//! A failed settlement is classified permanent (`SettlementFailedPermanent`,
+//! manual review) when its error can never succeed or it is out of attempts.
+//! During a systemic outage that classification goes wrong for everything at
+//! once and floods manual review. Each processor keeps a rolling window of its
+//! settlement outcomes; once the permanent share of the window is over the
+//! threshold, it records the trip under `shared::constants::RETRY_BUDGET_TRIPPED_KEY`
+//! and sends a critical alert. While the key is present every processor keeps
+//! failures that would have been permanent as retryable, until an operator
+//! acknowledges the trip with `POST /api/admin/retry-budget/acknowledge`.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde_json::json;
+use shared::api::{BetStatus, RetryBudgetTrip};
+use shared::constants::RETRY_BUDGET_TRIPPED_KEY;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+use crate::blockchain_client::failed_settlement_status;
+use crate::config::RetryBudgetConfig;
+
+const ALERT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// PagerDuty Events v2 endpoint, used when a routing key is configured without a URL
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// Settlement outcomes seen within the window, oldest first
+#[derive(Default)]
+struct Window {
+    /// When each outcome was seen, and whether it was a permanent failure
+    outcomes: VecDeque<(Instant, bool)>,
+    permanent: usize,
+}
+
+impl Window {
+    fn record(&mut self, now: Instant, permanent: bool, window: Duration) {
+        self.outcomes.push_back((now, permanent));
+        self.permanent += permanent as usize;
+        while let Some(&(seen, permanent)) = self.outcomes.front() {
+            if now.duration_since(seen) <= window {
+                break;
+            }
+            self.outcomes.pop_front();
+            self.permanent -= permanent as usize;
+        }
+    }
+
+    fn permanent_percent(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        self.permanent as f64 * 100.0 / self.outcomes.len() as f64
+    }
+
+    fn over_budget(&self, threshold_percent: f64, min_samples: usize) -> bool {
+        self.outcomes.len() >= min_samples.max(1) && self.permanent_percent() > threshold_percent
+    }
+
+    fn clear(&mut self) {
+        self.outcomes.clear();
+        self.permanent = 0;
+    }
+}
+
+/// Alert body: a PagerDuty Events v2 trigger with a routing key, plain JSON otherwise
+fn alert_body(trip: &RetryBudgetTrip, pagerduty_routing_key: Option<&str>) -> serde_json::Value {
+    let summary = format!(
+        "{} of {} settlements failed permanently in {}s (budget {}%); permanent failures held as retryable until acknowledged",
+        trip.permanent_failures, trip.outcomes, trip.window_seconds, trip.threshold_percent
+    );
+    match pagerduty_routing_key {
+        Some(routing_key) => json!({
+            "routing_key": routing_key,
+            "event_action": "trigger",
+            "dedup_key": RETRY_BUDGET_TRIPPED_KEY,
+            "payload": {
+                "summary": summary,
+                "source": trip.instance_id,
+                "severity": "critical",
+                "timestamp": trip.tripped_at,
+                "custom_details": trip,
+            },
+        }),
+        None => json!({
+            "event": "retry_budget.tripped",
+            "severity": "critical",
+            "summary": summary,
+            "trip": trip,
+        }),
+    }
+}
+
+pub struct RetryBudget {
+    redis: ConnectionManager,
+    http: reqwest::Client,
+    config: RetryBudgetConfig,
+    instance_id: String,
+    window: Mutex<Window>,
+    tripped: AtomicBool,
+}
+
+impl RetryBudget {
+    /// `None` when the threshold is zero
+    pub async fn connect(config: &RetryBudgetConfig, instance_id: String) -> Result<Option<Self>> {
+        if config.threshold_percent <= 0.0 {
+            return Ok(None);
+        }
+        let http = reqwest::Client::builder()
+            .timeout(ALERT_TIMEOUT)
+            .build()
+            .context("Failed to build retry budget alert HTTP client")?;
+        let redis = redis::Client::open(config.redis_url.as_str())
+            .context("Invalid Redis URL")?
+            .get_connection_manager()
+            .await
+            .context("Failed to connect to Redis for the retry budget")?;
+        Ok(Some(Self {
+            redis,
+            http,
+            config: config.clone(),
+            instance_id,
+            window: Mutex::new(Window::default()),
+            tripped: AtomicBool::new(false),
+        }))
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.tripped.load(Ordering::SeqCst)
+    }
+
+    /// Count a completed settlement towards the window
+    pub fn record_success(&self) {
+        if !self.is_tripped() {
+            self.record(false);
+        }
+    }
+
+    /// `failed_settlement_status` for a failed settlement, held as retryable
+    /// while the budget is tripped
+    pub async fn classify(&self, retryable: bool, retry_count: u32) -> BetStatus {
+        let status = failed_settlement_status(retryable, retry_count);
+        let permanent = status == BetStatus::FailedManualReview;
+        if !self.is_tripped() {
+            if let Some(trip) = self.record(permanent) {
+                self.trip(trip).await;
+            }
+        }
+        if permanent && self.is_tripped() {
+            metrics::counter!("retry_budget_held_failures_total").increment(1);
+            return BetStatus::FailedRetryable;
+        }
+        status
+    }
+
+    /// Record an outcome, returning the trip if it put the window over budget
+    fn record(&self, permanent: bool) -> Option<RetryBudgetTrip> {
+        let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+        window.record(Instant::now(), permanent, Duration::from_secs(self.config.window_seconds));
+        metrics::gauge!("retry_budget_permanent_percent").set(window.permanent_percent());
+        window
+            .over_budget(self.config.threshold_percent, self.config.min_samples)
+            .then(|| RetryBudgetTrip {
+                tripped_at: Utc::now(),
+                instance_id: self.instance_id.clone(),
+                permanent_failures: window.permanent as u64,
+                outcomes: window.outcomes.len() as u64,
+                window_seconds: self.config.window_seconds,
+                threshold_percent: self.config.threshold_percent,
+            })
+    }
+
+    /// Record the trip for every processor; only the first to record it alerts
+    async fn trip(&self, trip: RetryBudgetTrip) {
+        let payload = serde_json::to_string(&trip).unwrap_or_default();
+        let mut conn = self.redis.clone();
+        let first = match redis::cmd("SET")
+            .arg(RETRY_BUDGET_TRIPPED_KEY)
+            .arg(payload)
+            .arg("NX")
+            .query_async::<Option<String>>(&mut conn)
+            .await
+        {
+            Ok(set) => set.is_some(),
+            Err(e) => {
+                warn!(error = %e, "Failed to record retry budget trip; holding failures on this processor only");
+                true
+            }
+        };
+        self.set_tripped(true);
+        if !first {
+            warn!("Retry budget already tripped by another processor");
+            return;
+        }
+
+        error!(
+            permanent_failures = trip.permanent_failures,
+            outcomes = trip.outcomes,
+            window_seconds = trip.window_seconds,
+            threshold_percent = trip.threshold_percent,
+            "CRITICAL: Permanent settlement failures over retry budget - holding them as retryable until acknowledged"
+        );
+        metrics::counter!("retry_budget_trips_total").increment(1);
+
+        let routing_key = self.config.pagerduty_routing_key.as_deref();
+        let Some(url) = self
+            .config
+            .alert_url
+            .clone()
+            .or_else(|| routing_key.map(|_| PAGERDUTY_EVENTS_URL.to_string()))
+        else {
+            return;
+        };
+        let request = self.http.post(url).json(&alert_body(&trip, routing_key));
+        tokio::spawn(async move {
+            if let Err(e) = request.send().await.and_then(|response| response.error_for_status()) {
+                error!(error = %e, "Failed to send retry budget alert");
+                metrics::counter!("retry_budget_alert_failures_total").increment(1);
+            }
+        });
+    }
+
+    /// Poll loop following trips and acknowledgements; on Redis errors the last known state is kept
+    pub async fn run(self: Arc<Self>) {
+        let poll_interval = Duration::from_millis(self.config.poll_interval_ms);
+        info!(
+            threshold_percent = self.config.threshold_percent,
+            window_seconds = self.config.window_seconds,
+            min_samples = self.config.min_samples,
+            "Retry budget watcher started"
+        );
+
+        loop {
+            let mut conn = self.redis.clone();
+            match conn.exists::<_, bool>(RETRY_BUDGET_TRIPPED_KEY).await {
+                Ok(tripped) => self.set_tripped(tripped),
+                Err(e) => warn!(error = %e, "Retry budget check failed; keeping last known state"),
+            }
+            sleep(poll_interval).await;
+        }
+    }
+
+    fn set_tripped(&self, tripped: bool) {
+        let was_tripped = self.tripped.swap(tripped, Ordering::SeqCst);
+        if was_tripped != tripped {
+            if tripped {
+                warn!("Retry budget tripped - permanent settlement failures held as retryable");
+            } else {
+                // Start over, so outcomes from before the acknowledgement can't trip it again
+                self.window.lock().unwrap_or_else(|e| e.into_inner()).clear();
+                info!("Retry budget acknowledged - permanent failure classification resumed");
+            }
+        }
+        metrics::gauge!("retry_budget_tripped").set(if tripped { 1.0 } else { 0.0 });
+    }
+}
+
+/// `failed_settlement_status`, through `budget` when one is configured
+pub async fn classify_failure(budget: Option<&RetryBudget>, retryable: bool, retry_count: u32) -> BetStatus {
+    match budget {
+        Some(budget) => budget.classify(retryable, retry_count).await,
+        None => failed_settlement_status(retryable, retry_count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_over_budget() {
+        let window_len = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut window = Window::default();
+
+        // Under the minimum sample nothing trips, however bad
+        for _ in 0..4 {
+            window.record(start, true, window_len);
+        }
+        assert!(!window.over_budget(50.0, 10));
+
+        for _ in 0..6 {
+            window.record(start, false, window_len);
+        }
+        assert_eq!(window.permanent_percent(), 40.0);
+        assert!(!window.over_budget(50.0, 10));
+        window.record(start, true, window_len);
+        window.record(start, true, window_len);
+        // Exactly at the threshold is still within budget
+        assert!(!window.over_budget(50.0, 10));
+        window.record(start, true, window_len);
+        assert!(window.over_budget(50.0, 10));
+
+        // Outcomes older than the window drop out
+        window.record(start + Duration::from_secs(61), false, window_len);
+        assert_eq!((window.outcomes.len(), window.permanent), (1, 0));
+        assert!(!window.over_budget(50.0, 1));
+    }
+
+    #[test]
+    fn test_alert_body() {
+        let trip = RetryBudgetTrip {
+            tripped_at: Utc::now(),
+            instance_id: "processor-1".to_string(),
+            permanent_failures: 30,
+            outcomes: 40,
+            window_seconds: 300,
+            threshold_percent: 25.0,
+        };
+        let pagerduty = alert_body(&trip, Some("routing"));
+        assert_eq!(pagerduty["event_action"], "trigger");
+        assert_eq!(pagerduty["payload"]["severity"], "critical");
+        assert_eq!(pagerduty["payload"]["source"], "processor-1");
+
+        let plain = alert_body(&trip, None);
+        assert_eq!(plain["event"], "retry_budget.tripped");
+        assert_eq!(plain["trip"]["permanent_failures"], 30);
+    }
+}
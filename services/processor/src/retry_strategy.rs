@@ -2,10 +2,12 @@ use backoff::{ExponentialBackoff, ExponentialBackoffBuilder};
 use std::time::Duration;
 
 #[derive(Clone)]
+#[allow(dead_code)]
 pub struct RetryStrategy {
     max_retries: u32,
 }
 
+#[allow(dead_code)]
 impl RetryStrategy {
     pub fn new(max_retries: u32) -> Self {
         Self { max_retries }
@@ -0,0 +1,182 @@
+//! Token-bucket rate limiting toward Solana RPC
+//!
+//! Every settlement worker shares the same `SolanaClientPool`, so its clients
+//! are the one place all RPC traffic passes through. Each client sends through
+//! a `RateLimitedSender` that waits for a token from its endpoint's request
+//! bucket and, for `sendTransaction`, from the pool-wide transaction bucket.
+//! Bursts queue up locally instead of turning into 429s and cascading retries.
+//! An endpoint that still answers 429 is marked throttled for a while, and the
+//! pool steers callers to the other endpoints until it cools down.
+
+use async_trait::async_trait;
+use solana_client::client_error::{ClientErrorKind, Result as ClientResult};
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_rpc_client::http_sender::HttpSender;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// How long an endpoint that answered 429 is avoided by the pool
+pub const THROTTLE_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Allows `rate` operations per second with bursts of up to one second's worth
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    /// `None` for a non-positive rate, i.e. unlimited
+    pub fn new(per_second: f64) -> Option<Self> {
+        if per_second <= 0.0 {
+            return None;
+        }
+        let burst = per_second.max(1.0);
+        Some(Self {
+            rate: per_second,
+            burst,
+            state: Mutex::new(BucketState { tokens: burst, updated: Instant::now() }),
+        })
+    }
+
+    /// Take a token, returning how long the caller has to wait before using it.
+    /// Tokens may go negative, so concurrent callers queue in arrival order.
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let refill = now.saturating_duration_since(state.updated).as_secs_f64() * self.rate;
+        state.tokens = (state.tokens + refill).min(self.burst) - 1.0;
+        state.updated = now;
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.rate)
+        }
+    }
+
+    pub async fn acquire(&self, kind: &'static str) {
+        let wait = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            metrics::histogram!("rpc_rate_limit_wait_seconds", "kind" => kind).record(wait.as_secs_f64());
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Request budget and 429 history of one RPC endpoint
+#[derive(Debug)]
+pub struct EndpointLimiter {
+    /// Position in the pool; used as the metric label since URLs may embed API keys
+    label: String,
+    requests: Option<TokenBucket>,
+    throttled_at: Mutex<Option<Instant>>,
+}
+
+impl EndpointLimiter {
+    pub fn new(index: usize, requests_per_second: f64) -> Self {
+        Self {
+            label: index.to_string(),
+            requests: TokenBucket::new(requests_per_second),
+            throttled_at: Mutex::new(None),
+        }
+    }
+
+    /// Metric label of the endpoint
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn record_throttled(&self) {
+        *self.throttled_at.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        metrics::counter!("rpc_throttled_total", "endpoint" => self.label.clone()).increment(1);
+    }
+
+    /// Whether the endpoint answered 429 within the cooldown
+    pub fn is_throttled(&self) -> bool {
+        self.throttled_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|at| at.elapsed() < THROTTLE_COOLDOWN)
+    }
+}
+
+/// `HttpSender` that waits for rate-limit tokens before each call
+pub struct RateLimitedSender {
+    inner: HttpSender,
+    endpoint: Arc<EndpointLimiter>,
+    /// Pool-wide `sendTransaction` budget
+    transactions: Option<Arc<TokenBucket>>,
+}
+
+impl RateLimitedSender {
+    pub fn new(url: &str, endpoint: Arc<EndpointLimiter>, transactions: Option<Arc<TokenBucket>>) -> Self {
+        Self { inner: HttpSender::new(url), endpoint, transactions }
+    }
+}
+
+#[async_trait]
+impl RpcSender for RateLimitedSender {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> ClientResult<serde_json::Value> {
+        if let (RpcRequest::SendTransaction, Some(transactions)) = (request, &self.transactions) {
+            transactions.acquire("transaction").await;
+        }
+        if let Some(requests) = &self.endpoint.requests {
+            requests.acquire("request").await;
+        }
+
+        // HttpSender retries 429s itself; its rate-limited time shows whether it had to
+        let rate_limited_before = self.inner.get_transport_stats().rate_limited_time;
+        let result = self.inner.send(request, params).await;
+        let retried_429 = self.inner.get_transport_stats().rate_limited_time > rate_limited_before;
+        let failed_429 = matches!(
+            result.as_ref().map_err(|e| e.kind()),
+            Err(ClientErrorKind::Reqwest(e)) if e.status().map(|s| s.as_u16()) == Some(429)
+        );
+        if retried_429 || failed_429 {
+            warn!(endpoint = %self.endpoint.label, %request, "RPC endpoint rate limited the processor");
+            self.endpoint.record_throttled();
+        }
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_queues_bursts() {
+        assert!(TokenBucket::new(0.0).is_none());
+
+        let bucket = TokenBucket::new(2.0).unwrap();
+        let start = Instant::now();
+        bucket.state.lock().unwrap().updated = start;
+
+        // One second's worth goes straight through, then callers queue at the rate
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::from_millis(500));
+        assert_eq!(bucket.reserve(start), Duration::from_secs(1));
+
+        // Refills never exceed the burst
+        assert_eq!(bucket.reserve(start + Duration::from_secs(10)), Duration::ZERO);
+        assert_eq!(bucket.reserve(start + Duration::from_secs(10)), Duration::ZERO);
+        assert!(bucket.reserve(start + Duration::from_secs(10)) > Duration::ZERO);
+    }
+}
@@ -0,0 +1,265 @@
+//! Outbound settlement webhooks
+//!
+//! Once the blockchain API has accepted a `SettlementComplete` or
+//! `SettlementFailedPermanent` status, the processor POSTs the outcome to the
+//! configured URL so downstream systems don't have to poll for it. Each body is
+//! signed with HMAC-SHA256 over `{timestamp}.{body}`, sent as
+//! `X-Settlement-Signature: t={timestamp},v1={hex}`.
+//!
+//! The first attempt runs in the background right away. Failed deliveries go to
+//! a Redis retry queue shared by every processor instance and are retried with
+//! exponential backoff until `max_attempts`, then dropped. Receivers should
+//! dedupe on `delivery_id`: a delivery may arrive more than once.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use shared::errors::ServiceError;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::SettlementWebhookConfig;
+
+/// Redis sorted set of deliveries awaiting a retry, scored by when they are due
+const RETRY_QUEUE_KEY: &str = "settlement:webhooks:retry";
+
+/// Due deliveries taken from the retry queue per poll
+const RETRY_BATCH: isize = 50;
+
+const RETRY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest wait between two attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(3600);
+
+pub const SIGNATURE_HEADER: &str = "X-Settlement-Signature";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SettlementEventKind {
+    #[serde(rename = "settlement.completed")]
+    Completed,
+    #[serde(rename = "settlement.failed")]
+    FailedPermanent,
+}
+
+impl SettlementEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettlementEventKind::Completed => "settlement.completed",
+            SettlementEventKind::FailedPermanent => "settlement.failed",
+        }
+    }
+}
+
+/// Body of a webhook delivery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettlementEvent {
+    pub delivery_id: Uuid,
+    pub event: SettlementEventKind,
+    pub transaction_id: u64,
+    pub solana_tx_id: Option<String>,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl SettlementEvent {
+    pub fn completed(transaction_id: u64, solana_tx_id: String) -> Self {
+        Self {
+            delivery_id: Uuid::new_v4(),
+            event: SettlementEventKind::Completed,
+            transaction_id,
+            solana_tx_id: Some(solana_tx_id),
+            error_code: None,
+            error_message: None,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    pub fn failed_permanent(transaction_id: u64, error: &ServiceError) -> Self {
+        Self {
+            delivery_id: Uuid::new_v4(),
+            event: SettlementEventKind::FailedPermanent,
+            transaction_id,
+            solana_tx_id: None,
+            error_code: Some(error.code.clone()),
+            error_message: Some(error.message.clone()),
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+/// Retry queue entry
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingDelivery {
+    event: SettlementEvent,
+    /// Attempts made so far
+    attempts: u32,
+}
+
+/// `X-Settlement-Signature` value for `body` sent at `timestamp` (unix seconds)
+pub fn sign(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Wait after `attempts` failed attempts: 2^attempts seconds, capped
+fn backoff(attempts: u32) -> Duration {
+    Duration::from_secs(1u64 << attempts.min(12)).min(MAX_BACKOFF)
+}
+
+pub struct SettlementWebhook {
+    http: reqwest::Client,
+    url: String,
+    secret: String,
+    max_attempts: u32,
+    redis: ConnectionManager,
+}
+
+impl SettlementWebhook {
+    /// `None` when no webhook URL is configured
+    pub async fn connect(config: &SettlementWebhookConfig) -> Result<Option<Self>> {
+        let Some(url) = config.url.clone() else {
+            return Ok(None);
+        };
+        let Some(secret) = config.secret.clone() else {
+            bail!("SETTLEMENT_WEBHOOK_SECRET must be set with SETTLEMENT_WEBHOOK_URL");
+        };
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .context("Failed to build webhook HTTP client")?;
+        let redis = redis::Client::open(config.redis_url.as_str())
+            .context("Invalid Redis URL")?
+            .get_connection_manager()
+            .await
+            .context("Failed to connect to Redis for the webhook retry queue")?;
+        Ok(Some(Self { http, url, secret, max_attempts: config.max_attempts.max(1), redis }))
+    }
+
+    /// Deliver `event` in the background; a failed first attempt is queued for retry
+    pub fn emit(self: &Arc<Self>, event: SettlementEvent) {
+        let webhook = self.clone();
+        tokio::spawn(async move {
+            webhook.attempt(PendingDelivery { event, attempts: 0 }).await;
+        });
+    }
+
+    async fn send(&self, event: &SettlementEvent) -> Result<()> {
+        let body = serde_json::to_vec(event)?;
+        let signature = sign(self.secret.as_bytes(), Utc::now().timestamp(), &body);
+        self.http
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn attempt(&self, mut delivery: PendingDelivery) {
+        let event = delivery.event.event.as_str();
+        let result = self.send(&delivery.event).await;
+        delivery.attempts += 1;
+        let outcome = match result {
+            Ok(()) => "delivered",
+            Err(e) if delivery.attempts >= self.max_attempts => {
+                tracing::error!(
+                    tx_id = delivery.event.transaction_id,
+                    delivery_id = %delivery.event.delivery_id,
+                    attempts = delivery.attempts,
+                    error = %e,
+                    "Settlement webhook dropped after its last attempt"
+                );
+                "dropped"
+            }
+            Err(e) => {
+                tracing::warn!(
+                    tx_id = delivery.event.transaction_id,
+                    attempts = delivery.attempts,
+                    error = %e,
+                    "Settlement webhook failed; queued for retry"
+                );
+                if let Err(e) = self.enqueue(&delivery).await {
+                    tracing::error!(
+                        tx_id = delivery.event.transaction_id,
+                        error = %e,
+                        "Failed to queue settlement webhook retry; delivery lost"
+                    );
+                }
+                "retrying"
+            }
+        };
+        metrics::counter!("settlement_webhooks_total", "event" => event, "outcome" => outcome).increment(1);
+    }
+
+    async fn enqueue(&self, delivery: &PendingDelivery) -> Result<()> {
+        let due = Utc::now().timestamp_millis() + backoff(delivery.attempts).as_millis() as i64;
+        let payload = serde_json::to_string(delivery)?;
+        let _: () = self.redis.clone().zadd(RETRY_QUEUE_KEY, payload, due).await?;
+        Ok(())
+    }
+
+    /// Retry due deliveries forever
+    pub async fn run_retries(self: Arc<Self>) {
+        tracing::info!(max_attempts = self.max_attempts, "Settlement webhook retries started");
+        loop {
+            tokio::time::sleep(RETRY_POLL_INTERVAL).await;
+            if let Err(e) = self.retry_due().await {
+                tracing::warn!(error = %e, "Failed to read the settlement webhook retry queue");
+            }
+        }
+    }
+
+    async fn retry_due(&self) -> Result<()> {
+        let mut redis = self.redis.clone();
+        let now = Utc::now().timestamp_millis();
+        let due: Vec<String> = redis.zrangebyscore_limit(RETRY_QUEUE_KEY, "-inf", now, 0, RETRY_BATCH).await?;
+        for payload in due {
+            // Whoever removes the entry owns the retry, so instances never send it twice
+            let claimed: i64 = redis.zrem(RETRY_QUEUE_KEY, &payload).await?;
+            if claimed == 0 {
+                continue;
+            }
+            match serde_json::from_str::<PendingDelivery>(&payload) {
+                Ok(delivery) => self.attempt(delivery).await,
+                Err(e) => tracing::error!(error = %e, "Dropping unreadable settlement webhook retry"),
+            }
+        }
+        let queued: u64 = redis.zcard(RETRY_QUEUE_KEY).await?;
+        metrics::gauge!("settlement_webhook_retry_queue").set(queued as f64);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_and_backoff() {
+        let signature = sign(b"secret", 1_700_000_000, br#"{"transaction_id":1}"#);
+        assert!(signature.starts_with("t=1700000000,v1="));
+        assert_eq!(signature.len(), "t=1700000000,v1=".len() + 64);
+        // Any change to the body or timestamp changes the signature
+        assert_ne!(signature, sign(b"secret", 1_700_000_000, br#"{"transaction_id":2}"#));
+        assert_ne!(signature, sign(b"secret", 1_700_000_001, br#"{"transaction_id":1}"#));
+
+        assert_eq!(backoff(1), Duration::from_secs(2));
+        assert_eq!(backoff(5), Duration::from_secs(32));
+        assert_eq!(backoff(30), MAX_BACKOFF);
+
+        let event = SettlementEvent::completed(7, "5sig".to_string());
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "settlement.completed");
+    }
+}
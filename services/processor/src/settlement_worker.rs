@@ -2,18 +2,40 @@
 
 use crate::{
     blockchain_client::{BlockchainClient, GameSettlementInfo},
+    compute_budget::with_compute_unit_price,
     config::Config,
-    coordinator::{SettlementBatch, BatchType},
+    coordinator::{BatchType, SettlementBatch, WorkerInbox},
+    deployments::VaultDeployment,
+    domain::BetStatus,
+    drain::DrainMode,
+    batch_journal::{BatchJournal, BatchRecord},
+    settlement_webhook::{SettlementEvent, SettlementWebhook},
+    kill_switch::KillSwitch,
+    progress::ProgressRegistry,
+    resubmit::{self, ResubmitPolicy},
+    retry_budget::{classify_failure, RetryBudget},
     solana_client::SolanaClientPool,
-    solana_tx,
+    solana_error_mapper::map_solana_error,
+    solana_pda::{derive_processed_bet_pda, processed_bet_state, OnChainState},
+    telemetry::{self, SettlementLabels, POLLED_BATCH_TYPE},
+    vault_init::{vault_init_instruction, UserVault},
+    write_locks::WriteLockScheduler,
 };
 use anyhow::{Context, Result};
-use solana_sdk::signature::{Keypair, Signer};
+use futures::stream::{self, Stream, StreamExt};
+use shared::clock::{Clock, SystemClock};
+use shared::types::BetId;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 pub struct SettlementWorker {
     blockchain_client: Arc<BlockchainClient>,
@@ -21,7 +43,16 @@ pub struct SettlementWorker {
     processor_keypair: Arc<Keypair>,
     config: Config,
     worker_id: usize,
-    work_receiver: Option<mpsc::Receiver<SettlementBatch>>,
+    work_receiver: Option<WorkerInbox>,
+    kill_switch: Option<Arc<KillSwitch>>,
+    progress: Arc<ProgressRegistry>,
+    journal: Option<Arc<BatchJournal>>,
+    drain: Option<Arc<DrainMode>>,
+    webhook: Option<Arc<SettlementWebhook>>,
+    retry_budget: Option<Arc<RetryBudget>>,
+    write_locks: Option<Arc<WriteLockScheduler>>,
+    /// Paces polling and retries, and stamps retry times
+    clock: Arc<dyn Clock>,
 }
 
 impl SettlementWorker {
@@ -39,6 +70,14 @@ impl SettlementWorker {
             config,
             worker_id,
             work_receiver: None,
+            kill_switch: None,
+            progress: Arc::default(),
+            journal: None,
+            drain: None,
+            webhook: None,
+            retry_budget: None,
+            write_locks: None,
+            clock: SystemClock::shared(),
         }
     }
 
@@ -48,7 +87,7 @@ impl SettlementWorker {
         processor_keypair: Arc<Keypair>,
         config: Config,
         worker_id: usize,
-        work_receiver: mpsc::Receiver<SettlementBatch>,
+        work_receiver: WorkerInbox,
     ) -> Self {
         Self {
             blockchain_client,
@@ -57,9 +96,70 @@ impl SettlementWorker {
             config,
             worker_id,
             work_receiver: Some(work_receiver),
+            kill_switch: None,
+            progress: Arc::default(),
+            journal: None,
+            drain: None,
+            webhook: None,
+            retry_budget: None,
+            write_locks: None,
+            clock: SystemClock::shared(),
         }
     }
 
+    /// Stop fetching new settlements while the kill switch is engaged (legacy mode)
+    pub fn with_kill_switch(mut self, kill_switch: Option<Arc<KillSwitch>>) -> Self {
+        self.kill_switch = kill_switch;
+        self
+    }
+
+    /// Report batch and settlement progress to the admin API
+    pub fn with_progress(mut self, progress: Arc<ProgressRegistry>) -> Self {
+        progress.register_worker(self.worker_id);
+        self.progress = progress;
+        self
+    }
+
+    /// Record batches with failed settlements for `processor replay --batch`
+    pub fn with_journal(mut self, journal: Option<Arc<BatchJournal>>) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    /// Raise batch parallelism and add a priority fee while the coordinator drains a backlog
+    pub fn with_drain_mode(mut self, drain: Option<Arc<DrainMode>>) -> Self {
+        self.drain = drain;
+        self
+    }
+
+    /// Push completed and permanently failed settlements to the settlement webhook
+    pub fn with_webhook(mut self, webhook: Option<Arc<SettlementWebhook>>) -> Self {
+        self.webhook = webhook;
+        self
+    }
+
+    /// Hold permanent failures as retryable while the retry budget is tripped
+    pub fn with_retry_budget(mut self, retry_budget: Option<Arc<RetryBudget>>) -> Self {
+        self.retry_budget = retry_budget;
+        self
+    }
+
+    /// Stagger payouts on the same casino vault and spends on the same allowance
+    pub fn with_write_locks(mut self, write_locks: Option<Arc<WriteLockScheduler>>) -> Self {
+        self.write_locks = write_locks;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Run a single settlement through the normal path, outside any batch (used by replay)
+    pub async fn replay_settlement(&self, game: GameSettlementInfo) -> Result<()> {
+        self.process_settlement(game).await
+    }
+
     pub async fn run(mut self) {
         if self.config.processor.coordinator_enabled {
             // New coordinator-based mode
@@ -77,27 +177,50 @@ impl SettlementWorker {
             "Settlement worker starting (coordinator mode)"
         );
 
-        let Some(mut receiver) = self.work_receiver.take() else {
+        let Some(mut inbox) = self.work_receiver.take() else {
             error!(worker_id = self.worker_id, "Worker started in coordinator mode but has no channel");
             return;
         };
 
-        while let Some(batch) = receiver.recv().await {
+        loop {
+            // High-priority lane is always drained first
+            let batch = tokio::select! {
+                biased;
+                Some(batch) = inbox.high.recv() => batch,
+                Some(batch) = inbox.normal.recv() => batch,
+                else => break,
+            };
+
+            let lane = batch.lane.as_str();
+            let batch_type = batch.batch_type.as_str();
+            let dispatched_at = batch.dispatched_at;
+            metrics::histogram!("settlement_lane_queue_wait_seconds", "lane" => lane)
+                .record(dispatched_at.elapsed().as_secs_f64());
+
             info!(
                 worker_id = self.worker_id,
                 batch_id = %batch.batch_id,
                 batch_type = ?batch.batch_type,
+                lane,
                 settlement_count = batch.settlements.len(),
                 "Received batch from coordinator"
             );
 
-            if let Err(e) = self.process_settlement_batch(batch).await {
+            self.progress.batch_started(self.worker_id, &batch);
+            let result = self.process_settlement_batch(batch).await;
+            self.progress.batch_finished(self.worker_id);
+            let success = result.is_ok();
+            if let Err(e) = result {
                 error!(
                     worker_id = self.worker_id,
                     error = %e,
                     "Batch processing failed"
                 );
             }
+
+            let completion = dispatched_at.elapsed().as_secs_f64();
+            metrics::histogram!("settlement_lane_latency_seconds", "lane" => lane).record(completion);
+            telemetry::record_batch_completion(self.worker_id, batch_type, lane, success, completion);
         }
 
         warn!(worker_id = self.worker_id, "Coordinator channel closed, worker shutting down");
@@ -116,6 +239,12 @@ impl SettlementWorker {
         );
 
         loop {
+            if self.kill_switch.as_ref().is_some_and(|ks| ks.is_engaged()) {
+                debug!(worker_id = self.worker_id, "Kill switch engaged - skipping cycle");
+                self.clock.sleep(poll_interval).await;
+                continue;
+            }
+
             info!(worker_id = self.worker_id, "Starting settlement batch processing cycle");
             
             if let Err(e) = self.process_batch().await {
@@ -123,17 +252,31 @@ impl SettlementWorker {
             }
 
             info!(worker_id = self.worker_id, "Completed batch processing, sleeping for {} seconds", poll_interval.as_secs());
-            sleep(poll_interval).await;
+            self.clock.sleep(poll_interval).await;
         }
     }
 
     /// Process a batch received from coordinator
     async fn process_settlement_batch(&self, batch: SettlementBatch) -> Result<()> {
         let start_time = std::time::Instant::now();
+        let mut failed_tx_ids = Vec::new();
 
-        // Process each settlement in the batch
-        for game in batch.settlements {
-            if let Err(e) = self.process_settlement(game).await {
+        // Settlements are independent transactions; confirm up to `parallelism` at once
+        let parallelism = self.config.processor.limits_for(batch.batch_type).max_parallel_transactions.max(1);
+        let parallelism = self.drain.as_ref().map_or(parallelism, |drain| drain.batch_parallelism(parallelism));
+        let batch_type = batch.batch_type.as_str();
+        let mut results = settle_concurrently(&batch.settlements, parallelism, |game| async move {
+            let labels = SettlementLabels::new(&game, self.worker_id, batch_type);
+            let started = std::time::Instant::now();
+            let result = self.process_settlement(game).await;
+            telemetry::record_settlement(&labels, result.is_ok(), started.elapsed());
+            result
+        });
+
+        while let Some((tx_id, result)) = results.next().await {
+            self.progress.settlement_finished(self.worker_id, result.as_ref().err());
+            if let Err(e) = result {
+                failed_tx_ids.push(tx_id);
                 error!(
                     worker_id = self.worker_id,
                     batch_id = %batch.batch_id,
@@ -143,14 +286,24 @@ impl SettlementWorker {
             }
         }
 
+        drop(results);
+
         let duration = start_time.elapsed();
         info!(
             worker_id = self.worker_id,
             batch_id = %batch.batch_id,
+            parallelism,
             duration_ms = duration.as_millis(),
             "Batch processing completed"
         );
 
+        if let (Some(journal), false) = (&self.journal, failed_tx_ids.is_empty()) {
+            let record = BatchRecord::new(&batch, self.worker_id, failed_tx_ids);
+            if let Err(e) = journal.record(&record).await {
+                warn!(worker_id = self.worker_id, batch_id = %batch.batch_id, error = %e, "Failed to journal batch");
+            }
+        }
+
         Ok(())
     }
 
@@ -180,7 +333,12 @@ impl SettlementWorker {
 
         // Process each settlement
         for game in games {
-            if let Err(e) = self.process_settlement(game).await {
+            let labels = SettlementLabels::new(&game, self.worker_id, POLLED_BATCH_TYPE);
+            let started = std::time::Instant::now();
+            let result = self.process_settlement(game).await;
+            telemetry::record_settlement(&labels, result.is_ok(), started.elapsed());
+            self.progress.settlement_finished(self.worker_id, result.as_ref().err());
+            if let Err(e) = result {
                 // Log error but continue with other settlements
                 error!(worker_id = self.worker_id, error = %e, "Settlement processing failed");
             }
@@ -220,11 +378,16 @@ impl SettlementWorker {
             ).await;
         }
 
+        // Leave the settlement untouched while its deployment runs a newer program
+        let deployment = self.config.solana.deployment_for(game.casino_id.as_deref());
+        self.solana_client.ensure_program_supported(deployment).await?;
+
         // Update status to SubmittedToSolana
         match self.blockchain_client
             .update_settlement_status(
                 tx_id,
-                "SubmittedToSolana",
+                &BetStatus::Batched,
+                &BetStatus::SubmittedToSolana,
                 None,
                 None,
                 game.version,
@@ -258,59 +421,68 @@ impl SettlementWorker {
         let solana_tx_sig = match self.settle_on_solana(&game).await {
             Ok(sig) => sig,
             Err(e) => {
-                let error_msg = format!("Solana settlement failed: {}", e);
+                let failure = map_solana_error(&e);
+                failure.record_if_lost_to_expiry(1);
                 warn!(
                     worker_id = self.worker_id,
                     tx_id,
                     error = %e,
+                    error_code = failure.code(),
+                    retryable = failure.retryable,
                     "Solana settlement failed, updating status to SettlementFailed"
                 );
                 
                 // Calculate retry logic: max 3 retries with 5s, 10s, 15s backoff
                 let new_retry_count = game.retry_count + 1;
-                let (status, next_retry_after) = if new_retry_count >= 3 {
-                    // Exceeded max retries - mark as permanent failure
-                    ("SettlementFailedPermanent", None)
+                let status =
+                    classify_failure(self.retry_budget.as_deref(), failure.retryable, new_retry_count).await;
+                let next_retry_after = if status == BetStatus::FailedManualReview {
+                    // Terminal contract error or exceeded max retries - no further attempts
+                    None
                 } else {
                     // Calculate backoff: 5s, 10s, 15s
                     let backoff_seconds = (new_retry_count as i64) * 5;
-                    let now_ms = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis() as i64;
-                    let retry_after = now_ms + (backoff_seconds * 1000);
-                    ("SettlementFailed", Some(retry_after))
+                    Some(self.clock.now().timestamp_millis() + (backoff_seconds * 1000))
                 };
                 
                 info!(
                     worker_id = self.worker_id,
                     tx_id,
                     retry_count = new_retry_count,
-                    status = status,
+                    status = ?status,
                     next_retry_after,
                     "Updating settlement status with retry logic"
                 );
                 
                 // Update status to SettlementFailed or SettlementFailedPermanent
-                if let Err(update_err) = self.blockchain_client
+                match self.blockchain_client
                     .update_settlement_status(
                         tx_id,
-                        status,
+                        &BetStatus::SubmittedToSolana,
+                        &status,
                         None,
-                        Some(error_msg),
+                        Some(failure.error.clone()),
                         game.version + 1,
                         Some(new_retry_count),
                         next_retry_after,
                     )
                     .await
                 {
-                    error!(
-                        worker_id = self.worker_id,
-                        tx_id,
-                        solana_error = %e,
-                        update_error = %update_err,
-                        "Failed to update settlement status to SettlementFailed"
-                    );
+                    Ok(_) if status == BetStatus::FailedManualReview => {
+                        if let Some(webhook) = &self.webhook {
+                            webhook.emit(SettlementEvent::failed_permanent(tx_id, &failure.error));
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(update_err) => {
+                        error!(
+                            worker_id = self.worker_id,
+                            tx_id,
+                            solana_error = %e,
+                            update_error = %update_err,
+                            "Failed to update settlement status to SettlementFailed"
+                        );
+                    }
                 }
                 
                 return Err(e);
@@ -359,7 +531,8 @@ impl SettlementWorker {
             match self.blockchain_client
                 .update_settlement_status(
                     tx_id,
-                    "SettlementComplete",
+                    &BetStatus::SubmittedToSolana,
+                    &BetStatus::Completed,
                     Some(solana_tx_sig.clone()),
                     None,
                     expected_version,
@@ -369,6 +542,12 @@ impl SettlementWorker {
                 .await
             {
                 Ok(_) => {
+                    if let Some(webhook) = &self.webhook {
+                        webhook.emit(SettlementEvent::completed(tx_id, solana_tx_sig.clone()));
+                    }
+                    if let Some(retry_budget) = &self.retry_budget {
+                        retry_budget.record_success();
+                    }
                     if retry_count > 0 {
                         info!(
                             worker_id = self.worker_id,
@@ -414,7 +593,7 @@ impl SettlementWorker {
                         "CRITICAL: Failed to update SettlementComplete, will retry indefinitely"
                     );
                     
-                    sleep(Duration::from_secs(backoff_seconds)).await;
+                    self.clock.sleep(Duration::from_secs(backoff_seconds)).await;
                     
                     // Exponential backoff capped at 60 seconds
                     backoff_seconds = (backoff_seconds * 2).min(60);
@@ -423,30 +602,96 @@ impl SettlementWorker {
         }
     }
 
+    /// Prepend the priority fee of `batch_type`: the base or, while draining, the drain fee if higher
+    fn with_priority_fee(&self, batch_type: BatchType, instructions: Vec<Instruction>) -> Vec<Instruction> {
+        let drain_fee = self.drain.as_ref().map_or(0, |drain| drain.priority_fee());
+        let base = self.config.processor.priority_fee_micro_lamports.max(drain_fee);
+        with_compute_unit_price(self.config.processor.limits_for(batch_type).priority_fee(base), instructions)
+    }
+
+    /// Hold the submission until its turn on the account it contends for
+    async fn wait_write_turn(&self, account: &Pubkey, batch_type: BatchType) {
+        if let Some(write_locks) = &self.write_locks {
+            write_locks.wait_turn(std::slice::from_ref(account), batch_type).await;
+        }
+    }
+
+    /// The user's vault: `initialize_vault_for` to put ahead of a settlement for a
+    /// user without one, and its payout address
+    fn user_vault(&self, client: &RpcClient, deployment: &VaultDeployment, user: &Pubkey) -> Result<UserVault> {
+        vault_init_instruction(
+            client,
+            self.solana_client.commitments().read,
+            deployment,
+            user,
+            &self.processor_keypair.pubkey(),
+            self.config.processor.auto_init_vaults,
+        )
+    }
+
+    fn resubmit_policy(&self) -> ResubmitPolicy {
+        ResubmitPolicy {
+            rebroadcast_interval: Duration::from_millis(self.config.processor.rebroadcast_interval_ms),
+            max_blockhash_attempts: self.config.processor.max_blockhash_attempts,
+        }
+    }
+
     async fn settle_on_solana(&self, game: &GameSettlementInfo) -> Result<String> {
-        let bet_id = format!("bet-{}", game.transaction_id);
+        let bet_id = settlement_bet_id(game.transaction_id);
+        let deployment = self.config.solana.deployment_for(game.casino_id.as_deref());
+
+        // A settlement that landed but whose status update failed is only reported,
+        // including one from before full-length ids that landed under the legacy seed
+        let client = self.solana_client.get_client().await;
+        let (pda, on_chain) = settlement_state(&client, game.transaction_id, &deployment.program_id)?;
+        if on_chain.processed {
+            let signature = on_chain
+                .signature
+                .with_context(|| format!("Processed-bet PDA {} exists but its transaction could not be found", pda))?;
+            info!(
+                worker_id = self.worker_id,
+                tx_id = game.transaction_id,
+                %pda,
+                solana_tx = %signature,
+                "Settlement already landed on-chain, marking as complete"
+            );
+            return Ok(signature);
+        }
         
         // Determine if win or loss
         let is_win = game.outcome == "Win";
 
-        if is_win {
+        let result = if is_win {
             // Win: payout from casino vault
-            self.process_payout(game, &bet_id).await
+            self.process_payout(game, &bet_id, deployment).await
         } else {
             // Loss: spend from user's allowance
-            self.process_spend(game, &bet_id).await
-        }
+            self.process_spend(game, &bet_id, deployment).await
+        };
+
+        metrics::counter!(
+            "settlement_solana_submissions_total",
+            "deployment" => deployment.name.clone(),
+            "result" => if result.is_ok() { "success" } else { "failure" }
+        )
+        .increment(1);
+
+        result
     }
 
-    async fn process_payout(&self, game: &GameSettlementInfo, bet_id: &str) -> Result<String> {
-        use solana_sdk::{transaction::Transaction, system_program};
-        use crate::solana_pda::{derive_casino_pda, derive_user_vault_pda};
+    async fn process_payout(
+        &self,
+        game: &GameSettlementInfo,
+        bet_id: &BetId,
+        deployment: &VaultDeployment,
+    ) -> Result<String> {
+        use crate::solana_pda::{derive_casino_pda, derive_payout_record_pda, derive_user_vault_pda};
         use crate::solana_instructions::build_payout_instruction;
         
         // Parse addresses
         let player_pubkey = game.player_address.parse()
             .context("Invalid player address")?;
-        let vault_program_id = self.config.solana.vault_program_id.parse()?;
+        let vault_program_id = deployment.program_id;
 
         // Derive PDAs
         let (casino_pda, _) = derive_casino_pda(&vault_program_id);
@@ -460,49 +705,59 @@ impl SettlementWorker {
             &vault_program_id,
         );
 
-        // Derive PDA for processed bet
-        let (processed_bet_pda, _) = solana_sdk::pubkey::Pubkey::find_program_address(
-            &[b"processed-bet", bet_id.as_bytes()],
-            &vault_program_id,
-        );
+        let client = self.solana_client.get_client().await;
+        let user_vault = self.user_vault(&client, deployment, &player_pubkey)?;
+        let mut instructions: Vec<_> = user_vault.init.into_iter().collect();
+
+        // Created by the payout, so a second payout for this bet fails
+        let payout_record = derive_payout_record_pda(bet_id, &vault_program_id);
 
         // Build payout instruction
         let payout_ix = build_payout_instruction(
             &vault_program_id,
+            &deployment.discriminators.payout,
             &casino_pda,
             &casino_vault,
             &vault_authority,
             &user_vault_pda,
-            &processed_bet_pda,
+            &payout_record,
             &self.processor_keypair.pubkey(),
+            // Game settlements are native SOL: the payout address is paid directly
+            user_vault.payout_address.as_ref(),
+            &derive_processed_bet_pda(bet_id, &vault_program_id),
             game.payout,
-            bet_id,
-        );
-
-        // Get recent blockhash and send
-        let client = self.solana_client.get_client().await;
-        let recent_blockhash = client.get_latest_blockhash()?;
-        
-        let transaction = Transaction::new_signed_with_payer(
-            &[payout_ix],
-            Some(&self.processor_keypair.pubkey()),
-            &[&*self.processor_keypair],
-            recent_blockhash,
+            bet_id.as_str(),
         );
+        instructions.push(payout_ix);
 
-        let signature = client.send_and_confirm_transaction(&transaction)?;
-        Ok(signature.to_string())
+        self.wait_write_turn(&casino_vault, BatchType::Payout).await;
+        resubmit::submit(
+            client,
+            self.processor_keypair.clone(),
+            self.with_priority_fee(BatchType::Payout, instructions),
+            payout_record,
+            self.solana_client.commitments(),
+            self.resubmit_policy(),
+        )
+        .await
     }
 
-    async fn process_spend(&self, game: &GameSettlementInfo, bet_id: &str) -> Result<String> {
-        use solana_sdk::transaction::Transaction;
-        use crate::solana_pda::{derive_casino_pda, derive_user_vault_pda, derive_latest_allowance_pda_from_nonce_registry};
+    async fn process_spend(
+        &self,
+        game: &GameSettlementInfo,
+        bet_id: &BetId,
+        deployment: &VaultDeployment,
+    ) -> Result<String> {
+        use crate::solana_pda::{
+            derive_casino_pda, derive_latest_allowance_pda_from_nonce_registry, derive_user_vault_pda,
+            token_config_account,
+        };
         use crate::solana_instructions::build_spend_from_allowance_instruction;
         
         // Parse addresses
         let player_pubkey = game.player_address.parse()
             .context("Invalid player address")?;
-        let vault_program_id = self.config.solana.vault_program_id.parse()?;
+        let vault_program_id = deployment.program_id;
 
         // Derive PDAs
         let (casino_pda, _) = derive_casino_pda(&vault_program_id);
@@ -519,23 +774,35 @@ impl SettlementWorker {
         // Get client for allowance lookup
         let client = self.solana_client.get_client().await;
         
+        let mut instructions: Vec<_> = self.user_vault(&client, deployment, &player_pubkey)?.init.into_iter().collect();
+
         // Derive allowance PDA
         let allowance = derive_latest_allowance_pda_from_nonce_registry(
-            &*client,
+            &client,
             &vault_program_id,
             &player_pubkey,
             &casino_pda,
+            self.solana_client.commitments().read,
         ).context("Failed to derive allowance PDA")?;
 
         // Derive PDA for processed bet
-        let (processed_bet_pda, _) = solana_sdk::pubkey::Pubkey::find_program_address(
-            &[b"processed-bet", bet_id.as_bytes()],
+        let processed_bet_pda = derive_processed_bet_pda(bet_id, &vault_program_id);
+
+        // Game settlements are native SOL; its bet limits as the program version takes them
+        let program_version = self.solana_client.ensure_program_supported(deployment).await?;
+        let token_config = token_config_account(
+            &client,
             &vault_program_id,
-        );
+            &casino_pda,
+            &solana_sdk::system_program::ID,
+            self.solana_client.commitments().read,
+            program_version,
+        )?;
 
         // Build spend instruction
         let spend_ix = build_spend_from_allowance_instruction(
             &vault_program_id,
+            &deployment.discriminators.spend_from_allowance,
             &user_vault_pda,
             &casino_pda,
             &allowance,
@@ -545,21 +812,216 @@ impl SettlementWorker {
             None, // user_token_account
             None, // casino_token_account
             &self.processor_keypair.pubkey(),
+            token_config.as_ref(),
             game.bet_amount,
-            bet_id,
+            bet_id.as_str(),
         );
 
-        // Get recent blockhash and send
-        let recent_blockhash = client.get_latest_blockhash()?;
-        
-        let transaction = Transaction::new_signed_with_payer(
-            &[spend_ix],
-            Some(&self.processor_keypair.pubkey()),
-            &[&*self.processor_keypair],
-            recent_blockhash,
-        );
+        instructions.push(spend_ix);
+
+        self.wait_write_turn(&allowance, BatchType::Spend).await;
+        resubmit::submit(
+            client,
+            self.processor_keypair.clone(),
+            self.with_priority_fee(BatchType::Spend, instructions),
+            processed_bet_pda,
+            self.solana_client.commitments(),
+            self.resubmit_policy(),
+        )
+        .await
+    }
+}
+
+/// Run `settle` over a batch with at most `parallelism` settlements in flight,
+/// yielding each tx id with its result as it completes
+fn settle_concurrently<'a, T, F, Fut>(
+    settlements: &'a [GameSettlementInfo],
+    parallelism: usize,
+    settle: F,
+) -> impl Stream<Item = (u64, Result<T>)> + 'a
+where
+    F: Fn(GameSettlementInfo) -> Fut + 'a,
+    Fut: Future<Output = Result<T>> + 'a,
+{
+    stream::iter(settlements.iter().cloned())
+        .map(move |game| {
+            let tx_id = game.transaction_id;
+            let settlement = settle(game);
+            async move { (tx_id, settlement.await) }
+        })
+        .buffer_unordered(parallelism.max(1))
+}
+
+/// On-chain bet id used for a settlement's processed-bet PDA: the tx id as a
+/// full-length UUID, as the vault program requires 32 hex characters
+pub fn settlement_bet_id(tx_id: u64) -> BetId {
+    BetId::new(Uuid::from_u128(tx_id as u128))
+}
 
-        let signature = client.send_and_confirm_transaction(&transaction)?;
-        Ok(signature.to_string())
+/// Bet id settlements used before ids were full-length; only for checking old PDAs
+pub fn legacy_settlement_bet_id(tx_id: u64) -> String {
+    format!("bet-{}", tx_id)
+}
+
+/// Processed-bet PDA of a settlement and its on-chain state. Settlements from
+/// before full-length bet ids used `bet-{tx_id}` seeds; either PDA means settled.
+pub fn settlement_state(client: &RpcClient, tx_id: u64, program_id: &Pubkey) -> Result<(Pubkey, OnChainState)> {
+    find_settlement_state(tx_id, program_id, |pda| processed_bet_state(client, pda))
+}
+
+fn find_settlement_state(
+    tx_id: u64,
+    program_id: &Pubkey,
+    state_of: impl Fn(&Pubkey) -> Result<OnChainState>,
+) -> Result<(Pubkey, OnChainState)> {
+    let pda = derive_processed_bet_pda(&settlement_bet_id(tx_id), program_id);
+    let state = state_of(&pda)?;
+    if state.processed {
+        return Ok((pda, state));
+    }
+
+    let (legacy_pda, _) =
+        Pubkey::find_program_address(&[b"processed-bet", legacy_settlement_bet_id(tx_id).as_bytes()], program_id);
+    let legacy_state = state_of(&legacy_pda)?;
+    Ok(if legacy_state.processed { (legacy_pda, legacy_state) } else { (pda, state) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana_client::Commitments;
+    use solana_sdk::commitment_config::CommitmentConfig;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn settlement(tx_id: u64) -> GameSettlementInfo {
+        GameSettlementInfo {
+            transaction_id: tx_id,
+            player_address: "player".to_string(),
+            game_type: "coinflip".to_string(),
+            bet_amount: 1_000_000,
+            token: "SOL".to_string(),
+            outcome: "Win".to_string(),
+            payout: 2_000_000,
+            vrf_proof: String::new(),
+            vrf_output: String::new(),
+            block_height: 1,
+            version: 1,
+            solana_tx_id: None,
+            retry_count: 0,
+            next_retry_after: None,
+            allowance_pda: None,
+            casino_id: None,
+            priority: false,
+            status: None,
+            allowance_expires_at: None,
+        }
+    }
+
+    /// Submit through the real resubmit path against a mock RPC that confirms
+    /// the transaction or fails it with an instruction error
+    async fn submit_to_mock(confirms: bool, interval: Duration) -> Result<String> {
+        let url = if confirms { "succeeds" } else { "instruction_error" };
+        let commitment = CommitmentConfig::confirmed();
+        resubmit::submit(
+            Arc::new(RpcClient::new_mock(url.to_string())),
+            Arc::new(Keypair::new()),
+            Vec::new(),
+            Pubkey::new_unique(),
+            Commitments { read: commitment, blockhash: commitment, confirmation: commitment },
+            ResubmitPolicy { rebroadcast_interval: interval, max_blockhash_attempts: 1 },
+        )
+        .await
+    }
+
+    #[test]
+    fn test_settlement_state_checks_the_legacy_pda() {
+        let program_id = Pubkey::new_unique();
+        let pda = derive_processed_bet_pda(&settlement_bet_id(7), &program_id);
+        let (legacy_pda, _) =
+            Pubkey::find_program_address(&[b"processed-bet", legacy_settlement_bet_id(7).as_bytes()], &program_id);
+        let landed_at = |landed: Pubkey| {
+            move |candidate: &Pubkey| {
+                Ok(if *candidate == landed {
+                    OnChainState { processed: true, signature: Some("sig".to_string()) }
+                } else {
+                    OnChainState::default()
+                })
+            }
+        };
+
+        // Landed before the upgrade: found under the legacy seed
+        let (found, state) = find_settlement_state(7, &program_id, landed_at(legacy_pda)).unwrap();
+        assert_eq!((found, state.processed), (legacy_pda, true));
+        let (found, state) = find_settlement_state(7, &program_id, landed_at(pda)).unwrap();
+        assert_eq!((found, state.signature.as_deref()), (pda, Some("sig")));
+
+        // Neither: the current PDA is the one to settle under
+        let (found, state) = find_settlement_state(7, &program_id, landed_at(Pubkey::new_unique())).unwrap();
+        assert_eq!((found, state.processed), (pda, false));
+        // An RPC error is not taken as "not settled"
+        assert!(find_settlement_state(7, &program_id, |_| anyhow::bail!("rpc down")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mixed_results_are_all_reported() {
+        let settlements: Vec<_> = (1..=8).map(settlement).collect();
+        let results: Vec<_> = settle_concurrently(&settlements, 3, |game| {
+            // The first settlement is the slowest; the rest must not wait on it
+            let interval = Duration::from_millis(if game.transaction_id == 1 { 200 } else { 5 });
+            submit_to_mock(game.transaction_id % 3 != 0, interval)
+        })
+        .collect()
+        .await;
+
+        assert_eq!(results.len(), settlements.len());
+        let reported: HashSet<u64> = results.iter().map(|(tx_id, _)| *tx_id).collect();
+        assert_eq!(reported, (1..=8).collect());
+        let failed: HashSet<u64> = results.iter().filter(|(_, r)| r.is_err()).map(|(tx_id, _)| *tx_id).collect();
+        assert_eq!(failed, HashSet::from([3, 6]));
+        assert!(results.iter().filter_map(|(_, r)| r.as_ref().ok()).all(|signature| !signature.is_empty()));
+        // Results arrive as settlements complete, not in batch order
+        assert_eq!(results.last().map(|(tx_id, _)| *tx_id), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_limit_is_respected() {
+        let settlements: Vec<_> = (1..=10).map(settlement).collect();
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let results: Vec<_> = settle_concurrently(&settlements, 3, |game| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                let result = submit_to_mock(game.transaction_id % 2 == 0, Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                result
+            }
+        })
+        .collect()
+        .await;
+
+        assert_eq!(results.len(), 10);
+        assert_eq!(results.iter().filter(|(_, r)| r.is_err()).count(), 5);
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+
+        // A parallelism of 0 still settles, one at a time
+        peak.store(0, Ordering::SeqCst);
+        let results: Vec<_> = settle_concurrently(&settlements[..3], 0, |_| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                let result = submit_to_mock(true, Duration::from_millis(5)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                result
+            }
+        })
+        .collect()
+        .await;
+        assert!(results.iter().all(|(_, r)| r.is_ok()));
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
     }
 }
@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::{signature::Signature, transaction::Transaction};
+use std::sync::Arc;
+
+/// Most secondary endpoints a settlement transaction is shadow-broadcast to
+pub const MAX_SHADOW_ENDPOINTS: usize = 2;
+
+/// Pool endpoints chosen to send one settlement transaction: the primary
+/// sends and confirms it, the shadows only broadcast it. A transaction lands
+/// at most once whichever endpoint's copy reaches the leader first, so the
+/// extra copies need no deduplication of our own.
+pub struct ShadowBroadcast {
+    /// Pool label of the primary endpoint
+    primary: String,
+    /// Pool label and client of each shadow endpoint
+    shadows: Vec<(String, Arc<RpcClient>)>,
+}
+
+impl ShadowBroadcast {
+    pub fn new(primary: String, shadows: Vec<(String, Arc<RpcClient>)>) -> Self {
+        Self { primary, shadows }
+    }
+
+    /// Send through `client` (the primary) and confirm, broadcasting the same
+    /// signed transaction to the shadows without waiting on them.
+    ///
+    /// When the primary gives up but a shadow's copy landed, the transaction
+    /// counts as confirmed.
+    pub async fn send_and_confirm(&self, client: &RpcClient, transaction: &Transaction) -> Result<Signature> {
+        for (label, shadow) in &self.shadows {
+            broadcast(label.clone(), shadow.clone(), transaction.clone());
+        }
+
+        let error = match client.send_and_confirm_transaction(transaction) {
+            Ok(signature) => {
+                record_landed(&self.primary, "primary");
+                return Ok(signature);
+            }
+            Err(e) => e,
+        };
+
+        let signature = transaction.signatures[0];
+        for (label, shadow) in &self.shadows {
+            if let Ok(Some(Ok(()))) = shadow.get_signature_status(&signature) {
+                tracing::warn!(
+                    %signature,
+                    endpoint = %label,
+                    error = %error,
+                    "Primary RPC failed to confirm a settlement transaction that landed through a shadow broadcast"
+                );
+                record_landed(label, "shadow");
+                return Ok(signature);
+            }
+        }
+        Err(error).context("Failed to send and confirm transaction")
+    }
+}
+
+/// Fire-and-forget send without preflight; the primary already simulated it
+fn broadcast(label: String, client: Arc<RpcClient>, transaction: Transaction) {
+    tokio::task::spawn_blocking(move || {
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            ..RpcSendTransactionConfig::default()
+        };
+        let outcome = match client.send_transaction_with_config(&transaction, config) {
+            Ok(_) => "sent",
+            Err(e) => {
+                tracing::debug!(endpoint = %label, error = %e, "Shadow broadcast failed");
+                "error"
+            }
+        };
+        metrics::counter!("settlement_shadow_broadcasts_total", "endpoint" => label, "outcome" => outcome).increment(1);
+    });
+}
+
+/// Count a confirmed settlement transaction against the endpoint that confirmed it
+fn record_landed(endpoint: &str, role: &'static str) {
+    metrics::counter!("settlement_transactions_landed_total", "endpoint" => endpoint.to_string(), "role" => role)
+        .increment(1);
+}
+
+/// Up to `count` shadow endpoints for `primary`: the available endpoints
+/// following it in pool order
+pub fn pick_shadows(primary: usize, available: &[bool], count: usize) -> Vec<usize> {
+    let len = available.len();
+    (1..len)
+        .map(|offset| (primary + offset) % len)
+        .filter(|&index| available[index])
+        .take(count.min(MAX_SHADOW_ENDPOINTS))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_shadows_skips_primary_and_unavailable_endpoints() {
+        let available = [true, false, true, true];
+        assert_eq!(pick_shadows(0, &available, 1), vec![2]);
+        assert_eq!(pick_shadows(2, &available, 2), vec![3, 0]);
+        // Never more than the cap, however many are configured
+        assert_eq!(pick_shadows(0, &[true; 5], 4), vec![1, 2]);
+        assert!(pick_shadows(0, &available, 0).is_empty());
+        assert!(pick_shadows(0, &[true], 2).is_empty());
+    }
+}
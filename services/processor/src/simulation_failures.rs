@@ -0,0 +1,128 @@
+//! Fingerprints of failed batch simulations
+//!
+//! A batch whose preflight simulation fails is usually failing for one bet,
+//! and that bet fails the same way on every retry until something changes on
+//! chain (an uninitialized token account, an exhausted allowance). Each failure
+//! is fingerprinted by the program error and the wallet of the bet whose
+//! instruction failed; that wallet's vault, allowance and token accounts all
+//! derive from it. Bets of a fingerprinted wallet are left out of batches
+//! until the fingerprint expires, instead of spending RPC quota on a simulation
+//! known to fail.
+
+use solana_sdk::{instruction::InstructionError, pubkey::Pubkey, transaction::TransactionError};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// What a failed simulation blamed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FailureFingerprint {
+    /// Anchor error name (with the account it named, if any) or the runtime error
+    pub error: String,
+    /// Wallet of the bet whose instruction failed
+    pub account: Pubkey,
+}
+
+/// Program error of a simulation failure, and the index of the failing instruction
+///
+/// `None` for failures no single instruction caused (fees, blockhash) and for
+/// an exhausted compute budget, which is the batch's size rather than a bet.
+pub fn classify(err: &TransactionError, logs: &[String]) -> Option<(usize, String)> {
+    let TransactionError::InstructionError(index, instruction_error) = err else {
+        return None;
+    };
+    if *instruction_error == InstructionError::ComputationalBudgetExceeded {
+        return None;
+    }
+    let error = anchor_error(logs).unwrap_or_else(|| match instruction_error {
+        InstructionError::Custom(code) => format!("Custom({})", code),
+        other => format!("{:?}", other),
+    });
+    Some((*index as usize, error))
+}
+
+/// `Name` or `Name(account)` from an Anchor error log line
+fn anchor_error(logs: &[String]) -> Option<String> {
+    let line = logs.iter().find(|line| line.contains("AnchorError"))?;
+    let code = line.split("Error Code: ").nth(1)?.split('.').next()?.trim();
+    let account = line
+        .split("caused by account: ")
+        .nth(1)
+        .and_then(|rest| rest.split('.').next())
+        .map(str::trim);
+    Some(match account {
+        Some(account) => format!("{}({})", code, account),
+        None => code.to_string(),
+    })
+}
+
+/// Recent fingerprints per wallet, each dropped after `ttl`
+#[derive(Debug)]
+pub struct SimulationFailureCache {
+    ttl: Duration,
+    fingerprints: Mutex<HashMap<Pubkey, (FailureFingerprint, Instant)>>,
+}
+
+impl SimulationFailureCache {
+    /// A zero `ttl` records nothing, so no bet is ever skipped
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, fingerprints: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn record(&self, fingerprint: FailureFingerprint) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        tracing::warn!(
+            account = %fingerprint.account,
+            error = %fingerprint.error,
+            ttl_seconds = self.ttl.as_secs(),
+            "Simulation failure fingerprinted; skipping the wallet's bets until it expires"
+        );
+        metrics::counter!("simulation_failure_fingerprints_total", "error" => fingerprint.error.clone()).increment(1);
+        let mut fingerprints = self.fingerprints.lock().unwrap_or_else(|e| e.into_inner());
+        fingerprints.insert(fingerprint.account, (fingerprint, Instant::now()));
+        metrics::gauge!("simulation_failure_fingerprints_active").set(fingerprints.len() as f64);
+    }
+
+    /// The unexpired fingerprint matching a bet of `account`, if any
+    pub fn matching(&self, account: &Pubkey) -> Option<FailureFingerprint> {
+        let mut fingerprints = self.fingerprints.lock().unwrap_or_else(|e| e.into_inner());
+        fingerprints.retain(|_, (_, recorded)| recorded.elapsed() < self.ttl);
+        metrics::gauge!("simulation_failure_fingerprints_active").set(fingerprints.len() as f64);
+        let fingerprint = fingerprints.get(account).map(|(fingerprint, _)| fingerprint.clone())?;
+        metrics::counter!("simulation_fingerprint_skips_total", "error" => fingerprint.error.clone()).increment(1);
+        Some(fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprints_name_the_failing_instruction() {
+        let logs = vec![
+            "Program log: Instruction: SettleBet".to_string(),
+            "Program log: AnchorError caused by account: user_token_account. Error Code: AccountNotInitialized. \
+             Error Number: 3012. Error Message: The program expected this account to be already initialized."
+                .to_string(),
+        ];
+        let err = TransactionError::InstructionError(2, InstructionError::Custom(3012));
+        assert_eq!(classify(&err, &logs), Some((2, "AccountNotInitialized(user_token_account)".to_string())));
+        assert_eq!(classify(&err, &[]), Some((2, "Custom(3012)".to_string())));
+        assert_eq!(classify(&TransactionError::BlockhashNotFound, &logs), None);
+        let budget = TransactionError::InstructionError(1, InstructionError::ComputationalBudgetExceeded);
+        assert_eq!(classify(&budget, &[]), None);
+
+        let account = Pubkey::new_unique();
+        let cache = SimulationFailureCache::new(Duration::from_secs(60));
+        cache.record(FailureFingerprint { error: "Custom(6003)".to_string(), account });
+        assert_eq!(cache.matching(&account).map(|f| f.error), Some("Custom(6003)".to_string()));
+        assert!(cache.matching(&Pubkey::new_unique()).is_none());
+
+        let disabled = SimulationFailureCache::new(Duration::ZERO);
+        disabled.record(FailureFingerprint { error: "Custom(6003)".to_string(), account });
+        assert!(disabled.matching(&account).is_none());
+    }
+}
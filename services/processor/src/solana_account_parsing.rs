@@ -1,6 +1,6 @@
 //! Account data parsing utilities for Solana accounts
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use solana_sdk::pubkey::Pubkey;
 
 /// Parse the next_nonce from allowance nonce registry account data
@@ -1,17 +1,80 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use shared::secrets::read_keypair;
 use solana_client::rpc_client::RpcClient;
+use solana_rpc_client::rpc_client::RpcClientConfig;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
-    signature::{Keypair, read_keypair_file},
+    signature::Keypair,
 };
-use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::time::{Duration, Instant};
 
+use crate::config::SolanaConfig;
+use crate::deployments::VaultDeployment;
+use crate::program_version::ProgramVersionGuard;
+use crate::rpc_rate_limit::{EndpointLimiter, RateLimitedSender, TokenBucket};
+use crate::shadow_broadcast::{pick_shadows, ShadowBroadcast};
+
+/// Commitment level for each kind of RPC call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Commitments {
+    /// Account reads while building transactions
+    pub read: CommitmentConfig,
+    /// Blockhash a transaction is signed with
+    pub blockhash: CommitmentConfig,
+    /// Preflight, confirmation and settled-state checks; the clients' default
+    pub confirmation: CommitmentConfig,
+}
+
+impl Commitments {
+    pub fn from_config(config: &SolanaConfig) -> Self {
+        Self {
+            read: parse_commitment(&config.read_commitment),
+            blockhash: parse_commitment(&config.blockhash_commitment),
+            confirmation: parse_commitment(&config.confirmation_commitment),
+        }
+    }
+}
+
+/// Unknown levels fall back to `confirmed`
+pub fn parse_commitment(level: &str) -> CommitmentConfig {
+    match level {
+        "processed" => CommitmentConfig::processed(),
+        "confirmed" => CommitmentConfig::confirmed(),
+        "finalized" => CommitmentConfig::finalized(),
+        _ => CommitmentConfig::confirmed(),
+    }
+}
+
+/// Per-second budgets for the pool's RPC traffic; 0 is unlimited
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimits {
+    /// Requests to each endpoint, aligned with the pool's URLs; missing entries are unlimited
+    pub requests_per_second: Vec<f64>,
+    /// `sendTransaction` calls across all endpoints
+    pub transactions_per_second: f64,
+}
+
+impl RateLimits {
+    pub fn from_config(config: &SolanaConfig) -> Self {
+        Self {
+            requests_per_second: config.rpc_requests_per_second.clone(),
+            transactions_per_second: config.transactions_per_second,
+        }
+    }
+}
+
+/// Program version check interval for pools not configured otherwise
+const DEFAULT_PROGRAM_VERSION_CHECK: Duration = Duration::from_secs(30);
+
 pub struct SolanaClientPool {
     clients: Vec<HealthCheckedClient>,
     current_index: Arc<RwLock<usize>>,
+    commitments: Commitments,
+    program_versions: ProgramVersionGuard,
+    /// Secondary endpoints each settlement transaction is also broadcast to
+    shadow_count: usize,
 }
 
 struct HealthCheckedClient {
@@ -19,48 +82,90 @@ struct HealthCheckedClient {
     url: String,
     last_health_check: Arc<RwLock<Instant>>,
     is_healthy: Arc<RwLock<bool>>,
+    limiter: Arc<EndpointLimiter>,
 }
 
-impl SolanaClientPool {
-    pub async fn new(rpc_urls: Vec<String>, commitment: String) -> Result<Self> {
-        let commitment_config = match commitment.as_str() {
-            "processed" => CommitmentConfig::processed(),
-            "confirmed" => CommitmentConfig::confirmed(),
-            "finalized" => CommitmentConfig::finalized(),
-            _ => CommitmentConfig::confirmed(),
-        };
+impl HealthCheckedClient {
+    /// Healthy and not recently rate limited
+    async fn is_available(&self) -> bool {
+        *self.is_healthy.read().await && !self.limiter.is_throttled()
+    }
+}
 
+impl SolanaClientPool {
+    pub async fn new(rpc_urls: Vec<String>, rate_limits: RateLimits, commitments: Commitments) -> Result<Self> {
+        // One transaction budget for the whole pool, so failing over doesn't double it
+        let transactions = TokenBucket::new(rate_limits.transactions_per_second).map(Arc::new);
         let mut clients = Vec::new();
-        for url in rpc_urls {
-            let client = RpcClient::new_with_commitment(url.clone(), commitment_config);
+        for (index, url) in rpc_urls.into_iter().enumerate() {
+            let requests_per_second = rate_limits.requests_per_second.get(index).copied().unwrap_or(0.0);
+            let limiter = Arc::new(EndpointLimiter::new(index, requests_per_second));
+            let sender = RateLimitedSender::new(&url, limiter.clone(), transactions.clone());
+            let client = RpcClient::new_sender(sender, RpcClientConfig::with_commitment(commitments.confirmation));
             clients.push(HealthCheckedClient {
                 client: Arc::new(client),
-                url: url.clone(),
+                url,
                 last_health_check: Arc::new(RwLock::new(Instant::now())),
                 is_healthy: Arc::new(RwLock::new(true)),
+                limiter,
             });
         }
 
         Ok(Self {
             clients,
             current_index: Arc::new(RwLock::new(0)),
+            commitments,
+            program_versions: ProgramVersionGuard::new(DEFAULT_PROGRAM_VERSION_CHECK),
+            shadow_count: 0,
         })
     }
 
+    /// Broadcast settlement transactions to up to `count` secondary endpoints
+    /// besides the one sending them; 0 disables shadow broadcasts
+    pub fn with_shadow_broadcast(mut self, count: usize) -> Self {
+        self.shadow_count = count;
+        self
+    }
+
+    /// How long a deployment's program version is trusted before it is read again
+    pub fn with_program_version_check(mut self, interval: Duration) -> Self {
+        self.program_versions = ProgramVersionGuard::new(interval);
+        self
+    }
+
+    /// Commitment levels calls through this pool's clients should use
+    pub fn commitments(&self) -> Commitments {
+        self.commitments
+    }
+
+    /// Error unless `deployment` runs a vault program version this build supports
+    pub async fn ensure_program_supported(&self, deployment: &VaultDeployment) -> Result<u32> {
+        let client = self.get_client().await;
+        self.program_versions.ensure_supported(client, deployment).await
+    }
+
     pub async fn get_client(&self) -> Arc<RpcClient> {
         let mut index = self.current_index.write().await;
-        let client = &self.clients[*index];
-        
-        // Round-robin to next client
-        *index = (*index + 1) % self.clients.len();
-        
-        client.client.clone()
+        let start = *index;
+
+        // Round-robin, skipping endpoints that recently answered 429 unless all have
+        let mut chosen = start;
+        for offset in 0..self.clients.len() {
+            let candidate = (start + offset) % self.clients.len();
+            if !self.clients[candidate].limiter.is_throttled() {
+                chosen = candidate;
+                break;
+            }
+        }
+        *index = (chosen + 1) % self.clients.len();
+
+        self.clients[chosen].client.clone()
     }
 
+    /// First endpoint that passed its last health check and isn't being rate limited
     pub async fn get_healthy_client(&self) -> Option<Arc<RpcClient>> {
         for client in &self.clients {
-            let is_healthy = *client.is_healthy.read().await;
-            if is_healthy {
+            if client.is_available().await {
                 return Some(client.client.clone());
             }
         }
@@ -86,6 +191,22 @@ impl SolanaClientPool {
         Some(self.get_client().await)
     }
 
+    /// Endpoints to send a settlement transaction through, with `primary` sending it
+    pub async fn shadow_broadcast(&self, primary: &Arc<RpcClient>) -> ShadowBroadcast {
+        let position = self.clients.iter().position(|c| Arc::ptr_eq(&c.client, primary)).unwrap_or(0);
+        let mut available = Vec::with_capacity(self.clients.len());
+        for client in &self.clients {
+            available.push(client.is_available().await);
+        }
+        let shadows = pick_shadows(position, &available, self.shadow_count)
+            .into_iter()
+            .map(|index| (self.clients[index].limiter.label().to_string(), self.clients[index].client.clone()))
+            .collect();
+        let primary = self.clients.get(position).map(|c| c.limiter.label().to_string()).unwrap_or_default();
+        ShadowBroadcast::new(primary, shadows)
+    }
+
+    #[allow(dead_code)]
     pub async fn mark_unhealthy(&self, client_url: &str) {
         for client in &self.clients {
             if client.url == client_url {
@@ -109,7 +230,7 @@ impl SolanaClientPool {
                 let url_clone = client.url.clone();
                 let is_healthy_clone = client.is_healthy.clone();
                 
-                tokio::task::spawn_blocking(move || {
+                let healthy = tokio::task::spawn_blocking(move || {
                     match client_clone.get_health() {
                         Ok(_) => {
                             tracing::debug!("RPC {} is healthy", url_clone);
@@ -120,20 +241,17 @@ impl SolanaClientPool {
                             false
                         }
                     }
-                }).await.ok().map(|healthy| {
-                    let is_healthy_clone = is_healthy_clone.clone();
-                    tokio::spawn(async move {
-                        let mut is_healthy = is_healthy_clone.write().await;
-                        *is_healthy = healthy;
-                    });
-                });
+                }).await.ok();
+
+                if let Some(healthy) = healthy {
+                    *is_healthy_clone.write().await = healthy;
+                }
             }
         }
     }
 }
 
-pub fn load_processor_keypair(path: &str) -> Result<Keypair> {
-    let keypair = read_keypair_file(Path::new(path))
-        .map_err(|e| anyhow::anyhow!("Failed to load processor keypair: {}", e))?;
-    Ok(keypair)
+/// Load the processor keypair from a path or, once secrets are resolved, the keypair's JSON bytes
+pub fn load_processor_keypair(keypair: &str) -> Result<Keypair> {
+    read_keypair(keypair).context("Failed to load processor keypair")
 }
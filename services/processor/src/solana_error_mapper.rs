@@ -0,0 +1,215 @@
+//! Map Solana/Anchor failures onto the shared `ErrorCode` taxonomy
+//!
+//! RPC errors reach us as strings such as `custom program error: 0x1772` or, from
+//! Anchor logs, `Error Code: AllowanceExpired. Error Number: 6002`. The decoded code
+//! decides whether a settlement is worth retrying or should go straight to manual review.
+
+use shared::errors::{ErrorCategory, ErrorCode, ServiceError};
+
+/// Anchor numbers `#[error_code]` variants from 6000
+const ANCHOR_ERROR_OFFSET: u32 = 6000;
+
+/// A Solana failure decoded into the shared taxonomy
+#[derive(Debug, Clone)]
+pub struct SolanaFailure {
+    pub error: ServiceError,
+    /// Whether resubmitting the same settlement can succeed
+    pub retryable: bool,
+}
+
+impl SolanaFailure {
+    pub fn code(&self) -> &str {
+        &self.error.code
+    }
+
+    /// Count a settlement that failed for good because its allowance expired first
+    pub fn record_if_lost_to_expiry(&self, settlements: usize) {
+        if self.code() == ErrorCode::CONTRACT_ALLOWANCE_EXPIRED.as_str() {
+            metrics::counter!("settlements_lost_to_allowance_expiry_total").increment(settlements as u64);
+        }
+    }
+}
+
+/// Decode an error returned while settling on Solana
+pub fn map_solana_error(error: &anyhow::Error) -> SolanaFailure {
+    let raw = format!("{:#}", error);
+    let (category, code, retryable) = classify(&raw);
+
+    metrics::counter!(
+        "solana_errors_total",
+        "code" => code.as_str(),
+        "retryable" => if retryable { "true" } else { "false" }
+    )
+    .increment(1);
+
+    SolanaFailure {
+        error: ServiceError::new(category, code, "Solana settlement failed").with_context(raw),
+        retryable,
+    }
+}
+
+fn classify(message: &str) -> (ErrorCategory, ErrorCode, bool) {
+    if let Some(code) = program_error_code(message) {
+        if let Some((code, retryable)) = vault_error(code) {
+            return (ErrorCategory::Contract, code, retryable);
+        }
+        if let Some((code, retryable)) = anchor_framework_error(code) {
+            return (ErrorCategory::Contract, code, retryable);
+        }
+    }
+
+    let lower = message.to_lowercase();
+    if lower.contains("blockhash not found") || lower.contains("block height exceeded") {
+        (ErrorCategory::Network, ErrorCode::NETWORK_BLOCKHASH_EXPIRED, true)
+    } else if lower.contains("timed out") || lower.contains("timeout") {
+        (ErrorCategory::Network, ErrorCode::NETWORK_RPC_TIMEOUT, true)
+    } else if lower.contains("connection") || lower.contains("503") || lower.contains("429") {
+        (ErrorCategory::Network, ErrorCode::NETWORK_RPC_UNAVAILABLE, true)
+    } else if lower.contains("insufficient funds for rent") {
+        (ErrorCategory::Contract, ErrorCode::CONTRACT_INSUFFICIENT_RENT, false)
+    } else if lower.contains("missing required signature") {
+        (ErrorCategory::Contract, ErrorCode::CONTRACT_UNAUTHORIZED_SIGNER, false)
+    } else if lower.contains("accountnotfound") || lower.contains("account not found") {
+        (ErrorCategory::Contract, ErrorCode::CONTRACT_ACCOUNT_NOT_FOUND, false)
+    } else {
+        // Unknown failures keep the old behaviour: retry until the retry budget runs out
+        (ErrorCategory::Contract, ErrorCode::CONTRACT_EXECUTION_FAILED, true)
+    }
+}
+
+/// Extract the program error number from an RPC or Anchor log message
+fn program_error_code(message: &str) -> Option<u32> {
+    if let Some(idx) = message.find("custom program error: 0x") {
+        let hex: String = message[idx + "custom program error: 0x".len()..]
+            .chars()
+            .take_while(|c| c.is_ascii_hexdigit())
+            .collect();
+        return u32::from_str_radix(&hex, 16).ok();
+    }
+
+    if let Some(idx) = message.find("Error Number: ") {
+        let digits: String = message[idx + "Error Number: ".len()..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        return digits.parse().ok();
+    }
+
+    if let Some(idx) = message.find("Custom(") {
+        let digits: String = message[idx + "Custom(".len()..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        return digits.parse().ok();
+    }
+
+    None
+}
+
+/// `VaultError` variants, in declaration order (see contracts/programs/vault/src/errors.rs)
+fn vault_error(code: u32) -> Option<(ErrorCode, bool)> {
+    let mapped = match code.checked_sub(ANCHOR_ERROR_OFFSET)? {
+        0 => (ErrorCode::CONTRACT_INSUFFICIENT_BALANCE, true), // InsufficientBalance (vault may be topped up)
+        1 => (ErrorCode::CONTRACT_INVALID_BET, false),          // InvalidBetAmount
+        2 => (ErrorCode::CONTRACT_ALLOWANCE_EXPIRED, false),    // AllowanceExpired
+        3 => (ErrorCode::CONTRACT_ALLOWANCE_REVOKED, false),    // AllowanceRevoked
+        4 => (ErrorCode::CONTRACT_INSUFFICIENT_ALLOWANCE, false), // InsufficientAllowance
+        5 | 6 => (ErrorCode::CONTRACT_EXECUTION_FAILED, false), // AllowanceDurationTooLong, AllowanceAmountTooHigh
+        7 => (ErrorCode::CONTRACT_RATE_LIMITED, true),          // RateLimitExceeded
+        8..=11 => (ErrorCode::CONTRACT_INVALID_TOKEN_ACCOUNT, false), // token account owner/mint/frozen/uninitialized
+        12 | 13 => (ErrorCode::CONTRACT_ARITHMETIC, false),     // ArithmeticOverflow, ArithmeticUnderflow
+        14 | 15 => (ErrorCode::CONTRACT_UNAUTHORIZED_SIGNER, false), // UnauthorizedProcessor, UnauthorizedAuthority
+        16 => (ErrorCode::CONTRACT_CASINO_PAUSED, true),        // CasinoPaused
+        17 | 18 | 22 => (ErrorCode::CONTRACT_INVALID_PDA, false), // InvalidVaultPDA, InvalidCasinoVaultPDA, InvalidAllowancePDA
+        19 => (ErrorCode::CONTRACT_DOUBLE_SPEND, false),        // DuplicateBetId
+        20 => (ErrorCode::CONTRACT_INVALID_BET, false),         // InvalidBetId
+        21 => (ErrorCode::CONTRACT_INVALID_TOKEN_ACCOUNT, false), // TokenMintMismatch
+        23..=25 => (ErrorCode::CONTRACT_INVALID_TOKEN_ACCOUNT, false), // missing delegation/account/program
+        26 => (ErrorCode::CONTRACT_INSUFFICIENT_ALLOWANCE, false), // InvalidAllowanceNonce
+        27..=31 => (ErrorCode::CONTRACT_EXECUTION_FAILED, false), // authority and processor-set admin errors
+        32 => (ErrorCode::CONTRACT_INVALID_BET, false),         // SpendExceedsSingleLimit
+        33 => (ErrorCode::CONTRACT_RATE_LIMITED, true),         // SpendVelocityExceeded (window resets hourly)
+        34 => (ErrorCode::CONTRACT_INVALID_BET, false),         // BetNotRefundable
+        35 => (ErrorCode::CONTRACT_DOUBLE_SPEND, false),        // BetAlreadyRefunded
+        36 => (ErrorCode::CONTRACT_EXECUTION_FAILED, false),    // InvalidTokenConfig
+        37 | 38 => (ErrorCode::CONTRACT_INVALID_BET, false),    // TokenBetsDisabled, TokenNotConfigured
+        39 => (ErrorCode::CONTRACT_EXECUTION_FAILED, false),    // ProgramVersionDowngrade
+        40 => (ErrorCode::CONTRACT_UNAUTHORIZED_SIGNER, false), // UnauthorizedTreasury
+        41 => (ErrorCode::CONTRACT_EXECUTION_FAILED, false),    // InvalidFundingAmount
+        45 => (ErrorCode::CONTRACT_DOUBLE_SPEND, false),        // BetAlreadySettled
+        _ => return None,
+    };
+    Some(mapped)
+}
+
+/// Anchor's own account and constraint errors (below 6000)
+fn anchor_framework_error(code: u32) -> Option<(ErrorCode, bool)> {
+    match code {
+        2006 => Some((ErrorCode::CONTRACT_INVALID_PDA, false)), // ConstraintSeeds
+        2000..=2999 => Some((ErrorCode::CONTRACT_EXECUTION_FAILED, false)),
+        3012 => Some((ErrorCode::CONTRACT_ACCOUNT_NOT_FOUND, false)), // AccountNotInitialized
+        3000..=3999 => Some((ErrorCode::CONTRACT_EXECUTION_FAILED, false)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decodes_custom_program_error() {
+        let (category, code, retryable) =
+            classify("Transaction simulation failed: Error processing Instruction 0: custom program error: 0x1772");
+        assert_eq!(category, ErrorCategory::Contract);
+        assert_eq!(code, ErrorCode::CONTRACT_ALLOWANCE_EXPIRED);
+        assert!(!retryable);
+    }
+
+    #[test]
+    fn test_decodes_anchor_log_error() {
+        let (_, code, retryable) =
+            classify("Program log: AnchorError occurred. Error Code: DuplicateBetId. Error Number: 6019.");
+        assert_eq!(code, ErrorCode::CONTRACT_DOUBLE_SPEND);
+        assert!(!retryable);
+
+        let (_, code, _) = classify("InstructionError(0, Custom(6004))");
+        assert_eq!(code, ErrorCode::CONTRACT_INSUFFICIENT_ALLOWANCE);
+
+        let (_, code, retryable) =
+            classify("Program log: AnchorError occurred. Error Code: BetAlreadyRefunded. Error Number: 6035.");
+        assert_eq!(code, ErrorCode::CONTRACT_DOUBLE_SPEND);
+        assert!(!retryable);
+
+        let (_, code, retryable) = classify("InstructionError(0, Custom(6045))"); // BetAlreadySettled
+        assert_eq!(code, ErrorCode::CONTRACT_DOUBLE_SPEND);
+        assert!(!retryable);
+
+        let (_, code, retryable) = classify("custom program error: 0x1796"); // TokenNotConfigured
+        assert_eq!(code, ErrorCode::CONTRACT_INVALID_BET);
+        assert!(!retryable);
+    }
+
+    #[test]
+    fn test_transient_errors_are_retryable() {
+        let (category, code, retryable) = classify("RPC response error: Blockhash not found");
+        assert_eq!(category, ErrorCategory::Network);
+        assert_eq!(code, ErrorCode::NETWORK_BLOCKHASH_EXPIRED);
+        assert!(retryable);
+
+        let (_, code, retryable) = classify("custom program error: 0x1780"); // CasinoPaused
+        assert_eq!(code, ErrorCode::CONTRACT_CASINO_PAUSED);
+        assert!(retryable);
+
+        let (_, code, retryable) = classify("InstructionError(0, Custom(6033))"); // SpendVelocityExceeded
+        assert_eq!(code, ErrorCode::CONTRACT_RATE_LIMITED);
+        assert!(retryable);
+    }
+
+    #[test]
+    fn test_unknown_error_falls_back_to_execution_failed() {
+        let (_, code, retryable) = classify("something unexpected");
+        assert_eq!(code, ErrorCode::CONTRACT_EXECUTION_FAILED);
+        assert!(retryable);
+    }
+}
@@ -11,9 +11,9 @@ use std::str::FromStr;
 
 use shared::program_ids::{SPL_ASSOCIATED_TOKEN_ACCOUNT_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID};
 
-/// Build spend_from_allowance instruction
+/// Accounts shared by `spend_from_allowance` and `settle_bet`, in program order
 #[allow(clippy::too_many_arguments)]
-pub fn build_spend_from_allowance_instruction(
+fn allowance_spend_accounts(
     program_id: &Pubkey,
     user_vault: &Pubkey,
     casino: &Pubkey,
@@ -24,21 +24,8 @@ pub fn build_spend_from_allowance_instruction(
     user_token_account: Option<&Pubkey>,
     casino_token_account: Option<&Pubkey>,
     processor: &Pubkey,
-    amount: u64,
-    bet_id: &str,
-) -> Instruction {
-    // Instruction discriminator for spend_from_allowance
-    // SHA256("global:spend_from_allowance")[0..8]
-    let mut data = vec![143, 226, 77, 235, 46, 46, 239, 222]; // spend_from_allowance discriminator
-    
-    // Serialize amount (u64)
-    data.extend_from_slice(&amount.to_le_bytes());
-    
-    // Serialize bet_id (String)
-    let bet_id_bytes = bet_id.as_bytes();
-    data.extend_from_slice(&(bet_id_bytes.len() as u32).to_le_bytes());
-    data.extend_from_slice(bet_id_bytes);
-
+    token_config: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
     let mut accounts = vec![
         AccountMeta::new(*user_vault, false),
         AccountMeta::new(*casino, false),
@@ -81,6 +68,56 @@ pub fn build_spend_from_allowance_instruction(
         accounts.push(AccountMeta::new_readonly(*program_id, false));
     }
 
+    // Appended last so programs without per-token limits ignore it
+    accounts.push(AccountMeta::new_readonly(*token_config.unwrap_or(program_id), false));
+
+    accounts
+}
+
+/// Build spend_from_allowance instruction
+#[allow(clippy::too_many_arguments)]
+pub fn build_spend_from_allowance_instruction(
+    program_id: &Pubkey,
+    discriminator: &[u8; 8],
+    user_vault: &Pubkey,
+    casino: &Pubkey,
+    allowance: &Pubkey,
+    processed_bet: &Pubkey,
+    casino_vault: &Pubkey,
+    vault_authority: &Pubkey,
+    user_token_account: Option<&Pubkey>,
+    casino_token_account: Option<&Pubkey>,
+    processor: &Pubkey,
+    token_config: Option<&Pubkey>,
+    amount: u64,
+    bet_id: &str,
+) -> Instruction {
+    // Instruction discriminator for spend_from_allowance, per deployment
+    // SHA256("global:spend_from_allowance")[0..8] for current builds
+    let mut data = discriminator.to_vec();
+    
+    // Serialize amount (u64)
+    data.extend_from_slice(&amount.to_le_bytes());
+    
+    // Serialize bet_id (String)
+    let bet_id_bytes = bet_id.as_bytes();
+    data.extend_from_slice(&(bet_id_bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(bet_id_bytes);
+
+    let accounts = allowance_spend_accounts(
+        program_id,
+        user_vault,
+        casino,
+        allowance,
+        processed_bet,
+        casino_vault,
+        vault_authority,
+        user_token_account,
+        casino_token_account,
+        processor,
+        token_config,
+    );
+
     Instruction {
         program_id: *program_id,
         accounts,
@@ -88,21 +125,83 @@ pub fn build_spend_from_allowance_instruction(
     }
 }
 
-/// Build payout instruction
+/// Build settle_bet instruction: stake spend and payout (0 for a loss) in one step
+///
+/// `payout_destination` is the vault's payout address (its token account for SPL),
+/// required by the program for wins on vaults that set one.
+#[allow(clippy::too_many_arguments)]
+pub fn build_settle_bet_instruction(
+    program_id: &Pubkey,
+    discriminator: &[u8; 8],
+    user_vault: &Pubkey,
+    casino: &Pubkey,
+    allowance: &Pubkey,
+    processed_bet: &Pubkey,
+    casino_vault: &Pubkey,
+    vault_authority: &Pubkey,
+    user_token_account: Option<&Pubkey>,
+    casino_token_account: Option<&Pubkey>,
+    processor: &Pubkey,
+    token_config: Option<&Pubkey>,
+    payout_destination: Option<&Pubkey>,
+    amount: u64,
+    payout: u64,
+    bet_id: &str,
+) -> Instruction {
+    let mut data = discriminator.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&payout.to_le_bytes());
+    let bet_id_bytes = bet_id.as_bytes();
+    data.extend_from_slice(&(bet_id_bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(bet_id_bytes);
+
+    let mut accounts = allowance_spend_accounts(
+        program_id,
+        user_vault,
+        casino,
+        allowance,
+        processed_bet,
+        casino_vault,
+        vault_authority,
+        user_token_account,
+        casino_token_account,
+        processor,
+        token_config,
+    );
+    // Trailing optional account: left off entirely when the vault pays itself
+    if let Some(destination) = payout_destination {
+        accounts.push(AccountMeta::new(*destination, false));
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Build payout instruction; `payout_destination` as for `build_settle_bet_instruction`
+///
+/// `processed_bet` is the bet's processed-bet PDA, whether or not it exists: the
+/// program refuses to pay a bet `settle_bet` already settled.
+#[allow(clippy::too_many_arguments)]
 pub fn build_payout_instruction(
     program_id: &Pubkey,
+    discriminator: &[u8; 8],
     casino: &Pubkey,
     casino_vault: &Pubkey,
     vault_authority: &Pubkey,
     user_vault: &Pubkey,
-    processed_bet: &Pubkey,
+    payout_record: &Pubkey,
     processor: &Pubkey,
+    payout_destination: Option<&Pubkey>,
+    processed_bet: &Pubkey,
     amount: u64,
     bet_id: &str,
 ) -> Instruction {
-    // Instruction discriminator for payout
-    // SHA256("global:payout")[0..8]
-    let mut data = vec![149, 140, 194, 236, 174, 189, 6, 239]; // payout discriminator
+    // Instruction discriminator for payout, per deployment
+    // SHA256("global:payout")[0..8] for current builds
+    let mut data = discriminator.to_vec();
     
     // Serialize amount (u64)
     data.extend_from_slice(&amount.to_le_bytes());
@@ -112,20 +211,145 @@ pub fn build_payout_instruction(
     data.extend_from_slice(&(bet_id_bytes.len() as u32).to_le_bytes());
     data.extend_from_slice(bet_id_bytes);
 
+    let accounts = vec![
+        AccountMeta::new(*user_vault, false),              // vault
+        AccountMeta::new(*casino, false),                   // casino (writable for stats)
+        AccountMeta::new(*casino_vault, false),             // casino_vault (program-owned, holds SOL)
+        AccountMeta::new_readonly(*vault_authority, false), // vault_authority (PDA for SPL signing)
+        // For SOL transfers, pass program_id as placeholder for optional token accounts
+        AccountMeta::new_readonly(*program_id, false),      // user_token_account (optional)
+        AccountMeta::new_readonly(*program_id, false),      // casino_token_account (optional)
+        AccountMeta::new(*payout_record, false),            // payout_record (created; one payout per bet)
+        AccountMeta::new(*processor, true),                 // processor (signer)
+        AccountMeta::new_readonly(system_program::ID, false), // system_program
+        AccountMeta::new_readonly(*program_id, false),      // token_program (optional; none for SOL)
+        AccountMeta::new(*payout_destination.unwrap_or(program_id), false), // payout_destination (optional)
+        AccountMeta::new_readonly(*processed_bet, false),   // processed_bet (read; may not exist)
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Build migrate_allowance instruction: resize an allowance that predates
+/// velocity limits so the program can spend from it; `payer` covers the rent
+pub fn build_migrate_allowance_instruction(
+    program_id: &Pubkey,
+    discriminator: &[u8; 8],
+    allowance: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
     Instruction {
         program_id: *program_id,
         accounts: vec![
-            AccountMeta::new(*user_vault, false),              // vault
-            AccountMeta::new(*casino, false),                   // casino (writable for stats)
-            AccountMeta::new(*casino_vault, false),             // casino_vault (program-owned, holds SOL)
-            AccountMeta::new_readonly(*vault_authority, false), // vault_authority (PDA for SPL signing)
-            // For SOL transfers, pass program_id as placeholder for optional token accounts
-            AccountMeta::new_readonly(*program_id, false),      // user_token_account (optional)
-            AccountMeta::new_readonly(*program_id, false),      // casino_token_account (optional)
-            AccountMeta::new_readonly(*processed_bet, false),   // processed_bet (reference)
-            AccountMeta::new(*processor, true),                 // processor (signer)
-            AccountMeta::new_readonly(system_program::ID, false), // system_program
-            // token_program (optional) - omit for SOL
+            AccountMeta::new(*allowance, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: discriminator.to_vec(),
+    }
+}
+
+/// Build refund_bet instruction: return a voided losing bet's stake
+#[allow(clippy::too_many_arguments)]
+pub fn build_refund_bet_instruction(
+    program_id: &Pubkey,
+    discriminator: &[u8; 8],
+    user_vault: &Pubkey,
+    casino: &Pubkey,
+    processed_bet: &Pubkey,
+    casino_vault: &Pubkey,
+    vault_authority: &Pubkey,
+    token_accounts: Option<(&Pubkey, &Pubkey)>,
+    processor: &Pubkey,
+    bet_id: &str,
+) -> Instruction {
+    let mut data = discriminator.to_vec();
+    let bet_id_bytes = bet_id.as_bytes();
+    data.extend_from_slice(&(bet_id_bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(bet_id_bytes);
+
+    let mut accounts = vec![
+        AccountMeta::new(*user_vault, false),
+        AccountMeta::new_readonly(*casino, false),
+        AccountMeta::new(*processed_bet, false),
+        AccountMeta::new(*casino_vault, false),
+        AccountMeta::new_readonly(*vault_authority, false),
+    ];
+    // Same optional-account placeholders as settle_bet
+    match token_accounts {
+        Some((user_ta, casino_ta)) => {
+            accounts.push(AccountMeta::new(*user_ta, false));
+            accounts.push(AccountMeta::new(*casino_ta, false));
+        }
+        None => {
+            accounts.push(AccountMeta::new(*program_id, false));
+            accounts.push(AccountMeta::new(*program_id, false));
+        }
+    }
+    accounts.push(AccountMeta::new(*processor, true));
+    accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+    accounts.push(AccountMeta::new_readonly(
+        match token_accounts {
+            Some(_) => Pubkey::from_str(SPL_TOKEN_PROGRAM_ID).expect("Valid SPL token program ID"),
+            None => *program_id,
+        },
+        false,
+    ));
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Build initialize_vault_for instruction: create `owner`'s vault, paid by the processor
+pub fn build_initialize_vault_for_instruction(
+    program_id: &Pubkey,
+    discriminator: &[u8; 8],
+    user_vault: &Pubkey,
+    casino: &Pubkey,
+    processor: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let mut data = discriminator.to_vec();
+    data.extend_from_slice(owner.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*user_vault, false),
+            AccountMeta::new_readonly(*casino, false),
+            AccountMeta::new(*processor, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Build fund_casino_vault instruction; `treasury` signs and pays in `amount` lamports
+pub fn build_fund_casino_vault_instruction(
+    program_id: &Pubkey,
+    discriminator: &[u8; 8],
+    casino: &Pubkey,
+    casino_vault: &Pubkey,
+    treasury: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = discriminator.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*casino, false),
+            AccountMeta::new(*casino_vault, false),
+            AccountMeta::new(*treasury, true),
+            AccountMeta::new_readonly(system_program::ID, false),
         ],
         data,
     }
@@ -171,6 +395,8 @@ pub fn build_create_ata_instruction(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::deployments::Discriminators;
+    use shared::vault::anchor_discriminator;
 
     #[test]
     fn test_build_spend_from_allowance_instruction() {
@@ -186,6 +412,7 @@ mod tests {
         // Test SOL mode (no token accounts)
         let instruction = build_spend_from_allowance_instruction(
             &program_id,
+            &Discriminators::default().spend_from_allowance,
             &user_vault,
             &casino,
             &allowance,
@@ -195,17 +422,124 @@ mod tests {
             None,
             None,
             &processor,
+            None,
             1000,
             "test-bet-id",
         );
 
         assert_eq!(instruction.program_id, program_id);
-        assert_eq!(instruction.accounts.len(), 11);
+        assert_eq!(instruction.accounts.len(), 12);
+        assert_eq!(instruction.accounts[11].pubkey, program_id);
         
         // Verify discriminator
         assert_eq!(&instruction.data[0..8], [143, 226, 77, 235, 46, 46, 239, 222]);
     }
 
+    #[test]
+    fn test_build_settle_bet_instruction() {
+        let program_id = Pubkey::new_unique();
+        let processed_bet = Pubkey::new_unique();
+        let discriminators = Discriminators::default();
+        let token_config = Pubkey::new_unique();
+
+        let instruction = build_settle_bet_instruction(
+            &program_id,
+            &discriminators.settle_bet.unwrap(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &processed_bet,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            None,
+            None,
+            &Pubkey::new_unique(),
+            Some(&token_config),
+            None,
+            1000,
+            1980,
+            "settle-test",
+        );
+
+        // Same accounts as spend_from_allowance, one processed-bet PDA
+        assert_eq!(instruction.accounts.len(), 12);
+        assert_eq!(instruction.accounts[3].pubkey, processed_bet);
+        assert_eq!(instruction.accounts[11].pubkey, token_config);
+        assert!(!instruction.accounts[11].is_writable);
+
+        // A vault with a payout address takes it as a last, writable account
+        let destination = Pubkey::new_unique();
+        let redirected = build_settle_bet_instruction(
+            &program_id,
+            &discriminators.settle_bet.unwrap(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &processed_bet,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            None,
+            None,
+            &Pubkey::new_unique(),
+            None,
+            Some(&destination),
+            1000,
+            1980,
+            "settle-test",
+        );
+        assert_eq!(redirected.accounts.len(), 13);
+        assert_eq!(redirected.accounts[11].pubkey, program_id);
+        assert_eq!(redirected.accounts[12], AccountMeta::new(destination, false));
+        assert_eq!(&instruction.data[0..8], anchor_discriminator("settle_bet"));
+        assert_eq!(&instruction.data[8..16], 1000u64.to_le_bytes());
+        assert_eq!(&instruction.data[16..24], 1980u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_build_refund_bet_instruction() {
+        let program_id = Pubkey::new_unique();
+        let processed_bet = Pubkey::new_unique();
+        let processor = Pubkey::new_unique();
+
+        let instruction = build_refund_bet_instruction(
+            &program_id,
+            &Discriminators::default().refund_bet.unwrap(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &processed_bet,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            None,
+            &processor,
+            "refund-test",
+        );
+
+        assert_eq!(instruction.accounts.len(), 10);
+        assert!(instruction.accounts[2].is_writable);
+        assert_eq!(instruction.accounts[2].pubkey, processed_bet);
+        assert_eq!(instruction.accounts[5].pubkey, program_id);
+        assert!(instruction.accounts[7].is_signer);
+        assert_eq!(&instruction.data[0..8], anchor_discriminator("refund_bet"));
+        assert_eq!(&instruction.data[8..12], 11u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_build_migrate_allowance_instruction() {
+        let allowance = Pubkey::new_unique();
+        let processor = Pubkey::new_unique();
+        let instruction = build_migrate_allowance_instruction(
+            &Pubkey::new_unique(),
+            &Discriminators::default().migrate_allowance.unwrap(),
+            &allowance,
+            &processor,
+        );
+
+        assert_eq!(instruction.data, anchor_discriminator("migrate_allowance"));
+        assert_eq!(instruction.accounts[0].pubkey, allowance);
+        assert!(instruction.accounts[0].is_writable);
+        assert!(instruction.accounts[1].is_signer && instruction.accounts[1].is_writable);
+    }
+
     #[test]
     fn test_build_payout_instruction() {
         let program_id = Pubkey::new_unique();
@@ -213,25 +547,59 @@ mod tests {
         let casino_vault = Pubkey::new_unique();
         let vault_authority = Pubkey::new_unique();
         let user_vault = Pubkey::new_unique();
-        let processed_bet = Pubkey::new_unique();
+        let payout_record = Pubkey::new_unique();
         let processor = Pubkey::new_unique();
+        let processed_bet = Pubkey::new_unique();
 
         let instruction = build_payout_instruction(
             &program_id,
+            &Discriminators::default().payout,
             &casino,
             &casino_vault,
             &vault_authority,
             &user_vault,
-            &processed_bet,
+            &payout_record,
             &processor,
+            None,
+            &processed_bet,
             2000,
             "payout-test",
         );
 
         assert_eq!(instruction.program_id, program_id);
-        assert_eq!(instruction.accounts.len(), 9);
+        assert_eq!(instruction.accounts.len(), 12);
+        assert_eq!(instruction.accounts[6].pubkey, payout_record);
+        assert!(instruction.accounts[6].is_writable);
+        // No payout address: the optional accounts are placeholders ahead of the processed bet
+        assert_eq!((instruction.accounts[9].pubkey, instruction.accounts[10].pubkey), (program_id, program_id));
+        assert_eq!(instruction.accounts[11].pubkey, processed_bet);
+        assert!(!instruction.accounts[11].is_writable);
         
         // Verify discriminator
         assert_eq!(&instruction.data[0..8], [149, 140, 194, 236, 174, 189, 6, 239]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_build_initialize_vault_for_instruction() {
+        let user_vault = Pubkey::new_unique();
+        let processor = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let instruction = build_initialize_vault_for_instruction(
+            &Pubkey::new_unique(),
+            &Discriminators::default().initialize_vault_for.unwrap(),
+            &user_vault,
+            &Pubkey::new_unique(),
+            &processor,
+            &owner,
+        );
+
+        assert_eq!(instruction.accounts.len(), 4);
+        assert!(instruction.accounts[0].is_writable);
+        assert_eq!(instruction.accounts[0].pubkey, user_vault);
+        assert!(instruction.accounts[2].is_signer);
+        assert_eq!(instruction.accounts[2].pubkey, processor);
+        assert_eq!(&instruction.data[0..8], anchor_discriminator("initialize_vault_for"));
+        assert_eq!(&instruction.data[8..], owner.as_ref());
+    }
+}
@@ -1,14 +1,24 @@
 //! Program Derived Address (PDA) derivation utilities
 
 use anyhow::{Context, Result};
+use shared::types::BetId;
+use shared::vault::TOKEN_CONFIG_REQUIRED_VERSION;
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey};
 
 use crate::solana_account_parsing::parse_allowance_nonce_registry_next_nonce;
 
+/// Fetch an account at `commitment`; a missing account is an error, as with `get_account`
+pub fn get_account_at(client: &RpcClient, address: &Pubkey, commitment: CommitmentConfig) -> Result<Account> {
+    client
+        .get_account_with_commitment(address, commitment)?
+        .value
+        .with_context(|| format!("AccountNotFound: pubkey={}", address))
+}
+
 /// Check if an allowance account exists on-chain
-pub fn allowance_account_exists(client: &RpcClient, allowance: &Pubkey) -> bool {
-    match client.get_account(allowance) {
+pub fn allowance_account_exists(client: &RpcClient, allowance: &Pubkey, commitment: CommitmentConfig) -> bool {
+    match get_account_at(client, allowance, commitment) {
         Ok(_) => true,
         Err(e) => {
             tracing::warn!(
@@ -27,14 +37,14 @@ pub fn derive_latest_allowance_pda_from_nonce_registry(
     program_id: &Pubkey,
     user: &Pubkey,
     casino: &Pubkey,
+    commitment: CommitmentConfig,
 ) -> Result<Pubkey> {
     let (nonce_registry, _) = Pubkey::find_program_address(
         &[b"allowance-nonce", user.as_ref(), casino.as_ref()],
         program_id,
     );
 
-    let acct = client
-        .get_account(&nonce_registry)
+    let acct = get_account_at(client, &nonce_registry, commitment)
         .with_context(|| format!("Nonce registry account {} not found", nonce_registry))?;
     
     let next_nonce = parse_allowance_nonce_registry_next_nonce(&acct.data)
@@ -50,7 +60,7 @@ pub fn derive_latest_allowance_pda_from_nonce_registry(
         program_id,
     );
 
-    if !allowance_account_exists(client, &allowance) {
+    if !allowance_account_exists(client, &allowance, commitment) {
         anyhow::bail!(
             "Derived allowance PDA {} for nonce {} is not initialized",
             allowance,
@@ -74,10 +84,93 @@ pub fn derive_user_vault_pda(user_pubkey: &Pubkey, casino_pubkey: &Pubkey, progr
     )
 }
 
+/// Derive the processed-bet PDA the vault program creates once a bet is settled
+pub fn derive_processed_bet_pda(bet_id: &BetId, program_id: &Pubkey) -> Pubkey {
+    shared::vault::processed_bet_pda(bet_id, program_id)
+}
+
+/// Derive the payout record `payout` creates, distinct from the processed-bet PDA
+pub fn derive_payout_record_pda(bet_id: &BetId, program_id: &Pubkey) -> Pubkey {
+    shared::vault::payout_record_pda(bet_id, program_id)
+}
+
+/// The mint's bet-limits account to pass for a program at `program_version`
+///
+/// From `TOKEN_CONFIG_REQUIRED_VERSION` the program takes the PDA whether or not
+/// it exists. Older programs take it only once the casino authority configured
+/// it, and the placeholder otherwise. A failed lookup is an error, not "not
+/// configured", so a configured mint's limits are never dropped.
+pub fn token_config_account(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    casino: &Pubkey,
+    mint: &Pubkey,
+    commitment: CommitmentConfig,
+    program_version: u32,
+) -> Result<Option<Pubkey>> {
+    let token_config = shared::vault::token_config_pda(casino, mint, program_id);
+    if program_version >= TOKEN_CONFIG_REQUIRED_VERSION {
+        return Ok(Some(token_config));
+    }
+    let account = client
+        .get_account_with_commitment(&token_config, commitment)
+        .context("Failed to fetch token config PDA")?
+        .value;
+    Ok(account.map(|_| token_config))
+}
+
+/// What the on-chain processed-bet PDA says about a settlement
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OnChainState {
+    pub processed: bool,
+    /// Oldest transaction touching the PDA, i.e. the one that created it
+    pub signature: Option<String>,
+}
+
+/// Whether a bet's processed-bet PDA exists, and the transaction that created it.
+/// Read at the client's (confirmation) commitment: this decides whether a bet is settled.
+pub fn processed_bet_state(client: &RpcClient, pda: &Pubkey) -> Result<OnChainState> {
+    let account = client
+        .get_account_with_commitment(pda, client.commitment())
+        .context("Failed to fetch processed-bet PDA")?
+        .value;
+    if account.is_none() {
+        return Ok(OnChainState::default());
+    }
+
+    let signature = client
+        .get_signatures_for_address(pda)
+        .context("Failed to fetch processed-bet PDA signatures")?
+        .into_iter()
+        .rev()
+        .find(|status| status.err.is_none())
+        .map(|status| status.signature);
+
+    Ok(OnChainState { processed: true, signature })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_token_config_account() {
+        let (program_id, casino) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = solana_sdk::system_program::ID;
+        let pda = shared::vault::token_config_pda(&casino, &mint, &program_id);
+        let commitment = CommitmentConfig::confirmed();
+        let lookup = |url: &str, version: u32| {
+            token_config_account(&RpcClient::new_mock(url.to_string()), &program_id, &casino, &mint, commitment, version)
+        };
+
+        // Required from its version on, configured or not, without a lookup
+        assert_eq!(lookup("fails", TOKEN_CONFIG_REQUIRED_VERSION).unwrap(), Some(pda));
+        // Older programs get the placeholder while it doesn't exist...
+        assert_eq!(lookup("succeeds", TOKEN_CONFIG_REQUIRED_VERSION - 1).unwrap(), None);
+        // ...but an RPC error isn't taken for that
+        assert!(lookup("fails", TOKEN_CONFIG_REQUIRED_VERSION - 1).is_err());
+    }
+
     #[test]
     fn test_derive_casino_pda() {
         let program_id = Pubkey::new_unique();
@@ -103,4 +196,4 @@ mod tests {
         );
         assert_eq!(vault_pda, expected.0);
     }
-}
\ No newline at end of file
+}
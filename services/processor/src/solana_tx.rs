@@ -4,45 +4,86 @@
 //! to the Solana blockchain. It has been decomposed into focused modules for maintainability.
 
 // Re-export commonly used functions from other modules in the crate
-pub use crate::solana_account_parsing::{parse_allowance_nonce_registry_next_nonce, parse_allowance_token_mint};
-pub use crate::solana_instructions::{build_create_ata_instruction, build_payout_instruction, build_spend_from_allowance_instruction};
-pub use crate::solana_pda::{allowance_account_exists, derive_casino_pda, derive_latest_allowance_pda_from_nonce_registry, derive_user_vault_pda};
-pub use crate::solana_simulation::simulate_coinflip;
+pub use crate::solana_account_parsing::parse_allowance_token_mint;
+pub use crate::solana_instructions::{
+    build_create_ata_instruction, build_migrate_allowance_instruction, build_payout_instruction,
+    build_settle_bet_instruction, build_spend_from_allowance_instruction,
+};
+pub use crate::solana_pda::{
+    allowance_account_exists, derive_casino_pda, derive_latest_allowance_pda_from_nonce_registry, derive_payout_record_pda,
+    derive_processed_bet_pda, derive_user_vault_pda, get_account_at, token_config_account,
+};
 
 use anyhow::{Context, Result};
 use spl_associated_token_account::get_associated_token_address;
 use solana_client::rpc_client::RpcClient;
 use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use shared::types::BetId;
+use shared::vault::AllowanceAccount;
+use simulation::{FairnessProof, Outcome, Simulator};
 use solana_sdk::{
+    instruction::Instruction,
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
     system_program,
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
 };
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
-use uuid::Uuid;
 
+use crate::batch_tuner::{BatchObservation, BatchSizeTuner};
+use crate::compute_budget::{
+    with_compute_unit_limit, with_compute_unit_price, ComputeUnitEstimator, TxShape, MAX_COMPUTE_UNIT_LIMIT,
+};
+use crate::coordinator::BatchType;
+use crate::deployments::VaultDeployment;
 use crate::domain::Bet;
+use crate::shadow_broadcast::ShadowBroadcast;
+use crate::simulation_failures::{self, FailureFingerprint, SimulationFailureCache};
+use crate::solana_client::Commitments;
+use crate::vault_init::vault_init_instruction;
+use crate::write_locks::WriteLockScheduler;
 
 /// Build and submit a batch of bets to Solana
 ///
 /// This is the main entry point for processing bet transactions. It:
 /// 1. Validates input constraints
-/// 2. Simulates coinflip outcomes for all bets
-/// 3. Builds spend_from_allowance instructions
-/// 4. Builds payout instructions for winning bets
-/// 5. Creates any missing Associated Token Accounts
-/// 6. Simulates the transaction for debugging
-/// 7. Sends and confirms the transaction
+/// 2. Takes committed outcomes as-is and draws the rest from the simulator
+/// 3. Builds one settle_bet instruction per bet (stake spend plus any payout),
+///    or spend_from_allowance + payout on deployments without settle_bet
+/// 4. Records the outcome and payout on the bet's single processed-bet PDA
+/// 5. Creates any missing Associated Token Accounts, and missing user vaults
+///    when `auto_init_vaults` is set
+/// 6. Sets a compute unit limit from simulation (cached per batch shape), feeding
+///    size and compute units to the batch tuner; a failed simulation is
+///    fingerprinted in `simulation_failures` against the bet that caused it,
+///    and a compute unit price of `priority_fee_micro_lamports` unless it is 0
+/// 7. Waits its turn in `write_locks` on the casino vault (wins) and
+///    allowances (losses) it writes, then sends and confirms the transaction,
+///    shadow-broadcasting it to the endpoints in `broadcast`
 ///
-/// Returns the transaction signature and bet results
+/// Returns the transaction signature and bet outcomes
+#[allow(clippy::too_many_arguments)]
 pub async fn submit_batch_transaction(
     client: &RpcClient,
+    broadcast: &ShadowBroadcast,
+    commitments: Commitments,
     bets: &[Bet],
     processor_keypair: &Keypair,
-    vault_program_id: &Pubkey,
+    deployment: &VaultDeployment,
+    program_version: u32,
+    simulator: &Simulator,
+    batch_tuner: &BatchSizeTuner,
+    compute_estimator: &ComputeUnitEstimator,
+    simulation_failures: &SimulationFailureCache,
     max_bets_per_tx: usize,
-) -> Result<(String, Vec<(Uuid, bool, i64)>)> {
+    priority_fee_micro_lamports: u64,
+    write_locks: Option<&WriteLockScheduler>,
+    auto_init_vaults: bool,
+) -> Result<(String, Vec<Outcome>)> {
+    let vault_program_id = &deployment.program_id;
+
     // Limit batch size to avoid transaction size / compute limits.
     if bets.len() > max_bets_per_tx {
         anyhow::bail!(
@@ -54,13 +95,37 @@ pub async fn submit_batch_transaction(
 
     // Simulate coinflip outcomes first
     let mut results = Vec::new();
-    let mut instructions = Vec::new();
+    let mut instructions = with_compute_unit_price(priority_fee_micro_lamports, Vec::new());
+    // Bet-limit accounts looked up once per mint in the batch
+    let mut token_configs: HashMap<Pubkey, Option<Pubkey>> = HashMap::new();
+    // Payout address of each user whose vault was checked; a vault is only initialized once per batch
+    let mut vaults_checked: HashMap<Pubkey, Option<Pubkey>> = HashMap::new();
+    // Allowances resized earlier in this transaction
+    let mut migrated_allowances = HashSet::new();
+    // Index of each bet's first instruction, to blame a failed simulation on a bet
+    let mut bet_starts = Vec::with_capacity(bets.len());
+    // Casino vault for wins, allowance for losses: what the transaction contends on
+    let mut write_keys = Vec::new();
 
     for bet in bets {
+        bet_starts.push(instructions.len());
+
         // Determine bet result
-        let won = simulate_coinflip();
-        let payout = if won { bet.stake_amount * 2 } else { 0 };
-        results.push((bet.bet_id, won, payout));
+        // Bets committed to a backend daily seed arrive with their outcome drawn
+        let outcome = match (&bet.server_seed_hash, bet.won, bet.payout_amount) {
+            (Some(server_seed_hash), Some(won), Some(payout)) => Outcome {
+                bet_id: bet.bet_id,
+                won,
+                payout,
+                proof: FairnessProof {
+                    server_seed_hash: server_seed_hash.clone(),
+                    client_seed: bet.client_seed.clone().unwrap_or_default(),
+                },
+            },
+            _ => simulator.coinflip(bet.bet_id, bet.client_seed.as_deref().unwrap_or_default(), bet.stake_amount),
+        };
+        let (won, payout) = (outcome.won, outcome.payout);
+        results.push(outcome);
 
         // Parse user wallet pubkey
         let user_pubkey = Pubkey::from_str(&bet.user_wallet)
@@ -72,6 +137,24 @@ pub async fn submit_batch_transaction(
         // Derive user vault PDA
         let (user_vault_pda, _) = derive_user_vault_pda(&user_pubkey, &casino_pda, vault_program_id);
 
+        let payout_address = match vaults_checked.get(&user_pubkey) {
+            Some(payout_address) => *payout_address,
+            None => {
+                let user_vault = vault_init_instruction(
+                    client,
+                    commitments.read,
+                    deployment,
+                    &user_pubkey,
+                    &processor_keypair.pubkey(),
+                    auto_init_vaults,
+                )
+                .with_context(|| format!("Bet {}", bet.bet_id))?;
+                instructions.extend(user_vault.init);
+                vaults_checked.insert(user_pubkey, user_vault.payout_address);
+                user_vault.payout_address
+            }
+        };
+
         // Derive casino vault PDA (program-owned account holding SOL)
         let (casino_vault, _) = Pubkey::find_program_address(
             &[b"casino-vault", casino_pda.as_ref()],
@@ -89,7 +172,7 @@ pub async fn submit_batch_transaction(
         // from the on-chain nonce registry.
         let allowance = if let Some(pda_str) = bet.allowance_pda.as_ref().filter(|s| !s.is_empty()) {
             let pda = Pubkey::from_str(pda_str).context("Invalid allowance_pda pubkey")?;
-            if allowance_account_exists(client, &pda) {
+            if allowance_account_exists(client, &pda, commitments.read) {
                 pda
             } else {
                 tracing::warn!(
@@ -97,7 +180,7 @@ pub async fn submit_batch_transaction(
                     bet.bet_id,
                     pda
                 );
-                derive_latest_allowance_pda_from_nonce_registry(client, vault_program_id, &user_pubkey, &casino_pda)
+                derive_latest_allowance_pda_from_nonce_registry(client, vault_program_id, &user_pubkey, &casino_pda, commitments.read)
                     .with_context(|| {
                         format!(
                             "Allowance account not initialized (provided {}, no nonce-registry fallback) for bet {}",
@@ -106,7 +189,7 @@ pub async fn submit_batch_transaction(
                     })?
             }
         } else {
-            derive_latest_allowance_pda_from_nonce_registry(client, vault_program_id, &user_pubkey, &casino_pda)
+            derive_latest_allowance_pda_from_nonce_registry(client, vault_program_id, &user_pubkey, &casino_pda, commitments.read)
                 .with_context(|| {
                     format!(
                         "Bet {} missing allowance_pda and no initialized allowance could be derived from nonce registry",
@@ -115,25 +198,44 @@ pub async fn submit_batch_transaction(
                 })?
         };
 
+        write_keys.push(if won { casino_vault } else { allowance });
+
         // Determine whether this allowance is native SOL (no SPL token accounts) or SPL.
         // If we include token accounts for a native SOL allowance, Anchor will attempt to
         // deserialize them and fail with AccountNotInitialized.
-        let allowance_acct = client
-            .get_account(&allowance)
+        let allowance_acct = get_account_at(client, &allowance, commitments.read)
             .with_context(|| format!("Failed to fetch allowance account {}", allowance))?;
         let allowance_token_mint = parse_allowance_token_mint(&allowance_acct.data)
             .with_context(|| format!("Failed to parse allowance token_mint for {}", allowance))?;
         let is_native_sol = allowance_token_mint == system_program::ID || allowance_token_mint == Pubkey::default();
 
+        // The program can't read an allowance from before velocity limits until it is resized
+        if let Some(migrate_allowance) = deployment.discriminators.migrate_allowance {
+            if AllowanceAccount::needs_migration(&allowance_acct.data) && migrated_allowances.insert(allowance) {
+                instructions.push(build_migrate_allowance_instruction(
+                    vault_program_id,
+                    &migrate_allowance,
+                    &allowance,
+                    &processor_keypair.pubkey(),
+                ));
+            }
+        }
+
         let mut user_token_account: Option<Pubkey> = None;
         let mut casino_token_account: Option<Pubkey> = None;
+        // Winnings go to the payout address itself for SOL, to its token account for SPL
+        let mut payout_destination = payout_address;
 
         if !is_native_sol {
             let user_ata = get_associated_token_address(&user_pubkey, &allowance_token_mint);
             let casino_ata = get_associated_token_address(&casino_pda, &allowance_token_mint);
 
             // User ATA must exist if spending SPL tokens.
-            if client.get_account(&user_ata).is_err() {
+            if get_account_at(client, &user_ata, commitments.read).is_err() {
+                simulation_failures.record(FailureFingerprint {
+                    error: "UserTokenAccountNotInitialized".to_string(),
+                    account: user_pubkey,
+                });
                 anyhow::bail!(
                     "User token account {} not initialized for mint {} (bet {})",
                     user_ata,
@@ -143,7 +245,7 @@ pub async fn submit_batch_transaction(
             }
 
             // Casino ATA can be created by the processor if missing.
-            if client.get_account(&casino_ata).is_err() {
+            if get_account_at(client, &casino_ata, commitments.read).is_err() {
                 let create_ata_ix = build_create_ata_instruction(
                     &processor_keypair.pubkey(),
                     &casino_pda,
@@ -152,20 +254,74 @@ pub async fn submit_batch_transaction(
                 instructions.push(create_ata_ix);
             }
 
+            // Unlike the casino's, the payout address's token account is the owner's to create
+            if let Some(payout_address) = payout_address.filter(|_| won) {
+                let payout_ata = get_associated_token_address(&payout_address, &allowance_token_mint);
+                if get_account_at(client, &payout_ata, commitments.read).is_err() {
+                    simulation_failures.record(FailureFingerprint {
+                        error: "PayoutTokenAccountNotInitialized".to_string(),
+                        account: user_pubkey,
+                    });
+                    anyhow::bail!(
+                        "Payout address {} has no token account {} for mint {} (bet {})",
+                        payout_address,
+                        payout_ata,
+                        allowance_token_mint,
+                        bet.bet_id
+                    );
+                }
+                payout_destination = Some(payout_ata);
+            }
+
             user_token_account = Some(user_ata);
             casino_token_account = Some(casino_ata);
         }
 
-        // Derive processed_bet PDA (use UUID string without hyphens to stay under 32 byte limit)
-        let bet_id_no_hyphens = bet.bet_id.to_string().replace("-", "");
-        let (processed_bet, _) = Pubkey::find_program_address(
-            &[b"processed-bet", bet_id_no_hyphens.as_bytes()],
-            vault_program_id,
-        );
+        let token_config = match token_configs.get(&allowance_token_mint) {
+            Some(token_config) => *token_config,
+            None => {
+                let token_config = token_config_account(
+                    client,
+                    vault_program_id,
+                    &casino_pda,
+                    &allowance_token_mint,
+                    commitments.read,
+                    program_version,
+                )?;
+                token_configs.insert(allowance_token_mint, token_config);
+                token_config
+            }
+        };
+
+        // Full 32-char id: the program rejects anything shorter
+        let bet_id = BetId::new(bet.bet_id);
+        let processed_bet = derive_processed_bet_pda(&bet_id, vault_program_id);
+
+        let Some(settle_bet) = deployment.discriminators.settle_bet else {
+            instructions.extend(legacy_settlement_instructions(
+                deployment,
+                &user_vault_pda,
+                &casino_pda,
+                &allowance,
+                &processed_bet,
+                &casino_vault,
+                &vault_authority,
+                user_token_account.as_ref(),
+                casino_token_account.as_ref(),
+                &processor_keypair.pubkey(),
+                token_config.as_ref(),
+                payout_destination.as_ref(),
+                &bet_id,
+                bet.stake_amount as u64,
+                won.then_some(payout as u64),
+            ));
+            continue;
+        };
 
-        // Build spend_from_allowance instruction
-        let spend_ix = build_spend_from_allowance_instruction(
+        // Spend and payout land together, recorded on one processed-bet PDA
+        instructions.push(build_settle_bet_instruction(
             vault_program_id,
+            &settle_bet,
             &user_vault_pda,
             &casino_pda,
             &allowance,
@@ -175,52 +331,195 @@ pub async fn submit_batch_transaction(
             user_token_account.as_ref(),
             casino_token_account.as_ref(),
             &processor_keypair.pubkey(),
+            token_config.as_ref(),
+            // A loss pays nothing, so it needs no destination
+            payout_destination.filter(|_| won).as_ref(),
             bet.stake_amount as u64,
-            &bet_id_no_hyphens, // Pass without hyphens to match PDA derivation
-        );
-        instructions.push(spend_ix);
-
-        // If user won, add payout instruction
-        if won {
-            // Use same UUID format (no hyphens) for payout processed-bet PDA
-            let payout_bet_id = format!("payout{}", bet.bet_id.to_string().replace("-", "").chars().take(24).collect::<String>());
-            let (processed_bet_payout, _) = Pubkey::find_program_address(
-                &[b"payout", payout_bet_id.as_bytes()],
-                vault_program_id,
-            );
-            
-            let payout_ix = build_payout_instruction(
-                vault_program_id,
-                &casino_pda,
-                &casino_vault,
-                &vault_authority,
-                &user_vault_pda,
-                &processed_bet_payout,
-                &processor_keypair.pubkey(),
-                payout as u64,
-                &payout_bet_id,
-            );
-            instructions.push(payout_ix);
-        }
+            if won { payout as u64 } else { 0 },
+            bet_id.as_str(),
+        ));
     }
 
     // Get recent blockhash
-    let recent_blockhash = client
-        .get_latest_blockhash()
+    let (recent_blockhash, _) = client
+        .get_latest_blockhash_with_commitment(commitments.blockhash)
         .context("Failed to get recent blockhash")?;
 
-    // Build and sign transaction
-    let transaction = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&processor_keypair.pubkey()),
-        &[processor_keypair],
-        recent_blockhash,
+    let sign = |limit: u32| {
+        Transaction::new_signed_with_payer(
+            &with_compute_unit_limit(limit, &instructions),
+            Some(&processor_keypair.pubkey()),
+            &[processor_keypair],
+            recent_blockhash,
+        )
+    };
+
+    // Oversized transactions can never land; shrink future batches instead.
+    // The limit value doesn't change the size, so measure under the maximum.
+    let probe = sign(MAX_COMPUTE_UNIT_LIMIT);
+    let tx_bytes = bincode::serialized_size(&probe).context("Failed to size transaction")? as usize;
+    if tx_bytes > PACKET_DATA_SIZE {
+        batch_tuner.record_too_large(bets.len());
+        anyhow::bail!(
+            "Transaction too large: {} bytes for {} bets (max {})",
+            tx_bytes,
+            bets.len(),
+            PACKET_DATA_SIZE
+        );
+    }
+
+    let fingerprint = |err: &TransactionError, logs: &[String]| {
+        let Some((index, error)) = simulation_failures::classify(err, logs) else {
+            return;
+        };
+        // Instruction 0 is the compute unit limit; `bet_starts` already counts any price
+        let bet = index
+            .checked_sub(1)
+            .and_then(|ix| bet_starts.partition_point(|&start| start <= ix).checked_sub(1));
+        if let Some(account) = bet.and_then(|bet| Pubkey::from_str(&bets[bet].user_wallet).ok()) {
+            simulation_failures.record(FailureFingerprint { error, account });
+        }
+    };
+
+    // Request exactly what this shape of batch needs; simulate under the
+    // maximum limit the first time a shape is seen to measure it
+    let shape = TxShape::of(&instructions);
+    let cached = match compute_estimator.cached(&shape) {
+        Some(limit) => {
+            let transaction = sign(limit);
+            match preflight(client, &transaction, bets.len(), &fingerprint) {
+                Ok(units) => Some((transaction, units)),
+                // The cached limit was too low for this batch; measure it again
+                Err(e) if is_budget_exceeded(&e) => {
+                    compute_estimator.invalidate(&shape);
+                    None
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        None => None,
+    };
+    let (transaction, units) = match cached {
+        Some((transaction, units)) => {
+            if let Some(units) = units {
+                compute_estimator.record(shape, units);
+            }
+            (transaction, units)
+        }
+        None => {
+            let units = match preflight(client, &probe, bets.len(), &fingerprint) {
+                // Even the maximum limit is not enough for this many bets
+                Err(e) if is_budget_exceeded(&e) => {
+                    batch_tuner.record_too_large(bets.len());
+                    return Err(e);
+                }
+                result => result?,
+            };
+            match units {
+                Some(units) => (sign(compute_estimator.record(shape, units)), Some(units)),
+                // Nothing measured; keep the maximum rather than guess
+                None => (probe, None),
+            }
+        }
+    };
+    batch_tuner.record(BatchObservation {
+        bet_count: bets.len(),
+        tx_bytes,
+        compute_units: units,
+    });
+
+    if let Some(write_locks) = write_locks {
+        write_keys.sort();
+        write_keys.dedup();
+        let batch_type = if results.iter().any(|outcome| outcome.won) { BatchType::Payout } else { BatchType::Spend };
+        write_locks.wait_turn(&write_keys, batch_type).await;
+    }
+
+    // Send and confirm transaction
+    let signature = broadcast.send_and_confirm(client, &transaction).await?;
+
+    tracing::info!(
+        "Solana transaction confirmed: {} ({} bets)",
+        signature,
+        bets.len()
     );
 
-    // Preflight simulation to capture full program logs on failure.
-    // This makes diagnosing Anchor constraint failures and CPI errors much easier.
+    Ok((signature.to_string(), results))
+}
+
+/// spend_from_allowance plus, for a win, payout: for deployments without settle_bet.
+/// The two instructions share a transaction but not a processed-bet record.
+#[allow(clippy::too_many_arguments)]
+fn legacy_settlement_instructions(
+    deployment: &VaultDeployment,
+    user_vault_pda: &Pubkey,
+    casino_pda: &Pubkey,
+    allowance: &Pubkey,
+    processed_bet: &Pubkey,
+    casino_vault: &Pubkey,
+    vault_authority: &Pubkey,
+    user_token_account: Option<&Pubkey>,
+    casino_token_account: Option<&Pubkey>,
+    processor: &Pubkey,
+    token_config: Option<&Pubkey>,
+    payout_destination: Option<&Pubkey>,
+    bet_id: &BetId,
+    amount: u64,
+    payout: Option<u64>,
+) -> Vec<Instruction> {
+    let vault_program_id = &deployment.program_id;
+
+    let mut instructions = vec![build_spend_from_allowance_instruction(
+        vault_program_id,
+        &deployment.discriminators.spend_from_allowance,
+        user_vault_pda,
+        casino_pda,
+        allowance,
+        processed_bet,
+        casino_vault,
+        vault_authority,
+        user_token_account,
+        casino_token_account,
+        processor,
+        token_config,
+        amount,
+        bet_id.as_str(),
+    )];
+
+    // If user won, add payout instruction
+    if let Some(payout) = payout {
+        // Same full id, under the payout seed namespace
+        let payout_record = derive_payout_record_pda(bet_id, vault_program_id);
+
+        instructions.push(build_payout_instruction(
+            vault_program_id,
+            &deployment.discriminators.payout,
+            casino_pda,
+            casino_vault,
+            vault_authority,
+            user_vault_pda,
+            &payout_record,
+            processor,
+            payout_destination,
+            processed_bet,
+            payout,
+            bet_id.as_str(),
+        ));
+    }
+    instructions
+}
+
+/// Preflight simulation to capture full program logs on failure; returns the
+/// units consumed. An RPC error is only logged, leaving the send to decide.
+/// A failed simulation is passed to `fingerprint` with its logs.
+fn preflight(
+    client: &RpcClient,
+    transaction: &Transaction,
+    bet_count: usize,
+    fingerprint: &dyn Fn(&TransactionError, &[String]),
+) -> Result<Option<u64>> {
     let sim = client.simulate_transaction_with_config(
-        &transaction,
+        transaction,
         RpcSimulateTransactionConfig {
             sig_verify: false,
             replace_recent_blockhash: true,
@@ -231,11 +530,12 @@ pub async fn submit_batch_transaction(
     match sim {
         Ok(resp) => {
             if let Some(err) = resp.value.err {
+                fingerprint(&err, resp.value.logs.as_deref().unwrap_or_default());
                 if let Some(logs) = resp.value.logs {
                     let trimmed: Vec<String> = logs.into_iter().take(25).collect();
                     tracing::error!(
                         "Preflight simulation failed ({} bets). Logs:\n{}",
-                        bets.len(),
+                        bet_count,
                         trimmed.join("\n")
                     );
                     anyhow::bail!(
@@ -246,22 +546,16 @@ pub async fn submit_batch_transaction(
                 }
                 anyhow::bail!("Preflight simulation failed: {:?}", err);
             }
+            Ok(resp.value.units_consumed)
         }
         Err(e) => {
             tracing::warn!("Preflight simulation RPC error: {:#}", e);
+            Ok(None)
         }
     }
+}
 
-    // Send and confirm transaction
-    let signature = client
-        .send_and_confirm_transaction(&transaction)
-        .context("Failed to send and confirm transaction")?;
-
-    tracing::info!(
-        "Solana transaction confirmed: {} ({} bets)",
-        signature,
-        bets.len()
-    );
-
-    Ok((signature.to_string(), results))
+/// Whether a preflight failure was the compute budget running out
+fn is_budget_exceeded(err: &anyhow::Error) -> bool {
+    format!("{:#}", err).contains("ComputationalBudgetExceeded")
 }
@@ -0,0 +1,66 @@
+//! Warm standby
+//!
+//! With `PROCESSOR_STANDBY=true` an instance starts everything a takeover needs
+//! (RPC pool, processor keypair, blockchain API client, coordinator and
+//! workers) and then stays idle while another instance holds the coordinator
+//! lease. Until then it keeps the RPC pool's health checks current and fetches
+//! a blockhash on every check, so connections are open and a failing endpoint
+//! is known before the first settlement. Once the lease lapses the leader
+//! election takes it, the coordinator wakes immediately and the components
+//! that only the active instance runs start.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+use crate::leader_election::LeaderElection;
+use crate::solana_client::SolanaClientPool;
+
+pub struct WarmStandby {
+    election: Arc<LeaderElection>,
+    solana_client: Arc<SolanaClientPool>,
+    warm_interval: Duration,
+}
+
+impl WarmStandby {
+    pub fn new(election: Arc<LeaderElection>, solana_client: Arc<SolanaClientPool>, warm_interval: Duration) -> Self {
+        Self { election, solana_client, warm_interval }
+    }
+
+    /// Keep the instance warm until it becomes the active one
+    pub async fn run(self: Arc<Self>) {
+        metrics::gauge!("processor_standby").set(1.0);
+        info!(instance_id = self.election.instance_id(), "Warm standby; idle until the coordinator lease lapses");
+
+        while !self.election.is_leader() {
+            self.warm().await;
+            let _ = timeout(self.warm_interval, self.election.wait_for_leadership()).await;
+        }
+
+        metrics::gauge!("processor_standby").set(0.0);
+        info!(instance_id = self.election.instance_id(), "Standby is now the active processor");
+    }
+
+    /// Resolves once this instance holds the lease
+    pub async fn wait_for_takeover(&self) {
+        self.election.wait_for_leadership().await;
+    }
+
+    async fn warm(&self) {
+        self.solana_client.health_check_all().await;
+        let Some(client) = self.solana_client.get_healthy_client_or_any().await else {
+            return;
+        };
+        let commitment = self.solana_client.commitments().blockhash;
+        let fetched = tokio::task::spawn_blocking(move || {
+            client.get_latest_blockhash_with_commitment(commitment).map(|_| ()).map_err(|e| e.to_string())
+        })
+        .await;
+        let ready = matches!(fetched, Ok(Ok(())));
+        if let Ok(Err(e)) = fetched {
+            warn!(error = %e, "Standby could not fetch a blockhash; RPC pool is not ready for takeover");
+        }
+        metrics::gauge!("processor_standby_rpc_ready").set(if ready { 1.0 } else { 0.0 });
+    }
+}
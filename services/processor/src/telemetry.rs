@@ -0,0 +1,175 @@
+//! Settlement metrics
+//!
+//! Settlement counters and histograms are recorded through the helpers below so
+//! every series carries the same dimensions (`casino_id`, `token`, `game_type`,
+//! `worker_id`, `batch_type`) and the same `result` values. Label values come
+//! from the blockchain API; free-form ones are normalized so a malformed
+//! settlement can't open new series.
+
+use metrics::Label;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::blockchain_client::GameSettlementInfo;
+
+/// `batch_type` of settlements polled without the coordinator, which mix payouts and spends
+pub const POLLED_BATCH_TYPE: &str = "polled";
+
+/// Longest `casino_id` or `game_type` kept as a label value
+const MAX_IDENTIFIER_LEN: usize = 32;
+
+/// Dimensions of one settlement's series
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettlementLabels {
+    casino_id: String,
+    token: String,
+    game_type: String,
+    worker_id: String,
+    batch_type: &'static str,
+}
+
+impl SettlementLabels {
+    pub fn new(settlement: &GameSettlementInfo, worker_id: usize, batch_type: &'static str) -> Self {
+        Self {
+            casino_id: settlement.casino_id.as_deref().map_or_else(|| "default".to_string(), identifier_label),
+            token: token_label(&settlement.token),
+            game_type: identifier_label(&settlement.game_type),
+            worker_id: worker_id.to_string(),
+            batch_type,
+        }
+    }
+
+    fn with_result(&self, result: &'static str) -> Vec<Label> {
+        vec![
+            Label::new("casino_id", self.casino_id.clone()),
+            Label::new("token", self.token.clone()),
+            Label::new("game_type", self.game_type.clone()),
+            Label::new("worker_id", self.worker_id.clone()),
+            Label::new("batch_type", self.batch_type),
+            Label::new("result", result),
+        ]
+    }
+}
+
+/// `SOL` or a mint address; anything else is `other`
+fn token_label(token: &str) -> String {
+    if token.eq_ignore_ascii_case("sol") {
+        "SOL".to_string()
+    } else if Pubkey::from_str(token).is_ok() {
+        token.to_string()
+    } else {
+        "other".to_string()
+    }
+}
+
+/// Short alphanumeric identifiers, lowercased; anything else is `other`
+fn identifier_label(value: &str) -> String {
+    let valid = !value.is_empty()
+        && value.len() <= MAX_IDENTIFIER_LEN
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        value.to_ascii_lowercase()
+    } else {
+        "other".to_string()
+    }
+}
+
+fn result_label(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "failure"
+    }
+}
+
+/// One settlement finished, from pickup to its status update
+pub fn record_settlement(labels: &SettlementLabels, success: bool, duration: Duration) {
+    let labels = labels.with_result(result_label(success));
+    metrics::counter!("settlements_total", labels.clone()).increment(1);
+    metrics::histogram!("settlement_duration_seconds", labels).record(duration.as_secs_f64());
+}
+
+/// A worker pool batch polled without the coordinator finished
+pub fn record_batch(worker_id: usize, success: bool, duration: Duration) {
+    let labels = vec![
+        Label::new("worker_id", worker_id.to_string()),
+        Label::new("batch_type", POLLED_BATCH_TYPE),
+        Label::new("result", result_label(success)),
+    ];
+    metrics::counter!("batches_processed_total", labels.clone()).increment(1);
+    metrics::histogram!("batch_processing_duration_seconds", labels).record(duration.as_secs_f64());
+}
+
+/// A coordinator batch finished, `completion` seconds after dispatch
+pub fn record_batch_completion(worker_id: usize, batch_type: &'static str, lane: &'static str, success: bool, completion: f64) {
+    metrics::histogram!(
+        "settlement_batch_completion_seconds",
+        "worker_id" => worker_id.to_string(),
+        "batch_type" => batch_type,
+        "lane" => lane,
+        "result" => result_label(success)
+    )
+    .record(completion);
+}
+
+pub fn record_worker_error(worker_id: usize) {
+    metrics::counter!("worker_errors_total", "worker_id" => worker_id.to_string()).increment(1);
+}
+
+pub fn record_worker_circuit_open(worker_id: usize) {
+    metrics::counter!("worker_circuit_breaker_open_total", "worker_id" => worker_id.to_string()).increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settlement_labels_are_normalized() {
+        let mint = Pubkey::new_unique().to_string();
+        let settlement = GameSettlementInfo {
+            transaction_id: 1,
+            player_address: "player".to_string(),
+            game_type: "CoinFlip".to_string(),
+            bet_amount: 100_000_000,
+            token: mint.clone(),
+            outcome: "Win".to_string(),
+            payout: 200_000_000,
+            vrf_proof: String::new(),
+            vrf_output: String::new(),
+            block_height: 1,
+            version: 1,
+            solana_tx_id: None,
+            retry_count: 0,
+            next_retry_after: None,
+            allowance_pda: None,
+            casino_id: None,
+            priority: false,
+            status: None,
+            allowance_expires_at: None,
+        };
+        let labels = SettlementLabels::new(&settlement, 3, "payout");
+        assert_eq!(
+            labels.with_result("success"),
+            vec![
+                Label::new("casino_id", "default"),
+                Label::new("token", mint),
+                Label::new("game_type", "coinflip"),
+                Label::new("worker_id", "3"),
+                Label::new("batch_type", "payout"),
+                Label::new("result", "success"),
+            ]
+        );
+
+        let odd = GameSettlementInfo {
+            token: "sol".to_string(),
+            game_type: "dice 🎲".to_string(),
+            casino_id: Some("x".repeat(64)),
+            ..settlement
+        };
+        let labels = SettlementLabels::new(&odd, 0, POLLED_BATCH_TYPE);
+        assert_eq!((labels.token.as_str(), labels.game_type.as_str()), ("SOL", "other"));
+        assert_eq!(labels.casino_id, "other");
+    }
+}
@@ -0,0 +1,271 @@
+//! Casino vault top-ups from the treasury
+//!
+//! Payouts drain the casino vault. When its tracked balance drops below the
+//! floor, this task moves lamports from the treasury back up to the target with
+//! `fund_casino_vault`, which also updates the tracked balance. With
+//! `TREASURY_KEYPAIR_PATH` set the processor signs and sends the top-up itself;
+//! without it (e.g. a multisig treasury) the instruction is logged for signing,
+//! the way `ops-cli fund --export` or a Squads proposal would send it. Every
+//! check also alerts while the treasury itself runs low. With leader election
+//! on, only the leader tops up.
+
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use shared::secrets::read_keypair;
+use shared::vault::{casino_pda, casino_vault_pda, CasinoAccount, CasinoVaultAccount};
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
+
+use crate::config::Config;
+use crate::deployments::VaultDeployment;
+use crate::leader_election::LeaderElection;
+use crate::solana_client::SolanaClientPool;
+use crate::solana_instructions::build_fund_casino_vault_instruction;
+
+/// Left in the treasury so it stays rent-exempt and can pay its own fees
+const TREASURY_RESERVE_LAMPORTS: u64 = 10_000_000;
+
+/// What one check should do about a deployment's casino vault
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopUp {
+    /// At or above the floor
+    NotNeeded,
+    /// Move this many lamports; less than the shortfall when the treasury can't cover it
+    Fund(u64),
+    /// Below the floor, with nothing spendable in the treasury
+    TreasuryEmpty,
+}
+
+/// Top-up back to `target` once the vault is below `floor`, capped by what the treasury can spare
+pub fn plan(vault_balance: u64, treasury_balance: u64, floor: u64, target: u64) -> TopUp {
+    if vault_balance >= floor {
+        return TopUp::NotNeeded;
+    }
+    let shortfall = target.max(floor) - vault_balance;
+    match shortfall.min(treasury_balance.saturating_sub(TREASURY_RESERVE_LAMPORTS)) {
+        0 => TopUp::TreasuryEmpty,
+        amount => TopUp::Fund(amount),
+    }
+}
+
+pub struct TreasuryTopUp {
+    solana_client: Arc<SolanaClientPool>,
+    processor_keypair: Arc<Keypair>,
+    /// `None` exports top-ups instead of sending them
+    treasury_keypair: Option<Arc<Keypair>>,
+    leader_election: Option<Arc<LeaderElection>>,
+    config: Config,
+}
+
+impl TreasuryTopUp {
+    pub fn new(
+        solana_client: Arc<SolanaClientPool>,
+        processor_keypair: Arc<Keypair>,
+        config: Config,
+        leader_election: Option<Arc<LeaderElection>>,
+    ) -> Result<Self> {
+        let treasury_keypair = config
+            .casino_topup
+            .treasury_keypair_path
+            .as_deref()
+            .map(|keypair| read_keypair(keypair).context("Failed to load treasury keypair"))
+            .transpose()?
+            .map(Arc::new);
+        Ok(Self { solana_client, processor_keypair, treasury_keypair, leader_election, config })
+    }
+
+    pub async fn run(self: Arc<Self>) {
+        let topup = &self.config.casino_topup;
+        let interval = Duration::from_secs(topup.check_interval_seconds);
+        info!(
+            floor_lamports = topup.floor_lamports,
+            target_lamports = topup.target_lamports,
+            treasury = ?self.treasury_keypair.as_ref().map(|k| k.pubkey()),
+            mode = if self.treasury_keypair.is_some() { "send" } else { "export" },
+            "Casino top-up started"
+        );
+
+        loop {
+            if self.leader_election.as_ref().is_none_or(|election| election.is_leader()) {
+                for deployment in &self.config.solana.deployments {
+                    if let Err(e) = self.check(deployment).await {
+                        metrics::counter!(
+                            "casino_topups_total",
+                            "deployment" => deployment.name.clone(),
+                            "outcome" => "failed"
+                        )
+                        .increment(1);
+                        warn!(deployment = %deployment.name, error = %e, "Casino top-up check failed");
+                    }
+                }
+            }
+            sleep(interval).await;
+        }
+    }
+
+    async fn check(&self, deployment: &VaultDeployment) -> Result<()> {
+        let Some(discriminator) = deployment.discriminators.fund_casino_vault else {
+            debug!(deployment = %deployment.name, "Vault deployment has no fund_casino_vault; skipping top-up");
+            return Ok(());
+        };
+        self.solana_client.ensure_program_supported(deployment).await?;
+
+        let program_id = deployment.program_id;
+        let casino = casino_pda(&program_id);
+        let casino_vault = casino_vault_pda(&casino, &program_id);
+        let client = self.solana_client.get_client().await;
+        let commitment = self.solana_client.commitments().read;
+        let configured_treasury = self.treasury_keypair.as_ref().map(|k| k.pubkey());
+        let (casino_account, vault_balance, treasury, treasury_balance) =
+            tokio::task::spawn_blocking(move || -> Result<_> {
+                let casino_data = client
+                    .get_account_with_commitment(&casino, commitment)
+                    .context("Failed to fetch casino account")?
+                    .value
+                    .context("Casino account not found")?;
+                let casino_account = CasinoAccount::decode(&casino_data.data)?;
+                let vault_data = client
+                    .get_account_with_commitment(&casino_vault, commitment)
+                    .context("Failed to fetch casino vault")?
+                    .value
+                    .context("Casino vault not found")?;
+                let vault_balance = CasinoVaultAccount::decode(&vault_data.data)?.sol_balance;
+                let treasury = configured_treasury.unwrap_or(casino_account.treasury);
+                let treasury_balance = client
+                    .get_balance_with_commitment(&treasury, commitment)
+                    .context("Failed to fetch treasury balance")?
+                    .value;
+                Ok((casino_account, vault_balance, treasury, treasury_balance))
+            })
+            .await
+            .context("Top-up balance lookup task failed")??;
+
+        let topup = &self.config.casino_topup;
+        metrics::gauge!("casino_vault_balance_lamports", "deployment" => deployment.name.clone()).set(vault_balance as f64);
+        metrics::gauge!("treasury_balance_lamports", "deployment" => deployment.name.clone()).set(treasury_balance as f64);
+        if treasury_balance < topup.treasury_low_balance_lamports {
+            metrics::counter!("treasury_low_balance_total", "deployment" => deployment.name.clone()).increment(1);
+            error!(
+                deployment = %deployment.name,
+                treasury = %treasury,
+                treasury_balance,
+                threshold = topup.treasury_low_balance_lamports,
+                "Treasury balance low; fund the treasury"
+            );
+        }
+
+        let amount = match plan(vault_balance, treasury_balance, topup.floor_lamports, topup.target_lamports) {
+            TopUp::NotNeeded => return Ok(()),
+            TopUp::Fund(amount) => amount,
+            TopUp::TreasuryEmpty => {
+                metrics::counter!(
+                    "casino_topups_total",
+                    "deployment" => deployment.name.clone(),
+                    "outcome" => "treasury_empty"
+                )
+                .increment(1);
+                error!(
+                    deployment = %deployment.name,
+                    vault_balance,
+                    treasury = %treasury,
+                    treasury_balance,
+                    "Casino vault below floor and the treasury cannot fund a top-up"
+                );
+                return Ok(());
+            }
+        };
+
+        let instruction =
+            build_fund_casino_vault_instruction(&program_id, &discriminator, &casino, &casino_vault, &treasury, amount);
+        let outcome = match &self.treasury_keypair {
+            Some(treasury_keypair) => {
+                // The program accepts the casino's treasury or its authority
+                if treasury != casino_account.treasury && treasury != casino_account.authority {
+                    bail!(
+                        "Treasury keypair {} is neither the casino treasury {} nor its authority",
+                        treasury,
+                        casino_account.treasury
+                    );
+                }
+                let signature = self.send(instruction, treasury_keypair.clone()).await?;
+                info!(deployment = %deployment.name, amount, vault_balance, %signature, "Casino vault topped up");
+                "sent"
+            }
+            None => {
+                warn!(
+                    deployment = %deployment.name,
+                    amount,
+                    vault_balance,
+                    treasury = %treasury,
+                    instruction = %export_json(&instruction),
+                    "Casino vault below floor; sign this top-up (e.g. `ops-cli fund --lamports {}`)",
+                    amount
+                );
+                "exported"
+            }
+        };
+        metrics::counter!("casino_topups_total", "deployment" => deployment.name.clone(), "outcome" => outcome)
+            .increment(1);
+        Ok(())
+    }
+
+    /// Sign with the treasury; the processor pays the fee
+    async fn send(&self, instruction: Instruction, treasury: Arc<Keypair>) -> Result<String> {
+        let client = self.solana_client.get_client().await;
+        let payer = self.processor_keypair.clone();
+        let blockhash_commitment = self.solana_client.commitments().blockhash;
+        tokio::task::spawn_blocking(move || {
+            let (blockhash, _) = client
+                .get_latest_blockhash_with_commitment(blockhash_commitment)
+                .context("Failed to fetch blockhash")?;
+            let transaction =
+                Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[&*payer, &*treasury], blockhash);
+            let signature = client
+                .send_and_confirm_transaction(&transaction)
+                .context("Failed to send and confirm top-up transaction")?;
+            Ok(signature.to_string())
+        })
+        .await
+        .context("Top-up submission task failed")?
+    }
+}
+
+/// The instruction as multisig tools import it
+fn export_json(instruction: &Instruction) -> serde_json::Value {
+    json!({
+        "program_id": instruction.program_id.to_string(),
+        "accounts": instruction.accounts.iter().map(|meta| json!({
+            "pubkey": meta.pubkey.to_string(),
+            "is_signer": meta.is_signer,
+            "is_writable": meta.is_writable,
+        })).collect::<Vec<_>>(),
+        "data_base58": solana_sdk::bs58::encode(&instruction.data).into_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_tops_up_to_target_within_treasury() {
+        let sol = 1_000_000_000;
+        assert_eq!(plan(20 * sol, 100 * sol, 10 * sol, 50 * sol), TopUp::NotNeeded);
+        assert_eq!(plan(10 * sol, 100 * sol, 10 * sol, 50 * sol), TopUp::NotNeeded);
+        assert_eq!(plan(4 * sol, 100 * sol, 10 * sol, 50 * sol), TopUp::Fund(46 * sol));
+
+        // A target under the floor still lifts the vault to the floor
+        assert_eq!(plan(4 * sol, 100 * sol, 10 * sol, 0), TopUp::Fund(6 * sol));
+
+        // A short treasury funds what it can spare, keeping its reserve
+        assert_eq!(plan(4 * sol, 20 * sol, 10 * sol, 50 * sol), TopUp::Fund(20 * sol - TREASURY_RESERVE_LAMPORTS));
+        assert_eq!(plan(4 * sol, TREASURY_RESERVE_LAMPORTS, 10 * sol, 50 * sol), TopUp::TreasuryEmpty);
+    }
+}
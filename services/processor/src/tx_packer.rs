@@ -0,0 +1,219 @@
+//! Packing settlements into transactions
+//!
+//! Chunking by count fills a transaction of losing native SOL settlements
+//! with as many bets as one of SPL wins that may also create the casino's token
+//! account, so either space is wasted or the transaction is too large. Each
+//! settlement instead gets an estimate of the bytes and compute units it adds,
+//! from the instructions `solana_tx` will build for it, and transactions are
+//! packed first-fit decreasing: largest settlements first, each into the first
+//! transaction with room, under the packet size, the compute budget and the
+//! per-transaction bet cap.
+//!
+//! Estimates assume the casino token account of each SPL mint has to be
+//! created, once per transaction. User vault initialization is not counted;
+//! a batch that still does not fit is caught by `solana_tx` and shrinks the
+//! batch tuner's cap.
+
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use std::cmp::Reverse;
+
+use crate::blockchain_client::GameSettlementInfo;
+use crate::deployments::VaultDeployment;
+
+/// Signature, message header, blockhash, the compute budget instruction and
+/// the accounts every settlement shares (processor, casino, casino vault,
+/// vault authority, token config and the programs)
+const TX_BASE_BYTES: usize = 366;
+/// settle_bet: 3 new accounts (user vault, allowance, processed bet) and 13 account indices plus 60 bytes of data
+const SETTLE_BET_BYTES: usize = 172;
+/// spend_from_allowance: the same accounts with 52 bytes of data
+const LEGACY_SPEND_BYTES: usize = 164;
+/// payout of a legacy win: 10 account indices, no new accounts, 52 bytes of data
+const LEGACY_PAYOUT_BYTES: usize = 65;
+/// The user's token account
+const SPL_BYTES: usize = 32;
+/// Per mint: the casino token account, the token program, and creating the
+/// casino token account (mint, ATA program and rent sysvar)
+const MINT_OVERHEAD_BYTES: usize = 170;
+
+const SETTLE_BET_COMPUTE_UNITS: u64 = 30_000;
+const LEGACY_SPEND_COMPUTE_UNITS: u64 = 25_000;
+const LEGACY_PAYOUT_COMPUTE_UNITS: u64 = 20_000;
+/// Token transfer CPIs in place of lamport moves
+const SPL_COMPUTE_UNITS: u64 = 25_000;
+const MINT_OVERHEAD_COMPUTE_UNITS: u64 = 25_000;
+
+/// What a transaction may hold
+#[derive(Debug, Clone, Copy)]
+pub struct PackLimits {
+    pub max_bytes: usize,
+    pub max_compute_units: u64,
+    pub max_settlements: usize,
+}
+
+impl PackLimits {
+    pub fn new(max_compute_units: u64, max_settlements: usize) -> Self {
+        Self {
+            max_bytes: PACKET_DATA_SIZE,
+            max_compute_units,
+            max_settlements: max_settlements.max(1),
+        }
+    }
+}
+
+/// Estimated size and compute units one settlement adds to a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettlementCost {
+    pub bytes: usize,
+    pub compute_units: u64,
+    /// SPL mint, whose overhead is paid once per transaction
+    pub mint: Option<String>,
+}
+
+impl SettlementCost {
+    pub fn estimate(settlement: &GameSettlementInfo, deployment: &VaultDeployment) -> Self {
+        let won = settlement.outcome == "Win" && settlement.payout > 0;
+        let (mut bytes, mut compute_units) = if deployment.discriminators.settle_bet.is_some() {
+            (SETTLE_BET_BYTES, SETTLE_BET_COMPUTE_UNITS)
+        } else if won {
+            (
+                LEGACY_SPEND_BYTES + LEGACY_PAYOUT_BYTES,
+                LEGACY_SPEND_COMPUTE_UNITS + LEGACY_PAYOUT_COMPUTE_UNITS,
+            )
+        } else {
+            (LEGACY_SPEND_BYTES, LEGACY_SPEND_COMPUTE_UNITS)
+        };
+        let mint = (!settlement.token.eq_ignore_ascii_case("SOL")).then(|| settlement.token.clone());
+        if mint.is_some() {
+            bytes += SPL_BYTES;
+            compute_units += SPL_COMPUTE_UNITS;
+        }
+        Self { bytes, compute_units, mint }
+    }
+
+    /// Share of the tighter limit, to order settlements largest first
+    fn weight(&self, limits: &PackLimits) -> f64 {
+        let (mut bytes, mut compute_units) = (self.bytes, self.compute_units);
+        if self.mint.is_some() {
+            bytes += MINT_OVERHEAD_BYTES;
+            compute_units += MINT_OVERHEAD_COMPUTE_UNITS;
+        }
+        let by_bytes = bytes as f64 / limits.max_bytes.saturating_sub(TX_BASE_BYTES).max(1) as f64;
+        let by_compute = compute_units as f64 / limits.max_compute_units.max(1) as f64;
+        by_bytes.max(by_compute)
+    }
+}
+
+struct Transaction<T> {
+    items: Vec<T>,
+    bytes: usize,
+    compute_units: u64,
+    mints: Vec<String>,
+}
+
+impl<T> Transaction<T> {
+    fn new() -> Self {
+        Self { items: Vec::new(), bytes: TX_BASE_BYTES, compute_units: 0, mints: Vec::new() }
+    }
+
+    /// Bytes and compute units `cost` would add here
+    fn added(&self, cost: &SettlementCost) -> (usize, u64) {
+        match &cost.mint {
+            Some(mint) if !self.mints.contains(mint) => {
+                (cost.bytes + MINT_OVERHEAD_BYTES, cost.compute_units + MINT_OVERHEAD_COMPUTE_UNITS)
+            }
+            _ => (cost.bytes, cost.compute_units),
+        }
+    }
+
+    fn fits(&self, cost: &SettlementCost, limits: &PackLimits) -> bool {
+        let (bytes, compute_units) = self.added(cost);
+        self.items.len() < limits.max_settlements
+            && self.bytes + bytes <= limits.max_bytes
+            && self.compute_units + compute_units <= limits.max_compute_units
+    }
+
+    fn push(&mut self, item: T, cost: SettlementCost) {
+        let (bytes, compute_units) = self.added(&cost);
+        self.bytes += bytes;
+        self.compute_units += compute_units;
+        if let Some(mint) = cost.mint.filter(|mint| !self.mints.contains(mint)) {
+            self.mints.push(mint);
+        }
+        self.items.push(item);
+    }
+}
+
+/// Pack `items` into as few transactions as the limits allow. A settlement too
+/// large for any transaction still gets one of its own.
+pub fn pack<T>(items: Vec<T>, limits: PackLimits, cost: impl Fn(&T) -> SettlementCost) -> Vec<Vec<T>> {
+    let mut costed: Vec<(T, SettlementCost)> = items
+        .into_iter()
+        .map(|item| {
+            let cost = cost(&item);
+            (item, cost)
+        })
+        .collect();
+    // Stable, so equal settlements keep their fetch order
+    costed.sort_by_key(|(_, cost)| Reverse((cost.weight(&limits) * 1e9) as u64));
+
+    let mut transactions: Vec<Transaction<T>> = Vec::new();
+    for (item, cost) in costed {
+        match transactions.iter_mut().find(|tx| tx.fits(&cost, &limits)) {
+            Some(tx) => tx.push(item, cost),
+            None => {
+                let mut tx = Transaction::new();
+                tx.push(item, cost);
+                transactions.push(tx);
+            }
+        }
+    }
+
+    for tx in &transactions {
+        metrics::histogram!("tx_packer_settlements_per_tx").record(tx.items.len() as f64);
+        metrics::histogram!("tx_packer_estimated_bytes").record(tx.bytes as f64);
+    }
+    transactions.into_iter().map(|tx| tx.items).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(bytes: usize, mint: Option<&str>) -> SettlementCost {
+        SettlementCost { bytes, compute_units: 10_000, mint: mint.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_packs_by_size_compute_and_count() {
+        let limits = PackLimits::new(1_400_000, 12);
+
+        // Native SOL settle_bet settlements: as many as the packet holds
+        let small = pack(vec![(); 12], limits, |_| cost(SETTLE_BET_BYTES, None));
+        let per_tx = (PACKET_DATA_SIZE - TX_BASE_BYTES) / SETTLE_BET_BYTES;
+        assert_eq!(small[0].len(), per_tx);
+        assert_eq!(small.iter().map(Vec::len).sum::<usize>(), 12);
+
+        // Legacy wins are larger, so fewer fit; the small ones fill the gaps
+        let mixed = pack(vec![true, false, true, false, true, false], limits, |&win| {
+            cost(if win { LEGACY_SPEND_BYTES + LEGACY_PAYOUT_BYTES } else { LEGACY_SPEND_BYTES }, None)
+        });
+        assert!(mixed.iter().all(|tx| {
+            let bytes: usize = tx.iter().map(|&win| if win { 229 } else { 164 }).sum();
+            TX_BASE_BYTES + bytes <= PACKET_DATA_SIZE
+        }));
+        assert_eq!(mixed.len(), 2);
+
+        // A mint's overhead is paid once per transaction
+        let spl = pack(vec![(); 2], limits, |_| cost(SETTLE_BET_BYTES + SPL_BYTES, Some("USDC")));
+        assert_eq!(spl.len(), 1);
+
+        // Compute budget and the count cap bind too
+        let heavy = pack(vec![(); 3], PackLimits::new(25_000, 12), |_| cost(100, None));
+        assert_eq!(heavy.len(), 2);
+        assert_eq!(pack(vec![(); 5], PackLimits::new(1_400_000, 2), |_| cost(10, None)).len(), 3);
+
+        // Oversized settlements are not dropped
+        assert_eq!(pack(vec![(); 2], limits, |_| cost(PACKET_DATA_SIZE, None)).len(), 2);
+    }
+}
@@ -0,0 +1,95 @@
+//! Missing user vault detection
+//!
+//! Every settlement instruction takes the user's vault PDA, which users create
+//! themselves with `initialize_vault`. A settlement for a user without one can
+//! only land if the processor creates the vault first, with
+//! `initialize_vault_for` in the same transaction (`PROCESSOR_AUTO_INIT_VAULTS`).
+//! Otherwise it fails here with a clear error instead of on-chain.
+//!
+//! The same lookup reads the vault's payout address, which wins on it must be
+//! paid to (`set_payout_address`).
+
+use anyhow::{bail, Context, Result};
+use shared::vault::VaultAccount;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, instruction::Instruction, pubkey::Pubkey};
+
+use crate::deployments::VaultDeployment;
+use crate::solana_instructions::build_initialize_vault_for_instruction;
+use crate::solana_pda::{derive_casino_pda, derive_user_vault_pda};
+
+/// What a settlement needs to know about the user's vault
+#[derive(Debug, Default)]
+pub struct UserVault {
+    /// `initialize_vault_for`, to put ahead of the settlement when the vault is missing
+    pub init: Option<Instruction>,
+    /// Where the vault's winnings go instead of the vault itself
+    pub payout_address: Option<Pubkey>,
+}
+
+/// Look up `owner`'s vault: `initialize_vault_for` when it is missing and `auto_init`
+/// is on, its payout address when it exists
+pub fn vault_init_instruction(
+    client: &RpcClient,
+    commitment: CommitmentConfig,
+    deployment: &VaultDeployment,
+    owner: &Pubkey,
+    processor: &Pubkey,
+    auto_init: bool,
+) -> Result<UserVault> {
+    let program_id = deployment.program_id;
+    let (casino, _) = derive_casino_pda(&program_id);
+    let (user_vault, _) = derive_user_vault_pda(owner, &casino, &program_id);
+
+    let account = client
+        .get_account_with_commitment(&user_vault, commitment)
+        .with_context(|| format!("Failed to look up user vault {}", user_vault))?
+        .value;
+    if let Some(account) = account {
+        let vault = VaultAccount::decode(&account.data)
+            .with_context(|| format!("Failed to decode user vault {}", user_vault))?;
+        return Ok(UserVault { init: None, payout_address: vault.payout_address });
+    }
+
+    let discriminator = deployment.discriminators.initialize_vault_for.filter(|_| auto_init);
+    let action = if discriminator.is_some() { "initialize" } else { "fail" };
+    metrics::counter!(
+        "user_vaults_missing_total",
+        "deployment" => deployment.name.clone(),
+        "action" => action
+    )
+    .increment(1);
+
+    let Some(discriminator) = discriminator else {
+        if auto_init {
+            bail!(
+                "User vault {} for {} not initialized; vault deployment '{}' has no initialize_vault_for instruction",
+                user_vault,
+                owner,
+                deployment.name
+            );
+        }
+        bail!(
+            "User vault {} for {} not initialized (set PROCESSOR_AUTO_INIT_VAULTS=true to create it)",
+            user_vault,
+            owner
+        );
+    };
+
+    tracing::info!(
+        user = %owner,
+        user_vault = %user_vault,
+        deployment = %deployment.name,
+        "User vault missing; initializing it with the settlement"
+    );
+    let init = build_initialize_vault_for_instruction(
+        &program_id,
+        &discriminator,
+        &user_vault,
+        &casino,
+        processor,
+        owner,
+    );
+    // A new vault has no payout address yet
+    Ok(UserVault { init: Some(init), payout_address: None })
+}
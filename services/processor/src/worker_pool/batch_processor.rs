@@ -2,28 +2,57 @@
 //!
 //! Handles the full lifecycle of batch processing: fetch from blockchain, execute on Solana, update blockchain.
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use reqwest::Client;
+use simulation::{Outcome, Simulator};
 use solana_sdk::signature::Keypair;
 use std::sync::Arc;
 use std::str::FromStr;
 use uuid::Uuid;
 
+use crate::batch_tuner::BatchSizeTuner;
 use crate::circuit_breaker::CircuitBreaker;
-use crate::config::Config;
-use crate::domain::Bet;
+use crate::compute_budget::ComputeUnitEstimator;
+use crate::config::{Config, SolanaConfig};
+use crate::coordinator::BatchType;
+use crate::deployments::VaultDeployment;
+use crate::domain::{Bet, BetStatus};
 use crate::retry_strategy::RetryStrategy;
 use crate::solana_client::SolanaClientPool;
+use crate::settlement_webhook::{SettlementEvent, SettlementWebhook};
+use crate::simulation_failures::SimulationFailureCache;
+use crate::solana_error_mapper::map_solana_error;
+use crate::telemetry::{self, SettlementLabels, POLLED_BATCH_TYPE};
+use crate::tx_packer::{self, PackLimits, SettlementCost};
 use crate::blockchain_client::{BlockchainClient, GameSettlementInfo};
+use crate::write_locks::WriteLockScheduler;
+use crate::retry_budget::{classify_failure, RetryBudget};
 
 /// Orchestrates batch processing for a worker
 #[derive(Clone)]
 pub struct BatchProcessor {
     pub solana_client: Arc<SolanaClientPool>,
     pub processor_keypair: Arc<Keypair>,
+    #[allow(dead_code)]
     pub http: Client,
+    #[allow(dead_code)]
     pub retry_strategy: RetryStrategy,
     pub circuit_breaker: Arc<CircuitBreaker>,
+    pub blockchain_client: Arc<BlockchainClient>,
+    /// Draws coinflip outcomes from the configured server seed
+    pub simulator: Simulator,
+    /// Bets per transaction, adapted from simulated size and compute units
+    pub batch_tuner: Arc<BatchSizeTuner>,
+    /// Compute unit limits per transaction shape
+    pub compute_estimator: Arc<ComputeUnitEstimator>,
+    /// Wallets whose bets recently failed simulation, left out of batches for a while
+    pub simulation_failures: Arc<SimulationFailureCache>,
+    /// Told about completed and permanently failed settlements
+    pub webhook: Option<Arc<SettlementWebhook>>,
+    /// Holds permanent failures as retryable when too many settlements fail permanently
+    pub retry_budget: Option<Arc<RetryBudget>>,
+    /// Staggers transactions writing the same casino vault or allowance
+    pub write_locks: Option<Arc<WriteLockScheduler>>,
     pub config: Config,
 }
 
@@ -40,17 +69,34 @@ impl BatchProcessor {
         );
         let _enter = span.enter();
 
-        // Create blockchain client
-        let blockchain_client = BlockchainClient::new(
-            self.config.blockchain.api_base_url.clone(),
-            self.config.blockchain.api_key.clone(),
-        );
+        let blockchain_client = &self.blockchain_client;
 
         // Phase 1: Fetch pending settlements from blockchain
         let settlements = blockchain_client
             .fetch_pending_settlements(self.config.blockchain.settlement_batch_size)
             .await?;
 
+        // Bets known to fail simulation stay pending until their fingerprint expires
+        let settlements: Vec<GameSettlementInfo> = settlements
+            .into_iter()
+            .filter(|s| {
+                let Ok(wallet) = solana_sdk::pubkey::Pubkey::from_str(&s.player_address) else {
+                    return true;
+                };
+                match self.simulation_failures.matching(&wallet) {
+                    Some(fingerprint) => {
+                        tracing::debug!(
+                            tx_id = s.transaction_id,
+                            error = %fingerprint.error,
+                            "Skipping settlement with a known simulation failure"
+                        );
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .collect();
+
         if settlements.is_empty() {
             tracing::trace!("No pending settlements to process");
             return Ok(());
@@ -58,23 +104,45 @@ impl BatchProcessor {
 
         tracing::info!(
             settlement_count = settlements.len(),
-            max_bets_per_tx = self.config.processor.max_bets_per_tx,
             "Processing batch of pending settlements from blockchain"
         );
 
         metrics::gauge!("pending_settlements_fetched").set(settlements.len() as f64);
 
-        // Phase 2: Split into chunks for Solana (max 12 bets per transaction)
-        let max_per_tx = self.config.processor.max_bets_per_tx.max(1);
+        // Phase 2: Split payouts from spends, group by vault deployment and pack into
+        // transactions by estimated size and compute units, within each type's limits
+        let settlement_count = settlements.len();
+        let (wins, losses): (Vec<_>, Vec<_>) = settlements.into_iter().partition(|s| s.outcome == "Win");
+        let mut chunks = Vec::new();
+        for (batch_type, group) in [(BatchType::Payout, wins), (BatchType::Spend, losses)] {
+            let max_per_tx = self.batch_tuner.current().min(self.config.processor.limits_for(batch_type).max_per_tx);
+            let limits = PackLimits::new(self.config.processor.batch_compute_unit_limit, max_per_tx);
+            chunks.extend(
+                chunk_by_deployment(&group, &self.config.solana, limits)
+                    .into_iter()
+                    .map(|(deployment, chunk)| (batch_type, deployment, chunk)),
+            );
+        }
 
-        for (chunk_idx, chunk) in settlements.chunks(max_per_tx).enumerate() {
+        for (chunk_idx, (batch_type, deployment, chunk)) in chunks.iter().enumerate() {
             let chunk_span = tracing::info_span!(
                 "process_chunk",
                 chunk_idx,
-                chunk_size = chunk.len()
+                chunk_size = chunk.len(),
+                batch_type = batch_type.as_str(),
+                deployment = %deployment.name
             );
             let _chunk_enter = chunk_span.enter();
 
+            // Pending settlements are picked up again once the processor is upgraded
+            let program_version = match self.solana_client.ensure_program_supported(deployment).await {
+                Ok(version) => version,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Skipping chunk for unsupported vault program");
+                    continue;
+                }
+            };
+
             // Convert settlements to Bet format
             let bets: Vec<Bet> = chunk
                 .iter()
@@ -82,7 +150,8 @@ impl BatchProcessor {
                 .collect::<Result<Vec<_>>>()?;
 
             // Execute on Solana
-            let result = self.execute_settlements_on_solana(&bets).await;
+            let chunk_started = std::time::Instant::now();
+            let result = self.execute_settlements_on_solana(&bets, deployment, program_version, *batch_type).await;
 
             match result {
                 Ok((signature, results)) => {
@@ -93,11 +162,14 @@ impl BatchProcessor {
                     );
 
                     // Phase 3: Update settlement statuses on blockchain
-                    for (settlement, (bet_id, won, payout)) in chunk.iter().zip(results.iter()) {
+                    for (settlement, outcome) in chunk.iter().zip(results.iter()) {
+                        let Outcome { bet_id, won, payout, proof } = outcome;
+                        let labels = SettlementLabels::new(settlement, worker_id, POLLED_BATCH_TYPE);
                         match blockchain_client
                             .update_settlement_status(
                                 settlement.transaction_id,
-                                "SettlementComplete",
+                                &BetStatus::Batched,
+                                &BetStatus::Completed,
                                 Some(signature.clone()),
                                 None, // No error on success
                                 settlement.version,
@@ -107,15 +179,23 @@ impl BatchProcessor {
                             .await
                         {
                             Ok(new_version) => {
+                                if let Some(webhook) = &self.webhook {
+                                    webhook.emit(SettlementEvent::completed(settlement.transaction_id, signature.clone()));
+                                }
+                                if let Some(retry_budget) = &self.retry_budget {
+                                    retry_budget.record_success();
+                                }
                                 tracing::info!(
                                     tx_id = settlement.transaction_id,
                                     bet_id = %bet_id,
                                     won,
                                     payout,
+                                    server_seed_hash = %proof.server_seed_hash,
                                     new_version,
                                     signature = %signature,
                                     "Settlement completed and status updated on blockchain"
                                 );
+                                telemetry::record_settlement(&labels, true, chunk_started.elapsed());
                             }
                             Err(e) => {
                                 let error_str = e.to_string();
@@ -128,6 +208,7 @@ impl BatchProcessor {
                                         "Settlement already updated by another worker - skipping"
                                     );
                                     metrics::counter!("settlement_duplicate_processing_total").increment(1);
+                                    telemetry::record_settlement(&labels, true, chunk_started.elapsed());
                                 } else {
                                     tracing::error!(
                                         tx_id = settlement.transaction_id,
@@ -137,13 +218,15 @@ impl BatchProcessor {
                                         "CRITICAL: Failed to update settlement status (Solana succeeded but blockchain update failed)"
                                     );
                                     metrics::counter!("settlement_status_update_failures_total").increment(1);
+                                    telemetry::record_settlement(&labels, false, chunk_started.elapsed());
                                 }
                                 // Continue processing other settlements even if one update fails
                             }
                         }
                     }
 
-                    metrics::counter!("settlements_processed_total").increment(chunk.len() as u64);
+                    metrics::counter!("settlements_processed_total", "deployment" => deployment.name.clone())
+                        .increment(chunk.len() as u64);
                 }
                 Err(e) => {
                     tracing::error!(
@@ -154,29 +237,37 @@ impl BatchProcessor {
                     );
 
                     // Update all settlements in this chunk as failed
+                    let failure = map_solana_error(&e);
+                    failure.record_if_lost_to_expiry(chunk.len());
                     for settlement in chunk {
-                        let error_msg = format!("Solana transaction failed: {}", e);
-                        
-                        // Calculate retry logic: max 3 retries with 5s, 10s, 15s backoff
+                        telemetry::record_settlement(
+                            &SettlementLabels::new(settlement, worker_id, POLLED_BATCH_TYPE),
+                            false,
+                            chunk_started.elapsed(),
+                        );
+                        // Calculate retry logic: max 3 retries with 5s, 10s, 15s backoff,
+                        // unless the contract error can never succeed
                         let new_retry_count = settlement.retry_count + 1;
-                        let (status, next_retry_after) = if new_retry_count >= 3 {
-                            ("SettlementFailedPermanent", None)
+                        let status =
+                            classify_failure(self.retry_budget.as_deref(), failure.retryable, new_retry_count).await;
+                        let next_retry_after = if status == BetStatus::FailedManualReview {
+                            None
                         } else {
                             let backoff_seconds = (new_retry_count as i64) * 5;
                             let now_ms = std::time::SystemTime::now()
                                 .duration_since(std::time::UNIX_EPOCH)
                                 .unwrap()
                                 .as_millis() as i64;
-                            let retry_after = now_ms + (backoff_seconds * 1000);
-                            ("SettlementFailed", Some(retry_after))
+                            Some(now_ms + (backoff_seconds * 1000))
                         };
                         
                         match blockchain_client
                             .update_settlement_status(
                                 settlement.transaction_id,
-                                status,
+                                &BetStatus::Batched,
+                                &status,
                                 None,
-                                Some(error_msg.clone()),
+                                Some(failure.error.clone()),
                                 settlement.version,
                                 Some(new_retry_count),
                                 next_retry_after,
@@ -184,10 +275,14 @@ impl BatchProcessor {
                             .await
                         {
                             Ok(new_version) => {
+                                if let Some(webhook) = self.webhook.as_ref().filter(|_| status == BetStatus::FailedManualReview) {
+                                    webhook.emit(SettlementEvent::failed_permanent(settlement.transaction_id, &failure.error));
+                                }
                                 tracing::warn!(
                                     tx_id = settlement.transaction_id,
                                     new_version,
-                                    error = %error_msg,
+                                    error = %failure.error,
+                                    retryable = failure.retryable,
                                     "Settlement marked as failed on blockchain"
                                 );
                             }
@@ -211,7 +306,9 @@ impl BatchProcessor {
                         }
                     }
 
-                    metrics::counter!("settlement_chunk_failures_total").increment(1);
+                    metrics::counter!("settlement_chunk_failures_total", "deployment" => deployment.name.clone())
+                        .increment(1);
+                    telemetry::record_batch(worker_id, false, start_time.elapsed());
 
                     // Stop processing this batch
                     return Err(e);
@@ -222,12 +319,11 @@ impl BatchProcessor {
         let elapsed = start_time.elapsed();
         tracing::info!(
             duration_ms = elapsed.as_millis(),
-            settlement_count = settlements.len(),
+            settlement_count,
             "Batch completed successfully"
         );
 
-        metrics::histogram!("batch_processing_duration_seconds").record(elapsed.as_secs_f64());
-        metrics::counter!("batches_processed_total").increment(1);
+        telemetry::record_batch(worker_id, true, elapsed);
 
         Ok(())
     }
@@ -240,12 +336,12 @@ impl BatchProcessor {
             user_wallet: settlement.player_address.clone(),
             vault_address: String::new(), // Will be derived in Solana tx building
             allowance_pda: settlement.allowance_pda.clone(), // Use allowance from blockchain
-            casino_id: None,
+            casino_id: settlement.casino_id.clone(),
             game_type: settlement.game_type.clone(),
             stake_amount: settlement.bet_amount as i64,
             stake_token: settlement.token.clone(),
             choice: "heads".to_string(), // Not relevant for settlements (already determined)
-            status: crate::domain::BetStatus::Pending,
+            status: BetStatus::Pending,
             external_batch_id: None,
             solana_tx_id: None,
             retry_count: 0,
@@ -254,6 +350,9 @@ impl BatchProcessor {
             last_error_message: None,
             payout_amount: Some(settlement.payout as i64),
             won: Some(settlement.outcome == "Win"),
+            server_seed_hash: None,
+            client_seed: None,
+            metadata: None,
         })
     }
 
@@ -261,10 +360,14 @@ impl BatchProcessor {
     async fn execute_settlements_on_solana(
         &self,
         bets: &[Bet],
-    ) -> Result<(String, Vec<(Uuid, bool, i64)>)> {
+        deployment: &VaultDeployment,
+        program_version: u32,
+        batch_type: BatchType,
+    ) -> Result<(String, Vec<Outcome>)> {
         let span = tracing::debug_span!(
             "execute_settlements_on_solana",
-            bet_count = bets.len()
+            bet_count = bets.len(),
+            deployment = %deployment.name
         );
         let _enter = span.enter();
 
@@ -289,22 +392,64 @@ impl BatchProcessor {
             .get_healthy_client_or_any()
             .await
             .ok_or_else(|| anyhow::anyhow!("No RPC clients configured"))?;
+        let broadcast = self.solana_client.shadow_broadcast(&client).await;
 
-        // Get vault program ID from environment
-        let vault_program_id = solana_sdk::pubkey::Pubkey::from_str(
-            &std::env::var("VAULT_PROGRAM_ID").context("VAULT_PROGRAM_ID not set")?
-        )
-        .context("Invalid VAULT_PROGRAM_ID")?;
+        // Load-test latency and failures; `instant` adds neither
+        let behavior = &self.config.simulation.behavior;
+        let (delay, fail) = behavior.next_settlement();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        if fail {
+            metrics::counter!("simulated_settlement_failures_total").increment(1);
+            // Worded as an RPC timeout so it is retried like one
+            anyhow::bail!("Simulated RPC timeout (simulation profile {})", behavior.name);
+        }
 
         // Submit batch transaction to Solana
         tracing::info!(bet_count = bets.len(), "Submitting batch to Solana");
+        let limits = self.config.processor.limits_for(batch_type);
         crate::solana_tx::submit_batch_transaction(
             &client,
+            &broadcast,
+            self.solana_client.commitments(),
             bets,
             &self.processor_keypair,
-            &vault_program_id,
-            self.config.processor.max_bets_per_tx,
+            deployment,
+            program_version,
+            &self.simulator,
+            &self.batch_tuner,
+            &self.compute_estimator,
+            &self.simulation_failures,
+            limits.max_per_tx,
+            limits.priority_fee(self.config.processor.priority_fee_micro_lamports),
+            self.write_locks.as_deref(),
+            self.config.processor.auto_init_vaults,
         )
         .await
     }
-}
\ No newline at end of file
+}
+
+/// Group settlements by the vault deployment of their casino, then pack each
+/// group into transactions; a transaction only targets one program
+fn chunk_by_deployment<'a>(
+    settlements: &[GameSettlementInfo],
+    solana: &'a SolanaConfig,
+    limits: PackLimits,
+) -> Vec<(&'a VaultDeployment, Vec<GameSettlementInfo>)> {
+    solana
+        .deployments
+        .iter()
+        .flat_map(|deployment| {
+            let group: Vec<_> = settlements
+                .iter()
+                .filter(|s| solana.deployment_for(s.casino_id.as_deref()).name == deployment.name)
+                .cloned()
+                .collect();
+            tx_packer::pack(group, limits, |s| SettlementCost::estimate(s, deployment))
+                .into_iter()
+                .map(|chunk| (deployment, chunk))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
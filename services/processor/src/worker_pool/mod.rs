@@ -6,12 +6,15 @@
 mod pool;
 mod worker;
 mod batch_processor;
-mod backend_client;
 
 // Re-export the main interface
 pub use pool::WorkerPool;
 
 // Re-export components that might be useful for testing
+#[allow(unused_imports)]
 pub use worker::Worker;
+#[allow(unused_imports)]
 pub use batch_processor::BatchProcessor;
-pub use backend_client::BackendClient;
\ No newline at end of file
+// Backend API calls go through the typed client shared with other services
+#[allow(unused_imports)]
+pub use atomiq_client::{BackendApi, HttpBackend};
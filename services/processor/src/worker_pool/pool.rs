@@ -3,17 +3,27 @@
 //! Manages multiple workers for parallel bet processing.
 
 use anyhow::Result;
+use shared::clock::Clock;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::batch_tuner::BatchSizeTuner;
+use crate::claim_signals::ClaimSignalSource;
+use crate::blockchain_client::BlockchainClient;
+use crate::compute_budget::ComputeUnitEstimator;
 use crate::config::Config;
+use crate::simulation_failures::SimulationFailureCache;
+use crate::retry_budget::RetryBudget;
+use crate::settlement_webhook::SettlementWebhook;
 use crate::solana_client::SolanaClientPool;
+use crate::write_locks::WriteLockScheduler;
 use solana_sdk::signature::Keypair;
 
 use super::worker::Worker;
 
 /// Pool of workers for processing bets
 pub struct WorkerPool {
+    #[allow(dead_code)]
     config: Config,
     workers: Vec<Worker>,
     running: Arc<RwLock<bool>>,
@@ -25,8 +35,15 @@ impl WorkerPool {
         config: Config,
         solana_client: Arc<SolanaClientPool>,
         processor_keypair: Keypair,
+        blockchain_client: Arc<BlockchainClient>,
+        batch_tuner: Arc<BatchSizeTuner>,
     ) -> Self {
         let processor_keypair = Arc::new(processor_keypair);
+        // Shared so every worker benefits from estimates already measured
+        let compute_estimator = Arc::new(ComputeUnitEstimator::new(config.processor.compute_unit_margin_percent));
+        let simulation_failures = Arc::new(SimulationFailureCache::new(std::time::Duration::from_secs(
+            config.processor.simulation_failure_ttl_seconds,
+        )));
         let mut workers = Vec::new();
 
         for id in 0..config.processor.worker_count {
@@ -35,6 +52,10 @@ impl WorkerPool {
                 config.clone(),
                 solana_client.clone(),
                 processor_keypair.clone(),
+                blockchain_client.clone(),
+                batch_tuner.clone(),
+                compute_estimator.clone(),
+                simulation_failures.clone(),
             ));
         }
 
@@ -45,6 +66,48 @@ impl WorkerPool {
         }
     }
 
+    /// Push completed and permanently failed settlements to the settlement webhook
+    pub fn with_webhook(mut self, webhook: Option<Arc<SettlementWebhook>>) -> Self {
+        self.workers = self.workers.into_iter().map(|worker| worker.with_webhook(webhook.clone())).collect();
+        self
+    }
+
+    /// Hold permanent failures as retryable while the retry budget is tripped
+    pub fn with_retry_budget(mut self, retry_budget: Option<Arc<RetryBudget>>) -> Self {
+        self.workers = self
+            .workers
+            .into_iter()
+            .map(|worker| worker.with_retry_budget(retry_budget.clone()))
+            .collect();
+        self
+    }
+
+    /// Wake workers on the backend's claim signals
+    pub fn with_claim_signals(mut self, claim_signals: Option<Arc<ClaimSignalSource>>) -> Self {
+        self.workers = self
+            .workers
+            .into_iter()
+            .map(|worker| worker.with_claim_signals(claim_signals.clone()))
+            .collect();
+        self
+    }
+
+    /// Stagger batch transactions contending for the same write lock
+    pub fn with_write_locks(mut self, write_locks: Option<Arc<WriteLockScheduler>>) -> Self {
+        self.workers = self
+            .workers
+            .into_iter()
+            .map(|worker| worker.with_write_locks(write_locks.clone()))
+            .collect();
+        self
+    }
+
+    /// Time the workers' circuit breakers on `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.workers = self.workers.into_iter().map(|worker| worker.with_clock(clock.clone())).collect();
+        self
+    }
+
     /// Start all workers
     pub async fn start(&self) -> Result<()> {
         let mut running = self.running.write().await;
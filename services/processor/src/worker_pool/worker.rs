@@ -4,36 +4,61 @@
 
 use anyhow::Result;
 use reqwest::Client;
+use shared::clock::Clock;
+use simulation::Simulator;
 use solana_sdk::signature::Keypair;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::time::{interval, Duration};
 
+use crate::batch_tuner::BatchSizeTuner;
+use crate::blockchain_client::BlockchainClient;
+use crate::compute_budget::ComputeUnitEstimator;
 use crate::circuit_breaker::CircuitBreaker;
+use crate::claim_signals::ClaimSignalSource;
 use crate::config::Config;
 use crate::retry_strategy::RetryStrategy;
+use crate::retry_budget::RetryBudget;
+use crate::settlement_webhook::SettlementWebhook;
+use crate::simulation_failures::SimulationFailureCache;
+use crate::telemetry;
 use crate::solana_client::SolanaClientPool;
+use crate::write_locks::WriteLockScheduler;
 
 use super::batch_processor::BatchProcessor;
 
+/// Failures in a row that open a worker's circuit breaker
+const CIRCUIT_BREAKER_FAILURES: u64 = 5;
+/// Seconds an open circuit breaker waits before letting a call through
+const CIRCUIT_BREAKER_RESET_SECONDS: u64 = 60;
+
 /// Individual worker for processing bets
 #[derive(Clone)]
 pub struct Worker {
     pub id: usize,
     batch_processor: BatchProcessor,
+    /// Runs a batch as soon as the backend signals a claimable bet
+    claim_signals: Option<Arc<ClaimSignalSource>>,
 }
 
 impl Worker {
     /// Create a new worker
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: usize,
         config: Config,
         solana_client: Arc<SolanaClientPool>,
         processor_keypair: Arc<Keypair>,
+        blockchain_client: Arc<BlockchainClient>,
+        batch_tuner: Arc<BatchSizeTuner>,
+        compute_estimator: Arc<ComputeUnitEstimator>,
+        simulation_failures: Arc<SimulationFailureCache>,
     ) -> Self {
         let http = Client::new();
-        let circuit_breaker = Arc::new(CircuitBreaker::new(5, 60));
+        let circuit_breaker = Arc::new(CircuitBreaker::new(CIRCUIT_BREAKER_FAILURES, CIRCUIT_BREAKER_RESET_SECONDS));
         let retry_strategy = RetryStrategy::new(config.processor.max_retries);
+        let simulator = Simulator::new(config.simulation.server_seed.clone())
+            .with_win_ratio(config.simulation.behavior.win_ratio);
 
         let batch_processor = BatchProcessor {
             solana_client,
@@ -41,15 +66,56 @@ impl Worker {
             http,
             retry_strategy,
             circuit_breaker,
+            blockchain_client,
+            simulator,
+            batch_tuner,
+            compute_estimator,
+            simulation_failures,
+            webhook: None,
+            retry_budget: None,
+            write_locks: None,
             config,
         };
 
         Self {
             id,
             batch_processor,
+            claim_signals: None,
         }
     }
 
+    /// Push completed and permanently failed settlements to the settlement webhook
+    pub fn with_webhook(mut self, webhook: Option<Arc<SettlementWebhook>>) -> Self {
+        self.batch_processor.webhook = webhook;
+        self
+    }
+
+    /// Hold permanent failures as retryable while the retry budget is tripped
+    pub fn with_retry_budget(mut self, retry_budget: Option<Arc<RetryBudget>>) -> Self {
+        self.batch_processor.retry_budget = retry_budget;
+        self
+    }
+
+    /// Stagger batch transactions contending for the same write lock
+    pub fn with_write_locks(mut self, write_locks: Option<Arc<WriteLockScheduler>>) -> Self {
+        self.batch_processor.write_locks = write_locks;
+        self
+    }
+
+    /// Time the circuit breaker's reset timeout on `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.batch_processor.circuit_breaker = Arc::new(
+            CircuitBreaker::new(CIRCUIT_BREAKER_FAILURES, CIRCUIT_BREAKER_RESET_SECONDS).with_clock(clock),
+        );
+        self
+    }
+
+    /// Run a batch on each claim signal as well as on the interval
+    pub fn with_claim_signals(mut self, claim_signals: Option<Arc<ClaimSignalSource>>) -> Self {
+        self.claim_signals = claim_signals;
+        self
+    }
+
     /// Run the worker's main processing loop
     pub async fn run(&self, running: Arc<RwLock<bool>>) -> Result<()> {
         tracing::info!("Worker {} started", self.id);
@@ -58,8 +124,20 @@ impl Worker {
             self.batch_processor.config.processor.batch_interval_seconds
         ));
 
+        let mut claim_waiter = self.claim_signals.as_ref().map(|signals| signals.waiter());
+
         loop {
-            ticker.tick().await;
+            match claim_waiter.as_mut() {
+                Some(waiter) => {
+                    tokio::select! {
+                        _ = ticker.tick() => {}
+                        _ = waiter.signalled() => {}
+                    }
+                }
+                None => {
+                    ticker.tick().await;
+                }
+            }
 
             let is_running = *running.read().await;
             if !is_running {
@@ -69,14 +147,14 @@ impl Worker {
             // Check circuit breaker
             if self.batch_processor.circuit_breaker.is_open().await {
                 tracing::warn!("Worker {}: Circuit breaker is open, skipping batch", self.id);
-                metrics::counter!("worker_circuit_breaker_open_total").increment(1);
+                telemetry::record_worker_circuit_open(self.id);
                 continue;
             }
 
             // Process batch
             if let Err(e) = self.batch_processor.process_batch(self.id).await {
                 tracing::error!("Worker {} batch processing error: {:?}", self.id, e);
-                metrics::counter!("worker_errors_total").increment(1);
+                telemetry::record_worker_error(self.id);
             }
 
             // Health check Solana RPC
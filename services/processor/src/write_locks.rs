@@ -0,0 +1,92 @@
+//! Write-lock aware submission scheduling
+//!
+//! A leader runs transactions that write the same account one after another,
+//! so payouts sent at once all queue behind the casino vault's write lock and
+//! the ones that miss their slot are re-sent on a new blockhash. Before its
+//! transaction goes out, a settlement takes a turn on the accounts that decide
+//! its conflicts: the casino vault for a payout, the user's allowance for a
+//! spend. Submissions sharing an account go out at least `stagger` apart;
+//! submissions with disjoint accounts are never held back.
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::coordinator::BatchType;
+
+/// Accounts tracked before turns already passed are dropped
+const PRUNE_AT: usize = 10_000;
+
+/// Next free submission turn per written account, shared by every worker
+#[derive(Debug)]
+pub struct WriteLockScheduler {
+    stagger: Duration,
+    next_turn: Mutex<HashMap<Pubkey, Instant>>,
+}
+
+impl WriteLockScheduler {
+    pub fn new(stagger: Duration) -> Self {
+        Self {
+            stagger,
+            next_turn: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserve the earliest turn free on all of `accounts`; the one after it goes to the next caller
+    fn reserve(&self, accounts: &[Pubkey], now: Instant) -> Instant {
+        let mut next_turn = self.next_turn.lock().unwrap_or_else(|e| e.into_inner());
+        if next_turn.len() >= PRUNE_AT {
+            next_turn.retain(|_, turn| *turn > now);
+        }
+        let turn = accounts
+            .iter()
+            .filter_map(|account| next_turn.get(account).copied())
+            .fold(now, Instant::max);
+        for account in accounts {
+            next_turn.insert(*account, turn + self.stagger);
+        }
+        turn
+    }
+
+    /// Wait until a `batch_type` transaction writing `accounts` may be submitted
+    pub async fn wait_turn(&self, accounts: &[Pubkey], batch_type: BatchType) {
+        let turn = self.reserve(accounts, Instant::now());
+        let wait = turn.saturating_duration_since(Instant::now());
+        metrics::histogram!("write_lock_wait_seconds", "batch_type" => batch_type.as_str()).record(wait.as_secs_f64());
+        if !wait.is_zero() {
+            metrics::counter!("write_lock_staggered_total", "batch_type" => batch_type.as_str()).increment(1);
+            tokio::time::sleep_until(turn).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflicting_turns_staggered_disjoint_not() {
+        let scheduler = WriteLockScheduler::new(Duration::from_millis(400));
+        let now = Instant::now();
+        let (casino_vault, alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        // Payouts all write the casino vault: each waits for the one before
+        assert_eq!(scheduler.reserve(&[casino_vault], now), now);
+        assert_eq!(scheduler.reserve(&[casino_vault], now), now + Duration::from_millis(400));
+        assert_eq!(scheduler.reserve(&[casino_vault], now), now + Duration::from_millis(800));
+
+        // Spends of different users go out at once
+        assert_eq!(scheduler.reserve(&[alice], now), now);
+        assert_eq!(scheduler.reserve(&[bob], now), now);
+
+        // A transaction writing several accounts waits for the busiest of them
+        assert_eq!(scheduler.reserve(&[alice, casino_vault], now), now + Duration::from_millis(1200));
+        assert_eq!(scheduler.reserve(&[alice], now), now + Duration::from_millis(1600));
+
+        // Turns already passed don't hold anything back
+        let later = now + Duration::from_secs(5);
+        assert_eq!(scheduler.reserve(&[casino_vault], later), later);
+    }
+}
@@ -1,7 +1,5 @@
 /// Integration tests for processor worker pool and batch processing
 use redis::{Client as RedisClient, Commands};
-use std::time::Duration;
-use tokio::time::sleep;
 use uuid::Uuid;
 
 /// Test context for processor tests
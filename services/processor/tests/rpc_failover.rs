@@ -1,7 +1,6 @@
 /// RPC failover and resilience tests
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use tokio::sync::Mutex;
 
 /// Mock RPC client that can simulate failures
 struct MockRpcClient {
@@ -47,7 +46,7 @@ async fn test_rpc_retry_on_failure() {
     let mut result = Err("Not attempted".to_string());
     let max_retries = 5;
     
-    for attempt in 0..max_retries {
+    for _ in 0..max_retries {
         result = client.send_transaction().await;
         if result.is_ok() {
             break;
@@ -66,7 +65,7 @@ async fn test_rpc_failover_to_backup() {
     let primary = MockRpcClient::new(100); // Always fails
     let backup = MockRpcClient::new(0);    // Always succeeds
     
-    let endpoints = vec![primary, backup];
+    let endpoints = [primary, backup];
     
     let mut result = Err("No endpoints tried".to_string());
     
@@ -165,6 +164,7 @@ async fn test_circuit_breaker_half_open_recovery() {
     }
     
     assert_eq!(circuit_state, "closed", "Circuit should close after successful test");
+    assert_eq!(consecutive_failures, 0, "Failure count should reset after recovery");
 }
 
 #[tokio::test]
@@ -198,7 +198,7 @@ async fn test_rate_limit_handling() {
     let client = MockRpcClient::new(0);
     
     let mut rate_limited = false;
-    let mut retry_after_ms = 1000;
+    let retry_after_ms = 1000;
     
     // Simulate receiving 429 error
     if client.get_request_count() % 10 == 9 {
@@ -227,14 +227,13 @@ async fn test_rpc_health_check() {
 
 #[tokio::test]
 async fn test_multiple_rpc_endpoints_load_balancing() {
-    let endpoints = vec![
+    let endpoints = [
         Arc::new(MockRpcClient::new(0)),
         Arc::new(MockRpcClient::new(0)),
         Arc::new(MockRpcClient::new(0)),
     ];
     
     let mut current_endpoint = 0;
-    let mut total_requests = 0;
     
     // Round-robin through endpoints
     for _ in 0..30 {
@@ -242,12 +241,11 @@ async fn test_multiple_rpc_endpoints_load_balancing() {
         let _result = client.send_transaction().await;
         
         current_endpoint = (current_endpoint + 1) % endpoints.len();
-        total_requests += 1;
     }
     
     // Each endpoint should have received ~10 requests
     for (i, endpoint) in endpoints.iter().enumerate() {
         let count = endpoint.get_request_count();
-        assert!(count >= 9 && count <= 11, "Endpoint {} should have ~10 requests, got {}", i, count);
+        assert!((9..=11).contains(&count), "Endpoint {} should have ~10 requests, got {}", i, count);
     }
 }
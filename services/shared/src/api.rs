@@ -0,0 +1,967 @@
+//! Request and response bodies of the backend REST API
+//!
+//! The backend serves these types and `atomiq-client` consumes them, so both
+//! sides of `/api/bets`, `/api/auth/token`, `/api/sessions`, `/api/tokens`,
+//! `/api/games` and `/api/external/*` agree on the wire format.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::types::LamportAmount;
+
+/// Opaque client data kept with a bet, e.g. client version or campaign tag
+pub type BetMetadata = BTreeMap<String, serde_json::Value>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BetStatus {
+    Pending,
+    Batched,
+    SubmittedToSolana,
+    ConfirmedOnSolana,
+    Completed,
+    FailedRetryable,
+    FailedManualReview,
+    /// Never claimed within the bet TTL; will not be settled
+    Expired,
+}
+
+impl BetStatus {
+    /// Statuses the settlement pipeline never moves a bet out of (only admins can)
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            BetStatus::Completed | BetStatus::FailedManualReview | BetStatus::Expired
+        )
+    }
+
+    /// Whether the settlement pipeline may move a bet from this status to `next`
+    ///
+    /// Pending bets are claimed into a batch or expire; a batch moves its bets
+    /// forward through submission and confirmation to a result, and retryable
+    /// failures are claimed again. Admin overrides (manual settlement, retries
+    /// of finished bets) are not pipeline transitions and aren't checked here.
+    pub fn can_transition_to(&self, next: &BetStatus) -> bool {
+        use BetStatus::*;
+        match self {
+            Pending => matches!(next, Batched | Expired),
+            Batched => matches!(
+                next,
+                SubmittedToSolana | ConfirmedOnSolana | Completed | FailedRetryable | FailedManualReview
+            ),
+            SubmittedToSolana => matches!(next, ConfirmedOnSolana | Completed | FailedRetryable | FailedManualReview),
+            ConfirmedOnSolana => matches!(next, Completed | FailedRetryable | FailedManualReview),
+            FailedRetryable => matches!(next, Batched | Completed | FailedManualReview),
+            Completed | FailedManualReview | Expired => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bet {
+    pub bet_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub user_wallet: String,
+    pub vault_address: String,
+    pub allowance_pda: Option<String>,
+    pub casino_id: Option<String>,
+    pub game_type: String,
+    pub stake_amount: i64,
+    pub stake_token: String,
+    pub choice: String,
+    pub status: BetStatus,
+    pub external_batch_id: Option<Uuid>,
+    pub solana_tx_id: Option<String>,
+    pub retry_count: i32,
+    pub processor_id: Option<String>,
+    pub last_error_code: Option<String>,
+    pub last_error_message: Option<String>,
+    pub payout_amount: Option<i64>,
+    pub won: Option<bool>,
+    /// Commitment of the server seed the outcome was drawn from; verifiable
+    /// once the seed is revealed
+    #[serde(default)]
+    pub server_seed_hash: Option<String>,
+    /// Player-chosen (or generated) seed mixed into the outcome
+    #[serde(default)]
+    pub client_seed: Option<String>,
+    /// As sent with `CreateBetRequest`
+    #[serde(default)]
+    pub metadata: Option<BetMetadata>,
+}
+
+impl Bet {
+    /// How a result reported for this bet by `batch_id` compares with its current state
+    ///
+    /// Only transitions `BetStatus::can_transition_to` allows are applied. A
+    /// replayed final result matching the bet's is skipped; anything else
+    /// touching a finished bet, any other illegal transition, or a result reported
+    /// by a batch the bet no longer belongs to conflicts.
+    pub fn diff_result(&self, batch_id: Uuid, result: &BetResult) -> (BetUpdateOutcome, Option<String>) {
+        if self.status.is_terminal() {
+            return if self.reflects_result(batch_id, result) {
+                (BetUpdateOutcome::SkippedTerminal, None)
+            } else {
+                (
+                    BetUpdateOutcome::Conflict,
+                    Some(format!("Bet already finished as {:?} with a different result", self.status)),
+                )
+            };
+        }
+        if let Some(owner) = self.external_batch_id.filter(|owner| *owner != batch_id) {
+            return (BetUpdateOutcome::Conflict, Some(format!("Bet now belongs to batch {}", owner)));
+        }
+        if !self.status.can_transition_to(&result.status) {
+            return (
+                BetUpdateOutcome::Conflict,
+                Some(format!("Cannot move bet from {:?} to {:?}", self.status, result.status)),
+            );
+        }
+        (BetUpdateOutcome::Applied, None)
+    }
+
+    /// Whether the bet already holds `result` as reported by `batch_id`, e.g.
+    /// because the update carrying it was applied and is now being resent
+    pub fn reflects_result(&self, batch_id: Uuid, result: &BetResult) -> bool {
+        result.status == self.status
+            && result.won.is_none_or(|won| Some(won) == self.won)
+            && result.payout_amount.is_none_or(|payout| Some(payout) == self.payout_amount)
+            && (self.status.is_terminal() || self.external_batch_id.is_none_or(|owner| owner == batch_id))
+    }
+}
+
+/// Commitment a settlement transaction reached on-chain; a failed transaction
+/// ranks below every other status
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationStatus {
+    Failed,
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl ConfirmationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfirmationStatus::Failed => "failed",
+            ConfirmationStatus::Processed => "processed",
+            ConfirmationStatus::Confirmed => "confirmed",
+            ConfirmationStatus::Finalized => "finalized",
+        }
+    }
+
+    /// Whether the transaction's effects are in a confirmed block
+    pub fn is_landed(&self) -> bool {
+        matches!(self, ConfirmationStatus::Confirmed | ConfirmationStatus::Finalized)
+    }
+
+    /// Whether the status can't change any more
+    pub fn is_final(&self) -> bool {
+        matches!(self, ConfirmationStatus::Finalized | ConfirmationStatus::Failed)
+    }
+}
+
+/// Transactions that moved a bet's funds, from processor reports and the reconciler
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SettlementReport {
+    /// Debited the stake from the user's allowance
+    #[serde(default)]
+    pub spend_tx: Option<String>,
+    /// Paid out a win; the same as `spend_tx` when one transaction did both
+    #[serde(default)]
+    pub payout_tx: Option<String>,
+    #[serde(default)]
+    pub confirmation_status: Option<ConfirmationStatus>,
+    /// Slot the settlement landed in
+    #[serde(default)]
+    pub slot: Option<u64>,
+}
+
+impl SettlementReport {
+    /// This report with the fields `newer` sets replaced
+    pub fn merge(self, newer: SettlementReport) -> Self {
+        Self {
+            spend_tx: newer.spend_tx.or(self.spend_tx),
+            payout_tx: newer.payout_tx.or(self.payout_tx),
+            confirmation_status: newer.confirmation_status.or(self.confirmation_status),
+            slot: newer.slot.or(self.slot),
+        }
+    }
+}
+
+/// How far a bet's funds have moved; whether it failed is in its status
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementPhase {
+    /// Waiting to settle; the allowance has not been debited
+    Queued,
+    /// The stake left the allowance on-chain, but the bet isn't complete yet
+    FundsMoved,
+    Complete,
+}
+
+/// `settlement` of a bet read
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BetSettlement {
+    pub phase: SettlementPhase,
+    pub spend_tx: Option<String>,
+    pub payout_tx: Option<String>,
+    pub confirmation_status: Option<ConfirmationStatus>,
+    pub slot: Option<u64>,
+}
+
+impl BetSettlement {
+    /// Settlement of `bet`, filling what `report` doesn't know from the bet itself:
+    /// its transaction both spends and pays out, and is confirmed once the bet is
+    pub fn of(bet: &Bet, report: Option<&SettlementReport>) -> Self {
+        let report = report.cloned().unwrap_or_default();
+        let spend_tx = report.spend_tx.or_else(|| bet.solana_tx_id.clone());
+        let paid = bet.won == Some(true) && bet.payout_amount.is_some_and(|payout| payout > 0);
+        let payout_tx = report.payout_tx.or_else(|| spend_tx.clone().filter(|_| paid));
+        let confirmed = matches!(bet.status, BetStatus::ConfirmedOnSolana | BetStatus::Completed);
+        let confirmation_status = report
+            .confirmation_status
+            .or_else(|| (confirmed && spend_tx.is_some()).then_some(ConfirmationStatus::Confirmed));
+        let phase = if bet.status == BetStatus::Completed {
+            SettlementPhase::Complete
+        } else if spend_tx.is_some() && confirmation_status.is_some_and(|status| status.is_landed()) {
+            SettlementPhase::FundsMoved
+        } else {
+            SettlementPhase::Queued
+        };
+        Self { phase, spend_tx, payout_tx, confirmation_status, slot: report.slot }
+    }
+}
+
+/// `GET /api/bets/:bet_id` body: the bet plus its settlement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BetDetail {
+    #[serde(flatten)]
+    pub bet: Bet,
+    pub settlement: BetSettlement,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateBetRequest {
+    pub user_wallet: Option<String>,
+    pub vault_address: Option<String>,
+    pub allowance_pda: Option<String>,
+    #[serde(deserialize_with = "deserialize_lamport_amount")]
+    pub stake_amount: LamportAmount,
+    pub stake_token: String,
+    /// One of the enabled games listed by `/api/games`
+    #[serde(default = "default_game_type")]
+    pub game_type: String,
+    pub choice: String,
+    /// Mixed into the outcome so the operator can't pick it alone; generated when absent
+    #[serde(default)]
+    pub client_seed: Option<String>,
+    /// Place the bet in this session, which supplies the wallet, vault and allowance
+    #[serde(default)]
+    pub session_id: Option<Uuid>,
+    /// Stored with the bet and returned in reads; capped in size and key count
+    #[serde(default)]
+    pub metadata: Option<BetMetadata>,
+}
+
+fn default_game_type() -> String {
+    "coinflip".to_string()
+}
+
+// Custom deserializer for LamportAmount from u64
+fn deserialize_lamport_amount<'de, D>(deserializer: D) -> Result<LamportAmount, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let amount_u64 = u64::deserialize(deserializer)?;
+    LamportAmount::try_from(amount_u64)
+        .map_err(|e| serde::de::Error::custom(format!("Invalid stake amount: {}", e)))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchStatus {
+    Created,
+    Submitted,
+    Confirmed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateBatchRequest {
+    pub status: BatchStatus,
+    pub solana_tx_id: Option<String>,
+    pub bet_results: Vec<BetResult>,
+    pub error_message: Option<String>,
+    /// Transaction fees paid for the batch, counted in the daily report
+    #[serde(default)]
+    pub fee_lamports: Option<i64>,
+    /// Processor that claimed the batch; updates from any other are rejected
+    #[serde(default)]
+    pub processor_id: Option<String>,
+    /// `batch_version` from the claim or the previous update; stale versions are rejected
+    #[serde(default)]
+    pub batch_version: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BetResult {
+    pub bet_id: Uuid,
+    pub status: BetStatus,
+    pub solana_tx_id: Option<String>,
+    pub error_message: Option<String>,
+    /// Shared `ErrorCode` string of the failure, e.g. `NETWORK_RPC_UNAVAILABLE`
+    #[serde(default)]
+    pub error_code: Option<String>,
+    pub won: Option<bool>,
+    pub payout_amount: Option<i64>,
+    /// Commitment of the server seed the outcome was drawn from
+    #[serde(default)]
+    pub server_seed_hash: Option<String>,
+    /// On-chain detail of the settlement, when the processor has it
+    #[serde(default)]
+    pub settlement: Option<SettlementReport>,
+}
+
+/// What a batch update did with one bet's result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BetUpdateOutcome {
+    Applied,
+    /// The bet already finished with this result, or already holds it from the
+    /// resent update; nothing changed
+    SkippedTerminal,
+    /// The result contradicts the bet's current state (a backward transition, a
+    /// different final outcome or another batch's bet) and was not applied
+    Conflict,
+    /// Not applied because storing it failed; resending it is safe
+    Error,
+}
+
+impl BetUpdateOutcome {
+    /// Whether sending the same result again could change anything
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, BetUpdateOutcome::Error)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BetUpdateResult {
+    pub bet_id: Uuid,
+    pub outcome: BetUpdateOutcome,
+    /// The bet's status once the update was handled; absent for unknown bets
+    #[serde(default)]
+    pub status: Option<BetStatus>,
+    /// Why the result was skipped or not applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateBatchResponse {
+    pub success: bool,
+    pub batch_id: Uuid,
+    /// Results applied
+    pub updated_count: usize,
+    /// Results in conflict or not stored
+    pub error_count: usize,
+    /// Version to send with the batch's next update
+    #[serde(default)]
+    pub batch_version: u64,
+    /// One entry per bet result, in request order
+    #[serde(default)]
+    pub results: Vec<BetUpdateResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingBetsResponse {
+    pub batch_id: Uuid,
+    pub processor_id: String,
+    pub bets: Vec<Bet>,
+    /// Version to send with the batch's first update
+    #[serde(default)]
+    pub batch_version: u64,
+}
+
+/// Name of the server-sent events of `GET /api/external/bets/subscribe`
+pub const CLAIMABLE_EVENT: &str = "claimable";
+
+/// Data of a `claimable` event: a bet processors can claim now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimableEvent {
+    pub bet_id: Uuid,
+    /// On the priority lane, e.g. an admin-initiated settlement
+    #[serde(default)]
+    pub priority: bool,
+}
+
+/// The bet terms covered by a receipt signature
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReceiptPayload {
+    pub bet_id: Uuid,
+    pub wallet: String,
+    pub stake: i64,
+    pub choice: String,
+    pub created_at: String,
+    /// Provably-fair commitment; absent on bets placed before commit-reveal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_seed_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_seed: Option<String>,
+}
+
+impl ReceiptPayload {
+    pub fn from_bet(bet: &Bet) -> Self {
+        Self {
+            bet_id: bet.bet_id,
+            wallet: bet.user_wallet.clone(),
+            stake: bet.stake_amount,
+            choice: bet.choice.clone(),
+            // Millisecond precision matches what is persisted, so the payload rebuilds exactly
+            created_at: bet.created_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+            server_seed_hash: bet.server_seed_hash.clone(),
+            client_seed: bet.client_seed.clone(),
+        }
+    }
+
+    /// Canonical bytes that are signed (JSON with fields in declaration order)
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("receipt payload serializes")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BetReceipt {
+    pub payload: ReceiptPayload,
+    /// Base58 detached ed25519 signature over `payload`
+    pub signature: String,
+    /// Base58 public key of the signer
+    pub signer: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefundStatus {
+    /// Waiting for a processor to run `refund_bet`
+    Pending,
+    Completed,
+    /// Rejected on-chain; needs an operator
+    Failed,
+}
+
+/// Stake refund of a bet voided after it settled as a loss
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Refund {
+    pub bet_id: Uuid,
+    pub user_wallet: String,
+    pub vault_address: String,
+    #[serde(default)]
+    pub casino_id: Option<String>,
+    pub stake_token: String,
+    /// Stake returned to the user's vault
+    pub amount: i64,
+    pub status: RefundStatus,
+    pub requested_by: String,
+    pub reason: String,
+    pub requested_at: DateTime<Utc>,
+    /// Attempts reported back so far
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub solana_tx_id: Option<String>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl Refund {
+    /// Record an attempt's outcome; `true` once the refund is final
+    ///
+    /// Results for a refund that is already final are ignored, so a replayed
+    /// report cannot reopen it.
+    pub fn apply(&mut self, result: &RefundResult, now: DateTime<Utc>) -> bool {
+        if self.status != RefundStatus::Pending {
+            return true;
+        }
+        self.attempts = self.attempts.saturating_add(1);
+        if result.solana_tx_id.is_some() {
+            self.solana_tx_id = result.solana_tx_id.clone();
+        }
+        if result.error_message.is_some() {
+            self.last_error = result.error_message.clone();
+        }
+        self.status = result.status;
+        if self.status == RefundStatus::Pending {
+            return false;
+        }
+        self.completed_at = Some(now);
+        true
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingRefundsResponse {
+    pub processor_id: String,
+    pub refunds: Vec<Refund>,
+}
+
+/// Outcome of one `refund_bet` attempt; `pending` asks for a later retry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefundResult {
+    pub processor_id: String,
+    pub status: RefundStatus,
+    pub solana_tx_id: Option<String>,
+    pub error_message: Option<String>,
+}
+
+/// Where a stake token's bet limits come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenLimitSource {
+    /// The casino's `TokenConfig` account for the mint
+    OnChain,
+    /// The program's built-in native SOL limits; no `TokenConfig` exists
+    Default,
+}
+
+/// Bet limits for one stake token, in the token's base units
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenInfo {
+    /// `SOL` or the mint address, as sent in `stake_token`
+    pub stake_token: String,
+    pub mint: String,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub bet_enabled: bool,
+    pub source: TokenLimitSource,
+    /// Unix seconds of the last `set_token_config`
+    pub updated_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokensResponse {
+    pub tokens: Vec<TokenInfo>,
+}
+
+/// A game bets may be placed on, and the rules bet creation enforces for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameConfig {
+    /// Sent as `game_type` when creating a bet
+    pub game_type: String,
+    /// Disabled games reject new bets; bets already placed still settle
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Smallest stake, as a multiple of the backend's minimum bet
+    #[serde(default = "default_stake_multiplier")]
+    pub min_stake_multiplier: f64,
+    /// Largest stake, as a multiple of the backend's maximum bet
+    #[serde(default = "default_stake_multiplier")]
+    pub max_stake_multiplier: f64,
+    /// Each valid choice and the multiple of the stake it pays when it wins
+    pub payout_table: BTreeMap<String, f64>,
+    /// Choices one bet may combine (comma-separated in `choice`)
+    #[serde(default = "default_max_choices")]
+    pub max_choices: usize,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_stake_multiplier() -> f64 {
+    1.0
+}
+
+fn default_max_choices() -> usize {
+    1
+}
+
+/// A game with its stake range resolved against the backend's bet limits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameInfo {
+    #[serde(flatten)]
+    pub config: GameConfig,
+    pub min_stake: u64,
+    pub max_stake: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GamesResponse {
+    pub games: Vec<GameInfo>,
+}
+
+/// Caps a session puts on top of its allowance's own limits
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionLimits {
+    /// Bets the session accepts; the backend's `SESSION_MAX_ROUNDS` when absent
+    #[serde(default)]
+    pub max_rounds: Option<u32>,
+    /// Total stake across the session's bets; unlimited when absent
+    #[serde(default)]
+    pub max_total_stake: Option<u64>,
+    #[serde(default)]
+    pub max_stake_per_bet: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSessionRequest {
+    pub user_wallet: String,
+    /// Allowance the session's bets spend from; approved once up front
+    pub allowance_pda: String,
+    #[serde(default)]
+    pub limits: SessionLimits,
+    /// Capped by, and by default equal to, the allowance's remaining lifetime
+    #[serde(default)]
+    pub duration_seconds: Option<i64>,
+}
+
+/// A run of bets against one allowance, e.g. "play N rounds" with one approval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub session_id: Uuid,
+    pub user_wallet: String,
+    pub vault_address: String,
+    pub allowance_pda: String,
+    /// `SOL` or the allowance's mint; every bet in the session stakes it
+    pub stake_token: String,
+    /// `max_rounds` is always set once the session exists
+    pub limits: SessionLimits,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Outcomes of a session's bets so far
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionResults {
+    pub rounds: u32,
+    /// Still moving through settlement
+    pub pending: u32,
+    pub won: u32,
+    pub lost: u32,
+    /// Expired or waiting for manual review; no outcome yet
+    pub unsettled: u32,
+    /// Stakes of won and lost bets
+    pub total_staked: i64,
+    pub total_payout: i64,
+    /// `total_payout - total_staked`
+    pub net: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionResponse {
+    pub session: Session,
+    pub rounds_remaining: u32,
+    /// Stake the session still accepts; absent when it has no total cap
+    pub stake_remaining: Option<u64>,
+    pub results: SessionResults,
+    /// Newest first
+    pub bets: Vec<Bet>,
+}
+
+/// Scope of the tokens issued by `POST /api/auth/token`: reading the wallet's own bets
+pub const WALLET_READ_SCOPE: &str = "bets:read";
+
+/// Message a wallet signs to get a read token, binding the wallet and the time of signing
+pub fn wallet_token_message(wallet: &str, issued_at: i64) -> String {
+    format!("Atomik: read access to the bets of {} (issued at {})", wallet, issued_at)
+}
+
+/// Body of `POST /api/auth/token`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletTokenRequest {
+    pub wallet: String,
+    /// Unix seconds; must be recent
+    pub issued_at: i64,
+    /// Base58 ed25519 signature of `wallet_token_message(wallet, issued_at)` by the wallet
+    pub signature: String,
+}
+
+/// Bearer token for the wallet's `GET /api/bets*` requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletTokenResponse {
+    pub token: String,
+    pub wallet: String,
+    pub scope: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Error body returned by every backend endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutHoldStatus {
+    /// Waiting for the hold delay to pass or an admin to approve it
+    PendingReview,
+    Approved,
+}
+
+/// Large payout the coordinator holds back until it is released
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutHold {
+    pub transaction_id: u64,
+    pub player_address: String,
+    /// Lamports
+    pub payout: u64,
+    pub status: PayoutHoldStatus,
+    pub held_at: DateTime<Utc>,
+    /// When the hold lapses without approval; `None` waits for an admin
+    #[serde(default)]
+    pub release_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub approved_by: Option<String>,
+    #[serde(default)]
+    pub approved_at: Option<DateTime<Utc>>,
+}
+
+impl PayoutHold {
+    /// Whether the payout may be dispatched at `now`
+    pub fn is_released(&self, now: DateTime<Utc>) -> bool {
+        self.status == PayoutHoldStatus::Approved || self.release_at.is_some_and(|at| at <= now)
+    }
+
+    pub fn approve(&mut self, operator: &str, now: DateTime<Utc>) {
+        self.status = PayoutHoldStatus::Approved;
+        self.approved_by = Some(operator.to_string());
+        self.approved_at = Some(now);
+    }
+}
+
+/// Permanent-failure retry budget that went over its threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryBudgetTrip {
+    pub tripped_at: DateTime<Utc>,
+    /// Processor instance whose window went over the budget
+    pub instance_id: String,
+    /// Outcomes in that window classified as permanent failures
+    pub permanent_failures: u64,
+    /// All settlement outcomes in that window
+    pub outcomes: u64,
+    pub window_seconds: u64,
+    pub threshold_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBody {
+    /// Shared `ErrorCode` string, e.g. `VALIDATION_INVALID_INPUT`
+    pub code: String,
+    pub message: String,
+    pub category: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn won_bet(status: BetStatus) -> Bet {
+        Bet {
+            bet_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            user_wallet: "wallet".to_string(),
+            vault_address: "vault".to_string(),
+            allowance_pda: None,
+            casino_id: None,
+            game_type: "coinflip".to_string(),
+            stake_amount: 100,
+            stake_token: "SOL".to_string(),
+            choice: "heads".to_string(),
+            status,
+            external_batch_id: None,
+            solana_tx_id: None,
+            retry_count: 0,
+            processor_id: None,
+            last_error_code: None,
+            last_error_message: None,
+            payout_amount: Some(200),
+            won: Some(true),
+            server_seed_hash: None,
+            client_seed: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_terminal_statuses() {
+        assert!(BetStatus::Completed.is_terminal());
+        assert!(BetStatus::FailedManualReview.is_terminal());
+        assert!(BetStatus::Expired.is_terminal());
+        assert!(!BetStatus::Pending.is_terminal());
+        assert!(!BetStatus::SubmittedToSolana.is_terminal());
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        use BetStatus::*;
+        let all = [
+            Pending,
+            Batched,
+            SubmittedToSolana,
+            ConfirmedOnSolana,
+            Completed,
+            FailedRetryable,
+            FailedManualReview,
+            Expired,
+        ];
+        let legal = [
+            (Pending, Batched),
+            (Pending, Expired),
+            (Batched, SubmittedToSolana),
+            (Batched, ConfirmedOnSolana),
+            (Batched, Completed),
+            (Batched, FailedRetryable),
+            (Batched, FailedManualReview),
+            (SubmittedToSolana, ConfirmedOnSolana),
+            (SubmittedToSolana, Completed),
+            (SubmittedToSolana, FailedRetryable),
+            (SubmittedToSolana, FailedManualReview),
+            (ConfirmedOnSolana, Completed),
+            (ConfirmedOnSolana, FailedRetryable),
+            (ConfirmedOnSolana, FailedManualReview),
+            (FailedRetryable, Batched),
+            (FailedRetryable, Completed),
+            (FailedRetryable, FailedManualReview),
+        ];
+        for from in &all {
+            for to in &all {
+                let expected = legal.iter().any(|(f, t)| f == from && t == to);
+                assert_eq!(from.can_transition_to(to), expected, "{:?} -> {:?}", from, to);
+            }
+            // Finished bets go nowhere
+            if from.is_terminal() {
+                assert!(all.iter().all(|to| !from.can_transition_to(to)), "{:?}", from);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bet_results_only_move_bets_forward() {
+        let batch_id = Uuid::new_v4();
+        let bet = |status: BetStatus| Bet { external_batch_id: Some(batch_id), ..won_bet(status) };
+        let result = |status: BetStatus, payout_amount: Option<i64>| BetResult {
+            bet_id: Uuid::nil(),
+            status,
+            solana_tx_id: None,
+            error_message: None,
+            error_code: None,
+            won: Some(true),
+            payout_amount,
+            server_seed_hash: None,
+            settlement: None,
+        };
+        let outcome = |bet: &Bet, result: &BetResult| bet.diff_result(batch_id, result).0;
+
+        let batched = bet(BetStatus::Batched);
+        assert_eq!(outcome(&batched, &result(BetStatus::Completed, Some(200))), BetUpdateOutcome::Applied);
+        assert_eq!(outcome(&batched, &result(BetStatus::FailedRetryable, None)), BetUpdateOutcome::Applied);
+        assert_eq!(outcome(&batched, &result(BetStatus::Pending, None)), BetUpdateOutcome::Conflict);
+        let pending = Bet { external_batch_id: None, ..won_bet(BetStatus::Pending) };
+        assert_eq!(outcome(&pending, &result(BetStatus::Completed, Some(200))), BetUpdateOutcome::Conflict);
+        let elsewhere = batched.diff_result(Uuid::new_v4(), &result(BetStatus::Completed, Some(200))).0;
+        assert_eq!(elsewhere, BetUpdateOutcome::Conflict);
+
+        // A replayed failure would count the retry twice
+        let retrying = bet(BetStatus::FailedRetryable);
+        assert_eq!(outcome(&retrying, &result(BetStatus::FailedRetryable, None)), BetUpdateOutcome::Conflict);
+
+        let completed = bet(BetStatus::Completed);
+        assert_eq!(outcome(&completed, &result(BetStatus::Completed, Some(200))), BetUpdateOutcome::SkippedTerminal);
+        assert_eq!(outcome(&completed, &result(BetStatus::Completed, None)), BetUpdateOutcome::SkippedTerminal);
+        assert_eq!(outcome(&completed, &result(BetStatus::Completed, Some(999))), BetUpdateOutcome::Conflict);
+        assert_eq!(outcome(&completed, &result(BetStatus::FailedManualReview, None)), BetUpdateOutcome::Conflict);
+
+        // A resent update finds its results already held, unless the bet moved to another batch
+        assert!(retrying.reflects_result(batch_id, &result(BetStatus::FailedRetryable, None)));
+        assert!(!retrying.reflects_result(Uuid::new_v4(), &result(BetStatus::FailedRetryable, None)));
+        assert!(completed.reflects_result(Uuid::new_v4(), &result(BetStatus::Completed, Some(200))));
+        assert!(!batched.reflects_result(batch_id, &result(BetStatus::Completed, Some(200))));
+    }
+
+    #[test]
+    fn test_settlement_phase_follows_funds() {
+        let queued = BetSettlement::of(&won_bet(BetStatus::Batched), None);
+        assert_eq!(queued.phase, SettlementPhase::Queued);
+        assert!(queued.spend_tx.is_none());
+
+        // Sent but not landed yet
+        let submitted = Bet { solana_tx_id: Some("5sig".to_string()), ..won_bet(BetStatus::SubmittedToSolana) };
+        assert_eq!(BetSettlement::of(&submitted, None).phase, SettlementPhase::Queued);
+        let report = SettlementReport {
+            confirmation_status: Some(ConfirmationStatus::Confirmed),
+            slot: Some(42),
+            ..SettlementReport::default()
+        };
+        let moved = BetSettlement::of(&submitted, Some(&report));
+        assert_eq!(moved.phase, SettlementPhase::FundsMoved);
+        assert_eq!(moved.spend_tx.as_deref(), Some("5sig"));
+        assert_eq!(moved.payout_tx.as_deref(), Some("5sig"));
+        assert_eq!(moved.slot, Some(42));
+
+        let completed = Bet { solana_tx_id: Some("5sig".to_string()), won: Some(false), ..won_bet(BetStatus::Completed) };
+        let complete = BetSettlement::of(&completed, None);
+        assert_eq!(complete.phase, SettlementPhase::Complete);
+        assert_eq!(complete.confirmation_status, Some(ConfirmationStatus::Confirmed));
+        assert!(complete.payout_tx.is_none());
+
+        let failed = report.merge(SettlementReport {
+            confirmation_status: Some(ConfirmationStatus::Failed),
+            ..SettlementReport::default()
+        });
+        assert_eq!(failed.slot, Some(42));
+        assert_eq!(BetSettlement::of(&submitted, Some(&failed)).phase, SettlementPhase::Queued);
+    }
+
+    #[test]
+    fn test_update_batch_request_without_fees() {
+        let req: UpdateBatchRequest = serde_json::from_str(
+            r#"{"status":"confirmed","solana_tx_id":null,"bet_results":[],"error_message":null}"#,
+        )
+        .unwrap();
+        assert!(req.fee_lamports.is_none());
+    }
+
+    #[test]
+    fn test_refund_result_is_final_once() {
+        let mut refund = Refund {
+            bet_id: Uuid::new_v4(),
+            user_wallet: "wallet".to_string(),
+            vault_address: "vault".to_string(),
+            casino_id: None,
+            stake_token: "SOL".to_string(),
+            amount: 100_000_000,
+            status: RefundStatus::Pending,
+            requested_by: "ops@casino".to_string(),
+            reason: "Game voided".to_string(),
+            requested_at: Utc::now(),
+            attempts: 0,
+            solana_tx_id: None,
+            last_error: None,
+            completed_at: None,
+        };
+        let result = |status, tx: Option<&str>, error: Option<&str>| RefundResult {
+            processor_id: "processor-1".to_string(),
+            status,
+            solana_tx_id: tx.map(str::to_string),
+            error_message: error.map(str::to_string),
+        };
+
+        assert!(!refund.apply(&result(RefundStatus::Pending, None, Some("rpc timeout")), Utc::now()));
+        assert_eq!(refund.attempts, 1);
+        assert!(refund.completed_at.is_none());
+
+        assert!(refund.apply(&result(RefundStatus::Completed, Some("5sig"), None), Utc::now()));
+        assert_eq!(refund.status, RefundStatus::Completed);
+        assert_eq!(refund.solana_tx_id.as_deref(), Some("5sig"));
+        assert_eq!(refund.last_error.as_deref(), Some("rpc timeout"));
+
+        // A late failure report does not reopen it
+        assert!(refund.apply(&result(RefundStatus::Failed, None, Some("late")), Utc::now()));
+        assert_eq!((refund.status, refund.attempts), (RefundStatus::Completed, 2));
+    }
+}
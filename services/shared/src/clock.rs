@@ -0,0 +1,188 @@
+//! Time source for time-based logic
+//!
+//! Backoff, expiry checks and sweepers read the time and sleep through a
+//! [`Clock`] instead of calling `Utc::now()` and `tokio::time::sleep`
+//! directly. Services run on [`SystemClock`]; tests use a [`ManualClock`],
+//! which stands still until the test moves it, so time-based behaviour is
+//! checked without real waits or races. [`timeout`] bounds a future by a
+//! clock the same way `tokio::time::timeout` does by the real time.
+
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// A sleep started by [`Clock::sleep`]
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+pub trait Clock: Send + Sync {
+    /// Current wall-clock time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Resolves once `duration` has passed on this clock
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// The real time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn shared() -> Arc<dyn Clock> {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Run `future` until it completes or `duration` passes on `clock`, whichever
+/// is first; None if the time ran out
+pub async fn timeout<F: Future>(clock: &dyn Clock, duration: Duration, future: F) -> Option<F::Output> {
+    tokio::select! {
+        output = future => Some(output),
+        _ = clock.sleep(duration) => None,
+    }
+}
+
+/// A clock frozen at a given time, moved only by `advance` and `set`
+///
+/// Sleeps end when the clock is moved to or past their deadline.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: watch::Sender<DateTime<Utc>>,
+    sleeping: Arc<AtomicUsize>,
+    started: AtomicUsize,
+}
+
+impl ManualClock {
+    pub fn at(now: DateTime<Utc>) -> Self {
+        Self { now: watch::channel(now).0, sleeping: Arc::new(AtomicUsize::new(0)), started: AtomicUsize::new(0) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.send_modify(|now| *now = deadline(*now, by));
+    }
+
+    /// Move the clock to `now`, which may be in the past
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.now.send_replace(now);
+    }
+
+    /// Sleeps started on this clock that have not ended
+    pub fn sleeping(&self) -> usize {
+        self.sleeping.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `count` sleeps in all have been started on this clock,
+    /// e.g. before advancing past a backoff the code under test is about to take
+    pub async fn until_started(&self, count: usize) {
+        while self.started.load(Ordering::SeqCst) < count {
+            tokio::task::yield_now().await;
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.borrow()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        let mut now = self.now.subscribe();
+        let until = deadline(*now.borrow(), duration);
+        let sleeping = SleepingGuard::new(self.sleeping.clone());
+        self.started.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            let _sleeping = sleeping;
+            // Ends early only if the clock is dropped
+            let _ = now.wait_for(|now| *now >= until).await;
+        })
+    }
+}
+
+fn deadline(now: DateTime<Utc>, duration: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| now.checked_add_signed(duration))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Counts a sleep as waiting from when it is started until it ends or is dropped
+struct SleepingGuard(Arc<AtomicUsize>);
+
+impl SleepingGuard {
+    fn new(sleeping: Arc<AtomicUsize>) -> Self {
+        sleeping.fetch_add(1, Ordering::SeqCst);
+        Self(sleeping)
+    }
+}
+
+impl Drop for SleepingGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_manual_clock_sleeps_until_advanced() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let clock = Arc::new(ManualClock::at(start));
+
+        let sleeper = tokio::spawn({
+            let clock = clock.clone();
+            async move { clock.sleep(Duration::from_secs(10)).await }
+        });
+        clock.until_started(1).await;
+
+        clock.advance(Duration::from_secs(9));
+        tokio::task::yield_now().await;
+        assert!(!sleeper.is_finished());
+        assert_eq!(clock.sleeping(), 1);
+
+        clock.advance(Duration::from_secs(1));
+        sleeper.await.unwrap();
+        assert_eq!(clock.sleeping(), 0);
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(10));
+
+        // Nothing to wait for once the deadline has passed
+        clock.set(start);
+        clock.sleep(Duration::ZERO).await;
+    }
+
+    #[tokio::test]
+    async fn test_timeout_ends_on_the_clock() {
+        let clock = Arc::new(ManualClock::at(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()));
+
+        let waiter = tokio::spawn({
+            let clock = clock.clone();
+            async move { timeout(clock.as_ref(), Duration::from_secs(5), std::future::pending::<()>()).await }
+        });
+        clock.until_started(1).await;
+        clock.advance(Duration::from_secs(4));
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(waiter.await.unwrap(), None);
+
+        // A future that is ready wins without the clock moving
+        assert_eq!(timeout(clock.as_ref(), Duration::from_secs(5), async { 7 }).await, Some(7));
+        assert_eq!(clock.sleeping(), 0);
+    }
+}
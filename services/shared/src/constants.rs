@@ -1,7 +1,7 @@
-/// Shared constants for Atomik Wallet betting system
-/// 
-/// This module centralizes all magic numbers and configuration constants
-/// to prevent inconsistencies across backend, processor, and smart contracts.
+//! Shared constants for Atomik Wallet betting system
+//! 
+//! This module centralizes all magic numbers and configuration constants
+//! to prevent inconsistencies across backend, processor, and smart contracts.
 
 use solana_sdk::pubkey;
 use solana_sdk::pubkey::Pubkey;
@@ -46,7 +46,8 @@ pub const RENT_EXEMPT_RESERVE_USER_VAULT: u64 = 1_566_960;
 /// Maximum bet ID length (UUID without hyphens = 32 chars)
 /// 
 /// Rationale: Solana PDA seeds have 32-byte limit per seed.
-/// UUIDs are 36 chars with hyphens, 32 without.
+/// UUIDs are 36 chars with hyphens, 32 without. The vault program requires
+/// exactly this length so ids cannot be truncated into collisions.
 pub const MAX_BET_ID_LENGTH: usize = 32;
 
 /// Rate limiter window duration (1 hour)
@@ -70,3 +71,25 @@ pub const RETRY_BACKOFF_BASE_MS: i64 = 2_000;
 
 /// Maximum backoff delay in milliseconds for retry logic
 pub const RETRY_BACKOFF_MAX_MS: i64 = 60_000;
+
+/// Redis key holding the incident kill switch
+/// 
+/// Present (JSON describing who engaged it and why) while intake and settlement
+/// are halted. The backend rejects new bets and processors stop dispatching.
+pub const KILL_SWITCH_KEY: &str = "killswitch:active";
+
+/// Redis key prefix of a held payout, suffixed with the settlement's transaction id
+///
+/// Written by processors when they hold a large payout, approved through the
+/// backend admin API.
+pub const PAYOUT_HOLD_PREFIX: &str = "payout:hold:";
+
+/// Redis sorted set of payouts awaiting review, scored by when they were held
+pub const PAYOUT_HOLDS_PENDING_KEY: &str = "payout:holds:pending";
+
+/// Redis key holding a tripped permanent-failure retry budget
+///
+/// Set by the first processor whose window of settlement outcomes goes over the
+/// budget; while present no processor classifies failures as permanent. Cleared
+/// when an operator acknowledges it through the backend admin API.
+pub const RETRY_BUDGET_TRIPPED_KEY: &str = "settlement:retry_budget:tripped";
@@ -38,6 +38,10 @@ pub enum ErrorCategory {
 
     /// Authorization/Authentication errors (401/403)
     Unauthorized,
+
+    /// Conflict with the resource's current state (409 Conflict)
+    /// e.g. a stale version or another owner's claim
+    Conflict,
 }
 
 impl ErrorCategory {
@@ -50,6 +54,7 @@ impl ErrorCategory {
             ErrorCategory::Internal => 500,
             ErrorCategory::NotFound => 404,
             ErrorCategory::Unauthorized => 401,
+            ErrorCategory::Conflict => 409,
         }
     }
 
@@ -62,6 +67,7 @@ impl ErrorCategory {
             ErrorCategory::Internal => "error",
             ErrorCategory::NotFound => "info",
             ErrorCategory::Unauthorized => "warn",
+            ErrorCategory::Conflict => "warn",
         }
     }
 }
@@ -70,43 +76,99 @@ impl ErrorCategory {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ErrorCode(pub &'static str);
 
-impl ErrorCode {
+/// A catalog entry: the category a code is raised under and what it means
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorCodeInfo {
+    pub code: ErrorCode,
+    pub category: ErrorCategory,
+    pub description: &'static str,
+}
+
+/// Declares each code once, as a constant and as an entry in `ErrorCode::CATALOG`
+macro_rules! error_codes {
+    ($($name:ident => $category:ident, $description:literal;)*) => {
+        impl ErrorCode {
+            $(pub const $name: ErrorCode = ErrorCode(stringify!($name));)*
+
+            /// Every code, in declaration order
+            pub const CATALOG: &'static [ErrorCodeInfo] = &[
+                $(ErrorCodeInfo {
+                    code: ErrorCode::$name,
+                    category: ErrorCategory::$category,
+                    description: $description,
+                },)*
+            ];
+        }
+    };
+}
+
+error_codes! {
     // Validation errors
-    pub const VALIDATION_INVALID_BET_ID: ErrorCode = ErrorCode("VALIDATION_INVALID_BET_ID");
-    pub const VALIDATION_INVALID_AMOUNT: ErrorCode = ErrorCode("VALIDATION_INVALID_AMOUNT");
-    pub const VALIDATION_INVALID_WALLET: ErrorCode = ErrorCode("VALIDATION_INVALID_WALLET");
-    pub const VALIDATION_INVALID_CHOICE: ErrorCode = ErrorCode("VALIDATION_INVALID_CHOICE");
-    pub const VALIDATION_INSUFFICIENT_BALANCE: ErrorCode =
-        ErrorCode("VALIDATION_INSUFFICIENT_BALANCE");
-    pub const VALIDATION_ALLOWANCE_EXPIRED: ErrorCode = ErrorCode("VALIDATION_ALLOWANCE_EXPIRED");
+    VALIDATION_INVALID_BET_ID => Validation, "The bet ID is not a valid UUID";
+    VALIDATION_INVALID_AMOUNT => Validation, "The amount is out of range or malformed";
+    VALIDATION_INVALID_WALLET => Validation, "The wallet address is not a valid public key";
+    VALIDATION_INVALID_CHOICE => Validation, "The bet choice is not valid for the game";
+    VALIDATION_GAME_UNAVAILABLE => Validation, "The game is unknown or currently disabled";
+    VALIDATION_INSUFFICIENT_BALANCE => Validation, "The vault balance does not cover the stake";
+    VALIDATION_ALLOWANCE_EXPIRED => Validation, "The allowance has expired";
+    VALIDATION_BET_EXPIRED => Validation, "The bet expired before it was settled";
+    VALIDATION_SESSION_LIMIT => Validation, "The bet would exceed a limit of its session";
+    VALIDATION_INVALID_STATUS => Validation, "The request is not allowed in the resource's current status";
+    VALIDATION_INVALID_INPUT => Validation, "The request body or parameters are invalid";
+    VALIDATION_MISSING_FIELD => Validation, "A required field is missing from the request body";
+
+    // Authorization errors
+    AUTH_UNAUTHORIZED => Unauthorized, "Missing or invalid credentials";
+    AUTH_JURISDICTION_BLOCKED => Unauthorized, "Betting is not available from the client's location";
 
     // Network errors
-    pub const NETWORK_RPC_UNAVAILABLE: ErrorCode = ErrorCode("NETWORK_RPC_UNAVAILABLE");
-    pub const NETWORK_RPC_TIMEOUT: ErrorCode = ErrorCode("NETWORK_RPC_TIMEOUT");
-    pub const NETWORK_REDIS_CONNECTION: ErrorCode = ErrorCode("NETWORK_REDIS_CONNECTION");
-    pub const NETWORK_DATABASE_CONNECTION: ErrorCode = ErrorCode("NETWORK_DATABASE_CONNECTION");
-    pub const NETWORK_BACKEND_UNAVAILABLE: ErrorCode = ErrorCode("NETWORK_BACKEND_UNAVAILABLE");
+    NETWORK_RPC_UNAVAILABLE => Network, "The Solana RPC endpoint is unavailable";
+    NETWORK_RPC_TIMEOUT => Network, "The Solana RPC endpoint timed out";
+    NETWORK_REDIS_CONNECTION => Network, "Redis is unavailable";
+    NETWORK_DATABASE_CONNECTION => Network, "The archive database is unavailable";
+    NETWORK_BACKEND_UNAVAILABLE => Network, "The backend API is unavailable";
+    NETWORK_BLOCKHASH_EXPIRED => Network, "The transaction's blockhash expired before it landed";
+    NETWORK_SERVICE_HALTED => Network, "Betting is halted by the kill switch";
+    NETWORK_INTAKE_OVERLOADED => Network, "Bets are refused while the settlement queue is backed up; retry later";
+    NETWORK_INDEXER_UNAVAILABLE => Network, "The vault indexer is unavailable or not configured";
+    NETWORK_PROCESSOR_TIMEOUT => Network, "The processor that claimed the bet stopped reporting on it";
 
     // Smart contract errors
-    pub const CONTRACT_EXECUTION_FAILED: ErrorCode = ErrorCode("CONTRACT_EXECUTION_FAILED");
-    pub const CONTRACT_INSUFFICIENT_RENT: ErrorCode = ErrorCode("CONTRACT_INSUFFICIENT_RENT");
-    pub const CONTRACT_INVALID_PDA: ErrorCode = ErrorCode("CONTRACT_INVALID_PDA");
-    pub const CONTRACT_UNAUTHORIZED_SIGNER: ErrorCode = ErrorCode("CONTRACT_UNAUTHORIZED_SIGNER");
-    pub const CONTRACT_ACCOUNT_NOT_FOUND: ErrorCode = ErrorCode("CONTRACT_ACCOUNT_NOT_FOUND");
+    CONTRACT_EXECUTION_FAILED => Contract, "The vault program rejected the transaction";
+    CONTRACT_INSUFFICIENT_RENT => Contract, "An account lacks the lamports to stay rent-exempt";
+    CONTRACT_INVALID_PDA => Contract, "An account does not match its expected program address";
+    CONTRACT_UNAUTHORIZED_SIGNER => Contract, "A signer is not authorized for the instruction";
+    CONTRACT_ACCOUNT_NOT_FOUND => Contract, "A required on-chain account does not exist";
+    CONTRACT_ALLOWANCE_EXPIRED => Contract, "The allowance expired on-chain";
+    CONTRACT_ALLOWANCE_REVOKED => Contract, "The allowance was revoked";
+    CONTRACT_INSUFFICIENT_ALLOWANCE => Contract, "The allowance does not cover the stake";
+    CONTRACT_INSUFFICIENT_BALANCE => Contract, "The vault balance does not cover the transfer";
+    CONTRACT_DOUBLE_SPEND => Contract, "The bet was already settled or refunded on-chain";
+    CONTRACT_CASINO_PAUSED => Contract, "The casino is paused on-chain";
+    CONTRACT_RATE_LIMITED => Contract, "An allowance spend rate limit was reached; retry later";
+    CONTRACT_INVALID_BET => Contract, "The program rejected the bet's amount, ID, limits or token";
+    CONTRACT_INVALID_TOKEN_ACCOUNT => Contract, "A token account or mint is wrong or missing";
+    CONTRACT_ARITHMETIC => Contract, "An on-chain amount overflowed";
 
     // Internal errors
-    pub const INTERNAL_UNEXPECTED: ErrorCode = ErrorCode("INTERNAL_UNEXPECTED");
-    pub const INTERNAL_SERIALIZATION: ErrorCode = ErrorCode("INTERNAL_SERIALIZATION");
-    pub const INTERNAL_DESERIALIZATION: ErrorCode = ErrorCode("INTERNAL_DESERIALIZATION");
-    pub const INTERNAL_DATABASE_QUERY: ErrorCode = ErrorCode("INTERNAL_DATABASE_QUERY");
-    pub const INTERNAL_CONFIGURATION: ErrorCode = ErrorCode("INTERNAL_CONFIGURATION");
+    INTERNAL_UNEXPECTED => Internal, "An unexpected internal error";
+    INTERNAL_SERIALIZATION => Internal, "A value could not be serialized";
+    INTERNAL_DESERIALIZATION => Internal, "A stored value could not be deserialized";
+    INTERNAL_DATABASE_QUERY => Internal, "A database query failed";
+    INTERNAL_CONFIGURATION => Internal, "The service is misconfigured";
 
     // Resource errors
-    pub const NOT_FOUND_BET: ErrorCode = ErrorCode("NOT_FOUND_BET");
-    pub const NOT_FOUND_BATCH: ErrorCode = ErrorCode("NOT_FOUND_BATCH");
-    pub const NOT_FOUND_VAULT: ErrorCode = ErrorCode("NOT_FOUND_VAULT");
-    pub const NOT_FOUND_ALLOWANCE: ErrorCode = ErrorCode("NOT_FOUND_ALLOWANCE");
+    NOT_FOUND_BET => NotFound, "No bet with this ID";
+    NOT_FOUND_BATCH => NotFound, "No claimed batch with this ID";
+    NOT_FOUND_VAULT => NotFound, "The wallet has no vault";
+    NOT_FOUND_ALLOWANCE => NotFound, "No allowance at this address";
+    NOT_FOUND_SESSION => NotFound, "No session with this ID";
+
+    // Conflict errors
+    CONFLICT_BATCH_CLAIM => Conflict, "The batch belongs to another processor or the update is stale";
+}
 
+impl ErrorCode {
     pub fn as_str(&self) -> &'static str {
         self.0
     }
@@ -300,6 +362,7 @@ mod tests {
         assert_eq!(ErrorCategory::Validation.status_code(), 400);
         assert_eq!(ErrorCategory::Network.status_code(), 503);
         assert_eq!(ErrorCategory::NotFound.status_code(), 404);
+        assert_eq!(ErrorCategory::Conflict.status_code(), 409);
         assert_eq!(ErrorCategory::Internal.status_code(), 500);
     }
 
@@ -311,6 +374,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_catalog_codes_are_unique_and_prefixed_by_category() {
+        let mut codes: Vec<&str> = ErrorCode::CATALOG.iter().map(|info| info.code.as_str()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), ErrorCode::CATALOG.len());
+
+        for info in ErrorCode::CATALOG {
+            let prefix = match info.category {
+                ErrorCategory::Validation => "VALIDATION_",
+                ErrorCategory::Network => "NETWORK_",
+                ErrorCategory::Contract => "CONTRACT_",
+                ErrorCategory::Internal => "INTERNAL_",
+                ErrorCategory::NotFound => "NOT_FOUND_",
+                ErrorCategory::Unauthorized => "AUTH_",
+                ErrorCategory::Conflict => "CONFLICT_",
+            };
+            assert!(info.code.as_str().starts_with(prefix), "{} is not a {:?} code", info.code, info.category);
+        }
+    }
+
     #[test]
     fn test_service_error_creation() {
         let error = ServiceError::invalid_bet_id("test-123");
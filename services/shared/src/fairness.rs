@@ -0,0 +1,97 @@
+//! Provably-fair outcome derivation and verification
+//!
+//! Outcomes are drawn from a ChaCha20 RNG seeded with
+//! `HMAC-SHA256(key = server_seed, message = bet_id || client_seed)`, where
+//! `bet_id` is the hyphenated UUID string. The server publishes
+//! `sha256(server_seed)` before taking bets and reveals the seed after rotation,
+//! so anyone can recompute every outcome with [`verify_coinflip`].
+
+use hmac::{Hmac, Mac};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Server seed length in bytes
+pub const SERVER_SEED_LEN: usize = 32;
+
+/// Longest client seed accepted at bet creation
+pub const MAX_CLIENT_SEED_LEN: usize = 64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FairnessError {
+    #[error("server seed is not valid hex")]
+    InvalidSeedHex,
+    #[error("server seed must be {SERVER_SEED_LEN} bytes, got {0}")]
+    InvalidSeedLength(usize),
+    #[error("revealed seed does not match commitment {0}")]
+    CommitmentMismatch(String),
+}
+
+/// Decode a hex server seed
+pub fn decode_server_seed(hex_seed: &str) -> Result<[u8; SERVER_SEED_LEN], FairnessError> {
+    let bytes = hex::decode(hex_seed.trim()).map_err(|_| FairnessError::InvalidSeedHex)?;
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| FairnessError::InvalidSeedLength(b.len()))
+}
+
+/// Published commitment of a server seed: hex `sha256(server_seed)`
+pub fn seed_commitment(server_seed: &[u8]) -> String {
+    hex::encode(Sha256::digest(server_seed))
+}
+
+/// Per-bet RNG seed
+pub fn bet_seed(server_seed: &[u8], bet_id: Uuid, client_seed: &str) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(server_seed).expect("HMAC accepts any key length");
+    mac.update(bet_id.to_string().as_bytes());
+    mac.update(client_seed.as_bytes());
+    mac.finalize().into_bytes().into()
+}
+
+/// Coinflip result for a bet; true means the player won
+pub fn coinflip_won(server_seed: &[u8], bet_id: Uuid, client_seed: &str) -> bool {
+    ChaCha20Rng::from_seed(bet_seed(server_seed, bet_id, client_seed)).gen_bool(0.5)
+}
+
+/// Replay a coinflip from a revealed server seed, checking it against the
+/// commitment stored on the bet. Returns whether the player won.
+pub fn verify_coinflip(
+    revealed_seed: &str,
+    server_seed_hash: &str,
+    bet_id: Uuid,
+    client_seed: &str,
+) -> Result<bool, FairnessError> {
+    let seed = decode_server_seed(revealed_seed)?;
+    if !seed_commitment(&seed).eq_ignore_ascii_case(server_seed_hash) {
+        return Err(FairnessError::CommitmentMismatch(server_seed_hash.to_string()));
+    }
+    Ok(coinflip_won(&seed, bet_id, client_seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_coinflip() {
+        let seed = [7u8; SERVER_SEED_LEN];
+        let hash = seed_commitment(&seed);
+        let bet_id = Uuid::from_u128(42);
+
+        let won = coinflip_won(&seed, bet_id, "lucky");
+        assert_eq!(verify_coinflip(&hex::encode(seed), &hash, bet_id, "lucky"), Ok(won));
+        assert_eq!(
+            verify_coinflip(&hex::encode([8u8; SERVER_SEED_LEN]), &hash, bet_id, "lucky"),
+            Err(FairnessError::CommitmentMismatch(hash.clone()))
+        );
+        assert_eq!(decode_server_seed("abcd"), Err(FairnessError::InvalidSeedLength(2)));
+
+        // The client seed changes the draw for at least some bets
+        assert!((0..64u128).any(|i| {
+            let id = Uuid::from_u128(i);
+            coinflip_won(&seed, id, "a") != coinflip_won(&seed, id, "b")
+        }));
+    }
+}
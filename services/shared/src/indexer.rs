@@ -0,0 +1,172 @@
+//! Records served by the account indexer's internal HTTP API
+//!
+//! The indexer mirrors vault program accounts into Redis; the backend reads
+//! them through these types instead of querying RPC. Every record carries the
+//! slot it was observed at, so callers can judge how fresh it is.
+
+use serde::{Deserialize, Serialize};
+
+use crate::vault::AllowanceAccount;
+
+/// Redis stream the indexer appends each newly seen deposit to, as `payload` JSON
+pub const DEPOSITS_STREAM_KEY: &str = "indexer:deposits:stream";
+
+/// A user's `Vault` account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedVault {
+    pub address: String,
+    pub owner: String,
+    pub casino: String,
+    /// Balance tracked by the program
+    pub sol_balance: u64,
+    /// Lamports actually held, including the rent-exempt reserve
+    pub lamports: u64,
+    pub last_activity: i64,
+    /// Where winnings are paid instead of the vault, when the owner set one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payout_address: Option<String>,
+    pub slot: u64,
+}
+
+/// An `Allowance` account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedAllowance {
+    pub address: String,
+    pub user: String,
+    pub token_mint: String,
+    pub amount: u64,
+    pub spent: u64,
+    pub expires_at: i64,
+    pub nonce: u64,
+    pub revoked: bool,
+    pub spend_count: u32,
+    /// Per-spend and hourly velocity limits; 0 means unlimited
+    #[serde(default)]
+    pub max_single_spend: u64,
+    #[serde(default)]
+    pub max_spend_per_hour: u64,
+    pub slot: u64,
+}
+
+impl IndexedAllowance {
+    pub fn from_account(address: String, allowance: &AllowanceAccount, slot: u64) -> Self {
+        Self {
+            address,
+            user: allowance.user.to_string(),
+            token_mint: allowance.token_mint.to_string(),
+            amount: allowance.amount,
+            spent: allowance.spent,
+            expires_at: allowance.expires_at,
+            nonce: allowance.nonce,
+            revoked: allowance.revoked,
+            spend_count: allowance.spend_count,
+            max_single_spend: allowance.max_single_spend,
+            max_spend_per_hour: allowance.max_spend_per_hour,
+            slot,
+        }
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.amount.saturating_sub(self.spent)
+    }
+
+    /// Usable at `now` (unix seconds): not revoked, not expired and not used up
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.revoked && now <= self.expires_at && self.remaining() > 0
+    }
+}
+
+/// An `AllowanceNonceRegistry` account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedNonceRegistry {
+    pub address: String,
+    pub user: String,
+    pub next_nonce: u64,
+    pub slot: u64,
+}
+
+/// A `ProcessedBet` account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedProcessedBet {
+    pub address: String,
+    pub bet_id: String,
+    pub user: String,
+    pub amount: u64,
+    pub processed_at: i64,
+    pub signature: String,
+    /// `None` for bets settled before `settle_bet` recorded outcomes
+    #[serde(default)]
+    pub won: Option<bool>,
+    #[serde(default)]
+    pub payout: u64,
+    pub slot: u64,
+}
+
+/// A `deposit_sol` or `deposit_spl` instruction into a user's vault
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedDeposit {
+    pub signature: String,
+    /// Position of the instruction in the transaction, which may hold several deposits
+    pub instruction_index: u8,
+    pub vault: String,
+    pub owner: String,
+    /// `None` for SOL deposits
+    pub token_mint: Option<String>,
+    /// Lamports, or base units of `token_mint`
+    pub amount: u64,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+}
+
+/// `GET /users/:owner/allowances`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserAllowances {
+    /// `None` until the user's first allowance approval creates the registry
+    pub next_nonce: Option<u64>,
+    /// Newest (highest nonce) first
+    pub allowances: Vec<IndexedAllowance>,
+}
+
+/// `GET /health`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexerStatus {
+    /// Slot of the last full `getProgramAccounts` snapshot
+    pub snapshot_slot: Option<u64>,
+    /// Slot of the last account update from the program subscription
+    pub last_account_slot: Option<u64>,
+    /// Slot of the last program transaction seen by the logs subscription
+    pub last_log_slot: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowance_activity() {
+        let mut allowance = IndexedAllowance {
+            address: "A".to_string(),
+            user: "U".to_string(),
+            token_mint: "M".to_string(),
+            amount: 1_000,
+            spent: 400,
+            expires_at: 100,
+            nonce: 0,
+            revoked: false,
+            spend_count: 2,
+            max_single_spend: 0,
+            max_spend_per_hour: 0,
+            slot: 1,
+        };
+        assert_eq!(allowance.remaining(), 600);
+        assert!(allowance.is_active(100));
+        assert!(!allowance.is_active(101));
+
+        allowance.spent = 1_000;
+        assert!(!allowance.is_active(50));
+
+        allowance.spent = 0;
+        allowance.revoked = true;
+        assert!(!allowance.is_active(50));
+    }
+}
@@ -1,7 +1,15 @@
+pub mod api;
+pub mod clock;
 pub mod constants;
 pub mod types;
 pub mod errors;
+pub mod fairness;
+pub mod indexer;
+pub mod profile;
 pub mod program_ids;
+#[cfg(feature = "secrets")]
+pub mod secrets;
+pub mod vault;
 
 pub use constants::*;
 pub use types::*;
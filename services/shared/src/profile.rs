@@ -0,0 +1,248 @@
+//! Deployment profiles and the effective-config printout
+//!
+//! `--profile local|devnet|mainnet` (or `CONFIG_PROFILE`) supplies defaults for
+//! the settings that differ between environments: cluster, RPC endpoint, vault
+//! program, commitment and RPC rate limits. A setting present in the
+//! environment always overrides its profile default. Without a profile the
+//! RPC endpoint and vault program stay required, as they were before profiles.
+//!
+//! The `redact*` functions are `serialize_with` helpers for printing a loaded
+//! config without the secrets in it.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize, Serializer};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+/// Settings each profile has a default for
+pub const PROFILE_SETTINGS: [&str; 6] = [
+    "SOLANA_NETWORK",
+    "SOLANA_RPC_URL",
+    "VAULT_PROGRAM_ID",
+    "SOLANA_COMMITMENT",
+    "SOLANA_RPC_REQUESTS_PER_SECOND",
+    "SOLANA_TRANSACTIONS_PER_SECOND",
+];
+
+const REDACTED: &str = "<redacted>";
+
+/// Vault program as declared in `contracts/programs/vault`
+const VAULT_PROGRAM_ID: &str = "BtZT2B1NkEGZwNT5CS326HbdbXzggiTYSUiYmSDyhTDJ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// `solana-test-validator` on this machine
+    Local,
+    Devnet,
+    Mainnet,
+}
+
+/// Defaults of the settings in `PROFILE_SETTINGS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileDefaults {
+    pub network: &'static str,
+    pub rpc_url: Option<&'static str>,
+    pub vault_program_id: Option<&'static str>,
+    pub commitment: &'static str,
+    /// Per endpoint; 0 is unlimited
+    pub rpc_requests_per_second: &'static str,
+    pub transactions_per_second: &'static str,
+}
+
+impl Profile {
+    /// `CONFIG_PROFILE`, when `--profile` isn't given
+    pub fn from_env() -> Result<Option<Self>> {
+        match env::var("CONFIG_PROFILE") {
+            Ok(value) if !value.trim().is_empty() => {
+                Ok(Some(value.parse().map_err(anyhow::Error::msg).context("Invalid CONFIG_PROFILE")?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Profile::Local => "local",
+            Profile::Devnet => "devnet",
+            Profile::Mainnet => "mainnet",
+        }
+    }
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "local" => Ok(Profile::Local),
+            "devnet" => Ok(Profile::Devnet),
+            "mainnet" => Ok(Profile::Mainnet),
+            other => Err(format!("unknown profile {:?} (expected local, devnet or mainnet)", other)),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ProfileDefaults {
+    /// Defaults of `profile`; with none, the defaults the flat config always had
+    pub fn of(profile: Option<Profile>) -> Self {
+        match profile {
+            None => Self {
+                network: "devnet",
+                rpc_url: None,
+                vault_program_id: None,
+                commitment: "confirmed",
+                rpc_requests_per_second: "40",
+                transactions_per_second: "20",
+            },
+            Some(Profile::Local) => Self {
+                network: "localnet",
+                rpc_url: Some("http://127.0.0.1:8899"),
+                vault_program_id: Some(VAULT_PROGRAM_ID),
+                commitment: "processed",
+                rpc_requests_per_second: "0",
+                transactions_per_second: "0",
+            },
+            Some(Profile::Devnet) => Self {
+                network: "devnet",
+                rpc_url: Some("https://api.devnet.solana.com"),
+                vault_program_id: Some(VAULT_PROGRAM_ID),
+                commitment: "confirmed",
+                rpc_requests_per_second: "40",
+                transactions_per_second: "20",
+            },
+            // No program default: mainnet runs whatever was deployed there
+            Some(Profile::Mainnet) => Self {
+                network: "mainnet-beta",
+                rpc_url: Some("https://api.mainnet-beta.solana.com"),
+                vault_program_id: None,
+                commitment: "finalized",
+                rpc_requests_per_second: "100",
+                transactions_per_second: "50",
+            },
+        }
+    }
+
+    /// Profile settings set explicitly in the environment
+    pub fn overrides() -> Vec<&'static str> {
+        PROFILE_SETTINGS
+            .into_iter()
+            .filter(|name| env::var(name).is_ok_and(|value| !value.is_empty()))
+            .collect()
+    }
+}
+
+/// `name` from the environment, else `default`
+pub fn setting(name: &str, default: Option<&str>) -> Result<String> {
+    env::var(name)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .or_else(|| default.map(str::to_string))
+        .with_context(|| format!("{} must be set (or pick a --profile that defaults it)", name))
+}
+
+/// Prints a secret as `<redacted>`
+pub fn redact<S: Serializer>(_value: &str, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED)
+}
+
+pub fn redact_optional<S: Serializer>(value: &Option<String>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    match value {
+        Some(_) => serializer.serialize_str(REDACTED),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Prints a URL without the credentials in it, e.g. `redis://<redacted>@host:6379`
+pub fn redact_url<S: Serializer>(value: &str, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&without_credentials(value))
+}
+
+pub fn redact_optional_url<S: Serializer>(
+    value: &Option<String>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => redact_url(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Prints a keypair setting's path or secret reference, but never a keypair itself
+pub fn redact_keypair<S: Serializer>(value: &str, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    if value.trim_start().starts_with('[') {
+        serializer.serialize_str(REDACTED)
+    } else {
+        serializer.serialize_str(value)
+    }
+}
+
+pub fn redact_optional_keypair<S: Serializer>(
+    value: &Option<String>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => redact_keypair(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn without_credentials(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    match rest[..authority_end].rfind('@') {
+        Some(at) => format!("{}://{}{}", scheme, REDACTED, &rest[at..]),
+        None => url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Printed {
+        #[serde(serialize_with = "redact_url")]
+        redis_url: String,
+        #[serde(serialize_with = "redact_optional")]
+        api_key: Option<String>,
+        #[serde(serialize_with = "redact_keypair")]
+        keypair: String,
+    }
+
+    #[test]
+    fn test_profiles_and_redaction() {
+        assert_eq!("Mainnet".parse::<Profile>(), Ok(Profile::Mainnet));
+        assert!("staging".parse::<Profile>().is_err());
+        assert_eq!(ProfileDefaults::of(Some(Profile::Devnet)).rpc_url, Some("https://api.devnet.solana.com"));
+        assert_eq!(ProfileDefaults::of(Some(Profile::Mainnet)).vault_program_id, None);
+        assert_eq!(ProfileDefaults::of(None).commitment, "confirmed");
+        assert!(setting("PROFILE_TEST_UNSET_SETTING", None).is_err());
+        assert_eq!(setting("PROFILE_TEST_UNSET_SETTING", Some("x")).unwrap(), "x");
+
+        let printed = serde_json::to_value(Printed {
+            redis_url: "redis://:hunter2@redis:6379/0".to_string(),
+            api_key: Some("hunter2".to_string()),
+            keypair: "[1,2,3]".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            printed,
+            serde_json::json!({
+                "redis_url": "redis://<redacted>@redis:6379/0",
+                "api_key": "<redacted>",
+                "keypair": "<redacted>",
+            })
+        );
+        assert_eq!(without_credentials("redis://localhost:6379"), "redis://localhost:6379");
+    }
+}
@@ -0,0 +1,342 @@
+//! Secrets referenced from configuration
+//!
+//! Config values holding secrets (keypairs, API keys, credentials inside Redis
+//! and Postgres URLs) may name where the secret is kept instead of holding it:
+//!
+//! - `file:///run/secrets/admin-key` reads a file, without its trailing newline
+//! - `env://OTHER_VAR` reads another environment variable
+//! - `aws-sm://<secret-id>?region=<region>` reads an AWS Secrets Manager secret
+//!   (region defaults to `AWS_REGION` / `AWS_DEFAULT_REGION`)
+//! - `gcp-sm://projects/<project>/secrets/<name>[/versions/<version>]` reads a
+//!   GCP Secret Manager version, `latest` by default
+//!
+//! A `#field` suffix picks one field of a JSON secret. Any other value is the
+//! secret itself, so plaintext values keep working. AWS credentials come from
+//! `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`; GCP
+//! access tokens from `GOOGLE_OAUTH_ACCESS_TOKEN` or the instance metadata server.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use solana_sdk::signature::{read_keypair_file, Keypair};
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const GCP_METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// Where a secret is kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretSource {
+    /// The config value is the secret
+    Plain(String),
+    File(PathBuf),
+    Env(String),
+    AwsSecretsManager { secret_id: String, region: Option<String> },
+    /// Full resource name of a secret version
+    GcpSecretManager { version: String },
+}
+
+/// A parsed config value: the secret's source and the JSON field to pick, if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretRef {
+    pub source: SecretSource,
+    pub field: Option<String>,
+}
+
+impl SecretRef {
+    pub fn parse(value: &str) -> Result<Self> {
+        let Some((scheme, rest)) = value.split_once("://") else {
+            return Ok(Self { source: SecretSource::Plain(value.to_string()), field: None });
+        };
+        let (location, field) = match rest.split_once('#') {
+            Some((location, field)) => (location, Some(field.to_string())),
+            None => (rest, None),
+        };
+        let source = match scheme {
+            "file" => SecretSource::File(PathBuf::from(location)),
+            "env" => SecretSource::Env(location.to_string()),
+            "aws-sm" => {
+                let (secret_id, query) = location.split_once('?').unwrap_or((location, ""));
+                let region = query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("region="))
+                    .map(str::to_string);
+                SecretSource::AwsSecretsManager { secret_id: secret_id.to_string(), region }
+            }
+            "gcp-sm" => {
+                if !location.starts_with("projects/") || !location.contains("/secrets/") {
+                    bail!("gcp-sm:// needs projects/<project>/secrets/<name>");
+                }
+                let version = if location.contains("/versions/") {
+                    location.to_string()
+                } else {
+                    format!("{}/versions/latest", location)
+                };
+                SecretSource::GcpSecretManager { version }
+            }
+            // Plain values that merely look like URLs, e.g. redis:// or postgres://
+            _ => return Ok(Self { source: SecretSource::Plain(value.to_string()), field: None }),
+        };
+        if matches!(&source, SecretSource::File(path) if path.as_os_str().is_empty())
+            || matches!(&source, SecretSource::Env(name) | SecretSource::AwsSecretsManager { secret_id: name, .. } if name.is_empty())
+        {
+            bail!("{}:// needs a location", scheme);
+        }
+        Ok(Self { source, field })
+    }
+}
+
+/// Resolves config values, fetching each referenced secret once
+#[derive(Default)]
+pub struct Resolver {
+    client: Option<reqwest::Client>,
+    resolved: HashMap<String, String>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The secret a config value names, or the value itself when it names none
+    pub async fn resolve(&mut self, value: &str) -> Result<String> {
+        if let Some(secret) = self.resolved.get(value) {
+            return Ok(secret.clone());
+        }
+        let secret_ref = SecretRef::parse(value)?;
+        let secret = match &secret_ref.source {
+            SecretSource::Plain(value) => return Ok(value.clone()),
+            SecretSource::File(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read secret file {}", path.display()))?
+                .trim_end_matches(['\n', '\r'])
+                .to_string(),
+            SecretSource::Env(name) => env::var(name).with_context(|| format!("{} is not set", name))?,
+            SecretSource::AwsSecretsManager { secret_id, region } => {
+                aws_secret(self.client()?, secret_id, region.as_deref()).await?
+            }
+            SecretSource::GcpSecretManager { version } => gcp_secret(self.client()?, version).await?,
+        };
+        let secret = match &secret_ref.field {
+            Some(field) => json_field(&secret, field)?,
+            None => secret,
+        };
+        self.resolved.insert(value.to_string(), secret.clone());
+        Ok(secret)
+    }
+
+    /// Replace `value` with the secret it names
+    pub async fn resolve_in_place(&mut self, value: &mut String) -> Result<()> {
+        *value = self.resolve(value).await?;
+        Ok(())
+    }
+
+    pub async fn resolve_optional(&mut self, value: &mut Option<String>) -> Result<()> {
+        if let Some(value) = value {
+            self.resolve_in_place(value).await?;
+        }
+        Ok(())
+    }
+
+    fn client(&mut self) -> Result<&reqwest::Client> {
+        if self.client.is_none() {
+            self.client = Some(reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?);
+        }
+        Ok(self.client.as_ref().expect("client was just built"))
+    }
+}
+
+/// A keypair from a resolved config value: the keypair's JSON bytes as written
+/// by the Solana CLI, or the path of such a file
+pub fn read_keypair(value: &str) -> Result<Keypair> {
+    if value.trim_start().starts_with('[') {
+        let bytes: Vec<u8> = serde_json::from_str(value).context("Keypair secret is not a JSON byte array")?;
+        return Keypair::from_bytes(&bytes).map_err(|e| anyhow!("Invalid keypair secret: {}", e));
+    }
+    read_keypair_file(value).map_err(|e| anyhow!("Failed to read keypair {}: {}", value, e))
+}
+
+fn json_field(secret: &str, field: &str) -> Result<String> {
+    let value: serde_json::Value = serde_json::from_str(secret).context("Secret is not a JSON object")?;
+    match value.get(field) {
+        Some(serde_json::Value::String(value)) => Ok(value.clone()),
+        Some(value) => Ok(value.to_string()),
+        None => bail!("Secret has no field {:?}", field),
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// AWS Signature Version 4 key for one day, region and service
+fn sigv4_signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+/// `Authorization` header of a SigV4-signed `POST /` with `headers` (lowercase
+/// names, sorted) and `body`
+fn sigv4_authorization(
+    access_key: &str,
+    secret_key: &str,
+    region: &str,
+    service: &str,
+    amz_date: &str,
+    headers: &[(&str, String)],
+    body: &str,
+) -> String {
+    let date = &amz_date[..8];
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+    let canonical_request =
+        format!("POST\n/\n\n{}\n{}\n{}", canonical_headers, signed_headers, sha256_hex(body.as_bytes()));
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign =
+        format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, sha256_hex(canonical_request.as_bytes()));
+    let signature = hex::encode(hmac_sha256(
+        &sigv4_signing_key(secret_key, date, region, service),
+        string_to_sign.as_bytes(),
+    ));
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        access_key, scope, signed_headers, signature
+    )
+}
+
+async fn aws_secret(client: &reqwest::Client, secret_id: &str, region: Option<&str>) -> Result<String> {
+    let region = match region {
+        Some(region) => region.to_string(),
+        None => env::var("AWS_REGION")
+            .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+            .context("aws-sm:// needs ?region= or AWS_REGION")?,
+    };
+    let access_key = env::var("AWS_ACCESS_KEY_ID").context("AWS_ACCESS_KEY_ID is not set")?;
+    let secret_key = env::var("AWS_SECRET_ACCESS_KEY").context("AWS_SECRET_ACCESS_KEY is not set")?;
+    let session_token = env::var("AWS_SESSION_TOKEN").ok().filter(|token| !token.is_empty());
+
+    let host = format!("secretsmanager.{}.amazonaws.com", region);
+    let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let body = serde_json::json!({ "SecretId": secret_id }).to_string();
+    let mut headers = vec![
+        ("content-type", "application/x-amz-json-1.1".to_string()),
+        ("host", host.clone()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    headers.push(("x-amz-target", "secretsmanager.GetSecretValue".to_string()));
+    let authorization =
+        sigv4_authorization(&access_key, &secret_key, &region, "secretsmanager", &amz_date, &headers, &body);
+
+    let mut request = client.post(format!("https://{}/", host)).header("authorization", authorization).body(body);
+    for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+        request = request.header(*name, value);
+    }
+    let response = request.send().await.context("AWS Secrets Manager request failed")?;
+    let status = response.status();
+    let payload: serde_json::Value = response.json().await.context("Unreadable AWS Secrets Manager response")?;
+    if !status.is_success() {
+        bail!("AWS Secrets Manager returned {} for {}: {}", status, secret_id, payload);
+    }
+    if let Some(secret) = payload["SecretString"].as_str() {
+        return Ok(secret.to_string());
+    }
+    let binary = payload["SecretBinary"].as_str().context("Secret has neither SecretString nor SecretBinary")?;
+    let bytes = base64::engine::general_purpose::STANDARD.decode(binary)?;
+    String::from_utf8(bytes).context("SecretBinary is not UTF-8")
+}
+
+async fn gcp_access_token(client: &reqwest::Client) -> Result<String> {
+    if let Ok(token) = env::var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+        return Ok(token);
+    }
+    let response: serde_json::Value = client
+        .get(GCP_METADATA_TOKEN_URL)
+        .header("Metadata-Flavor", "Google")
+        .send()
+        .await
+        .context("No GOOGLE_OAUTH_ACCESS_TOKEN and the GCP metadata server is unreachable")?
+        .error_for_status()?
+        .json()
+        .await?;
+    response["access_token"].as_str().map(str::to_string).context("Metadata server returned no access token")
+}
+
+async fn gcp_secret(client: &reqwest::Client, version: &str) -> Result<String> {
+    let token = gcp_access_token(client).await?;
+    let response = client
+        .get(format!("https://secretmanager.googleapis.com/v1/{}:access", version))
+        .bearer_auth(token)
+        .send()
+        .await
+        .context("GCP Secret Manager request failed")?;
+    let status = response.status();
+    let payload: serde_json::Value = response.json().await.context("Unreadable GCP Secret Manager response")?;
+    if !status.is_success() {
+        bail!("GCP Secret Manager returned {} for {}: {}", status, version, payload);
+    }
+    let data = payload["payload"]["data"].as_str().context("Secret version has no payload")?;
+    let bytes = base64::engine::general_purpose::STANDARD.decode(data)?;
+    String::from_utf8(bytes).context("Secret payload is not UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_secret_references() {
+        assert_eq!(
+            SecretRef::parse("aws-sm://prod/processor?region=eu-west-1#api_key").unwrap(),
+            SecretRef {
+                source: SecretSource::AwsSecretsManager {
+                    secret_id: "prod/processor".to_string(),
+                    region: Some("eu-west-1".to_string()),
+                },
+                field: Some("api_key".to_string()),
+            }
+        );
+        assert_eq!(
+            SecretRef::parse("gcp-sm://projects/p/secrets/redis").unwrap().source,
+            SecretSource::GcpSecretManager { version: "projects/p/secrets/redis/versions/latest".to_string() }
+        );
+        assert!(SecretRef::parse("gcp-sm://redis").is_err());
+        for plain in ["redis://:pw@localhost:6379", "settlement-api-key#2026"] {
+            assert_eq!(SecretRef::parse(plain).unwrap().source, SecretSource::Plain(plain.to_string()));
+        }
+
+        // Example from the AWS SigV4 documentation
+        let key = sigv4_signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex::encode(key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+
+        let path = env::temp_dir().join(format!("secret-{}", std::process::id()));
+        std::fs::write(&path, "{\"api_key\":\"k\",\"port\":6379}\n").unwrap();
+        let mut resolver = Resolver::new();
+        let reference = format!("file://{}#api_key", path.display());
+        assert_eq!(resolver.resolve(&reference).await.unwrap(), "k");
+        assert_eq!(resolver.resolve(&format!("file://{}#port", path.display())).await.unwrap(), "6379");
+        std::fs::remove_file(&path).unwrap();
+        // Cached, so the file is read once
+        assert_eq!(resolver.resolve(&reference).await.unwrap(), "k");
+
+        let keypair = Keypair::new();
+        let bytes = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        assert_eq!(read_keypair(&bytes).unwrap().to_bytes(), keypair.to_bytes());
+    }
+}
@@ -1,7 +1,7 @@
-/// Type-safe wrappers for domain primitives
-/// 
-/// These types prevent common errors by enforcing validation at construction time
-/// and providing checked arithmetic operations.
+//! Type-safe wrappers for domain primitives
+//! 
+//! These types prevent common errors by enforcing validation at construction time
+//! and providing checked arithmetic operations.
 
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
@@ -30,16 +30,21 @@ pub enum ValidationError {
 
 /// Type-safe bet identifier with validation
 /// 
-/// Enforces maximum length and format validation to prevent PDA derivation errors.
+/// Always the 32-character lowercase hex form of a UUID, so each bet maps to
+/// exactly one processed-bet PDA seed.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BetId(String);
 
 impl BetId {
     /// Create a new BetId from a UUID
     pub fn new(uuid: Uuid) -> Self {
-        // Remove hyphens to save space in PDA seeds (36 -> 32 chars)
-        let id = uuid.to_string().replace("-", "");
-        Self(id)
+        // Simple (unhyphenated) form fits a PDA seed (36 -> 32 chars)
+        Self(uuid.simple().to_string())
+    }
+
+    /// PDA seed bytes
+    pub fn seed(&self) -> &[u8] {
+        self.0.as_bytes()
     }
     
     /// Get the inner string representation
@@ -63,8 +68,8 @@ impl TryFrom<String> for BetId {
     type Error = ValidationError;
     
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        // Remove hyphens if present
-        let normalized = value.replace("-", "");
+        // Remove hyphens if present; one case so equal UUIDs share a seed
+        let normalized = value.replace("-", "").to_ascii_lowercase();
         
         // Validate length
         if normalized.len() > MAX_BET_ID_LENGTH {
@@ -73,6 +78,10 @@ impl TryFrom<String> for BetId {
                 max: MAX_BET_ID_LENGTH,
             });
         }
+        // Truncated ids could collide with each other
+        if normalized.len() < MAX_BET_ID_LENGTH {
+            return Err(ValidationError::InvalidBetIdFormat(value));
+        }
         
         // Validate it's a valid UUID format
         Uuid::parse_str(&normalized)
@@ -105,7 +114,7 @@ pub struct LamportAmount(u64);
 impl LamportAmount {
     /// Create a new LamportAmount with validation
     pub fn new(amount: u64) -> Result<Self, ValidationError> {
-        if amount < MIN_BET_LAMPORTS || amount > MAX_BET_LAMPORTS {
+        if !(MIN_BET_LAMPORTS..=MAX_BET_LAMPORTS).contains(&amount) {
             return Err(ValidationError::BetAmountOutOfRange {
                 amount,
                 min: MIN_BET_LAMPORTS,
@@ -263,6 +272,18 @@ mod tests {
         let result = BetId::try_from(long_string);
         assert!(matches!(result, Err(ValidationError::BetIdTooLong { .. })));
     }
+
+    #[test]
+    fn test_bet_id_is_full_lowercase_uuid() {
+        let uuid = Uuid::new_v4();
+        let hyphenated = uuid.to_string().to_uppercase();
+        assert_eq!(BetId::try_from(hyphenated).unwrap(), BetId::new(uuid));
+        assert_eq!(BetId::new(uuid).as_str().len(), MAX_BET_ID_LENGTH);
+
+        // A truncated id is rejected rather than padded or accepted
+        let truncated = uuid.simple().to_string()[..24].to_string();
+        assert!(matches!(BetId::try_from(truncated), Err(ValidationError::InvalidBetIdFormat(_))));
+    }
     
     #[test]
     fn test_lamport_amount_validation() {
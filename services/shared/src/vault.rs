@@ -0,0 +1,876 @@
+//! Vault program PDAs, admin instruction builders and account decoding
+//!
+//! Mirrors the account layouts and instruction signatures in
+//! `contracts/programs/vault`, so services and tooling build identical
+//! transactions without depending on the Anchor crate.
+
+use anyhow::{bail, Context, Result};
+use solana_sdk::{
+    hash::hashv,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::types::BetId;
+
+/// Anchor instruction discriminator: `sha256("global:<name>")[..8]`
+pub fn anchor_discriminator(name: &str) -> [u8; 8] {
+    let hash = hashv(&[format!("global:{}", name).as_bytes()]);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+    discriminator
+}
+
+/// Anchor account discriminator: `sha256("account:<Name>")[..8]`
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    let hash = hashv(&[format!("account:{}", name).as_bytes()]);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+    discriminator
+}
+
+/// Derive casino PDA
+pub fn casino_pda(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"casino"], program_id).0
+}
+
+/// Derive the casino vault PDA holding house funds
+pub fn casino_vault_pda(casino: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"casino-vault", casino.as_ref()], program_id).0
+}
+
+/// Derive the vault authority PDA that signs for casino token accounts
+pub fn vault_authority_pda(casino: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"vault-authority", casino.as_ref()], program_id).0
+}
+
+/// Derive user vault PDA
+pub fn user_vault_pda(user: &Pubkey, casino: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"vault", casino.as_ref(), user.as_ref()], program_id).0
+}
+
+/// Derive the per-user allowance nonce registry PDA
+pub fn allowance_nonce_registry_pda(user: &Pubkey, casino: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"allowance-nonce", user.as_ref(), casino.as_ref()], program_id).0
+}
+
+/// Derive the allowance PDA for a given nonce
+pub fn allowance_pda(user: &Pubkey, casino: &Pubkey, nonce: u64, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"allowance", user.as_ref(), casino.as_ref(), &nonce.to_le_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// Derive the allowance-approval rate limiter PDA
+pub fn rate_limiter_pda(user: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"rate-limiter", user.as_ref()], program_id).0
+}
+
+/// Build initialize_casino_vault; `payer` signs and pays rent, `authority` becomes the casino admin
+pub fn build_initialize_casino_vault_instruction(program_id: &Pubkey, payer: &Pubkey, authority: &Pubkey) -> Instruction {
+    let casino = casino_pda(program_id);
+    let mut data = anchor_discriminator("initialize_casino_vault").to_vec();
+    data.extend_from_slice(authority.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(casino, false),
+            AccountMeta::new(casino_vault_pda(&casino, program_id), false),
+            AccountMeta::new_readonly(vault_authority_pda(&casino, program_id), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Derive the per-mint bet limits PDA; native SOL uses the system program id as its mint
+pub fn token_config_pda(casino: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"token-config", casino.as_ref(), mint.as_ref()], program_id).0
+}
+
+/// Build set_token_config instruction creating or updating a mint's bet limits
+pub fn build_set_token_config_instruction(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    min_bet: u64,
+    max_bet: u64,
+    bet_enabled: bool,
+) -> Instruction {
+    let casino = casino_pda(program_id);
+    let mut data = anchor_discriminator("set_token_config").to_vec();
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&min_bet.to_le_bytes());
+    data.extend_from_slice(&max_bet.to_le_bytes());
+    data.push(bet_enabled as u8);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(token_config_pda(&casino, mint, program_id), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Build pause_casino (or unpause_casino) instruction signed by the casino authority
+pub fn build_set_paused_instruction(program_id: &Pubkey, authority: &Pubkey, paused: bool) -> Instruction {
+    let name = if paused { "pause_casino" } else { "unpause_casino" };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(casino_pda(program_id), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: anchor_discriminator(name).to_vec(),
+    }
+}
+
+/// Build set_processor instruction rotating the key allowed to settle bets
+pub fn build_set_processor_instruction(program_id: &Pubkey, authority: &Pubkey, new_processor: &Pubkey) -> Instruction {
+    let mut data = anchor_discriminator("set_processor").to_vec();
+    data.extend_from_slice(new_processor.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(casino_pda(program_id), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+/// Build add_processor instruction authorizing another key to settle bets
+pub fn build_add_processor_instruction(program_id: &Pubkey, authority: &Pubkey, processor: &Pubkey) -> Instruction {
+    let mut data = anchor_discriminator("add_processor").to_vec();
+    data.extend_from_slice(processor.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(casino_pda(program_id), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+/// Build remove_processor instruction revoking a processor key
+pub fn build_remove_processor_instruction(program_id: &Pubkey, authority: &Pubkey, processor: &Pubkey) -> Instruction {
+    let mut data = anchor_discriminator("remove_processor").to_vec();
+    data.extend_from_slice(processor.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(casino_pda(program_id), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+/// Build migrate_casino instruction resizing a casino account for extra processors;
+/// the authority pays the additional rent
+pub fn build_migrate_casino_instruction(program_id: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(casino_pda(program_id), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: anchor_discriminator("migrate_casino").to_vec(),
+    }
+}
+
+/// Build migrate instruction bringing the casino account to the program's layout
+/// and stamping its version; the authority pays any additional rent
+pub fn build_migrate_instruction(program_id: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(casino_pda(program_id), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: anchor_discriminator("migrate").to_vec(),
+    }
+}
+
+/// Build set_authority instruction transferring casino admin rights (e.g. to a multisig vault)
+pub fn build_set_authority_instruction(program_id: &Pubkey, authority: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    let mut data = anchor_discriminator("set_authority").to_vec();
+    data.extend_from_slice(new_authority.as_ref());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(casino_pda(program_id), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+/// Build withdraw_casino_funds instruction; lamports go to the authority
+pub fn build_withdraw_casino_funds_instruction(program_id: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+    let casino = casino_pda(program_id);
+    let mut data = anchor_discriminator("withdraw_casino_funds").to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(casino_vault_pda(&casino, program_id), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Build fund_casino_vault instruction; `treasury` (casino treasury or authority) pays in `amount` lamports
+pub fn build_fund_casino_vault_instruction(program_id: &Pubkey, treasury: &Pubkey, amount: u64) -> Instruction {
+    let casino = casino_pda(program_id);
+    let mut data = anchor_discriminator("fund_casino_vault").to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(casino_vault_pda(&casino, program_id), false),
+            AccountMeta::new(*treasury, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Build initialize_vault instruction; `user` creates and pays for their own vault
+pub fn build_initialize_vault_instruction(program_id: &Pubkey, user: &Pubkey) -> Instruction {
+    let casino = casino_pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_vault_pda(user, &casino, program_id), false),
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: anchor_discriminator("initialize_vault").to_vec(),
+    }
+}
+
+/// Build deposit_sol instruction moving `amount` lamports from `user` into their vault
+pub fn build_deposit_sol_instruction(program_id: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+    let casino = casino_pda(program_id);
+    let mut data = anchor_discriminator("deposit_sol").to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_vault_pda(user, &casino, program_id), false),
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Build reconcile_casino_vault instruction syncing the tracked balance with actual lamports
+pub fn build_reconcile_casino_vault_instruction(program_id: &Pubkey, authority: &Pubkey) -> Instruction {
+    let casino = casino_pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(casino, false),
+            AccountMeta::new(casino_vault_pda(&casino, program_id), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: anchor_discriminator("reconcile_casino_vault").to_vec(),
+    }
+}
+
+/// Record the vault program creates when a bet's stake is spent or the bet is settled
+pub fn processed_bet_pda(bet_id: &BetId, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"processed-bet", bet_id.seed()], program_id).0
+}
+
+/// Record `payout` creates for a bet; a namespace apart from processed-bet records
+pub fn payout_record_pda(bet_id: &BetId, program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"payout", bet_id.seed()], program_id).0
+}
+
+/// Little-endian reader over Anchor account data, after the 8-byte discriminator
+struct AccountReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    account: &'static str,
+}
+
+impl<'a> AccountReader<'a> {
+    fn new(data: &'a [u8], account: &'static str) -> Result<Self> {
+        if data.len() < 8 {
+            bail!("{} account data too short: {} bytes", account, data.len());
+        }
+        Ok(Self { data, offset: 8, account })
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + N)
+            .with_context(|| format!("{} account data too short: {} bytes", self.account, self.data.len()))?;
+        self.offset += N;
+        Ok(bytes.try_into().expect("slice has N bytes"))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey> {
+        Ok(Pubkey::new_from_array(self.take()?))
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.offset)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.take()?))
+    }
+
+    /// Borsh string: u32 length prefix, then UTF-8 bytes
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + len)
+            .with_context(|| format!("{} account data too short: {} bytes", self.account, self.data.len()))?;
+        self.offset += len;
+        String::from_utf8(bytes.to_vec()).with_context(|| format!("{} account has a non UTF-8 string", self.account))
+    }
+}
+
+/// Decoded user `Vault` account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultAccount {
+    pub owner: Pubkey,
+    pub casino: Pubkey,
+    pub bump: u8,
+    pub sol_balance: u64,
+    pub created_at: i64,
+    pub last_activity: i64,
+    /// Where the owner's winnings are paid instead of the vault, set with `set_payout_address`
+    pub payout_address: Option<Pubkey>,
+}
+
+impl VaultAccount {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = AccountReader::new(data, "Vault")?;
+        Ok(Self {
+            owner: r.pubkey()?,
+            casino: r.pubkey()?,
+            bump: r.u8()?,
+            sol_balance: r.u64()?,
+            created_at: r.i64()?,
+            last_activity: r.i64()?,
+            // Vaults are only extended by `set_payout_address`; a cleared one holds the default key
+            payout_address: if r.remaining() >= 32 {
+                Some(r.pubkey()?).filter(|address| *address != Pubkey::default())
+            } else {
+                None
+            },
+        })
+    }
+}
+
+/// Extra processor slots in `Casino`, mirroring the program's `MAX_EXTRA_PROCESSORS`
+pub const MAX_EXTRA_PROCESSORS: usize = 4;
+
+/// Newest `Casino::program_version` these account decoders and instruction builders
+/// understand; settling against a newer program risks layout or discriminator mismatches
+pub const MAX_SUPPORTED_PROGRAM_VERSION: u32 = 3;
+
+/// First program version that requires the mint's `TokenConfig` PDA on every
+/// stake, whether or not the authority configured it
+pub const TOKEN_CONFIG_REQUIRED_VERSION: u32 = 3;
+
+/// Decoded `Casino` account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CasinoAccount {
+    pub authority: Pubkey,
+    pub processor: Pubkey,
+    pub treasury: Pubkey,
+    pub bump: u8,
+    pub vault_authority_bump: u8,
+    pub paused: bool,
+    pub total_bets: u64,
+    pub total_volume: u64,
+    pub created_at: i64,
+    /// Additional authorized processors, empty slots omitted
+    pub extra_processors: Vec<Pubkey>,
+    /// Version stamped by the program's `migrate`; 0 for accounts it never touched
+    pub program_version: u32,
+}
+
+impl CasinoAccount {
+    /// Account size including discriminator, extra processor slots and version
+    pub const LEN: usize = 8 + 32 * 3 + 3 + 8 * 3 + 32 * MAX_EXTRA_PROCESSORS + 4;
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = AccountReader::new(data, "Casino")?;
+        Ok(Self {
+            authority: r.pubkey()?,
+            processor: r.pubkey()?,
+            treasury: r.pubkey()?,
+            bump: r.u8()?,
+            vault_authority_bump: r.u8()?,
+            paused: r.bool()?,
+            total_bets: r.u64()?,
+            total_volume: r.u64()?,
+            created_at: r.i64()?,
+            // Accounts not yet resized by `migrate_casino` end at `created_at`
+            extra_processors: if r.remaining() > 0 {
+                (0..MAX_EXTRA_PROCESSORS)
+                    .map(|_| r.pubkey())
+                    .filter(|key| !matches!(key, Ok(key) if *key == Pubkey::default()))
+                    .collect::<Result<_>>()?
+            } else {
+                Vec::new()
+            },
+            program_version: if r.remaining() >= 4 { r.u32()? } else { 0 },
+        })
+    }
+
+    /// Whether the account predates `extra_processors` or `program_version`
+    pub fn needs_migration(data: &[u8]) -> bool {
+        data.len() < Self::LEN
+    }
+
+    /// Every key allowed to settle bets, primary first
+    pub fn processors(&self) -> impl Iterator<Item = &Pubkey> {
+        std::iter::once(&self.processor).chain(&self.extra_processors)
+    }
+}
+
+/// Decoded `CasinoVault` account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CasinoVaultAccount {
+    pub casino: Pubkey,
+    pub bump: u8,
+    pub sol_balance: u64,
+    pub created_at: i64,
+    pub last_activity: i64,
+}
+
+impl CasinoVaultAccount {
+    /// Account size including discriminator, for the rent-exempt reserve
+    pub const LEN: usize = 8 + 32 + 1 + 8 + 8 + 8;
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = AccountReader::new(data, "CasinoVault")?;
+        Ok(Self {
+            casino: r.pubkey()?,
+            bump: r.u8()?,
+            sol_balance: r.u64()?,
+            created_at: r.i64()?,
+            last_activity: r.i64()?,
+        })
+    }
+}
+
+/// Decoded `AllowanceNonceRegistry` account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceRegistryAccount {
+    pub user: Pubkey,
+    pub casino: Pubkey,
+    pub next_nonce: u64,
+    pub bump: u8,
+}
+
+impl NonceRegistryAccount {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = AccountReader::new(data, "AllowanceNonceRegistry")?;
+        Ok(Self {
+            user: r.pubkey()?,
+            casino: r.pubkey()?,
+            next_nonce: r.u64()?,
+            bump: r.u8()?,
+        })
+    }
+}
+
+/// Decoded `Allowance` account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowanceAccount {
+    pub user: Pubkey,
+    pub casino: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub spent: u64,
+    pub expires_at: i64,
+    pub created_at: i64,
+    pub nonce: u64,
+    pub revoked: bool,
+    pub bump: u8,
+    pub last_spent_at: i64,
+    pub spend_count: u32,
+    /// Velocity limits set by `approve_allowance_v3`; 0 means unlimited
+    pub max_single_spend: u64,
+    pub max_spend_per_hour: u64,
+    pub window_start: i64,
+    pub window_spent: u64,
+}
+
+impl AllowanceAccount {
+    /// Account size including discriminator and velocity limits
+    pub const LEN: usize = 8 + 32 * 3 + 8 * 5 + 1 + 1 + 8 + 4 + 8 * 4;
+
+    /// Whether the account predates velocity limits and needs `migrate_allowance`
+    /// before the program will spend from or revoke it
+    pub fn needs_migration(data: &[u8]) -> bool {
+        data.len() < Self::LEN
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = AccountReader::new(data, "Allowance")?;
+        Ok(Self {
+            user: r.pubkey()?,
+            casino: r.pubkey()?,
+            token_mint: r.pubkey()?,
+            amount: r.u64()?,
+            spent: r.u64()?,
+            expires_at: r.i64()?,
+            created_at: r.i64()?,
+            nonce: r.u64()?,
+            revoked: r.bool()?,
+            bump: r.u8()?,
+            last_spent_at: r.i64()?,
+            spend_count: r.u32()?,
+            // Accounts created before velocity limits end at `spend_count`
+            max_single_spend: if r.remaining() > 0 { r.u64()? } else { 0 },
+            max_spend_per_hour: if r.remaining() > 0 { r.u64()? } else { 0 },
+            window_start: if r.remaining() > 0 { r.i64()? } else { 0 },
+            window_spent: if r.remaining() > 0 { r.u64()? } else { 0 },
+        })
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.amount.saturating_sub(self.spent)
+    }
+
+    /// Usable at `now` (unix seconds): not revoked and not expired
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.revoked && now <= self.expires_at
+    }
+
+    /// Amount still spendable in the hourly window at `now`, if the allowance has an hourly limit
+    pub fn hourly_remaining(&self, now: i64) -> Option<u64> {
+        if self.max_spend_per_hour == 0 {
+            return None;
+        }
+        let window_spent = if now - self.window_start >= 3600 { 0 } else { self.window_spent };
+        Some(self.max_spend_per_hour.saturating_sub(window_spent))
+    }
+}
+
+/// Outcome stored on a `ProcessedBet` by `settle_bet`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BetOutcome {
+    /// Settled by `spend_from_allowance` (optionally paired with `payout`)
+    Unrecorded,
+    Lost,
+    Won,
+    /// Voided after a loss by `refund_bet`; `payout` holds the returned stake
+    Refunded,
+}
+
+impl BetOutcome {
+    fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(BetOutcome::Unrecorded),
+            1 => Ok(BetOutcome::Lost),
+            2 => Ok(BetOutcome::Won),
+            3 => Ok(BetOutcome::Refunded),
+            other => bail!("Unknown ProcessedBet outcome {}", other),
+        }
+    }
+}
+
+/// Decoded `ProcessedBet` account, created once per settled bet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessedBetAccount {
+    pub bet_id: String,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub processed_at: i64,
+    pub signature: String,
+    pub bump: u8,
+    pub outcome: BetOutcome,
+    pub payout: u64,
+}
+
+impl ProcessedBetAccount {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = AccountReader::new(data, "ProcessedBet")?;
+        Ok(Self {
+            bet_id: r.string()?,
+            user: r.pubkey()?,
+            amount: r.u64()?,
+            processed_at: r.i64()?,
+            signature: r.string()?,
+            bump: r.u8()?,
+            // Accounts created before `settle_bet` end at the bump
+            outcome: if r.remaining() > 0 { BetOutcome::from_u8(r.u8()?)? } else { BetOutcome::Unrecorded },
+            payout: if r.remaining() > 0 { r.u64()? } else { 0 },
+        })
+    }
+}
+
+/// Decoded `TokenConfig` account holding a mint's bet limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenConfigAccount {
+    pub casino: Pubkey,
+    /// `system_program::ID` for native SOL
+    pub mint: Pubkey,
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub bet_enabled: bool,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl TokenConfigAccount {
+    pub const LEN: usize = 8 + 32 * 2 + 8 * 2 + 1 + 8 + 1;
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = AccountReader::new(data, "TokenConfig")?;
+        Ok(Self {
+            casino: r.pubkey()?,
+            mint: r.pubkey()?,
+            min_bet: r.u64()?,
+            max_bet: r.u64()?,
+            bet_enabled: r.bool()?,
+            updated_at: r.i64()?,
+            bump: r.u8()?,
+        })
+    }
+
+    pub fn is_native_sol(&self) -> bool {
+        self.mint == system_program::ID
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_casino() {
+        let authority = Pubkey::new_unique();
+        let processor = Pubkey::new_unique();
+        let mut data = vec![0u8; 8];
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(processor.as_ref());
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&[254, 253, 1]);
+        data.extend_from_slice(&10u64.to_le_bytes());
+        data.extend_from_slice(&500u64.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+
+        let casino = CasinoAccount::decode(&data).unwrap();
+        assert_eq!(casino.authority, authority);
+        assert_eq!(casino.processor, processor);
+        assert!(casino.paused);
+        assert_eq!(casino.total_volume, 500);
+        assert_eq!(casino.created_at, 1_700_000_000);
+
+        assert!(casino.extra_processors.is_empty());
+        assert!(CasinoAccount::needs_migration(&data));
+
+        assert!(CasinoAccount::decode(&data[..data.len() - 1]).is_err());
+
+        let extra = Pubkey::new_unique();
+        data.extend_from_slice(Pubkey::default().as_ref());
+        data.extend_from_slice(extra.as_ref());
+        data.extend_from_slice(&[0u8; 32 * (MAX_EXTRA_PROCESSORS - 2)]);
+        assert!(CasinoAccount::needs_migration(&data));
+        assert_eq!(CasinoAccount::decode(&data).unwrap().program_version, 0);
+
+        data.extend_from_slice(&1u32.to_le_bytes());
+        assert!(!CasinoAccount::needs_migration(&data));
+        let casino = CasinoAccount::decode(&data).unwrap();
+        assert_eq!(casino.program_version, 1);
+        assert_eq!(casino.extra_processors, vec![extra]);
+        assert_eq!(casino.processors().copied().collect::<Vec<_>>(), vec![processor, extra]);
+    }
+
+    #[test]
+    fn test_decode_vault_payout_address() {
+        let owner = Pubkey::new_unique();
+        let mut data = vec![0u8; 8];
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.push(255);
+        data.extend_from_slice(&500u64.to_le_bytes());
+        data.extend_from_slice(&1i64.to_le_bytes());
+        data.extend_from_slice(&2i64.to_le_bytes());
+
+        // Vaults never extended by `set_payout_address`
+        let vault = VaultAccount::decode(&data).unwrap();
+        assert_eq!((vault.owner, vault.sol_balance, vault.payout_address), (owner, 500, None));
+
+        let cold = Pubkey::new_unique();
+        let mut extended = data.clone();
+        extended.extend_from_slice(cold.as_ref());
+        assert_eq!(VaultAccount::decode(&extended).unwrap().payout_address, Some(cold));
+
+        // Cleared
+        data.extend_from_slice(Pubkey::default().as_ref());
+        assert_eq!(VaultAccount::decode(&data).unwrap().payout_address, None);
+    }
+
+    #[test]
+    fn test_decode_processed_bet() {
+        let user = Pubkey::new_unique();
+        let mut data = account_discriminator("ProcessedBet").to_vec();
+        data.extend_from_slice(&7u32.to_le_bytes());
+        data.extend_from_slice(b"bet-123");
+        data.extend_from_slice(user.as_ref());
+        data.extend_from_slice(&250u64.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.push(255);
+
+        let bet = ProcessedBetAccount::decode(&data).unwrap();
+        assert_eq!(bet.bet_id, "bet-123");
+        assert_eq!(bet.user, user);
+        assert_eq!(bet.amount, 250);
+        assert_eq!(bet.signature, "");
+        assert_eq!(bet.bump, 255);
+        assert_eq!(bet.outcome, BetOutcome::Unrecorded);
+
+        data.push(2);
+        data.extend_from_slice(&500u64.to_le_bytes());
+        let bet = ProcessedBetAccount::decode(&data).unwrap();
+        assert_eq!((bet.outcome, bet.payout), (BetOutcome::Won, 500));
+
+        let outcome_at = data.len() - 9;
+        data[outcome_at] = 3;
+        assert_eq!(ProcessedBetAccount::decode(&data).unwrap().outcome, BetOutcome::Refunded);
+
+        assert!(ProcessedBetAccount::decode(&data[..20]).is_err());
+    }
+
+    #[test]
+    fn test_decode_allowance_limits() {
+        let mut data = account_discriminator("Allowance").to_vec();
+        data.extend_from_slice(&[0u8; 32 * 3]);
+        for value in [1_000u64, 300, 1_700_003_600, 1_700_000_000, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&[0, 255]);
+        data.extend_from_slice(&1_700_000_100i64.to_le_bytes());
+        data.extend_from_slice(&3u32.to_le_bytes());
+
+        // Pre-limit layout decodes as unlimited
+        let allowance = AllowanceAccount::decode(&data).unwrap();
+        assert_eq!(allowance.spend_count, 3);
+        assert_eq!(allowance.hourly_remaining(1_700_000_200), None);
+        assert!(AllowanceAccount::needs_migration(&data));
+
+        for value in [100u64, 500] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&300u64.to_le_bytes());
+        let allowance = AllowanceAccount::decode(&data).unwrap();
+        assert_eq!((allowance.max_single_spend, allowance.max_spend_per_hour), (100, 500));
+        assert_eq!(data.len(), AllowanceAccount::LEN);
+        assert!(!AllowanceAccount::needs_migration(&data));
+        assert_eq!(allowance.hourly_remaining(1_700_000_200), Some(200));
+        assert_eq!(allowance.hourly_remaining(1_700_003_600), Some(500));
+    }
+
+    #[test]
+    fn test_spend_and_payout_pdas_are_distinct() {
+        let program_id = Pubkey::new_unique();
+        let a = BetId::new(uuid::Uuid::from_u128(1));
+        let b = BetId::new(uuid::Uuid::from_u128(2));
+        assert_ne!(processed_bet_pda(&a, &program_id), payout_record_pda(&a, &program_id));
+        assert_ne!(processed_bet_pda(&a, &program_id), processed_bet_pda(&b, &program_id));
+    }
+
+    #[test]
+    fn test_set_processor_layout() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let new_processor = Pubkey::new_unique();
+        let ix = build_set_processor_instruction(&program_id, &authority, &new_processor);
+
+        assert_eq!(&ix.data[..8], &anchor_discriminator("set_processor"));
+        assert_eq!(&ix.data[8..], new_processor.as_ref());
+        assert_eq!(ix.accounts[0].pubkey, casino_pda(&program_id));
+        assert!(ix.accounts[0].is_writable);
+        assert!(ix.accounts[1].is_signer);
+    }
+
+    #[test]
+    fn test_token_config_layout() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let ix = build_set_token_config_instruction(&program_id, &authority, &mint, 10_000, 5_000_000, true);
+
+        assert_eq!(&ix.data[..8], &anchor_discriminator("set_token_config"));
+        assert_eq!(&ix.data[8..40], mint.as_ref());
+        assert_eq!(ix.data.len(), 8 + 32 + 8 + 8 + 1);
+        let casino = casino_pda(&program_id);
+        assert_eq!(ix.accounts[1].pubkey, token_config_pda(&casino, &mint, &program_id));
+        assert_ne!(token_config_pda(&casino, &system_program::ID, &program_id), ix.accounts[1].pubkey);
+
+        let mut data = account_discriminator("TokenConfig").to_vec();
+        data.extend_from_slice(casino.as_ref());
+        data.extend_from_slice(system_program::ID.as_ref());
+        data.extend_from_slice(&10_000_000u64.to_le_bytes());
+        data.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+        data.push(0);
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.push(254);
+        assert_eq!(data.len(), TokenConfigAccount::LEN);
+
+        let config = TokenConfigAccount::decode(&data).unwrap();
+        assert!(config.is_native_sol());
+        assert!(!config.bet_enabled);
+        assert_eq!((config.min_bet, config.max_bet, config.bump), (10_000_000, 1_000_000_000, 254));
+        assert!(TokenConfigAccount::decode(&data[..data.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Behavior profiles for load testing the simulated path
+//!
+//! By default the processor settles outcomes drawn from the server seed with no
+//! extra delay or failures (`instant`). A profile adds artificial settlement
+//! latency, a share of settlements that fail as if Solana rejected them, and
+//! optionally a fixed win ratio, so a load test exercises realistic timing,
+//! retries and payout volume. Outcomes under a win ratio are derived from the
+//! bet id alone and no longer verify against the server seed commitment.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Delay added before each settlement: `base_ms` plus up to `jitter_ms`, and
+/// `tail_ms` more for a `tail_rate` share of settlements (congested slots)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Latency {
+    pub base_ms: u64,
+    pub jitter_ms: u64,
+    pub tail_ms: u64,
+    pub tail_rate: f64,
+}
+
+impl Latency {
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Duration {
+        let mut ms = self.base_ms;
+        if self.jitter_ms > 0 {
+            ms += rng.gen_range(0..=self.jitter_ms);
+        }
+        if self.tail_ms > 0 && rng.gen_bool(self.tail_rate) {
+            ms += self.tail_ms;
+        }
+        Duration::from_millis(ms)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BehaviorProfile {
+    pub name: String,
+    pub latency: Latency,
+    /// Share of settlements failed before they reach Solana, 0.0 to 1.0
+    pub failure_rate: f64,
+    /// Share of simulated bets won, chosen deterministically from the bet id;
+    /// `None` draws outcomes from the server seed
+    pub win_ratio: Option<f64>,
+}
+
+impl BehaviorProfile {
+    /// No delay, no failures, provably-fair outcomes
+    pub fn instant() -> Self {
+        Self {
+            name: "instant".to_string(),
+            latency: Latency::default(),
+            failure_rate: 0.0,
+            win_ratio: None,
+        }
+    }
+
+    /// Roughly mainnet confirmation times with occasional congestion
+    pub fn realistic() -> Self {
+        Self {
+            name: "realistic".to_string(),
+            latency: Latency { base_ms: 400, jitter_ms: 400, tail_ms: 2_000, tail_rate: 0.05 },
+            failure_rate: 0.01,
+            win_ratio: None,
+        }
+    }
+
+    /// A struggling cluster: slow confirmations and frequent failures
+    pub fn degraded() -> Self {
+        Self {
+            name: "degraded".to_string(),
+            latency: Latency { base_ms: 800, jitter_ms: 1_200, tail_ms: 5_000, tail_rate: 0.2 },
+            failure_rate: 0.1,
+            win_ratio: None,
+        }
+    }
+
+    /// Whether this profile changes anything about the simulated path
+    pub fn is_instant(&self) -> bool {
+        self.latency == Latency::default() && self.failure_rate == 0.0 && self.win_ratio.is_none()
+    }
+
+    /// Reject rates outside 0.0 to 1.0
+    pub fn validate(&self) -> Result<(), String> {
+        let rates = [
+            ("latency tail rate", Some(self.latency.tail_rate)),
+            ("failure rate", Some(self.failure_rate)),
+            ("win ratio", self.win_ratio),
+        ];
+        for (what, rate) in rates {
+            if let Some(rate) = rate.filter(|rate| !(0.0..=1.0).contains(rate)) {
+                return Err(format!("{} {} is outside 0.0..=1.0", what, rate));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether to fail this settlement
+    pub fn should_fail<R: Rng + ?Sized>(&self, rng: &mut R) -> bool {
+        self.failure_rate > 0.0 && rng.gen_bool(self.failure_rate)
+    }
+
+    /// Delay to add before the next settlement and whether it fails
+    pub fn next_settlement(&self) -> (Duration, bool) {
+        let mut rng = rand::thread_rng();
+        (self.latency.sample(&mut rng), self.should_fail(&mut rng))
+    }
+}
+
+impl Default for BehaviorProfile {
+    fn default() -> Self {
+        Self::instant()
+    }
+}
+
+impl FromStr for BehaviorProfile {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "instant" => Ok(Self::instant()),
+            "realistic" => Ok(Self::realistic()),
+            "degraded" => Ok(Self::degraded()),
+            other => Err(format!(
+                "unknown simulation profile {:?} (expected instant, realistic or degraded)",
+                other
+            )),
+        }
+    }
+}
+
+/// Whether `bet_id` wins under `win_ratio`; the same id always gets the same outcome
+pub(crate) fn wins_at_ratio(bet_id: Uuid, win_ratio: f64) -> bool {
+    // splitmix64 finalizer, so sequential ids spread evenly
+    let (high, low) = bet_id.as_u64_pair();
+    let mut x = high ^ low.rotate_left(32);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^= x >> 31;
+    (x as f64 / u64::MAX as f64) < win_ratio
+}
@@ -0,0 +1,190 @@
+//! Seedable bet outcome simulation
+//!
+//! Every bet draws its outcome deterministically from a server seed, the bet id
+//! and the player's client seed (see `shared::fairness` for the derivation), so
+//! the same inputs always produce the same outcome. Bets record the SHA-256
+//! commitment of the server seed; once the seed is revealed, anyone can replay
+//! the bet with [`verify_coinflip`] and check both the commitment and the outcome.
+//!
+//! Load tests can swap that fairness for a fixed win ratio and add latency and
+//! failures to settlement with a [`BehaviorProfile`].
+
+mod behavior;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub use behavior::{BehaviorProfile, Latency};
+pub use shared::fairness::{verify_coinflip, FairnessError, SERVER_SEED_LEN};
+use shared::fairness::{coinflip_won, decode_server_seed, seed_commitment};
+
+/// Coinflip payout multiplier on a win
+pub const COINFLIP_MULTIPLIER: i64 = 2;
+
+/// Secret seed all bet seeds are derived from
+#[derive(Clone, PartialEq, Eq)]
+pub struct ServerSeed([u8; SERVER_SEED_LEN]);
+
+impl ServerSeed {
+    /// Fresh seed from the OS RNG
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; SERVER_SEED_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    pub fn from_hex(hex_seed: &str) -> Result<Self, FairnessError> {
+        decode_server_seed(hex_seed).map(Self)
+    }
+
+    /// Hex encoding; this reveals the seed
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// SHA-256 of the seed, published before the seed is revealed
+    pub fn commitment(&self) -> String {
+        seed_commitment(&self.0)
+    }
+}
+
+/// Deserializes from the hex encoding
+impl<'de> Deserialize<'de> for ServerSeed {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex_seed = String::deserialize(deserializer)?;
+        Self::from_hex(&hex_seed).map_err(serde::de::Error::custom)
+    }
+}
+
+// Never print the secret itself
+impl fmt::Debug for ServerSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ServerSeed").field(&self.commitment()).finish()
+    }
+}
+
+/// Fresh random client seed for bets placed without one
+pub fn generate_client_seed() -> String {
+    let mut bytes = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Provably-fair data stored with a settled bet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FairnessProof {
+    /// Commitment of the server seed the outcome was drawn from
+    pub server_seed_hash: String,
+    pub client_seed: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub bet_id: Uuid,
+    pub won: bool,
+    pub payout: i64,
+    pub proof: FairnessProof,
+}
+
+/// Draws bet outcomes from one server seed
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    seed: Arc<ServerSeed>,
+    commitment: String,
+    /// Load-test win ratio overriding the seed, from a `BehaviorProfile`
+    win_ratio: Option<f64>,
+}
+
+impl Simulator {
+    pub fn new(seed: ServerSeed) -> Self {
+        Self {
+            commitment: seed.commitment(),
+            seed: Arc::new(seed),
+            win_ratio: None,
+        }
+    }
+
+    /// Win a fixed share of bets, chosen from the bet id, instead of drawing from the seed.
+    /// Outcomes then fail `verify_coinflip`; for load testing only.
+    pub fn with_win_ratio(mut self, win_ratio: Option<f64>) -> Self {
+        self.win_ratio = win_ratio;
+        self
+    }
+
+    /// Commitment of this simulator's server seed
+    pub fn commitment(&self) -> &str {
+        &self.commitment
+    }
+
+    pub fn coinflip(&self, bet_id: Uuid, client_seed: &str, stake_amount: i64) -> Outcome {
+        let won = match self.win_ratio {
+            Some(win_ratio) => behavior::wins_at_ratio(bet_id, win_ratio),
+            None => coinflip_won(&self.seed.0, bet_id, client_seed),
+        };
+        Outcome {
+            bet_id,
+            won,
+            payout: if won { stake_amount * COINFLIP_MULTIPLIER } else { 0 },
+            proof: FairnessProof {
+                server_seed_hash: self.commitment.clone(),
+                client_seed: client_seed.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coinflip_is_deterministic_and_verifiable() {
+        let seed = ServerSeed::from_hex(&"ab".repeat(SERVER_SEED_LEN)).unwrap();
+        let simulator = Simulator::new(seed.clone());
+
+        let bets: Vec<Uuid> = (0..1000u128).map(Uuid::from_u128).collect();
+        let outcomes: Vec<Outcome> = bets.iter().map(|id| simulator.coinflip(*id, "", 100)).collect();
+        assert_eq!(outcomes, bets.iter().map(|id| simulator.coinflip(*id, "", 100)).collect::<Vec<_>>());
+
+        // Roughly fair
+        let wins = outcomes.iter().filter(|o| o.won).count();
+        assert!(wins > 400 && wins < 600, "{} wins out of 1000", wins);
+        assert!(outcomes.iter().all(|o| o.payout == if o.won { 200 } else { 0 }));
+
+        // A revealed seed replays every outcome; a different seed fails the commitment
+        for outcome in &outcomes[..20] {
+            let proof = &outcome.proof;
+            let won = verify_coinflip(&seed.to_hex(), &proof.server_seed_hash, outcome.bet_id, &proof.client_seed).unwrap();
+            assert_eq!(won, outcome.won);
+        }
+        let other = ServerSeed::generate();
+        assert!(matches!(
+            verify_coinflip(&other.to_hex(), simulator.commitment(), bets[0], ""),
+            Err(FairnessError::CommitmentMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_behavior_profiles() {
+        let simulator = Simulator::new(ServerSeed::generate()).with_win_ratio(Some(0.3));
+        let bets: Vec<Uuid> = (0..10_000u128).map(Uuid::from_u128).collect();
+        let wins = bets.iter().filter(|id| simulator.coinflip(**id, "", 100).won).count();
+        assert!((2_800..3_200).contains(&wins), "{} wins out of 10000", wins);
+        // Deterministic per bet id, whatever the seed
+        let other = Simulator::new(ServerSeed::generate()).with_win_ratio(Some(0.3));
+        assert!(bets[..100].iter().all(|id| other.coinflip(*id, "x", 100).won == simulator.coinflip(*id, "", 100).won));
+
+        let profile: BehaviorProfile = "Realistic".parse().unwrap();
+        assert!(!profile.is_instant() && BehaviorProfile::default().is_instant());
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let delay = profile.latency.sample(&mut rng).as_millis();
+            assert!((400..=2_800).contains(&delay), "{}ms", delay);
+        }
+        assert!("slow".parse::<BehaviorProfile>().is_err());
+        assert!(BehaviorProfile { failure_rate: 1.5, ..BehaviorProfile::instant() }.validate().is_err());
+    }
+}
@@ -0,0 +1,367 @@
+//! End-to-end smoke test of a deployment
+//!
+//! Run on demand to validate a deployment before opening traffic. A throwaway
+//! user, funded from `--funder` or a devnet airdrop, creates and funds a vault,
+//! approves an allowance through the backend's prepare endpoint and places one
+//! bet. Once the processor settles it, the chain must agree with the backend:
+//! a processed-bet record for the bet, the stake spent from the allowance and
+//! the vault balance moved by stake and payout. The first failing step exits
+//! non-zero.
+
+use anyhow::{bail, ensure, Context, Result};
+use atomiq_client::{BackendApi, Bet, BetStatus, CreateBetRequest, HttpBackend, WalletTokenRequest};
+use base64::Engine;
+use clap::Parser;
+use serde::Deserialize;
+use shared::api::wallet_token_message;
+use shared::types::{BetId, LamportAmount};
+use shared::vault::{
+    build_deposit_sol_instruction, build_initialize_vault_instruction, casino_pda, processed_bet_pda,
+    user_vault_pda, AllowanceAccount, BetOutcome, ProcessedBetAccount, VaultAccount,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signature, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Lamports the user needs on top of the stake: vault, nonce registry and allowance rent, and fees
+const FEE_AND_RENT_BUDGET_LAMPORTS: u64 = 20_000_000;
+
+const SETTLEMENT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Parser)]
+#[command(name = "smoke-test", version, about = "End-to-end settlement check against a deployment")]
+struct Cli {
+    #[arg(long, env = "SOLANA_RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+    #[arg(long, env = "VAULT_PROGRAM_ID")]
+    program_id: Pubkey,
+    #[arg(long, env = "BACKEND_URL", default_value = "http://localhost:3001")]
+    backend_url: String,
+    /// Keypair paying for the throwaway user; a devnet airdrop when unset
+    #[arg(long, env = "SMOKE_TEST_FUNDER_KEYPAIR")]
+    funder: Option<PathBuf>,
+    /// Stake in lamports (default: the backend's default minimum bet)
+    #[arg(long, default_value_t = 100_000_000)]
+    stake: u64,
+    #[arg(long, default_value = "coinflip")]
+    game_type: String,
+    #[arg(long, default_value = "heads")]
+    choice: String,
+    /// How long the processor gets to settle the bet
+    #[arg(long, default_value_t = 180)]
+    settle_timeout_seconds: u64,
+}
+
+/// The fields of `POST /api/users/:wallet/allowances/prepare` used here
+#[derive(Debug, Deserialize)]
+struct PreparedAllowance {
+    transaction: String,
+    allowance_pda: String,
+}
+
+/// On-chain state read around the bet
+struct Observed {
+    vault_before: VaultAccount,
+    vault_after: VaultAccount,
+    allowance: AllowanceAccount,
+    processed: ProcessedBetAccount,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+    let started = Instant::now();
+
+    let rpc = RpcClient::new_with_commitment(cli.rpc_url.clone(), CommitmentConfig::confirmed());
+    let backend = HttpBackend::new(cli.backend_url.clone());
+    let user = Keypair::new();
+    let casino = casino_pda(&cli.program_id);
+    let vault = user_vault_pda(&user.pubkey(), &casino, &cli.program_id);
+    println!("Throwaway user {} (vault {})", user.pubkey(), vault);
+
+    println!("[1/6] Funding user");
+    fund(&rpc, &cli, &user.pubkey(), cli.stake + FEE_AND_RENT_BUDGET_LAMPORTS).await?;
+
+    println!("[2/6] Initializing vault and depositing the stake");
+    send(
+        &rpc,
+        &[
+            build_initialize_vault_instruction(&cli.program_id, &user.pubkey()),
+            build_deposit_sol_instruction(&cli.program_id, &user.pubkey(), cli.stake),
+        ],
+        &user,
+    )
+    .await
+    .context("Vault setup failed")?;
+    let vault_before = VaultAccount::decode(&rpc.get_account_data(&vault).await?)?;
+    ensure!(vault_before.sol_balance == cli.stake, "Vault holds {} after depositing {}", vault_before.sol_balance, cli.stake);
+
+    println!("[3/6] Approving an allowance prepared by the backend");
+    let prepared = prepare_allowance(&cli, &user.pubkey()).await?;
+    let mut approval: Transaction = bincode::deserialize(
+        &base64::engine::general_purpose::STANDARD.decode(&prepared.transaction).context("Prepared transaction is not base64")?,
+    )
+    .context("Prepared transaction does not decode")?;
+    let blockhash = approval.message.recent_blockhash;
+    approval.try_sign(&[&user], blockhash).context("Prepared transaction needs other signers")?;
+    rpc.send_and_confirm_transaction(&approval).await.context("Allowance approval failed")?;
+    let allowance = Pubkey::from_str(&prepared.allowance_pda).context("Backend returned an invalid allowance PDA")?;
+
+    println!("[4/6] Placing a {} bet on {:?}", cli.game_type, cli.choice);
+    let created = backend
+        .create_bet(&CreateBetRequest {
+            user_wallet: Some(user.pubkey().to_string()),
+            vault_address: Some(vault.to_string()),
+            allowance_pda: Some(allowance.to_string()),
+            stake_amount: LamportAmount::new(cli.stake)?,
+            stake_token: "SOL".to_string(),
+            game_type: cli.game_type.clone(),
+            choice: cli.choice.clone(),
+            client_seed: None,
+            session_id: None,
+            metadata: None,
+        })
+        .await
+        .context("Backend rejected the bet")?;
+    let bet_id = created.bet.bet_id;
+    println!("      bet {}", bet_id);
+
+    println!("[5/6] Waiting for the processor to settle it");
+    let token = wallet_token(&backend, &user).await?;
+    let backend = backend.with_bearer_token(token);
+    let bet = wait_for_settlement(&backend, bet_id, Duration::from_secs(cli.settle_timeout_seconds)).await?;
+    let signature = bet.solana_tx_id.as_deref().context("Completed bet has no settlement transaction")?;
+    let signature = Signature::from_str(signature).context("Bet has an invalid settlement signature")?;
+    match rpc.get_signature_status(&signature).await? {
+        Some(Ok(())) => {}
+        Some(Err(e)) => bail!("Settlement transaction {} failed on-chain: {}", signature, e),
+        None => bail!("Settlement transaction {} is unknown to the cluster", signature),
+    }
+    println!("      settled by {} ({})", signature, if bet.won == Some(true) { "won" } else { "lost" });
+
+    println!("[6/6] Verifying on-chain state");
+    let processed_pda = processed_bet_pda(&BetId::new(bet_id), &cli.program_id);
+    let observed = Observed {
+        vault_before,
+        vault_after: VaultAccount::decode(&rpc.get_account_data(&vault).await?)?,
+        allowance: AllowanceAccount::decode(&rpc.get_account_data(&allowance).await?)?,
+        processed: ProcessedBetAccount::decode(
+            &rpc.get_account_data(&processed_pda).await.context("No processed-bet record for the bet")?,
+        )?,
+    };
+    check_settlement(&bet, &user.pubkey(), cli.stake, &observed)?;
+
+    println!("Smoke test passed in {}s", started.elapsed().as_secs());
+    Ok(())
+}
+
+/// Send `lamports` to `user` from the funder, or airdrop them
+async fn fund(rpc: &RpcClient, cli: &Cli, user: &Pubkey, lamports: u64) -> Result<()> {
+    match &cli.funder {
+        Some(path) => {
+            let funder = read_keypair_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read funder keypair {}: {}", path.display(), e))?;
+            send(rpc, &[system_instruction::transfer(&funder.pubkey(), user, lamports)], &funder)
+                .await
+                .context("Funding transfer failed")?;
+        }
+        None => {
+            let signature = rpc.request_airdrop(user, lamports).await.context("Airdrop request failed")?;
+            rpc.poll_for_signature_with_commitment(&signature, CommitmentConfig::confirmed())
+                .await
+                .context("Airdrop did not confirm; pass --funder where airdrops are unavailable")?;
+        }
+    }
+    Ok(())
+}
+
+async fn send(rpc: &RpcClient, instructions: &[Instruction], payer: &Keypair) -> Result<Signature> {
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], blockhash);
+    Ok(rpc.send_and_confirm_transaction(&transaction).await?)
+}
+
+async fn prepare_allowance(cli: &Cli, user: &Pubkey) -> Result<PreparedAllowance> {
+    let url = format!("{}/api/users/{}/allowances/prepare", cli.backend_url.trim_end_matches('/'), user);
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&serde_json::json!({ "amount": cli.stake, "duration_seconds": 3600 }))
+        .send()
+        .await
+        .context("Allowance prepare request failed")?;
+    let status = response.status();
+    if !status.is_success() {
+        bail!("Backend could not prepare the allowance ({}): {}", status, response.text().await.unwrap_or_default());
+    }
+    Ok(response.json().await?)
+}
+
+/// Sign in as `user` for a token that reads its bets
+async fn wallet_token(backend: &HttpBackend, user: &Keypair) -> Result<String> {
+    let wallet = user.pubkey().to_string();
+    let issued_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let signature = user.sign_message(wallet_token_message(&wallet, issued_at).as_bytes());
+    let response = backend
+        .issue_wallet_token(&WalletTokenRequest { wallet, issued_at, signature: signature.to_string() })
+        .await
+        .context("Backend refused a wallet token")?;
+    Ok(response.token)
+}
+
+/// Poll the backend until the bet completes; any other final status fails
+async fn wait_for_settlement(backend: &HttpBackend, bet_id: Uuid, timeout: Duration) -> Result<Bet> {
+    let deadline = Instant::now() + timeout;
+    let mut last_status = None;
+    loop {
+        let bet = backend.get_bet(bet_id).await?.context("Backend lost the bet")?;
+        if last_status.as_ref() != Some(&bet.status) {
+            println!("      status {:?}", bet.status);
+            last_status = Some(bet.status.clone());
+        }
+        match &bet.status {
+            BetStatus::Completed => return Ok(bet),
+            status if status.is_terminal() => bail!(
+                "Bet ended {:?}: {} {}",
+                status,
+                bet.last_error_code.unwrap_or_default(),
+                bet.last_error_message.unwrap_or_default()
+            ),
+            _ => {}
+        }
+        if Instant::now() >= deadline {
+            bail!("Bet not settled within {}s (last status {:?})", timeout.as_secs(), bet.status);
+        }
+        tokio::time::sleep(SETTLEMENT_POLL_INTERVAL).await;
+    }
+}
+
+/// The chain agrees with the backend about a completed bet
+fn check_settlement(bet: &Bet, user: &Pubkey, stake: u64, observed: &Observed) -> Result<()> {
+    let processed = &observed.processed;
+    ensure!(processed.bet_id == BetId::new(bet.bet_id).as_str(), "Processed-bet record is for {}", processed.bet_id);
+    ensure!(processed.user == *user, "Processed-bet record is for user {}", processed.user);
+    ensure!(processed.amount == stake, "Processed-bet record staked {}, not {}", processed.amount, stake);
+
+    let payout = u64::try_from(bet.payout_amount.unwrap_or(0)).context("Negative payout")?;
+    ensure!(
+        processed.payout == payout,
+        "Backend reports a payout of {}, the chain {}",
+        payout,
+        processed.payout
+    );
+    // Settlements made with spend_from_allowance + payout leave the outcome unrecorded
+    if processed.outcome != BetOutcome::Unrecorded {
+        let won_on_chain = processed.outcome == BetOutcome::Won;
+        ensure!(won_on_chain == (bet.won == Some(true)), "Chain records the bet as {:?}", processed.outcome);
+    }
+
+    ensure!(
+        observed.allowance.spent == stake,
+        "Allowance spent {}, expected the stake {}",
+        observed.allowance.spent,
+        stake
+    );
+    let expected = observed.vault_before.sol_balance - stake + processed.payout;
+    ensure!(
+        observed.vault_after.sol_balance == expected,
+        "Vault holds {}, expected {} ({} - stake {} + payout {})",
+        observed.vault_after.sol_balance,
+        expected,
+        observed.vault_before.sol_balance,
+        stake,
+        processed.payout
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settlement_checks() {
+        let user = Pubkey::new_unique();
+        let bet_id = Uuid::new_v4();
+        let bet: Bet = serde_json::from_value(serde_json::json!({
+            "bet_id": bet_id,
+            "created_at": "2026-01-01T00:00:00Z",
+            "user_wallet": user.to_string(),
+            "vault_address": Pubkey::new_unique().to_string(),
+            "allowance_pda": null,
+            "casino_id": null,
+            "game_type": "coinflip",
+            "stake_amount": 100,
+            "stake_token": "SOL",
+            "choice": "heads",
+            "status": "completed",
+            "external_batch_id": null,
+            "solana_tx_id": null,
+            "retry_count": 0,
+            "processor_id": null,
+            "last_error_code": null,
+            "last_error_message": null,
+            "payout_amount": 200,
+            "won": true,
+        }))
+        .unwrap();
+        let vault = |sol_balance| VaultAccount {
+            owner: user,
+            casino: Pubkey::new_unique(),
+            bump: 255,
+            sol_balance,
+            created_at: 0,
+            last_activity: 0,
+            payout_address: None,
+        };
+        let mut observed = Observed {
+            vault_before: vault(100),
+            vault_after: vault(200),
+            allowance: AllowanceAccount {
+                user,
+                casino: Pubkey::new_unique(),
+                token_mint: Pubkey::default(),
+                amount: 100,
+                spent: 100,
+                expires_at: 0,
+                created_at: 0,
+                nonce: 0,
+                revoked: false,
+                bump: 255,
+                last_spent_at: 0,
+                spend_count: 1,
+                max_single_spend: 0,
+                max_spend_per_hour: 0,
+                window_start: 0,
+                window_spent: 0,
+            },
+            processed: ProcessedBetAccount {
+                bet_id: BetId::new(bet_id).into_string(),
+                user,
+                amount: 100,
+                processed_at: 0,
+                signature: String::new(),
+                bump: 255,
+                outcome: BetOutcome::Won,
+                payout: 200,
+            },
+        };
+        assert!(check_settlement(&bet, &user, 100, &observed).is_ok());
+
+        observed.vault_after.sol_balance = 100;
+        assert!(check_settlement(&bet, &user, 100, &observed).is_err());
+        observed.vault_after.sol_balance = 200;
+        observed.processed.outcome = BetOutcome::Lost;
+        assert!(check_settlement(&bet, &user, 100, &observed).is_err());
+    }
+}
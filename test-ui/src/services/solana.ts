@@ -36,6 +36,8 @@ const RPC_URL =
 const VAULT_PROGRAM_ID = import.meta.env.VITE_VAULT_PROGRAM_ID;
 const SOLANA_NETWORK = (import.meta.env.VITE_SOLANA_NETWORK ||
   "devnet") as string;
+// `Allowance` size including velocity limits; shorter accounts need migrate_allowance
+const ALLOWANCE_ACCOUNT_LEN = 190;
 
 type SendTransactionFn = (
   transaction: Transaction | VersionedTransaction,
@@ -986,7 +988,25 @@ export class SolanaService {
 
     // Add unique memo to prevent transaction deduplication
     const memoIx = createUniqueMemoInstruction();
-    const tx = new Transaction().add(memoIx).add(ix);
+    const tx = new Transaction().add(memoIx);
+    // Allowances approved before velocity limits must be resized first
+    const info = await withRateLimitRetry(() =>
+      connection.getAccountInfo(params.allowancePda, "confirmed"),
+    );
+    if (info && info.data.length < ALLOWANCE_ACCOUNT_LEN) {
+      tx.add(
+        new TransactionInstruction({
+          programId: this.programId,
+          keys: [
+            { pubkey: params.allowancePda, isSigner: false, isWritable: true },
+            { pubkey: params.user, isSigner: true, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+          ],
+          data: await buildIxData("migrate_allowance"),
+        }),
+      );
+    }
+    tx.add(ix);
     addPriorityFeeInstructions(tx, 15000);
     tx.feePayer = params.user;
     const signature = params.signTransaction
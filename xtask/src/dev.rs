@@ -0,0 +1,402 @@
+//! `cargo xtask dev`: the whole stack on one machine
+//!
+//! Starts Redis in Docker (unless `--redis-url` points at one), a
+//! `solana-test-validator` with the vault program loaded at its declared id,
+//! then creates and funds the casino, authorizes a generated processor key and
+//! airdrops SOL to the generated keypairs. The backend and processor are built
+//! and run with the environment this wires up (`CONFIG_PROFILE=local` plus the
+//! local endpoints and keypairs); their output goes to this terminal, the
+//! validator's to `<state-dir>/logs`. Ctrl-C, or any component exiting, stops
+//! everything.
+//!
+//! Keypairs are kept in the state directory across runs; the ledger starts
+//! from genesis unless `--keep-ledger` is given. Settlement also needs the
+//! blockchain API (`--blockchain-api-url`), which is not started here.
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use shared::profile::{Profile, ProfileDefaults};
+use shared::vault::{
+    build_add_processor_instruction, build_fund_casino_vault_instruction, build_initialize_casino_vault_instruction,
+    casino_pda,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, write_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const REDIS_CONTAINER: &str = "atomiq-dev-redis";
+const REDIS_IMAGE: &str = "redis:7-alpine";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// SOL airdropped to each generated keypair and `--fund` wallet
+const AIRDROP_SOL: u64 = 100;
+
+#[derive(Debug, Args)]
+pub struct DevArgs {
+    /// Keypairs, validator ledger and logs, relative to the workspace root
+    #[arg(long, default_value = "target/dev")]
+    state_dir: PathBuf,
+    /// Use this Redis instead of starting one in Docker
+    #[arg(long)]
+    redis_url: Option<String>,
+    #[arg(long, default_value_t = 6379)]
+    redis_port: u16,
+    #[arg(long, default_value_t = 8899)]
+    rpc_port: u16,
+    #[arg(long, default_value_t = 3001)]
+    api_port: u16,
+    /// Vault program build, relative to the workspace root; built with `cargo build-sbf` when missing
+    #[arg(long, default_value = "contracts/target/deploy/vault.so")]
+    program_so: PathBuf,
+    /// Blockchain API the processor polls for settlements
+    #[arg(long, env = "BLOCKCHAIN_API_URL", default_value = "http://localhost:8080")]
+    blockchain_api_url: String,
+    /// Lamports deposited in the casino vault to cover payouts
+    #[arg(long, default_value_t = 50 * LAMPORTS_PER_SOL)]
+    casino_float: u64,
+    /// Additional wallets to airdrop SOL to, e.g. a browser wallet for the test UI
+    #[arg(long = "fund", value_name = "PUBKEY")]
+    fund: Vec<Pubkey>,
+    /// Keep the previous run's ledger instead of starting from genesis
+    #[arg(long)]
+    keep_ledger: bool,
+    /// Build and run the services in release mode
+    #[arg(long)]
+    release: bool,
+}
+
+/// Endpoints and keypairs the services are started with
+#[derive(Debug)]
+struct DevEnv {
+    rpc_url: String,
+    redis_url: String,
+    program_id: Pubkey,
+    api_port: u16,
+    blockchain_api_url: String,
+    authority_keypair: PathBuf,
+    processor_keypair: PathBuf,
+}
+
+impl DevEnv {
+    /// Environment shared by the backend and the processor
+    fn vars(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("CONFIG_PROFILE", Profile::Local.to_string()),
+            ("SOLANA_RPC_URL", self.rpc_url.clone()),
+            ("VAULT_PROGRAM_ID", self.program_id.to_string()),
+            ("REDIS_URL", self.redis_url.clone()),
+            ("API_PORT", self.api_port.to_string()),
+            ("BACKEND_API_URL", format!("http://127.0.0.1:{}", self.api_port)),
+            ("BLOCKCHAIN_API_URL", self.blockchain_api_url.clone()),
+            ("PROCESSOR_KEYPAIR", self.processor_keypair.display().to_string()),
+            ("CASINO_AUTHORITY_KEYPAIR_PATH", self.authority_keypair.display().to_string()),
+        ]
+    }
+}
+
+/// Running components, stopped in reverse start order when dropped
+#[derive(Default)]
+struct Stack {
+    children: Vec<(&'static str, Child)>,
+    redis_container: bool,
+}
+
+impl Stack {
+    fn spawn(&mut self, name: &'static str, command: &mut Command) -> Result<()> {
+        let child = command.spawn().with_context(|| format!("Failed to start {}", name))?;
+        self.children.push((name, child));
+        Ok(())
+    }
+
+    /// Fails if a component has already exited
+    fn check(&mut self) -> Result<()> {
+        for (name, child) in &mut self.children {
+            if let Some(status) = child.try_wait()? {
+                bail!("{} exited ({})", name, status);
+            }
+        }
+        Ok(())
+    }
+
+    /// Block until Ctrl-C or until a component exits
+    fn wait(&mut self, stop: &AtomicBool) -> Result<()> {
+        while !stop.load(Ordering::SeqCst) {
+            self.check()?;
+            sleep(POLL_INTERVAL);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Stack {
+    fn drop(&mut self) {
+        for (name, child) in self.children.iter_mut().rev() {
+            println!("Stopping {}", name);
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        if self.redis_container {
+            println!("Stopping Redis");
+            let _ = Command::new("docker").args(["stop", REDIS_CONTAINER]).stdout(Stdio::null()).status();
+        }
+    }
+}
+
+pub fn run(args: DevArgs) -> Result<()> {
+    let root = workspace_root();
+    let state_dir = root.join(&args.state_dir);
+    let logs = state_dir.join("logs");
+    fs::create_dir_all(&logs).with_context(|| format!("Failed to create {}", logs.display()))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = stop.clone();
+    ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst)).context("Failed to install Ctrl-C handler")?;
+
+    let program_id = Pubkey::from_str(
+        ProfileDefaults::of(Some(Profile::Local)).vault_program_id.context("Local profile has no vault program")?,
+    )?;
+    let authority = load_or_generate(&state_dir.join("casino-authority.json"))?;
+    let processor = load_or_generate(&state_dir.join("processor.json"))?;
+    let player = load_or_generate(&state_dir.join("player.json"))?;
+
+    let mut stack = Stack::default();
+
+    let redis_url = match args.redis_url.clone() {
+        Some(url) => url,
+        None => {
+            println!("Starting Redis ({}) on port {}", REDIS_IMAGE, args.redis_port);
+            let _ = Command::new("docker").args(["rm", "-f", REDIS_CONTAINER]).output();
+            let status = Command::new("docker")
+                .args(["run", "--rm", "-d", "--name", REDIS_CONTAINER, "-p"])
+                .arg(format!("{}:6379", args.redis_port))
+                .arg(REDIS_IMAGE)
+                .stdout(Stdio::null())
+                .status()
+                .context("Failed to run docker; pass --redis-url to use an existing Redis")?;
+            if !status.success() {
+                bail!("docker run {} failed ({})", REDIS_IMAGE, status);
+            }
+            stack.redis_container = true;
+            wait_for_redis(args.redis_port)?;
+            format!("redis://127.0.0.1:{}", args.redis_port)
+        }
+    };
+
+    let program_so = root.join(&args.program_so);
+    if !program_so.exists() {
+        build_program(&root, &program_so)?;
+    }
+
+    println!("Starting solana-test-validator on port {} (log: {})", args.rpc_port, logs.join("validator.log").display());
+    let validator_log = File::create(logs.join("validator.log"))?;
+    let mut validator = Command::new("solana-test-validator");
+    validator
+        .arg("--ledger")
+        .arg(state_dir.join("ledger"))
+        .args(["--rpc-port", &args.rpc_port.to_string(), "--quiet", "--bpf-program", &program_id.to_string()])
+        .arg(&program_so)
+        .stdout(validator_log.try_clone()?)
+        .stderr(validator_log);
+    if !args.keep_ledger {
+        validator.arg("--reset");
+    }
+    stack.spawn("solana-test-validator", &mut validator)?;
+
+    let rpc_url = format!("http://127.0.0.1:{}", args.rpc_port);
+    let rpc = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+    wait_for_validator(&rpc, &mut stack)?;
+
+    let mut wallets = vec![authority.pubkey(), processor.pubkey(), player.pubkey()];
+    wallets.extend(&args.fund);
+    for wallet in &wallets {
+        airdrop(&rpc, wallet, AIRDROP_SOL * LAMPORTS_PER_SOL)?;
+    }
+    setup_casino(&rpc, &program_id, &authority, &processor.pubkey(), args.casino_float)?;
+
+    let env = DevEnv {
+        rpc_url,
+        redis_url,
+        program_id,
+        api_port: args.api_port,
+        blockchain_api_url: args.blockchain_api_url.clone(),
+        authority_keypair: state_dir.join("casino-authority.json"),
+        processor_keypair: state_dir.join("processor.json"),
+    };
+
+    println!("Building backend and processor");
+    let mut build = Command::new(env!("CARGO"));
+    build.current_dir(&root).args(["build", "-p", "backend", "-p", "processor"]);
+    if args.release {
+        build.arg("--release");
+    }
+    if !build.status()?.success() {
+        bail!("Building the services failed");
+    }
+
+    let bin_dir = root.join("target").join(if args.release { "release" } else { "debug" });
+    for (name, dir) in [("backend", "services/backend"), ("processor", "services/processor")] {
+        println!("Starting {}", name);
+        stack.spawn(name, Command::new(bin_dir.join(name)).current_dir(root.join(dir)).envs(env.vars()))?;
+    }
+
+    print_summary(&env, &state_dir, &player.pubkey());
+    let result = stack.wait(&stop);
+    drop(stack);
+    result
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().expect("xtask is inside the workspace").to_path_buf()
+}
+
+fn load_or_generate(path: &Path) -> Result<Keypair> {
+    if path.exists() {
+        return read_keypair_file(path).map_err(|e| anyhow::anyhow!("Failed to read keypair {}: {}", path.display(), e));
+    }
+    let keypair = Keypair::new();
+    write_keypair_file(&keypair, path)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .with_context(|| format!("Failed to write keypair to {}", path.display()))?;
+    println!("Generated {} ({})", path.display(), keypair.pubkey());
+    Ok(keypair)
+}
+
+fn build_program(root: &Path, program_so: &Path) -> Result<()> {
+    let out_dir = program_so.parent().context("--program-so has no parent directory")?;
+    println!("{} not found; building the vault program", program_so.display());
+    let status = Command::new(env!("CARGO"))
+        .current_dir(root)
+        .args(["build-sbf", "--manifest-path", "contracts/programs/vault/Cargo.toml", "--sbf-out-dir"])
+        .arg(out_dir)
+        .status()
+        .context("Failed to run cargo build-sbf (install the Solana CLI tools)")?;
+    if !status.success() || !program_so.exists() {
+        bail!("Building the vault program did not produce {}", program_so.display());
+    }
+    Ok(())
+}
+
+fn wait_for_redis(port: u16) -> Result<()> {
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        if redis_ping(port).unwrap_or(false) {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            bail!("Redis did not answer PING on port {} within {}s", port, STARTUP_TIMEOUT.as_secs());
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
+
+fn redis_ping(port: u16) -> Result<bool> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    stream.write_all(b"PING\r\n")?;
+    let mut reply = [0u8; 7];
+    let read = stream.read(&mut reply)?;
+    Ok(reply[..read].starts_with(b"+PONG"))
+}
+
+fn wait_for_validator(rpc: &RpcClient, stack: &mut Stack) -> Result<()> {
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    while rpc.get_health().is_err() {
+        stack.check()?;
+        if Instant::now() >= deadline {
+            bail!("Validator RPC not healthy within {}s", STARTUP_TIMEOUT.as_secs());
+        }
+        sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+fn airdrop(rpc: &RpcClient, wallet: &Pubkey, lamports: u64) -> Result<()> {
+    let signature = rpc.request_airdrop(wallet, lamports).with_context(|| format!("Airdrop to {} failed", wallet))?;
+    rpc.poll_for_signature_with_commitment(&signature, CommitmentConfig::confirmed())
+        .with_context(|| format!("Airdrop to {} did not confirm", wallet))?;
+    println!("Airdropped {} SOL to {}", lamports / LAMPORTS_PER_SOL, wallet);
+    Ok(())
+}
+
+/// Create the casino with `authority` as admin, authorize `processor` and fund the casino vault
+fn setup_casino(rpc: &RpcClient, program_id: &Pubkey, authority: &Keypair, processor: &Pubkey, float: u64) -> Result<()> {
+    if rpc.get_account(&casino_pda(program_id)).is_ok() {
+        println!("Casino already initialized (kept ledger)");
+        return Ok(());
+    }
+    let mut instructions = vec![
+        build_initialize_casino_vault_instruction(program_id, &authority.pubkey(), &authority.pubkey()),
+        build_add_processor_instruction(program_id, &authority.pubkey(), processor),
+    ];
+    if float > 0 {
+        instructions.push(build_fund_casino_vault_instruction(program_id, &authority.pubkey(), float));
+    }
+    send(rpc, &instructions, authority).context("Casino setup failed")?;
+    println!("Casino initialized; processor {} authorized, vault funded with {} lamports", processor, float);
+    Ok(())
+}
+
+fn send(rpc: &RpcClient, instructions: &[Instruction], payer: &Keypair) -> Result<()> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], blockhash);
+    rpc.send_and_confirm_transaction(&transaction)?;
+    Ok(())
+}
+
+fn print_summary(env: &DevEnv, state_dir: &Path, player: &Pubkey) {
+    println!();
+    println!("Local stack is up (Ctrl-C to stop)");
+    for (name, value) in env.vars() {
+        println!("  {}={}", name, value);
+    }
+    println!("  funded player: {} ({})", player, state_dir.join("player.json").display());
+    println!(
+        "  smoke test: cargo run -p smoke-test -- --rpc-url {} --backend-url http://127.0.0.1:{} --program-id {} --funder {}",
+        env.rpc_url,
+        env.api_port,
+        env.program_id,
+        state_dir.join("player.json").display()
+    );
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_env() {
+        let env = DevEnv {
+            rpc_url: "http://127.0.0.1:8899".to_string(),
+            redis_url: "redis://127.0.0.1:6379".to_string(),
+            program_id: Pubkey::new_unique(),
+            api_port: 3001,
+            blockchain_api_url: "http://localhost:8080".to_string(),
+            authority_keypair: PathBuf::from("target/dev/casino-authority.json"),
+            processor_keypair: PathBuf::from("target/dev/processor.json"),
+        };
+        let vars = env.vars();
+        let var = |name: &str| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.as_str());
+        assert_eq!(var("CONFIG_PROFILE"), Some("local"));
+        assert_eq!(var("BACKEND_API_URL"), Some("http://127.0.0.1:3001"));
+        assert_eq!(var("VAULT_PROGRAM_ID"), Some(env.program_id.to_string().as_str()));
+        assert_eq!(var("PROCESSOR_KEYPAIR"), Some("target/dev/processor.json"));
+    }
+}
@@ -0,0 +1,25 @@
+//! Development tasks, run with `cargo xtask <task>`
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+mod dev;
+
+#[derive(Debug, Parser)]
+#[command(name = "xtask", about = "Development tasks")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run Redis, a local validator with the vault program, the backend and the processor
+    Dev(dev::DevArgs),
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Dev(args) => dev::run(args),
+    }
+}